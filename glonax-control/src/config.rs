@@ -3,4 +3,7 @@ pub struct Config {
     /// Unix socket listener configuration.
     #[serde(default)]
     pub unix_listener: glonax::service::UnixServerConfig,
+    /// Units for displayed values.
+    #[serde(default)]
+    pub units: glonax::util::units::UnitSystem,
 }
@@ -32,6 +32,9 @@ struct Args {
         value_hint = ValueHint::FilePath
     )]
     path: Option<std::path::PathBuf>,
+    /// Units for displayed values, metric or imperial.
+    #[arg(short, long, value_parser = parse_units)]
+    units: Option<units::UnitSystem>,
     /// Level of verbosity.
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
@@ -133,10 +136,44 @@ enum Command {
         /// On or off.
         toggle: String,
     },
+    /// Motion smoothing profile command.
+    MotionProfile {
+        /// Aggressive, normal, gentle or off.
+        profile: String,
+    },
+    /// Attachment change command.
+    Attachment {
+        /// Attachment identifier.
+        id: u8,
+    },
+    /// Tare the load weighing.
+    LoadTare,
+    /// Latch the emergency stop.
+    EmergencyStop,
+    /// Reset the latched emergency stop.
+    EmergencyReset,
+    /// Start the factory acceptance test.
+    AcceptanceTest,
+    /// Network service tick interval command.
+    TickInterval {
+        /// Interval in milliseconds, zero for the default interval.
+        millis: u32,
+        /// Network index, in the order of the network configuration.
+        #[arg(short, long, default_value_t = 0)]
+        network: u8,
+    },
     /// Queue target.
     Target { x: f32, y: f32, z: f32 },
     /// Instance information.
     Info,
+    /// Machine capability.
+    Capability,
+}
+
+fn parse_units(value: &str) -> Result<units::UnitSystem, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid unit system '{}'", value))
 }
 
 #[tokio::main]
@@ -184,6 +221,8 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
         .path
         .unwrap_or_else(|| config.unix_listener.path.clone());
 
+    let units = args.units.unwrap_or(config.units);
+
     glonax::log_system();
 
     log::info!("Starting {}", bin_name);
@@ -193,7 +232,15 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
     let (mut client, instance) = glonax::protocol::client::ClientBuilder::new(user_agent)
         .stream(true)
         .unix_connect(&socket_path)
-        .await?;
+        .await
+        .map_err(
+            |e| match glonax::protocol::frame::HandshakeRejected::from_io_error(&e) {
+                Some(rejected) => {
+                    anyhow::anyhow!("Runtime refused the session: {}", rejected.reason)
+                }
+                None => e.into(),
+            },
+        )?;
 
     log::debug!("Connected to {}", socket_path.display());
     log::info!("{}", instance);
@@ -284,6 +331,67 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
                         println!("Rotator: {}", rotator);
                     }
                 }
+                glonax::core::RotatorRate::MESSAGE_TYPE => {
+                    let rate = client
+                        .recv_packet::<glonax::core::RotatorRate>(frame.payload_length)
+                        .await?;
+
+                    if let Some(ObjectFilter::Rotator) = filter {
+                        println!(
+                            "source={} velocity={:.2} acceleration={:.2} valid={}",
+                            rate.source,
+                            rate.velocity.to_degrees(),
+                            rate.acceleration.to_degrees(),
+                            rate.valid
+                        );
+                    } else {
+                        println!("Rotator rate: {}", rate);
+                    }
+                }
+                glonax::core::LoadEstimate::MESSAGE_TYPE => {
+                    let estimate = client
+                        .recv_packet::<glonax::core::LoadEstimate>(frame.payload_length)
+                        .await?;
+
+                    if estimate.valid {
+                        println!("Load: {:.0}", units.mass(estimate.mass));
+                    } else {
+                        println!("Load: invalid");
+                    }
+                }
+                glonax::core::CylinderPressure::MESSAGE_TYPE => {
+                    let pressure = client
+                        .recv_packet::<glonax::core::CylinderPressure>(frame.payload_length)
+                        .await?;
+
+                    println!(
+                        "Cylinder pressure: {:?} head={:.1} rod={:.1}",
+                        pressure.actuator,
+                        units.pressure(pressure.head),
+                        units.pressure(pressure.rod)
+                    );
+                }
+                glonax::core::OperationHours::MESSAGE_TYPE => {
+                    let hours = client
+                        .recv_packet::<glonax::core::OperationHours>(frame.payload_length)
+                        .await?;
+
+                    println!("Operation hours: {}", hours);
+                }
+                glonax::core::DiagnosticCodes::MESSAGE_TYPE => {
+                    let codes = client
+                        .recv_packet::<glonax::core::DiagnosticCodes>(frame.payload_length)
+                        .await?;
+
+                    println!("Diagnostic codes: {}", codes);
+                }
+                glonax::core::Capability::MESSAGE_TYPE => {
+                    let capability = client
+                        .recv_packet::<glonax::core::Capability>(frame.payload_length)
+                        .await?;
+
+                    println!("Capability: {}", capability);
+                }
                 glonax::world::Actor::MESSAGE_TYPE => {
                     let actor = client
                         .recv_packet::<glonax::world::Actor>(frame.payload_length)
@@ -292,7 +400,9 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
                     let bucket_world_location = actor.world_location("bucket");
                     println!(
                         "Bucket: world location: X={:.2} Y={:.2} Z={:.2}",
-                        bucket_world_location.x, bucket_world_location.y, bucket_world_location.z
+                        units.distance(bucket_world_location.x),
+                        units.distance(bucket_world_location.y),
+                        units.distance(bucket_world_location.z)
                     );
                 }
                 _ => {
@@ -427,10 +537,61 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
             let control = Control::MachineTravelAlarm(toggle);
             client.send_packet(&control).await?;
         }
+        Command::MotionProfile { profile } => {
+            let profile = profile
+                .parse::<glonax::core::SmoothingProfile>()
+                .map_err(|_| anyhow::anyhow!("Invalid value for motion profile"))?;
+
+            log::info!("Motion profile: {}", profile);
+
+            let control = Control::MotionProfile(profile);
+            client.send_packet(&control).await?;
+        }
+        Command::Attachment { id } => {
+            log::info!("Attachment select: {}", id);
+
+            let control = Control::AttachmentSelect(id);
+            client.send_packet(&control).await?;
+        }
+        Command::LoadTare => {
+            log::info!("Load tare");
+
+            client.send_packet(&Control::LoadTare).await?;
+        }
+        Command::EmergencyStop => {
+            log::warn!("Emergency stop");
+
+            client.send_packet(&Control::EmergencyStop).await?;
+        }
+        Command::EmergencyReset => {
+            log::info!("Reset emergency");
+
+            client.send_packet(&Control::ResetEmergency).await?;
+        }
+        Command::AcceptanceTest => {
+            log::warn!("Start acceptance test, the machine moves");
+
+            client.send_packet(&Control::AcceptanceTest).await?;
+        }
+        Command::TickInterval { millis, network } => {
+            let control = Control::TickInterval {
+                network,
+                interval: millis.div_ceil(10).min(u8::MAX as u32) as u8,
+            };
+
+            log::info!("{}", control);
+
+            client.send_packet(&control).await?;
+        }
         Command::Target { x, y, z } => {
             let target = Target::from_point(x, y, z);
 
-            log::info!("Queue target: {}", target);
+            log::info!(
+                "Queue target: X={} Y={} Z={}",
+                units.distance(target.point.x),
+                units.distance(target.point.y),
+                units.distance(target.point.z)
+            );
 
             client.send_packet(&target).await?;
         }
@@ -444,7 +605,49 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
                 instance.serial_number()
             );
         }
+        Command::Capability => {
+            use glonax::protocol::Packetize;
+
+            client.send_request(Capability::MESSAGE_TYPE).await?;
+
+            let frame = client.read_frame().await?;
+            if frame.message != Capability::MESSAGE_TYPE {
+                return Err(anyhow::anyhow!("Machine capability not available"));
+            }
+
+            let capability = client
+                .recv_packet::<Capability>(frame.payload_length)
+                .await?;
+
+            println!("Type: {:?}", capability.machine_type);
+            for actuator in capability.actuators() {
+                println!(
+                    "Actuator: id={} name={} min={} max={}",
+                    actuator.actuator.id(),
+                    actuator.name,
+                    actuator.min,
+                    actuator.max
+                );
+            }
+            for control in capability.controls() {
+                println!("Control: {}", control);
+            }
+            for sensor in capability.sensors() {
+                println!(
+                    "Sensor: source=0x{:X} name={} unit={}",
+                    sensor.source, sensor.name, sensor.unit
+                );
+            }
+            for segment in capability.segments() {
+                println!(
+                    "Segment: name={} source=0x{:X}",
+                    segment.name, segment.source
+                );
+            }
+        }
     }
 
+    client.close().await?;
+
     Ok(())
 }
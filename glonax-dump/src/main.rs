@@ -188,8 +188,12 @@ async fn daemonize(config: &config::DumpConfig) -> anyhow::Result<()> {
         log::debug!("> Target {:2}    {}", idx, target);
     }
 
-    let ground_plane = Cuboid::new(Vector3::new(10.0, 10.0, 1.0));
-    let ground_transform = Isometry3::translation(0.0, 0.0, -1.0);
+    let collision = glonax::world::CollisionConfig::default();
+    collision.build()?;
+
+    let [x, y, z] = collision.ground.location;
+    let ground_plane = Cuboid::new(collision.ground.half_extents.into());
+    let ground_transform = Isometry3::translation(x, y, z);
 
     // let obst0_box = Cuboid::new(Vector3::new(2.5, 0.205, 0.725));
     // let obst0_box_buffer = Cuboid::new(Vector3::new(
@@ -199,8 +203,9 @@ async fn daemonize(config: &config::DumpConfig) -> anyhow::Result<()> {
     // ));
     // let obst0_transform = Isometry3::translation(3.0, 2.5, 0.725);
 
-    let bucket_geometry = Cuboid::new(Vector3::new(0.75, 1.04, 0.25));
-    let bucket_transform = Isometry3::translation(0.75, 0.0, 0.375);
+    let [x, y, z] = collision.bucket.location;
+    let bucket_geometry = Cuboid::new(collision.bucket.half_extents.into());
+    let bucket_transform = Isometry3::translation(x, y, z);
 
     loop {
         if program.is_empty() {
@@ -439,17 +444,18 @@ async fn daemonize(config: &config::DumpConfig) -> anyhow::Result<()> {
             for joint_diff in perception_chain.rotation_error(&objective_chain) {
                 let joint = joint_diff.joint;
 
-                if joint.actuator().is_none()
-                    || joint.profile().is_none()
-                    || joint_diff.rotation.axis().is_none()
-                {
+                if joint.actuator().is_none() || joint.profile().is_none() {
                     continue;
                 }
 
-                let axis = joint_diff.rotation.axis().unwrap();
-                let error_angle = axis.x * joint_diff.rotation.angle()
-                    + axis.y * joint_diff.rotation.angle()
-                    + axis.z * joint_diff.rotation.angle();
+                // The frame rotates around the Z axis, the boom, arm and
+                // attachment rotate around the Y axis.
+                let joint_axis = if joint.ty() == &glonax::robot::JointType::Continuous {
+                    Vector3::z_axis()
+                } else {
+                    Vector3::y_axis()
+                };
+                let error_angle = glonax::math::twist_angle(&joint_diff.rotation, &joint_axis);
 
                 let actuator = joint.actuator().unwrap();
                 let profile = joint.profile().unwrap();
@@ -3,4 +3,7 @@ pub struct Config {
     /// Unix socket listener configuration.
     #[serde(default)]
     pub unix_listener: glonax::service::UnixServerConfig,
+    /// Input configuration.
+    #[serde(default)]
+    pub input: crate::curve::InputConfig,
 }
@@ -0,0 +1,258 @@
+use glonax::core::Motion;
+
+use crate::input::Level;
+
+/// Axis deadzone and response curve.
+///
+/// Axis values within the deadzone are zero. Outside the deadzone the axis
+/// deflection is shaped by an exponential curve, where an expo of zero is a
+/// linear response and an expo of one is a cubic response. The result is
+/// scaled and saturated at the maximum motion power.
+#[derive(Clone, Copy, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct DeadzoneCurve {
+    /// Deadzone around the center in axis units.
+    pub deadzone: f32,
+    /// Exponential curve factor between 0 and 1.
+    #[serde(default)]
+    pub expo: f32,
+    /// Output scale.
+    #[serde(default = "DeadzoneCurve::default_scale")]
+    pub scale: f32,
+}
+
+impl DeadzoneCurve {
+    /// Full axis deflection.
+    const AXIS_MAX: f32 = i16::MAX as f32;
+
+    fn default_scale() -> f32 {
+        1.0
+    }
+
+    /// Construct a new linear curve with a deadzone.
+    pub const fn linear(deadzone: f32) -> Self {
+        Self {
+            deadzone,
+            expo: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    /// Apply the curve to an axis value.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The axis value.
+    ///
+    /// # Returns
+    ///
+    /// The shaped axis value, saturated at the maximum motion power.
+    pub fn apply(&self, value: i16) -> i16 {
+        // Clamp first so the curve is symmetric around the center.
+        let value = value.max(-i16::MAX);
+
+        if value.ramp(self.deadzone as i16) == 0 {
+            return 0;
+        }
+
+        let expo = self.expo.clamp(0.0, 1.0);
+        let x = value.unsigned_abs() as f32 / Self::AXIS_MAX;
+        let y = ((1.0 - expo) * x + expo * x.powi(3)) * self.scale * Self::AXIS_MAX;
+
+        let output = y.round().clamp(0.0, Motion::POWER_MAX as f32) as i16;
+
+        if value.is_negative() {
+            -output
+        } else {
+            output
+        }
+    }
+
+    /// Curve lookup table.
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - The axis value increment between entries.
+    ///
+    /// # Returns
+    ///
+    /// The axis values from center to full deflection and their shaped value.
+    pub fn lookup(&self, step: usize) -> Vec<(i16, i16)> {
+        let mut table = (0..i16::MAX)
+            .step_by(step.max(1))
+            .map(|value| (value, self.apply(value)))
+            .collect::<Vec<_>>();
+
+        table.push((i16::MAX, self.apply(i16::MAX)));
+        table
+    }
+}
+
+/// Deadzone and response curve per axis.
+///
+/// The default deadzones match the motion limited thresholds of the axes.
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct AxisCurves {
+    /// Slew axis.
+    #[serde(default = "AxisCurves::default_slew")]
+    pub slew: DeadzoneCurve,
+    /// Arm axis.
+    #[serde(default = "AxisCurves::default_arm")]
+    pub arm: DeadzoneCurve,
+    /// Boom axis.
+    #[serde(default = "AxisCurves::default_boom")]
+    pub boom: DeadzoneCurve,
+    /// Attachment axis.
+    #[serde(default = "AxisCurves::default_attachment")]
+    pub attachment: DeadzoneCurve,
+    /// Left track axis.
+    #[serde(default = "AxisCurves::default_track")]
+    pub left_track: DeadzoneCurve,
+    /// Right track axis.
+    #[serde(default = "AxisCurves::default_track")]
+    pub right_track: DeadzoneCurve,
+}
+
+impl AxisCurves {
+    fn default_slew() -> DeadzoneCurve {
+        DeadzoneCurve::linear(2_000.0)
+    }
+
+    fn default_arm() -> DeadzoneCurve {
+        DeadzoneCurve::linear(3_000.0)
+    }
+
+    fn default_boom() -> DeadzoneCurve {
+        DeadzoneCurve::linear(3_500.0)
+    }
+
+    fn default_attachment() -> DeadzoneCurve {
+        DeadzoneCurve::linear(4_000.0)
+    }
+
+    fn default_track() -> DeadzoneCurve {
+        DeadzoneCurve::linear(2_000.0)
+    }
+
+    /// Curves by axis name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &DeadzoneCurve)> {
+        [
+            ("slew", &self.slew),
+            ("arm", &self.arm),
+            ("boom", &self.boom),
+            ("attachment", &self.attachment),
+            ("left_track", &self.left_track),
+            ("right_track", &self.right_track),
+        ]
+        .into_iter()
+    }
+}
+
+impl Default for AxisCurves {
+    fn default() -> Self {
+        Self {
+            slew: Self::default_slew(),
+            arm: Self::default_arm(),
+            boom: Self::default_boom(),
+            attachment: Self::default_attachment(),
+            left_track: Self::default_track(),
+            right_track: Self::default_track(),
+        }
+    }
+}
+
+/// Calibration of a joystick axis.
+#[derive(Clone, Copy, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct AxisCalibration {
+    /// Joystick axis number.
+    pub axis: u8,
+    /// Axis calibration.
+    #[serde(flatten)]
+    pub calibration: crate::joystick::Calibration,
+}
+
+/// Input configuration.
+#[derive(Clone, Debug, Default, serde_derive::Deserialize, PartialEq)]
+pub struct InputConfig {
+    /// Axis curves.
+    #[serde(default)]
+    pub axis: AxisCurves,
+    /// Joystick axis calibration.
+    #[serde(default)]
+    pub calibration: Vec<AxisCalibration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curves() -> [DeadzoneCurve; 3] {
+        [
+            DeadzoneCurve::linear(3_072.0),
+            DeadzoneCurve {
+                deadzone: 2_048.0,
+                expo: 0.6,
+                scale: 1.0,
+            },
+            DeadzoneCurve {
+                deadzone: 4_096.0,
+                expo: 1.0,
+                scale: 1.5,
+            },
+        ]
+    }
+
+    #[test]
+    fn curve_symmetry() {
+        for curve in curves() {
+            for value in (0..=i16::MAX).step_by(97) {
+                assert_eq!(curve.apply(-value), -curve.apply(value), "{}", value);
+            }
+
+            assert_eq!(curve.apply(i16::MIN), -curve.apply(i16::MAX));
+        }
+    }
+
+    #[test]
+    fn curve_deadzone() {
+        for curve in curves() {
+            let deadzone = curve.deadzone as i16;
+
+            for value in -(deadzone - 1)..deadzone {
+                assert_eq!(curve.apply(value), 0, "{}", value);
+            }
+
+            assert_ne!(curve.apply(deadzone), 0);
+            assert_ne!(curve.apply(-deadzone), 0);
+        }
+    }
+
+    #[test]
+    fn curve_monotonic() {
+        for curve in curves() {
+            let table = curve.lookup(61);
+
+            for pair in table.windows(2) {
+                assert!(pair[0].1 <= pair[1].1, "{:?}", pair);
+            }
+
+            assert_eq!(table.first(), Some(&(0, 0)));
+        }
+
+        // Scaled curves saturate at full power.
+        assert_eq!(curves()[2].apply(i16::MAX), Motion::POWER_MAX);
+        assert_eq!(curves()[2].apply(-i16::MAX), -Motion::POWER_MAX);
+    }
+
+    #[test]
+    fn curve_default_linear() {
+        let curve = DeadzoneCurve::linear(3_072.0);
+
+        assert_eq!(curve.apply(120), 0);
+        assert_eq!(curve.apply(20_000), 20_000);
+        assert_eq!(curve.apply(-5_960), -5_960);
+
+        let curves = AxisCurves::default();
+        assert_eq!(curves.boom.apply(-3_000), 0);
+        assert_eq!(curves.slew.apply(-16_200), -16_200);
+    }
+}
@@ -0,0 +1,472 @@
+use std::{
+    io::Write,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Force feedback event type.
+const EV_FF: u16 = 0x15;
+/// Rumble effect type.
+const FF_RUMBLE: u16 = 0x50;
+/// Size of the force feedback capability bitmap in bytes.
+const FF_BITMAP_SIZE: usize = 16;
+
+/// Encode an ioctl request that writes to the device.
+const fn ioc_write(nr: u64, size: usize) -> u64 {
+    (1 << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+}
+
+/// Encode an ioctl request that reads from the device.
+const fn ioc_read(nr: u64, size: usize) -> u64 {
+    (2 << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+}
+
+/// Get the force feedback capability bitmap.
+const EVIOCGBIT_FF: u64 = ioc_read(0x20 + EV_FF as u64, FF_BITMAP_SIZE);
+
+/// Upload a force feedback effect.
+const EVIOCSFF: u64 = ioc_write(0x80, std::mem::size_of::<libc::ff_effect>());
+/// Erase a force feedback effect.
+const EVIOCRMFF: u64 = ioc_write(0x81, std::mem::size_of::<libc::c_int>());
+
+/// Rumble effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rumble {
+    /// Strong (low frequency) motor magnitude.
+    pub strong: u16,
+    /// Weak (high frequency) motor magnitude.
+    pub weak: u16,
+    /// Effect duration.
+    pub duration: Duration,
+}
+
+/// Force feedback device.
+pub trait ForceFeedback {
+    /// Upload a rumble effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The effect to replace, or -1 for a new effect.
+    /// * `rumble` - The rumble effect.
+    ///
+    /// # Returns
+    ///
+    /// The effect identifier assigned by the device.
+    fn upload(&mut self, id: i16, rumble: &Rumble) -> std::io::Result<i16>;
+
+    /// Start playing an effect.
+    fn play(&mut self, id: i16) -> std::io::Result<()>;
+
+    /// Stop playing an effect.
+    fn stop(&mut self, id: i16) -> std::io::Result<()>;
+
+    /// Remove an effect from the device.
+    fn erase(&mut self, id: i16) -> std::io::Result<()>;
+}
+
+/// Kernel event device.
+pub struct EventDevice {
+    file: std::fs::File,
+}
+
+impl EventDevice {
+    /// Open an event device.
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+
+        Ok(Self { file })
+    }
+
+    /// Test if the device supports rumble effects.
+    pub fn supports_rumble(&self) -> std::io::Result<bool> {
+        let mut bitmap = [0u8; FF_BITMAP_SIZE];
+
+        if unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                EVIOCGBIT_FF as _,
+                bitmap.as_mut_ptr(),
+            )
+        } < 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let bit = FF_RUMBLE as usize;
+        Ok(bitmap[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn write_event(&mut self, id: i16, value: i32) -> std::io::Result<()> {
+        let event = libc::input_event {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            type_: EV_FF,
+            code: id as u16,
+            value,
+        };
+
+        let buffer = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+
+        self.file.write_all(buffer)
+    }
+}
+
+impl ForceFeedback for EventDevice {
+    fn upload(&mut self, id: i16, rumble: &Rumble) -> std::io::Result<i16> {
+        let mut effect = unsafe { std::mem::zeroed::<libc::ff_effect>() };
+
+        effect.type_ = FF_RUMBLE;
+        effect.id = id;
+        effect.replay.length = rumble.duration.as_millis().min(u16::MAX as u128) as u16;
+
+        unsafe {
+            let effect_rumble = effect.u.as_mut_ptr() as *mut libc::ff_rumble_effect;
+            (*effect_rumble).strong_magnitude = rumble.strong;
+            (*effect_rumble).weak_magnitude = rumble.weak;
+
+            if libc::ioctl(self.file.as_raw_fd(), EVIOCSFF as _, &mut effect) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+
+        Ok(effect.id)
+    }
+
+    fn play(&mut self, id: i16) -> std::io::Result<()> {
+        self.write_event(id, 1)
+    }
+
+    fn stop(&mut self, id: i16) -> std::io::Result<()> {
+        self.write_event(id, 0)
+    }
+
+    fn erase(&mut self, id: i16) -> std::io::Result<()> {
+        if unsafe { libc::ioctl(self.file.as_raw_fd(), EVIOCRMFF as _, id as libc::c_int) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Force feedback output.
+///
+/// Plays a single rumble effect on the device. Without a device all
+/// operations are a no-op, so input devices without force feedback are
+/// handled the same as devices with force feedback.
+pub struct Feedback<D: ForceFeedback = EventDevice> {
+    /// Force feedback device, `None` if absent.
+    device: Option<D>,
+    /// Uploaded effect.
+    effect: Option<i16>,
+}
+
+impl Feedback<EventDevice> {
+    /// Open the force feedback output of an event device.
+    ///
+    /// If the device cannot be opened or does not support rumble effects the
+    /// output is absent.
+    pub fn open(path: &Path) -> Self {
+        match EventDevice::open(path) {
+            Ok(device) => match device.supports_rumble() {
+                Ok(true) => Self::new(Some(device)),
+                Ok(false) => {
+                    log::debug!("No rumble support on {}", path.display());
+                    Self::new(None)
+                }
+                Err(e) => {
+                    log::debug!("No force feedback on {}: {}", path.display(), e);
+                    Self::new(None)
+                }
+            },
+            Err(e) => {
+                log::debug!("No force feedback on {}: {}", path.display(), e);
+                Self::new(None)
+            }
+        }
+    }
+}
+
+impl<D: ForceFeedback> Feedback<D> {
+    /// Construct a new force feedback output.
+    pub fn new(device: Option<D>) -> Self {
+        Self {
+            device,
+            effect: None,
+        }
+    }
+
+    /// Test if the force feedback device is present.
+    #[inline]
+    pub fn is_present(&self) -> bool {
+        self.device.is_some()
+    }
+
+    /// Play a rumble effect.
+    ///
+    /// The effect replaces the effect that is currently playing.
+    pub fn rumble(&mut self, rumble: &Rumble) -> std::io::Result<()> {
+        let Some(device) = &mut self.device else {
+            return Ok(());
+        };
+
+        let id = device.upload(self.effect.unwrap_or(-1), rumble)?;
+        self.effect = Some(id);
+
+        device.play(id)
+    }
+
+    /// Play a rumble effect.
+    ///
+    /// Same as [`Feedback::rumble`], but fails if the force feedback device is
+    /// absent.
+    pub fn try_rumble(&mut self, rumble: &Rumble) -> std::io::Result<()> {
+        if !self.is_present() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "no force feedback device",
+            ));
+        }
+
+        self.rumble(rumble)
+    }
+
+    /// Stop the rumble effect.
+    pub fn stop(&mut self) -> std::io::Result<()> {
+        match (&mut self.device, self.effect) {
+            (Some(device), Some(id)) => device.stop(id),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<D: ForceFeedback> Drop for Feedback<D> {
+    fn drop(&mut self) {
+        if let (Some(device), Some(id)) = (&mut self.device, self.effect) {
+            if let Err(e) = device.erase(id) {
+                log::debug!("Failed to erase force feedback effect: {}", e);
+            }
+        }
+    }
+}
+
+/// Resolve the event device of a joystick device.
+///
+/// The joystick interface (`/dev/input/jsN`) cannot drive force feedback,
+/// only the event interface (`/dev/input/eventM`) of the same input device
+/// can. Both interfaces are children of the input device in sysfs, so the
+/// event device is found next to the joystick device at
+/// `<sys_class_input>/jsN/device/eventM`. A symbolic link to the joystick
+/// device, such as `/dev/input/by-id/*-joystick`, is followed first.
+///
+/// # Arguments
+///
+/// * `sys_class_input` - The input class directory, `/sys/class/input` on Linux.
+/// * `dev_input` - The input device directory, `/dev/input` on Linux.
+/// * `joystick` - The joystick device path.
+///
+/// # Returns
+///
+/// The event device path, or `None` if the joystick device is unknown.
+pub fn resolve_event_node(
+    sys_class_input: &Path,
+    dev_input: &Path,
+    joystick: &Path,
+) -> Option<PathBuf> {
+    let joystick = std::fs::canonicalize(joystick).unwrap_or_else(|_| joystick.to_path_buf());
+    let name = joystick.file_name()?;
+
+    let mut events = std::fs::read_dir(sys_class_input.join(name).join("device"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("event"))
+        .collect::<Vec<_>>();
+
+    events.sort();
+    events.first().map(|name| dev_input.join(name))
+}
+
+/// Resolve the event device of a joystick device.
+///
+/// See [`resolve_event_node`].
+pub fn event_node(joystick: &Path) -> Option<PathBuf> {
+    resolve_event_node(
+        Path::new("/sys/class/input"),
+        Path::new("/dev/input"),
+        joystick,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug, PartialEq)]
+    enum Operation {
+        Upload(i16, Rumble),
+        Play(i16),
+        Stop(i16),
+        Erase(i16),
+    }
+
+    #[derive(Default)]
+    struct MockDevice {
+        operations: Rc<RefCell<Vec<Operation>>>,
+    }
+
+    impl ForceFeedback for MockDevice {
+        fn upload(&mut self, id: i16, rumble: &Rumble) -> std::io::Result<i16> {
+            self.operations
+                .borrow_mut()
+                .push(Operation::Upload(id, *rumble));
+            Ok(if id < 0 { 3 } else { id })
+        }
+
+        fn play(&mut self, id: i16) -> std::io::Result<()> {
+            self.operations.borrow_mut().push(Operation::Play(id));
+            Ok(())
+        }
+
+        fn stop(&mut self, id: i16) -> std::io::Result<()> {
+            self.operations.borrow_mut().push(Operation::Stop(id));
+            Ok(())
+        }
+
+        fn erase(&mut self, id: i16) -> std::io::Result<()> {
+            self.operations.borrow_mut().push(Operation::Erase(id));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn feedback_sequence() {
+        let device = MockDevice::default();
+        let operations = device.operations.clone();
+
+        let rumble = Rumble {
+            strong: 0x4000,
+            weak: 0xC000,
+            duration: Duration::from_millis(150),
+        };
+
+        let mut feedback = Feedback::new(Some(device));
+        assert!(feedback.is_present());
+
+        feedback.stop().unwrap();
+        assert!(operations.borrow().is_empty());
+
+        feedback.rumble(&rumble).unwrap();
+        feedback.rumble(&rumble).unwrap();
+        feedback.stop().unwrap();
+        drop(feedback);
+
+        assert_eq!(
+            *operations.borrow(),
+            vec![
+                Operation::Upload(-1, rumble),
+                Operation::Play(3),
+                Operation::Upload(3, rumble),
+                Operation::Play(3),
+                Operation::Stop(3),
+                Operation::Erase(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn feedback_absent() {
+        let rumble = Rumble {
+            strong: u16::MAX,
+            weak: 0,
+            duration: Duration::from_millis(500),
+        };
+
+        let mut feedback = Feedback::<MockDevice>::new(None);
+        assert!(!feedback.is_present());
+        feedback.rumble(&rumble).unwrap();
+        feedback.stop().unwrap();
+
+        let mut feedback = Feedback::open(Path::new("/dev/input/glonax-absent"));
+        assert!(!feedback.is_present());
+        feedback.rumble(&rumble).unwrap();
+        feedback.stop().unwrap();
+    }
+
+    #[test]
+    fn feedback_unsupported() {
+        let rumble = Rumble {
+            strong: u16::MAX,
+            weak: 0,
+            duration: Duration::from_millis(500),
+        };
+
+        let mut feedback = Feedback::<MockDevice>::new(None);
+        let err = feedback.try_rumble(&rumble).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+        let device = MockDevice::default();
+        let operations = device.operations.clone();
+
+        let mut feedback = Feedback::new(Some(device));
+        feedback.try_rumble(&rumble).unwrap();
+        assert_eq!(operations.borrow().len(), 2);
+    }
+
+    #[test]
+    fn feedback_event_node() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join(format!("glonax-input-sys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let sys_class_input = root.join("sys/class/input");
+        let dev_input = root.join("dev/input");
+        let device = root.join("sys/devices/usb1/1-1/1-1:1.0/input/input7");
+
+        for dir in ["js0", "event12", "mouse1"] {
+            std::fs::create_dir_all(device.join(dir)).unwrap();
+        }
+        std::fs::create_dir_all(sys_class_input.join("js0")).unwrap();
+        std::fs::create_dir_all(dev_input.join("by-id")).unwrap();
+        symlink(&device, sys_class_input.join("js0/device")).unwrap();
+        std::fs::write(dev_input.join("js0"), []).unwrap();
+        symlink(
+            dev_input.join("js0"),
+            dev_input.join("by-id/usb-Logitech_Extreme_3D-joystick"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            resolve_event_node(&sys_class_input, &dev_input, &dev_input.join("js0")),
+            Some(dev_input.join("event12"))
+        );
+        assert_eq!(
+            resolve_event_node(
+                &sys_class_input,
+                &dev_input,
+                &dev_input.join("by-id/usb-Logitech_Extreme_3D-joystick")
+            ),
+            Some(dev_input.join("event12"))
+        );
+        assert_eq!(
+            resolve_event_node(&sys_class_input, &dev_input, &dev_input.join("js1")),
+            None
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,8 +1,14 @@
+use std::time::Duration;
+
 use crate::{
-    input::{ButtonState, Level, Scancode},
+    feedback::Rumble,
+    input::{ButtonState, Scancode},
     joystick::{Event, EventType},
 };
 
+/// Axis deflection at which an actuator is at its motion limit.
+const AXIS_MOTION_LIMIT: u16 = 32_000;
+
 pub trait InputDevice {
     /// Maps the given event to a scancode.
     ///
@@ -14,12 +20,28 @@ pub trait InputDevice {
     ///
     /// An optional scancode if the event can be mapped, or `None` otherwise.
     fn map(&mut self, event: &Event) -> Option<Scancode>;
+
+    /// Haptic feedback for a scancode.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The scancode mapped from the last event.
+    ///
+    /// # Returns
+    ///
+    /// An optional rumble effect to play, or `None` if the device does not
+    /// give feedback on the scancode.
+    fn feedback(&mut self, _code: &Scancode) -> Option<Rumble> {
+        None
+    }
 }
 
 #[derive(Default)]
 pub struct XboxController {
     reverse_left: bool,
     reverse_right: bool,
+    /// Actuator axes at their motion limit, by slew, arm, boom and attachment.
+    motion_limit: [bool; 4],
 }
 
 impl InputDevice for XboxController {
@@ -111,6 +133,29 @@ impl InputDevice for XboxController {
             _ => None,
         }
     }
+
+    /// Rumble when an actuator axis reaches its motion limit.
+    ///
+    /// The rumble is played once when the axis reaches the limit. The axis
+    /// must leave the limit before the rumble is played again.
+    fn feedback(&mut self, code: &Scancode) -> Option<Rumble> {
+        let (index, value) = match code {
+            Scancode::Slew(value) => (0, value),
+            Scancode::Arm(value) => (1, value),
+            Scancode::Boom(value) => (2, value),
+            Scancode::Attachment(value) => (3, value),
+            _ => return None,
+        };
+
+        let at_limit = value.unsigned_abs() >= AXIS_MOTION_LIMIT;
+        let was_at_limit = std::mem::replace(&mut self.motion_limit[index], at_limit);
+
+        (at_limit && !was_at_limit).then_some(Rumble {
+            strong: 0x4000,
+            weak: 0xC000,
+            duration: Duration::from_millis(150),
+        })
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -164,24 +209,24 @@ impl InputDevice for LogitechJoystick {
                 ..
             } => Some(if self.mode == LogitechJoystickMode::Right {
                 Scancode::Boom(if event.value.is_negative() {
-                    event.value.ramp(3_500)
+                    event.value
                 } else {
-                    (event.value / 2).ramp(1_750)
+                    event.value / 2
                 })
             } else {
-                Scancode::Arm((event.value / 2).ramp(1_500))
+                Scancode::Arm(event.value / 2)
             }),
             Event {
                 ty: EventType::Axis(0),
                 ..
             } => Some(if self.mode == LogitechJoystickMode::Right {
                 Scancode::Attachment(if event.value.is_negative() {
-                    (event.value / 2).ramp(2_000)
+                    event.value / 2
                 } else {
-                    event.value.ramp(4_000)
+                    event.value
                 })
             } else {
-                Scancode::Slew((event.value / 2).ramp(1_000))
+                Scancode::Slew(event.value / 2)
             }),
             Event {
                 ty: EventType::Button(1),
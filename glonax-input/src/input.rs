@@ -1,5 +1,7 @@
 use glonax::core::{Actuator, Engine, Motion, Object};
 
+use crate::curve::AxisCurves;
+
 /// Level trait.
 pub trait Level {
     /// Return the value of self above the lower threshold.
@@ -75,6 +77,8 @@ pub(crate) enum Scancode {
     Left(ButtonState),
     /// Right button.
     Right(ButtonState),
+    /// Input device disconnected.
+    Disconnected,
 }
 
 pub(crate) struct InputState {
@@ -99,6 +103,9 @@ pub(crate) struct InputState {
 
     /// The RPM (Revolutions Per Minute) of the engine.
     pub(crate) engine_rpm: u16,
+
+    /// Deadzone and response curve per axis.
+    pub(crate) curves: AxisCurves,
 }
 
 impl InputState {
@@ -114,11 +121,8 @@ impl InputState {
                     return None;
                 }
 
-                let value = if self.limit_motion {
-                    (value / 2).ramp(1_000)
-                } else {
-                    value.ramp(1_000)
-                };
+                let value = self.curves.slew.apply(value);
+                let value = if self.limit_motion { value / 2 } else { value };
 
                 Some(Object::Motion(Motion::new(Actuator::Slew, value)))
             }
@@ -127,11 +131,8 @@ impl InputState {
                     return None;
                 }
 
-                let value = if self.limit_motion {
-                    (value / 2).ramp(1_500)
-                } else {
-                    value.ramp(1_500)
-                };
+                let value = self.curves.arm.apply(value);
+                let value = if self.limit_motion { value / 2 } else { value };
 
                 Some(Object::Motion(Motion::new(Actuator::Arm, value)))
             }
@@ -140,14 +141,11 @@ impl InputState {
                     return None;
                 }
 
-                let value = if value.is_negative() {
-                    if self.limit_motion {
-                        (value / 2).ramp(2_000)
-                    } else {
-                        value.ramp(2_000)
-                    }
+                let value = self.curves.attachment.apply(value);
+                let value = if value.is_negative() && self.limit_motion {
+                    value / 2
                 } else {
-                    value.ramp(4_000)
+                    value
                 };
 
                 Some(Object::Motion(Motion::new(Actuator::Attachment, value)))
@@ -157,12 +155,11 @@ impl InputState {
                     return None;
                 }
 
-                let value = if value.is_negative() {
-                    value.ramp(3_500)
-                } else if self.limit_motion {
-                    (value / 2).ramp(1_750)
+                let value = self.curves.boom.apply(value);
+                let value = if value.is_positive() && self.limit_motion {
+                    value / 2
                 } else {
-                    value.ramp(1_750)
+                    value
                 };
 
                 Some(Object::Motion(Motion::new(Actuator::Boom, value)))
@@ -172,7 +169,7 @@ impl InputState {
                     return None;
                 }
 
-                let value = value.ramp(2_000);
+                let value = self.curves.left_track.apply(value);
                 if self.drive_lock {
                     Some(Object::Motion(Motion::StraightDrive(value)))
                 } else {
@@ -184,7 +181,7 @@ impl InputState {
                     return None;
                 }
 
-                let value = value.ramp(2_000);
+                let value = self.curves.right_track.apply(value);
                 if self.drive_lock {
                     Some(Object::Motion(Motion::StraightDrive(value)))
                 } else {
@@ -213,6 +210,10 @@ impl InputState {
                 self.motion_lock = true;
                 Some(Object::Motion(Motion::StopAll))
             }
+            Scancode::Disconnected => {
+                self.motion_lock = true;
+                Some(Object::Motion(Motion::StopAll))
+            }
             Scancode::Abort(ButtonState::Released) => {
                 self.motion_lock = false;
                 Some(Object::Motion(Motion::ResumeAll))
@@ -257,6 +258,7 @@ mod tests {
             motion_lock: false,
             limit_motion: false,
             engine_rpm: 1_000,
+            curves: AxisCurves::default(),
         };
 
         assert_eq!(
@@ -288,6 +290,7 @@ mod tests {
             motion_lock: false,
             limit_motion: true,
             engine_rpm: 1_000,
+            curves: AxisCurves::default(),
         };
 
         assert_eq!(
@@ -308,4 +311,22 @@ mod tests {
             Some(Object::Motion(Motion::new(Actuator::Boom, 0_i16)))
         );
     }
+
+    #[test]
+    fn input_state_disconnected() {
+        let mut state = InputState {
+            drive_lock: false,
+            motion_lock: false,
+            limit_motion: false,
+            engine_rpm: 1_000,
+            curves: AxisCurves::default(),
+        };
+
+        assert_eq!(
+            state.try_from(Scancode::Disconnected),
+            Some(Object::Motion(Motion::StopAll))
+        );
+        assert!(state.motion_lock);
+        assert_eq!(state.try_from(Scancode::Boom(22_000)), None);
+    }
 }
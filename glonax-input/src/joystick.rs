@@ -1,13 +1,37 @@
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+use futures_util::{Stream, StreamExt};
 use tokio::io::{AsyncReadExt, BufReader};
 
+use crate::feedback::{Feedback, Rumble};
+
 /// Button pressed/released.
 const JS_EVENT_TYPE_BUTTON: u8 = 0x1;
 /// Joystick moved.
 const JS_EVENT_TYPE_AXIS: u8 = 0x2;
 /// Initial state of device.
 const JS_EVENT_INIT: u8 = 0x80;
+/// Maximum reconnect poll interval.
+const POLL_INTERVAL_MAX: Duration = Duration::from_secs(5);
+/// Size of the device name buffer.
+const NAME_SIZE: usize = 128;
+
+/// Encode an ioctl request that reads from the joystick.
+const fn ioc_read(nr: u64, size: usize) -> u64 {
+    (2 << 30) | ((size as u64) << 16) | ((b'j' as u64) << 8) | nr
+}
+
+/// Get the number of axes.
+const JSIOCGAXES: u64 = ioc_read(0x11, std::mem::size_of::<u8>());
+/// Get the number of buttons.
+const JSIOCGBUTTONS: u64 = ioc_read(0x12, std::mem::size_of::<u8>());
+/// Get the device name.
+const JSIOCGNAME: u64 = ioc_read(0x13, NAME_SIZE);
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -20,6 +44,10 @@ pub enum EventType {
     Axis(u8),
     /// Axis moved.
     AxisInit(u8),
+    /// Device connected.
+    Connected,
+    /// Device disconnected.
+    Disconnected,
 }
 
 #[allow(dead_code)]
@@ -33,42 +61,52 @@ pub struct Event {
     pub value: i16,
 }
 
-impl From<&[u8]> for Event {
-    fn from(buffer: &[u8]) -> Self {
-        let event: JsEvent = unsafe { std::ptr::read(buffer.as_ptr() as *const JsEvent) };
+impl TryFrom<&[u8]> for Event {
+    type Error = std::io::Error;
+
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        let event = JsEvent::from_bytes(buffer).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "joystick event too small")
+        })?;
 
         if event.ty == JS_EVENT_TYPE_BUTTON {
-            Event {
+            Ok(Event {
                 time: event.time,
                 ty: EventType::Button(event.number),
                 value: event.value,
-            }
+            })
         } else if event.ty == JS_EVENT_TYPE_AXIS {
-            Event {
+            Ok(Event {
                 time: event.time,
                 ty: EventType::Axis(event.number),
                 value: -event.value,
-            }
+            })
         } else if event.ty == JS_EVENT_INIT | JS_EVENT_TYPE_BUTTON {
-            Event {
+            Ok(Event {
                 time: event.time,
                 ty: EventType::ButtonInit(event.number),
                 value: event.value,
-            }
+            })
         } else if event.ty == JS_EVENT_INIT | JS_EVENT_TYPE_AXIS {
-            Event {
+            Ok(Event {
                 time: event.time,
                 ty: EventType::AxisInit(event.number),
                 value: -event.value,
-            }
+            })
         } else {
-            unimplemented!();
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unknown joystick event type: {}", event.ty),
+            ))
         }
     }
 }
 
+/// Kernel joystick event.
+///
+/// The event is decoded field by field from the native endian bytes, the
+/// buffer is never reinterpreted as a struct.
 #[derive(Debug)]
-#[repr(C)]
 struct JsEvent {
     /// Event timestamp in milliseconds.
     time: u32,
@@ -80,23 +118,722 @@ struct JsEvent {
     number: u8,
 }
 
-pub struct Joystick(BufReader<tokio::fs::File>);
+impl JsEvent {
+    /// Size of the event on the wire.
+    const SIZE: usize = 8;
+
+    /// Decode the event from a buffer.
+    ///
+    /// Returns `None` if the buffer is smaller than an event.
+    fn from_bytes(buffer: &[u8]) -> Option<Self> {
+        let buffer: &[u8; Self::SIZE] = buffer.get(..Self::SIZE)?.try_into().ok()?;
+
+        Some(Self {
+            time: u32::from_ne_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]),
+            value: i16::from_ne_bytes([buffer[4], buffer[5]]),
+            ty: buffer[6],
+            number: buffer[7],
+        })
+    }
+}
+
+/// Axis calibration.
+///
+/// Worn or cheap sticks drift around the center and do not reach the full
+/// deflection. The calibration ignores the drift within the deadzone and
+/// rescales the usable range between the deadzone and the observed extremes
+/// to the full axis range. The calibration is in event units, after the axis
+/// direction of the event is applied.
+#[derive(Clone, Copy, Debug, PartialEq, serde_derive::Deserialize)]
+pub struct Calibration {
+    /// Deadzone around the center.
+    #[serde(default)]
+    pub deadzone: i16,
+    /// Axis value at full negative deflection.
+    #[serde(default = "Calibration::default_min")]
+    pub min: i16,
+    /// Axis value at full positive deflection.
+    #[serde(default = "Calibration::default_max")]
+    pub max: i16,
+    /// Invert the axis.
+    #[serde(default)]
+    pub invert: bool,
+}
+
+impl Calibration {
+    fn default_min() -> i16 {
+        -i16::MAX
+    }
+
+    fn default_max() -> i16 {
+        i16::MAX
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            deadzone: 0,
+            min: Self::default_min(),
+            max: Self::default_max(),
+            invert: false,
+        }
+    }
+}
+
+impl Event {
+    /// Axis value normalized to the range -1.0 to 1.0.
+    pub fn value_normal(&self) -> f32 {
+        (self.value as f32 / i16::MAX as f32).clamp(-1.0, 1.0)
+    }
+
+    /// Axis value calibrated and normalized to the range -1.0 to 1.0.
+    ///
+    /// Values within the deadzone are zero. Beyond the deadzone the value is
+    /// rescaled so that the calibrated extremes are at full deflection.
+    ///
+    /// # Arguments
+    ///
+    /// * `cal` - The axis calibration.
+    pub fn value_normal_calibrated(&self, cal: &Calibration) -> f32 {
+        let deadzone = cal.deadzone.max(0) as f32 / i16::MAX as f32;
+        let value = self.value_normal();
+
+        let (extreme, magnitude) = if value > 0.0 {
+            (cal.max as f32 / i16::MAX as f32, value)
+        } else {
+            (-(cal.min as f32) / i16::MAX as f32, -value)
+        };
+
+        if magnitude <= deadzone || extreme <= deadzone {
+            return 0.0;
+        }
+
+        let normal = ((magnitude - deadzone) / (extreme - deadzone)).min(1.0) * value.signum();
+
+        if cal.invert {
+            -normal
+        } else {
+            normal
+        }
+    }
+
+    /// Test if the event reports the initial state of the device.
+    pub fn is_init(&self) -> bool {
+        matches!(self.ty, EventType::ButtonInit(_) | EventType::AxisInit(_))
+    }
+
+    /// Synthetic event for a device state change.
+    fn device(ty: EventType) -> Self {
+        Self {
+            time: 0,
+            ty,
+            value: 0,
+        }
+    }
+}
+
+/// Decode the device name from the ioctl buffer.
+///
+/// The name is NUL terminated, unless it fills the entire buffer.
+fn parse_name(buffer: &[u8]) -> String {
+    let len = buffer
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buffer.len());
+
+    String::from_utf8_lossy(&buffer[..len]).trim().to_string()
+}
+
+/// Test if the error means the device is gone.
+///
+/// A device unplugged while reading fails with `ENODEV`, or with `EIO` on
+/// some USB host controllers.
+fn is_disconnect(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::UnexpectedEof
+        || matches!(error.raw_os_error(), Some(libc::ENODEV) | Some(libc::EIO))
+}
+
+/// Event stream configuration.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StreamConfig {
+    /// Skip the init events the device sends on connect.
+    pub skip_init: bool,
+}
+
+pub struct Joystick {
+    /// Device path.
+    path: PathBuf,
+    /// Device reader, `None` while the device is disconnected.
+    reader: Option<BufReader<tokio::fs::File>>,
+    /// Reconnect poll interval.
+    poll_interval: Duration,
+    /// Current reconnect backoff.
+    backoff: Duration,
+    /// Force feedback event device path.
+    feedback_path: Option<PathBuf>,
+    /// Force feedback output.
+    feedback: Feedback,
+    /// Calibration by axis number.
+    calibration: HashMap<u8, Calibration>,
+}
 
 impl Joystick {
     /// Construct new gamepad driver.
-    pub async fn open(path: &Path) -> std::io::Result<Self> {
-        Ok(Self(BufReader::with_capacity(
-            16 * std::mem::size_of::<JsEvent>(),
-            tokio::fs::File::open(path).await?,
-        )))
+    ///
+    /// The driver survives a disconnect of the device. The device is opened
+    /// on the first event. When the device disappears a `Disconnected` event
+    /// is returned and the device path is polled until it reappears, which
+    /// is reported with a `Connected` event. The device then replays its
+    /// state as init events. The poll interval doubles after every failed
+    /// attempt, up to 5 seconds.
+    pub fn reconnecting(path: &Path, poll_interval: Duration) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            reader: None,
+            poll_interval,
+            backoff: poll_interval,
+            feedback_path: None,
+            feedback: Feedback::new(None),
+            calibration: HashMap::new(),
+        }
+    }
+
+    /// Set the force feedback event device.
+    ///
+    /// The force feedback output is driven through the event device that
+    /// belongs to the joystick. The event device is opened when the
+    /// joystick connects. Without an explicit event device, the event device
+    /// is resolved from the joystick device, see [`crate::feedback::event_node`].
+    /// If the event device is absent or does not support rumble effects,
+    /// force feedback is unsupported.
+    pub fn with_feedback(mut self, event_path: &Path) -> Self {
+        self.feedback_path = Some(event_path.to_path_buf());
+        self
+    }
+
+    /// Set the calibration of an axis.
+    ///
+    /// The axis events are calibrated before they are returned, the value is
+    /// rescaled to the full axis range.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The axis number.
+    /// * `calibration` - The axis calibration.
+    pub fn set_calibration(&mut self, axis: u8, calibration: Calibration) {
+        self.calibration.insert(axis, calibration);
+    }
+
+    /// Read from the device with an ioctl.
+    fn ioctl_read(&self, request: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+        let Some(reader) = &self.reader else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "joystick is disconnected",
+            ));
+        };
+
+        if unsafe {
+            libc::ioctl(
+                reader.get_ref().as_raw_fd(),
+                request as _,
+                buffer.as_mut_ptr(),
+            )
+        } < 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Device name as reported by the driver.
+    ///
+    /// Returns a `NotConnected` error while the device is disconnected.
+    pub fn name(&self) -> std::io::Result<String> {
+        let mut buffer = [0u8; NAME_SIZE];
+        self.ioctl_read(JSIOCGNAME, &mut buffer)?;
+
+        Ok(parse_name(&buffer))
+    }
+
+    /// Number of axes on the device.
+    ///
+    /// Returns a `NotConnected` error while the device is disconnected.
+    pub fn axis_count(&self) -> std::io::Result<u8> {
+        let mut buffer = [0u8; 1];
+        self.ioctl_read(JSIOCGAXES, &mut buffer)?;
+
+        Ok(buffer[0])
+    }
+
+    /// Number of buttons on the device.
+    ///
+    /// Returns a `NotConnected` error while the device is disconnected.
+    pub fn button_count(&self) -> std::io::Result<u8> {
+        let mut buffer = [0u8; 1];
+        self.ioctl_read(JSIOCGBUTTONS, &mut buffer)?;
+
+        Ok(buffer[0])
+    }
+
+    /// Apply the axis calibration to an event.
+    fn calibrate(&self, mut event: Event) -> Event {
+        if let EventType::Axis(axis) | EventType::AxisInit(axis) = event.ty {
+            if let Some(calibration) = self.calibration.get(&axis) {
+                event.value =
+                    (event.value_normal_calibrated(calibration) * i16::MAX as f32).round() as i16;
+            }
+        }
+
+        event
+    }
+
+    /// Test if the force feedback output is present.
+    #[inline]
+    pub fn has_feedback(&self) -> bool {
+        self.feedback.is_present()
+    }
+
+    /// Play a rumble effect.
+    ///
+    /// Returns an `Unsupported` error if the joystick has no force feedback.
+    ///
+    /// # Arguments
+    ///
+    /// * `strong` - The strong (low frequency) motor magnitude.
+    /// * `weak` - The weak (high frequency) motor magnitude.
+    /// * `duration` - The effect duration.
+    pub async fn rumble(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        duration: Duration,
+    ) -> std::io::Result<()> {
+        self.feedback.try_rumble(&Rumble {
+            strong,
+            weak,
+            duration,
+        })
+    }
+
+    /// Stop the rumble effect.
+    pub async fn stop_rumble(&mut self) -> std::io::Result<()> {
+        self.feedback.stop()
+    }
+
+    fn reader(file: tokio::fs::File) -> BufReader<tokio::fs::File> {
+        BufReader::with_capacity(16 * JsEvent::SIZE, file)
+    }
+
+    /// Drop the device and its force feedback output.
+    fn disconnect(&mut self) {
+        self.reader = None;
+        self.feedback = Feedback::new(None);
+    }
+
+    /// Reopen the device.
+    ///
+    /// The current device is dropped and the original device path is opened
+    /// again, along with its force feedback output. Unlike the reconnect in
+    /// [`Joystick::next_event`], this does not wait for the device to appear.
+    /// If the device cannot be opened the joystick is left disconnected and
+    /// the next event will poll for it.
+    pub async fn reconnect(&mut self) -> std::io::Result<()> {
+        self.disconnect();
+
+        let file = tokio::fs::File::open(&self.path).await?;
+
+        self.reader = Some(Self::reader(file));
+        self.backoff = self.poll_interval;
+
+        let feedback_path = self
+            .feedback_path
+            .clone()
+            .or_else(|| crate::feedback::event_node(&self.path));
+        if let Some(path) = feedback_path {
+            self.feedback = Feedback::open(&path);
+        }
+
+        Ok(())
+    }
+
+    /// Stream the joystick events.
+    ///
+    /// The stream yields the same events as [`Joystick::next_event`] and
+    /// never ends, the device is reconnected when it disappears. The stream
+    /// can be combined with other streams, for example to merge the events
+    /// of multiple controllers. The stream borrows the joystick, drop it to
+    /// query the device or play force feedback in between events.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The stream configuration.
+    pub fn events(
+        &mut self,
+        config: StreamConfig,
+    ) -> impl Stream<Item = std::io::Result<Event>> + Send + '_ {
+        futures_util::stream::unfold(self, |joystick| async move {
+            let event = joystick.next_event().await;
+            Some((event, joystick))
+        })
+        .filter(move |event| {
+            let is_init = matches!(event, Ok(event) if event.is_init());
+            std::future::ready(!(config.skip_init && is_init))
+        })
     }
 
     /// Return the next event from the gamepad.
     pub async fn next_event(&mut self) -> std::io::Result<Event> {
-        let mut buf = [0; std::mem::size_of::<JsEvent>()];
+        loop {
+            let Some(reader) = &mut self.reader else {
+                match self.reconnect().await {
+                    Ok(_) => return Ok(Event::device(EventType::Connected)),
+                    Err(e) => {
+                        log::trace!("Failed to open {}: {}", self.path.display(), e);
+                        tokio::time::sleep(self.backoff).await;
+                        self.backoff =
+                            (self.backoff * 2).min(POLL_INTERVAL_MAX.max(self.poll_interval));
+                        continue;
+                    }
+                }
+            };
+
+            let mut buf = [0; JsEvent::SIZE];
+
+            return match reader.read_exact(&mut buf).await {
+                Ok(_) => Event::try_from(&buf[..]).map(|event| self.calibrate(event)),
+                Err(e) if is_disconnect(&e) => {
+                    self.disconnect();
+                    Ok(Event::device(EventType::Disconnected))
+                }
+                Err(e) => Err(e),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_bytes(time: u32, value: i16, ty: u8, number: u8) -> Vec<u8> {
+        let mut buffer = time.to_ne_bytes().to_vec();
+        buffer.extend(value.to_ne_bytes());
+        buffer.extend([ty, number]);
+        buffer
+    }
+
+    #[test]
+    fn event_decode() {
+        let event = Event::try_from(&event_bytes(1_234, 500, JS_EVENT_TYPE_AXIS, 3)[..]).unwrap();
+        assert_eq!(event.time, 1_234);
+        assert_eq!(event.value, -500);
+        assert!(matches!(event.ty, EventType::Axis(3)));
+
+        let buffer = event_bytes(7, 1, JS_EVENT_INIT | JS_EVENT_TYPE_BUTTON, 9);
+        let event = Event::try_from(&buffer[..]).unwrap();
+        assert_eq!(event.value, 1);
+        assert!(matches!(event.ty, EventType::ButtonInit(9)));
+    }
+
+    #[test]
+    fn event_decode_invalid() {
+        let buffer = event_bytes(1_234, 500, JS_EVENT_TYPE_AXIS, 3);
+
+        assert!(Event::try_from(&buffer[..7]).is_err());
+        assert!(Event::try_from(&[][..]).is_err());
+        assert!(Event::try_from(&event_bytes(0, 0, 0x40, 0)[..]).is_err());
+    }
+
+    #[test]
+    fn joystick_identity() {
+        // Name as returned by JSIOCGNAME for an Xbox controller.
+        let mut buffer = [0u8; NAME_SIZE];
+        buffer[..31].copy_from_slice(b"Microsoft X-Box 360 pad\0garbage");
+        assert_eq!(parse_name(&buffer), "Microsoft X-Box 360 pad");
+
+        // Name that fills the buffer without terminator.
+        assert_eq!(parse_name(b"Logitech Extreme 3D"), "Logitech Extreme 3D");
+        assert_eq!(parse_name(&[0u8; NAME_SIZE]), "");
+
+        assert_eq!(JSIOCGAXES, 0x8001_6a11);
+        assert_eq!(JSIOCGBUTTONS, 0x8001_6a12);
+        assert_eq!(JSIOCGNAME, 0x8080_6a13);
+
+        let joystick = Joystick::reconnecting(Path::new("/dev/null"), Duration::ZERO);
+        assert_eq!(
+            joystick.name().unwrap_err().kind(),
+            std::io::ErrorKind::NotConnected
+        );
+    }
+
+    fn axis(value: i16) -> Event {
+        Event {
+            time: 0,
+            ty: EventType::Axis(0),
+            value,
+        }
+    }
+
+    #[test]
+    fn calibration_deadzone() {
+        let cal = Calibration {
+            deadzone: 2_000,
+            min: -28_000,
+            max: 30_000,
+            invert: false,
+        };
+
+        for value in [-2_000, -1_999, -150, 0, 1, 980, 2_000] {
+            assert_eq!(axis(value).value_normal_calibrated(&cal), 0.0, "{}", value);
+        }
+
+        assert!(axis(2_001).value_normal_calibrated(&cal) > 0.0);
+        assert!(axis(-2_001).value_normal_calibrated(&cal) < 0.0);
+    }
+
+    #[test]
+    fn calibration_rescale() {
+        let cal = Calibration {
+            deadzone: 2_000,
+            min: -28_000,
+            max: 30_000,
+            invert: false,
+        };
+
+        let assert_normal = |value: i16, cal: &Calibration, expected: f32| {
+            let normal = axis(value).value_normal_calibrated(cal);
+            assert!((normal - expected).abs() < 1e-6, "{}: {}", value, normal);
+        };
+
+        assert_normal(30_000, &cal, 1.0);
+        assert_normal(-28_000, &cal, -1.0);
+        assert_normal(16_000, &cal, 0.5);
+        assert_normal(-15_000, &cal, -0.5);
+
+        // Beyond the calibrated extremes the value saturates.
+        assert_eq!(axis(i16::MAX).value_normal_calibrated(&cal), 1.0);
+        assert_eq!(axis(i16::MIN).value_normal_calibrated(&cal), -1.0);
+
+        let inverted = Calibration {
+            invert: true,
+            ..cal
+        };
+        assert_normal(16_000, &inverted, -0.5);
+
+        // The default calibration is the linear mapping.
+        for value in [-i16::MAX, -12_345, 0, 777, i16::MAX] {
+            let event = axis(value);
+            assert_eq!(
+                event.value_normal_calibrated(&Calibration::default()),
+                event.value_normal()
+            );
+        }
+        assert_eq!(axis(i16::MIN).value_normal(), -1.0);
+
+        let mut joystick = Joystick::reconnecting(Path::new("/dev/null"), Duration::ZERO);
+        joystick.set_calibration(0, cal);
+        assert_eq!(joystick.calibrate(axis(30_000)).value, i16::MAX);
+        assert_eq!(joystick.calibrate(axis(1_000)).value, 0);
+    }
+
+    #[tokio::test]
+    async fn joystick_reconnect() {
+        use std::{io::Write, os::unix::ffi::OsStrExt};
+
+        fn mkfifo(path: &Path) {
+            let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+            assert_eq!(unsafe { libc::mkfifo(path.as_ptr(), 0o600) }, 0);
+        }
+
+        fn write(path: &Path, buffer: Vec<u8>) {
+            let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+            file.write_all(&buffer).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("glonax-js-{}", std::process::id()));
+        mkfifo(&path);
+
+        let mut joystick = Joystick::reconnecting(&path, Duration::from_millis(10));
+
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || write(&path, event_bytes(1, 500, JS_EVENT_TYPE_AXIS, 0))
+        });
+
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Connected));
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Axis(0)));
+
+        writer.join().unwrap();
+
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Disconnected));
+
+        std::fs::remove_file(&path).unwrap();
+
+        let writer = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                std::thread::sleep(Duration::from_millis(50));
+                mkfifo(&path);
+                write(
+                    &path,
+                    event_bytes(2, 1, JS_EVENT_INIT | JS_EVENT_TYPE_BUTTON, 4),
+                );
+            }
+        });
+
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Connected));
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::ButtonInit(4)));
+
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn joystick_reconnect_explicit() {
+        let path = std::env::temp_dir().join(format!("glonax-js-explicit-{}", std::process::id()));
+
+        let mut joystick = Joystick::reconnecting(&path, Duration::from_millis(10));
+
+        let error = joystick.reconnect().await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(joystick.reader.is_none());
+
+        std::fs::write(&path, event_bytes(3, 200, JS_EVENT_TYPE_AXIS, 1)).unwrap();
+
+        joystick.reconnect().await.unwrap();
+        assert!(joystick.reader.is_some());
+
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Axis(1)));
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Disconnected));
+        assert!(joystick.reader.is_none());
+
+        std::fs::remove_file(&path).unwrap();
+
+        // The poll interval backs off while the device is absent.
+        let _ = tokio::time::timeout(Duration::from_millis(100), joystick.next_event()).await;
+        assert!(joystick.backoff > Duration::from_millis(10));
+        assert!(joystick.backoff <= POLL_INTERVAL_MAX);
+
+        std::fs::write(&path, event_bytes(4, 1, JS_EVENT_TYPE_BUTTON, 2)).unwrap();
+
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Connected));
+        assert_eq!(joystick.backoff, Duration::from_millis(10));
+        let event = joystick.next_event().await.unwrap();
+        assert!(matches!(event.ty, EventType::Button(2)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Write a fixed sequence of events to a file that acts as the device.
+    fn memory_device(name: &str, events: &[(u32, i16, u8, u8)]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("glonax-js-{}-{}", name, std::process::id()));
+
+        let buffer: Vec<u8> = events
+            .iter()
+            .flat_map(|(time, value, ty, number)| event_bytes(*time, *value, *ty, *number))
+            .collect();
+        std::fs::write(&path, buffer).unwrap();
+
+        path
+    }
+
+    const DEVICE_EVENTS: [(u32, i16, u8, u8); 4] = [
+        (0, 0, JS_EVENT_INIT | JS_EVENT_TYPE_AXIS, 0),
+        (0, 0, JS_EVENT_INIT | JS_EVENT_TYPE_BUTTON, 1),
+        (10, 500, JS_EVENT_TYPE_AXIS, 0),
+        (20, 1, JS_EVENT_TYPE_BUTTON, 1),
+    ];
+
+    #[tokio::test]
+    async fn joystick_stream() {
+        let path = memory_device("stream", &DEVICE_EVENTS);
+
+        let mut joystick = Joystick::reconnecting(&path, Duration::from_millis(10));
+        let events: Vec<_> = joystick
+            .events(StreamConfig::default())
+            .take(6)
+            .map(|event| event.unwrap().ty)
+            .collect()
+            .await;
+
+        assert!(matches!(
+            events[..],
+            [
+                EventType::Connected,
+                EventType::AxisInit(0),
+                EventType::ButtonInit(1),
+                EventType::Axis(0),
+                EventType::Button(1),
+                EventType::Disconnected,
+            ]
+        ));
+
+        let mut joystick = Joystick::reconnecting(&path, Duration::from_millis(10));
+        let events: Vec<_> = joystick
+            .events(StreamConfig { skip_init: true })
+            .take(4)
+            .map(|event| event.unwrap().ty)
+            .collect()
+            .await;
+
+        assert!(matches!(
+            events[..],
+            [
+                EventType::Connected,
+                EventType::Axis(0),
+                EventType::Button(1),
+                EventType::Disconnected,
+            ]
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn joystick_stream_merge() {
+        let path_left = memory_device("merge-left", &DEVICE_EVENTS);
+        let path_right = memory_device("merge-right", &DEVICE_EVENTS[2..]);
+
+        let mut left = Joystick::reconnecting(&path_left, Duration::from_millis(10));
+        let mut right = Joystick::reconnecting(&path_right, Duration::from_millis(10));
+
+        // Each controller is read until it disconnects.
+        fn controller(joystick: &mut Joystick) -> impl Stream<Item = std::io::Result<Event>> + '_ {
+            joystick
+                .events(StreamConfig { skip_init: true })
+                .take_while(|event| {
+                    let is_disconnect =
+                        matches!(event, Ok(e) if matches!(e.ty, EventType::Disconnected));
+                    std::future::ready(!is_disconnect)
+                })
+        }
+
+        let merged = tokio_stream::StreamExt::merge(controller(&mut left), controller(&mut right));
+        let events: Vec<_> = tokio_stream::StreamExt::timeout(merged, Duration::from_secs(1))
+            .map(|event| event.unwrap().unwrap().ty)
+            .collect()
+            .await;
+
+        assert_eq!(events.len(), 6);
 
-        self.0.read_exact(&mut buf).await?;
+        let count =
+            |predicate: fn(&EventType) -> bool| events.iter().filter(|ty| predicate(ty)).count();
+        assert_eq!(count(|ty| matches!(ty, EventType::Connected)), 2);
+        assert_eq!(count(|ty| matches!(ty, EventType::Axis(0))), 2);
+        assert_eq!(count(|ty| matches!(ty, EventType::Button(1))), 2);
 
-        Ok(Event::from(&buf[..]))
+        std::fs::remove_file(&path_left).unwrap();
+        std::fs::remove_file(&path_right).unwrap();
     }
 }
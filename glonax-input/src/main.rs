@@ -5,8 +5,11 @@
 // of the included license.  See the LICENSE file for details.
 
 use clap::{Parser, ValueEnum, ValueHint};
+use futures_util::StreamExt;
 
 mod config;
+mod curve;
+mod feedback;
 mod gamepad;
 mod input;
 mod joystick;
@@ -23,6 +26,33 @@ enum ControlMode {
     LogitechLeft,
 }
 
+impl ControlMode {
+    /// Infer the control mode from the device name.
+    ///
+    /// Logitech joysticks are inferred as solo mode.
+    fn from_device_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+
+        if name.contains("xbox") || name.contains("x-box") {
+            Some(ControlMode::Xbox)
+        } else if name.contains("logitech") {
+            Some(ControlMode::LogitechSolo)
+        } else {
+            None
+        }
+    }
+
+    /// Input device of the control mode.
+    fn input_device(&self) -> Box<dyn gamepad::InputDevice> {
+        match self {
+            ControlMode::Xbox => Box::<gamepad::XboxController>::default(),
+            ControlMode::LogitechSolo => Box::new(gamepad::LogitechJoystick::solo_mode()),
+            ControlMode::LogitechRight => Box::new(gamepad::LogitechJoystick::right_mode()),
+            ControlMode::LogitechLeft => Box::new(gamepad::LogitechJoystick::left_mode()),
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(author = "Copyright (C) 2024 Laixer Equipment B.V.")]
 #[command(version, propagate_version = true)]
@@ -46,17 +76,23 @@ struct Args {
     )]
     path: Option<std::path::PathBuf>,
     /// Gamepad input device.
-    #[arg(value_hint = ValueHint::FilePath)]
-    device: std::path::PathBuf,
+    #[arg(value_hint = ValueHint::FilePath, required_unless_present = "dump_curve")]
+    device: Option<std::path::PathBuf>,
+    /// Force feedback event device, resolved from the input device if omitted.
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    feedback: Option<std::path::PathBuf>,
     /// Configure failsafe mode.
     #[arg(short, long, default_value_t = true)]
     fail_safe: bool,
     /// Input commands will translate to the full motion range.
     #[arg(long)]
     full_motion: bool,
-    /// Control mode.
+    /// Control mode, inferred from the device name if omitted.
     #[arg(short, long)]
-    mode: ControlMode,
+    mode: Option<ControlMode>,
+    /// Print the axis response curves and exit.
+    #[arg(long)]
+    dump_curve: bool,
     /// Quiet output (no logging).
     #[arg(long)]
     quiet: bool,
@@ -108,9 +144,28 @@ async fn main() -> anyhow::Result<()> {
 
     log::trace!("{:#?}", config);
 
+    if args.dump_curve {
+        dump_curve(&config.input.axis);
+        return Ok(());
+    }
+
     run(config, args).await
 }
 
+/// Print the response curve lookup of each axis.
+fn dump_curve(curves: &curve::AxisCurves) {
+    for (name, curve) in curves.iter() {
+        println!(
+            "{}: deadzone={} expo={} scale={}",
+            name, curve.deadzone, curve.expo, curve.scale
+        );
+
+        for (input, output) in curve.lookup(2_048) {
+            println!("  {:>6} => {:>6}", input, output);
+        }
+    }
+}
+
 async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
     use glonax::consts::*;
 
@@ -126,22 +181,42 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
     log::debug!("Runtime version: {}", VERSION);
     log::debug!("Socket path: {}", socket_path.display());
 
-    let mut joystick = joystick::Joystick::open(&args.device).await?;
+    let Some(device) = &args.device else {
+        return Err(anyhow::anyhow!("Input device is required"));
+    };
 
-    log::debug!("Using joystick {}", args.device.display());
+    let mut joystick =
+        joystick::Joystick::reconnecting(device, std::time::Duration::from_millis(500));
 
-    let mut input_device: Box<dyn crate::gamepad::InputDevice> = match args.mode {
-        ControlMode::Xbox => Box::<gamepad::XboxController>::default(),
-        ControlMode::LogitechSolo => Box::new(gamepad::LogitechJoystick::solo_mode()),
-        ControlMode::LogitechRight => Box::new(gamepad::LogitechJoystick::right_mode()),
-        ControlMode::LogitechLeft => Box::new(gamepad::LogitechJoystick::left_mode()),
-    };
+    log::debug!("Using joystick {}", device.display());
+
+    if let Some(feedback) = &args.feedback {
+        joystick = joystick.with_feedback(feedback);
+
+        log::debug!("Using force feedback {}", feedback.display());
+    }
+
+    for axis in &config.input.calibration {
+        joystick.set_calibration(axis.axis, axis.calibration);
+
+        log::debug!(
+            "Axis {} calibrated: deadzone={} min={} max={} invert={}",
+            axis.axis,
+            axis.calibration.deadzone,
+            axis.calibration.min,
+            axis.calibration.max,
+            axis.calibration.invert
+        );
+    }
+
+    let mut input_device = args.mode.map(|mode| mode.input_device());
 
     let mut input_state = input::InputState {
         drive_lock: false,
         motion_lock: true,
         limit_motion: !args.full_motion,
         engine_rpm: 0,
+        curves: config.input.axis.clone(),
     };
 
     if args.fail_safe {
@@ -174,9 +249,73 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
         return Err(anyhow::anyhow!("Incompatible runtime version"));
     }
 
+    // The input devices do not map the initial device state.
+    let stream_config = joystick::StreamConfig { skip_init: true };
+
     loop {
-        let event = joystick.next_event().await?;
-        if let Some(code) = input_device.map(&event) {
+        let event = {
+            let mut events = std::pin::pin!(joystick.events(stream_config));
+
+            tokio::select! {
+                Some(event) = events.next() => event?,
+                _ = tokio::signal::ctrl_c() => {
+                    log::info!("Termination requested");
+                    break;
+                }
+            }
+        };
+
+        let code = match event.ty {
+            joystick::EventType::Connected => {
+                let name = joystick.name().unwrap_or_default();
+                log::info!("Joystick connected: {}", name);
+                log::debug!(
+                    "Joystick has {} axes and {} buttons",
+                    joystick.axis_count().unwrap_or_default(),
+                    joystick.button_count().unwrap_or_default()
+                );
+                if joystick.has_feedback() {
+                    log::debug!("Force feedback is available");
+                }
+
+                if input_device.is_none() {
+                    let Some(mode) = ControlMode::from_device_name(&name) else {
+                        return Err(anyhow::anyhow!(
+                            "Cannot infer control mode from device '{}', set the control mode",
+                            name
+                        ));
+                    };
+
+                    log::info!(
+                        "Control mode inferred: {}",
+                        mode.to_possible_value().unwrap().get_name()
+                    );
+                    input_device = Some(mode.input_device());
+                }
+
+                None
+            }
+            joystick::EventType::Disconnected => {
+                log::warn!("Joystick disconnected, motion is locked");
+                Some(input::Scancode::Disconnected)
+            }
+            _ => input_device.as_mut().and_then(|device| device.map(&event)),
+        };
+
+        if let Some(rumble) = code
+            .as_ref()
+            .filter(|_| joystick.has_feedback())
+            .and_then(|code| input_device.as_mut()?.feedback(code))
+        {
+            if let Err(e) = joystick
+                .rumble(rumble.strong, rumble.weak, rumble.duration)
+                .await
+            {
+                log::warn!("Failed to play force feedback: {}", e);
+            }
+        }
+
+        if let Some(code) = code {
             if let Some(object) = input_state.try_from(code) {
                 log::trace!("{:?}", object);
 
@@ -188,4 +327,35 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
             }
         }
     }
+
+    if let Err(e) = joystick.stop_rumble().await {
+        log::warn!("Failed to stop force feedback: {}", e);
+    }
+
+    client.close().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_mode_from_device_name() {
+        assert!(matches!(
+            ControlMode::from_device_name("Microsoft X-Box 360 pad"),
+            Some(ControlMode::Xbox)
+        ));
+        assert!(matches!(
+            ControlMode::from_device_name("Xbox Wireless Controller"),
+            Some(ControlMode::Xbox)
+        ));
+        assert!(matches!(
+            ControlMode::from_device_name("Logitech Logitech Extreme 3D"),
+            Some(ControlMode::LogitechSolo)
+        ));
+        assert!(ControlMode::from_device_name("Sony PLAYSTATION(R)3 Controller").is_none());
+        assert!(ControlMode::from_device_name("").is_none());
+    }
 }
@@ -0,0 +1,119 @@
+//! Benchmarks for the protocol encode and decode path.
+//!
+//! Run with `cargo bench -p glonax --features bench`. Criterion stores the
+//! results in `target/criterion` and compares each run against the last
+//! saved baseline. Use `-- --save-baseline <name>` to record a baseline and
+//! `-- --baseline <name>` to compare against it.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nalgebra::{Rotation3, Vector3};
+
+use glonax::{
+    core::{Actuator, Engine, Motion, Rotator},
+    protocol::{frame::Frame, Packetize, Stream},
+    util::crc,
+    world::{Actor, ActorBuilder, ActorSegment},
+};
+
+fn motion() -> Motion {
+    Motion::from_iter(vec![
+        (Actuator::Boom, 12_000),
+        (Actuator::Arm, -8_000),
+        (Actuator::Attachment, 4_000),
+        (Actuator::Slew, -16_000),
+    ])
+}
+
+fn actor() -> Actor {
+    ActorBuilder::new("excavator")
+        .attach_segment(
+            "undercarriage",
+            ActorSegment::new(Vector3::new(0.0, 0.0, 0.0)),
+        )
+        .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 1.295)))
+        .attach_segment("boom", ActorSegment::new(Vector3::new(0.16, 0.0, 0.595)))
+        .attach_segment("arm", ActorSegment::new(Vector3::new(6.0, 0.0, 0.0)))
+        .attach_segment(
+            "attachment",
+            ActorSegment::new(Vector3::new(2.97, 0.0, 0.0)),
+        )
+        .build()
+}
+
+fn rotator() -> Rotator {
+    Rotator::absolute(0x6A, Rotation3::from_euler_angles(0.1, 0.2, 0.3))
+}
+
+/// Encode a packet into a frame and decode it back.
+fn bench_packet<P: Packetize>(c: &mut Criterion, name: &str, packet: P) {
+    let mut group = c.benchmark_group(name);
+
+    group.bench_function("encode", |b| {
+        b.iter(|| Frame::from_packet(black_box(&packet)))
+    });
+
+    let bytes = packet.to_bytes();
+
+    group.bench_function("decode", |b| {
+        b.iter(|| P::try_from(black_box(bytes.as_slice())))
+    });
+
+    group.finish();
+}
+
+/// Send a packet over a duplex stream and receive it on the other end.
+fn bench_stream<P: Packetize>(c: &mut Criterion, name: &str, packet: P) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    let (client, server) = tokio::io::duplex(4_096);
+    let streams = tokio::sync::Mutex::new((Stream::new(client), Stream::new(server)));
+
+    c.bench_function(&format!("{}/stream", name), |b| {
+        b.to_async(&runtime).iter(|| async {
+            let (client, server) = &mut *streams.lock().await;
+
+            client.send_packet(black_box(&packet)).await.unwrap();
+
+            let frame = server.read_frame().await.unwrap();
+            server
+                .recv_packet::<P>(frame.payload_length)
+                .await
+                .map_err(|_| ())
+                .unwrap();
+        })
+    });
+}
+
+/// Compare the CRC implementations on a maximum size payload.
+fn bench_crc(c: &mut Criterion) {
+    let data = (0..1_024).map(|i| i as u8).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("crc16");
+
+    group.bench_function("bitwise", |b| {
+        b.iter(|| crc::crc16_bitwise(black_box(&data)))
+    });
+    group.bench_function("table", |b| b.iter(|| crc::crc16_table(black_box(&data))));
+    group.bench_function("slice8", |b| b.iter(|| crc::crc16_slice8(black_box(&data))));
+
+    group.finish();
+}
+
+fn protocol(c: &mut Criterion) {
+    bench_packet(c, "engine", Engine::from_rpm(1_500));
+    bench_packet(c, "motion", motion());
+    bench_packet(c, "actor", actor());
+    bench_packet(c, "rotator", rotator());
+
+    bench_stream(c, "engine", Engine::from_rpm(1_500));
+    bench_stream(c, "motion", motion());
+    bench_stream(c, "actor", actor());
+    bench_stream(c, "rotator", rotator());
+}
+
+criterion_group!(benches, protocol, bench_crc);
+criterion_main!(benches);
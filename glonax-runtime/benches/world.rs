@@ -0,0 +1,51 @@
+//! Benchmarks for the world model.
+//!
+//! Run with `cargo bench -p glonax --features bench`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nalgebra::{Rotation3, Vector3};
+
+use glonax::world::{Actor, ActorBuilder, ActorSegment};
+
+const SEGMENTS: [&str; 5] = ["undercarriage", "frame", "boom", "arm", "attachment"];
+
+fn actor() -> Actor {
+    let mut actor = ActorBuilder::new("excavator")
+        .attach_segment(
+            "undercarriage",
+            ActorSegment::new(Vector3::new(0.0, 0.0, 0.0)),
+        )
+        .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 1.295)))
+        .attach_segment("boom", ActorSegment::new(Vector3::new(0.16, 0.0, 0.595)))
+        .attach_segment("arm", ActorSegment::new(Vector3::new(6.0, 0.0, 0.0)))
+        .attach_segment(
+            "attachment",
+            ActorSegment::new(Vector3::new(2.97, 0.0, 0.0)),
+        )
+        .build();
+
+    actor.set_segment_rotation("frame", Rotation3::from_euler_angles(0.0, 0.0, 0.7));
+    actor.set_segment_rotation("boom", Rotation3::from_euler_angles(0.0, -0.4, 0.0));
+    actor.set_segment_rotation("arm", Rotation3::from_euler_angles(0.0, 1.3, 0.0));
+
+    actor
+}
+
+/// Compare a location query per segment with the batch transforms.
+fn world_locations(c: &mut Criterion) {
+    let actor = actor();
+
+    let mut group = c.benchmark_group("world_locations");
+
+    group.bench_function("per_segment", |b| {
+        b.iter(|| SEGMENTS.map(|name| black_box(&actor).world_location(name)))
+    });
+    group.bench_function("batch", |b| b.iter(|| black_box(&actor).world_transforms()));
+
+    group.finish();
+}
+
+criterion_group!(benches, world_locations);
+criterion_main!(benches);
@@ -1,4 +1,9 @@
-use std::{io, mem::MaybeUninit, os::unix::prelude::*};
+use std::{
+    io,
+    mem::MaybeUninit,
+    os::unix::prelude::*,
+    task::{ready, Context, Poll},
+};
 
 use socket2::SockAddr;
 use tokio::io::unix::AsyncFd;
@@ -11,6 +16,68 @@ mod sys {
     }
 }
 
+/// Transmit timeout error class.
+pub const CAN_ERR_TX_TIMEOUT: u32 = 0x0000_0001;
+/// Controller problem error class.
+pub const CAN_ERR_CRTL: u32 = 0x0000_0004;
+/// Protocol violation error class, such as a CRC, stuff or form error.
+pub const CAN_ERR_PROT: u32 = 0x0000_0008;
+/// Transceiver status error class.
+pub const CAN_ERR_TRX: u32 = 0x0000_0010;
+/// No acknowledgement on transmission error class.
+pub const CAN_ERR_ACK: u32 = 0x0000_0020;
+/// Bus-off error class.
+pub const CAN_ERR_BUSOFF: u32 = 0x0000_0040;
+/// Bus error class.
+pub const CAN_ERR_BUSERROR: u32 = 0x0000_0080;
+
+/// Error classes reported by the control network.
+pub const CAN_ERR_BUS_MASK: u32 = CAN_ERR_TX_TIMEOUT
+    | CAN_ERR_CRTL
+    | CAN_ERR_PROT
+    | CAN_ERR_TRX
+    | CAN_ERR_ACK
+    | CAN_ERR_BUSOFF
+    | CAN_ERR_BUSERROR;
+
+/// CAN error frame.
+///
+/// Error frames are generated by the controller and only delivered by a
+/// socket with an error filter. An error frame is returned as an I/O error
+/// by the socket, so that it is never mistaken for a data frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CANErrorFrame {
+    /// Error class.
+    pub class: u32,
+    /// Error details.
+    pub data: [u8; 8],
+}
+
+impl CANErrorFrame {
+    /// Retrieve the error frame from an I/O error.
+    ///
+    /// # Returns
+    ///
+    /// The error frame, or `None` if the error is not an error frame.
+    pub fn from_io_error(error: &io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|e| e.downcast_ref::<Self>())
+    }
+}
+
+impl std::error::Error for CANErrorFrame {}
+
+impl std::fmt::Display for CANErrorFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CAN error frame: class 0x{:X}", self.class)
+    }
+}
+
+impl From<CANErrorFrame> for io::Error {
+    fn from(value: CANErrorFrame) -> Self {
+        io::Error::other(value)
+    }
+}
+
 pub struct SockAddrJ1939 {
     pub name: u64,
     pub pgn: u32,
@@ -108,6 +175,59 @@ impl From<SockAddr> for SockAddrCAN {
     }
 }
 
+/// Kernel filter for raw CAN sockets.
+///
+/// A frame passes the filter if `frame_id & mask == id & mask`. The identifier
+/// includes the `CAN_EFF_FLAG` for extended frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CANFilter {
+    /// CAN identifier.
+    pub id: u32,
+    /// CAN identifier mask.
+    pub mask: u32,
+}
+
+impl CANFilter {
+    /// Test if a raw CAN identifier passes the filter.
+    #[inline]
+    pub fn matches(&self, can_id: u32) -> bool {
+        can_id & self.mask == self.id & self.mask
+    }
+}
+
+/// Kernel filter for J1939 sockets.
+///
+/// Predicates that are not set match any value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct J1939Filter {
+    /// Parameter group number.
+    pub pgn: Option<u32>,
+    /// Source address.
+    pub address: Option<u8>,
+}
+
+impl From<&J1939Filter> for libc::j1939_filter {
+    fn from(value: &J1939Filter) -> Self {
+        Self {
+            name: 0,
+            name_mask: 0,
+            pgn: value.pgn.unwrap_or(0),
+            pgn_mask: value.pgn.map_or(0, |_| libc::J1939_PGN_MAX),
+            addr: value.address.unwrap_or(0),
+            addr_mask: value.address.map_or(0, |_| 0xff),
+        }
+    }
+}
+
+/// Convert a raw CAN frame into a J1939 frame.
+fn frame_from_can(can_frame: &libc::can_frame) -> j1939::Frame {
+    let length = (can_frame.can_dlc as usize).min(can_frame.data.len());
+
+    j1939::FrameBuilder::new(j1939::Id::new(can_frame.can_id & libc::CAN_EFF_MASK))
+        .copy_from_slice(&can_frame.data[..length])
+        .build()
+}
+
 pub struct CANSocket(AsyncFd<socket2::Socket>);
 
 impl CANSocket {
@@ -126,6 +246,23 @@ impl CANSocket {
         Ok(Self(AsyncFd::new(socket)?))
     }
 
+    /// Construct a pair of connected sockets.
+    ///
+    /// The sockets are not bound to a CAN interface, a frame sent on one
+    /// socket is received on the other.
+    #[cfg(test)]
+    pub(crate) fn pair() -> io::Result<(Self, Self)> {
+        let (left, right) = std::os::unix::net::UnixDatagram::pair()?;
+
+        let left = socket2::Socket::from(std::os::fd::OwnedFd::from(left));
+        let right = socket2::Socket::from(std::os::fd::OwnedFd::from(right));
+
+        left.set_nonblocking(true)?;
+        right.set_nonblocking(true)?;
+
+        Ok((Self(AsyncFd::new(left)?), Self(AsyncFd::new(right)?)))
+    }
+
     /// Bind to a J1939 address and network interface.
     pub fn bind_j1939(address: &SockAddrJ1939) -> io::Result<Self> {
         let socket = socket2::Socket::new_raw(
@@ -213,9 +350,19 @@ impl CANSocket {
 
     /// Receives a single J1939 frame on the socket from the remote address
     /// to which it is connected. On success, returns the J1939 frame.
+    #[inline]
     pub async fn recv(&self) -> io::Result<j1939::Frame> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Attempts to receive a single J1939 frame on the socket.
+    ///
+    /// The frame is received into storage on the stack, so that polling
+    /// multiple sockets in a loop does not allocate. If the socket is not
+    /// ready, the current task is registered for wakeup.
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<io::Result<j1939::Frame>> {
         loop {
-            let mut guard = self.0.readable().await?;
+            let mut guard = ready!(self.0.poll_read_ready(cx))?;
 
             let mut storage = MaybeUninit::<libc::can_frame>::zeroed();
 
@@ -230,11 +377,15 @@ impl CANSocket {
                 Ok(result) => {
                     let can_frame = unsafe { storage.assume_init() };
 
-                    return result.map(|_size| {
-                        j1939::FrameBuilder::new(j1939::Id::new(can_frame.can_id & 0x1fffffff))
-                            .copy_from_slice(&can_frame.data[..can_frame.can_dlc as usize])
-                            .build()
-                    });
+                    if result.is_ok() && can_frame.can_id & libc::CAN_ERR_FLAG != 0 {
+                        return Poll::Ready(Err(CANErrorFrame {
+                            class: can_frame.can_id & libc::CAN_ERR_MASK,
+                            data: can_frame.data,
+                        }
+                        .into()));
+                    }
+
+                    return Poll::Ready(result.map(|_size| frame_from_can(&can_frame)));
                 }
                 Err(_would_block) => continue,
             }
@@ -293,6 +444,62 @@ impl CANSocket {
         }
     }
 
+    /// Sets the `CAN_RAW_FILTER` option for this socket.
+    ///
+    /// The kernel drops every frame that does not pass any of the filters, so
+    /// that unwanted frames are never copied to userspace. An empty filter
+    /// list drops all frames.
+    pub fn set_filter(&self, filters: &[CANFilter]) -> io::Result<()> {
+        let filters = filters
+            .iter()
+            .map(|filter| libc::can_filter {
+                can_id: filter.id,
+                can_mask: filter.mask,
+            })
+            .collect::<Vec<_>>();
+
+        self.setsockopt(libc::SOL_CAN_RAW, libc::CAN_RAW_FILTER, &filters)
+    }
+
+    /// Sets the `SO_J1939_FILTER` option for this socket.
+    ///
+    /// This only applies to sockets bound with [`bind_j1939`]. The kernel
+    /// drops every packet that does not pass any of the filters.
+    ///
+    /// [`bind_j1939`]: method@Self::bind_j1939
+    pub fn set_j1939_filter(&self, filters: &[J1939Filter]) -> io::Result<()> {
+        let filters = filters
+            .iter()
+            .map(libc::j1939_filter::from)
+            .collect::<Vec<_>>();
+
+        self.setsockopt(libc::SOL_CAN_J1939, libc::SO_J1939_FILTER, &filters)
+    }
+
+    /// Set the error classes delivered as error frames.
+    ///
+    /// The error frames are returned by a receive as a [`CANErrorFrame`].
+    pub fn set_error_filter(&self, mask: u32) -> io::Result<()> {
+        self.setsockopt(libc::SOL_CAN_RAW, libc::CAN_RAW_ERR_FILTER, &[mask])
+    }
+
+    fn setsockopt<T>(&self, level: libc::c_int, name: libc::c_int, value: &[T]) -> io::Result<()> {
+        unsafe {
+            if libc::setsockopt(
+                self.0.as_raw_fd(),
+                level,
+                name,
+                value.as_ptr() as *const libc::c_void,
+                std::mem::size_of_val(value) as libc::socklen_t,
+            ) < 0
+            {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
     /// Get the value of the `SO_ERROR` option on this socket.
     ///
     /// This will retrieve the stored error in the underlying socket, clearing
@@ -309,3 +516,35 @@ impl AsRawFd for CANSocket {
         self.0.as_raw_fd()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_from_can_frame() {
+        let id = j1939::IdBuilder::from_pgn(j1939::PGN::ProprietaryB(0x50))
+            .sa(0x4A)
+            .da(0x20)
+            .build();
+
+        for length in 0..=8 {
+            let data = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+
+            let mut can_frame = unsafe { MaybeUninit::<libc::can_frame>::zeroed().assume_init() };
+            can_frame.can_id = id.as_raw() | libc::CAN_EFF_FLAG;
+            can_frame.can_dlc = length as u8;
+            can_frame.data = data;
+
+            let frame = frame_from_can(&can_frame);
+
+            let expected = j1939::FrameBuilder::new(id)
+                .copy_from_slice(&data[..length])
+                .build();
+
+            assert_eq!(frame.id(), expected.id());
+            assert_eq!(frame.as_ref(), expected.as_ref());
+            assert_eq!(frame.len(), length);
+        }
+    }
+}
@@ -0,0 +1,508 @@
+use crate::protocol::Packetize;
+
+use super::{
+    codec::{DecodeError, Decoder, EncodeError, Encoder},
+    Actuator, Control, MachineType,
+};
+
+/// Capability encoding version.
+///
+/// The version is the schema version of the message, but unlike the other
+/// messages it is only changed when the encoding changes in a way that is not
+/// backwards compatible. New sections can be added without changing the version,
+/// decoders skip sections they do not know.
+const CAPABILITY_VERSION: u8 = 0x01;
+
+const SECTION_MACHINE: u8 = 0x01;
+const SECTION_ACTUATOR: u8 = 0x02;
+const SECTION_CONTROL: u8 = 0x03;
+const SECTION_SENSOR: u8 = 0x04;
+const SECTION_SEGMENT: u8 = 0x05;
+
+/// Maximum size of a section, limited by the section length prefix.
+const SECTION_SIZE_MAX: usize = u16::MAX as usize;
+
+/// Actuator descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActuatorDescriptor {
+    /// Actuator.
+    pub actuator: Actuator,
+    /// Display name.
+    pub name: String,
+    /// Minimum motion value.
+    pub min: i16,
+    /// Maximum motion value.
+    pub max: i16,
+}
+
+impl ActuatorDescriptor {
+    /// Size of the encoded descriptor.
+    fn encoded_len(&self) -> usize {
+        1 + 2 + 2 + 2 + self.name.len()
+    }
+}
+
+/// Sensor descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensorDescriptor {
+    /// Sensor source address.
+    pub source: u8,
+    /// Display name.
+    pub name: String,
+    /// Unit of the sensor value.
+    pub unit: String,
+}
+
+impl SensorDescriptor {
+    /// Size of the encoded descriptor.
+    fn encoded_len(&self) -> usize {
+        1 + 2 + self.name.len() + 2 + self.unit.len()
+    }
+}
+
+/// Segment descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentDescriptor {
+    /// Segment name.
+    pub name: String,
+    /// Encoder source address of the segment joint.
+    pub source: u8,
+}
+
+impl SegmentDescriptor {
+    /// Size of the encoded descriptor.
+    fn encoded_len(&self) -> usize {
+        1 + 2 + self.name.len()
+    }
+}
+
+/// Machine capability descriptor.
+///
+/// Describes what this particular machine supports, so that clients can build
+/// their interface without machine specific knowledge. The descriptor is
+/// assembled by the runtime from configuration and from the drivers that are
+/// present on the network.
+///
+/// Every section of the descriptor must fit its length prefix. A descriptor
+/// that does not fit is refused when it is added, so the capability always
+/// encodes.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::core::{Actuator, Capability, MachineType};
+///
+/// let capability = Capability::new(MachineType::Excavator)
+///     .with_actuator(Actuator::Boom, "Boom", i16::MIN, i16::MAX)
+///     .with_sensor(0x6B, "boom", "rad");
+///
+/// assert_eq!(capability.actuators().len(), 1);
+/// assert_eq!(capability.sensors()[0].unit, "rad");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capability {
+    /// Machine type.
+    pub machine_type: MachineType,
+    /// Actuators.
+    actuators: Vec<ActuatorDescriptor>,
+    /// Controls mapped to hardware outputs.
+    controls: Vec<Control>,
+    /// Sensors.
+    sensors: Vec<SensorDescriptor>,
+    /// Segments of the machine geometry.
+    segments: Vec<SegmentDescriptor>,
+}
+
+impl Capability {
+    /// Construct a new empty capability descriptor.
+    pub fn new(machine_type: MachineType) -> Self {
+        Self {
+            machine_type,
+            actuators: Vec::new(),
+            controls: Vec::new(),
+            sensors: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Actuators.
+    pub fn actuators(&self) -> &[ActuatorDescriptor] {
+        &self.actuators
+    }
+
+    /// Controls mapped to hardware outputs.
+    pub fn controls(&self) -> &[Control] {
+        &self.controls
+    }
+
+    /// Sensors.
+    pub fn sensors(&self) -> &[SensorDescriptor] {
+        &self.sensors
+    }
+
+    /// Segments of the machine geometry.
+    pub fn segments(&self) -> &[SegmentDescriptor] {
+        &self.segments
+    }
+
+    /// Add an actuator.
+    ///
+    /// An actuator that is already described is replaced.
+    ///
+    /// Returns `false` if the actuator does not fit in the actuator section,
+    /// the capability is not changed in that case.
+    pub fn add_actuator(
+        &mut self,
+        actuator: Actuator,
+        name: impl ToString,
+        min: i16,
+        max: i16,
+    ) -> bool {
+        let descriptor = ActuatorDescriptor {
+            actuator,
+            name: name.to_string(),
+            min,
+            max,
+        };
+
+        let len = self
+            .actuators
+            .iter()
+            .filter(|a| a.actuator != actuator)
+            .map(ActuatorDescriptor::encoded_len)
+            .sum::<usize>();
+        if len + descriptor.encoded_len() > SECTION_SIZE_MAX {
+            return false;
+        }
+
+        self.actuators.retain(|a| a.actuator != actuator);
+        self.actuators.push(descriptor);
+
+        true
+    }
+
+    /// Add a control.
+    ///
+    /// The control value is ignored, only the control variant is described.
+    pub fn add_control(&mut self, control: Control) {
+        let control_type = control.control_type();
+
+        if !self
+            .controls
+            .iter()
+            .any(|c| c.control_type() == control_type)
+        {
+            self.controls.push(control);
+        }
+    }
+
+    /// Add a sensor.
+    ///
+    /// Returns `false` if the sensor does not fit in the sensor section, the
+    /// capability is not changed in that case.
+    pub fn add_sensor(&mut self, source: u8, name: impl ToString, unit: impl ToString) -> bool {
+        let descriptor = SensorDescriptor {
+            source,
+            name: name.to_string(),
+            unit: unit.to_string(),
+        };
+
+        let len = self
+            .sensors
+            .iter()
+            .map(SensorDescriptor::encoded_len)
+            .sum::<usize>();
+        if len + descriptor.encoded_len() > SECTION_SIZE_MAX {
+            return false;
+        }
+
+        self.sensors.push(descriptor);
+
+        true
+    }
+
+    /// Add a segment.
+    ///
+    /// Returns `false` if the segment does not fit in the segment section, the
+    /// capability is not changed in that case.
+    pub fn add_segment(&mut self, name: impl ToString, source: u8) -> bool {
+        let descriptor = SegmentDescriptor {
+            name: name.to_string(),
+            source,
+        };
+
+        let len = self
+            .segments
+            .iter()
+            .map(SegmentDescriptor::encoded_len)
+            .sum::<usize>();
+        if len + descriptor.encoded_len() > SECTION_SIZE_MAX {
+            return false;
+        }
+
+        self.segments.push(descriptor);
+
+        true
+    }
+
+    /// Attach an actuator.
+    ///
+    /// An actuator that does not fit is ignored, see [`Capability::add_actuator`].
+    pub fn with_actuator(
+        mut self,
+        actuator: Actuator,
+        name: impl ToString,
+        min: i16,
+        max: i16,
+    ) -> Self {
+        self.add_actuator(actuator, name, min, max);
+        self
+    }
+
+    /// Attach a control.
+    pub fn with_control(mut self, control: Control) -> Self {
+        self.add_control(control);
+        self
+    }
+
+    /// Attach a sensor.
+    ///
+    /// A sensor that does not fit is ignored, see [`Capability::add_sensor`].
+    pub fn with_sensor(mut self, source: u8, name: impl ToString, unit: impl ToString) -> Self {
+        self.add_sensor(source, name, unit);
+        self
+    }
+
+    /// Attach a segment.
+    ///
+    /// A segment that does not fit is ignored, see [`Capability::add_segment`].
+    pub fn with_segment(mut self, name: impl ToString, source: u8) -> Self {
+        self.add_segment(name, source);
+        self
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Type: {:?}; Actuators: {}; Controls: {}; Sensors: {}; Segments: {}",
+            self.machine_type,
+            self.actuators.len(),
+            self.controls.len(),
+            self.sensors.len(),
+            self.segments.len()
+        )
+    }
+}
+
+impl TryFrom<&[u8]> for Capability {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        if decoder.version() != CAPABILITY_VERSION {
+            return Err(DecodeError::UnsupportedVersion(decoder.version()));
+        }
+
+        let mut machine_type = None;
+        let mut capability = Capability::new(MachineType::Excavator);
+
+        while decoder.has_remaining() {
+            let tag = decoder.get_u8()?;
+            let mut section = decoder.get_section()?;
+
+            match tag {
+                SECTION_MACHINE => {
+                    machine_type = Some(section.get_value::<MachineType>()?);
+                }
+                SECTION_ACTUATOR => {
+                    while section.has_remaining() {
+                        let actuator = Actuator::try_from(section.get_u8()? as u16)
+                            .map_err(|_| section.invalid(1))?;
+                        let min = section.get_i16()?;
+                        let max = section.get_i16()?;
+                        let name = section.get_string()?;
+
+                        capability.add_actuator(actuator, name, min, max);
+                    }
+                }
+                SECTION_CONTROL => {
+                    while section.has_remaining() {
+                        let control =
+                            Control::from_parts(section.get_u8()?, 0).ok_or(section.invalid(1))?;
+                        capability.add_control(control);
+                    }
+                }
+                SECTION_SENSOR => {
+                    while section.has_remaining() {
+                        let source = section.get_u8()?;
+                        let name = section.get_string()?;
+                        let unit = section.get_string()?;
+
+                        capability.add_sensor(source, name, unit);
+                    }
+                }
+                SECTION_SEGMENT => {
+                    while section.has_remaining() {
+                        let source = section.get_u8()?;
+                        let name = section.get_string()?;
+
+                        capability.add_segment(name, source);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The machine section is required.
+        capability.machine_type = machine_type.ok_or(DecodeError::InvalidValue { offset: 0 })?;
+
+        Ok(capability)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Capability {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl Capability {
+    /// Encode the capability descriptor.
+    fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut encoder = Encoder::with_capacity(CAPABILITY_VERSION, 256);
+
+        encoder.put_u8(SECTION_MACHINE);
+        encoder.put_section(|section| {
+            section.put_u8(self.machine_type as u8);
+            Ok(())
+        })?;
+
+        encoder.put_u8(SECTION_ACTUATOR);
+        encoder.put_section(|section| {
+            for actuator in &self.actuators {
+                section.put_u8(actuator.actuator.id());
+                section.put_i16(actuator.min);
+                section.put_i16(actuator.max);
+                section.put_string(&actuator.name)?;
+            }
+            Ok(())
+        })?;
+
+        encoder.put_u8(SECTION_CONTROL);
+        encoder.put_section(|section| {
+            for control in &self.controls {
+                section.put_u8(control.control_type());
+            }
+            Ok(())
+        })?;
+
+        encoder.put_u8(SECTION_SENSOR);
+        encoder.put_section(|section| {
+            for sensor in &self.sensors {
+                section.put_u8(sensor.source);
+                section.put_string(&sensor.name)?;
+                section.put_string(&sensor.unit)?;
+            }
+            Ok(())
+        })?;
+
+        encoder.put_u8(SECTION_SEGMENT);
+        encoder.put_section(|section| {
+            for segment in &self.segments {
+                section.put_u8(segment.source);
+                section.put_string(&segment.name)?;
+            }
+            Ok(())
+        })?;
+
+        Ok(encoder.into_vec())
+    }
+}
+
+impl Packetize for Capability {
+    const MESSAGE_TYPE: u8 = 0x17;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        // Every section is checked when a descriptor is added, so encoding
+        // does not fail.
+        self.encode().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    const GOLDEN: [u8; 54] = [
+        0x01,
+        0x01, 0x00, 0x01, 0x01,
+        0x02, 0x00, 0x0B, 0x00, 0x80, 0x00, 0x7F, 0xFF, 0x00, 0x04, b'B', b'o', b'o', b'm',
+        0x03, 0x00, 0x01, 0x1E,
+        0x04, 0x00, 0x0C, 0x6B, 0x00, 0x04, b'b', b'o', b'o', b'm', 0x00, 0x03, b'r', b'a', b'd',
+        0x05, 0x00, 0x0D, 0x6B, 0x00, 0x04, b'b', b'o', b'o', b'm', 0x6C, 0x00, 0x03, b'a', b'r', b'm',
+    ];
+
+    fn capability() -> Capability {
+        Capability::new(MachineType::Excavator)
+            .with_actuator(Actuator::Boom, "Boom", i16::MIN, i16::MAX)
+            .with_control(Control::MachineHorn(true))
+            .with_sensor(0x6B, "boom", "rad")
+            .with_segment("boom", 0x6B)
+            .with_segment("arm", 0x6C)
+    }
+
+    #[test]
+    fn test_capability_golden() {
+        assert_eq!(capability().to_bytes(), GOLDEN.to_vec());
+    }
+
+    #[test]
+    fn test_capability_decode() {
+        let capability_b = Capability::try_from(GOLDEN.to_vec()).unwrap();
+
+        assert_eq!(capability_b.machine_type, MachineType::Excavator);
+        assert_eq!(capability_b.actuators, capability().actuators);
+        assert_eq!(capability_b.controls, vec![Control::MachineHorn(false)]);
+        assert_eq!(capability_b.sensors, capability().sensors);
+        assert_eq!(capability_b.segments, capability().segments);
+    }
+
+    #[test]
+    fn test_capability_overflow() {
+        let name = "x".repeat(u16::MAX as usize);
+
+        let mut descriptor = capability();
+        assert!(!descriptor.add_sensor(0x6C, &name, "rad"));
+        assert!(!descriptor.add_actuator(Actuator::Boom, &name, 0, 0));
+        assert!(descriptor.add_segment(&name[..u16::MAX as usize - 16], 0x6D));
+        assert!(!descriptor.add_segment("bucket", 0x6E));
+
+        assert_eq!(descriptor.actuators, capability().actuators);
+        assert_eq!(descriptor.sensors, capability().sensors);
+        assert_eq!(descriptor.segments.len(), 3);
+
+        let capability_b = Capability::try_from(descriptor.to_bytes()).unwrap();
+        assert_eq!(capability_b.segments, descriptor.segments);
+    }
+
+    #[test]
+    fn test_capability_extension() {
+        let mut bytes = GOLDEN.to_vec();
+        bytes.extend_from_slice(&[0x7F, 0x00, 0x02, 0xAB, 0xCD]);
+
+        let capability_b = Capability::try_from(bytes).unwrap();
+        assert_eq!(capability_b.segments, capability().segments);
+
+        let mut bytes = GOLDEN.to_vec();
+        bytes[0] = 0x02;
+        assert!(Capability::try_from(bytes).is_err());
+
+        assert!(Capability::try_from(GOLDEN[..20].to_vec()).is_err());
+    }
+}
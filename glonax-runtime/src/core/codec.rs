@@ -0,0 +1,839 @@
+use bytes::{BufMut, BytesMut};
+
+/// Error decoding a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The buffer ends before the field at the offset.
+    ShortBuffer {
+        /// Offset of the field.
+        offset: usize,
+        /// Size of the field.
+        len: usize,
+    },
+    /// The field at the offset holds an invalid value.
+    InvalidValue {
+        /// Offset of the field.
+        offset: usize,
+    },
+    /// The message schema version is not supported.
+    UnsupportedVersion(u8),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ShortBuffer { offset, len } => {
+                write!(f, "buffer too short for {} bytes at offset {}", len, offset)
+            }
+            Self::InvalidValue { offset } => write!(f, "invalid value at offset {}", offset),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported schema version {}", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Error encoding a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The length of a string or section does not fit its length prefix.
+    LengthOverflow(usize),
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LengthOverflow(len) => {
+                write!(f, "length {} exceeds the maximum of {}", len, u16::MAX)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+/// Maximum length of an encoded string, limited by the length prefix.
+pub const STRING_LEN_MAX: usize = u16::MAX as usize;
+
+/// Truncate a string to the maximum length of an encoded string.
+///
+/// The string is cut at a character boundary, so the result can be shorter
+/// than the maximum length.
+pub fn truncate_string(value: &str) -> &str {
+    let mut end = value.len().min(STRING_LEN_MAX);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &value[..end]
+}
+
+/// Message encoder.
+///
+/// Every message starts with the schema version of the message. The version
+/// is bumped when fields are appended to the message, so a decoder knows which
+/// fields are present. Fields are never removed or reordered.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::core::codec::{Decoder, Encoder};
+///
+/// let mut encoder = Encoder::new(1);
+/// encoder.put_u16(1_500);
+/// encoder.put_string("boom").unwrap();
+///
+/// let bytes = encoder.into_vec();
+/// assert_eq!(bytes, [0x01, 0x05, 0xDC, 0x00, 0x04, b'b', b'o', b'o', b'm']);
+///
+/// let mut decoder = Decoder::new(&bytes).unwrap();
+/// assert_eq!(decoder.version(), 1);
+/// assert_eq!(decoder.get_u16(), Ok(1_500));
+/// assert_eq!(decoder.get_string().unwrap(), "boom");
+/// assert!(decoder.get_u8().is_err());
+/// ```
+pub struct Encoder {
+    buf: BytesMut,
+}
+
+impl Encoder {
+    /// Construct a new encoder.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The schema version of the message.
+    pub fn new(version: u8) -> Self {
+        Self::with_capacity(version, 32)
+    }
+
+    /// Construct a new encoder with a buffer capacity.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The schema version of the message.
+    /// * `capacity` - The expected size of the message, without the version.
+    pub fn with_capacity(version: u8, capacity: usize) -> Self {
+        let mut buf = BytesMut::with_capacity(1 + capacity);
+        buf.put_u8(version);
+
+        Self { buf }
+    }
+
+    /// Size of the encoded message, including the version.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Test if the encoder holds no bytes.
+    ///
+    /// The version is always encoded, so this is never the case.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    #[inline]
+    pub fn put_u8(&mut self, value: u8) {
+        self.buf.put_u8(value);
+    }
+
+    #[inline]
+    pub fn put_bool(&mut self, value: bool) {
+        self.buf.put_u8(u8::from(value));
+    }
+
+    #[inline]
+    pub fn put_u16(&mut self, value: u16) {
+        self.buf.put_u16(value);
+    }
+
+    #[inline]
+    pub fn put_i16(&mut self, value: i16) {
+        self.buf.put_i16(value);
+    }
+
+    #[inline]
+    pub fn put_u64(&mut self, value: u64) {
+        self.buf.put_u64(value);
+    }
+
+    #[inline]
+    pub fn put_f32(&mut self, value: f32) {
+        self.buf.put_f32(value);
+    }
+
+    #[inline]
+    pub fn put_slice(&mut self, value: &[u8]) {
+        self.buf.put_slice(value);
+    }
+
+    /// Put a string, prefixed by its length.
+    ///
+    /// Returns an error if the string is longer than the length prefix allows,
+    /// nothing is encoded in that case.
+    pub fn put_string(&mut self, value: &str) -> Result<(), EncodeError> {
+        let len =
+            u16::try_from(value.len()).map_err(|_| EncodeError::LengthOverflow(value.len()))?;
+
+        self.buf.put_u16(len);
+        self.buf.put_slice(value.as_bytes());
+
+        Ok(())
+    }
+
+    /// Put a string, prefixed by its length.
+    ///
+    /// A string longer than the length prefix allows is truncated, see
+    /// [`truncate_string`].
+    pub fn put_bounded_string(&mut self, value: &str) {
+        let value = truncate_string(value);
+
+        self.buf.put_u16(value.len() as u16);
+        self.buf.put_slice(value.as_bytes());
+    }
+
+    /// Put a section, prefixed by its length.
+    ///
+    /// Returns an error if the section fails to encode or if the section is
+    /// longer than the length prefix allows. The encoder must not be used after
+    /// an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `encode` - Encodes the fields of the section.
+    pub fn put_section(
+        &mut self,
+        encode: impl FnOnce(&mut Self) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError> {
+        let start = self.buf.len();
+        self.buf.put_u16(0);
+
+        encode(self)?;
+
+        let len = self.buf.len() - start - std::mem::size_of::<u16>();
+        let len = u16::try_from(len).map_err(|_| EncodeError::LengthOverflow(len))?;
+        self.buf[start..start + std::mem::size_of::<u16>()].copy_from_slice(&len.to_be_bytes());
+
+        Ok(())
+    }
+
+    /// Encoded message.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buf.to_vec()
+    }
+}
+
+/// Message decoder.
+///
+/// Reads the fields of a message in order. Reading past the end of the buffer
+/// returns an error with the offset of the field instead of panicking. Bytes
+/// following the last known field are ignored, they belong to fields added in
+/// a later schema version.
+#[derive(Clone)]
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    version: u8,
+}
+
+impl<'a> Decoder<'a> {
+    /// Construct a new decoder and read the schema version.
+    ///
+    /// Version zero is never encoded and is rejected.
+    pub fn new(buf: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut decoder = Self {
+            buf,
+            offset: 0,
+            version: 0,
+        };
+
+        decoder.version = decoder.get_u8()?;
+        if decoder.version == 0 {
+            return Err(DecodeError::UnsupportedVersion(0));
+        }
+
+        Ok(decoder)
+    }
+
+    /// Construct a new decoder for a message without a schema version.
+    ///
+    /// The version of the decoder is zero.
+    pub fn unversioned(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            offset: 0,
+            version: 0,
+        }
+    }
+
+    /// Schema version of the message.
+    #[inline]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Offset of the next field.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of bytes left in the buffer.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.offset
+    }
+
+    /// Test if there are bytes left in the buffer.
+    #[inline]
+    pub fn has_remaining(&self) -> bool {
+        self.remaining() > 0
+    }
+
+    /// Error for an invalid value in the field that was just read.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The size of the field.
+    #[inline]
+    pub fn invalid(&self, len: usize) -> DecodeError {
+        DecodeError::InvalidValue {
+            offset: self.offset - len,
+        }
+    }
+
+    /// Take the next bytes from the buffer.
+    pub fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.remaining() < len {
+            return Err(DecodeError::ShortBuffer {
+                offset: self.offset,
+                len,
+            });
+        }
+
+        let bytes = &self.buf[self.offset..self.offset + len];
+        self.offset += len;
+
+        Ok(bytes)
+    }
+
+    fn get_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        Ok(self.get_bytes(N)?.try_into().unwrap())
+    }
+
+    #[inline]
+    pub fn get_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.get_array::<1>()?[0])
+    }
+
+    #[inline]
+    pub fn get_bool(&mut self) -> Result<bool, DecodeError> {
+        Ok(self.get_u8()? != 0)
+    }
+
+    #[inline]
+    pub fn get_u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.get_array()?))
+    }
+
+    #[inline]
+    pub fn get_i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(i16::from_be_bytes(self.get_array()?))
+    }
+
+    #[inline]
+    pub fn get_u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_be_bytes(self.get_array()?))
+    }
+
+    #[inline]
+    pub fn get_f32(&mut self) -> Result<f32, DecodeError> {
+        Ok(f32::from_be_bytes(self.get_array()?))
+    }
+
+    /// Get a string, prefixed by its length.
+    ///
+    /// Invalid UTF-8 sequences are replaced.
+    pub fn get_string(&mut self) -> Result<String, DecodeError> {
+        let len = self.get_u16()? as usize;
+
+        Ok(String::from_utf8_lossy(self.get_bytes(len)?).to_string())
+    }
+
+    /// Get a section, prefixed by its length.
+    ///
+    /// The section is decoded by the returned decoder, which shares the
+    /// version and offsets of this decoder.
+    pub fn get_section(&mut self) -> Result<Decoder<'a>, DecodeError> {
+        let len = self.get_u16()? as usize;
+        let start = self.offset;
+        self.get_bytes(len)?;
+
+        Ok(Decoder {
+            buf: &self.buf[..start + len],
+            offset: start,
+            version: self.version,
+        })
+    }
+
+    /// Get a value from a byte.
+    ///
+    /// Returns an invalid value error if the byte does not convert.
+    pub fn get_value<T: TryFrom<u8>>(&mut self) -> Result<T, DecodeError> {
+        let value = self.get_u8()?;
+
+        T::try_from(value).map_err(|_| self.invalid(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use nalgebra::{Point3, Rotation3, UnitQuaternion};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        core::{
+            Actuator, Capability, Control, CylinderPressure, DiagnosticCodes,
+            DiagnosticTroubleCode, Engine, Gnss, Instance, LoadEstimate, ModuleError, ModuleStatus,
+            Motion, OperationHours, Rotator, RotatorRate, Target,
+        },
+        protocol::Packetize,
+    };
+
+    const ITERATIONS: usize = 200;
+
+    /// Encode and decode a packet.
+    ///
+    /// The packet must decode the same with trailing bytes, and must not panic
+    /// on any truncation of the message.
+    fn roundtrip<P: Packetize>(packet: &P, trailing: &[u8]) -> P {
+        let bytes = packet.to_bytes();
+
+        if let Some(size) = P::MESSAGE_SIZE {
+            assert_eq!(bytes.len(), size);
+        }
+
+        for len in 0..bytes.len() {
+            let result = P::try_from(&bytes[..len]);
+            if P::MESSAGE_SIZE.is_some() {
+                assert!(result.is_err());
+            }
+        }
+
+        let decoded = P::try_from(&bytes[..]).ok().unwrap();
+
+        let mut extended = bytes.clone();
+        extended.extend_from_slice(trailing);
+        let extended = P::try_from(&extended[..]).ok().unwrap();
+        assert_eq!(extended.to_bytes(), decoded.to_bytes());
+
+        decoded
+    }
+
+    /// Random bytes of a later schema version.
+    fn trailing(rng: &mut StdRng) -> Vec<u8> {
+        (0..rng.gen_range(1..16)).map(|_| rng.gen()).collect()
+    }
+
+    /// Random value of a type that converts from a byte.
+    fn pick<T: TryFrom<u8>>(rng: &mut StdRng) -> T {
+        loop {
+            if let Ok(value) = T::try_from(rng.gen()) {
+                return value;
+            }
+        }
+    }
+
+    fn string(rng: &mut StdRng) -> String {
+        let len = rng.gen_range(0..24);
+        (0..len).map(|_| rng.gen::<char>()).collect()
+    }
+
+    fn rotation(rng: &mut StdRng) -> Rotation3<f32> {
+        Rotation3::from_euler_angles(
+            rng.gen_range(-3.1..3.1),
+            rng.gen_range(-1.5..1.5),
+            rng.gen_range(-3.1..3.1),
+        )
+    }
+
+    #[test]
+    fn roundtrip_engine_control_motion() {
+        let mut rng = StdRng::seed_from_u64(770);
+
+        for _ in 0..ITERATIONS {
+            let engine = Engine {
+                driver_demand: rng.gen(),
+                actual_engine: rng.gen(),
+                rpm: rng.gen(),
+                state: pick(&mut rng),
+            };
+            assert_eq!(roundtrip(&engine, &trailing(&mut rng)), engine);
+
+            let control = loop {
+                match Control::from_parts(rng.gen(), rng.gen()) {
+                    Some(Control::TickInterval { interval, .. }) => {
+                        break Control::TickInterval {
+                            network: rng.gen(),
+                            interval,
+                        }
+                    }
+                    Some(control) => break control,
+                    None => {}
+                }
+            };
+            assert_eq!(roundtrip(&control, &trailing(&mut rng)), control);
+
+            let count = rng.gen_range(0..=8);
+            let motion = match rng.gen_range(0..6) {
+                0 => Motion::StopAll,
+                1 => Motion::ResumeAll,
+                2 => Motion::ResetAll,
+                3 => Motion::Stop(
+                    (0..count)
+                        .map(|_| *Actuator::ALL.choose(&mut rng).unwrap())
+                        .collect(),
+                ),
+                4 => Motion::StraightDrive(rng.gen()),
+                _ => Motion::from_iter(
+                    (0..count).map(|_| (*Actuator::ALL.choose(&mut rng).unwrap(), rng.gen())),
+                ),
+            };
+            assert_eq!(roundtrip(&motion, &trailing(&mut rng)), motion);
+        }
+    }
+
+    #[test]
+    fn roundtrip_sensor_packets() {
+        let mut rng = StdRng::seed_from_u64(770);
+
+        for _ in 0..ITERATIONS {
+            let gnss = Gnss {
+                location: (rng.gen_range(-90.0..90.0), rng.gen_range(-180.0..180.0)),
+                altitude: rng.gen_range(-100.0..1_000.0),
+                speed: rng.gen_range(0.0..50.0),
+                heading: rng.gen_range(0.0..360.0),
+                satellites: rng.gen(),
+                status: pick(&mut rng),
+                fix_quality: pick(&mut rng),
+                satellites_used: rng.gen(),
+                hdop: rng.gen_range(0.0..100.0),
+            };
+            assert_eq!(roundtrip(&gnss, &trailing(&mut rng)), gnss);
+
+            let rotator = Rotator {
+                source: rng.gen(),
+                rotator: rotation(&mut rng),
+                reference: pick(&mut rng),
+            };
+            let decoded = roundtrip(&rotator, &trailing(&mut rng));
+            assert_eq!(decoded.source, rotator.source);
+            assert_eq!(decoded.reference, rotator.reference);
+            assert!((decoded.rotator.matrix() - rotator.rotator.matrix()).amax() < 1e-4);
+
+            let rate = RotatorRate {
+                source: rng.gen(),
+                velocity: rng.gen_range(-10.0..10.0),
+                acceleration: rng.gen_range(-10.0..10.0),
+                valid: rng.gen(),
+            };
+            assert_eq!(roundtrip(&rate, &trailing(&mut rng)), rate);
+
+            let pressure = CylinderPressure::new(
+                *Actuator::ALL.choose(&mut rng).unwrap(),
+                rng.gen_range(0.0..400.0),
+                rng.gen_range(0.0..400.0),
+            );
+            assert_eq!(roundtrip(&pressure, &trailing(&mut rng)), pressure);
+
+            let estimate = LoadEstimate {
+                mass: rng.gen_range(0.0..10_000.0),
+                valid: rng.gen(),
+            };
+            assert_eq!(roundtrip(&estimate, &trailing(&mut rng)), estimate);
+
+            let target = Target {
+                point: Point3::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                ),
+                orientation: UnitQuaternion::from_rotation_matrix(&rotation(&mut rng)),
+                constraint: pick(&mut rng),
+            };
+            let decoded = roundtrip(&target, &trailing(&mut rng));
+            assert_eq!(decoded.point, target.point);
+            assert_eq!(decoded.constraint, target.constraint);
+            assert!(
+                (decoded.orientation.to_rotation_matrix().matrix()
+                    - target.orientation.to_rotation_matrix().matrix())
+                .amax()
+                    < 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip_status_packets() {
+        const ERRORS: [ModuleError; 7] = [
+            ModuleError::InvalidConfiguration,
+            ModuleError::VersionMismatch,
+            ModuleError::CommunicationTimeout,
+            ModuleError::GenericCommunicationError,
+            ModuleError::IOError,
+            ModuleError::SensorFrozen,
+            ModuleError::ServiceStalled,
+        ];
+
+        let mut rng = StdRng::seed_from_u64(770);
+
+        for _ in 0..ITERATIONS {
+            let status = ModuleStatus {
+                name: string(&mut rng),
+                state: pick(&mut rng),
+                error: rng.gen::<bool>().then(|| *ERRORS.choose(&mut rng).unwrap()),
+                update_rate: rng.gen::<bool>().then(|| rng.gen_range(0.0..1_000.0)),
+            };
+            assert_eq!(roundtrip(&status, &trailing(&mut rng)), status);
+
+            let instance = Instance::new(
+                uuid::Uuid::from_bytes(rng.gen()),
+                string(&mut rng),
+                pick(&mut rng),
+                rng.gen(),
+                string(&mut rng),
+            );
+            assert_eq!(roundtrip(&instance, &trailing(&mut rng)), instance);
+
+            let codes = DiagnosticCodes {
+                source: rng.gen(),
+                lamps: rng.gen(),
+                codes: (0..rng.gen_range(0..8))
+                    .map(|_| DiagnosticTroubleCode {
+                        spn: rng.gen_range(0..0x8_0000),
+                        fmi: rng.gen_range(0..32),
+                        occurrences: rng.gen_range(0..0x80),
+                    })
+                    .collect(),
+            };
+            assert_eq!(roundtrip(&codes, &trailing(&mut rng)), codes);
+
+            let mut hours = OperationHours {
+                engine: Duration::from_millis(rng.gen::<u32>() as u64),
+                actuator: HashMap::new(),
+            };
+            for actuator in Actuator::ALL {
+                if rng.gen() {
+                    let active = Duration::from_millis(rng.gen::<u32>() as u64);
+                    hours.actuator.insert(actuator, active);
+                }
+            }
+            assert_eq!(roundtrip(&hours, &trailing(&mut rng)), hours);
+
+            let mut capability = Capability::new(pick(&mut rng));
+            for actuator in Actuator::ALL {
+                if rng.gen() {
+                    capability.add_actuator(actuator, string(&mut rng), rng.gen(), rng.gen());
+                }
+            }
+            for _ in 0..rng.gen_range(0..4) {
+                capability.add_sensor(rng.gen(), string(&mut rng), string(&mut rng));
+                capability.add_segment(string(&mut rng), rng.gen());
+            }
+
+            // The capability is extended with sections, not with trailing bytes.
+            assert_eq!(
+                roundtrip(&capability, &[0x7F, 0x00, 0x01, 0xAB]),
+                capability
+            );
+        }
+    }
+
+    #[test]
+    fn short_buffer() {
+        let mut decoder = Decoder::new(&[0x01, 0x12, 0x34, 0x56]).unwrap();
+
+        assert_eq!(decoder.get_u16(), Ok(0x1234));
+        assert_eq!(
+            decoder.get_f32(),
+            Err(DecodeError::ShortBuffer { offset: 3, len: 4 })
+        );
+        assert_eq!(decoder.remaining(), 1);
+        assert_eq!(decoder.get_u8(), Ok(0x56));
+        assert!(!decoder.has_remaining());
+
+        assert_eq!(
+            Decoder::new(&[]).err(),
+            Some(DecodeError::ShortBuffer { offset: 0, len: 1 })
+        );
+        assert_eq!(
+            Decoder::new(&[0x00]).err(),
+            Some(DecodeError::UnsupportedVersion(0))
+        );
+
+        // The string length exceeds the buffer.
+        let mut decoder = Decoder::new(&[0x01, 0x00, 0x08, b'b']).unwrap();
+        assert_eq!(
+            decoder.get_string(),
+            Err(DecodeError::ShortBuffer { offset: 3, len: 8 })
+        );
+    }
+
+    #[test]
+    fn length_overflow() {
+        let long = "x".repeat(u16::MAX as usize + 1);
+
+        let mut encoder = Encoder::new(1);
+        assert_eq!(
+            encoder.put_string(&long),
+            Err(EncodeError::LengthOverflow(long.len()))
+        );
+        assert_eq!(encoder.len(), 1);
+
+        assert!(encoder.put_string(&long[1..]).is_ok());
+
+        let mut encoder = Encoder::new(1);
+        assert_eq!(
+            encoder.put_section(|section| {
+                section.put_slice(long.as_bytes());
+                Ok(())
+            }),
+            Err(EncodeError::LengthOverflow(long.len()))
+        );
+    }
+
+    #[test]
+    fn bounded_string() {
+        // The last character does not fit and is dropped as a whole.
+        let long = "x".repeat(u16::MAX as usize - 1) + "é";
+
+        let mut encoder = Encoder::new(1);
+        encoder.put_bounded_string(&long);
+
+        let bytes = encoder.into_vec();
+        let mut decoder = Decoder::new(&bytes).unwrap();
+        assert_eq!(decoder.get_string().unwrap(), &long[..long.len() - 2]);
+        assert_eq!(truncate_string("boom"), "boom");
+    }
+
+    #[test]
+    fn section() {
+        let mut encoder = Encoder::new(2);
+        encoder
+            .put_section(|section| {
+                section.put_u8(0xAB);
+                section.put_u8(0xCD);
+                Ok(())
+            })
+            .unwrap();
+        encoder.put_u8(0xEF);
+
+        let bytes = encoder.into_vec();
+        assert_eq!(bytes, [0x02, 0x00, 0x02, 0xAB, 0xCD, 0xEF]);
+
+        let mut decoder = Decoder::new(&bytes).unwrap();
+        let mut section = decoder.get_section().unwrap();
+        assert_eq!(section.version(), 2);
+        assert_eq!(section.get_u8(), Ok(0xAB));
+        assert_eq!(section.get_u8(), Ok(0xCD));
+        assert_eq!(
+            section.get_u8(),
+            Err(DecodeError::ShortBuffer { offset: 5, len: 1 })
+        );
+        assert_eq!(decoder.get_u8(), Ok(0xEF));
+    }
+
+    /// Messages as encoded by the current schema versions.
+    ///
+    /// A change that breaks the decoding of these messages breaks every
+    /// client in the field.
+    #[test]
+    fn compatibility() {
+        use crate::core::{EngineState, FixQuality, GnssStatus, ModuleState, RotationReference};
+
+        let engine = Engine::try_from(&[0x01, 0x32, 0x2A, 0x05, 0xDC, 0x10][..]).unwrap();
+        assert_eq!(engine.driver_demand, 50);
+        assert_eq!(engine.actual_engine, 42);
+        assert_eq!(engine.rpm, 1_500);
+        assert_eq!(engine.state, EngineState::Request);
+
+        let control = Control::try_from(&[0x01, 0x1E, 0x01][..]).unwrap();
+        assert_eq!(control, Control::MachineHorn(true));
+
+        let motion = Motion::try_from(&[0x01, 0x10, 0x01, 0x00, 0x00, 0xD1, 0x20][..]).unwrap();
+        assert_eq!(motion, Motion::new(Actuator::Boom, -12_000i16));
+
+        #[rustfmt::skip]
+        let rotator = Rotator::try_from(&[
+            0x01, 0x6A,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            0x3F, 0x00, 0x00, 0x00,
+            0x01,
+        ][..]).unwrap();
+        assert_eq!(rotator.source, 0x6A);
+        assert_eq!(rotator.reference, RotationReference::Relative);
+        assert!((rotator.rotator.euler_angles().2 - 0.5).abs() < 1e-6);
+
+        #[rustfmt::skip]
+        let gnss = Gnss::try_from(&[
+            0x01,
+            0x42, 0x50, 0x00, 0x00, 0x40, 0xA0, 0x00, 0x00,
+            0x40, 0x60, 0x00, 0x00,
+            0x3F, 0xC0, 0x00, 0x00,
+            0x42, 0xB4, 0x00, 0x00,
+            0x12, 0x01, 0x04, 0x0E,
+            0x3F, 0x00, 0x00, 0x00,
+        ][..]).unwrap();
+        assert_eq!(gnss.location, (52.0, 5.0));
+        assert_eq!(gnss.altitude, 3.5);
+        assert_eq!(gnss.speed, 1.5);
+        assert_eq!(gnss.heading, 90.0);
+        assert_eq!(gnss.satellites, 18);
+        assert_eq!(gnss.status, GnssStatus::LocationFix);
+        assert_eq!(gnss.fix_quality, FixQuality::RtkFixed);
+        assert_eq!(gnss.satellites_used, 14);
+        assert_eq!(gnss.hdop, 0.5);
+
+        #[rustfmt::skip]
+        let status = ModuleStatus::try_from(&[
+            0x01,
+            0x00, 0x04, b'g', b'n', b's', b's',
+            0xF8, 0x00,
+            0x01, 0x41, 0x20, 0x00, 0x00,
+        ][..]).unwrap();
+        assert_eq!(status.name, "gnss");
+        assert_eq!(status.state, ModuleState::Healthy);
+        assert_eq!(status.error, None);
+        assert_eq!(status.update_rate, Some(10.0));
+
+        #[rustfmt::skip]
+        let target = Target::try_from(&[
+            0x01,
+            0x3F, 0x80, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x40, 0x40, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00,
+        ][..]).unwrap();
+        assert_eq!(target, Target::from(Point3::new(1.0, 2.0, 3.0)));
+
+        // A newer peer appends fields.
+        let engine = Engine::try_from(&[0x02, 0x32, 0x2A, 0x05, 0xDC, 0x10, 0xFF, 0xFF][..]);
+        assert_eq!(engine.unwrap().rpm, 1_500);
+
+        // Version zero is never encoded.
+        assert_eq!(
+            Engine::try_from(&[0x00, 0x32, 0x2A, 0x05, 0xDC, 0x10][..]),
+            Err(DecodeError::UnsupportedVersion(0))
+        );
+        assert_eq!(
+            Engine::try_from(&[0x01, 0x32, 0x2A, 0x05, 0xDC, 0x03][..]),
+            Err(DecodeError::InvalidValue { offset: 5 })
+        );
+    }
+}
@@ -1,7 +1,13 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
-
 use crate::util::OnOffExt;
 
+use super::{
+    codec::{DecodeError, Decoder, Encoder},
+    SmoothingProfile,
+};
+
+/// Schema version of the control message.
+const SCHEMA_VERSION: u8 = 0x01;
+
 const CONTROL_TYPE_HYDRAULIC_QUICK_DISCONNECT: u8 = 0x5;
 const CONTROL_TYPE_HYDRAULIC_LOCK: u8 = 0x6;
 const CONTROL_TYPE_HYDRAULIC_BOOST: u8 = 0x7;
@@ -15,6 +21,13 @@ const CONTROL_TYPE_MACHINE_LIGHTS: u8 = 0x2D;
 const CONTROL_TYPE_MACHINE_HORN: u8 = 0x1E;
 const CONTROL_TYPE_MACHINE_STROBE_LIGHT: u8 = 0x1F;
 const CONTROL_TYPE_MACHINE_TRAVEL_ALARM: u8 = 0x20;
+const CONTROL_TYPE_MOTION_PROFILE: u8 = 0x30;
+const CONTROL_TYPE_ATTACHMENT_SELECT: u8 = 0x31;
+const CONTROL_TYPE_LOAD_TARE: u8 = 0x32;
+const CONTROL_TYPE_TICK_INTERVAL: u8 = 0x33;
+const CONTROL_TYPE_EMERGENCY_STOP: u8 = 0x40;
+const CONTROL_TYPE_RESET_EMERGENCY: u8 = 0x41;
+const CONTROL_TYPE_ACCEPTANCE_TEST: u8 = 0x42;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -45,6 +58,100 @@ pub enum Control {
     MachineStrobeLight(bool),
     /// Machine travel alarm.
     MachineTravelAlarm(bool),
+    /// Motion smoothing profile.
+    MotionProfile(SmoothingProfile),
+    /// Select the mounted attachment.
+    AttachmentSelect(u8),
+    /// Tare the payload load estimate.
+    LoadTare,
+    /// Network service tick interval.
+    TickInterval {
+        /// Network index, in the order the network services are scheduled.
+        network: u8,
+        /// Tick interval in units of 10 milliseconds, zero restores the
+        /// scheduled tick interval.
+        interval: u8,
+    },
+    /// Latch the emergency stop.
+    EmergencyStop,
+    /// Reset the latched emergency stop.
+    ResetEmergency,
+    /// Start the factory acceptance test.
+    AcceptanceTest,
+}
+
+impl Control {
+    /// Construct a control from its control type and value.
+    ///
+    /// The tick interval control targets the first network.
+    pub(crate) fn from_parts(control_type: u8, value: u8) -> Option<Self> {
+        let on = value == 1;
+
+        match control_type {
+            CONTROL_TYPE_HYDRAULIC_QUICK_DISCONNECT => Some(Control::HydraulicQuickDisconnect(on)),
+            CONTROL_TYPE_HYDRAULIC_LOCK => Some(Control::HydraulicLock(on)),
+            CONTROL_TYPE_HYDRAULIC_BOOST => Some(Control::HydraulicBoost(on)),
+            CONTROL_TYPE_HYDRAULIC_BOOM_CONFLUX => Some(Control::HydraulicBoomConflux(on)),
+            CONTROL_TYPE_HYDRAULIC_ARM_CONFLUX => Some(Control::HydraulicArmConflux(on)),
+            CONTROL_TYPE_HYDRAULIC_BOOM_FLOAT => Some(Control::HydraulicBoomFloat(on)),
+            CONTROL_TYPE_HYDRAULIC_RESET => Some(Control::HydraulicReset),
+            CONTROL_TYPE_MACHINE_SHUTDOWN => Some(Control::MachineShutdown),
+            CONTROL_TYPE_MACHINE_ILLUMINATION => Some(Control::MachineIllumination(on)),
+            CONTROL_TYPE_MACHINE_LIGHTS => Some(Control::MachineLights(on)),
+            CONTROL_TYPE_MACHINE_HORN => Some(Control::MachineHorn(on)),
+            CONTROL_TYPE_MACHINE_STROBE_LIGHT => Some(Control::MachineStrobeLight(on)),
+            CONTROL_TYPE_MACHINE_TRAVEL_ALARM => Some(Control::MachineTravelAlarm(on)),
+            CONTROL_TYPE_MOTION_PROFILE => Some(Control::MotionProfile(value.try_into().ok()?)),
+            CONTROL_TYPE_ATTACHMENT_SELECT => Some(Control::AttachmentSelect(value)),
+            CONTROL_TYPE_LOAD_TARE => Some(Control::LoadTare),
+            CONTROL_TYPE_TICK_INTERVAL => Some(Control::TickInterval {
+                network: 0,
+                interval: value,
+            }),
+            CONTROL_TYPE_EMERGENCY_STOP => Some(Control::EmergencyStop),
+            CONTROL_TYPE_RESET_EMERGENCY => Some(Control::ResetEmergency),
+            CONTROL_TYPE_ACCEPTANCE_TEST => Some(Control::AcceptanceTest),
+            _ => None,
+        }
+    }
+
+    /// Control type and value of the control.
+    fn parts(&self) -> (u8, u8) {
+        match self {
+            Control::HydraulicQuickDisconnect(on) => {
+                (CONTROL_TYPE_HYDRAULIC_QUICK_DISCONNECT, u8::from(*on))
+            }
+            Control::HydraulicLock(on) => (CONTROL_TYPE_HYDRAULIC_LOCK, u8::from(*on)),
+            Control::HydraulicBoost(on) => (CONTROL_TYPE_HYDRAULIC_BOOST, u8::from(*on)),
+            Control::HydraulicBoomConflux(on) => {
+                (CONTROL_TYPE_HYDRAULIC_BOOM_CONFLUX, u8::from(*on))
+            }
+            Control::HydraulicArmConflux(on) => (CONTROL_TYPE_HYDRAULIC_ARM_CONFLUX, u8::from(*on)),
+            Control::HydraulicBoomFloat(on) => (CONTROL_TYPE_HYDRAULIC_BOOM_FLOAT, u8::from(*on)),
+            Control::HydraulicReset => (CONTROL_TYPE_HYDRAULIC_RESET, 1),
+            Control::MachineShutdown => (CONTROL_TYPE_MACHINE_SHUTDOWN, 1),
+            Control::MachineIllumination(on) => (CONTROL_TYPE_MACHINE_ILLUMINATION, u8::from(*on)),
+            Control::MachineLights(on) => (CONTROL_TYPE_MACHINE_LIGHTS, u8::from(*on)),
+            Control::MachineHorn(on) => (CONTROL_TYPE_MACHINE_HORN, u8::from(*on)),
+            Control::MachineStrobeLight(on) => (CONTROL_TYPE_MACHINE_STROBE_LIGHT, u8::from(*on)),
+            Control::MachineTravelAlarm(on) => (CONTROL_TYPE_MACHINE_TRAVEL_ALARM, u8::from(*on)),
+            Control::MotionProfile(profile) => (CONTROL_TYPE_MOTION_PROFILE, *profile as u8),
+            Control::AttachmentSelect(id) => (CONTROL_TYPE_ATTACHMENT_SELECT, *id),
+            Control::LoadTare => (CONTROL_TYPE_LOAD_TARE, 1),
+            Control::TickInterval { interval, .. } => (CONTROL_TYPE_TICK_INTERVAL, *interval),
+            Control::EmergencyStop => (CONTROL_TYPE_EMERGENCY_STOP, 1),
+            Control::ResetEmergency => (CONTROL_TYPE_RESET_EMERGENCY, 1),
+            Control::AcceptanceTest => (CONTROL_TYPE_ACCEPTANCE_TEST, 1),
+        }
+    }
+
+    /// Control type of the control.
+    ///
+    /// Controls of the same variant have the same control type, regardless
+    /// of their value.
+    pub fn control_type(&self) -> u8 {
+        self.parts().0
+    }
 }
 
 impl std::fmt::Display for Control {
@@ -77,100 +184,68 @@ impl std::fmt::Display for Control {
             Control::MachineTravelAlarm(on) => {
                 write!(f, "Machine travel alarm: {}", on.as_on_off_str())
             }
+            Control::MotionProfile(profile) => write!(f, "Motion profile: {}", profile),
+            Control::AttachmentSelect(id) => write!(f, "Attachment select: {}", id),
+            Control::LoadTare => write!(f, "Load tare"),
+            Control::TickInterval {
+                network,
+                interval: 0,
+            } => write!(f, "Tick interval network {}: default", network),
+            Control::TickInterval { network, interval } => {
+                write!(
+                    f,
+                    "Tick interval network {}: {}ms",
+                    network,
+                    *interval as u32 * 10
+                )
+            }
+            Control::EmergencyStop => write!(f, "Emergency stop"),
+            Control::ResetEmergency => write!(f, "Reset emergency"),
+            Control::AcceptanceTest => write!(f, "Acceptance test"),
         }
     }
 }
 
-impl TryFrom<Vec<u8>> for Control {
-    type Error = ();
+impl TryFrom<&[u8]> for Control {
+    type Error = DecodeError;
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(&value);
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
-        let control_type = buf.get_u8();
-        let on = buf.get_u8() == 1;
+        let control_type = decoder.get_u8()?;
+        let value = decoder.get_u8()?;
 
-        match control_type {
-            CONTROL_TYPE_HYDRAULIC_QUICK_DISCONNECT => Ok(Control::HydraulicQuickDisconnect(on)),
-            CONTROL_TYPE_HYDRAULIC_LOCK => Ok(Control::HydraulicLock(on)),
-            CONTROL_TYPE_HYDRAULIC_BOOST => Ok(Control::HydraulicBoost(on)),
-            CONTROL_TYPE_HYDRAULIC_BOOM_CONFLUX => Ok(Control::HydraulicBoomConflux(on)),
-            CONTROL_TYPE_HYDRAULIC_ARM_CONFLUX => Ok(Control::HydraulicArmConflux(on)),
-            CONTROL_TYPE_HYDRAULIC_BOOM_FLOAT => Ok(Control::HydraulicBoomFloat(on)),
-            CONTROL_TYPE_HYDRAULIC_RESET => Ok(Control::HydraulicReset),
-            CONTROL_TYPE_MACHINE_SHUTDOWN => Ok(Control::MachineShutdown),
-            CONTROL_TYPE_MACHINE_ILLUMINATION => Ok(Control::MachineIllumination(on)),
-            CONTROL_TYPE_MACHINE_LIGHTS => Ok(Control::MachineLights(on)),
-            CONTROL_TYPE_MACHINE_HORN => Ok(Control::MachineHorn(on)),
-            CONTROL_TYPE_MACHINE_STROBE_LIGHT => Ok(Control::MachineStrobeLight(on)),
-            CONTROL_TYPE_MACHINE_TRAVEL_ALARM => Ok(Control::MachineTravelAlarm(on)),
-            _ => Err(()),
+        match Self::from_parts(control_type, value).ok_or(decoder.invalid(2))? {
+            Control::TickInterval { interval, .. } => Ok(Control::TickInterval {
+                network: decoder.get_u8()?,
+                interval,
+            }),
+            control => Ok(control),
         }
     }
 }
 
+impl TryFrom<Vec<u8>> for Control {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Control {
     const MESSAGE_TYPE: u8 = 0x45;
-    const MESSAGE_SIZE: Option<usize> = Some(2);
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(2);
+        let (control_type, value) = self.parts();
 
-        match self {
-            Control::HydraulicQuickDisconnect(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_QUICK_DISCONNECT);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicLock(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_LOCK);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicBoost(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_BOOST);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicBoomConflux(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_BOOM_CONFLUX);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicArmConflux(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_ARM_CONFLUX);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicBoomFloat(on) => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_BOOM_FLOAT);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::HydraulicReset => {
-                buf.put_u8(CONTROL_TYPE_HYDRAULIC_RESET);
-                buf.put_u8(1);
-            }
-            Control::MachineShutdown => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_SHUTDOWN);
-                buf.put_u8(1);
-            }
-            Control::MachineIllumination(on) => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_ILLUMINATION);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::MachineLights(on) => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_LIGHTS);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::MachineHorn(on) => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_HORN);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::MachineStrobeLight(on) => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_STROBE_LIGHT);
-                buf.put_u8(u8::from(*on));
-            }
-            Control::MachineTravelAlarm(on) => {
-                buf.put_u8(CONTROL_TYPE_MACHINE_TRAVEL_ALARM);
-                buf.put_u8(u8::from(*on));
-            }
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 3);
+        encoder.put_u8(control_type);
+        encoder.put_u8(value);
+        if let Control::TickInterval { network, .. } = self {
+            encoder.put_u8(*network);
         }
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
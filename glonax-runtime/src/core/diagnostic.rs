@@ -0,0 +1,268 @@
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// Schema version of the diagnostic codes message.
+const SCHEMA_VERSION: u8 = 0x01;
+
+/// Diagnostic trouble code.
+///
+/// The code identifies the suspect parameter (SPN) and how it failed (FMI).
+/// On the wire the code is packed in 4 bytes, the 19-bit SPN is split over
+/// the first three bytes with its 3 most significant bits sharing the third
+/// byte with the 5-bit FMI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagnosticTroubleCode {
+    /// Suspect parameter number.
+    pub spn: u32,
+    /// Failure mode identifier.
+    pub fmi: u8,
+    /// Occurrence count.
+    pub occurrences: u8,
+}
+
+impl DiagnosticTroubleCode {
+    /// Size of the code on the wire.
+    pub const SIZE: usize = 4;
+
+    /// Decode a code from its wire format.
+    pub fn from_bytes(bytes: [u8; Self::SIZE]) -> Self {
+        Self {
+            spn: u32::from_le_bytes([bytes[0], bytes[1], bytes[2] >> 5, 0]),
+            fmi: bytes[2] & 0x1F,
+            occurrences: bytes[3] & 0x7F,
+        }
+    }
+
+    /// Encode the code in its wire format.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let spn = self.spn.to_le_bytes();
+
+        [
+            spn[0],
+            spn[1],
+            (spn[2] & 0x07) << 5 | (self.fmi & 0x1F),
+            self.occurrences & 0x7F,
+        ]
+    }
+}
+
+impl std::fmt::Display for DiagnosticTroubleCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.spn, self.fmi)
+    }
+}
+
+/// Active diagnostic trouble codes of a unit.
+///
+/// The lamp status is kept as the J1939 lamp status byte, each lamp is 2 bits
+/// of which the value 1 means the lamp is on.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiagnosticCodes {
+    /// Source address of the unit.
+    pub source: u8,
+    /// Lamp status.
+    pub lamps: u8,
+    /// Active codes.
+    pub codes: Vec<DiagnosticTroubleCode>,
+}
+
+impl DiagnosticCodes {
+    /// Lamp status bit of the protect lamp.
+    const LAMP_PROTECT: u8 = 0;
+    /// Lamp status bit of the amber warning lamp.
+    const LAMP_AMBER_WARNING: u8 = 2;
+    /// Lamp status bit of the red stop lamp.
+    const LAMP_RED_STOP: u8 = 4;
+    /// Lamp status bit of the malfunction indicator lamp.
+    const LAMP_MALFUNCTION: u8 = 6;
+
+    #[inline]
+    fn is_lamp_on(&self, shift: u8) -> bool {
+        (self.lamps >> shift) & 0b11 == 0b01
+    }
+
+    /// Test if the protect lamp is on.
+    #[inline]
+    pub fn is_protect(&self) -> bool {
+        self.is_lamp_on(Self::LAMP_PROTECT)
+    }
+
+    /// Test if the amber warning lamp is on.
+    #[inline]
+    pub fn is_amber_warning(&self) -> bool {
+        self.is_lamp_on(Self::LAMP_AMBER_WARNING)
+    }
+
+    /// Test if the red stop lamp is on.
+    #[inline]
+    pub fn is_red_stop(&self) -> bool {
+        self.is_lamp_on(Self::LAMP_RED_STOP)
+    }
+
+    /// Test if the malfunction indicator lamp is on.
+    #[inline]
+    pub fn is_malfunction(&self) -> bool {
+        self.is_lamp_on(Self::LAMP_MALFUNCTION)
+    }
+}
+
+impl std::fmt::Display for DiagnosticCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Source: 0x{:X}", self.source)?;
+
+        for (lamp, name) in [
+            (self.is_red_stop(), "red stop"),
+            (self.is_amber_warning(), "amber warning"),
+            (self.is_protect(), "protect"),
+            (self.is_malfunction(), "malfunction"),
+        ] {
+            if lamp {
+                write!(f, " [{}]", name)?;
+            }
+        }
+
+        if self.codes.is_empty() {
+            return write!(f, " No active codes");
+        }
+
+        for code in &self.codes {
+            write!(f, " {}", code)?;
+            if code.occurrences > 1 {
+                write!(f, " (x{})", code.occurrences)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for DiagnosticCodes {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        let source = decoder.get_u8()?;
+        let lamps = decoder.get_u8()?;
+        let count = decoder.get_u8()? as usize;
+
+        let codes = decoder
+            .get_bytes(count * DiagnosticTroubleCode::SIZE)?
+            .chunks_exact(DiagnosticTroubleCode::SIZE)
+            .map(|chunk| DiagnosticTroubleCode::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        Ok(Self {
+            source,
+            lamps,
+            codes,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for DiagnosticCodes {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl crate::protocol::Packetize for DiagnosticCodes {
+    const MESSAGE_TYPE: u8 = 0x4B;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let count = self.codes.len().min(u8::MAX as usize);
+
+        let mut encoder =
+            Encoder::with_capacity(SCHEMA_VERSION, 3 + count * DiagnosticTroubleCode::SIZE);
+
+        encoder.put_u8(self.source);
+        encoder.put_u8(self.lamps);
+        encoder.put_u8(count as u8);
+
+        for code in self.codes.iter().take(count) {
+            encoder.put_slice(&code.to_bytes());
+        }
+
+        encoder.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Packetize;
+
+    #[test]
+    fn trouble_code_spn_boundary() {
+        // SPN 520192 (0x7F000) sets only the 3 most significant bits.
+        let code = DiagnosticTroubleCode::from_bytes([0x00, 0xF0, 0xE3, 0x02]);
+        assert_eq!(code.spn, 0x7_F000);
+        assert_eq!(code.fmi, 3);
+        assert_eq!(code.occurrences, 2);
+        assert_eq!(code.to_string(), "520192.3");
+
+        // The FMI does not leak into the SPN and vice versa.
+        let code = DiagnosticTroubleCode::from_bytes([0xFF, 0xFF, 0x1F, 0x01]);
+        assert_eq!(code.spn, 0xFFFF);
+        assert_eq!(code.fmi, 31);
+
+        let code = DiagnosticTroubleCode::from_bytes([0x00, 0x00, 0xE0, 0x01]);
+        assert_eq!(code.spn, 0x7_0000);
+        assert_eq!(code.fmi, 0);
+
+        // SPN 110 coolant temperature, FMI 0 above normal.
+        let code = DiagnosticTroubleCode {
+            spn: 110,
+            fmi: 0,
+            occurrences: 1,
+        };
+        assert_eq!(code.to_bytes(), [0x6E, 0x00, 0x00, 0x01]);
+
+        for spn in [0, 1, 0xFFFF, 0x1_0000, 0x4_5A5A, 0x7_FFFF] {
+            for fmi in [0, 1, 16, 31] {
+                let code = DiagnosticTroubleCode {
+                    spn,
+                    fmi,
+                    occurrences: 126,
+                };
+                assert_eq!(DiagnosticTroubleCode::from_bytes(code.to_bytes()), code);
+            }
+        }
+    }
+
+    #[test]
+    fn diagnostic_codes_packet() {
+        let codes = DiagnosticCodes {
+            source: 0x00,
+            lamps: 0b0001_0100,
+            codes: vec![
+                DiagnosticTroubleCode {
+                    spn: 110,
+                    fmi: 0,
+                    occurrences: 1,
+                },
+                DiagnosticTroubleCode {
+                    spn: 520_192,
+                    fmi: 31,
+                    occurrences: 3,
+                },
+            ],
+        };
+
+        assert!(codes.is_red_stop());
+        assert!(codes.is_amber_warning());
+        assert!(!codes.is_protect());
+        assert!(!codes.is_malfunction());
+        assert_eq!(
+            codes.to_string(),
+            "Source: 0x0 [red stop] [amber warning] 110.0 520192.31 (x3)"
+        );
+
+        let bytes = codes.to_bytes();
+        assert_eq!(bytes.len(), 1 + 3 + 2 * DiagnosticTroubleCode::SIZE);
+        assert_eq!(DiagnosticCodes::try_from(bytes).unwrap(), codes);
+
+        assert!(DiagnosticCodes::try_from(&[SCHEMA_VERSION, 0x00, 0x00, 0x01][..]).is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use bytes::{BufMut, BytesMut};
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// Schema version of the engine message.
+const SCHEMA_VERSION: u8 = 0x01;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EngineState {
@@ -148,38 +151,42 @@ impl std::fmt::Display for Engine {
     }
 }
 
-impl TryFrom<Vec<u8>> for Engine {
-    type Error = ();
+impl TryFrom<&[u8]> for Engine {
+    type Error = DecodeError;
 
-    fn try_from(buffer: Vec<u8>) -> Result<Self, Self::Error> {
-        let driver_demand = buffer[0];
-        let actual_engine = buffer[1];
-        let rpm = u16::from_be_bytes([buffer[2], buffer[3]]);
-
-        let state = EngineState::try_from(buffer[4])?;
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
         Ok(Self {
-            driver_demand,
-            actual_engine,
-            rpm,
-            state,
+            driver_demand: decoder.get_u8()?,
+            actual_engine: decoder.get_u8()?,
+            rpm: decoder.get_u16()?,
+            state: decoder.get_value()?,
         })
     }
 }
 
+impl TryFrom<Vec<u8>> for Engine {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Engine {
     const MESSAGE_TYPE: u8 = 0x43;
-    const MESSAGE_SIZE: Option<usize> = Some(5);
+    const MESSAGE_SIZE: Option<usize> = Some(1 + 5);
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(Self::MESSAGE_SIZE.unwrap());
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 5);
 
-        buf.put_u8(self.driver_demand);
-        buf.put_u8(self.actual_engine);
-        buf.put_u16(self.rpm);
-        buf.put_u8(self.state as u8);
+        encoder.put_u8(self.driver_demand);
+        encoder.put_u8(self.actual_engine);
+        encoder.put_u16(self.rpm);
+        encoder.put_u8(self.state as u8);
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
 
@@ -208,12 +215,13 @@ mod tests {
 
         let bytes = engine.to_bytes();
 
-        assert_eq!(bytes.len(), 5);
-        assert_eq!(bytes[0], 0x01);
-        assert_eq!(bytes[1], 0x02);
-        assert_eq!(bytes[2], 0x00);
-        assert_eq!(bytes[3], 0x03);
-        assert_eq!(bytes[4], 0x10);
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(bytes[0], SCHEMA_VERSION);
+        assert_eq!(bytes[1], 0x01);
+        assert_eq!(bytes[2], 0x02);
+        assert_eq!(bytes[3], 0x00);
+        assert_eq!(bytes[4], 0x03);
+        assert_eq!(bytes[5], 0x10);
 
         let engine = Engine::try_from(bytes).unwrap();
 
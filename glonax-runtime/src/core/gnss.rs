@@ -1,4 +1,19 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::VecDeque;
+
+use nalgebra::Vector3;
+
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// WGS84 semi-major axis in meters.
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 first eccentricity squared.
+const WGS84_E2: f64 = 6.694_379_990_14e-3;
+/// Horizontal dilution of precision when unknown.
+pub const HDOP_UNKNOWN: f32 = 99.99;
+/// Size of the GNSS message before the fix quality was added.
+const MESSAGE_SIZE_LEGACY: usize = (std::mem::size_of::<f32>() * 5) + 1 + 1;
+/// Schema version of the GNSS message.
+const SCHEMA_VERSION: u8 = 0x01;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GnssStatus {
@@ -8,6 +23,8 @@ pub enum GnssStatus {
     DeviceNotFound = 0x00,
     /// GNSS has a location fix.
     LocationFix = 0x01,
+    /// GNSS location is estimated by dead reckoning.
+    DeadReckoning = 0x02,
 }
 
 impl TryFrom<u8> for GnssStatus {
@@ -18,11 +35,115 @@ impl TryFrom<u8> for GnssStatus {
             0xFF => Ok(GnssStatus::Disabled),
             0x00 => Ok(GnssStatus::DeviceNotFound),
             0x01 => Ok(GnssStatus::LocationFix),
+            0x02 => Ok(GnssStatus::DeadReckoning),
             _ => Err(()),
         }
     }
 }
 
+/// GNSS fix quality.
+///
+/// The fix quality as reported by the receiver in the GGA sentence. The
+/// qualities are ordered by their accuracy with [`FixQuality::meets`], from
+/// meters for a standalone fix down to centimeters for an RTK fixed solution.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixQuality {
+    /// No fix.
+    #[default]
+    Invalid = 0x00,
+    /// Standalone fix.
+    Gps = 0x01,
+    /// Differential (DGPS, SBAS) fix.
+    Differential = 0x02,
+    /// Precise positioning service fix.
+    Pps = 0x03,
+    /// RTK fixed solution.
+    RtkFixed = 0x04,
+    /// RTK float solution.
+    RtkFloat = 0x05,
+    /// Estimated by dead reckoning.
+    DeadReckoning = 0x06,
+    /// Manual input.
+    Manual = 0x07,
+    /// Simulated.
+    Simulation = 0x08,
+}
+
+impl FixQuality {
+    /// Accuracy rank, higher is more accurate.
+    fn rank(&self) -> u8 {
+        match self {
+            FixQuality::Invalid | FixQuality::Manual | FixQuality::Simulation => 0,
+            FixQuality::DeadReckoning => 1,
+            FixQuality::Gps | FixQuality::Pps => 2,
+            FixQuality::Differential => 3,
+            FixQuality::RtkFloat => 4,
+            FixQuality::RtkFixed => 5,
+        }
+    }
+
+    /// Test if the fix quality is valid.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.rank() > 0
+    }
+
+    /// Test if the fix quality is at least as accurate as the minimum.
+    ///
+    /// A fix without a valid quality never meets the minimum.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glonax::core::FixQuality;
+    ///
+    /// assert!(FixQuality::RtkFixed.meets(FixQuality::Differential));
+    /// assert!(!FixQuality::Gps.meets(FixQuality::RtkFloat));
+    /// assert!(!FixQuality::Invalid.meets(FixQuality::Invalid));
+    /// ```
+    pub fn meets(&self, minimum: FixQuality) -> bool {
+        self.is_valid() && self.rank() >= minimum.rank()
+    }
+}
+
+impl TryFrom<u8> for FixQuality {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(FixQuality::Invalid),
+            0x01 => Ok(FixQuality::Gps),
+            0x02 => Ok(FixQuality::Differential),
+            0x03 => Ok(FixQuality::Pps),
+            0x04 => Ok(FixQuality::RtkFixed),
+            0x05 => Ok(FixQuality::RtkFloat),
+            0x06 => Ok(FixQuality::DeadReckoning),
+            0x07 => Ok(FixQuality::Manual),
+            0x08 => Ok(FixQuality::Simulation),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for FixQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FixQuality::Invalid => "no fix",
+            FixQuality::Gps => "GPS",
+            FixQuality::Differential => "DGPS",
+            FixQuality::Pps => "PPS",
+            FixQuality::RtkFixed => "RTK fixed",
+            FixQuality::RtkFloat => "RTK float",
+            FixQuality::DeadReckoning => "dead reckoning",
+            FixQuality::Manual => "manual",
+            FixQuality::Simulation => "simulation",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Gnss {
     /// GNSS Latitude and Longitude.
@@ -37,6 +158,12 @@ pub struct Gnss {
     pub satellites: u8,
     /// GNSS Status.
     pub status: GnssStatus,
+    /// GNSS Fix quality.
+    pub fix_quality: FixQuality,
+    /// GNSS Satellites used in the fix.
+    pub satellites_used: u8,
+    /// GNSS Horizontal dilution of precision.
+    pub hdop: f32,
 }
 
 impl Default for Gnss {
@@ -48,7 +175,436 @@ impl Default for Gnss {
             heading: 0.0,
             satellites: 0,
             status: GnssStatus::Disabled,
+            fix_quality: FixQuality::Invalid,
+            satellites_used: 0,
+            hdop: HDOP_UNKNOWN,
+        }
+    }
+}
+
+impl Gnss {
+    /// Test if the GNSS has a location fix.
+    #[inline]
+    pub fn is_fix(&self) -> bool {
+        self.status == GnssStatus::LocationFix
+    }
+
+    /// Test if the GNSS location is estimated by dead reckoning.
+    #[inline]
+    pub fn is_dead_reckoning(&self) -> bool {
+        self.status == GnssStatus::DeadReckoning
+    }
+
+    /// Test if the GNSS has a location fix of at least the minimum quality.
+    ///
+    /// # Arguments
+    ///
+    /// * `minimum` - The minimum fix quality.
+    #[inline]
+    pub fn is_fix_quality(&self, minimum: FixQuality) -> bool {
+        self.is_fix() && self.fix_quality.meets(minimum)
+    }
+
+    /// Project the location onto the local tangent plane of a datum.
+    ///
+    /// # Arguments
+    ///
+    /// * `datum` - The origin of the local tangent plane.
+    ///
+    /// # Returns
+    ///
+    /// The east, north and up displacement in meters, or `None` if there is no location fix.
+    pub fn to_enu(&self, datum: &Datum) -> Option<Vector3<f32>> {
+        if !self.is_fix() {
+            return None;
+        }
+
+        Some(datum.project(self.location.0, self.location.1, self.altitude))
+    }
+}
+
+/// Convert WGS84 geodetic coordinates to earth-centered, earth-fixed coordinates.
+fn geodetic_to_ecef(latitude: f64, longitude: f64, altitude: f64) -> Vector3<f64> {
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = longitude.to_radians().sin_cos();
+
+    let n = WGS84_A / (1.0 - WGS84_E2 * sin_lat * sin_lat).sqrt();
+
+    Vector3::new(
+        (n + altitude) * cos_lat * cos_lon,
+        (n + altitude) * cos_lat * sin_lon,
+        (n * (1.0 - WGS84_E2) + altitude) * sin_lat,
+    )
+}
+
+/// Origin of a local tangent plane.
+///
+/// The datum is a WGS84 coordinate that serves as the origin of a local east,
+/// north, up (ENU) frame. Coordinates near the datum are projected onto this
+/// frame in meters so they can be used together with the Cartesian world.
+///
+/// The datum is usually taken from configuration or from the first location
+/// fix at startup, and should not change during operation.
+#[derive(Copy, Clone, Debug, PartialEq, serde_derive::Deserialize)]
+pub struct Datum {
+    /// Latitude in degrees.
+    pub latitude: f64,
+    /// Longitude in degrees.
+    pub longitude: f64,
+    /// Altitude in meters.
+    #[serde(default)]
+    pub altitude: f64,
+}
+
+impl Datum {
+    /// Construct a new datum.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - The latitude in degrees.
+    /// * `longitude` - The longitude in degrees.
+    /// * `altitude` - The altitude in meters.
+    ///
+    /// # Returns
+    ///
+    /// A new `Datum` instance.
+    pub fn new(latitude: f64, longitude: f64, altitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    /// Construct a datum from a GNSS location.
+    ///
+    /// # Returns
+    ///
+    /// The datum at the GNSS location, or `None` if there is no location fix.
+    pub fn from_gnss(gnss: &Gnss) -> Option<Self> {
+        if !gnss.is_fix() {
+            return None;
+        }
+
+        Some(Self::new(
+            gnss.location.0 as f64,
+            gnss.location.1 as f64,
+            gnss.altitude as f64,
+        ))
+    }
+
+    /// Project a WGS84 coordinate onto the local tangent plane.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - The latitude in degrees.
+    /// * `longitude` - The longitude in degrees.
+    /// * `altitude` - The altitude in meters.
+    ///
+    /// # Returns
+    ///
+    /// The east, north and up displacement from the datum in meters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glonax::core::Datum;
+    ///
+    /// let datum = Datum::new(52.0, 5.0, 0.0);
+    /// let enu = datum.project(52.001, 5.0, 0.0);
+    ///
+    /// assert!((enu.y - 111.27).abs() < 0.1);
+    /// ```
+    pub fn project(&self, latitude: f32, longitude: f32, altitude: f32) -> Vector3<f32> {
+        let origin = geodetic_to_ecef(self.latitude, self.longitude, self.altitude);
+        let point = geodetic_to_ecef(latitude as f64, longitude as f64, altitude as f64);
+
+        let delta = point - origin;
+
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+
+        let east = -sin_lon * delta.x + cos_lon * delta.y;
+        let north = -sin_lat * cos_lon * delta.x - sin_lat * sin_lon * delta.y + cos_lat * delta.z;
+        let up = cos_lat * cos_lon * delta.x + cos_lat * sin_lon * delta.y + sin_lat * delta.z;
+
+        Vector3::new(east as f32, north as f32, up as f32)
+    }
+}
+
+/// GNSS position filter.
+///
+/// Smooths the GNSS location and altitude with a moving average over the last
+/// fixes. The speed, heading and satellite count are passed through from the
+/// most recent fix. The window is cleared whenever the GNSS status changes, so
+/// positions from before and after a fix change are never averaged together.
+#[derive(Clone, Debug)]
+pub struct GnssFilter {
+    /// Number of fixes in the window.
+    window: usize,
+    /// Location and altitude window.
+    samples: VecDeque<(f64, f64, f64)>,
+    /// Most recent raw GNSS.
+    raw: Option<Gnss>,
+    /// Most recent filtered GNSS.
+    filtered: Option<Gnss>,
+}
+
+impl GnssFilter {
+    /// Construct a new GNSS filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The number of fixes to average, at least 1.
+    ///
+    /// # Returns
+    ///
+    /// A new `GnssFilter` instance.
+    pub fn new(window: usize) -> Self {
+        assert!(window >= 1, "window must contain at least 1 fix");
+
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window),
+            raw: None,
+            filtered: None,
+        }
+    }
+
+    /// Add a new GNSS reading to the filter.
+    ///
+    /// Readings without a location fix are passed through unfiltered.
+    ///
+    /// # Arguments
+    ///
+    /// * `gnss` - The raw GNSS reading.
+    ///
+    /// # Returns
+    ///
+    /// The filtered GNSS reading.
+    pub fn update(&mut self, gnss: Gnss) -> Gnss {
+        if self.raw.is_some_and(|raw| raw.status != gnss.status) {
+            self.samples.clear();
+        }
+
+        self.raw = Some(gnss);
+
+        if !gnss.is_fix() {
+            self.samples.clear();
+            self.filtered = Some(gnss);
+            return gnss;
+        }
+
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back((
+            gnss.location.0 as f64,
+            gnss.location.1 as f64,
+            gnss.altitude as f64,
+        ));
+
+        let count = self.samples.len() as f64;
+        let (latitude, longitude, altitude) =
+            self.samples.iter().fold((0.0, 0.0, 0.0), |acc, sample| {
+                (acc.0 + sample.0, acc.1 + sample.1, acc.2 + sample.2)
+            });
+
+        let filtered = Gnss {
+            location: ((latitude / count) as f32, (longitude / count) as f32),
+            altitude: (altitude / count) as f32,
+            ..gnss
+        };
+
+        self.filtered = Some(filtered);
+
+        filtered
+    }
+
+    /// Clear the filter.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.raw = None;
+        self.filtered = None;
+    }
+
+    /// Most recent raw GNSS reading.
+    #[inline]
+    pub fn raw(&self) -> Option<&Gnss> {
+        self.raw.as_ref()
+    }
+
+    /// Most recent filtered GNSS reading.
+    #[inline]
+    pub fn filtered(&self) -> Option<&Gnss> {
+        self.filtered.as_ref()
+    }
+}
+
+/// GNSS dead reckoning estimator.
+///
+/// Propagates the last known location with the last known speed and heading
+/// while there is no location fix. The propagated location is marked with the
+/// `DeadReckoning` status. When the fix is reacquired the output is blended
+/// from the propagated location to the fix location, so the position does not
+/// jump. Dead reckoning stops after the maximum outage time.
+#[derive(Clone, Debug)]
+pub struct DeadReckoning {
+    /// Maximum outage time in seconds.
+    max_outage: f64,
+    /// Blend time in seconds.
+    blend: f64,
+    /// Time in seconds and last location estimate.
+    last: Option<(f64, Gnss)>,
+    /// Last location estimate in degrees, kept in full precision.
+    location: (f64, f64),
+    /// Time the outage started in seconds.
+    outage_start: Option<f64>,
+    /// Time the blend started in seconds and the location offset in degrees.
+    blend_offset: Option<(f64, (f64, f64))>,
+}
+
+impl DeadReckoning {
+    /// Construct a new dead reckoning estimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_outage` - The maximum outage time in seconds.
+    /// * `blend` - The time in seconds to blend back to the fix.
+    ///
+    /// # Returns
+    ///
+    /// A new `DeadReckoning` instance.
+    pub fn new(max_outage: f64, blend: f64) -> Self {
+        Self {
+            max_outage,
+            blend,
+            last: None,
+            location: (0.0, 0.0),
+            outage_start: None,
+            blend_offset: None,
+        }
+    }
+
+    /// Override the speed and heading of the last estimate.
+    ///
+    /// This can be used to feed the velocity from another source, such as
+    /// the track encoders, while there is no location fix.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - The speed in meters per second.
+    /// * `heading` - The heading in degrees clockwise from north.
+    pub fn set_velocity(&mut self, speed: f32, heading: f32) {
+        if let Some((_, gnss)) = &mut self.last {
+            gnss.speed = speed;
+            gnss.heading = heading;
+        }
+    }
+
+    /// Add a new GNSS reading to the estimator.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The reading time in seconds.
+    /// * `gnss` - The raw GNSS reading.
+    ///
+    /// # Returns
+    ///
+    /// The estimated GNSS reading.
+    pub fn update(&mut self, time: f64, gnss: Gnss) -> Gnss {
+        if gnss.is_fix() {
+            return self.update_fix(time, gnss);
+        }
+
+        let Some((last_time, last)) = self.last else {
+            return gnss;
+        };
+
+        let outage_start = *self.outage_start.get_or_insert(last_time);
+        if time - outage_start > self.max_outage {
+            return gnss;
         }
+
+        let distance = last.speed as f64 * (time - last_time);
+        let (sin_heading, cos_heading) = (last.heading as f64).to_radians().sin_cos();
+
+        self.location = offset_location(
+            self.location,
+            distance * sin_heading,
+            distance * cos_heading,
+        );
+
+        let estimate = Gnss {
+            location: (self.location.0 as f32, self.location.1 as f32),
+            satellites: gnss.satellites,
+            status: GnssStatus::DeadReckoning,
+            ..last
+        };
+
+        self.last = Some((time, estimate));
+        self.blend_offset = None;
+
+        estimate
+    }
+
+    fn update_fix(&mut self, time: f64, gnss: Gnss) -> Gnss {
+        if self.outage_start.take().is_some()
+            && self.last.is_some_and(|(_, last)| last.is_dead_reckoning())
+        {
+            self.blend_offset = Some((
+                time,
+                (
+                    self.location.0 - gnss.location.0 as f64,
+                    self.location.1 - gnss.location.1 as f64,
+                ),
+            ));
+        }
+
+        let mut estimate = gnss;
+        self.location = (gnss.location.0 as f64, gnss.location.1 as f64);
+
+        if let Some((blend_start, offset)) = self.blend_offset {
+            let weight = 1.0 - (time - blend_start) / self.blend;
+
+            if weight > 0.0 {
+                self.location.0 += offset.0 * weight;
+                self.location.1 += offset.1 * weight;
+
+                estimate.location = (self.location.0 as f32, self.location.1 as f32);
+            } else {
+                self.blend_offset = None;
+            }
+        }
+
+        self.last = Some((time, estimate));
+
+        estimate
+    }
+}
+
+/// Offset a WGS84 location by a small east and north displacement in meters.
+fn offset_location(location: (f64, f64), east: f64, north: f64) -> (f64, f64) {
+    let (sin_lat, cos_lat) = location.0.to_radians().sin_cos();
+
+    let denominator = 1.0 - WGS84_E2 * sin_lat * sin_lat;
+    let meridian_radius = WGS84_A * (1.0 - WGS84_E2) / denominator.powf(1.5);
+    let normal_radius = WGS84_A / denominator.sqrt();
+
+    (
+        location.0 + (north / meridian_radius).to_degrees(),
+        location.1 + (east / (normal_radius * cos_lat)).to_degrees(),
+    )
+}
+
+impl std::fmt::Display for Datum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({:.7}, {:.7}) {:.1}m",
+            self.latitude, self.longitude, self.altitude
+        )
     }
 }
 
@@ -63,47 +619,307 @@ impl std::fmt::Display for Gnss {
         s.push_str(&format!("Altitude: {:.1}m; ", self.altitude));
         s.push_str(&format!("Speed: {:.1}m/s; ", self.speed));
         s.push_str(&format!("Heading: {:.1}°; ", self.heading));
-        s.push_str(&format!("Satellites: {}", self.satellites));
+        s.push_str(&format!(
+            "Satellites: {}/{}; ",
+            self.satellites_used, self.satellites
+        ));
+        s.push_str(&format!("Fix: {}; ", self.fix_quality));
+        s.push_str(&format!("HDOP: {:.1}", self.hdop));
 
         write!(f, "{}", s)
     }
 }
 
+impl TryFrom<&[u8]> for Gnss {
+    type Error = DecodeError;
+
+    /// Decode the GNSS message.
+    ///
+    /// Messages from before the fix quality was added are accepted, the fix
+    /// quality is then derived from the status and the precision is unknown.
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        let mut gnss = Self {
+            location: (decoder.get_f32()?, decoder.get_f32()?),
+            altitude: decoder.get_f32()?,
+            speed: decoder.get_f32()?,
+            heading: decoder.get_f32()?,
+            satellites: decoder.get_u8()?,
+            status: decoder.get_value()?,
+            ..Default::default()
+        };
+
+        if decoder.has_remaining() {
+            gnss.fix_quality = decoder.get_value()?;
+            gnss.satellites_used = decoder.get_u8()?;
+            gnss.hdop = decoder.get_f32()?;
+        } else {
+            gnss.fix_quality = match gnss.status {
+                GnssStatus::LocationFix => FixQuality::Gps,
+                GnssStatus::DeadReckoning => FixQuality::DeadReckoning,
+                _ => FixQuality::Invalid,
+            };
+            gnss.satellites_used = gnss.satellites;
+        }
+
+        Ok(gnss)
+    }
+}
+
 impl TryFrom<Vec<u8>> for Gnss {
-    type Error = ();
+    type Error = DecodeError;
 
     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(&value);
-
-        Ok(Self {
-            location: (buf.get_f32(), buf.get_f32()),
-            altitude: buf.get_f32(),
-            speed: buf.get_f32(),
-            heading: buf.get_f32(),
-            satellites: buf.get_u8(),
-            status: GnssStatus::try_from(buf.get_u8())?,
-        })
+        Self::try_from(value.as_slice())
     }
 }
 
 impl crate::protocol::Packetize for Gnss {
     const MESSAGE_TYPE: u8 = 0x42;
-    const MESSAGE_SIZE: Option<usize> = Some((std::mem::size_of::<f32>() * 5) + 1 + 1);
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(Self::MESSAGE_SIZE.unwrap());
+        let mut encoder = Encoder::with_capacity(
+            SCHEMA_VERSION,
+            MESSAGE_SIZE_LEGACY + 1 + 1 + std::mem::size_of::<f32>(),
+        );
+
+        encoder.put_f32(self.location.0);
+        encoder.put_f32(self.location.1);
+
+        encoder.put_f32(self.altitude);
+        encoder.put_f32(self.speed);
+        encoder.put_f32(self.heading);
+
+        encoder.put_u8(self.satellites);
+
+        encoder.put_u8(self.status as u8);
+
+        encoder.put_u8(self.fix_quality as u8);
+        encoder.put_u8(self.satellites_used);
+        encoder.put_f32(self.hdop);
+
+        encoder.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datum_origin() {
+        let datum = Datum::new(52.0, 5.0, 10.0);
+        let enu = datum.project(52.0, 5.0, 10.0);
+
+        assert!(enu.norm() < 1e-3);
+    }
+
+    #[test]
+    fn test_datum_projection() {
+        let datum = Datum::new(52.0, 5.0, 0.0);
+
+        let enu = datum.project(52.001, 5.0, 0.0);
+        assert!(enu.x.abs() < 0.01);
+        assert!((enu.y - 111.27).abs() < 0.1);
+
+        let enu = datum.project(52.0, 5.001, 0.0);
+        assert!((enu.x - 68.68).abs() < 0.1);
+        assert!(enu.y.abs() < 0.01);
+
+        let enu = datum.project(52.0, 5.0, 25.0);
+        assert!((enu.z - 25.0).abs() < 0.01);
+    }
+
+    fn variance(values: &[f32]) -> f32 {
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+    }
+
+    #[test]
+    fn test_gnss_filter_smoothing() {
+        use rand::{Rng, SeedableRng};
+
+        let datum = Datum::new(52.0, 5.0, 0.0);
+        let mut filter = GnssFilter::new(10);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(718);
+
+        let mut raw_east = Vec::new();
+        let mut filtered_east = Vec::new();
+
+        for _ in 0..200 {
+            // Roughly one meter of noise around the datum.
+            let gnss = Gnss {
+                location: (
+                    52.0 + rng.gen_range(-1e-5..1e-5),
+                    5.0 + rng.gen_range(-1.5e-5..1.5e-5),
+                ),
+                status: GnssStatus::LocationFix,
+                ..Default::default()
+            };
+
+            let filtered = filter.update(gnss);
+
+            raw_east.push(gnss.to_enu(&datum).unwrap().x);
+            filtered_east.push(filtered.to_enu(&datum).unwrap().x);
+        }
+
+        assert!(variance(&filtered_east[10..]) < variance(&raw_east[10..]) / 4.0);
+        assert!(filtered_east[10..].iter().all(|east| east.abs() < 1.0));
+        assert_ne!(filter.raw(), filter.filtered());
+    }
+
+    #[test]
+    fn test_gnss_filter_reset() {
+        let mut filter = GnssFilter::new(4);
+
+        let mut gnss = Gnss {
+            location: (52.0, 5.0),
+            status: GnssStatus::LocationFix,
+            ..Default::default()
+        };
+
+        for _ in 0..4 {
+            filter.update(gnss);
+        }
+
+        gnss.status = GnssStatus::DeviceNotFound;
+        gnss.location = (0.0, 0.0);
+        assert_eq!(filter.update(gnss), gnss);
+
+        gnss.status = GnssStatus::LocationFix;
+        gnss.location = (51.0, 4.0);
+        assert_eq!(filter.update(gnss).location, (51.0, 4.0));
+    }
+
+    #[test]
+    fn test_dead_reckoning_outage() {
+        let datum = Datum::new(52.0, 5.0, 0.0);
+        let mut estimator = DeadReckoning::new(30.0, 2.0);
+
+        // Drive north at 2 m/s for 10 seconds.
+        for i in 0..=100 {
+            let time = i as f64 * 0.1;
+            let (latitude, longitude) = offset_location((52.0, 5.0), 0.0, 2.0 * time);
+
+            let gnss = Gnss {
+                location: (latitude as f32, longitude as f32),
+                speed: 2.0,
+                heading: 0.0,
+                satellites: 12,
+                status: GnssStatus::LocationFix,
+                ..Default::default()
+            };
+
+            assert_eq!(estimator.update(time, gnss), gnss);
+        }
+
+        // Outage of 5 seconds.
+        let mut estimate = Gnss::default();
+        for i in 101..=150 {
+            let gnss = Gnss {
+                status: GnssStatus::DeviceNotFound,
+                ..Default::default()
+            };
+
+            estimate = estimator.update(i as f64 * 0.1, gnss);
+            assert!(estimate.is_dead_reckoning());
+        }
+
+        let enu = datum.project(estimate.location.0, estimate.location.1, 0.0);
+        assert!(enu.x.abs() < 0.5);
+        assert!((enu.y - 30.0).abs() < 0.5);
+
+        // Reacquire with a 3 meter error in the estimate.
+        let (latitude, longitude) = offset_location((52.0, 5.0), 0.0, 33.0);
+        let fix = Gnss {
+            location: (latitude as f32, longitude as f32),
+            speed: 0.0,
+            status: GnssStatus::LocationFix,
+            ..Default::default()
+        };
+
+        let blended = estimator.update(15.1, fix);
+        let enu = datum.project(blended.location.0, blended.location.1, 0.0);
+        assert!(blended.is_fix());
+        assert!((enu.y - 30.0).abs() < 0.6);
+
+        let blended = estimator.update(16.1, fix);
+        let enu = datum.project(blended.location.0, blended.location.1, 0.0);
+        assert!(enu.y > 30.5 && enu.y < 32.5);
+
+        assert_eq!(estimator.update(17.2, fix), fix);
+    }
+
+    #[test]
+    fn test_dead_reckoning_max_outage() {
+        let mut estimator = DeadReckoning::new(1.0, 1.0);
+
+        let fix = Gnss {
+            location: (52.0, 5.0),
+            speed: 1.0,
+            status: GnssStatus::LocationFix,
+            ..Default::default()
+        };
+        estimator.update(0.0, fix);
+
+        let lost = Gnss {
+            status: GnssStatus::DeviceNotFound,
+            ..Default::default()
+        };
+        assert!(estimator.update(0.5, lost).is_dead_reckoning());
+        assert_eq!(estimator.update(1.5, lost), lost);
+    }
+
+    #[test]
+    fn test_gnss_packet() {
+        use crate::protocol::Packetize;
+
+        let gnss = Gnss {
+            location: (52.0, 5.0),
+            altitude: 3.5,
+            satellites: 18,
+            status: GnssStatus::LocationFix,
+            fix_quality: FixQuality::RtkFixed,
+            satellites_used: 14,
+            hdop: 0.6,
+            ..Default::default()
+        };
+
+        let bytes = gnss.to_bytes();
+        assert_eq!(bytes.len(), 1 + MESSAGE_SIZE_LEGACY + 6);
+        assert_eq!(Gnss::try_from(bytes.as_slice()).unwrap(), gnss);
+        assert!(gnss.is_fix_quality(FixQuality::RtkFloat));
+
+        // Legacy message without the fix quality.
+        let legacy = Gnss::try_from(&bytes[..1 + MESSAGE_SIZE_LEGACY]).unwrap();
+        assert_eq!(legacy.location, gnss.location);
+        assert_eq!(legacy.fix_quality, FixQuality::Gps);
+        assert_eq!(legacy.satellites_used, 18);
+        assert_eq!(legacy.hdop, HDOP_UNKNOWN);
+        assert!(!legacy.is_fix_quality(FixQuality::Differential));
+
+        assert!(Gnss::try_from(&bytes[..MESSAGE_SIZE_LEGACY]).is_err());
+        assert!(Gnss::try_from(&bytes[..1 + MESSAGE_SIZE_LEGACY + 5]).is_err());
+    }
+
+    #[test]
+    fn test_gnss_to_enu() {
+        let mut gnss = Gnss {
+            location: (52.0, 5.0),
+            ..Default::default()
+        };
 
-        buf.put_f32(self.location.0);
-        buf.put_f32(self.location.1);
+        assert!(Datum::from_gnss(&gnss).is_none());
 
-        buf.put_f32(self.altitude);
-        buf.put_f32(self.speed);
-        buf.put_f32(self.heading);
+        gnss.status = GnssStatus::LocationFix;
 
-        buf.put_u8(self.satellites);
+        let datum = Datum::from_gnss(&gnss).unwrap();
 
-        buf.put_u8(self.status as u8);
+        gnss.location = (51.999, 4.999);
 
-        buf.to_vec()
+        let enu = gnss.to_enu(&datum).unwrap();
+        assert!((enu.x + 68.68).abs() < 0.5);
+        assert!((enu.y + 111.27).abs() < 0.5);
     }
 }
@@ -1,7 +1,12 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde_derive::Deserialize;
 
-use super::MachineType;
+use super::{
+    codec::{DecodeError, Decoder, Encoder},
+    MachineType,
+};
+
+/// Schema version of the instance message.
+const SCHEMA_VERSION: u8 = 0x01;
 
 /// Represents an instance of a machine.
 ///
@@ -279,53 +284,53 @@ impl std::fmt::Display for Instance {
     }
 }
 
-impl TryFrom<Vec<u8>> for Instance {
-    type Error = ();
-
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(&value);
+impl TryFrom<&[u8]> for Instance {
+    type Error = DecodeError;
 
-        let id = uuid::Uuid::from_slice(&buf.copy_to_bytes(16)).map_err(|_| ())?;
-        let ty = MachineType::try_from(buf.get_u8()).map_err(|_| ())?;
-        let version = (buf.get_u8(), buf.get_u8(), buf.get_u8());
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
-        let model_len = buf.get_u16() as usize;
-        let model = buf.copy_to_bytes(model_len);
+        let id = uuid::Uuid::from_slice(decoder.get_bytes(16)?).map_err(|_| decoder.invalid(16))?;
+        let ty = decoder.get_value()?;
+        let version = (decoder.get_u8()?, decoder.get_u8()?, decoder.get_u8()?);
 
-        let serial_len = buf.get_u16() as usize;
-        let serial_number = buf.copy_to_bytes(serial_len);
+        let model = decoder.get_string()?;
+        let serial_number = decoder.get_string()?;
 
         Ok(Instance {
             id,
             ty,
             version,
-            model: String::from_utf8_lossy(&model).to_string(),
-            serial_number: String::from_utf8_lossy(&serial_number).to_string(),
+            model,
+            serial_number,
         })
     }
 }
 
+impl TryFrom<Vec<u8>> for Instance {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Instance {
     const MESSAGE_TYPE: u8 = 0x15;
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(64);
-
-        buf.put(&self.id.as_bytes()[..]);
-        buf.put_u8(self.ty as u8);
-        buf.put_u8(self.version.0);
-        buf.put_u8(self.version.1);
-        buf.put_u8(self.version.2);
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 64);
 
-        let model_bytes = self.model.as_bytes();
-        buf.put_u16(model_bytes.len() as u16);
-        buf.put(model_bytes);
+        encoder.put_slice(self.id.as_bytes());
+        encoder.put_u8(self.ty as u8);
+        encoder.put_u8(self.version.0);
+        encoder.put_u8(self.version.1);
+        encoder.put_u8(self.version.2);
 
-        let serial_bytes = self.serial_number.as_bytes();
-        buf.put_u16(serial_bytes.len() as u16);
-        buf.put(serial_bytes);
+        encoder.put_bounded_string(&self.model);
+        encoder.put_bounded_string(&self.serial_number);
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
 
@@ -0,0 +1,180 @@
+use super::{
+    codec::{DecodeError, Decoder, Encoder},
+    Actuator,
+};
+
+/// Schema version of the cylinder pressure message.
+const SCHEMA_VERSION_PRESSURE: u8 = 0x01;
+/// Schema version of the load estimate message.
+const SCHEMA_VERSION_ESTIMATE: u8 = 0x01;
+
+/// Hydraulic cylinder pressure.
+///
+/// The pressures are measured on both sides of the cylinder piston.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CylinderPressure {
+    /// Actuator driving the cylinder.
+    pub actuator: Actuator,
+    /// Piston head side pressure in bar.
+    pub head: f32,
+    /// Piston rod side pressure in bar.
+    pub rod: f32,
+}
+
+impl CylinderPressure {
+    /// Construct a new cylinder pressure.
+    pub fn new(actuator: Actuator, head: f32, rod: f32) -> Self {
+        Self {
+            actuator,
+            head,
+            rod,
+        }
+    }
+}
+
+impl std::fmt::Display for CylinderPressure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} Head={:.1}bar Rod={:.1}bar",
+            self.actuator, self.head, self.rod
+        )
+    }
+}
+
+impl TryFrom<&[u8]> for CylinderPressure {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        Ok(Self {
+            actuator: Actuator::try_from(decoder.get_u8()? as u16)
+                .map_err(|_| decoder.invalid(1))?,
+            head: decoder.get_f32()?,
+            rod: decoder.get_f32()?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for CylinderPressure {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl crate::protocol::Packetize for CylinderPressure {
+    const MESSAGE_TYPE: u8 = 0x48;
+    const MESSAGE_SIZE: Option<usize> = Some(1 + 1 + (std::mem::size_of::<f32>() * 2));
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION_PRESSURE, 9);
+
+        encoder.put_u8(self.actuator.id());
+        encoder.put_f32(self.head);
+        encoder.put_f32(self.rod);
+
+        encoder.into_vec()
+    }
+}
+
+/// Estimated payload.
+///
+/// The payload is estimated from the boom cylinder pressure and the machine
+/// geometry. Without recent pressure or geometry the estimate is marked as
+/// invalid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LoadEstimate {
+    /// Payload mass in kilograms.
+    pub mass: f32,
+    /// Whether the estimate is valid.
+    pub valid: bool,
+}
+
+impl LoadEstimate {
+    /// Construct a new valid load estimate.
+    pub fn new(mass: f32) -> Self {
+        Self { mass, valid: true }
+    }
+
+    /// Construct a new invalid load estimate.
+    pub fn invalid() -> Self {
+        Self {
+            mass: 0.0,
+            valid: false,
+        }
+    }
+}
+
+impl std::fmt::Display for LoadEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.valid {
+            write!(f, "Payload {:.0}kg", self.mass)
+        } else {
+            write!(f, "Payload invalid")
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for LoadEstimate {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        Ok(Self {
+            mass: decoder.get_f32()?,
+            valid: decoder.get_bool()?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for LoadEstimate {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl crate::protocol::Packetize for LoadEstimate {
+    const MESSAGE_TYPE: u8 = 0x49;
+    const MESSAGE_SIZE: Option<usize> = Some(1 + std::mem::size_of::<f32>() + 1);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION_ESTIMATE, 5);
+
+        encoder.put_f32(self.mass);
+        encoder.put_bool(self.valid);
+
+        encoder.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Packetize;
+
+    #[test]
+    fn test_load_packets() {
+        let pressure = CylinderPressure::new(Actuator::Boom, 182.5, 31.0);
+
+        let bytes = pressure.to_bytes();
+        assert_eq!(bytes.len(), 10);
+        assert_eq!(CylinderPressure::try_from(bytes).unwrap(), pressure);
+
+        let estimate = LoadEstimate::new(1_250.0);
+        assert_eq!(
+            LoadEstimate::try_from(estimate.to_bytes()).unwrap(),
+            estimate
+        );
+        assert!(
+            !LoadEstimate::try_from(LoadEstimate::invalid().to_bytes())
+                .unwrap()
+                .valid
+        );
+    }
+}
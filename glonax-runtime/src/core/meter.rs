@@ -0,0 +1,145 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::{
+    codec::{DecodeError, Decoder, Encoder},
+    Actuator,
+};
+
+/// Schema version of the operation hours message.
+const SCHEMA_VERSION: u8 = 0x01;
+
+/// Cumulative operation hours.
+///
+/// Holds the engine running time and the active time of each actuator.
+/// Actuators are only metered while the engine is running.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperationHours {
+    /// Engine running time.
+    pub engine: Duration,
+    /// Active time per actuator.
+    pub actuator: HashMap<Actuator, Duration>,
+}
+
+impl OperationHours {
+    /// Engine running time in hours.
+    #[inline]
+    pub fn engine_hours(&self) -> f32 {
+        self.engine.as_secs_f32() / 3_600.0
+    }
+
+    /// Active time of an actuator.
+    #[inline]
+    pub fn active(&self, actuator: Actuator) -> Duration {
+        self.actuator.get(&actuator).copied().unwrap_or_default()
+    }
+
+    /// Duty cycle of an actuator.
+    ///
+    /// The duty cycle is the active time of the actuator relative to the
+    /// engine running time, between 0 and 1.
+    pub fn duty_cycle(&self, actuator: Actuator) -> f32 {
+        if self.engine.is_zero() {
+            return 0.0;
+        }
+
+        self.active(actuator).as_secs_f32() / self.engine.as_secs_f32()
+    }
+}
+
+impl std::fmt::Display for OperationHours {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Engine {:.1}h", self.engine_hours())?;
+
+        for actuator in Actuator::ALL {
+            if self.actuator.contains_key(&actuator) {
+                write!(
+                    f,
+                    " {:?}={:.1}%",
+                    actuator,
+                    self.duty_cycle(actuator) * 100.0
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for OperationHours {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        let engine = Duration::from_millis(decoder.get_u64()?);
+        let count = decoder.get_u8()? as usize;
+
+        let mut actuator = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let id =
+                Actuator::try_from(decoder.get_u8()? as u16).map_err(|_| decoder.invalid(1))?;
+            actuator.insert(id, Duration::from_millis(decoder.get_u64()?));
+        }
+
+        Ok(Self { engine, actuator })
+    }
+}
+
+impl TryFrom<Vec<u8>> for OperationHours {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl crate::protocol::Packetize for OperationHours {
+    const MESSAGE_TYPE: u8 = 0x4A;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 9 + self.actuator.len() * 9);
+
+        encoder.put_u64(self.engine.as_millis() as u64);
+        encoder.put_u8(self.actuator.len() as u8);
+
+        for actuator in Actuator::ALL {
+            if let Some(active) = self.actuator.get(&actuator) {
+                encoder.put_u8(actuator.id());
+                encoder.put_u64(active.as_millis() as u64);
+            }
+        }
+
+        encoder.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Packetize;
+
+    #[test]
+    fn operation_hours_packet() {
+        let mut hours = OperationHours {
+            engine: Duration::from_secs(7_200),
+            ..Default::default()
+        };
+        hours
+            .actuator
+            .insert(Actuator::Boom, Duration::from_secs(1_800));
+        hours
+            .actuator
+            .insert(Actuator::Slew, Duration::from_millis(250));
+
+        assert_eq!(hours.engine_hours(), 2.0);
+        assert_eq!(hours.duty_cycle(Actuator::Boom), 0.25);
+        assert_eq!(hours.duty_cycle(Actuator::Arm), 0.0);
+
+        let bytes = hours.to_bytes();
+        assert_eq!(bytes.len(), 1 + 9 + 2 * 9);
+        assert_eq!(OperationHours::try_from(bytes).unwrap(), hours);
+
+        assert!(OperationHours::try_from(&[SCHEMA_VERSION; 10][..]).is_err());
+        assert_eq!(OperationHours::default().duty_cycle(Actuator::Boom), 0.0);
+    }
+}
@@ -1,20 +1,35 @@
 use std::time::Instant;
 
+pub use self::capability::{ActuatorDescriptor, Capability, SegmentDescriptor, SensorDescriptor};
 pub use self::control::Control;
+pub use self::diagnostic::{DiagnosticCodes, DiagnosticTroubleCode};
 pub use self::engine::{Engine, EngineState};
-pub use self::gnss::{Gnss, GnssStatus};
+pub use self::gnss::{
+    Datum, DeadReckoning, FixQuality, Gnss, GnssFilter, GnssStatus, HDOP_UNKNOWN,
+};
 pub use self::instance::Instance;
+pub use self::load::{CylinderPressure, LoadEstimate};
+pub use self::meter::OperationHours;
 pub use self::motion::Actuator;
 pub use self::motion::Motion;
+pub use self::motion::SmoothingProfile;
+pub use self::rate::RotatorRate;
 pub use self::rotation::{RotationReference, Rotator};
 pub use self::status::{ModuleError, ModuleState, ModuleStatus};
 pub use self::target::Target;
 
+pub mod codec;
+
+mod capability;
 mod control;
+mod diagnostic;
 mod engine;
 mod gnss;
 mod instance;
+mod load;
+mod meter;
 mod motion;
+mod rate;
 mod rotation;
 mod status;
 mod target;
@@ -32,8 +47,20 @@ pub enum Object {
     Target(Target),
     /// Rotator.
     Rotator(Rotator),
+    /// Rotator rate.
+    RotatorRate(RotatorRate),
     /// Module status.
     ModuleStatus(ModuleStatus),
+    /// Machine capability.
+    Capability(Capability),
+    /// Hydraulic cylinder pressure.
+    CylinderPressure(CylinderPressure),
+    /// Payload load estimate.
+    LoadEstimate(LoadEstimate),
+    /// Cumulative operation hours.
+    OperationHours(OperationHours),
+    /// Active diagnostic trouble codes.
+    DiagnosticCodes(DiagnosticCodes),
 }
 
 /// Represents the type of an object.
@@ -154,6 +181,11 @@ impl TryFrom<u8> for MachineType {
     }
 }
 
+/// Machine state repository.
+///
+/// Holds the last known state of the machine. The state can be written to a
+/// snapshot and restored after a restart as an initial estimate. Restored
+/// state is stale until it is confirmed by live telemetry.
 #[repr(C)]
 pub struct Repository {
     /// Instance.
@@ -168,6 +200,10 @@ pub struct Repository {
     pub rotator: std::collections::HashMap<u8, Rotator>,
     /// Module status.
     pub module_status: std::collections::HashMap<String, ModuleStatus>,
+    /// Rotator sources restored from a snapshot and not yet confirmed.
+    stale_rotator: std::collections::HashSet<u8>,
+    /// Engine restored from a snapshot and not yet confirmed.
+    stale_engine: bool,
 }
 
 impl Repository {
@@ -179,6 +215,8 @@ impl Repository {
             control: std::collections::HashSet::new(),
             rotator: std::collections::HashMap::new(),
             module_status: std::collections::HashMap::new(),
+            stale_rotator: std::collections::HashSet::new(),
+            stale_engine: false,
         }
     }
 
@@ -191,4 +229,263 @@ impl Repository {
     pub fn machine_type(&self) -> MachineType {
         self.machine_type
     }
+
+    /// Update the repository with live telemetry.
+    ///
+    /// Live telemetry confirms any state restored from a snapshot.
+    pub fn update(&mut self, object: Object) {
+        match object {
+            Object::Engine(engine) => {
+                self.engine = engine;
+                self.stale_engine = false;
+            }
+            Object::Rotator(rotator) => {
+                self.stale_rotator.remove(&rotator.source);
+                self.rotator.insert(rotator.source, rotator);
+            }
+            Object::ModuleStatus(status) => {
+                self.module_status.insert(status.name.clone(), status);
+            }
+            Object::Control(control) => self.set_control(control),
+            _ => {}
+        }
+    }
+
+    /// Last selected motion smoothing profile.
+    pub fn motion_profile(&self) -> Option<SmoothingProfile> {
+        self.control.iter().find_map(|control| match control {
+            Control::MotionProfile(profile) => Some(*profile),
+            _ => None,
+        })
+    }
+
+    /// Set the control, replacing the previous value of the same control.
+    fn set_control(&mut self, control: Control) {
+        let control_type = control.control_type();
+
+        self.control.retain(|c| c.control_type() != control_type);
+        self.control.insert(control);
+    }
+
+    /// Test if any state restored from a snapshot is not yet confirmed.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        self.stale_engine || !self.stale_rotator.is_empty()
+    }
+
+    /// Test if the engine is restored from a snapshot and not yet confirmed.
+    #[inline]
+    pub fn is_engine_stale(&self) -> bool {
+        self.stale_engine
+    }
+
+    /// Test if the rotator is restored from a snapshot and not yet confirmed.
+    #[inline]
+    pub fn is_rotator_stale(&self, source: u8) -> bool {
+        self.stale_rotator.contains(&source)
+    }
+
+    /// Discard the state restored from a snapshot and not yet confirmed.
+    ///
+    /// Unconfirmed rotators are removed and an unconfirmed engine is reset,
+    /// so a source that never reports again does not hold back the snapshot.
+    pub fn expire_stale(&mut self) {
+        for source in self.stale_rotator.drain() {
+            self.rotator.remove(&source);
+        }
+
+        if self.stale_engine {
+            self.engine = Engine::default();
+            self.stale_engine = false;
+        }
+    }
+
+    /// Encode the machine state as a snapshot.
+    ///
+    /// The snapshot holds the engine, the rotators and the controls as a
+    /// sequence of protocol frames. Module status is not part of the snapshot
+    /// since it is only meaningful while the module is online.
+    pub fn snapshot(&self) -> Vec<u8> {
+        use crate::protocol::frame::Frame;
+
+        let mut frames = vec![Frame::from_packet(&self.engine)];
+        frames.extend(self.rotator.values().map(Frame::from_packet));
+        frames.extend(self.control.iter().map(Frame::from_packet));
+
+        frames
+            .iter()
+            .flat_map(|frame| frame.as_ref())
+            .copied()
+            .collect()
+    }
+
+    /// Restore the machine state from a snapshot.
+    ///
+    /// The restored engine and rotators are marked stale until they are
+    /// confirmed by live telemetry. Unknown messages in the snapshot are
+    /// skipped.
+    pub async fn restore(&mut self, snapshot: &[u8]) -> std::io::Result<()> {
+        use crate::protocol::{Packetize, Stream};
+
+        let mut stream = Stream::new(snapshot);
+
+        while !stream.inner().is_empty() {
+            let frame = stream.read_frame().await?;
+
+            match frame.message {
+                Engine::MESSAGE_TYPE => {
+                    self.engine = stream.recv_packet::<Engine>(frame.payload_length).await?;
+                    self.stale_engine = true;
+                }
+                Rotator::MESSAGE_TYPE => {
+                    let rotator = stream.recv_packet::<Rotator>(frame.payload_length).await?;
+                    self.stale_rotator.insert(rotator.source);
+                    self.rotator.insert(rotator.source, rotator);
+                }
+                Control::MESSAGE_TYPE => {
+                    let control = stream.recv_packet::<Control>(frame.payload_length).await?;
+                    self.set_control(control);
+                }
+                _ => stream.skip_packet(frame.payload_length).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the machine state snapshot to a file.
+    ///
+    /// The snapshot is written to a temporary file first and then moved in
+    /// place, so a crash during the write never leaves a partial snapshot.
+    pub async fn write_snapshot(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let path_tmp = path.with_extension("tmp");
+
+        tokio::fs::write(&path_tmp, self.snapshot()).await?;
+        tokio::fs::rename(&path_tmp, path).await
+    }
+
+    /// Restore the machine state from a snapshot file.
+    pub async fn read_snapshot(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<()> {
+        let snapshot = tokio::fs::read(path).await?;
+        self.restore(&snapshot).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Rotation3, Vector3};
+
+    use super::*;
+
+    fn repository() -> Repository {
+        Repository::new(
+            Instance::new(
+                "d55bcd75-8d30-49af-ac18-ee7cbce7822f",
+                "test",
+                MachineType::Excavator,
+                (3, 5, 13),
+                "ABC123",
+            ),
+            MachineType::Excavator,
+        )
+    }
+
+    #[tokio::test]
+    async fn repository_snapshot() {
+        let mut repository = self::repository();
+
+        let boom = Rotator::relative(0x6B, Rotation3::from_axis_angle(&Vector3::y_axis(), 0.5));
+        let arm = Rotator::relative(0x6C, Rotation3::from_axis_angle(&Vector3::y_axis(), -1.2));
+
+        repository.update(Object::Engine(Engine::from_rpm(1_200)));
+        repository.update(Object::Rotator(boom));
+        repository.update(Object::Rotator(arm));
+        repository.update(Object::Control(Control::HydraulicLock(true)));
+        repository.update(Object::Control(Control::HydraulicLock(false)));
+        repository.update(Object::Control(Control::MotionProfile(
+            SmoothingProfile::Gentle,
+        )));
+        assert!(!repository.is_stale());
+
+        let path = std::env::temp_dir().join(format!("glonax-snapshot-{}.bin", std::process::id()));
+        repository.write_snapshot(&path).await.unwrap();
+
+        let mut repository_b = self::repository();
+        repository_b.read_snapshot(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(repository_b.engine, repository.engine);
+        assert_eq!(repository_b.rotator.len(), 2);
+        assert!((repository_b.rotator[&0x6B].rotator.angle() - 0.5).abs() < 1e-6);
+        assert!((repository_b.rotator[&0x6C].rotator.angle() - 1.2).abs() < 1e-6);
+        assert!(repository_b
+            .control
+            .contains(&Control::HydraulicLock(false)));
+        assert_eq!(repository_b.control.len(), 2);
+        assert_eq!(
+            repository_b.motion_profile(),
+            Some(SmoothingProfile::Gentle)
+        );
+
+        assert!(self::repository().restore(&[0x4C, 0x58]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn repository_snapshot_stale() {
+        let mut repository = self::repository();
+        repository.update(Object::Engine(Engine::from_rpm(1_200)));
+        repository.update(Object::Rotator(Rotator::relative(
+            0x6B,
+            Rotation3::identity(),
+        )));
+
+        let mut repository_b = self::repository();
+        repository_b.restore(&repository.snapshot()).await.unwrap();
+        assert!(repository_b.is_stale());
+
+        repository_b.update(Object::Engine(Engine::from_rpm(800)));
+        assert!(repository_b.is_stale());
+
+        repository_b.update(Object::Rotator(Rotator::relative(
+            0x6B,
+            Rotation3::identity(),
+        )));
+        assert!(!repository_b.is_stale());
+    }
+
+    #[tokio::test]
+    async fn repository_snapshot_expire() {
+        let mut repository = self::repository();
+        repository.update(Object::Engine(Engine::from_rpm(1_200)));
+        repository.update(Object::Rotator(Rotator::relative(
+            0x6B,
+            Rotation3::identity(),
+        )));
+        repository.update(Object::Rotator(Rotator::relative(
+            0x6C,
+            Rotation3::identity(),
+        )));
+
+        let mut repository_b = self::repository();
+        repository_b.restore(&repository.snapshot()).await.unwrap();
+        assert!(repository_b.is_engine_stale());
+        assert!(repository_b.is_rotator_stale(0x6B));
+
+        repository_b.update(Object::Rotator(Rotator::relative(
+            0x6B,
+            Rotation3::identity(),
+        )));
+        assert!(!repository_b.is_rotator_stale(0x6B));
+        assert!(repository_b.is_rotator_stale(0x6C));
+
+        repository_b.expire_stale();
+        assert!(!repository_b.is_stale());
+        assert_eq!(repository_b.engine, Engine::default());
+        assert!(repository_b.rotator.contains_key(&0x6B));
+        assert!(!repository_b.rotator.contains_key(&0x6C));
+    }
 }
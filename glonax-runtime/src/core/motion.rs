@@ -1,42 +1,161 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use super::codec::{DecodeError, Decoder, Encoder};
 
 const MOTION_TYPE_STOP_ALL: u8 = 0x00;
 const MOTION_TYPE_RESUME_ALL: u8 = 0x01;
 const MOTION_TYPE_RESET_ALL: u8 = 0x02;
+const MOTION_TYPE_STOP: u8 = 0x03;
 const MOTION_TYPE_STRAIGHT_DRIVE: u8 = 0x05;
 const MOTION_TYPE_CHANGE: u8 = 0x10;
 
 const MOTION_MAX_CHANGE_SET_COUNT: usize = 32;
 
+/// Schema version of the motion message.
+const SCHEMA_VERSION: u8 = 0x01;
+
+/// Excavator actuator.
+///
+/// This is the canonical actuator definition, all other parts of the runtime
+/// refer to it. The identifier of an actuator is the hydraulic control unit
+/// channel the valve is wired to. Channels 0 to 3 are on the first bank,
+/// channels 4 to 7 on the second bank.
+///
+/// | Actuator   | Channel | Bank | Slot |
+/// |------------|---------|------|------|
+/// | Boom       | 0       | 0    | 0    |
+/// | Slew       | 1       | 0    | 1    |
+/// | LimpRight  | 2       | 0    | 2    |
+/// | LimpLeft   | 3       | 0    | 3    |
+/// | Arm        | 4       | 1    | 0    |
+/// | Attachment | 5       | 1    | 1    |
 // FUTURE: Move to glonax-server or an excatavator module
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Actuator {
-    /// Boom actuator.
+    /// Boom actuator, channel 0.
     Boom = 0,
-    /// Arm actuator.
+    /// Arm actuator, channel 4.
     Arm = 4,
-    /// Attachment actuator.
+    /// Attachment actuator, channel 5.
     Attachment = 5,
-    /// Slew actuator.
+    /// Slew actuator, channel 1.
     Slew = 1,
-    /// Left limp actuator.
+    /// Left limp actuator, channel 3.
     LimpLeft = 3,
-    /// Right limp actuator.
+    /// Right limp actuator, channel 2.
     LimpRight = 2,
 }
 
+impl Actuator {
+    /// All actuators.
+    pub const ALL: [Actuator; 6] = [
+        Actuator::Boom,
+        Actuator::Arm,
+        Actuator::Attachment,
+        Actuator::Slew,
+        Actuator::LimpLeft,
+        Actuator::LimpRight,
+    ];
+
+    /// Actuator identifier.
+    ///
+    /// This is the only mapping from actuator to identifier. The identifier
+    /// is used on the wire and by the hydraulic control unit, never convert
+    /// an actuator to a number by other means.
+    #[inline]
+    pub const fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
 impl TryFrom<u16> for Actuator {
     type Error = ();
 
     fn try_from(value: u16) -> Result<Self, Self::Error> {
-        match value {
-            0 => Ok(Actuator::Boom),
-            4 => Ok(Actuator::Arm),
-            5 => Ok(Actuator::Attachment),
-            1 => Ok(Actuator::Slew),
-            3 => Ok(Actuator::LimpLeft),
-            2 => Ok(Actuator::LimpRight),
-            _ => Err(()),
+        Actuator::ALL
+            .into_iter()
+            .find(|actuator| actuator.id() as u16 == value)
+            .ok_or(())
+    }
+}
+
+/// Motion smoothing profile.
+///
+/// The profile defines how the hydraulic driver shapes motion commands. The
+/// ramp rate limits how fast the power of an actuator can increase, the
+/// deadband suppresses small inputs. A decrease in power is never ramped, so
+/// stopping is always immediate. Without a profile the motion commands pass
+/// through as requested.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SmoothingProfile {
+    /// Fast response, small deadband.
+    Aggressive = 0,
+    /// Balanced response.
+    Normal = 1,
+    /// Slow response, large deadband.
+    Gentle = 2,
+    /// No smoothing.
+    #[default]
+    Off = 3,
+}
+
+impl SmoothingProfile {
+    /// All smoothing profiles.
+    pub const ALL: [SmoothingProfile; 4] = [
+        SmoothingProfile::Aggressive,
+        SmoothingProfile::Normal,
+        SmoothingProfile::Gentle,
+        SmoothingProfile::Off,
+    ];
+
+    /// Maximum power increase per second, or `None` if not limited.
+    pub const fn ramp_rate(&self) -> Option<f32> {
+        match self {
+            SmoothingProfile::Aggressive => Some(1_200_000.0),
+            SmoothingProfile::Normal => Some(600_000.0),
+            SmoothingProfile::Gentle => Some(250_000.0),
+            SmoothingProfile::Off => None,
+        }
+    }
+
+    /// Power below which input is suppressed.
+    pub const fn deadband(&self) -> MotionValueType {
+        match self {
+            SmoothingProfile::Aggressive => 250,
+            SmoothingProfile::Normal => 750,
+            SmoothingProfile::Gentle => 1_500,
+            SmoothingProfile::Off => 0,
+        }
+    }
+}
+
+impl TryFrom<u8> for SmoothingProfile {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        SmoothingProfile::ALL
+            .into_iter()
+            .find(|profile| *profile as u8 == value)
+            .ok_or(())
+    }
+}
+
+impl std::str::FromStr for SmoothingProfile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SmoothingProfile::ALL
+            .into_iter()
+            .find(|profile| profile.to_string().eq_ignore_ascii_case(s))
+            .ok_or(())
+    }
+}
+
+impl std::fmt::Display for SmoothingProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmoothingProfile::Aggressive => write!(f, "aggressive"),
+            SmoothingProfile::Normal => write!(f, "normal"),
+            SmoothingProfile::Gentle => write!(f, "gentle"),
+            SmoothingProfile::Off => write!(f, "off"),
         }
     }
 }
@@ -52,26 +171,23 @@ pub struct ChangeSet {
 }
 
 // FUTURE: Replace Vec with a fixed size array
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub enum Motion {
     /// Stop all motion until resumed.
+    #[default]
     StopAll,
     /// Resume all motion.
     ResumeAll,
     /// Reset the motion state machine.
     ResetAll,
+    /// Stop motion on actuators.
+    Stop(Vec<Actuator>),
     /// Drive straight forward or backwards.
     StraightDrive(MotionValueType),
     /// Change motion on actuators.
     Change(Vec<ChangeSet>),
 }
 
-impl Default for Motion {
-    fn default() -> Self {
-        Self::StopAll
-    }
-}
-
 impl Motion {
     /// Maximum power setting.
     pub const POWER_MAX: MotionValueType = MotionValueType::MAX;
@@ -113,6 +229,7 @@ impl std::fmt::Display for Motion {
             Motion::StopAll => write!(f, "Stop all"),
             Motion::ResumeAll => write!(f, "Resume all"),
             Motion::ResetAll => write!(f, "Reset all"),
+            Motion::Stop(actuators) => write!(f, "Stop: {:?}", actuators),
             Motion::StraightDrive(value) => write!(f, "Straight drive: {}", value),
             Motion::Change(changes) => {
                 let mut s = String::new();
@@ -135,85 +252,99 @@ impl std::fmt::Display for Motion {
     }
 }
 
-impl TryFrom<Vec<u8>> for Motion {
-    type Error = ();
+impl TryFrom<&[u8]> for Motion {
+    type Error = DecodeError;
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(&value);
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
-        match buf.get_u8() {
+        let get_actuator = |decoder: &mut Decoder| {
+            Actuator::try_from(decoder.get_u16()?).map_err(|_| decoder.invalid(2))
+        };
+
+        match decoder.get_u8()? {
             MOTION_TYPE_STOP_ALL => Ok(Motion::StopAll),
             MOTION_TYPE_RESUME_ALL => Ok(Motion::ResumeAll),
             MOTION_TYPE_RESET_ALL => Ok(Motion::ResetAll),
-            MOTION_TYPE_STRAIGHT_DRIVE => {
-                if buf.len() != std::mem::size_of::<i16>() {
-                    return Err(());
+            MOTION_TYPE_STOP => {
+                let count = decoder.get_u8()?;
+                if count as usize > MOTION_MAX_CHANGE_SET_COUNT {
+                    return Err(decoder.invalid(1));
                 }
 
-                Ok(Motion::StraightDrive(buf.get_i16()))
+                let mut actuators = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    actuators.push(get_actuator(&mut decoder)?);
+                }
+                Ok(Motion::Stop(actuators))
             }
+            MOTION_TYPE_STRAIGHT_DRIVE => Ok(Motion::StraightDrive(decoder.get_i16()?)),
             MOTION_TYPE_CHANGE => {
-                use std::mem::size_of;
-                const CHANGESET_SIZE: usize = size_of::<u16>() + size_of::<i16>();
-
-                if buf.len() < size_of::<u8>() {
-                    return Err(());
-                }
-
-                let count = buf.get_u8();
+                let count = decoder.get_u8()?;
                 if count as usize > MOTION_MAX_CHANGE_SET_COUNT {
-                    return Err(());
-                }
-
-                if buf.len() != count as usize * CHANGESET_SIZE {
-                    return Err(());
+                    return Err(decoder.invalid(1));
                 }
 
                 let mut changes = Vec::with_capacity(count as usize);
                 for _ in 0..count {
                     changes.push(ChangeSet {
-                        actuator: buf.get_u16().try_into()?,
-                        value: buf.get_i16(),
+                        actuator: get_actuator(&mut decoder)?,
+                        value: decoder.get_i16()?,
                     });
                 }
                 Ok(Motion::Change(changes))
             }
-            _ => Err(()),
+            _ => Err(decoder.invalid(1)),
         }
     }
 }
 
+impl TryFrom<Vec<u8>> for Motion {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Motion {
     const MESSAGE_TYPE: u8 = 0x20;
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(32);
+        let mut encoder = Encoder::new(SCHEMA_VERSION);
 
         match self {
             Motion::StopAll => {
-                buf.put_u8(MOTION_TYPE_STOP_ALL);
+                encoder.put_u8(MOTION_TYPE_STOP_ALL);
             }
             Motion::ResumeAll => {
-                buf.put_u8(MOTION_TYPE_RESUME_ALL);
+                encoder.put_u8(MOTION_TYPE_RESUME_ALL);
             }
             Motion::ResetAll => {
-                buf.put_u8(MOTION_TYPE_RESET_ALL);
+                encoder.put_u8(MOTION_TYPE_RESET_ALL);
+            }
+            Motion::Stop(actuators) => {
+                encoder.put_u8(MOTION_TYPE_STOP);
+                encoder.put_u8(actuators.len() as u8);
+                for actuator in actuators {
+                    encoder.put_u16(actuator.id() as u16);
+                }
             }
             Motion::StraightDrive(value) => {
-                buf.put_u8(MOTION_TYPE_STRAIGHT_DRIVE);
-                buf.put_i16(*value);
+                encoder.put_u8(MOTION_TYPE_STRAIGHT_DRIVE);
+                encoder.put_i16(*value);
             }
             Motion::Change(changes) => {
-                buf.put_u8(MOTION_TYPE_CHANGE);
-                buf.put_u8(changes.len() as u8);
+                encoder.put_u8(MOTION_TYPE_CHANGE);
+                encoder.put_u8(changes.len() as u8);
                 for change in changes {
-                    buf.put_u16(change.actuator as u16);
-                    buf.put_i16(change.value);
+                    encoder.put_u16(change.actuator.id() as u16);
+                    encoder.put_i16(change.value);
                 }
             }
         }
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
 
@@ -231,4 +362,26 @@ mod tests {
 
         assert_eq!(motion, motion2);
     }
+
+    #[test]
+    fn test_motion_stop() {
+        let motion = Motion::Stop(vec![Actuator::Arm, Actuator::LimpRight]);
+        let bytes = motion.to_bytes();
+
+        assert_eq!(
+            bytes,
+            vec![SCHEMA_VERSION, MOTION_TYPE_STOP, 2, 0x00, 0x04, 0x00, 0x02]
+        );
+        assert_eq!(Motion::try_from(bytes).unwrap(), motion);
+        assert!(!motion.is_movable());
+    }
+
+    #[test]
+    fn test_actuator_id() {
+        for actuator in Actuator::ALL {
+            assert_eq!(Actuator::try_from(actuator.id() as u16), Ok(actuator));
+        }
+
+        assert!(Actuator::try_from(6).is_err());
+    }
 }
@@ -0,0 +1,119 @@
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// Schema version of the rotator rate message.
+const SCHEMA_VERSION: u8 = 0x01;
+
+/// Represents the rate of change of a rotator.
+///
+/// The velocity and acceleration are derived from the rotator positions
+/// reported by the same source. During signal gaps the derivative cannot be
+/// calculated and the rate is marked as invalid.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RotatorRate {
+    /// The source of the rotation.
+    pub source: u8,
+    /// Angular velocity in radians per second.
+    pub velocity: f32,
+    /// Angular acceleration in radians per second squared.
+    pub acceleration: f32,
+    /// Whether the derivative is valid.
+    pub valid: bool,
+}
+
+impl RotatorRate {
+    /// Construct a new valid rotator rate.
+    pub fn new(source: u8, velocity: f32, acceleration: f32) -> Self {
+        Self {
+            source,
+            velocity,
+            acceleration,
+            valid: true,
+        }
+    }
+
+    /// Construct a new invalid rotator rate.
+    pub fn invalid(source: u8) -> Self {
+        Self {
+            source,
+            velocity: 0.0,
+            acceleration: 0.0,
+            valid: false,
+        }
+    }
+}
+
+impl std::fmt::Display for RotatorRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.valid {
+            write!(
+                f,
+                "0x{:X} Velocity={:.2}°/s Acceleration={:.2}°/s²",
+                self.source,
+                self.velocity.to_degrees(),
+                self.acceleration.to_degrees()
+            )
+        } else {
+            write!(f, "0x{:X} Invalid", self.source)
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for RotatorRate {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+
+        Ok(Self {
+            source: decoder.get_u8()?,
+            velocity: decoder.get_f32()?,
+            acceleration: decoder.get_f32()?,
+            valid: decoder.get_bool()?,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for RotatorRate {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl crate::protocol::Packetize for RotatorRate {
+    const MESSAGE_TYPE: u8 = 0x47;
+    const MESSAGE_SIZE: Option<usize> = Some(1 + (std::mem::size_of::<f32>() * 2) + 1 + 1);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 10);
+
+        encoder.put_u8(self.source);
+        encoder.put_f32(self.velocity);
+        encoder.put_f32(self.acceleration);
+        encoder.put_bool(self.valid);
+
+        encoder.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Packetize;
+
+    #[test]
+    fn test_rotator_rate() {
+        let rate = RotatorRate::new(0x6B, 0.5, -1.25);
+
+        let bytes = rate.to_bytes();
+        assert_eq!(bytes.len(), 11);
+
+        let rate2 = RotatorRate::try_from(bytes).unwrap();
+        assert_eq!(rate, rate2);
+
+        let rate = RotatorRate::invalid(0x6A);
+        let rate2 = RotatorRate::try_from(rate.to_bytes()).unwrap();
+        assert!(!rate2.valid);
+    }
+}
@@ -1,5 +1,10 @@
 use nalgebra::Rotation3;
 
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// Schema version of the rotator message.
+const SCHEMA_VERSION: u8 = 0x01;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum RotationReference {
     Absolute,
@@ -63,41 +68,49 @@ impl std::fmt::Display for Rotator {
     }
 }
 
-impl TryFrom<Vec<u8>> for Rotator {
-    type Error = ();
+impl TryFrom<&[u8]> for Rotator {
+    type Error = DecodeError;
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        use bytes::Buf;
-
-        let mut buf = bytes::Bytes::copy_from_slice(value.as_slice());
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
         Ok(Self {
-            source: buf.get_u8(),
-            rotator: Rotation3::from_euler_angles(buf.get_f32(), buf.get_f32(), buf.get_f32()),
-            reference: RotationReference::try_from(buf.get_u8()).unwrap(),
+            source: decoder.get_u8()?,
+            rotator: Rotation3::from_euler_angles(
+                decoder.get_f32()?,
+                decoder.get_f32()?,
+                decoder.get_f32()?,
+            ),
+            reference: decoder.get_value()?,
         })
     }
 }
 
+impl TryFrom<Vec<u8>> for Rotator {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Rotator {
     const MESSAGE_TYPE: u8 = 0x46;
-    const MESSAGE_SIZE: Option<usize> = Some((std::mem::size_of::<f32>() * 3) + 1 + 1);
+    const MESSAGE_SIZE: Option<usize> = Some(1 + (std::mem::size_of::<f32>() * 3) + 1 + 1);
 
     fn to_bytes(&self) -> Vec<u8> {
-        use bytes::BufMut;
-
-        let mut buf = bytes::BytesMut::with_capacity((std::mem::size_of::<f32>() * 3) + 1 + 1);
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 14);
 
-        buf.put_u8(self.source);
+        encoder.put_u8(self.source);
 
         let (roll, pitch, yaw) = self.rotator.euler_angles();
-        buf.put_f32(roll);
-        buf.put_f32(pitch);
-        buf.put_f32(yaw);
+        encoder.put_f32(roll);
+        encoder.put_f32(pitch);
+        encoder.put_f32(yaw);
 
-        buf.put_u8(self.reference as u8);
+        encoder.put_u8(self.reference as u8);
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
 
@@ -116,8 +129,8 @@ mod tests {
 
         let bytes = rotator.to_bytes();
 
-        assert_eq!(bytes.len(), 14);
-        assert_eq!(bytes[13], 0x01);
+        assert_eq!(bytes.len(), 15);
+        assert_eq!(bytes[14], 0x01);
 
         let rotator = Rotator::try_from(bytes).unwrap();
 
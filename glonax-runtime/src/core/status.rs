@@ -1,4 +1,7 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
+use super::codec::{truncate_string, DecodeError, Decoder, Encoder, STRING_LEN_MAX};
+
+/// Schema version of the module status message.
+const SCHEMA_VERSION: u8 = 0x01;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ModuleState {
@@ -44,6 +47,8 @@ pub enum ModuleError {
     CommunicationTimeout,
     GenericCommunicationError,
     IOError,
+    SensorFrozen,
+    ServiceStalled,
 }
 
 impl std::fmt::Display for ModuleError {
@@ -57,38 +62,75 @@ impl std::fmt::Display for ModuleError {
                 ModuleError::CommunicationTimeout => "communication timeout",
                 ModuleError::GenericCommunicationError => "generic communication error",
                 ModuleError::IOError => "i/o error",
+                ModuleError::SensorFrozen => "sensor frozen",
+                ModuleError::ServiceStalled => "service stalled",
             }
         )
     }
 }
 
 // TODO: Split name into vendor and product
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ModuleStatus {
     /// Name of the module.
+    ///
+    /// The constructors truncate the name to the maximum encoded length.
     pub name: String,
     /// State of the module.
     pub state: ModuleState,
     /// Module error if any.
     pub error: Option<ModuleError>,
+    /// Update rate of the module in hertz, if known.
+    pub update_rate: Option<f32>,
 }
 
 impl ModuleStatus {
+    /// Bound the module name to the maximum encoded length.
+    fn bounded_name(name: String) -> String {
+        if name.len() > STRING_LEN_MAX {
+            truncate_string(&name).to_string()
+        } else {
+            name
+        }
+    }
+
     /// Construct a new healthy module status.
     pub fn healthy(name: String) -> Self {
         Self {
-            name,
+            name: Self::bounded_name(name),
             state: ModuleState::Healthy,
             error: None,
+            update_rate: None,
+        }
+    }
+
+    /// Construct a new degraded module status.
+    pub fn degraded(name: String, error: Option<ModuleError>) -> Self {
+        Self {
+            name: Self::bounded_name(name),
+            state: ModuleState::Degraded,
+            error,
+            update_rate: None,
         }
     }
 
     /// Construct a new faulty module status.
     pub fn faulty(name: String, error: ModuleError) -> Self {
         Self {
-            name,
+            name: Self::bounded_name(name),
             state: ModuleState::Faulty,
             error: Some(error),
+            update_rate: None,
+        }
+    }
+
+    /// Construct a new emergency module status.
+    pub fn emergency(name: String) -> Self {
+        Self {
+            name: Self::bounded_name(name),
+            state: ModuleState::Emergency,
+            error: None,
+            update_rate: None,
         }
     }
 
@@ -96,34 +138,58 @@ impl ModuleStatus {
     pub fn is_healthy(&self) -> bool {
         self.state == ModuleState::Healthy
     }
-}
 
-impl TryFrom<Vec<u8>> for ModuleStatus {
-    type Error = ();
+    /// Set the update rate of the module in hertz.
+    pub fn with_update_rate(mut self, update_rate: Option<f32>) -> Self {
+        self.update_rate = update_rate;
+        self
+    }
+}
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(&value);
+impl TryFrom<&[u8]> for ModuleStatus {
+    type Error = DecodeError;
 
-        let name_len = buf.get_u16() as usize;
-        let name = buf.split_to(name_len).to_vec();
-        let name = String::from_utf8_lossy(&name).to_string();
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
-        let state = ModuleState::try_from(buf.get_u8())?;
+        let name = decoder.get_string()?;
+        let state = decoder.get_value()?;
 
-        let error = match buf.get_u8() {
+        let error = match decoder.get_u8()? {
             0 => None,
-            1 => match buf.get_u8() {
+            1 => match decoder.get_u8()? {
                 0 => Some(ModuleError::InvalidConfiguration),
                 1 => Some(ModuleError::VersionMismatch),
                 2 => Some(ModuleError::CommunicationTimeout),
                 3 => Some(ModuleError::GenericCommunicationError),
                 4 => Some(ModuleError::IOError),
-                _ => return Err(()),
+                5 => Some(ModuleError::SensorFrozen),
+                6 => Some(ModuleError::ServiceStalled),
+                _ => return Err(decoder.invalid(1)),
             },
-            _ => return Err(()),
+            _ => return Err(decoder.invalid(1)),
         };
 
-        Ok(ModuleStatus { name, state, error })
+        let update_rate = if decoder.get_bool()? {
+            Some(decoder.get_f32()?)
+        } else {
+            None
+        };
+
+        Ok(ModuleStatus {
+            name,
+            state,
+            error,
+            update_rate,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for ModuleStatus {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
     }
 }
 
@@ -131,29 +197,32 @@ impl crate::protocol::Packetize for ModuleStatus {
     const MESSAGE_TYPE: u8 = 0x16;
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity(64);
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 64);
 
-        let name_bytes = self.name.as_bytes();
-        buf.put_u16(name_bytes.len() as u16);
-        buf.put(name_bytes);
-
-        buf.put_u8(self.state as u8);
+        encoder.put_bounded_string(&self.name);
+        encoder.put_u8(self.state as u8);
 
         // TODO: Replace with something more stable
         if let Some(error) = &self.error {
-            buf.put_u8(1);
-            buf.put_u8(match error {
+            encoder.put_u8(1);
+            encoder.put_u8(match error {
                 ModuleError::InvalidConfiguration => 0,
                 ModuleError::VersionMismatch => 1,
                 ModuleError::CommunicationTimeout => 2,
                 ModuleError::GenericCommunicationError => 3,
                 ModuleError::IOError => 4,
+                ModuleError::SensorFrozen => 5,
+                ModuleError::ServiceStalled => 6,
             });
         } else {
-            buf.put_u8(0);
+            encoder.put_u8(0);
         }
 
-        buf.to_vec()
+        // The update rate is always encoded, trailing bytes are not a field.
+        encoder.put_bool(self.update_rate.is_some());
+        encoder.put_f32(self.update_rate.unwrap_or_default());
+
+        encoder.into_vec()
     }
 }
 
@@ -167,7 +236,13 @@ impl std::fmt::Display for ModuleStatus {
                 Some(error) => format!("{}: {}", self.state, error),
                 None => format!("{}", self.state),
             }
-        )
+        )?;
+
+        if let Some(update_rate) = self.update_rate {
+            write!(f, " ({:.1} Hz)", update_rate)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -183,6 +258,7 @@ mod tests {
             name: "Test".to_string(),
             state: ModuleState::Healthy,
             error: None,
+            update_rate: None,
         };
 
         let bytes = status.to_bytes();
@@ -197,6 +273,7 @@ mod tests {
             name: "Test".to_string(),
             state: ModuleState::Degraded,
             error: Some(ModuleError::InvalidConfiguration),
+            update_rate: None,
         };
 
         let bytes = status.to_bytes();
@@ -204,4 +281,24 @@ mod tests {
 
         assert_eq!(status, status2);
     }
+
+    #[test]
+    fn test_module_status_update_rate() {
+        let status = ModuleStatus::degraded(
+            "kübler:encoder:0x6A".to_string(),
+            Some(ModuleError::SensorFrozen),
+        )
+        .with_update_rate(Some(50.0));
+
+        let bytes = status.to_bytes();
+        assert_eq!(ModuleStatus::try_from(bytes.as_slice()).unwrap(), status);
+        assert_eq!(
+            status.to_string(),
+            "kübler:encoder:0x6A: Degraded: sensor frozen (50.0 Hz)"
+        );
+
+        // Message without the update rate.
+        let status = status.with_update_rate(None);
+        assert_eq!(ModuleStatus::try_from(status.to_bytes()).unwrap(), status);
+    }
 }
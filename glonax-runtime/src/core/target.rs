@@ -1,6 +1,10 @@
-use bytes::{Buf, BufMut, Bytes, BytesMut};
 use nalgebra::{Point3, UnitQuaternion};
 
+use super::codec::{DecodeError, Decoder, Encoder};
+
+/// Schema version of the target message.
+const SCHEMA_VERSION: u8 = 0x01;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Constraint {
     /// Unconstrained motion order.
@@ -170,42 +174,53 @@ impl From<Point3<f32>> for Target {
     }
 }
 
-impl TryFrom<Vec<u8>> for Target {
-    type Error = ();
+impl TryFrom<&[u8]> for Target {
+    type Error = DecodeError;
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let mut buf = Bytes::copy_from_slice(value.as_slice());
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
 
-        let point = Point3::new(buf.get_f32(), buf.get_f32(), buf.get_f32());
-        let orientation =
-            UnitQuaternion::from_euler_angles(buf.get_f32(), buf.get_f32(), buf.get_f32());
+        let point = Point3::new(decoder.get_f32()?, decoder.get_f32()?, decoder.get_f32()?);
+        let orientation = UnitQuaternion::from_euler_angles(
+            decoder.get_f32()?,
+            decoder.get_f32()?,
+            decoder.get_f32()?,
+        );
 
         Ok(Self {
             point,
             orientation,
-            constraint: Constraint::try_from(buf.get_u8()).unwrap(),
+            constraint: decoder.get_value()?,
         })
     }
 }
 
+impl TryFrom<Vec<u8>> for Target {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl crate::protocol::Packetize for Target {
     const MESSAGE_TYPE: u8 = 0x44;
-    const MESSAGE_SIZE: Option<usize> = Some((std::mem::size_of::<f32>() * 6) + 1);
+    const MESSAGE_SIZE: Option<usize> = Some(1 + (std::mem::size_of::<f32>() * 6) + 1);
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BytesMut::with_capacity((std::mem::size_of::<f32>() * 6) + 1);
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 25);
 
-        buf.put_f32(self.point.coords[0]);
-        buf.put_f32(self.point.coords[1]);
-        buf.put_f32(self.point.coords[2]);
+        encoder.put_f32(self.point.coords[0]);
+        encoder.put_f32(self.point.coords[1]);
+        encoder.put_f32(self.point.coords[2]);
 
         let (roll, pitch, yaw) = self.orientation.euler_angles();
-        buf.put_f32(roll);
-        buf.put_f32(pitch);
-        buf.put_f32(yaw);
+        encoder.put_f32(roll);
+        encoder.put_f32(pitch);
+        encoder.put_f32(yaw);
 
-        buf.put_u8(self.constraint as u8);
+        encoder.put_u8(self.constraint as u8);
 
-        buf.to_vec()
+        encoder.into_vec()
     }
 }
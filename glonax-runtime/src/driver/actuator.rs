@@ -46,3 +46,200 @@ impl ActuatorState {
         }
     }
 }
+
+/// Motion profile configuration of a joint.
+///
+/// Converts the rotation error of the joint in radians to actuator power.
+#[derive(Clone, Copy, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct JointProfileConfig {
+    /// Power per radian of error.
+    pub gain: f32,
+    /// Power added to get the actuator moving.
+    pub offset: f32,
+    /// Minimum power magnitude.
+    #[serde(default)]
+    pub power_min: u16,
+    /// Maximum power magnitude.
+    #[serde(default = "JointProfileConfig::default_power_max")]
+    pub power_max: u16,
+    /// Error in radians below which no power is applied.
+    #[serde(default)]
+    pub deadband_rad: f32,
+    /// Invert the power.
+    #[serde(default)]
+    pub invert: bool,
+}
+
+impl JointProfileConfig {
+    fn default_power_max() -> u16 {
+        i16::MAX as u16
+    }
+
+    /// Construct a new joint profile configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `gain` - The power per radian of error.
+    /// * `offset` - The power added to get the actuator moving.
+    /// * `invert` - Invert the power.
+    pub fn new(gain: f32, offset: f32, invert: bool) -> Self {
+        Self {
+            gain,
+            offset,
+            power_min: 0,
+            power_max: Self::default_power_max(),
+            deadband_rad: 0.0,
+            invert,
+        }
+    }
+
+    /// Build the joint profile.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the gain, offset or deadband is negative, or the
+    /// power limits are out of range.
+    pub fn build(&self) -> std::io::Result<Linear> {
+        let invalid = |message: String| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ))
+        };
+
+        if !(self.gain.is_finite() && self.gain >= 0.0) {
+            return invalid(format!("invalid motion profile gain: {}", self.gain));
+        }
+        if !(self.offset.is_finite() && self.offset >= 0.0) {
+            return invalid(format!("invalid motion profile offset: {}", self.offset));
+        }
+        if !(self.deadband_rad.is_finite() && self.deadband_rad >= 0.0) {
+            return invalid(format!(
+                "invalid motion profile deadband: {}",
+                self.deadband_rad
+            ));
+        }
+        if self.power_max > i16::MAX as u16 || self.power_min > self.power_max {
+            return invalid(format!(
+                "invalid motion profile power limits: {} to {}",
+                self.power_min, self.power_max
+            ));
+        }
+
+        Ok(Linear::new(self.gain, self.offset, self.invert)
+            .with_deadband(self.deadband_rad)
+            .with_power_limit(self.power_min as f32, self.power_max as f32))
+    }
+}
+
+/// Motion profile configuration.
+///
+/// The defaults are tuned for the Volvo EC240CL.
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct MotionProfileConfig {
+    /// Frame joint profile.
+    #[serde(default = "MotionProfileConfig::default_frame")]
+    pub frame: JointProfileConfig,
+    /// Boom joint profile.
+    #[serde(default = "MotionProfileConfig::default_boom")]
+    pub boom: JointProfileConfig,
+    /// Arm joint profile.
+    #[serde(default = "MotionProfileConfig::default_arm")]
+    pub arm: JointProfileConfig,
+    /// Attachment joint profile.
+    #[serde(default = "MotionProfileConfig::default_attachment")]
+    pub attachment: JointProfileConfig,
+}
+
+impl MotionProfileConfig {
+    fn default_frame() -> JointProfileConfig {
+        JointProfileConfig::new(7_000.0, 12_000.0, false)
+    }
+
+    fn default_boom() -> JointProfileConfig {
+        JointProfileConfig::new(15_000.0, 12_000.0, false)
+    }
+
+    fn default_arm() -> JointProfileConfig {
+        JointProfileConfig::new(15_000.0, 12_000.0, true)
+    }
+
+    fn default_attachment() -> JointProfileConfig {
+        JointProfileConfig::new(15_000.0, 12_000.0, false)
+    }
+
+    /// Build the motion profile.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if any of the joint profiles is invalid.
+    pub fn build(&self) -> std::io::Result<MotionProfile> {
+        Ok(MotionProfile {
+            frame: self.frame.build()?,
+            boom: self.boom.build()?,
+            arm: self.arm.build()?,
+            attachment: self.attachment.build()?,
+        })
+    }
+}
+
+impl Default for MotionProfileConfig {
+    fn default() -> Self {
+        Self {
+            frame: Self::default_frame(),
+            boom: Self::default_boom(),
+            arm: Self::default_arm(),
+            attachment: Self::default_attachment(),
+        }
+    }
+}
+
+/// Motion profile per joint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionProfile {
+    /// Frame joint profile.
+    pub frame: Linear,
+    /// Boom joint profile.
+    pub boom: Linear,
+    /// Arm joint profile.
+    pub arm: Linear,
+    /// Attachment joint profile.
+    pub attachment: Linear,
+}
+
+impl Default for MotionProfile {
+    fn default() -> Self {
+        MotionProfileConfig::default().build().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn motion_profile_config() {
+        let profile = MotionProfileConfig::default().build().unwrap();
+        assert_eq!(profile.frame, Linear::new(7_000.0, 12_000.0, false));
+        assert_eq!(profile.arm, Linear::new(15_000.0, 12_000.0, true));
+
+        let mut config = JointProfileConfig::new(15_000.0, 12_000.0, false);
+        config.deadband_rad = 0.01;
+        config.power_max = 20_000;
+
+        let mut state = ActuatorState::bind(Actuator::Boom, config.build().unwrap());
+        assert_eq!(state.update(Some(0.005)).unwrap().value, 0);
+        assert_eq!(state.update(Some(1.0)).unwrap().value, -20_000);
+        assert_eq!(state.update(Some(-1.0)).unwrap().value, 20_000);
+
+        config.power_max = i16::MAX as u16 + 1;
+        assert!(config.build().is_err());
+
+        let mut config = JointProfileConfig::new(-1.0, 12_000.0, false);
+        assert!(config.build().is_err());
+        config.gain = 1.0;
+        config.power_min = 1_000;
+        config.power_max = 500;
+        assert!(config.build().is_err());
+    }
+}
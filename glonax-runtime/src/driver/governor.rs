@@ -2,7 +2,7 @@ use std::time::{Duration, Instant};
 
 use crate::core::{Engine, EngineState};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Governor {
     /// Default engine speed.
     rpm_idle: u16,
@@ -10,6 +10,12 @@ pub struct Governor {
     rpm_max: u16,
     /// Engine state transition timeout.
     state_transition_timeout: Duration,
+    /// Maximum change of the RPM setpoint per second.
+    ramp_limit: Option<f32>,
+    /// Speed drop at full load in percent of the setpoint.
+    droop: f32,
+    /// Last RPM setpoint.
+    setpoint: Option<f32>,
 }
 
 impl Governor {
@@ -29,9 +35,32 @@ impl Governor {
             rpm_idle,
             rpm_max,
             state_transition_timeout,
+            ramp_limit: None,
+            droop: 0.0,
+            setpoint: None,
         }
     }
 
+    /// Limit the change of the RPM setpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `rpm_per_sec` - The maximum change of the RPM setpoint per second.
+    pub fn with_ramp_limit(mut self, rpm_per_sec: f32) -> Self {
+        self.ramp_limit = Some(rpm_per_sec.abs());
+        self
+    }
+
+    /// Lower the RPM setpoint proportionally to the engine load.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - The speed drop at full load in percent of the setpoint.
+    pub fn with_droop(mut self, percent: f32) -> Self {
+        self.droop = percent.clamp(0.0, 100.0);
+        self
+    }
+
     /// Reshape the torque.
     ///
     /// This method reshapes the torque based on the engine speed.
@@ -157,4 +186,151 @@ impl Governor {
             },
         }
     }
+
+    /// Get the next engine setpoint.
+    ///
+    /// The requested RPM is lowered by the droop under the actual engine load
+    /// and approached no faster than the ramp limit. Any request other than a
+    /// running engine, such as a shutdown, bypasses the ramp. Without a ramp
+    /// limit and droop the requested engine state is returned as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `actual` - The actual engine state.
+    /// * `requested` - The requested engine state, as returned by [`Governor::next_state`].
+    /// * `dt` - The time since the last setpoint.
+    ///
+    /// # Returns
+    ///
+    /// The next engine setpoint.
+    pub fn next_setpoint(&mut self, actual: &Engine, requested: &Engine, dt: Duration) -> Engine {
+        if requested.state != EngineState::Request {
+            self.setpoint = None;
+            return *requested;
+        }
+
+        let load = actual.actual_engine.min(100) as f32 / 100.0;
+        let target = requested.rpm as f32 * (1.0 - self.droop / 100.0 * load);
+        let target = target.clamp(self.rpm_idle as f32, self.rpm_max as f32);
+
+        // The ramp starts from the actual speed after a bypass.
+        let setpoint = match self.ramp_limit {
+            Some(ramp_limit) => {
+                let last = self.setpoint.unwrap_or(actual.rpm as f32);
+                let step = ramp_limit * dt.as_secs_f32();
+                last + (target - last).clamp(-step, step)
+            }
+            None => target,
+        };
+
+        self.setpoint = Some(setpoint);
+
+        Engine {
+            rpm: setpoint.round() as u16,
+            ..*requested
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: Duration = Duration::from_millis(10);
+
+    fn running(rpm: u16, load: u8) -> Engine {
+        Engine {
+            rpm,
+            actual_engine: load,
+            state: EngineState::Request,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn governor_default_setpoint() {
+        let mut governor = Governor::new(800, 2_100, Duration::from_secs(2));
+
+        let setpoint = governor.next_setpoint(&running(800, 80), &running(1_800, 0), DT);
+        assert_eq!(setpoint, running(1_800, 0));
+    }
+
+    #[test]
+    fn governor_ramp_limit() {
+        let mut governor = Governor::new(800, 2_100, Duration::from_secs(2)).with_ramp_limit(500.0);
+
+        // Engine that follows the setpoint without delay.
+        let mut actual = running(800, 0);
+        let mut steps = 0;
+
+        while actual.rpm < 1_800 {
+            let setpoint = governor.next_setpoint(&actual, &running(1_800, 0), DT);
+
+            assert!(setpoint.rpm - actual.rpm <= 5);
+            actual.rpm = setpoint.rpm;
+            steps += 1;
+        }
+
+        // 1000 RPM at 500 RPM per second.
+        assert_eq!(steps, 200);
+
+        let setpoint = governor.next_setpoint(&actual, &running(1_000, 0), DT);
+        assert_eq!(setpoint.rpm, 1_795);
+    }
+
+    #[test]
+    fn governor_droop() {
+        let mut governor = Governor::new(800, 2_100, Duration::from_secs(2)).with_droop(5.0);
+
+        let request = running(2_000, 0);
+
+        assert_eq!(
+            governor.next_setpoint(&running(2_000, 0), &request, DT).rpm,
+            2_000
+        );
+        assert_eq!(
+            governor
+                .next_setpoint(&running(2_000, 50), &request, DT)
+                .rpm,
+            1_950
+        );
+        assert_eq!(
+            governor
+                .next_setpoint(&running(1_950, 100), &request, DT)
+                .rpm,
+            1_900
+        );
+        assert_eq!(
+            governor
+                .next_setpoint(&running(1_900, 255), &request, DT)
+                .rpm,
+            1_900
+        );
+
+        // The droop never drops below idle.
+        let request = running(810, 0);
+        assert_eq!(
+            governor.next_setpoint(&running(810, 100), &request, DT).rpm,
+            800
+        );
+    }
+
+    #[test]
+    fn governor_shutdown_bypasses_ramp() {
+        let mut governor = Governor::new(800, 2_100, Duration::from_secs(2)).with_ramp_limit(100.0);
+
+        let actual = running(1_800, 20);
+        governor.next_setpoint(&actual, &running(1_800, 0), DT);
+
+        let command = Engine::shutdown();
+        let request = governor.next_state(&actual, &command, None);
+        assert_eq!(request.state, EngineState::Stopping);
+
+        let setpoint = governor.next_setpoint(&actual, &request, DT);
+        assert_eq!(setpoint, request);
+
+        // The ramp starts over from the actual speed.
+        let setpoint = governor.next_setpoint(&running(900, 0), &running(1_800, 0), DT);
+        assert_eq!(setpoint.rpm, 901);
+    }
 }
@@ -1,3 +1,43 @@
+use crate::core::{FixQuality, Gnss, GnssStatus, HDOP_UNKNOWN};
+
+/// GNSS fix type as reported in the GSA sentence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixType {
+    /// No fix.
+    NoFix = 1,
+    /// Two dimensional fix, without altitude.
+    Fix2D = 2,
+    /// Three dimensional fix.
+    Fix3D = 3,
+}
+
+impl TryFrom<u8> for FixType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FixType::NoFix),
+            2 => Ok(FixType::Fix2D),
+            3 => Ok(FixType::Fix3D),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Satellite in view as reported in the GSV sentence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SatelliteInfo {
+    /// Satellite PRN number.
+    pub prn: u16,
+    /// Elevation in degrees.
+    pub elevation: Option<u8>,
+    /// Azimuth in degrees.
+    pub azimuth: Option<u16>,
+    /// Signal to noise ratio in dB-Hz, `None` if the satellite is not tracked.
+    pub snr: Option<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct NMEAMessage {
     /// WGS 84 coordinates.
     pub coordinates: Option<(f32, f32)>,
@@ -11,180 +51,215 @@ pub struct NMEAMessage {
     pub heading: Option<f32>,
     /// Timestamp.
     pub timestamp: Option<f64>,
+    /// Fix quality.
+    pub fix_quality: Option<FixQuality>,
+    /// Fix type.
+    pub fix_type: Option<FixType>,
+    /// Number of satellites used in the fix.
+    pub satellites_used: Option<u8>,
+    /// Position dilution of precision.
+    pub pdop: Option<f32>,
+    /// Horizontal dilution of precision.
+    pub hdop: Option<f32>,
+    /// Vertical dilution of precision.
+    pub vdop: Option<f32>,
+    /// Number of satellites in view.
+    pub satellites_in_view: Option<u8>,
+    /// Satellites in view, at most four per sentence.
+    pub satellite_info: Vec<SatelliteInfo>,
+}
+
+/// Parse an optional field, an empty field is `None`.
+fn field<T: std::str::FromStr>(str: &str) -> Option<T> {
+    if str.is_empty() {
+        None
+    } else {
+        str.parse().ok()
+    }
+}
+
+/// Validate the sentence checksum.
+///
+/// Returns the sentence between the `$` and the `*` if the checksum matches.
+fn checksum(line: &str) -> Option<&str> {
+    let (sentence, checksum) = line.trim_end().strip_prefix('$')?.split_once('*')?;
+
+    let expected = u8::from_str_radix(checksum, 16).ok()?;
+    let actual = sentence.bytes().fold(0, |acc, byte| acc ^ byte);
+
+    (checksum.len() == 2 && actual == expected).then_some(sentence)
 }
 
 impl NMEAMessage {
-    fn dms_to_degree(str: &str, quadrant: char) -> f32 {
-        let offset = if str.find('.').unwrap() == 4 { 2 } else { 3 };
-
-        let day = str
-            .chars()
-            .take(offset)
-            .collect::<String>()
-            .parse::<f32>()
-            .unwrap();
-        let min = str
-            .replace('.', "")
-            .chars()
-            .skip(offset)
-            .collect::<String>()
-            .parse::<f32>()
-            .unwrap();
-        let min = (min / 60.0) / 100000.0;
-
-        let degrees = day + min;
-
-        if quadrant == 'S' || quadrant == 'W' {
-            degrees * -1.0
-        } else {
-            degrees
-        }
-    }
-
-    fn decode(line: String) -> Self {
-        let mut this = Self {
-            coordinates: None,
-            satellites: None,
-            altitude: None,
-            speed: None,
-            heading: None,
-            timestamp: None,
-        };
-
-        if line.starts_with("$GNGGA") {
-            let sentence: Vec<&str> = line.split(',').collect();
-
-            if sentence.is_empty() {
-                return this;
-            }
+    /// Convert a `(d)ddmm.mmmm` coordinate to decimal degrees.
+    fn dms_to_degree(str: &str, quadrant: &str) -> Option<f32> {
+        let value = str.parse::<f64>().ok()?;
 
-            // let hour = sentence[1]
-            //     .chars()
-            //     .take(2)
-            //     .collect::<String>()
-            //     .parse::<u64>()
-            //     .unwrap();
-            // let minute = sentence[1]
-            //     .chars()
-            //     .skip(2)
-            //     .take(2)
-            //     .collect::<String>()
-            //     .parse::<u64>()
-            //     .unwrap();
-            // let second = sentence[1]
-            //     .chars()
-            //     .skip(4)
-            //     .take(2)
-            //     .collect::<String>()
-            //     .parse::<u64>()
-            //     .unwrap();
-            // println!("Timestamp: {}:{}:{}", hour, minute, second);
-
-            if !sentence[6].is_empty() {
-                let fix_quality = sentence[6].parse::<u8>().unwrap();
-                if fix_quality == 1 || fix_quality == 2 {
-                    let lat_line = sentence[2];
-                    let lat_quadrant = sentence[3].to_uppercase().chars().next().unwrap();
-
-                    let long_line = sentence[4];
-                    let long_quadrant = sentence[5].to_uppercase().chars().next().unwrap();
-
-                    this.coordinates = Some((
-                        Self::dms_to_degree(lat_line, lat_quadrant),
-                        Self::dms_to_degree(long_line, long_quadrant),
-                    ));
-                }
-            }
+        let degrees = (value / 100.0).trunc();
+        let minutes = value - degrees * 100.0;
 
-            if !sentence[7].is_empty() {
-                this.satellites = Some(sentence[7].parse::<u8>().unwrap());
-            }
+        let degrees = (degrees + minutes / 60.0) as f32;
 
-            if !sentence[9].is_empty() && !sentence[10].is_empty() {
-                let altitude = sentence[9].parse::<f32>().unwrap();
-                let altitude_unit = sentence[10].to_uppercase().chars().next().unwrap();
+        match quadrant {
+            "N" | "E" => Some(degrees),
+            "S" | "W" => Some(-degrees),
+            _ => None,
+        }
+    }
 
-                if altitude_unit == 'M' {
-                    this.altitude = Some(altitude);
-                }
-            }
-        } else if line.starts_with("$GNGLL") {
-            let sentence: Vec<&str> = line.split(',').collect();
+    fn coordinates(sentence: &[&str]) -> Option<(f32, f32)> {
+        Some((
+            Self::dms_to_degree(sentence[0], sentence[1])?,
+            Self::dms_to_degree(sentence[2], sentence[3])?,
+        ))
+    }
 
-            if sentence.is_empty() {
-                return this;
-            }
+    fn decode_gga(&mut self, sentence: &[&str]) -> Option<()> {
+        if sentence.len() < 11 {
+            return None;
+        }
 
-            // let last = sentence.last().unwrap();
-            // if !last.starts_with("N*") {
-            //     return this;
-            // }
+        let fix_quality = FixQuality::try_from(field::<u8>(sentence[6])?).ok()?;
+        if fix_quality.is_valid() {
+            self.coordinates = Self::coordinates(&sentence[2..6]);
+        }
 
-            if sentence[6].is_empty() {
-                return this;
-            }
+        self.fix_quality = Some(fix_quality);
+        self.satellites = field(sentence[7]);
+        self.hdop = field(sentence[8]);
 
-            let validity = sentence[6].to_uppercase().chars().next().unwrap();
-            if validity == 'A' {
-                let lat_line = sentence[1];
-                let lat_quadrant = sentence[2].to_uppercase().chars().next().unwrap();
+        if sentence[10] == "M" {
+            self.altitude = field(sentence[9]);
+        }
 
-                let long_line = sentence[3];
-                let long_quadrant = sentence[4].to_uppercase().chars().next().unwrap();
+        Some(())
+    }
 
-                this.coordinates = Some((
-                    Self::dms_to_degree(lat_line, lat_quadrant),
-                    Self::dms_to_degree(long_line, long_quadrant),
-                ));
-            }
-        } else if line.starts_with("$GNRMC") {
-            let sentence: Vec<&str> = line.split(',').collect();
+    fn decode_gll(&mut self, sentence: &[&str]) -> Option<()> {
+        if sentence.len() < 7 {
+            return None;
+        }
 
-            if sentence.is_empty() {
-                return this;
-            }
+        if sentence[6] == "A" {
+            self.coordinates = Self::coordinates(&sentence[1..5]);
+        }
 
-            // let last = sentence.last().unwrap();
-            // if !last.starts_with("N*") {
-            //     return this;
-            // }
+        Some(())
+    }
 
-            if sentence[2].is_empty() {
-                return this;
-            }
+    fn decode_rmc(&mut self, sentence: &[&str]) -> Option<()> {
+        if sentence.len() < 9 {
+            return None;
+        }
 
-            let validity = sentence[2].to_uppercase().chars().next().unwrap();
-            if validity == 'A' {
-                let lat_line = sentence[3];
-                let lat_quadrant = sentence[4].to_uppercase().chars().next().unwrap();
+        if sentence[2] == "A" {
+            self.coordinates = Self::coordinates(&sentence[3..7]);
+        }
 
-                let long_line = sentence[5];
-                let long_quadrant = sentence[6].to_uppercase().chars().next().unwrap();
+        self.speed = field(sentence[7]);
+        self.heading = field(sentence[8]);
 
-                this.coordinates = Some((
-                    Self::dms_to_degree(lat_line, lat_quadrant),
-                    Self::dms_to_degree(long_line, long_quadrant),
-                ));
-            }
+        Some(())
+    }
 
-            if sentence.len() < 8 {
-                return this;
-            }
+    fn decode_gsa(&mut self, sentence: &[&str]) -> Option<()> {
+        if sentence.len() < 18 {
+            return None;
+        }
 
-            if !sentence[7].is_empty() {
-                this.speed = Some(sentence[7].parse::<f32>().unwrap());
-            }
+        self.fix_type = FixType::try_from(field::<u8>(sentence[2])?).ok();
+        self.satellites_used =
+            Some(sentence[3..15].iter().filter(|prn| !prn.is_empty()).count() as u8);
+        self.pdop = field(sentence[15]);
+        self.hdop = field(sentence[16]);
+        self.vdop = field(sentence[17]);
 
-            if sentence.len() < 9 {
-                return this;
-            }
+        Some(())
+    }
 
-            if !sentence[8].is_empty() {
-                this.heading = Some(sentence[8].parse::<f32>().unwrap());
-            }
+    fn decode_gsv(&mut self, sentence: &[&str]) -> Option<()> {
+        if sentence.len() < 4 {
+            return None;
         }
 
-        this
+        self.satellites_in_view = Some(field(sentence[3])?);
+        self.satellite_info = sentence[4..]
+            .chunks_exact(4)
+            .filter_map(|satellite| {
+                Some(SatelliteInfo {
+                    prn: field(satellite[0])?,
+                    elevation: field(satellite[1]),
+                    azimuth: field(satellite[2]),
+                    snr: field(satellite[3]).filter(|snr| *snr > 0),
+                })
+            })
+            .collect();
+
+        Some(())
+    }
+
+    /// Decode a sentence.
+    ///
+    /// Returns `None` if the checksum does not match, the sentence is not
+    /// supported or the sentence is truncated.
+    fn decode(line: &str) -> Option<Self> {
+        let sentence = checksum(line)?.split(',').collect::<Vec<_>>();
+
+        let mut this = Self::default();
+
+        // The talker identifier is ignored, the receiver reports as GP, GL, GA
+        // or GN depending on the constellations in use.
+        match sentence[0].get(2..)? {
+            "GGA" => this.decode_gga(&sentence)?,
+            "GLL" => this.decode_gll(&sentence)?,
+            "RMC" => this.decode_rmc(&sentence)?,
+            "GSA" => this.decode_gsa(&sentence)?,
+            "GSV" => this.decode_gsv(&sentence)?,
+            _ => return None,
+        }
+
+        Some(this)
+    }
+
+    /// Apply the message to the GNSS state.
+    ///
+    /// Only the fields present in the message are updated, so the GNSS state
+    /// is completed by the consecutive sentences of an epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `gnss` - The GNSS state.
+    pub fn apply(&self, gnss: &mut Gnss) {
+        if let Some(fix_quality) = self.fix_quality {
+            gnss.fix_quality = fix_quality;
+            gnss.status = match fix_quality {
+                FixQuality::Invalid | FixQuality::Manual | FixQuality::Simulation => {
+                    GnssStatus::DeviceNotFound
+                }
+                FixQuality::DeadReckoning => GnssStatus::DeadReckoning,
+                _ => GnssStatus::LocationFix,
+            };
+            gnss.hdop = self.hdop.unwrap_or(HDOP_UNKNOWN);
+        }
+        if let Some(coordinates) = self.coordinates {
+            gnss.location = coordinates;
+        }
+        if let Some(altitude) = self.altitude {
+            gnss.altitude = altitude;
+        }
+        if let Some(speed) = self.speed {
+            // Speed over ground in knots.
+            gnss.speed = speed * 0.514_444;
+        }
+        if let Some(heading) = self.heading {
+            gnss.heading = heading;
+        }
+        if let Some(satellites_used) = self.satellites_used.or(self.satellites) {
+            gnss.satellites_used = satellites_used;
+        }
+        if let Some(satellites_in_view) = self.satellites_in_view {
+            gnss.satellites = satellites_in_view;
+        }
     }
 }
 
@@ -209,19 +284,179 @@ impl std::fmt::Display for NMEAMessage {
             self.heading
                 .as_ref()
                 .map_or_else(|| "-".to_owned(), |f| format!("{:.1}°", f)),
-        )
+        )?;
+
+        if let Some(fix_quality) = self.fix_quality {
+            write!(f, "; fix: {}", fix_quality)?;
+        }
+        if let Some(hdop) = self.hdop {
+            write!(f, "; HDOP: {:.1}", hdop)?;
+        }
+
+        Ok(())
     }
 }
 
-#[derive(Default)]
-pub struct Nmea;
+pub struct Nmea {
+    /// Minimum fix quality of a location.
+    minimum_fix: FixQuality,
+}
+
+impl Default for Nmea {
+    fn default() -> Self {
+        Self {
+            minimum_fix: FixQuality::Gps,
+        }
+    }
+}
 
 impl Nmea {
+    /// Set the minimum fix quality of a location.
+    ///
+    /// Locations with a lesser fix quality are not accepted, see
+    /// [`Nmea::accept`].
+    pub fn with_minimum_fix(mut self, minimum_fix: FixQuality) -> Self {
+        self.minimum_fix = minimum_fix;
+        self
+    }
+
+    /// Decode a NMEA sentence.
+    ///
+    /// Sentences with an invalid checksum and truncated sentences are
+    /// rejected.
     pub fn decode(&self, line: String) -> Option<NMEAMessage> {
-        if line.starts_with("$GNGGA") || line.starts_with("$GNGLL") || line.starts_with("$GNRMC") {
-            Some(NMEAMessage::decode(line))
-        } else {
-            None
+        NMEAMessage::decode(&line)
+    }
+
+    /// Test if the location is accepted.
+    ///
+    /// A location is only accepted if it has a fix of at least the minimum
+    /// fix quality.
+    pub fn accept(&self, gnss: &Gnss) -> bool {
+        gnss.is_fix_quality(self.minimum_fix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(line: &str) -> Option<NMEAMessage> {
+        Nmea::default().decode(line.to_string())
+    }
+
+    #[test]
+    fn nmea_gga() {
+        let message =
+            decode("$GNGGA,001043.00,4404.14036,N,12118.85961,W,1,12,0.98,1113.0,M,-21.3,M,,*47")
+                .unwrap();
+
+        let (latitude, longitude) = message.coordinates.unwrap();
+        assert!((latitude - 44.069_006).abs() < 1e-5);
+        assert!((longitude + 121.314_33).abs() < 1e-5);
+        assert_eq!(message.fix_quality, Some(FixQuality::Gps));
+        assert_eq!(message.satellites, Some(12));
+        assert_eq!(message.hdop, Some(0.98));
+        assert_eq!(message.altitude, Some(1113.0));
+
+        let message =
+            decode("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47").unwrap();
+        let (latitude, longitude) = message.coordinates.unwrap();
+        assert!((latitude - 48.1173).abs() < 1e-5);
+        assert!((longitude - 11.516_667).abs() < 1e-5);
+
+        let message = decode(
+            "$GNGGA,103501.00,5204.46410,N,00520.67852,E,4,12,0.55,2.3,M,43.3,M,1.0,0000*65",
+        )
+        .unwrap();
+        assert_eq!(message.fix_quality, Some(FixQuality::RtkFixed));
+    }
+
+    #[test]
+    fn nmea_gsa_gsv() {
+        let message = decode("$GNGSA,A,3,80,71,73,79,69,,,,,,,,1.83,1.09,1.47*17").unwrap();
+        assert_eq!(message.fix_type, Some(FixType::Fix3D));
+        assert_eq!(message.satellites_used, Some(5));
+        assert_eq!(message.pdop, Some(1.83));
+        assert_eq!(message.hdop, Some(1.09));
+        assert_eq!(message.vdop, Some(1.47));
+
+        let message = decode("$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39").unwrap();
+        assert_eq!(message.satellites_used, Some(5));
+
+        let message =
+            decode("$GPGSV,2,1,08,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45*75").unwrap();
+        assert_eq!(message.satellites_in_view, Some(8));
+        assert_eq!(message.satellite_info.len(), 4);
+        assert_eq!(
+            message.satellite_info[1],
+            SatelliteInfo {
+                prn: 2,
+                elevation: Some(17),
+                azimuth: Some(308),
+                snr: Some(41),
+            }
+        );
+
+        // Last sentence with an untracked satellite and an empty slot.
+        let message =
+            decode("$GPGSV,3,3,11,22,42,067,42,24,14,311,43,27,05,244,00,,,,*4D").unwrap();
+        assert_eq!(message.satellites_in_view, Some(11));
+        assert_eq!(message.satellite_info.len(), 3);
+        assert_eq!(message.satellite_info[2].snr, None);
+    }
+
+    #[test]
+    fn nmea_invalid() {
+        // Checksum failure.
+        assert!(decode(
+            "$GNGGA,103501.00,5204.46410,N,00520.67852,E,4,12,0.55,2.3,M,43.3,M,1.0,0000*7E"
+        )
+        .is_none());
+        assert!(decode("$GNGSA,A,3,80,71,73,79,69,,,,,,,,1.83,1.09,1.48*17").is_none());
+        // Missing checksum.
+        assert!(decode("$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,").is_none());
+
+        // Truncated sentences with a valid checksum.
+        assert!(decode("$GPGGA,123519,4807.038,N*27").is_none());
+        assert!(decode("$GNGSA,A,3,80,71*20").is_none());
+        assert!(decode("$GPGSV,2,1*56").is_none());
+        // Truncated while receiving.
+        assert!(decode("$GPGSV,2,1,08,01,40,083,46,02,17,308").is_none());
+
+        assert!(decode("$GPZDA,201530.00,04,07,2002,00,00*60").is_none());
+        assert!(decode("").is_none());
+        assert!(decode("$*00").is_none());
+    }
+
+    #[test]
+    fn nmea_minimum_fix() {
+        let nmea = Nmea::default().with_minimum_fix(FixQuality::RtkFloat);
+
+        let mut gnss = Gnss::default();
+        for line in [
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47",
+            "$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39",
+            "$GPGSV,2,1,08,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45*75",
+        ] {
+            nmea.decode(line.to_string()).unwrap().apply(&mut gnss);
         }
+
+        assert!(gnss.is_fix());
+        assert_eq!(gnss.satellites, 8);
+        assert_eq!(gnss.satellites_used, 5);
+        assert_eq!(gnss.hdop, 0.9);
+        assert!(!nmea.accept(&gnss));
+        assert!(Nmea::default().accept(&gnss));
+
+        nmea.decode(
+            "$GNGGA,103501.00,5204.46410,N,00520.67852,E,4,12,0.55,2.3,M,43.3,M,1.0,0000*65"
+                .to_string(),
+        )
+        .unwrap()
+        .apply(&mut gnss);
+
+        assert_eq!(gnss.fix_quality, FixQuality::RtkFixed);
+        assert!(nmea.accept(&gnss));
     }
 }
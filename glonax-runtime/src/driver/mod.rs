@@ -1,18 +1,23 @@
-use nalgebra::{Rotation3, UnitVector3};
+use nalgebra::{Rotation3, UnitQuaternion, UnitVector3};
 
-pub use actuator::{ActuatorMotionEvent, ActuatorState};
+pub use actuator::{
+    ActuatorMotionEvent, ActuatorState, JointProfileConfig, MotionProfile, MotionProfileConfig,
+};
 pub use error::{DeviceError, ErrorKind, Result};
 pub use governor::Governor;
-pub use hardware::nmea::Nmea;
+pub use hardware::nmea::{FixType, NMEAMessage, Nmea, SatelliteInfo};
+pub use net::diagnostic::DiagnosticMessageService;
 pub use net::encoder::KueblerEncoder;
 pub use net::engine::{EngineManagementSystem, EngineMessage};
 pub use net::fuzzer::Fuzzer;
-pub use net::hydraulic::HydraulicControlUnit;
+pub use net::hydraulic::{HydraulicControlUnit, MotionRamp, MotionRampConfig};
 pub use net::inclino::KueblerInclinometer;
 pub use net::inspector::{J1939ApplicationInspector, J1939Message};
+pub use net::sim::SimulatorConfig;
 pub use net::vcu::VehicleControlUnit;
 pub use net::volvo_ems::VolvoD7E;
 pub use r#virtual::encoder::VirtualEncoder;
+pub use r#virtual::trace::FrameTrace;
 
 mod actuator;
 mod error;
@@ -71,9 +76,13 @@ impl EncoderConverter {
         Rotation3::from_axis_angle(&self.axis, position)
     }
 
-    // TODO: This is incomplete
     /// Convert rotation to encoder position.
     ///
+    /// This is the inverse of [`EncoderConverter::to_rotation`]. The twist of
+    /// the rotation around the encoder axis is taken as the encoder angle, any
+    /// rotation around other axes is ignored. The position is wrapped into a
+    /// single encoder revolution.
+    ///
     /// # Arguments
     ///
     /// * `rotation` - The rotation to convert.
@@ -82,8 +91,63 @@ impl EncoderConverter {
     ///
     /// The encoder position corresponding to the rotation.
     pub fn from_rotation(&self, rotation: Rotation3<f32>) -> u32 {
-        let position = (std::f32::consts::PI * 2.0) - rotation.angle();
+        let quaternion = UnitQuaternion::from_rotation_matrix(&rotation);
+        let angle = crate::math::twist_angle(&quaternion, &self.axis);
+
+        let position = angle * if self.invert { -1.0 } else { 1.0 } + self.offset;
+        let revolution = std::f32::consts::TAU * self.factor;
+
+        (position * self.factor).round().rem_euclid(revolution) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn encoder_rotation_roundtrip() {
+        for invert in [false, true] {
+            for (offset, axis) in [
+                (0.0, Vector3::z_axis()),
+                (60_f32.to_radians(), Vector3::y_axis()),
+                (0.0, Vector3::x_axis()),
+            ] {
+                let converter = EncoderConverter::new(1000.0, offset, invert, axis);
+
+                for position in [0, 1, 500, 1_047, 3_141, 3_142, 4_000, 6_000, 6_282] {
+                    let rotation = converter.to_rotation(position as f32);
+                    assert_eq!(
+                        converter.from_rotation(rotation),
+                        position,
+                        "invert {} offset {} axis {:?}",
+                        invert,
+                        offset,
+                        axis
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encoder_rotation_axis() {
+        let converter = EncoderConverter::new(1000.0, 0.0, false, Vector3::y_axis());
+
+        // Rotation around another axis does not move the encoder.
+        let rotation = Rotation3::from_axis_angle(&Vector3::z_axis(), 1.0);
+        assert_eq!(converter.from_rotation(rotation), 0);
+
+        let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), 1.0);
+        assert_eq!(converter.from_rotation(rotation), 1_000);
+
+        // A negative angle wraps around the revolution.
+        let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), -1.0);
+        assert_eq!(converter.from_rotation(rotation), 5_283);
 
-        ((position + self.offset) * self.factor) as u32
+        let converter = EncoderConverter::new(1000.0, 0.0, true, Vector3::y_axis());
+        assert_eq!(converter.from_rotation(rotation), 1_000);
     }
 }
@@ -0,0 +1,251 @@
+use j1939::{Frame, PGN};
+
+use crate::{
+    core::{DiagnosticCodes, DiagnosticTroubleCode, Object},
+    net::{LongMessage, Parsable},
+    runtime::{J1939Unit, J1939UnitError, NetDriverContext},
+};
+
+/// Global address, accepts any source.
+const ADDRESS_GLOBAL: u8 = 0xFF;
+
+/// Decode an active diagnostic trouble codes (DM1) message.
+///
+/// The message starts with the lamp status and the lamp flash status,
+/// followed by the 4 byte trouble codes. A unit without active codes sends
+/// a single code with SPN and FMI set to zero.
+///
+/// # Arguments
+///
+/// * `source` - The source address of the message.
+/// * `data` - The message data.
+pub fn decode_dm1(source: u8, data: &[u8]) -> Option<DiagnosticCodes> {
+    if data.len() < 2 + DiagnosticTroubleCode::SIZE {
+        return None;
+    }
+
+    let codes = data[2..]
+        .chunks_exact(DiagnosticTroubleCode::SIZE)
+        .filter(|chunk| chunk.iter().any(|byte| *byte != 0xFF))
+        .map(|chunk| DiagnosticTroubleCode::from_bytes(chunk.try_into().unwrap()))
+        .filter(|code| code.spn != 0 || code.fmi != 0)
+        .collect();
+
+    Some(DiagnosticCodes {
+        source,
+        lamps: data[0],
+        codes,
+    })
+}
+
+/// Active diagnostic trouble codes service.
+///
+/// Decodes the DM1 messages of a unit, both the single frame messages and
+/// the messages reassembled by the transport protocol. With the global
+/// destination address the messages of every unit are decoded.
+#[derive(Clone)]
+pub struct DiagnosticMessageService {
+    /// Network interface.
+    interface: String,
+    /// Destination address.
+    destination_address: u8,
+    /// Source address.
+    source_address: u8,
+}
+
+impl DiagnosticMessageService {
+    /// Construct a new diagnostic message service.
+    pub fn new(interface: &str, da: u8, sa: u8) -> Self {
+        Self {
+            interface: interface.to_string(),
+            destination_address: da,
+            source_address: sa,
+        }
+    }
+
+    /// Test if the service accepts a message from the source address.
+    fn is_source(&self, source: u8) -> bool {
+        self.destination_address == ADDRESS_GLOBAL || self.destination_address == source
+    }
+
+    /// Decode a DM1 frame.
+    pub fn decode_frame(&self, frame: &Frame) -> Option<DiagnosticCodes> {
+        if frame.id().pgn() != PGN::DiagnosticMessage1 {
+            return None;
+        }
+
+        let source = frame.id().source_address();
+        if !self.is_source(source) {
+            return None;
+        }
+
+        decode_dm1(source, frame.pdu())
+    }
+
+    /// Decode a reassembled DM1 message.
+    pub fn decode_message(&self, message: &LongMessage) -> Option<DiagnosticCodes> {
+        if message.pgn != PGN::DiagnosticMessage1 || !self.is_source(message.source_address) {
+            return None;
+        }
+
+        decode_dm1(message.source_address, &message.data)
+    }
+
+    /// Publish the decoded codes as signal.
+    fn publish(
+        &self,
+        ctx: &mut NetDriverContext,
+        codes: DiagnosticCodes,
+        rx_queue: &mut Vec<Object>,
+    ) {
+        if codes.codes.is_empty() {
+            trace!("[{}] {}: {}", self.interface, self.name(), codes);
+        } else {
+            warn!("[{}] {}: {}", self.interface, self.name(), codes);
+        }
+
+        ctx.rx_mark();
+
+        rx_queue.push(Object::DiagnosticCodes(codes));
+    }
+}
+
+impl Parsable<Vec<DiagnosticTroubleCode>> for DiagnosticMessageService {
+    fn parse(&self, frame: &Frame) -> Option<Vec<DiagnosticTroubleCode>> {
+        self.decode_frame(frame).map(|codes| codes.codes)
+    }
+
+    fn parse_message(&self, message: &LongMessage) -> Option<Vec<DiagnosticTroubleCode>> {
+        self.decode_message(message).map(|codes| codes.codes)
+    }
+}
+
+impl J1939Unit for DiagnosticMessageService {
+    fn vendor(&self) -> &'static str {
+        "j1939"
+    }
+
+    fn product(&self) -> &'static str {
+        "dm1"
+    }
+
+    fn destination(&self) -> u8 {
+        self.destination_address
+    }
+
+    fn source(&self) -> u8 {
+        self.source_address
+    }
+
+    fn try_recv(
+        &self,
+        ctx: &mut NetDriverContext,
+        frame: &Frame,
+        rx_queue: &mut Vec<Object>,
+    ) -> Result<(), J1939UnitError> {
+        if let Some(codes) = self.decode_frame(frame) {
+            self.publish(ctx, codes, rx_queue);
+        }
+
+        Ok(())
+    }
+
+    fn try_recv_message(
+        &self,
+        ctx: &mut NetDriverContext,
+        message: &LongMessage,
+        rx_queue: &mut Vec<Object>,
+    ) -> Result<(), J1939UnitError> {
+        if let Some(codes) = self.decode_message(message) {
+            self.publish(ctx, codes, rx_queue);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use j1939::{FrameBuilder, IdBuilder};
+
+    use super::*;
+
+    fn dm1_frame(sa: u8, pdu: &[u8]) -> Frame {
+        FrameBuilder::new(IdBuilder::from_pgn(PGN::DiagnosticMessage1).sa(sa).build())
+            .copy_from_slice(pdu)
+            .build()
+    }
+
+    #[test]
+    fn dm1_single_frame() {
+        let service = DiagnosticMessageService::new("vcan0", 0x00, 0x27);
+
+        // Amber warning lamp, SPN 110 FMI 0 once.
+        let frame = dm1_frame(0x00, &[0x04, 0xFF, 0x6E, 0x00, 0x00, 0x01, 0xFF, 0xFF]);
+        let codes = service.decode_frame(&frame).unwrap();
+        assert!(codes.is_amber_warning());
+        assert_eq!(codes.source, 0x00);
+        assert_eq!(
+            service.parse(&frame).unwrap(),
+            vec![DiagnosticTroubleCode {
+                spn: 110,
+                fmi: 0,
+                occurrences: 1,
+            }]
+        );
+
+        // No active codes.
+        let frame = dm1_frame(0x00, &[0x00, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF]);
+        assert_eq!(service.parse(&frame).unwrap(), vec![]);
+
+        // Other unit.
+        let frame = dm1_frame(0x4A, &[0x04, 0xFF, 0x6E, 0x00, 0x00, 0x01, 0xFF, 0xFF]);
+        assert!(service.parse(&frame).is_none());
+
+        let service = DiagnosticMessageService::new("vcan0", ADDRESS_GLOBAL, 0x27);
+        assert_eq!(service.decode_frame(&frame).unwrap().source, 0x4A);
+    }
+
+    #[test]
+    fn dm1_multi_packet() {
+        let service = DiagnosticMessageService::new("vcan0", 0x00, 0x27);
+
+        let mut data = vec![0x14, 0xFF];
+        for (spn, fmi) in [(100, 1), (520_192, 31), (0x4_0000, 4)] {
+            data.extend(
+                DiagnosticTroubleCode {
+                    spn,
+                    fmi,
+                    occurrences: 2,
+                }
+                .to_bytes(),
+            );
+        }
+
+        let message = LongMessage {
+            pgn: PGN::DiagnosticMessage1,
+            source_address: 0x00,
+            destination_address: None,
+            data,
+        };
+
+        let codes = service.parse_message(&message).unwrap();
+        assert_eq!(
+            codes
+                .iter()
+                .map(|code| code.to_string())
+                .collect::<Vec<_>>(),
+            vec!["100.1", "520192.31", "262144.4"]
+        );
+
+        let mut ctx = NetDriverContext::default();
+        let mut rx_queue = vec![];
+        service
+            .try_recv_message(&mut ctx, &message, &mut rx_queue)
+            .unwrap();
+        assert!(matches!(
+            &rx_queue[..],
+            [Object::DiagnosticCodes(codes)] if codes.is_red_stop() && codes.codes.len() == 3
+        ));
+    }
+}
@@ -1,15 +1,28 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
 use j1939::{protocol, Frame, FrameBuilder, IdBuilder, Name, PGN};
 
 use crate::{
-    core::{Object, ObjectMessage, Rotator},
+    core::{Actuator, Motion, Object, ObjectMessage, Rotator},
     driver::EncoderConverter,
     net::Parsable,
     runtime::{J1939Unit, J1939UnitError, NetDriverContext},
+    world::FrameConvention,
 };
 
 const _CONFIG_PGN: PGN = PGN::ProprietaryA;
 const ENCODER_PGN: PGN = PGN::ProprietaryB(65_450);
 
+/// Time the position may stay identical while the actuator is commanded to move.
+pub const FREEZE_WINDOW: Duration = Duration::from_millis(500);
+/// Actuator power below which the actuator may not move.
+const MOVING_POWER_MIN: i16 = 4_000;
+/// Smoothing factor of the update interval.
+const UPDATE_ALPHA: f32 = 0.1;
+
 // TODO: Add configuration message.
 
 // TODO: Should this be EncoderStatus?
@@ -120,7 +133,7 @@ impl ProcessDataMessage {
     }
 
     #[allow(dead_code)]
-    fn to_frame(&self) -> Vec<Frame> {
+    pub(crate) fn to_frame(&self) -> Vec<Frame> {
         let mut frame_builder = FrameBuilder::new(
             IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
                 .sa(self.source_address)
@@ -174,6 +187,96 @@ impl std::fmt::Display for ProcessDataMessage {
     }
 }
 
+/// Actuator that moves the joint of a segment.
+fn actuator_by_segment(segment: &str) -> Option<Actuator> {
+    match segment {
+        "frame" => Some(Actuator::Slew),
+        "boom" => Some(Actuator::Boom),
+        "arm" => Some(Actuator::Arm),
+        "attachment" => Some(Actuator::Attachment),
+        _ => None,
+    }
+}
+
+/// Encoder position monitor.
+///
+/// An encoder that hangs may keep sending frames with the last position. The
+/// monitor considers the encoder frozen when the position stays bit-identical
+/// for the freeze window while the actuator is commanded to move. The window
+/// restarts when the actuator starts moving. The monitor also keeps a moving
+/// average of the update interval.
+#[derive(Debug)]
+struct EncoderMonitor {
+    /// Freeze window.
+    window: Duration,
+    /// Actuator is commanded to move.
+    moving: bool,
+    /// Last raw position.
+    position: Option<u32>,
+    /// Moment since the position is unchanged.
+    unchanged_since: Option<Instant>,
+    /// Moment of the last update.
+    last_update: Option<Instant>,
+    /// Moving average of the update interval.
+    interval: Option<Duration>,
+}
+
+impl EncoderMonitor {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            moving: false,
+            position: None,
+            unchanged_since: None,
+            last_update: None,
+            interval: None,
+        }
+    }
+
+    /// Set whether the actuator is commanded to move.
+    fn set_moving(&mut self, moving: bool, now: Instant) {
+        if moving && !self.moving {
+            self.unchanged_since = Some(now);
+        }
+
+        self.moving = moving;
+    }
+
+    /// Record a raw position.
+    fn update(&mut self, position: u32, now: Instant) {
+        if let Some(last_update) = self.last_update.replace(now) {
+            let sample = now.saturating_duration_since(last_update);
+
+            self.interval = Some(match self.interval {
+                Some(interval) => {
+                    interval.mul_f32(1.0 - UPDATE_ALPHA) + sample.mul_f32(UPDATE_ALPHA)
+                }
+                None => sample,
+            });
+        }
+
+        if self.position != Some(position) {
+            self.position = Some(position);
+            self.unchanged_since = Some(now);
+        }
+    }
+
+    /// Test if the encoder is frozen.
+    fn is_frozen(&self, now: Instant) -> bool {
+        self.moving
+            && self
+                .unchanged_since
+                .is_some_and(|since| now.saturating_duration_since(since) >= self.window)
+    }
+
+    /// Update rate in hertz.
+    fn update_rate(&self) -> Option<f32> {
+        self.interval
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| 1.0 / interval.as_secs_f32())
+    }
+}
+
 #[derive(Clone)]
 pub struct KueblerEncoder {
     /// Network interface.
@@ -184,34 +287,42 @@ pub struct KueblerEncoder {
     source_address: u8,
     /// Converter.
     converter: EncoderConverter,
+    /// Actuator that moves the joint.
+    actuator: Option<Actuator>,
+    /// Position monitor.
+    monitor: Arc<Mutex<EncoderMonitor>>,
 }
 
 impl KueblerEncoder {
     /// Construct a new encoder service.
     pub fn new(interface: &str, da: u8, sa: u8) -> Self {
-        // TODO: Load converter from configuration.
-        let converter = if da == 0x6a {
-            EncoderConverter::new(1000.0, 0.0, true, nalgebra::Vector3::z_axis())
-        } else if da == 0x6b {
-            EncoderConverter::new(
-                1000.0,
-                60_f32.to_radians(),
-                true,
-                nalgebra::Vector3::y_axis(),
-            )
-        } else if da == 0x6c || da == 0x6d {
-            EncoderConverter::new(1000.0, 0.0, true, nalgebra::Vector3::y_axis())
-        } else {
-            panic!("Unknown encoder address: {:x}", da)
-        };
+        // TODO: Load convention from configuration.
+        let convention = FrameConvention::default();
+        let converter = convention
+            .encoder_converter(da, 1000.0)
+            .unwrap_or_else(|| panic!("Unknown encoder address: {:x}", da));
+        let actuator = convention
+            .joint_by_source(da)
+            .and_then(|(segment, _)| actuator_by_segment(segment));
 
         Self {
             interface: interface.to_string(),
             destination_address: da,
             source_address: sa,
             converter,
+            actuator,
+            monitor: Arc::new(Mutex::new(EncoderMonitor::new(FREEZE_WINDOW))),
         }
     }
+
+    /// Set the freeze window.
+    ///
+    /// The encoder is frozen when its position does not change for the
+    /// window while the actuator is commanded to move.
+    pub fn with_freeze_window(self, window: Duration) -> Self {
+        self.monitor.lock().unwrap().window = window;
+        self
+    }
 }
 
 impl Parsable<EncoderMessage> for KueblerEncoder {
@@ -263,6 +374,18 @@ impl J1939Unit for KueblerEncoder {
         self.source_address
     }
 
+    fn describe(&self, capability: &mut crate::core::Capability) {
+        let convention = FrameConvention::default();
+
+        if let Some((segment, _)) = convention.joint_by_source(self.destination_address) {
+            capability.add_sensor(self.destination_address, segment, "rad");
+        }
+    }
+
+    fn share(&self) -> Option<Box<dyn J1939Unit>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn setup(
         &self,
         _ctx: &mut NetDriverContext,
@@ -312,6 +435,12 @@ impl J1939Unit for KueblerEncoder {
 
                     ctx.set_rx_last_message(ObjectMessage::signal(Object::Rotator(rotator)));
 
+                    let mut monitor = self.monitor.lock().unwrap();
+                    monitor.update(process_data.position, ctx.now());
+                    if let Some(update_rate) = monitor.update_rate() {
+                        ctx.set_update_rate(update_rate);
+                    }
+
                     rx_queue.push(Object::Rotator(rotator));
 
                     return match process_data.state {
@@ -328,11 +457,142 @@ impl J1939Unit for KueblerEncoder {
 
         Ok(())
     }
+
+    fn trigger(
+        &self,
+        ctx: &mut NetDriverContext,
+        _tx_queue: &mut Vec<j1939::Frame>,
+        object: &Object,
+    ) -> Result<(), J1939UnitError> {
+        let (Object::Motion(motion), Some(actuator)) = (object, self.actuator) else {
+            return Ok(());
+        };
+
+        let moving = match motion {
+            Motion::StopAll | Motion::ResetAll => Some(false),
+            Motion::Stop(actuators) if actuators.contains(&actuator) => Some(false),
+            Motion::Change(changes) => changes
+                .iter()
+                .rev()
+                .find(|change| change.actuator == actuator)
+                .map(|change| change.value.unsigned_abs() >= MOVING_POWER_MIN as u16),
+            _ => None,
+        };
+
+        if let Some(moving) = moving {
+            self.monitor.lock().unwrap().set_moving(moving, ctx.now());
+        }
+
+        Ok(())
+    }
+
+    fn tick(
+        &self,
+        ctx: &mut NetDriverContext,
+        _tx_queue: &mut Vec<j1939::Frame>,
+    ) -> Result<(), J1939UnitError> {
+        if self.monitor.lock().unwrap().is_frozen(ctx.now()) {
+            return Err(J1939UnitError::EncoderFrozen);
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use rand::{Rng, SeedableRng};
+
     use super::*;
+    use crate::runtime::Clock;
+
+    /// Feed a position stream at 50 Hz while the actuator moves.
+    ///
+    /// Returns the tick results after every frame.
+    fn stream(positions: impl Iterator<Item = u32>) -> (NetDriverContext, Vec<bool>) {
+        let encoder = KueblerEncoder::new("vcan0", 0x6B, 0x27);
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        encoder
+            .trigger(
+                &mut ctx,
+                &mut vec![],
+                &Object::Motion(Motion::new(Actuator::Boom, 12_000_i16)),
+            )
+            .unwrap();
+
+        let mut frozen = vec![];
+        for position in positions {
+            let frame = &ProcessDataMessage::from_position(0x6B, position).to_frame()[0];
+            encoder.try_recv(&mut ctx, frame, &mut vec![]).unwrap();
+
+            frozen.push(matches!(
+                encoder.tick(&mut ctx, &mut vec![]),
+                Err(J1939UnitError::EncoderFrozen)
+            ));
+
+            clock.advance(Duration::from_millis(20));
+        }
+
+        (ctx, frozen)
+    }
+
+    #[test]
+    fn encoder_frozen() {
+        // The position moves for a second, then hangs at the last position.
+        let positions = (0..50)
+            .map(|i| 1_000 + i * 4)
+            .chain(std::iter::repeat_n(1_196, 50));
+        let (ctx, frozen) = stream(positions);
+
+        // The last change is at frame 49, the window of 25 frames ends at frame 74.
+        assert!(frozen[..74].iter().all(|frozen| !frozen));
+        assert!(frozen[74..].iter().all(|frozen| *frozen));
+        assert!((ctx.update_rate().unwrap() - 50.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn encoder_noisy() {
+        // Slow motion with sensor noise, the raw value repeats now and then.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(764);
+        let positions = (0..200).map(move |i| 1_000 + i / 4 + rng.gen_range(0..2));
+        let (_, frozen) = stream(positions);
+
+        assert!(frozen.iter().all(|frozen| !frozen));
+    }
+
+    #[test]
+    fn encoder_frozen_at_rest() {
+        let encoder = KueblerEncoder::new("vcan0", 0x6B, 0x27);
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        let frame = &ProcessDataMessage::from_position(0x6B, 1_500).to_frame()[0];
+        for _ in 0..50 {
+            encoder.try_recv(&mut ctx, frame, &mut vec![]).unwrap();
+            clock.advance(Duration::from_millis(20));
+        }
+
+        // The actuator does not move, an identical position is expected.
+        assert!(encoder.tick(&mut ctx, &mut vec![]).is_ok());
+
+        // Commanded to move, the window starts with the command.
+        let motion = Object::Motion(Motion::new(Actuator::Boom, -8_000_i16));
+        encoder.trigger(&mut ctx, &mut vec![], &motion).unwrap();
+        clock.advance(FREEZE_WINDOW / 2);
+        assert!(encoder.tick(&mut ctx, &mut vec![]).is_ok());
+        clock.advance(FREEZE_WINDOW / 2);
+        assert!(encoder.tick(&mut ctx, &mut vec![]).is_err());
+
+        let stop = Object::Motion(Motion::StopAll);
+        encoder.trigger(&mut ctx, &mut vec![], &stop).unwrap();
+        assert!(encoder.tick(&mut ctx, &mut vec![]).is_ok());
+    }
 
     #[test]
     fn value_normal() {
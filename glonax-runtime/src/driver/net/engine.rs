@@ -49,6 +49,12 @@ pub enum EngineMessage {
     TankInformation1(spn::TankInformation1Message),
     VehicleElectricalPower(spn::VehicleElectricalPowerMessage),
     InletExhaustConditions1(spn::InletExhaustConditions1Message),
+    /// Engine speed in rpm.
+    EngineSpeed(u16),
+    /// Driver demand engine torque in percent.
+    DriverDemand(u8),
+    /// Actual engine torque in percent.
+    ActualEngine(u8),
 }
 
 // TODO: Implement Engine trait
@@ -311,6 +317,25 @@ impl Parsable<EngineMessage> for EngineManagementSystem {
             _ => None,
         }
     }
+
+    /// Parse a frame into separate messages.
+    ///
+    /// The electronic engine controller 1 message is split into the engine
+    /// speed, driver demand and actual engine torque, omitting the signals that
+    /// are not available. Other frames are parsed as a single message.
+    fn parse_many(&self, frame: &Frame) -> Vec<EngineMessage> {
+        match self.parse(frame) {
+            Some(EngineMessage::EngineController1(controller)) => [
+                controller.rpm.map(EngineMessage::EngineSpeed),
+                controller.driver_demand.map(EngineMessage::DriverDemand),
+                controller.actual_engine.map(EngineMessage::ActualEngine),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            message => message.into_iter().collect(),
+        }
+    }
 }
 
 impl J1939Unit for EngineManagementSystem {
@@ -330,6 +355,10 @@ impl J1939Unit for EngineManagementSystem {
         self.source_address
     }
 
+    fn describe(&self, capability: &mut crate::core::Capability) {
+        capability.add_sensor(self.destination_address, "engine speed", "rpm");
+    }
+
     fn setup(
         &self,
         _ctx: &mut NetDriverContext,
@@ -438,3 +467,100 @@ impl J1939Unit for EngineManagementSystem {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eec1_frame(sa: u8, controller: spn::ElectronicEngineController1Message) -> Frame {
+        FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ElectronicEngineController1)
+                .sa(sa)
+                .build(),
+        )
+        .copy_from_slice(&controller.to_pdu())
+        .build()
+    }
+
+    #[test]
+    fn parse_many_engine_controller() {
+        let ems = EngineManagementSystem::new("vcan0", 0x00, 0x27);
+
+        let frame = eec1_frame(
+            0x00,
+            spn::ElectronicEngineController1Message {
+                engine_torque_mode: Some(spn::EngineTorqueMode::HighSpeedGovernor),
+                driver_demand: Some(93),
+                actual_engine: Some(4),
+                rpm: Some(1_500),
+                source_addr: Some(0x27),
+                starter_mode: Some(spn::EngineStarterMode::StartFinished),
+            },
+        );
+
+        let messages = ems.parse_many(&frame);
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], EngineMessage::EngineSpeed(1_500)));
+        assert!(matches!(messages[1], EngineMessage::DriverDemand(93)));
+        assert!(matches!(messages[2], EngineMessage::ActualEngine(4)));
+
+        // The single message parser still returns the complete message.
+        assert!(matches!(
+            ems.parse(&frame),
+            Some(EngineMessage::EngineController1(_))
+        ));
+
+        let mut rx_queue = vec![];
+        ems.try_recv(&mut NetDriverContext::default(), &frame, &mut rx_queue)
+            .unwrap();
+        assert!(matches!(
+            rx_queue[..],
+            [Object::Engine(crate::core::Engine {
+                rpm: 1_500,
+                driver_demand: 93,
+                actual_engine: 4,
+                ..
+            })]
+        ));
+
+        // Signals that are not available are omitted.
+        let frame = eec1_frame(
+            0x00,
+            spn::ElectronicEngineController1Message {
+                engine_torque_mode: None,
+                driver_demand: None,
+                actual_engine: Some(4),
+                rpm: None,
+                source_addr: None,
+                starter_mode: None,
+            },
+        );
+
+        let messages = ems.parse_many(&frame);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], EngineMessage::ActualEngine(4)));
+
+        // Frames of other engines are ignored.
+        let frame = eec1_frame(
+            0x01,
+            spn::ElectronicEngineController1Message {
+                engine_torque_mode: None,
+                driver_demand: Some(93),
+                actual_engine: Some(4),
+                rpm: Some(1_500),
+                source_addr: None,
+                starter_mode: None,
+            },
+        );
+        assert!(ems.parse_many(&frame).is_empty());
+
+        // Other messages are parsed as a single message.
+        let frame = FrameBuilder::new(IdBuilder::from_pgn(PGN::Shutdown).sa(0x00).build())
+            .copy_from_slice(&[0xFF; 8])
+            .build();
+
+        let messages = ems.parse_many(&frame);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], EngineMessage::Shutdown(_)));
+    }
+}
@@ -1,9 +1,13 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use j1939::{protocol, Frame, FrameBuilder, IdBuilder, Name, PDU_NOT_AVAILABLE, PGN};
 
 use crate::{
-    core::{Motion, Object, ObjectMessage},
+    core::{Actuator, Control, CylinderPressure, Motion, Object, ObjectMessage, SmoothingProfile},
     net::Parsable,
     runtime::{J1939Unit, J1939UnitError, NetDriverContext},
 };
@@ -11,8 +15,13 @@ use crate::{
 use super::vecraft::{VecraftConfigMessage, VecraftFactoryResetMessage, VecraftStatusMessage};
 
 const STATUS_PGN: u32 = 65_288;
+const PRESSURE_PGN: u32 = 65_289;
 const BANK_PGN_LIST: [PGN; 2] = [PGN::Other(40_960), PGN::Other(41_216)];
 const BANK_SLOTS: usize = 4;
+/// Longest interval credited to a single ramp update.
+///
+/// The ramp must not jump to the target after an idle period.
+const RAMP_STEP_MAX: Duration = Duration::from_millis(50);
 
 pub enum HydraulicMessage {
     Actuator(ActuatorMessage),
@@ -21,6 +30,7 @@ pub enum HydraulicMessage {
     SoftwareIdentification((u8, u8, u8)),
     AddressClaim(Name),
     Status(VecraftStatusMessage),
+    Pressure(CylinderPressure),
 }
 
 pub struct ActuatorMessage {
@@ -33,6 +43,15 @@ pub struct ActuatorMessage {
 }
 
 impl ActuatorMessage {
+    /// Construct a new actuator message.
+    pub fn new(destination_address: u8, source_address: u8, actuators: [Option<i16>; 8]) -> Self {
+        Self {
+            destination_address,
+            source_address,
+            actuators,
+        }
+    }
+
     pub fn from_frame(destination_address: u8, source_address: u8, frame: &Frame) -> Self {
         let mut actuators: [Option<i16>; 8] = [None; 8];
 
@@ -71,7 +90,7 @@ impl ActuatorMessage {
         }
     }
 
-    fn to_frame(&self) -> Vec<Frame> {
+    pub fn to_frame(&self) -> Vec<Frame> {
         let mut frames = vec![];
 
         for (idx, bank) in BANK_PGN_LIST.into_iter().enumerate() {
@@ -212,6 +231,229 @@ impl std::fmt::Display for MotionConfigMessage {
     }
 }
 
+/// Motion ramp configuration.
+///
+/// The rates are in power units per second.
+#[derive(Clone, Copy, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct MotionRampConfig {
+    /// Maximum increase in power per second.
+    pub rise: f32,
+    /// Maximum decrease in power per second.
+    pub fall: f32,
+    /// Power below which input is suppressed.
+    #[serde(default)]
+    pub deadband: i16,
+}
+
+impl MotionRampConfig {
+    /// Slew rate limit of a smoothing profile.
+    ///
+    /// A decrease in power is not limited. Returns `None` if the profile does
+    /// not limit the slew rate.
+    pub fn from_profile(profile: SmoothingProfile) -> Option<Self> {
+        profile.ramp_rate().map(|rise| Self {
+            rise,
+            fall: f32::INFINITY,
+            deadband: profile.deadband(),
+        })
+    }
+}
+
+/// Motion command slew rate limiter.
+///
+/// Tracks the requested and the last commanded power per actuator and limits
+/// the change of power to the configured rates. The ramp continues on every
+/// update until each actuator reaches its target, including the actuators
+/// that are not part of the latest command. A command to neutral, a reversal
+/// through neutral and any stop bypass the ramp and drop the power to zero
+/// at once. Without a configuration the motion commands pass through.
+#[derive(Debug, Default)]
+pub struct MotionRamp {
+    /// Ramp configuration, if limited.
+    config: Option<MotionRampConfig>,
+    /// Requested power per actuator.
+    target: HashMap<Actuator, i16>,
+    /// Last commanded power per actuator.
+    power: HashMap<Actuator, i16>,
+    /// Moment of the last update.
+    last_update: Option<Instant>,
+}
+
+impl MotionRamp {
+    /// Construct a new motion ramp.
+    pub fn new(config: MotionRampConfig) -> Self {
+        Self {
+            config: Some(config),
+            ..Default::default()
+        }
+    }
+
+    /// Replace the ramp configuration.
+    ///
+    /// The ramp continues from the power last commanded.
+    pub fn set_config(&mut self, config: Option<MotionRampConfig>) {
+        self.config = config;
+    }
+
+    /// Seconds since the last update, capped to the maximum ramp step.
+    fn elapsed(&mut self, now: Instant) -> f32 {
+        let elapsed = self
+            .last_update
+            .map(|last| now.saturating_duration_since(last).min(RAMP_STEP_MAX))
+            .unwrap_or_default()
+            .as_secs_f32();
+
+        self.last_update = Some(now);
+
+        elapsed
+    }
+
+    /// Actuators which have not reached their target, in actuator order.
+    fn unfinished(&self) -> Vec<Actuator> {
+        let mut actuators = self
+            .target
+            .iter()
+            .filter(|(actuator, target)| self.power.get(actuator).unwrap_or(&0) != *target)
+            .map(|(actuator, _)| *actuator)
+            .collect::<Vec<_>>();
+
+        actuators.sort_by_key(|actuator| actuator.id());
+
+        actuators
+    }
+
+    fn ramp(&mut self, actuator: Actuator, elapsed: f32) -> i16 {
+        let target = self.target.get(&actuator).copied().unwrap_or(0);
+
+        let Some(config) = self.config else {
+            self.power.insert(actuator, target);
+            return target;
+        };
+
+        // An unlimited rate is applied at once, even without elapsed time.
+        let step = |rate: f32| {
+            if rate.is_infinite() {
+                rate
+            } else {
+                rate * elapsed
+            }
+        };
+
+        let target = target as f32;
+        let last = self.power.get(&actuator).copied().unwrap_or(0) as f32;
+
+        let base = if last.signum() == target.signum() {
+            last
+        } else {
+            0.0
+        };
+
+        let power = if target == 0.0 {
+            0.0
+        } else if target.abs() > base.abs() {
+            base + target.signum() * (target.abs() - base.abs()).min(step(config.rise))
+        } else {
+            base - target.signum() * (base.abs() - target.abs()).min(step(config.fall))
+        } as i16;
+
+        self.power.insert(actuator, power);
+
+        power
+    }
+
+    /// Apply the ramp to a motion command.
+    ///
+    /// A change sets the target of the actuators in the command, input within
+    /// the deadband sets the target to neutral. The returned command holds
+    /// these actuators and all other actuators still ramping.
+    ///
+    /// # Arguments
+    ///
+    /// * `motion` - The motion command as requested.
+    /// * `now` - The moment of the update.
+    ///
+    /// # Returns
+    ///
+    /// The motion command to send to the actuators.
+    pub fn apply(&mut self, motion: &Motion, now: Instant) -> Motion {
+        match motion {
+            Motion::StopAll | Motion::ResetAll => {
+                self.reset();
+                self.last_update = Some(now);
+                motion.clone()
+            }
+            Motion::Stop(actuators) => {
+                for actuator in actuators {
+                    self.target.remove(actuator);
+                    self.power.remove(actuator);
+                }
+                motion.clone()
+            }
+            Motion::Change(changes) => {
+                let elapsed = self.elapsed(now);
+
+                let deadband = self.config.map_or(0, |config| config.deadband);
+
+                let mut actuators = Vec::with_capacity(changes.len());
+                for change in changes {
+                    let target = if change.value.unsigned_abs() < deadband.unsigned_abs() {
+                        0
+                    } else {
+                        change.value
+                    };
+
+                    self.target.insert(change.actuator, target);
+                    actuators.push(change.actuator);
+                }
+
+                for actuator in self.unfinished() {
+                    if !actuators.contains(&actuator) {
+                        actuators.push(actuator);
+                    }
+                }
+
+                Motion::from_iter(
+                    actuators
+                        .into_iter()
+                        .map(|actuator| (actuator, self.ramp(actuator, elapsed))),
+                )
+            }
+            Motion::ResumeAll | Motion::StraightDrive(_) => motion.clone(),
+        }
+    }
+
+    /// Advance the actuators still ramping towards their target.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The moment of the update.
+    ///
+    /// # Returns
+    ///
+    /// The motion command to send to the actuators, or `None` if all actuators
+    /// reached their target.
+    pub fn update(&mut self, now: Instant) -> Option<Motion> {
+        let elapsed = self.elapsed(now);
+
+        let actuators = self.unfinished();
+        if actuators.is_empty() {
+            return None;
+        }
+
+        Some(Motion::from_iter(
+            actuators
+                .into_iter()
+                .map(|actuator| (actuator, self.ramp(actuator, elapsed))),
+        ))
+    }
+
+    /// Drop the target and the tracked power of all actuators.
+    pub fn reset(&mut self) {
+        self.target.clear();
+        self.power.clear();
+    }
+}
+
 #[derive(Clone)]
 pub struct HydraulicControlUnit {
     /// Network interface.
@@ -220,6 +462,10 @@ pub struct HydraulicControlUnit {
     destination_address: u8,
     /// Source address.
     source_address: u8,
+    /// Motion command slew rate limiter.
+    ramp: Arc<Mutex<MotionRamp>>,
+    /// Slew rate limit without a smoothing profile.
+    ramp_config: Option<MotionRampConfig>,
 }
 
 impl HydraulicControlUnit {
@@ -229,7 +475,36 @@ impl HydraulicControlUnit {
             interface: interface.to_string(),
             destination_address: da,
             source_address: sa,
+            ramp: Arc::new(Mutex::new(MotionRamp::default())),
+            ramp_config: None,
+        }
+    }
+
+    /// Limit the slew rate of the motion commands.
+    ///
+    /// By default the motion commands are not limited. A selected smoothing
+    /// profile takes precedence over this limit.
+    pub fn with_ramp(mut self, config: MotionRampConfig) -> Self {
+        self.ramp = Arc::new(Mutex::new(MotionRamp::new(config)));
+        self.ramp_config = Some(config);
+        self
+    }
+
+    /// Construct the frames for a motion command limited by the motion ramp.
+    ///
+    /// Actuators still ramping are advanced, even if the command does not
+    /// change them.
+    fn smooth_motion_command(&self, motion: &Motion, now: Instant) -> Vec<Frame> {
+        let mut ramp = self.ramp.lock().unwrap();
+        let mut frames = self.motion_command(&ramp.apply(motion, now));
+
+        if !matches!(motion, Motion::Change(_)) {
+            if let Some(motion) = ramp.update(now) {
+                frames.extend(self.motion_command(&motion));
+            }
         }
+
+        frames
     }
 
     /// Locks the motion controller
@@ -298,7 +573,37 @@ impl HydraulicControlUnit {
 
     /// Drive both tracks
     pub fn drive_straight(&self, value: i16) -> Vec<Frame> {
-        self.actuator_command([(2, value), (3, value)].into_iter().collect())
+        self.actuator_command(
+            [
+                (Actuator::LimpRight.id(), value),
+                (Actuator::LimpLeft.id(), value),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    /// Construct the frames for a motion command
+    pub fn motion_command(&self, motion: &Motion) -> Vec<Frame> {
+        // TODO: StopAll, ResumeAll, ResetAll should be moved into Control::HydraulicXXX
+        match motion {
+            Motion::StopAll => vec![self.lock()],
+            Motion::ResumeAll => vec![self.unlock()],
+            Motion::ResetAll => vec![self.motion_reset()],
+            Motion::Stop(actuators) => self.actuator_command(
+                actuators
+                    .iter()
+                    .map(|actuator| (actuator.id(), Motion::POWER_NEUTRAL))
+                    .collect(),
+            ),
+            Motion::StraightDrive(value) => self.drive_straight(*value),
+            Motion::Change(changes) => self.actuator_command(
+                changes
+                    .iter()
+                    .map(|changeset| (changeset.actuator.id(), changeset.value))
+                    .collect(),
+            ),
+        }
     }
 
     /// Sends a command to the motion controller
@@ -317,6 +622,23 @@ impl HydraulicControlUnit {
 
         message.to_frame()
     }
+
+    /// Parse the boom cylinder pressure.
+    ///
+    /// The head and rod side pressures are little endian in units of
+    /// 0.1 bar. Returns `None` if either pressure is not available.
+    fn parse_pressure(pdu: &[u8]) -> Option<CylinderPressure> {
+        if pdu[0..2] == [PDU_NOT_AVAILABLE, PDU_NOT_AVAILABLE]
+            || pdu[2..4] == [PDU_NOT_AVAILABLE, PDU_NOT_AVAILABLE]
+        {
+            return None;
+        }
+
+        let head = u16::from_le_bytes([pdu[0], pdu[1]]) as f32 / 10.0;
+        let rod = u16::from_le_bytes([pdu[2], pdu[3]]) as f32 / 10.0;
+
+        Some(CylinderPressure::new(Actuator::Boom, head, rod))
+    }
 }
 
 impl Parsable<HydraulicMessage> for HydraulicControlUnit {
@@ -408,6 +730,13 @@ impl Parsable<HydraulicMessage> for HydraulicControlUnit {
                     frame,
                 )))
             }
+            PGN::ProprietaryB(PRESSURE_PGN) => {
+                if frame.id().source_address() != self.destination_address {
+                    return None;
+                }
+
+                Self::parse_pressure(frame.pdu()).map(HydraulicMessage::Pressure)
+            }
             PGN::Other(40_960) | PGN::Other(41_216) => Some(HydraulicMessage::Actuator(
                 ActuatorMessage::from_frame(self.destination_address, self.source_address, frame),
             )),
@@ -433,6 +762,25 @@ impl J1939Unit for HydraulicControlUnit {
         self.source_address
     }
 
+    fn describe(&self, capability: &mut crate::core::Capability) {
+        let actuators = [
+            (Actuator::Boom, "Boom"),
+            (Actuator::Arm, "Arm"),
+            (Actuator::Attachment, "Attachment"),
+            (Actuator::Slew, "Slew"),
+            (Actuator::LimpLeft, "Left track"),
+            (Actuator::LimpRight, "Right track"),
+        ];
+
+        for (actuator, name) in actuators {
+            capability.add_actuator(actuator, name, Motion::POWER_MIN, Motion::POWER_MAX);
+        }
+    }
+
+    fn share(&self) -> Option<Box<dyn J1939Unit>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn setup(
         &self,
         _ctx: &mut NetDriverContext,
@@ -508,6 +856,13 @@ impl J1939Unit for HydraulicControlUnit {
 
                     return Ok(());
                 }
+                HydraulicMessage::Pressure(pressure) => {
+                    ctx.rx_mark();
+
+                    rx_queue.push(Object::CylinderPressure(pressure));
+
+                    return Ok(());
+                }
                 HydraulicMessage::Status(status) => {
                     // let object =
                     //     Object::Control(crate::core::Control::HydraulicLock(status.locked));
@@ -559,6 +914,19 @@ impl J1939Unit for HydraulicControlUnit {
         //     }
         // }
 
+        if let Object::Control(Control::MotionProfile(profile)) = object {
+            info!(
+                "[{}] {}: Motion profile: {}",
+                self.interface,
+                self.name(),
+                profile
+            );
+
+            let config = MotionRampConfig::from_profile(*profile).or(self.ramp_config);
+
+            self.ramp.lock().unwrap().set_config(config);
+        }
+
         if let Object::Motion(motion) = object {
             trace!(
                 "[{}] {}: Hydraulic: {}",
@@ -569,37 +937,26 @@ impl J1939Unit for HydraulicControlUnit {
 
             ctx.set_tx_last_message(ObjectMessage::command(object.clone()));
 
-            // TODO: StopAll, ResumeAll, ResetAll should be moved into Control::HydraulicXXX
-            match motion {
-                Motion::StopAll => {
-                    tx_queue.push(self.lock());
-                }
-                Motion::ResumeAll => {
-                    tx_queue.push(self.unlock());
-                }
-                Motion::ResetAll => {
-                    tx_queue.push(self.motion_reset());
-                }
-                Motion::StraightDrive(value) => {
-                    let frames = &self.drive_straight(*value);
-                    tx_queue.extend_from_slice(frames);
-                }
-                Motion::Change(changes) => {
-                    let frames = &self.actuator_command(
-                        changes
-                            .iter()
-                            .map(|changeset| (changeset.actuator as u8, changeset.value))
-                            .collect(),
-                    );
-
-                    tx_queue.extend_from_slice(frames);
-                }
-            }
+            tx_queue.extend(self.smooth_motion_command(motion, ctx.now()));
         }
 
         Ok(())
     }
 
+    fn stop_motion(
+        &self,
+        ctx: &mut NetDriverContext,
+        tx_queue: &mut Vec<j1939::Frame>,
+    ) -> Result<(), J1939UnitError> {
+        ctx.set_tx_last_message(ObjectMessage::command(Object::Motion(Motion::StopAll)));
+
+        self.ramp.lock().unwrap().reset();
+
+        tx_queue.push(self.lock());
+
+        Ok(())
+    }
+
     fn tick(
         &self,
         ctx: &mut NetDriverContext,
@@ -624,31 +981,7 @@ impl J1939Unit for HydraulicControlUnit {
             motion_command
         );
 
-        match &motion_command {
-            Motion::StopAll => {
-                tx_queue.push(self.lock());
-            }
-            Motion::ResumeAll => {
-                tx_queue.push(self.unlock());
-            }
-            Motion::ResetAll => {
-                tx_queue.push(self.motion_reset());
-            }
-            Motion::StraightDrive(value) => {
-                let frames = &self.drive_straight(*value);
-                tx_queue.extend_from_slice(frames);
-            }
-            Motion::Change(changes) => {
-                let frames = &self.actuator_command(
-                    changes
-                        .iter()
-                        .map(|changeset| (changeset.actuator as u8, changeset.value))
-                        .collect(),
-                );
-
-                tx_queue.extend_from_slice(frames);
-            }
-        }
+        tx_queue.extend(self.smooth_motion_command(&motion_command, ctx.now()));
 
         Ok(())
     }
@@ -887,4 +1220,309 @@ mod tests {
         assert_eq!(config_b.ident_on, None);
         assert!(config_b.reboot);
     }
+
+    fn motion_slots(motion: Motion) -> [Option<i16>; 8] {
+        use crate::protocol::Packetize;
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27);
+
+        // Over the wire, as a client would send it.
+        let motion = Motion::try_from(motion.to_bytes()).unwrap();
+
+        let mut actuators = [None; 8];
+        for frame in hcu.motion_command(&motion) {
+            let message = ActuatorMessage::from_frame(0x4A, 0x27, &frame);
+
+            for (slot, value) in message.actuators.into_iter().enumerate() {
+                if value.is_some() {
+                    actuators[slot] = value;
+                }
+            }
+        }
+
+        actuators
+    }
+
+    #[test]
+    fn motion_change_actuator_ids() {
+        let actuators = motion_slots(Motion::from_iter([
+            (Actuator::Boom, 1_000),
+            (Actuator::Arm, -2_000),
+            (Actuator::Attachment, 3_000),
+            (Actuator::Slew, -4_000),
+        ]));
+
+        assert_eq!(
+            actuators,
+            [
+                Some(1_000),
+                Some(-4_000),
+                None,
+                None,
+                Some(-2_000),
+                Some(3_000),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn actuator_hcu_channel() {
+        // Documented wiring: actuator, bank and slot.
+        let wiring = [
+            (Actuator::Boom, 0, 0),
+            (Actuator::Slew, 0, 1),
+            (Actuator::LimpRight, 0, 2),
+            (Actuator::LimpLeft, 0, 3),
+            (Actuator::Arm, 1, 0),
+            (Actuator::Attachment, 1, 1),
+        ];
+
+        assert_eq!(wiring.len(), Actuator::ALL.len());
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27);
+
+        for (actuator, bank, slot) in wiring {
+            let frames = hcu.motion_command(&Motion::new(actuator, 1_234_i16));
+
+            assert_eq!(frames.len(), 1, "{:?}", actuator);
+            assert_eq!(frames[0].id().pgn(), BANK_PGN_LIST[bank], "{:?}", actuator);
+            assert_eq!(
+                frames[0].pdu()[slot * 2..slot * 2 + 2],
+                1_234_i16.to_le_bytes(),
+                "{:?}",
+                actuator
+            );
+        }
+    }
+
+    #[test]
+    fn motion_profile_ramp() {
+        use crate::runtime::Clock;
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27);
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        let boom = |tx_queue: &[Frame]| {
+            ActuatorMessage::from_frame(0x4A, 0x27, &tx_queue[0]).actuators[0].unwrap()
+        };
+
+        // Without a profile the command passes through.
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 20_000);
+
+        hcu.stop_motion(&mut ctx, &mut Vec::new()).unwrap();
+        hcu.tick(&mut ctx, &mut Vec::new()).unwrap();
+
+        let control = Object::Control(Control::MotionProfile(SmoothingProfile::Normal));
+        hcu.trigger(&mut ctx, &mut Vec::new(), &control).unwrap();
+
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 6_000);
+
+        // The step scales with the elapsed time, not with the number of updates.
+        clock.advance(Duration::from_millis(5));
+        let mut tx_queue = Vec::new();
+        hcu.tick(&mut ctx, &mut tx_queue).unwrap();
+        assert_eq!(boom(&tx_queue), 9_000);
+
+        let mut tx_queue = Vec::new();
+        hcu.tick(&mut ctx, &mut tx_queue).unwrap();
+        assert_eq!(boom(&tx_queue), 9_000);
+
+        let control = Object::Control(Control::MotionProfile(SmoothingProfile::Gentle));
+        hcu.trigger(&mut ctx, &mut Vec::new(), &control).unwrap();
+
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        hcu.tick(&mut ctx, &mut tx_queue).unwrap();
+        assert_eq!(boom(&tx_queue), 11_500);
+
+        // A decrease is immediate and input within the deadband is neutral.
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 3_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 3_000);
+
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 1_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 0);
+    }
+
+    #[test]
+    fn motion_ramp_step() {
+        use crate::runtime::Clock;
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27).with_ramp(MotionRampConfig {
+            rise: 100_000.0,
+            fall: 50_000.0,
+            deadband: 0,
+        });
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        let boom = |tx_queue: &[Frame]| {
+            ActuatorMessage::from_frame(0x4A, 0x27, &tx_queue[0]).actuators[0].unwrap()
+        };
+
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+
+        let mut ramp = vec![boom(&tx_queue)];
+        for _ in 0..25 {
+            clock.advance(Duration::from_millis(10));
+
+            let mut tx_queue = Vec::new();
+            hcu.tick(&mut ctx, &mut tx_queue).unwrap();
+            ramp.push(boom(&tx_queue));
+        }
+
+        // At most 1000 per 10 milliseconds until the target is reached.
+        assert!(ramp
+            .windows(2)
+            .all(|step| step[1] >= step[0] && step[1] - step[0] <= 1_000));
+        assert_eq!(ramp[1], 1_000);
+        assert_eq!(ramp[19], 19_000);
+        assert_eq!(*ramp.last().unwrap(), 20_000);
+
+        // A decrease is limited to the fall rate.
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 10_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 19_500);
+
+        // Neutral is immediate.
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 0_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(boom(&tx_queue), 0);
+    }
+
+    #[test]
+    fn motion_ramp_concurrent() {
+        use crate::runtime::Clock;
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27).with_ramp(MotionRampConfig {
+            rise: 100_000.0,
+            fall: 100_000.0,
+            deadband: 0,
+        });
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        // Last power sent per actuator slot.
+        let mut actuators = [None; 8];
+        let mut send = |tx_queue: &[Frame]| {
+            let mut sent = [None; 8];
+            for frame in tx_queue {
+                let message = ActuatorMessage::from_frame(0x4A, 0x27, frame);
+                for (slot, value) in message.actuators.into_iter().enumerate() {
+                    if value.is_some() {
+                        actuators[slot] = value;
+                        sent[slot] = value;
+                    }
+                }
+            }
+            (actuators, sent)
+        };
+
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        send(&tx_queue);
+
+        clock.advance(Duration::from_millis(10));
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::new(Actuator::Arm, -5_000_i16));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        send(&tx_queue);
+
+        let mut state = ([None; 8], [None; 8]);
+        for _ in 0..25 {
+            clock.advance(Duration::from_millis(10));
+
+            let mut tx_queue = Vec::new();
+            hcu.tick(&mut ctx, &mut tx_queue).unwrap();
+            state = send(&tx_queue);
+        }
+
+        // The boom keeps ramping after the arm command took over.
+        let (actuators, sent) = state;
+        assert_eq!(actuators[Actuator::Boom.id() as usize], Some(20_000));
+        assert_eq!(actuators[Actuator::Arm.id() as usize], Some(-5_000));
+
+        // Once all actuators settled, only the last command is repeated.
+        assert_eq!(sent[Actuator::Boom.id() as usize], None);
+        assert_eq!(sent[Actuator::Arm.id() as usize], Some(-5_000));
+    }
+
+    #[test]
+    fn motion_ramp_stop_bypass() {
+        use crate::runtime::Clock;
+
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27).with_ramp(MotionRampConfig {
+            rise: 100_000.0,
+            fall: 1_000.0,
+            deadband: 0,
+        });
+
+        let clock = Clock::mock();
+        let mut ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        let object = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        for _ in 0..10 {
+            hcu.trigger(&mut ctx, &mut Vec::new(), &object).unwrap();
+            clock.advance(Duration::from_millis(10));
+        }
+
+        let mut tx_queue = Vec::new();
+        let object = Object::Motion(Motion::Stop(vec![Actuator::Boom]));
+        hcu.trigger(&mut ctx, &mut tx_queue, &object).unwrap();
+        assert_eq!(
+            ActuatorMessage::from_frame(0x4A, 0x27, &tx_queue[0]).actuators[0],
+            Some(0)
+        );
+
+        let mut tx_queue = Vec::new();
+        hcu.trigger(&mut ctx, &mut tx_queue, &Object::Motion(Motion::StopAll))
+            .unwrap();
+        let pdu = |frames: &[Frame]| frames.iter().map(|f| f.pdu().to_vec()).collect::<Vec<_>>();
+        assert_eq!(pdu(&tx_queue), pdu(&hcu.motion_command(&Motion::StopAll)));
+    }
+
+    #[test]
+    fn motion_stop_actuator_ids() {
+        let actuators = motion_slots(Motion::Stop(vec![Actuator::Boom, Actuator::LimpLeft]));
+
+        assert_eq!(
+            actuators,
+            [Some(0), None, None, Some(0), None, None, None, None]
+        );
+
+        let actuators = motion_slots(Motion::StraightDrive(500));
+
+        assert_eq!(
+            actuators,
+            [None, None, Some(500), Some(500), None, None, None, None]
+        );
+    }
 }
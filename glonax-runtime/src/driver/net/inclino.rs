@@ -240,6 +240,10 @@ impl J1939Unit for KueblerInclinometer {
         self.source_address
     }
 
+    fn describe(&self, capability: &mut crate::core::Capability) {
+        capability.add_sensor(self.destination_address, "inclinometer", "rad");
+    }
+
     fn setup(
         &self,
         _ctx: &mut NetDriverContext,
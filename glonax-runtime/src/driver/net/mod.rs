@@ -5,6 +5,7 @@ use super::{
     EngineManagementSystem, HydraulicControlUnit, KueblerEncoder, KueblerInclinometer, VolvoD7E,
 };
 
+pub mod diagnostic;
 pub mod ecu;
 pub mod encoder;
 pub mod engine;
@@ -46,6 +47,9 @@ pub(crate) fn driver_factory(
         ("volvo", "d7e") => Some(Box::new(VolvoD7E::new(interface, da, sa))),
         ("kübler", "inclinometer") => Some(Box::new(KueblerInclinometer::new(interface, da, sa))),
         ("j1939", "ecm") => Some(Box::new(EngineManagementSystem::new(interface, da, sa))),
+        ("j1939", "dm1") => Some(Box::new(diagnostic::DiagnosticMessageService::new(
+            interface, da, sa,
+        ))),
         ("j1939", "ecu") => Some(Box::new(ecu::ElectronicControlUnit::new(interface, da, sa))),
         ("kübler", "encoder") => Some(Box::new(KueblerEncoder::new(interface, da, sa))),
         _ => None,
@@ -30,6 +30,9 @@ impl Probe {
 }
 
 impl Parsable<EcuAddress> for Probe {
+    // The address is not returned until the probe records the addresses it
+    // has seen, otherwise every frame is reported.
+    #[allow(unused_variables, unused_assignments)]
     fn parse(&self, frame: &Frame) -> Option<EcuAddress> {
         let mut address = EcuAddress {
             destination_address: None,
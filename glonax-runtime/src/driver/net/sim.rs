@@ -1,14 +1,90 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use nalgebra::Vector3;
 
 use crate::{
     core::{Actuator, Object, Rotator},
-    driver::{EncoderConverter, VirtualEncoder},
+    driver::{EncoderConverter, FrameTrace, VirtualEncoder},
     net::Parsable,
     runtime::{J1939Unit, J1939UnitError, NetDriverContext},
 };
 
+use super::{
+    hydraulic::ActuatorMessage,
+    vecraft::{State, VecraftStatusMessage},
+};
+
+/// Address of the simulated hydraulic control unit.
+const HCU_ADDRESS: u8 = 0x4A;
+/// Address of the runtime commanding the simulated hydraulic control unit.
+const HCU_COMMAND_ADDRESS: u8 = 0x27;
+
+/// Simulated time between two encoder updates.
+const SIMULATION_STEP: Duration = Duration::from_millis(10);
+/// Maximum number of simulation steps taken at once.
+const SIMULATION_STEP_MAX: u32 = 1_000;
+
+/// Simulator configuration.
+#[derive(Clone, Debug, serde_derive::Deserialize)]
+pub struct SimulatorConfig {
+    /// Factor by which simulated time runs faster than real time.
+    #[serde(default = "SimulatorConfig::default_time_scale")]
+    pub time_scale: f32,
+    /// Recorded trace replayed instead of the synthetic model.
+    #[serde(skip)]
+    pub replay: Option<Arc<FrameTrace>>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            time_scale: Self::default_time_scale(),
+            replay: None,
+        }
+    }
+}
+
+impl PartialEq for SimulatorConfig {
+    fn eq(&self, other: &Self) -> bool {
+        // Frames cannot be compared, so traces are equal if they are the same.
+        let replay_eq = match (&self.replay, &other.replay) {
+            (Some(replay), Some(other)) => Arc::ptr_eq(replay, other),
+            (replay, other) => replay.is_none() && other.is_none(),
+        };
+
+        self.time_scale == other.time_scale && replay_eq
+    }
+}
+
+impl SimulatorConfig {
+    fn default_time_scale() -> f32 {
+        1.0
+    }
+
+    /// Build the simulator.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the time scale is not positive.
+    pub fn build(&self, interface: &str, da: u8, sa: u8) -> std::io::Result<Simulator> {
+        if !(self.time_scale.is_finite() && self.time_scale > 0.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid simulation time scale: {}", self.time_scale),
+            ));
+        }
+
+        let mut simulator = Simulator::new(interface, da, sa).with_time_scale(self.time_scale);
+        simulator.replay = self.replay.clone();
+
+        Ok(simulator)
+    }
+}
+
 #[derive(Clone)]
 pub struct Simulator {
     /// Network interface.
@@ -23,6 +99,20 @@ pub struct Simulator {
     velocity_list: [RefCell<i16>; 4],
     /// List of encoder positions.
     position_list: [RefCell<u32>; 4],
+    /// Simulated hydraulic motion lock.
+    motion_locked: RefCell<bool>,
+    /// Simulated hydraulic power applied per channel.
+    power_list: RefCell<[Option<i16>; 8]>,
+    /// Simulation start.
+    start: Instant,
+    /// Factor by which simulated time runs faster than real time.
+    time_scale: f32,
+    /// Last simulation update and the fraction of a step not yet taken.
+    last_update: RefCell<(Instant, f32)>,
+    /// Recorded trace replayed instead of the synthetic model.
+    replay: Option<Arc<FrameTrace>>,
+    /// Index of the next frame to replay.
+    replay_index: RefCell<usize>,
 }
 
 impl Simulator {
@@ -58,8 +148,98 @@ impl Simulator {
             encoder_list,
             velocity_list: Default::default(),
             position_list: Default::default(),
+            motion_locked: RefCell::new(false),
+            power_list: RefCell::new([None; 8]),
+            start: Instant::now(),
+            time_scale: 1.0,
+            last_update: RefCell::new((Instant::now(), 0.0)),
+            replay: None,
+            replay_index: RefCell::new(0),
         }
     }
+
+    /// Replay a recorded trace.
+    ///
+    /// The recorded frames are sent instead of the output of the synthetic
+    /// model. Frames are sent at their recorded offset, scaled by the
+    /// simulation time scale.
+    pub fn with_replay(mut self, trace: FrameTrace) -> Self {
+        self.replay = Some(Arc::new(trace));
+        self
+    }
+
+    /// Set the simulation time scale.
+    ///
+    /// All simulated delays are compressed by the same factor, so the
+    /// relative ordering of the simulated updates is preserved.
+    pub fn with_time_scale(mut self, time_scale: f32) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    /// Simulated time since the simulation started.
+    fn uptime(&self) -> Duration {
+        self.start.elapsed().mul_f32(self.time_scale)
+    }
+
+    /// Number of simulation steps since the last update.
+    ///
+    /// Real time elapsed since the last update is scaled into simulated time.
+    /// The fraction of a step that remains is carried over to the next update.
+    fn steps(&self) -> u32 {
+        let mut last_update = self.last_update.borrow_mut();
+
+        let now = Instant::now();
+        let steps = (now - last_update.0).as_secs_f32() * self.time_scale
+            / SIMULATION_STEP.as_secs_f32()
+            + last_update.1;
+
+        *last_update = (now, steps.fract());
+
+        (steps as u32).min(SIMULATION_STEP_MAX)
+    }
+
+    /// Recorded frames that are due since the last replay.
+    fn replay_frames(&self, trace: &FrameTrace) -> Vec<j1939::Frame> {
+        let uptime = self.uptime();
+        let mut replay_index = self.replay_index.borrow_mut();
+
+        let mut frames = vec![];
+        while let Some((offset, frame)) = trace.get(*replay_index) {
+            if *offset > uptime {
+                break;
+            }
+
+            frames.push(*frame);
+            *replay_index += 1;
+        }
+
+        if *replay_index == trace.len() && !frames.is_empty() {
+            info!("Replay of {} frames finished", trace.len());
+        }
+
+        frames
+    }
+
+    /// Simulated hydraulic control unit status and applied power.
+    ///
+    /// The frames are sent on behalf of the hydraulic control unit, so that
+    /// the runtime reads back the state of the simulated actuators.
+    fn hcu_status(&self) -> Vec<j1939::Frame> {
+        let status = VecraftStatusMessage {
+            state: State::Nominal,
+            locked: *self.motion_locked.borrow(),
+            inputs: 0,
+            uptime: self.uptime().as_secs() as u32,
+        };
+
+        // The applied power is broadcast, any unit on the network may read it back.
+        let mut frames = vec![status.to_frame(HCU_COMMAND_ADDRESS, HCU_ADDRESS)];
+        frames
+            .extend(ActuatorMessage::new(0xFF, HCU_ADDRESS, *self.power_list.borrow()).to_frame());
+
+        frames
+    }
 }
 
 impl J1939Unit for Simulator {
@@ -85,12 +265,34 @@ impl J1939Unit for Simulator {
         frame: &j1939::Frame,
         rx_queue: &mut Vec<Object>,
     ) -> Result<(), J1939UnitError> {
-        let hcu0 = crate::driver::HydraulicControlUnit::new(&self.interface, 0x4a, 0x27);
+        if self.replay.is_some() {
+            return Ok(());
+        }
 
-        let message = hcu0.parse(frame).unwrap();
+        let hcu0 = crate::driver::HydraulicControlUnit::new(
+            &self.interface,
+            HCU_ADDRESS,
+            HCU_COMMAND_ADDRESS,
+        );
+
+        // Ignore the read back of the simulated hydraulic control unit.
+        let message = if frame.id().source_address() != HCU_ADDRESS {
+            hcu0.parse(frame)
+        } else {
+            None
+        };
 
         match message {
-            crate::driver::net::hydraulic::HydraulicMessage::Actuator(actuator) => {
+            Some(crate::driver::net::hydraulic::HydraulicMessage::Actuator(actuator))
+                if !*self.motion_locked.borrow() =>
+            {
+                let mut power_list = self.power_list.borrow_mut();
+                for (power, value) in power_list.iter_mut().zip(actuator.actuators) {
+                    if value.is_some() {
+                        *power = value;
+                    }
+                }
+
                 if let Some(value) = actuator.actuators[0] {
                     *self.velocity_list[1].borrow_mut() = value;
                 }
@@ -107,28 +309,38 @@ impl J1939Unit for Simulator {
                     *self.velocity_list[3].borrow_mut() = value;
                 }
             }
-            crate::driver::net::hydraulic::HydraulicMessage::MotionConfig(motion) => {
+            Some(crate::driver::net::hydraulic::HydraulicMessage::MotionConfig(motion)) => {
+                if let Some(locked) = motion.locked {
+                    *self.motion_locked.borrow_mut() = locked;
+                }
+
                 if let Some(true) = motion.locked {
                     for v in self.velocity_list.iter() {
                         *v.borrow_mut() = 0;
                     }
+                    *self.power_list.borrow_mut() = [None; 8];
                 }
 
                 if let Some(true) = motion.reset {
                     for v in self.velocity_list.iter() {
                         *v.borrow_mut() = 0;
                     }
+                    *self.power_list.borrow_mut() = [None; 8];
                 }
             }
             _ => {}
         }
 
+        let steps = self.steps();
+
         // TOOD: Run this on every tick
         for (idx, encoder) in self.encoder_list.iter().enumerate() {
             let current_velocity = self.velocity_list[idx].borrow();
             let mut current_position = self.position_list[idx].borrow_mut();
 
-            let new_position = encoder.2.position(*current_position, *current_velocity);
+            let new_position = (0..steps).fold(*current_position, |position, _| {
+                encoder.2.position(position, *current_velocity)
+            });
 
             *current_position = new_position;
 
@@ -150,4 +362,188 @@ impl J1939Unit for Simulator {
 
         Ok(())
     }
+
+    fn tick(
+        &self,
+        _ctx: &mut NetDriverContext,
+        tx_queue: &mut Vec<j1939::Frame>,
+    ) -> Result<(), J1939UnitError> {
+        match &self.replay {
+            Some(trace) => tx_queue.extend(self.replay_frames(trace)),
+            None => tx_queue.extend(self.hcu_status()),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::Motion,
+        driver::{net::hydraulic::HydraulicMessage, HydraulicControlUnit},
+    };
+
+    fn hcu_state(frames: &[j1939::Frame]) -> (bool, [Option<i16>; 8]) {
+        let hcu = HydraulicControlUnit::new("vcan0", HCU_ADDRESS, HCU_COMMAND_ADDRESS);
+
+        let mut locked = None;
+        let mut actuators = [None; 8];
+
+        for frame in frames {
+            match hcu.parse(frame) {
+                Some(HydraulicMessage::Status(status)) => locked = Some(status.locked),
+                Some(HydraulicMessage::Actuator(message)) => {
+                    for (slot, value) in message.actuators.into_iter().enumerate() {
+                        if value.is_some() {
+                            actuators[slot] = value;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (locked.expect("no status frame"), actuators)
+    }
+
+    #[test]
+    fn simulated_hcu_status() {
+        let hcu = HydraulicControlUnit::new("vcan0", HCU_ADDRESS, HCU_COMMAND_ADDRESS);
+        let simulator = Simulator::new("vcan0", 0x4A, 0x27);
+
+        let mut ctx = NetDriverContext::default();
+        let mut rx_queue = Vec::new();
+        let mut tx_queue = Vec::new();
+
+        let command = Motion::from_iter([(Actuator::Boom, 12_000), (Actuator::Arm, -8_000)]);
+        for frame in hcu.motion_command(&command) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+        }
+
+        simulator.tick(&mut ctx, &mut tx_queue).unwrap();
+
+        let (locked, actuators) = hcu_state(&tx_queue);
+        assert!(!locked);
+        assert_eq!(actuators[Actuator::Boom.id() as usize], Some(12_000));
+        assert_eq!(actuators[Actuator::Arm.id() as usize], Some(-8_000));
+        assert_eq!(actuators[Actuator::Slew.id() as usize], None);
+
+        // Read back of the simulator itself is not a command.
+        for frame in tx_queue.drain(..) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+        }
+
+        for frame in hcu.motion_command(&Motion::StopAll) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+        }
+        for frame in hcu.motion_command(&command) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+        }
+
+        simulator.tick(&mut ctx, &mut tx_queue).unwrap();
+
+        let (locked, actuators) = hcu_state(&tx_queue);
+        assert!(locked);
+        assert_eq!(actuators, [None; 8]);
+    }
+
+    #[test]
+    fn simulated_time_scale() {
+        let hcu = HydraulicControlUnit::new("vcan0", HCU_ADDRESS, HCU_COMMAND_ADDRESS);
+        let simulator = Simulator::new("vcan0", 0x4A, 0x27);
+        let simulator_fast = SimulatorConfig {
+            time_scale: 10.0,
+            ..Default::default()
+        }
+        .build("vcan0", 0x4A, 0x27)
+        .unwrap();
+
+        let mut ctx = NetDriverContext::default();
+        let mut rx_queue = Vec::new();
+
+        let command = Motion::from_iter([(Actuator::Boom, 12_000)]);
+        for frame in hcu.motion_command(&command) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+            simulator_fast
+                .try_recv(&mut ctx, &frame, &mut rx_queue)
+                .unwrap();
+        }
+
+        let start = (
+            *simulator.position_list[1].borrow(),
+            *simulator_fast.position_list[1].borrow(),
+        );
+
+        std::thread::sleep(Duration::from_millis(100));
+
+        for frame in hcu.motion_command(&command) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+            simulator_fast
+                .try_recv(&mut ctx, &frame, &mut rx_queue)
+                .unwrap();
+        }
+
+        let travel = *simulator.position_list[1].borrow() - start.0;
+        let travel_fast = *simulator_fast.position_list[1].borrow() - start.1;
+
+        assert!(travel > 0);
+        assert!(travel_fast >= travel * 5);
+    }
+
+    #[test]
+    fn simulator_config_time_scale() {
+        for time_scale in [0.0, -1.0, f32::NAN] {
+            let config = SimulatorConfig {
+                time_scale,
+                ..Default::default()
+            };
+            assert!(config.build("vcan0", 0x4A, 0x27).is_err());
+        }
+
+        let config: SimulatorConfig = toml::from_str("").unwrap();
+        assert_eq!(config, SimulatorConfig::default());
+    }
+
+    #[test]
+    fn simulated_replay() {
+        let trace = FrameTrace::parse(
+            "(1718010000.000000) vcan0 18FF6A6B#0102030405060708\n\
+             (1718010000.000000) vcan0 0CFF004A#FF\n\
+             (1718010010.000000) vcan0 18FF6C6B#0807060504030201",
+        )
+        .unwrap();
+
+        let simulator = SimulatorConfig {
+            time_scale: 100.0,
+            replay: Some(Arc::new(trace.clone())),
+        }
+        .build("vcan0", 0x4A, 0x27)
+        .unwrap();
+
+        let mut ctx = NetDriverContext::default();
+        let mut rx_queue = Vec::new();
+        let mut tx_queue = Vec::new();
+
+        let hcu = HydraulicControlUnit::new("vcan0", HCU_ADDRESS, HCU_COMMAND_ADDRESS);
+        for frame in hcu.motion_command(&Motion::from_iter([(Actuator::Boom, 12_000)])) {
+            simulator.try_recv(&mut ctx, &frame, &mut rx_queue).unwrap();
+        }
+        assert!(rx_queue.is_empty());
+
+        simulator.tick(&mut ctx, &mut tx_queue).unwrap();
+        assert_eq!(tx_queue.len(), 2);
+
+        std::thread::sleep(Duration::from_millis(150));
+
+        simulator.tick(&mut ctx, &mut tx_queue).unwrap();
+        simulator.tick(&mut ctx, &mut tx_queue).unwrap();
+        assert_eq!(tx_queue.len(), 3);
+
+        for (frame, (_, recorded)) in tx_queue.iter().zip(trace.iter()) {
+            assert_eq!(frame.id(), recorded.id());
+            assert_eq!(frame.pdu(), recorded.pdu());
+        }
+    }
 }
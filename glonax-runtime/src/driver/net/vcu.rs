@@ -1,4 +1,4 @@
-use j1939::{protocol, Frame, Name, PGN};
+use j1939::{protocol, Frame, Name, PDU_NOT_AVAILABLE, PGN};
 
 use crate::{
     core::Object,
@@ -157,6 +157,14 @@ impl J1939Unit for VehicleControlUnit {
         Ok(())
     }
 
+    fn is_input_asserted(&self, frame: &Frame, input: u8) -> bool {
+        input < 8
+            && frame.id().pgn() == PGN::ProprietaryB(STATUS_PGN)
+            && frame.id().source_address() == self.destination_address
+            && frame.pdu()[3] != PDU_NOT_AVAILABLE
+            && frame.pdu()[3] & (1 << input) != 0
+    }
+
     fn try_recv(
         &self,
         ctx: &mut NetDriverContext,
@@ -151,6 +151,8 @@ pub struct VecraftStatusMessage {
     pub state: State,
     /// Motion lock.
     pub locked: bool,
+    /// Digital inputs, one bit per input.
+    pub inputs: u8,
     /// Uptime in seconds.
     pub uptime: u32,
 }
@@ -160,14 +162,18 @@ impl VecraftStatusMessage {
         Self {
             state: State::from(frame.pdu()[0]),
             locked: frame.pdu()[2] != PDU_NOT_AVAILABLE && frame.pdu()[2] == 0x1,
+            inputs: if frame.pdu()[3] != PDU_NOT_AVAILABLE {
+                frame.pdu()[3]
+            } else {
+                0
+            },
             uptime: u32::from_le_bytes(frame.pdu()[4..8].try_into().unwrap()),
         }
     }
 
-    #[allow(dead_code)]
     pub(crate) fn to_frame(&self, destination_address: u8, source_address: u8) -> Frame {
         FrameBuilder::new(
-            IdBuilder::from_pgn(PGN::ProprietarilyConfigurableMessage2)
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_288))
                 .da(destination_address)
                 .sa(source_address)
                 .build(),
@@ -176,7 +182,7 @@ impl VecraftStatusMessage {
             self.state.to_byte(),
             PDU_NOT_AVAILABLE,
             if self.locked { 0x1 } else { 0x0 },
-            PDU_NOT_AVAILABLE,
+            self.inputs,
             self.uptime.to_le_bytes()[0],
             self.uptime.to_le_bytes()[1],
             self.uptime.to_le_bytes()[2],
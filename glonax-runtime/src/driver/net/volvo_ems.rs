@@ -1,4 +1,7 @@
-use std::time::Duration;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use j1939::{Frame, FrameBuilder, IdBuilder, PGN};
 
@@ -35,7 +38,7 @@ pub struct VolvoD7E {
     /// Engine management system.
     ems: EngineManagementSystem,
     /// Governor.
-    governor: Governor,
+    governor: Arc<Mutex<Governor>>,
 }
 
 impl VolvoD7E {
@@ -46,10 +49,20 @@ impl VolvoD7E {
             destination_address: da,
             source_address: sa,
             ems: EngineManagementSystem::new(interface, da, sa),
-            governor: Governor::new(800, 2_100, Duration::from_millis(2_000)),
+            governor: Arc::new(Mutex::new(Governor::new(
+                800,
+                2_100,
+                Duration::from_millis(2_000),
+            ))),
         }
     }
 
+    /// Replace the engine governor.
+    pub fn with_governor(mut self, governor: Governor) -> Self {
+        self.governor = Arc::new(Mutex::new(governor));
+        self
+    }
+
     /// Request speed control
     pub fn speed_control(&self, state: VolvoEngineState, rpm: u16) -> Frame {
         FrameBuilder::new(
@@ -90,6 +103,10 @@ impl Parsable<EngineMessage> for VolvoD7E {
     fn parse(&self, frame: &Frame) -> Option<EngineMessage> {
         self.ems.parse(frame)
     }
+
+    fn parse_many(&self, frame: &Frame) -> Vec<EngineMessage> {
+        self.ems.parse_many(frame)
+    }
 }
 
 impl J1939Unit for VolvoD7E {
@@ -109,6 +126,14 @@ impl J1939Unit for VolvoD7E {
         self.source_address
     }
 
+    fn describe(&self, capability: &mut crate::core::Capability) {
+        capability.add_sensor(self.destination_address, "engine speed", "rpm");
+    }
+
+    fn share(&self) -> Option<Box<dyn J1939Unit>> {
+        Some(Box::new(self.clone()))
+    }
+
     fn try_recv(
         &self,
         ctx: &mut NetDriverContext,
@@ -147,9 +172,13 @@ impl J1939Unit for VolvoD7E {
                 }
             };
 
-            let governor_engine = self
-                .governor
-                .next_state(&engine_signal, &engine_command, None);
+            // The setpoint is held, the ramp only advances on tick.
+            let governor_engine = {
+                let mut governor = self.governor.lock().unwrap();
+
+                let request = governor.next_state(&engine_signal, &engine_command, None);
+                governor.next_setpoint(&engine_signal, &request, Duration::ZERO)
+            };
 
             trace!(
                 "[{}] {}: Engine: {}",
@@ -206,9 +235,12 @@ impl J1939Unit for VolvoD7E {
             }
         };
 
-        let governor_engine =
-            self.governor
-                .next_state(&engine_signal, &engine_command.0, engine_command.1);
+        let governor_engine = {
+            let mut governor = self.governor.lock().unwrap();
+
+            let request = governor.next_state(&engine_signal, &engine_command.0, engine_command.1);
+            governor.next_setpoint(&engine_signal, &request, ctx.tick_mark())
+        };
 
         trace!(
             "[{}] {}: Engine: {}",
@@ -0,0 +1,176 @@
+use std::{io, path::Path, time::Duration};
+
+use j1939::{Frame, FrameBuilder, Id};
+
+/// Recorded network frame trace.
+///
+/// A trace is a list of frames with their offset from the first frame. Traces
+/// use the candump log format, so a trace can be recorded on the machine with
+/// `candump -l` and replayed against the runtime. Each line holds a timestamp
+/// in seconds, the interface and the frame:
+///
+/// ```text
+/// (1718010000.000000) can0 18FF6A6B#0102030405060708
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use glonax::driver::FrameTrace;
+///
+/// let trace = FrameTrace::parse(
+///     "(1718010000.000000) can0 18FF6A6B#0102\n(1718010000.250000) can0 0CFF004A#FF",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(trace.len(), 2);
+/// assert_eq!(trace.duration().as_millis(), 250);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FrameTrace {
+    frames: Vec<(Duration, Frame)>,
+}
+
+impl FrameTrace {
+    /// Parse a trace from candump log lines.
+    ///
+    /// Empty lines and lines starting with `#` are ignored. The frames are
+    /// expected in chronological order.
+    pub fn parse(trace: &str) -> io::Result<Self> {
+        let mut frames = Vec::new();
+        let mut start = None;
+
+        for (idx, line) in trace.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (timestamp, frame) = Self::parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid trace line {}: {}", idx + 1, line),
+                )
+            })?;
+
+            let offset = timestamp
+                .checked_sub(*start.get_or_insert(timestamp))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("trace line {} is out of order", idx + 1),
+                    )
+                })?;
+
+            frames.push((offset, frame));
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// Read a trace from a file.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse a timestamp in seconds without loss of precision.
+    fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+        let (secs, fraction) = timestamp.split_once('.').unwrap_or((timestamp, ""));
+        if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let nanos = format!("{:0<9}", fraction).parse::<u32>().ok()?;
+
+        Some(Duration::new(secs.parse().ok()?, nanos))
+    }
+
+    fn parse_line(line: &str) -> Option<(Duration, Frame)> {
+        let mut parts = line.split_whitespace();
+
+        let timestamp = Self::parse_timestamp(parts.next()?.strip_prefix('(')?.strip_suffix(')')?)?;
+        let _interface = parts.next()?;
+        let (id, data) = parts.next()?.split_once('#')?;
+
+        if data.len() % 2 != 0 || data.len() > 16 {
+            return None;
+        }
+
+        let mut pdu = Vec::with_capacity(data.len() / 2);
+        for idx in (0..data.len()).step_by(2) {
+            pdu.push(u8::from_str_radix(data.get(idx..idx + 2)?, 16).ok()?);
+        }
+
+        let id = Id::new(u32::from_str_radix(id, 16).ok()?);
+
+        Some((
+            timestamp,
+            FrameBuilder::new(id).copy_from_slice(&pdu).build(),
+        ))
+    }
+
+    /// Number of frames in the trace.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Test if the trace has no frames.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Offset of the last frame from the first frame.
+    pub fn duration(&self) -> Duration {
+        self.frames
+            .last()
+            .map(|(offset, _)| *offset)
+            .unwrap_or_default()
+    }
+
+    /// Iterate over the frames and their offset from the first frame.
+    pub fn iter(&self) -> impl Iterator<Item = &(Duration, Frame)> {
+        self.frames.iter()
+    }
+
+    /// Retrieve frame and its offset by index.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&(Duration, Frame)> {
+        self.frames.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_parse() {
+        let trace = FrameTrace::parse(
+            "# recorded on vcan0\n\
+             (1718010000.000000) vcan0 18FF6A6B#0102030405060708\n\
+             \n\
+             (1718010000.010000) vcan0 0CFF004A#",
+        )
+        .unwrap();
+
+        assert_eq!(trace.len(), 2);
+
+        let (offset, frame) = trace.get(0).unwrap();
+        assert_eq!(*offset, Duration::ZERO);
+        assert_eq!(frame.id().as_raw(), 0x18FF6A6B);
+        assert_eq!(frame.pdu(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let (offset, frame) = trace.get(1).unwrap();
+        assert_eq!(offset.as_millis(), 10);
+        assert!(frame.is_empty());
+
+        assert!(FrameTrace::parse("(1718010000.0) vcan0 18FF6A6B#010").is_err());
+        assert!(FrameTrace::parse("vcan0 18FF6A6B#01").is_err());
+        assert!(FrameTrace::parse(
+            "(1718010001.0) vcan0 18FF6A6B#01\n(1718010000.0) vcan0 18FF6A6B#01"
+        )
+        .is_err());
+    }
+}
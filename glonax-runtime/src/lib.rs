@@ -22,8 +22,9 @@
 /// robot actor, program queue, and electronic control unit state.
 ///
 /// The `Governor` struct represents a governor for the engine. It has fields for the default
-/// engine speed and maximum RPM. It provides methods for reshaping torque and determining
-/// the engine mode based on the actual and requested engine modes.
+/// engine speed and maximum RPM. It provides methods for reshaping torque, determining
+/// the engine mode based on the actual and requested engine modes, and limiting the RPM
+/// setpoint by a ramp and droop under load.
 ///
 /// The `Operand` struct represents the operand, which is the current state of the machine.
 /// It includes the machine state and a governor for the engine. It provides methods for
@@ -55,6 +56,7 @@ pub use self::runtime::Error;
 pub use self::runtime::Runtime;
 
 static INSTANCE: std::sync::OnceLock<core::Instance> = std::sync::OnceLock::new();
+static CAPABILITY: std::sync::RwLock<Option<core::Capability>> = std::sync::RwLock::new(None);
 
 pub mod global {
     /// Get the Glonax runtime instance.
@@ -76,6 +78,24 @@ pub mod global {
     pub fn set_instance(instance: crate::core::Instance) {
         crate::INSTANCE.set(instance).unwrap();
     }
+
+    /// Get the machine capability.
+    ///
+    /// # Returns
+    ///
+    /// Returns the machine capability, or `None` if it has not been assembled yet.
+    pub fn capability() -> Option<crate::core::Capability> {
+        crate::CAPABILITY.read().unwrap().clone()
+    }
+
+    /// Set the machine capability.
+    ///
+    /// # Arguments
+    ///
+    /// * `capability` - The machine capability to set.
+    pub fn set_capability(capability: crate::core::Capability) {
+        *crate::CAPABILITY.write().unwrap() = Some(capability);
+    }
 }
 
 /// Glonax runtime module containing various constants.
@@ -233,6 +253,22 @@ pub mod consts {
     ///
     /// This constant represents the interval for the Glonax service pipeline.
     pub const SERVICE_PIPELINE_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Glonax minimum service tick interval.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use glonax::consts::SERVICE_TICK_INTERVAL_MIN;
+    /// use std::time::Duration;
+    ///
+    /// println!("Glonax minimum service tick interval: {:?}", SERVICE_TICK_INTERVAL_MIN);
+    /// ```
+    ///
+    /// # Remarks
+    ///
+    /// This constant represents the lower bound of a tick interval changed at runtime.
+    pub const SERVICE_TICK_INTERVAL_MIN: Duration = Duration::from_millis(5);
 }
 
 /// Log system information.
@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use nalgebra::{Matrix3, Vector3};
+
+/// Savitzky-Golay differentiator.
+///
+/// Fits a second order polynomial through a sliding window of samples using
+/// least squares and returns the first and second derivative at the center of
+/// the window. Samples do not need to be uniformly spaced in time. Fitting a
+/// polynomial instead of taking plain finite differences suppresses the noise
+/// amplification of numerical differentiation, at the cost of a delay of half
+/// the window.
+///
+/// The window is cleared whenever the time between two consecutive samples
+/// exceeds the maximum gap. Until the window has been refilled no derivative
+/// is returned.
+#[derive(Clone, Debug)]
+pub struct SavitzkyGolay {
+    /// Number of samples in the window.
+    window: usize,
+    /// Maximum time between samples in seconds.
+    max_gap: f64,
+    /// Sample window with time in seconds and value.
+    samples: VecDeque<(f64, f32)>,
+}
+
+impl SavitzkyGolay {
+    /// Construct a new differentiator.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - The number of samples in the window, at least 3.
+    /// * `max_gap` - The maximum time between samples in seconds.
+    ///
+    /// # Returns
+    ///
+    /// A new `SavitzkyGolay` instance.
+    pub fn new(window: usize, max_gap: f64) -> Self {
+        assert!(window >= 3, "window must contain at least 3 samples");
+
+        Self {
+            window,
+            max_gap,
+            samples: VecDeque::with_capacity(window),
+        }
+    }
+
+    /// Add a new sample to the window.
+    ///
+    /// The window is cleared if the sample is not newer than the last sample,
+    /// or if the time since the last sample exceeds the maximum gap.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The sample time in seconds.
+    /// * `value` - The sample value.
+    pub fn push(&mut self, time: f64, value: f32) {
+        if let Some((last_time, _)) = self.samples.back() {
+            if time <= *last_time || time - last_time > self.max_gap {
+                self.samples.clear();
+            }
+        }
+
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back((time, value));
+    }
+
+    /// Clear the sample window.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Test if the window is filled and a derivative can be calculated.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.samples.len() == self.window
+    }
+
+    /// Test if the last sample is older than the maximum gap.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The current time in seconds.
+    pub fn is_stale(&self, time: f64) -> bool {
+        self.samples
+            .back()
+            .is_none_or(|(last_time, _)| time - last_time > self.max_gap)
+    }
+
+    /// Time at the center of the window in seconds.
+    ///
+    /// This is the time for which the derivative is calculated.
+    pub fn center_time(&self) -> Option<f64> {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((first, _)), Some((last, _))) => Some((first + last) / 2.0),
+            _ => None,
+        }
+    }
+
+    /// Calculate the first and second derivative.
+    ///
+    /// # Returns
+    ///
+    /// The first and second derivative at the center of the window, or `None`
+    /// if the window is not filled.
+    pub fn derivative(&self) -> Option<(f32, f32)> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        let center = self.center_time()?;
+
+        let mut moments = [0.0_f64; 5];
+        let mut rhs = Vector3::zeros();
+
+        for (time, value) in &self.samples {
+            let tau = time - center;
+            let value = *value as f64;
+
+            let mut tau_n = 1.0;
+            for moment in moments.iter_mut() {
+                *moment += tau_n;
+                tau_n *= tau;
+            }
+
+            rhs += Vector3::new(value, value * tau, value * tau * tau);
+        }
+
+        let normal = Matrix3::new(
+            moments[0], moments[1], moments[2], //
+            moments[1], moments[2], moments[3], //
+            moments[2], moments[3], moments[4],
+        );
+
+        let coefficients = normal.lu().solve(&rhs)?;
+
+        Some((coefficients[1] as f32, (2.0 * coefficients[2]) as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMPLITUDE: f32 = 0.8;
+    const OMEGA: f32 = 2.0 * std::f32::consts::PI * 0.5;
+    const SAMPLE_PERIOD: f64 = 0.01;
+
+    fn sine(time: f64) -> f32 {
+        AMPLITUDE * (OMEGA * time as f32).sin()
+    }
+
+    #[test]
+    fn test_savitzky_golay_sine() {
+        let mut filter = SavitzkyGolay::new(9, 0.05);
+
+        for i in 0..400 {
+            let time = i as f64 * SAMPLE_PERIOD;
+            filter.push(time, sine(time));
+
+            if let Some((velocity, acceleration)) = filter.derivative() {
+                let center = filter.center_time().unwrap() as f32;
+
+                let velocity_expected = AMPLITUDE * OMEGA * (OMEGA * center).cos();
+                let acceleration_expected = -AMPLITUDE * OMEGA.powi(2) * (OMEGA * center).sin();
+
+                assert!((velocity - velocity_expected).abs() < 0.01 * AMPLITUDE * OMEGA);
+                assert!(
+                    (acceleration - acceleration_expected).abs() < 0.02 * AMPLITUDE * OMEGA.powi(2)
+                );
+            }
+        }
+
+        assert!(filter.is_valid());
+    }
+
+    #[test]
+    fn test_savitzky_golay_gap() {
+        let mut filter = SavitzkyGolay::new(9, 0.05);
+
+        for i in 0..100 {
+            let time = i as f64 * SAMPLE_PERIOD;
+            filter.push(time, sine(time));
+        }
+
+        assert!(filter.derivative().is_some());
+        assert!(filter.is_stale(1.5));
+
+        for i in 150..250 {
+            let time = i as f64 * SAMPLE_PERIOD;
+            filter.push(time, sine(time));
+
+            if i < 158 {
+                assert!(filter.derivative().is_none());
+            } else {
+                let (velocity, _) = filter.derivative().unwrap();
+                assert!(velocity.abs() <= AMPLITUDE * OMEGA * 1.01);
+            }
+        }
+    }
+}
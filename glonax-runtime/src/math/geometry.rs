@@ -1,4 +1,4 @@
-use nalgebra::{Rotation3, UnitQuaternion};
+use nalgebra::{Rotation3, UnitQuaternion, UnitVector3, Vector3};
 
 pub trait EulerAngles {
     /// Create a rotation matrix from a roll angle.
@@ -67,3 +67,150 @@ impl EulerAngles for UnitQuaternion<f32> {
         UnitQuaternion::from_euler_angles(0.0, 0.0, yaw)
     }
 }
+
+/// Rotation error between two rotations.
+///
+/// # Arguments
+///
+/// * `current` - The current rotation.
+/// * `target` - The target rotation.
+///
+/// # Returns
+///
+/// The axis and angle of the rotation from the current to the target rotation.
+/// The angle is in the range 0 to π. The X axis is returned if the rotations
+/// are equal.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::math::rotation_error_axis_angle;
+/// use nalgebra::{Rotation3, Vector3};
+///
+/// let current = Rotation3::from_axis_angle(&Vector3::y_axis(), 0.1);
+/// let target = Rotation3::from_axis_angle(&Vector3::y_axis(), 0.3);
+///
+/// let (axis, angle) = rotation_error_axis_angle(current, target);
+/// assert!((axis.y - 1.0).abs() < 1e-5);
+/// assert!((angle - 0.2).abs() < 1e-5);
+/// ```
+pub fn rotation_error_axis_angle(
+    current: Rotation3<f32>,
+    target: Rotation3<f32>,
+) -> (UnitVector3<f32>, f32) {
+    current
+        .rotation_to(&target)
+        .axis_angle()
+        .unwrap_or((Vector3::x_axis(), 0.0))
+}
+
+/// Signed angle of a rotation around an axis.
+///
+/// The rotation is decomposed into a twist around the axis and a swing around
+/// an axis perpendicular to it. Only the twist is returned, so the angle of a
+/// joint is recovered even if the rotation carries a small error around the
+/// other axes.
+///
+/// # Arguments
+///
+/// * `rotation` - The rotation.
+/// * `axis` - The axis of the twist.
+///
+/// # Returns
+///
+/// The twist angle in the range -π to π, following the right hand rule around
+/// the axis.
+pub fn twist_angle(rotation: &UnitQuaternion<f32>, axis: &UnitVector3<f32>) -> f32 {
+    let sign = if rotation.w < 0.0 { -1.0 } else { 1.0 };
+
+    2.0 * (sign * rotation.imag().dot(axis)).atan2(sign * rotation.w)
+}
+
+/// Spherical linear interpolation between two rotations.
+///
+/// The interpolation follows the shortest path between the rotations. Nearly
+/// equal rotations are interpolated linearly, so the interpolation never
+/// fails.
+///
+/// # Arguments
+///
+/// * `a` - The rotation at `t = 0`.
+/// * `b` - The rotation at `t = 1`.
+/// * `t` - The interpolation factor.
+pub fn slerp(a: UnitQuaternion<f32>, b: UnitQuaternion<f32>, t: f32) -> UnitQuaternion<f32> {
+    let mut dot = a.coords.dot(&b.coords);
+    let mut b = b.into_inner().coords;
+
+    // Both quaternions represent the same rotation, take the shortest path.
+    if dot < 0.0 {
+        b = -b;
+        dot = -dot;
+    }
+
+    let coords = if dot > 0.9995 {
+        a.coords.lerp(&b, t)
+    } else {
+        let theta = dot.clamp(-1.0, 1.0).acos();
+        let sin_theta = theta.sin();
+
+        a.coords * (((1.0 - t) * theta).sin() / sin_theta) + b * ((t * theta).sin() / sin_theta)
+    };
+
+    UnitQuaternion::from_quaternion(nalgebra::Quaternion::from(coords))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_error() {
+        let current = Rotation3::from_axis_angle(&Vector3::y_axis(), 0.10);
+        let target = Rotation3::from_axis_angle(&Vector3::y_axis(), 0.15);
+        let (axis, angle) = rotation_error_axis_angle(current, target);
+        assert!((axis.into_inner() - Vector3::y()).norm() < 1e-4);
+        assert!((angle - 0.05).abs() < 1e-5);
+
+        // The axis flips for a negative error.
+        let current = Rotation3::from_axis_angle(&Vector3::z_axis(), 0.2);
+        let target = Rotation3::from_axis_angle(&Vector3::z_axis(), 0.1);
+        let (axis, angle) = rotation_error_axis_angle(current, target);
+        assert!((axis.into_inner() + Vector3::z()).norm() < 1e-4);
+        assert!((angle - 0.1).abs() < 1e-5);
+
+        let error = UnitQuaternion::from_rotation_matrix(&current.rotation_to(&target));
+        assert!((twist_angle(&error, &Vector3::z_axis()) + 0.1).abs() < 1e-5);
+        assert!(twist_angle(&error, &Vector3::y_axis()).abs() < 1e-5);
+
+        let (axis, angle) = rotation_error_axis_angle(current, current);
+        assert_eq!(axis, Vector3::x_axis());
+        assert_eq!(angle, 0.0);
+
+        // Small swing around another axis does not affect the twist.
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.01)
+            * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -0.3);
+        assert!((twist_angle(&rotation, &Vector3::y_axis()) + 0.3).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = UnitQuaternion::identity();
+        let b = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.4);
+
+        assert!(slerp(a, b, 0.0).angle_to(&a) < 1e-5);
+        assert!(slerp(a, b, 1.0).angle_to(&b) < 1e-5);
+
+        let expected = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.1);
+        assert!(slerp(a, b, 0.25).angle_to(&expected) < 1e-5);
+
+        // The negated quaternion is the same rotation.
+        let negated = UnitQuaternion::new_unchecked(-b.into_inner());
+        assert!(slerp(a, negated, 0.25).angle_to(&expected) < 1e-5);
+
+        // Nearly equal rotations.
+        let c = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 1e-6);
+        let q = slerp(a, c, 0.5);
+        assert!(q.coords.iter().all(|v| v.is_finite()));
+        assert!(q.angle_to(&a) < 1e-5);
+    }
+}
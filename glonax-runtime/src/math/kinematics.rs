@@ -0,0 +1,132 @@
+use nalgebra::{Matrix2, Vector2};
+
+/// Calculate the Jacobian of a planar two link manipulator.
+///
+/// The Jacobian maps joint velocities onto the velocity of the end effector
+/// in the plane of the manipulator. The first link is rotated relative to the
+/// base, the second link is rotated relative to the first link.
+///
+/// # Arguments
+///
+/// * `l1` - The length of the first link
+/// * `l2` - The length of the second link
+/// * `q1` - The angle of the first joint in radians
+/// * `q2` - The angle of the second joint in radians
+///
+/// # Returns
+///
+/// The 2x2 Jacobian matrix
+///
+/// # Examples
+///
+/// ```
+/// use glonax::math::planar_jacobian;
+///
+/// let jacobian = planar_jacobian(1.0, 1.0, 0.0, std::f32::consts::FRAC_PI_2);
+///
+/// assert!((jacobian.determinant() - 1.0).abs() < 1e-6);
+/// ```
+pub fn planar_jacobian(l1: f32, l2: f32, q1: f32, q2: f32) -> Matrix2<f32> {
+    let (s1, c1) = q1.sin_cos();
+    let (s12, c12) = (q1 + q2).sin_cos();
+
+    Matrix2::new(-l1 * s1 - l2 * s12, -l2 * s12, l1 * c1 + l2 * c12, l2 * c12)
+}
+
+/// Calculate the condition number of a matrix.
+///
+/// The condition number is the ratio between the largest and the smallest
+/// singular value. A large condition number indicates the matrix is close to
+/// singular. A singular matrix has an infinite condition number.
+///
+/// # Arguments
+///
+/// * `matrix` - The matrix to analyze
+///
+/// # Returns
+///
+/// The condition number of the matrix
+///
+/// # Examples
+///
+/// ```
+/// use glonax::math::condition_number;
+/// use nalgebra::Matrix2;
+///
+/// assert_eq!(condition_number(&Matrix2::identity()), 1.0);
+/// assert_eq!(condition_number(&Matrix2::zeros()), f32::INFINITY);
+/// ```
+pub fn condition_number(matrix: &Matrix2<f32>) -> f32 {
+    let singular_values = matrix.singular_values();
+
+    let sigma_max = singular_values.max();
+    let sigma_min = singular_values.min();
+
+    if sigma_min <= f32::EPSILON * sigma_max {
+        f32::INFINITY
+    } else {
+        sigma_max / sigma_min
+    }
+}
+
+/// Apply damped least squares to a joint space error.
+///
+/// The joint error is mapped onto the task space and back using the damped
+/// pseudo inverse of the Jacobian. Directions which are well conditioned pass
+/// through nearly unaltered, while directions close to a singularity are
+/// suppressed. The returned error is never larger than the input error.
+///
+/// # Arguments
+///
+/// * `jacobian` - The Jacobian of the manipulator
+/// * `error` - The joint space error
+/// * `damping` - The damping factor, zero disables damping
+///
+/// # Returns
+///
+/// The damped joint space error
+pub fn damped_least_squares(
+    jacobian: &Matrix2<f32>,
+    error: &Vector2<f32>,
+    damping: f32,
+) -> Vector2<f32> {
+    let task_error = jacobian * error;
+
+    let jjt = jacobian * jacobian.transpose() + Matrix2::identity() * damping.powi(2);
+
+    match jjt.try_inverse() {
+        Some(jjt_inv) => jacobian.transpose() * jjt_inv * task_error,
+        None => *error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_condition_number_singular() {
+        let jacobian = planar_jacobian(510.0, 310.0, 0.3, 0.0);
+
+        assert!(condition_number(&jacobian) > 1e4);
+
+        let jacobian = planar_jacobian(510.0, 310.0, 0.3, -std::f32::consts::FRAC_PI_2);
+
+        assert!(condition_number(&jacobian) < 5.0);
+    }
+
+    #[test]
+    fn test_damped_least_squares() {
+        let jacobian = planar_jacobian(510.0, 310.0, 0.3, -1.2);
+        let error = Vector2::new(0.2, -0.4);
+
+        let undamped = damped_least_squares(&jacobian, &error, 0.0);
+        assert!((undamped - error).norm() < 1e-4);
+
+        let jacobian = planar_jacobian(510.0, 310.0, 0.3, 0.001);
+        let error = Vector2::new(0.5, -1.5);
+
+        let damped = damped_least_squares(&jacobian, &error, 50.0);
+        assert!(damped.norm() <= error.norm());
+    }
+}
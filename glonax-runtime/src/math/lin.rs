@@ -1,10 +1,21 @@
+/// Linear controller.
+///
+/// The power is proportional to the error, offset by the minimum power to get
+/// the actuator moving. Errors within the deadband result in no power.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Linear {
     /// Proportional gain
     kp: f32,
-    /// Derivative gain
+    /// Power offset
     offset: f32,
-    /// Integral of error
+    /// Invert the output
     inverse: bool,
+    /// Error below which no power is applied
+    deadband: f32,
+    /// Minimum power magnitude
+    power_min: f32,
+    /// Maximum power magnitude
+    power_max: f32,
 }
 
 impl Linear {
@@ -14,14 +25,34 @@ impl Linear {
             kp,
             offset,
             inverse,
+            deadband: 0.0,
+            power_min: 0.0,
+            power_max: i16::MAX as f32,
         }
     }
 
+    /// Set the deadband of the error.
+    pub fn with_deadband(mut self, deadband: f32) -> Self {
+        self.deadband = deadband.abs();
+        self
+    }
+
+    /// Set the minimum and maximum power magnitude.
+    pub fn with_power_limit(mut self, min: f32, max: f32) -> Self {
+        self.power_min = min;
+        self.power_max = max;
+        self
+    }
+
     /// Method to update the linear controller based on the current error
     pub fn update(&self, error: f32) -> f32 {
-        let value = (error * self.kp)
-            .clamp(i16::MIN as f32 + self.offset, i16::MAX as f32 - self.offset)
-            + (self.offset * error.signum());
+        if error.abs() <= self.deadband {
+            return 0.0;
+        }
+
+        let value = (error.abs() * self.kp + self.offset)
+            .clamp(self.power_min, self.power_max)
+            .copysign(error);
 
         if self.inverse {
             value
@@ -52,4 +83,39 @@ mod tests {
         let tolerance = 0.01;
         assert!((linear.update(-28.455_f32.to_radians()) + 19_449.5).abs() < tolerance);
     }
+
+    #[test]
+    fn test_linear_saturation() {
+        let linear = Linear::new(15_000.0, 12_000.0, true);
+
+        assert_eq!(linear.update(10.0), i16::MAX as f32);
+        assert_eq!(linear.update(-10.0), -(i16::MAX as f32));
+
+        let linear = linear.with_power_limit(14_000.0, 20_000.0);
+        assert_eq!(linear.update(10.0), 20_000.0);
+        assert_eq!(linear.update(0.01), 14_000.0);
+        assert_eq!(linear.update(-0.01), -14_000.0);
+    }
+
+    #[test]
+    fn test_linear_deadband() {
+        let linear = Linear::new(15_000.0, 12_000.0, true).with_deadband(0.02);
+
+        assert_eq!(linear.update(0.0), 0.0);
+        assert_eq!(linear.update(0.02), 0.0);
+        assert_eq!(linear.update(-0.015), 0.0);
+        assert_eq!(linear.update(0.1), 13_500.0);
+        assert_eq!(linear.update(-0.1), -13_500.0);
+    }
+
+    #[test]
+    fn test_linear_sign() {
+        let linear = Linear::new(7_000.0, 12_000.0, false);
+        let inverse = Linear::new(7_000.0, 12_000.0, true);
+
+        for error in [-1.0, -0.2, 0.2, 1.0] {
+            assert_eq!(linear.update(error), -inverse.update(error));
+            assert_eq!(inverse.update(error).signum(), error.signum());
+        }
+    }
 }
@@ -1,10 +1,18 @@
 use std::f32::consts::PI;
 
+pub use derivative::*;
 pub use geometry::*;
+pub use kinematics::*;
 pub use lin::*;
+pub use pid::Pid;
+pub use profile::TrapezoidalProfile;
 
+mod derivative;
 mod geometry;
+mod kinematics;
 mod lin;
+mod pid;
+mod profile;
 
 /// Calculate the shortest rotation between two points on a circle
 ///
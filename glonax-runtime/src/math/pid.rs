@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+/// PID controller.
+///
+/// The integral term is clamped to the integral limit, so the integral does not
+/// wind up while the actuator is saturated or blocked. The derivative term is
+/// taken on the measurement instead of the error, so a step in the setpoint
+/// does not kick the output.
+#[derive(Clone, Debug)]
+pub struct Pid {
+    /// Proportional gain.
+    kp: f32,
+    /// Integral gain.
+    ki: f32,
+    /// Derivative gain.
+    kd: f32,
+    /// Maximum magnitude of the integral term.
+    integral_limit: f32,
+    /// Integral term.
+    integral: f32,
+    /// Last measurement.
+    last_measurement: Option<f32>,
+}
+
+impl Pid {
+    /// Construct a new PID controller.
+    ///
+    /// # Arguments
+    ///
+    /// * `kp` - The proportional gain.
+    /// * `ki` - The integral gain.
+    /// * `kd` - The derivative gain.
+    /// * `integral_limit` - The maximum magnitude of the integral term.
+    pub fn new(kp: f32, ki: f32, kd: f32, integral_limit: f32) -> Self {
+        Self {
+            kp,
+            ki,
+            kd,
+            integral_limit: integral_limit.abs(),
+            integral: 0.0,
+            last_measurement: None,
+        }
+    }
+
+    /// Integral term.
+    #[inline]
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// Reset the integral and derivative state.
+    pub fn reset(&mut self) {
+        self.integral = 0.0;
+        self.last_measurement = None;
+    }
+
+    /// Update the controller with the error.
+    ///
+    /// The error is taken as the measurement against a fixed setpoint. Use
+    /// [`Pid::update_measurement`] if the setpoint moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The error between the setpoint and the measurement.
+    /// * `dt` - The time since the last update.
+    ///
+    /// # Returns
+    ///
+    /// The controller output.
+    pub fn update(&mut self, error: f32, dt: Duration) -> f32 {
+        self.update_measurement(0.0, -error, dt)
+    }
+
+    /// Update the controller with the setpoint and measurement.
+    ///
+    /// The integral and derivative terms are not updated if no time passed
+    /// since the last update.
+    ///
+    /// # Arguments
+    ///
+    /// * `setpoint` - The desired value.
+    /// * `measurement` - The measured value.
+    /// * `dt` - The time since the last update.
+    ///
+    /// # Returns
+    ///
+    /// The controller output.
+    pub fn update_measurement(&mut self, setpoint: f32, measurement: f32, dt: Duration) -> f32 {
+        let error = setpoint - measurement;
+        let dt = dt.as_secs_f32();
+
+        let mut derivative = 0.0;
+
+        if dt > 0.0 {
+            self.integral = (self.integral + self.ki * error * dt)
+                .clamp(-self.integral_limit, self.integral_limit);
+
+            if let Some(last_measurement) = self.last_measurement {
+                derivative = -(measurement - last_measurement) / dt;
+            }
+
+            self.last_measurement = Some(measurement);
+        } else if self.last_measurement.is_none() {
+            self.last_measurement = Some(measurement);
+        }
+
+        self.kp * error + self.integral + self.kd * derivative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_pid_steady_state() {
+        let mut pid = Pid::new(2.0, 1.5, 0.1, 10.0);
+
+        // First order plant with a constant disturbance.
+        let setpoint = 1.0;
+        let mut position = 0.0_f32;
+
+        for _ in 0..3_000 {
+            let output = pid.update(setpoint - position, DT);
+            position += (output - 0.5) * DT.as_secs_f32();
+        }
+
+        assert!((position - setpoint).abs() < 1e-3);
+        // The integral term compensates the disturbance.
+        assert!((pid.integral() - 0.5).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_pid_integral_limit() {
+        let mut pid = Pid::new(1.0, 10.0, 0.0, 2.0);
+
+        for _ in 0..1_000 {
+            pid.update(5.0, DT);
+        }
+
+        assert_eq!(pid.integral(), 2.0);
+        assert_eq!(pid.update(5.0, DT), 7.0);
+
+        // The integral recovers as soon as the error changes sign.
+        pid.update(-5.0, DT);
+        assert!(pid.integral() < 2.0);
+
+        for _ in 0..1_000 {
+            pid.update(-5.0, DT);
+        }
+        assert_eq!(pid.integral(), -2.0);
+
+        pid.reset();
+        assert_eq!(pid.integral(), 0.0);
+        assert!((pid.update(1.0, DT) - 1.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pid_derivative_on_measurement() {
+        let mut pid = Pid::new(1.0, 0.0, 1.0, 0.0);
+
+        assert_eq!(pid.update_measurement(0.0, 0.0, DT), 0.0);
+
+        // A setpoint step does not kick the derivative.
+        assert_eq!(pid.update_measurement(1.0, 0.0, DT), 1.0);
+
+        // A change in the measurement is damped.
+        let output = pid.update_measurement(1.0, 0.1, DT);
+        assert!((output - (0.9 - 10.0)).abs() < 1e-4);
+
+        // No time passed, no derivative.
+        assert_eq!(pid.update_measurement(1.0, 0.1, Duration::ZERO), 0.9);
+    }
+}
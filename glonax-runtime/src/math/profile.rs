@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+/// Trapezoidal motion profile.
+///
+/// The velocity ramps up at the maximum acceleration, cruises at the maximum
+/// velocity and ramps down at the maximum acceleration, so the motion starts
+/// and stops without a jump in velocity. If the distance is too short to reach
+/// the maximum velocity the profile is a triangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrapezoidalProfile {
+    /// Maximum velocity in units per second.
+    max_vel: f32,
+    /// Maximum acceleration in units per second squared.
+    max_accel: f32,
+}
+
+impl TrapezoidalProfile {
+    /// Construct a new trapezoidal motion profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_vel` - The maximum velocity in units per second.
+    /// * `max_accel` - The maximum acceleration in units per second squared.
+    pub fn new(max_vel: f32, max_accel: f32) -> Self {
+        Self {
+            max_vel: max_vel.abs(),
+            max_accel: max_accel.abs(),
+        }
+    }
+
+    /// Peak velocity and the duration of the acceleration and cruise phases.
+    fn phases(&self, distance: f32) -> (f32, f32, f32) {
+        let distance = distance.abs();
+
+        if self.max_vel <= 0.0 || self.max_accel <= 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+
+        let accel_distance = self.max_vel.powi(2) / self.max_accel;
+        if accel_distance >= distance {
+            let peak = (distance * self.max_accel).sqrt();
+            (peak, peak / self.max_accel, 0.0)
+        } else {
+            (
+                self.max_vel,
+                self.max_vel / self.max_accel,
+                (distance - accel_distance) / self.max_vel,
+            )
+        }
+    }
+
+    /// Duration of the motion over the distance.
+    pub fn duration(&self, distance: f32) -> Duration {
+        let (_, accel_time, cruise_time) = self.phases(distance);
+
+        Duration::from_secs_f32(2.0 * accel_time + cruise_time)
+    }
+
+    /// Sample the target velocity.
+    ///
+    /// # Arguments
+    ///
+    /// * `distance` - The signed distance of the motion.
+    /// * `elapsed` - The time since the start of the motion.
+    ///
+    /// # Returns
+    ///
+    /// The target velocity, with the sign of the distance. The velocity is
+    /// zero once the motion is completed.
+    pub fn sample(&self, distance: f32, elapsed: Duration) -> f32 {
+        let (peak, accel_time, cruise_time) = self.phases(distance);
+        let t = elapsed.as_secs_f32();
+        let total = 2.0 * accel_time + cruise_time;
+
+        let velocity = if t < accel_time {
+            self.max_accel * t
+        } else if t < accel_time + cruise_time {
+            peak
+        } else if t < total {
+            self.max_accel * (total - t)
+        } else {
+            0.0
+        };
+
+        velocity.min(peak).copysign(distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.001;
+
+    /// Integrate the profile and return the distance and maximum acceleration.
+    fn integrate(profile: &TrapezoidalProfile, distance: f32) -> (f32, f32, f32) {
+        let steps = (profile.duration(distance).as_secs_f32() / DT).ceil() as usize + 10;
+
+        let mut travelled = 0.0;
+        let mut last = 0.0;
+        let mut max_accel: f32 = 0.0;
+        let mut max_vel: f32 = 0.0;
+
+        for step in 0..steps {
+            let velocity = profile.sample(distance, Duration::from_secs_f32(step as f32 * DT));
+
+            travelled += velocity * DT;
+            max_accel = max_accel.max((velocity - last).abs() / DT);
+            max_vel = max_vel.max(velocity.abs());
+            last = velocity;
+        }
+
+        (travelled, max_accel, max_vel)
+    }
+
+    #[test]
+    fn test_trapezoidal_profile() {
+        let profile = TrapezoidalProfile::new(2.0, 4.0);
+
+        // Accelerate for 0.5s, cruise for 2s and decelerate for 0.5s.
+        assert!((profile.duration(5.0).as_secs_f32() - 3.0).abs() < 1e-5);
+        assert_eq!(profile.sample(5.0, Duration::from_millis(250)), 1.0);
+        assert_eq!(profile.sample(5.0, Duration::from_secs(1)), 2.0);
+        assert_eq!(profile.sample(-5.0, Duration::from_secs(1)), -2.0);
+        assert_eq!(profile.sample(5.0, Duration::from_secs(4)), 0.0);
+
+        for distance in [5.0, -5.0, 12.5] {
+            let (travelled, max_accel, max_vel) = integrate(&profile, distance);
+
+            assert!((travelled - distance).abs() < 0.01);
+            assert!(max_accel <= 4.0 + 0.01);
+            assert!(max_vel <= 2.0);
+        }
+    }
+
+    #[test]
+    fn test_triangular_profile() {
+        let profile = TrapezoidalProfile::new(2.0, 4.0);
+
+        // Too short to reach the maximum velocity.
+        let (travelled, max_accel, max_vel) = integrate(&profile, 0.5);
+        assert!((travelled - 0.5).abs() < 0.01);
+        assert!(max_accel <= 4.0 + 0.01);
+        assert!((max_vel - 2.0_f32.sqrt()).abs() < 0.01);
+
+        assert_eq!(profile.sample(0.0, Duration::ZERO), 0.0);
+        assert_eq!(profile.duration(0.0), Duration::ZERO);
+    }
+}
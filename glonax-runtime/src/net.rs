@@ -1,8 +1,22 @@
-use std::io;
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
 
 use j1939::{Frame, FrameBuilder, Id, IdBuilder, Name, PGN};
 
-pub use crate::can::{CANSocket, SockAddrCAN};
+mod bus;
+mod claim;
+mod stats;
+mod transport;
+
+pub use self::bus::{BusErrorStats, BusRecovery, ErrorSource};
+pub use self::claim::{
+    has_priority, name_value, AddressClaimant, Contention, ADDRESS_NULL, CLAIM_TIMEOUT,
+};
+pub use self::stats::{RouterStats, TrafficReport, TrafficStats};
+pub use self::transport::{LongMessage, Reassembler, SESSION_TIMEOUT};
+pub use crate::can::{CANErrorFrame, CANFilter, CANSocket, J1939Filter, SockAddrCAN};
 
 pub enum ConnectionManagement {
     RequestToSend = 0x10,
@@ -148,6 +162,234 @@ pub trait Parsable<T>: Send + Sync {
     /// Returns `None` if the frame is not parsable. Returns `Some(T)` if the frame is parsable
     /// and the message is successfully parsed and returned.
     fn parse(&self, frame: &Frame) -> Option<T>;
+
+    /// Parse a frame into any number of messages.
+    ///
+    /// Parsers that decode several signals from one frame return a message per
+    /// signal. The default implementation returns the message of [`Parsable::parse`].
+    fn parse_many(&self, frame: &Frame) -> Vec<T> {
+        self.parse(frame).into_iter().collect()
+    }
+
+    /// Parse a message reassembled by the transport protocol.
+    ///
+    /// Returns `None` if the message is not parsable. Parsers of single frame
+    /// messages do not need to implement this method.
+    fn parse_message(&self, _message: &LongMessage) -> Option<T> {
+        None
+    }
+}
+
+/// A parser with its messages mapped to a common message type.
+struct MappedParsable<P, M, T> {
+    parser: P,
+    map: fn(M) -> T,
+}
+
+impl<P: Parsable<M>, M, T> Parsable<T> for MappedParsable<P, M, T> {
+    fn parse(&self, frame: &Frame) -> Option<T> {
+        self.parser.parse(frame).map(self.map)
+    }
+
+    fn parse_many(&self, frame: &Frame) -> Vec<T> {
+        self.parser
+            .parse_many(frame)
+            .into_iter()
+            .map(self.map)
+            .collect()
+    }
+
+    fn parse_message(&self, message: &LongMessage) -> Option<T> {
+        self.parser.parse_message(message).map(self.map)
+    }
+}
+
+/// A set of parsers tried on a frame in one pass.
+///
+/// Parsers of different message types are mapped onto a common message type,
+/// so that a listen loop can dispatch a frame once instead of chaining a
+/// `try_accept` call per parser. Parsers are tried in the order they were
+/// added. The set is itself parsable and returns the index of the matching
+/// parser along with the message.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::driver::{J1939ApplicationInspector, KueblerEncoder};
+/// use glonax::net::{Parsable, ParsableSet};
+///
+/// enum Message {
+///     Encoder(glonax::driver::net::encoder::EncoderMessage),
+///     J1939(glonax::driver::J1939Message),
+/// }
+///
+/// let parsers = ParsableSet::new()
+///     .with_parser(KueblerEncoder::new("can0", 0x6A, 0xFB), Message::Encoder)
+///     .with_parser(J1939ApplicationInspector, Message::J1939);
+///
+/// assert_eq!(parsers.len(), 2);
+/// ```
+pub struct ParsableSet<T> {
+    parsers: Vec<Box<dyn Parsable<T>>>,
+}
+
+impl<T: 'static> ParsableSet<T> {
+    /// Construct a new empty parser set.
+    pub fn new() -> Self {
+        Self {
+            parsers: Vec::new(),
+        }
+    }
+
+    /// Add a parser to the set.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The parser to add.
+    /// * `map` - Map the parser message to the message type of the set.
+    pub fn add_parser<P, M>(&mut self, parser: P, map: fn(M) -> T)
+    where
+        P: Parsable<M> + 'static,
+        M: 'static,
+    {
+        self.parsers.push(Box::new(MappedParsable { parser, map }));
+    }
+
+    /// Attach a parser to the set.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The parser to add.
+    /// * `map` - Map the parser message to the message type of the set.
+    pub fn with_parser<P, M>(mut self, parser: P, map: fn(M) -> T) -> Self
+    where
+        P: Parsable<M> + 'static,
+        M: 'static,
+    {
+        self.add_parser(parser, map);
+        self
+    }
+
+    /// Number of parsers in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.parsers.len()
+    }
+
+    /// Test if the set has no parsers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.parsers.is_empty()
+    }
+
+    /// Parse a frame by every parser in the set.
+    ///
+    /// # Returns
+    ///
+    /// The index of each matching parser along with its message.
+    pub fn parse_all(&self, frame: &Frame) -> Vec<(usize, T)> {
+        self.parsers
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, parser)| parser.parse(frame).map(|message| (idx, message)))
+            .collect()
+    }
+}
+
+impl<T: 'static> Default for ParsableSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> Parsable<(usize, T)> for ParsableSet<T> {
+    /// Parse a frame by the first matching parser in the set.
+    fn parse(&self, frame: &Frame) -> Option<(usize, T)> {
+        self.parsers
+            .iter()
+            .enumerate()
+            .find_map(|(idx, parser)| parser.parse(frame).map(|message| (idx, message)))
+    }
+
+    /// Parse a frame into the messages of the first matching parser in the set.
+    fn parse_many(&self, frame: &Frame) -> Vec<(usize, T)> {
+        self.parsers
+            .iter()
+            .enumerate()
+            .map(|(idx, parser)| {
+                parser
+                    .parse_many(frame)
+                    .into_iter()
+                    .map(|message| (idx, message))
+                    .collect::<Vec<_>>()
+            })
+            .find(|messages| !messages.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Parse a message by the first matching parser in the set.
+    fn parse_message(&self, message: &LongMessage) -> Option<(usize, T)> {
+        self.parsers
+            .iter()
+            .enumerate()
+            .find_map(|(idx, parser)| parser.parse_message(message).map(|message| (idx, message)))
+    }
+}
+
+/// Fix the frame size to the maximum frame size.
+fn fixed_frame(frame: &Frame) -> Frame {
+    FrameBuilder::new(*frame.id())
+        .copy_from_slice(frame.as_ref())
+        .set_len(8)
+        .build()
+}
+
+/// Bus state shared between the clones of a control network.
+///
+/// The socket is replaced on bus-off recovery. The clones pick up the new
+/// socket on their next send or receive.
+struct SharedBus {
+    /// The network socket.
+    socket: Mutex<Arc<CANSocket>>,
+    /// Notified when the socket is replaced.
+    replaced: tokio::sync::Notify,
+    /// Bus error statistics.
+    errors: Mutex<BusErrorStats>,
+    /// Bus-off recovery.
+    recovery: Mutex<BusRecovery>,
+}
+
+impl SharedBus {
+    fn new(socket: CANSocket) -> Self {
+        Self {
+            socket: Mutex::new(Arc::new(socket)),
+            replaced: tokio::sync::Notify::new(),
+            errors: Mutex::new(BusErrorStats::default()),
+            recovery: Mutex::new(BusRecovery::default()),
+        }
+    }
+
+    /// Return the current socket.
+    fn socket(&self) -> Arc<CANSocket> {
+        self.socket.lock().unwrap().clone()
+    }
+
+    /// Attempt to receive a frame on the current socket.
+    ///
+    /// Error frames are recorded in the bus error statistics.
+    fn poll_recv(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<io::Result<Frame>> {
+        let socket = self.socket.lock().unwrap();
+
+        loop {
+            match std::task::ready!(socket.poll_recv(cx)) {
+                Err(e) => match CANErrorFrame::from_io_error(&e) {
+                    Some(error_frame) => self.errors.lock().unwrap().record_frame(error_frame),
+                    None => return std::task::Poll::Ready(Err(e)),
+                },
+                Ok(frame) => return std::task::Poll::Ready(Ok(frame)),
+            }
+        }
+    }
 }
 
 /// The control network is used to accept and store incoming frames.
@@ -160,9 +402,13 @@ pub trait Parsable<T>: Send + Sync {
 /// Fixing the frame size avoids the need to check the frame size in each
 /// service that uses the control network. This is both a performance
 /// optimization and a safety feature.
+///
+/// Clones share the socket, the bus error statistics and the bus-off
+/// recovery, so that a clone receiving frames and a clone sending frames
+/// observe and recover the same bus.
 pub struct ControlNetwork {
-    /// The network.
-    socket: CANSocket,
+    /// The network socket and bus state.
+    bus: Arc<SharedBus>,
     /// The current frame.
     frame: Option<Frame>,
     /// Network filter.
@@ -171,33 +417,103 @@ pub struct ControlNetwork {
     name: Name,
     /// Network interface.
     interface: String,
+    /// Traffic statistics.
+    traffic: TrafficStats,
+    /// Transport protocol reassembly.
+    transport: Reassembler,
+    /// The message completed by the current frame.
+    message: Option<LongMessage>,
+}
+
+impl Clone for ControlNetwork {
+    fn clone(&self) -> Self {
+        Self {
+            bus: self.bus.clone(),
+            frame: None,
+            filter: self.filter.clone(),
+            name: self.name,
+            interface: self.interface.clone(),
+            traffic: TrafficStats::default(),
+            transport: Reassembler::default(),
+            message: None,
+        }
+    }
 }
 
 impl ControlNetwork {
     /// Construct a new control network.
     fn from_socket(socket: CANSocket, name: &Name, interface: &str) -> Self {
         Self {
-            socket,
+            bus: Arc::new(SharedBus::new(socket)),
             frame: None,
             filter: Filter::accept(),
             name: *name,
             interface: interface.to_owned(),
+            traffic: TrafficStats::default(),
+            transport: Reassembler::default(),
+            message: None,
         }
     }
 
+    /// Construct a control network on an in-process socket.
+    ///
+    /// A frame sent on the control network is received on the returned peer
+    /// socket and the other way around.
+    #[cfg(test)]
+    pub(crate) fn pair(interface: &str, name: &Name) -> io::Result<(Self, CANSocket)> {
+        let (socket, peer) = CANSocket::pair()?;
+        Ok((Self::from_socket(socket, name, interface), peer))
+    }
+
+    /// Bind a socket to an interface.
+    ///
+    /// The socket delivers the bus error frames, so that errors such as CRC
+    /// and acknowledgement errors are counted before the bus goes down.
+    fn bind_socket(interface: &str) -> io::Result<CANSocket> {
+        let address = SockAddrCAN::new(interface);
+        // An index of zero binds the socket to all interfaces.
+        if address.ifindex == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("network interface {} does not exist", interface),
+            ));
+        }
+
+        let socket = CANSocket::bind(&address)?;
+
+        if let Err(e) = socket.set_error_filter(crate::can::CAN_ERR_BUS_MASK) {
+            log::warn!("[{}] Failed to set error filter: {}", interface, e);
+        }
+
+        Ok(socket)
+    }
+
     /// Construct a new control network and bind to an interface.
     pub fn bind(interface: &str, name: &Name) -> io::Result<Self> {
-        let socket = CANSocket::bind(&SockAddrCAN::new(interface))?;
+        let socket = Self::bind_socket(interface)?;
         Ok(Self::from_socket(socket, name, interface))
     }
 
     /// Set the global filter.
-    #[inline]
+    ///
+    /// If the filter can be expressed as kernel filters, the filter is pushed
+    /// down to the socket so that rejected frames are never read. The filter
+    /// is always applied in userspace as well.
     pub fn with_filter(mut self, filter: Filter) -> Self {
         self.filter = filter;
+        self.apply_filter();
         self
     }
 
+    /// Push the filter down to the socket.
+    fn apply_filter(&self) {
+        if let Some(filters) = self.filter.can_filters() {
+            if let Err(e) = self.bus.socket().set_filter(&filters) {
+                log::warn!("[{}] Failed to set kernel filter: {}", self.interface, e);
+            }
+        }
+    }
+
     /// Return the current frame source.
     #[inline]
     pub fn frame_source(&self) -> Option<u8> {
@@ -222,16 +538,103 @@ impl ControlNetwork {
         self.frame.as_ref()
     }
 
-    /// Send a frame.
+    /// Return the message completed by the current frame.
+    ///
+    /// See [`Router::message`].
     #[inline]
-    pub async fn send(&self, frame: &Frame) -> io::Result<usize> {
-        self.socket.send(frame).await
+    pub fn message(&self) -> Option<&LongMessage> {
+        self.message.as_ref()
     }
 
-    /// Send a vector of frames.
+    /// Return the bus error statistics.
     #[inline]
-    pub async fn send_vectored(&self, frames: &Vec<Frame>) -> io::Result<Vec<usize>> {
-        self.socket.send_vectored(frames).await
+    pub fn errors(&self) -> BusErrorStats {
+        *self.bus.errors.lock().unwrap()
+    }
+
+    /// Return the traffic statistics.
+    #[inline]
+    pub fn traffic(&self) -> &TrafficStats {
+        &self.traffic
+    }
+
+    /// Poll the socket for a pending bus error.
+    ///
+    /// Returns `true` if an error was recorded in the bus error statistics.
+    pub fn poll_errors(&self) -> bool {
+        let socket = self.bus.socket();
+        self.bus.errors.lock().unwrap().poll(socket.as_ref())
+    }
+
+    /// Record a bus error in the bus error statistics.
+    #[inline]
+    pub fn record_error(&self, error: &io::Error) {
+        self.bus.errors.lock().unwrap().record(error);
+    }
+
+    /// Return the bus-off recovery.
+    #[inline]
+    pub fn recovery(&self) -> BusRecovery {
+        self.bus.recovery.lock().unwrap().clone()
+    }
+
+    /// Recover from a bus-off condition.
+    ///
+    /// If the bus went bus-off and a recovery attempt is due, the socket is
+    /// bound to the interface again. Attempts are spaced by an exponential
+    /// backoff until a frame is received. The socket is replaced for every
+    /// clone of the control network.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if no attempt was made. Returns `Some` with the result of
+    /// the attempt otherwise.
+    pub fn recover(&mut self, now: std::time::Instant) -> Option<io::Result<()>> {
+        let errors = self.errors();
+        if !self.bus.recovery.lock().unwrap().update(&errors, now) {
+            return None;
+        }
+
+        match Self::bind_socket(&self.interface) {
+            Ok(socket) => {
+                *self.bus.socket.lock().unwrap() = Arc::new(socket);
+                self.apply_filter();
+                self.bus.recovery.lock().unwrap().restored();
+                self.bus.replaced.notify_waiters();
+                Some(Ok(()))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    /// Record a sent frame in the traffic statistics.
+    fn record_tx(&mut self, frame: &Frame, result: &io::Result<usize>) {
+        match result {
+            Ok(_) => self.traffic.record_tx(
+                frame.id().destination_address(),
+                frame.len(),
+                std::time::Instant::now(),
+            ),
+            Err(_) => self.traffic.record_tx_failure(),
+        }
+    }
+
+    /// Send a frame.
+    pub async fn send(&mut self, frame: &Frame) -> io::Result<usize> {
+        let result = self.bus.socket().send(frame).await;
+        self.record_tx(frame, &result);
+        result
+    }
+
+    /// Send a vector of frames.
+    ///
+    /// Frames are sent in order, frames after a failed frame are not sent.
+    pub async fn send_vectored(&mut self, frames: &Vec<Frame>) -> io::Result<Vec<usize>> {
+        let mut sizes = Vec::with_capacity(frames.len());
+        for frame in frames {
+            sizes.push(self.send(frame).await?);
+        }
+        Ok(sizes)
     }
 
     /// Listen for incoming packets.
@@ -246,15 +649,263 @@ impl ControlNetwork {
     /// received.
     pub async fn recv(&mut self) -> io::Result<()> {
         loop {
-            let frame = self.socket.recv().await?;
+            // A receive on a socket replaced by recovery is abandoned.
+            let replaced = self.bus.replaced.notified();
+            let frame = tokio::select! {
+                frame = std::future::poll_fn(|cx| self.bus.poll_recv(cx)) => frame?,
+                _ = replaced => continue,
+            };
+            let now = std::time::Instant::now();
+
+            self.traffic
+                .record_rx(frame.id().source_address(), frame.len(), now);
+
+            {
+                let mut recovery = self.bus.recovery.lock().unwrap();
+                if recovery.attempts() > 0 {
+                    recovery.working();
+                }
+            }
+
+            let message = self
+                .transport
+                .feed(&frame, now)
+                .filter(|message| self.filter.matches(&message.id()));
+
+            if message.is_some() || self.filter.matches(frame.id()) {
+                self.frame = Some(fixed_frame(&frame));
+                self.message = message;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Try to accept a frame and parse it.
+    ///
+    /// This method will return `None` if the frame is not accepted. Otherwise, it will return
+    /// `Some` with the resulting message.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to parse the frame.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the frame is not accepted. Returns `Some(T)` if the frame is accepted.
+    /// A message completed by the frame is handed to the service instead of the frame.
+    pub fn try_accept<T>(&self, service: &mut impl Parsable<T>) -> Option<T> {
+        match &self.message {
+            Some(message) => service.parse_message(message),
+            None => self.frame.and_then(|frame| service.parse(&frame)),
+        }
+    }
+
+    /// Try to accept a frame and parse it into any number of messages.
+    ///
+    /// Same as [`ControlNetwork::try_accept`], but every message the service
+    /// decodes from the frame is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to parse the frame.
+    ///
+    /// # Returns
+    ///
+    /// The parsed messages, empty if the frame is not accepted.
+    pub fn try_accept_many<T>(&self, service: &mut impl Parsable<T>) -> Vec<T> {
+        match &self.message {
+            Some(message) => service.parse_message(message).into_iter().collect(),
+            None => self
+                .frame
+                .map(|frame| service.parse_many(&frame))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The router accepts incoming frames from multiple control networks.
+///
+/// Frames are received from whichever network has a frame available first.
+/// The router keeps the index of the network the current frame came from, so
+/// that services can respond on the same network. Networks are identified by
+/// their index in the order they were added to the router. The networks are
+/// polled round-robin, so a busy network cannot starve the other networks.
+pub struct Router {
+    /// The networks.
+    networks: Vec<ControlNetwork>,
+    /// The current frame and the index of its source network.
+    frame: Option<(usize, Frame)>,
+    /// The message completed by the current frame.
+    message: Option<LongMessage>,
+    /// Transport protocol reassembly per network.
+    transport: std::collections::HashMap<usize, Reassembler>,
+    /// Router filter.
+    filter: Filter,
+    /// Index of the network polled first.
+    next: usize,
+    /// Router statistics.
+    stats: RouterStats,
+}
+
+impl Router {
+    /// Construct a new router over a set of networks.
+    pub fn new(networks: Vec<ControlNetwork>) -> Self {
+        Self {
+            networks,
+            transport: std::collections::HashMap::new(),
+            frame: None,
+            message: None,
+            filter: Filter::accept(),
+            next: 0,
+            stats: RouterStats::default(),
+        }
+    }
+
+    /// Set the router filter.
+    ///
+    /// The filter is pushed down to the socket of every network without a
+    /// network filter of its own, if it can be expressed as kernel filters.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        if let Some(filters) = filter.can_filters() {
+            for network in self.networks.iter().filter(|n| n.filter.is_accept_all()) {
+                if let Err(e) = network.bus.socket().set_filter(&filters) {
+                    log::warn!("[{}] Failed to set kernel filter: {}", network.interface, e);
+                }
+            }
+        }
+
+        self.filter = filter;
+        self
+    }
+
+    /// Return the networks of the router.
+    #[inline]
+    pub fn networks(&self) -> &[ControlNetwork] {
+        &self.networks
+    }
+
+    /// Return a network by index.
+    #[inline]
+    pub fn network(&self, index: usize) -> Option<&ControlNetwork> {
+        self.networks.get(index)
+    }
+
+    /// Return the current frame.
+    #[inline]
+    pub fn frame(&self) -> Option<&Frame> {
+        self.frame.as_ref().map(|(_, frame)| frame)
+    }
+
+    /// Return the current frame source address.
+    #[inline]
+    pub fn frame_source(&self) -> Option<u8> {
+        self.frame.map(|(_, frame)| frame.id().source_address())
+    }
+
+    /// Return the index of the network the current frame came from.
+    #[inline]
+    pub fn frame_source_net(&self) -> Option<usize> {
+        self.frame.map(|(index, _)| index)
+    }
+
+    /// Return the message completed by the current frame.
+    ///
+    /// Messages larger than a single frame are sent with the transport
+    /// protocol. The router reassembles the message, the frame that completes
+    /// the message carries it along.
+    #[inline]
+    pub fn message(&self) -> Option<&LongMessage> {
+        self.message.as_ref()
+    }
+
+    /// Return the router statistics.
+    #[inline]
+    pub fn stats(&self) -> &RouterStats {
+        &self.stats
+    }
+
+    /// Reset the router statistics.
+    pub fn reset_stats(&mut self) {
+        self.stats = RouterStats::default();
+    }
+
+    /// Store a frame received on a network.
+    ///
+    /// Returns `true` if the frame is accepted by the router filter. The frame
+    /// that completes a transport protocol message is accepted if the message
+    /// is accepted by the router filter.
+    fn accept(&mut self, index: usize, frame: &Frame, now: std::time::Instant) -> bool {
+        let message = self
+            .transport
+            .entry(index)
+            .or_default()
+            .feed(frame, now)
+            .filter(|message| self.filter.matches(&message.id()));
+
+        let accepted = message.is_some() || self.filter.matches(frame.id());
+        self.stats.record(frame.id().pgn().into(), accepted);
+
+        if !accepted {
+            return false;
+        }
+
+        self.frame = Some((index, fixed_frame(frame)));
+        self.message = message;
+
+        true
+    }
+
+    /// Listen for incoming frames on all networks.
+    ///
+    /// This method will block until a frame is received on any of the networks. The
+    /// frame and the index of its source network are stored in the router. Any frame
+    /// that does not match the filter will be ignored.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if a frame is received successfully. Returns an error if the frame cannot be
+    /// received or the router has no networks.
+    pub async fn recv(&mut self) -> io::Result<()> {
+        use std::task::Poll;
+
+        if self.networks.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "router has no networks",
+            ));
+        }
+
+        loop {
+            let networks = &self.networks;
+            let start = self.next;
+
+            // The sockets are polled in place, so receiving a frame does not
+            // allocate regardless of the number of networks. Polling starts
+            // at the network after the last one that delivered a frame.
+            let (index, frame) = std::future::poll_fn(|cx| {
+                for offset in 0..networks.len() {
+                    let index = (start + offset) % networks.len();
+
+                    if let Poll::Ready(result) = networks[index].bus.poll_recv(cx) {
+                        return Poll::Ready(result.map(|frame| (index, frame)));
+                    }
+                }
+
+                Poll::Pending
+            })
+            .await?;
+
+            self.next = (index + 1) % self.networks.len();
+
+            let now = std::time::Instant::now();
 
-            if self.filter.matches(frame.id()) {
-                let frame_fixed = FrameBuilder::new(*frame.id())
-                    .copy_from_slice(frame.as_ref())
-                    .set_len(8)
-                    .build();
+            self.networks[index]
+                .traffic
+                .record_rx(frame.id().source_address(), frame.len(), now);
 
-                self.frame = Some(frame_fixed);
+            if self.accept(index, &frame, now) {
                 break;
             }
         }
@@ -262,6 +913,22 @@ impl ControlNetwork {
         Ok(())
     }
 
+    /// Send a frame on a network.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the network.
+    /// * `frame` - The frame to send.
+    pub async fn send(&mut self, index: usize, frame: &Frame) -> io::Result<usize> {
+        match self.networks.get_mut(index) {
+            Some(network) => network.send(frame).await,
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no network with index {}", index),
+            )),
+        }
+    }
+
     /// Try to accept a frame and parse it.
     ///
     /// This method will return `None` if the frame is not accepted. Otherwise, it will return
@@ -273,9 +940,53 @@ impl ControlNetwork {
     ///
     /// # Returns
     ///
-    /// Returns `None` if the frame is not accepted. Returns `Some(T)` if the frame is accepted
+    /// Returns `None` if the frame is not accepted. Returns `Some(T)` if the frame is accepted.
+    /// A message completed by the frame is handed to the service instead of the frame.
     pub fn try_accept<T>(&self, service: &mut impl Parsable<T>) -> Option<T> {
-        self.frame.and_then(|frame| service.parse(&frame))
+        match &self.message {
+            Some(message) => service.parse_message(message),
+            None => self.frame.and_then(|(_, frame)| service.parse(&frame)),
+        }
+    }
+
+    /// Try to accept a frame and parse it into any number of messages.
+    ///
+    /// Same as [`Router::try_accept`], but every message the service decodes
+    /// from the frame is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to parse the frame.
+    ///
+    /// # Returns
+    ///
+    /// The parsed messages, empty if the frame is not accepted.
+    pub fn try_accept_many<T>(&self, service: &mut impl Parsable<T>) -> Vec<T> {
+        match &self.message {
+            Some(message) => service.parse_message(message).into_iter().collect(),
+            None => self
+                .frame
+                .map(|(_, frame)| service.parse_many(&frame))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Try to accept a frame and parse it.
+    ///
+    /// Same as [`Router::try_accept`], but the index of the network the frame came
+    /// from is returned with the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service to parse the frame.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the frame is not accepted. Returns `Some((index, T))` if the frame is
+    /// accepted.
+    pub fn try_accept_with_net<T>(&self, service: &mut impl Parsable<T>) -> Option<(usize, T)> {
+        let index = self.frame_source_net()?;
+        self.try_accept(service).map(|message| (index, message))
     }
 }
 
@@ -445,8 +1156,59 @@ impl FilterItem {
 
         true
     }
+
+    /// Convert the filter item into a kernel filter.
+    ///
+    /// Returns `None` if the filter item cannot be expressed as an identifier
+    /// and mask. A destination address is only supported together with a
+    /// PDU1 PGN.
+    fn can_filter(&self) -> Option<CANFilter> {
+        let mut filter = CANFilter {
+            id: libc::CAN_EFF_FLAG,
+            mask: libc::CAN_EFF_FLAG,
+        };
+
+        if let Some(priority) = self.priority {
+            filter.id |= (priority as u32 & 0x7) << 26;
+            filter.mask |= 0x7 << 26;
+        }
+
+        let pdu1 = match self.pgn {
+            Some(pgn) if pgn > 0xffff => return None,
+            Some(pgn) if (pgn >> 8) & 0xf0 < 0xf0 => {
+                if pgn & 0xff != 0 {
+                    return None;
+                }
+                filter.id |= (pgn & 0xff00) << 8;
+                filter.mask |= 0xff << 16;
+                true
+            }
+            Some(pgn) => {
+                filter.id |= pgn << 8;
+                filter.mask |= 0xffff << 8;
+                false
+            }
+            None => false,
+        };
+
+        if let Some(source_address) = self.source_address {
+            filter.id |= source_address as u32;
+            filter.mask |= 0xff;
+        }
+
+        if let Some(destination_address) = self.destination_address {
+            if !pdu1 {
+                return None;
+            }
+            filter.id |= (destination_address as u32) << 8;
+            filter.mask |= 0xff << 8;
+        }
+
+        Some(filter)
+    }
 }
 
+#[derive(Clone)]
 pub struct Filter {
     /// Filter items.
     items: Vec<FilterItem>,
@@ -505,6 +1267,31 @@ impl Filter {
         (self.accept && (self.items.is_empty() || match_items))
             || (!self.accept && (self.items.is_empty() || !match_items))
     }
+
+    /// Test if the filter accepts everything.
+    #[inline]
+    fn is_accept_all(&self) -> bool {
+        self.accept && self.items.is_empty()
+    }
+
+    /// Convert the filter into kernel filters.
+    ///
+    /// Only an accept filter with items can be pushed down to the kernel. A
+    /// frame passes if it matches any of the kernel filters, which is equal to
+    /// the userspace semantics.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if the filter accepts everything or any of its items
+    /// cannot be expressed as a kernel filter. The filter must then be applied
+    /// in userspace only.
+    pub fn can_filters(&self) -> Option<Vec<CANFilter>> {
+        if !self.accept || self.is_accept_all() {
+            return None;
+        }
+
+        self.items.iter().map(FilterItem::can_filter).collect()
+    }
 }
 
 impl Default for Filter {
@@ -516,6 +1303,351 @@ impl Default for Filter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::driver::{net::encoder::EncoderMessage, J1939ApplicationInspector, KueblerEncoder};
+
+    enum Message {
+        Encoder(EncoderMessage),
+        Other,
+    }
+
+    #[test]
+    fn test_router_frame_source_net() {
+        use crate::driver::J1939Message;
+
+        let mut router = Router::new(Vec::new());
+        assert_eq!(router.frame_source_net(), None);
+
+        let frame0 = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .sa(0x6A)
+                .build(),
+        )
+        .copy_from_slice(&[0x01])
+        .build();
+        let frame1 = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .sa(0x6B)
+                .build(),
+        )
+        .copy_from_slice(&[0x02])
+        .build();
+
+        assert!(router.accept(1, &frame1, std::time::Instant::now()));
+        assert_eq!(router.frame_source_net(), Some(1));
+        assert_eq!(router.frame_source(), Some(0x6B));
+        assert_eq!(router.frame().unwrap().len(), 8);
+
+        let (index, message) = router
+            .try_accept_with_net(&mut J1939ApplicationInspector)
+            .unwrap();
+        assert_eq!(index, 1);
+        assert!(matches!(message, J1939Message::ProprietaryB([0x02, ..])));
+
+        assert!(router.accept(0, &frame0, std::time::Instant::now()));
+        assert_eq!(router.frame_source_net(), Some(0));
+        assert_eq!(router.frame_source(), Some(0x6A));
+
+        let mut filter = Filter::accept();
+        filter.push(FilterItem::with_source_address(0x6B));
+
+        let mut router = Router::new(Vec::new()).with_filter(filter);
+        assert!(!router.accept(0, &frame0, std::time::Instant::now()));
+        assert_eq!(router.frame_source_net(), None);
+    }
+
+    #[test]
+    fn test_router_long_message() {
+        struct SoftwareIdentification;
+
+        impl Parsable<Vec<u8>> for SoftwareIdentification {
+            fn parse(&self, _frame: &Frame) -> Option<Vec<u8>> {
+                None
+            }
+
+            fn parse_message(&self, message: &LongMessage) -> Option<Vec<u8>> {
+                (message.pgn == PGN::SoftwareIdentification).then(|| message.data.clone())
+            }
+        }
+
+        let now = std::time::Instant::now();
+
+        let data0: Vec<u8> = b"glonax*1.0*".repeat(2);
+        let data1: Vec<u8> = b"vecraft*2.3*".repeat(3);
+        let frames0 = destination_specific(0x4A, 0x20, PGN::SoftwareIdentification, &data0);
+        let frames1 = destination_specific(0x4A, 0x21, PGN::SoftwareIdentification, &data1);
+
+        let mut filter = Filter::accept();
+        filter.push(FilterItem::with_pgn(PGN::SoftwareIdentification.into()));
+
+        let mut router = Router::new(Vec::new()).with_filter(filter);
+        let mut messages = vec![];
+
+        for (frame0, frame1) in frames0
+            .iter()
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .zip(&frames1)
+        {
+            if let Some(frame0) = frame0 {
+                if router.accept(0, frame0, now) {
+                    messages.extend(router.try_accept_with_net(&mut SoftwareIdentification));
+                }
+            }
+            if router.accept(1, frame1, now) {
+                messages.extend(router.try_accept_with_net(&mut SoftwareIdentification));
+            }
+        }
+
+        assert_eq!(messages, vec![(0, data0), (1, data1)]);
+        assert_eq!(router.frame_source(), Some(0x21));
+        assert_eq!(router.message().unwrap().source_address, 0x21);
+    }
+
+    #[test]
+    fn test_router_accept_many() {
+        use crate::driver::{EngineManagementSystem, EngineMessage};
+
+        let frame = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ElectronicEngineController1)
+                .sa(0x00)
+                .build(),
+        )
+        .copy_from_slice(
+            &j1939::spn::ElectronicEngineController1Message {
+                engine_torque_mode: None,
+                driver_demand: Some(93),
+                actual_engine: Some(4),
+                rpm: Some(1_500),
+                source_addr: None,
+                starter_mode: None,
+            }
+            .to_pdu(),
+        )
+        .build();
+
+        let mut router = Router::new(Vec::new());
+        assert!(router
+            .try_accept_many(&mut EngineManagementSystem::new("vcan0", 0x00, 0x27))
+            .is_empty());
+
+        assert!(router.accept(0, &frame, std::time::Instant::now()));
+
+        let mut parsers = ParsableSet::new()
+            .with_parser(KueblerEncoder::new("vcan0", 0x6A, 0xFB), |_| None)
+            .with_parser(EngineManagementSystem::new("vcan0", 0x00, 0x27), Some);
+
+        let messages = router.try_accept_many(&mut parsers);
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|(idx, _)| *idx == 1));
+        assert!(matches!(
+            messages[0].1,
+            Some(EngineMessage::EngineSpeed(1_500))
+        ));
+        assert!(matches!(
+            messages[1].1,
+            Some(EngineMessage::DriverDemand(93))
+        ));
+        assert!(matches!(
+            messages[2].1,
+            Some(EngineMessage::ActualEngine(4))
+        ));
+
+        // A single message parser accepts the frame once.
+        assert!(router.try_accept(&mut parsers).is_some());
+    }
+
+    #[test]
+    fn test_router_stats() {
+        let now = std::time::Instant::now();
+
+        let frame = |pgn: PGN, sa: u8| {
+            FrameBuilder::new(IdBuilder::from_pgn(pgn).sa(sa).build())
+                .copy_from_slice(&[0x01])
+                .build()
+        };
+
+        let mut filter = Filter::accept();
+        filter.push(FilterItem::with_source_address(0x6A));
+
+        let mut router = Router::new(Vec::new()).with_filter(filter);
+
+        for _ in 0..3 {
+            assert!(router.accept(0, &frame(PGN::ProprietaryB(65_450), 0x6A), now));
+        }
+        assert!(!router.accept(0, &frame(PGN::ProprietaryB(65_450), 0x6B), now));
+        assert!(!router.accept(1, &frame(PGN::AddressClaimed, 0x6B), now));
+
+        let stats = router.stats();
+        assert_eq!(stats.rx_total, 5);
+        assert_eq!(stats.rx_filtered, 2);
+        assert_eq!(stats.pgn_count(PGN::ProprietaryB(65_450).into()), 4);
+        assert_eq!(stats.pgn_count(PGN::AddressClaimed.into()), 1);
+        assert_eq!(stats.pgn_count(PGN::Request.into()), 0);
+        assert_eq!(
+            stats.histogram(),
+            vec![
+                (PGN::ProprietaryB(65_450).into(), 4),
+                (PGN::AddressClaimed.into(), 1)
+            ]
+        );
+
+        router.reset_stats();
+        assert_eq!(router.stats(), &RouterStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_router_round_robin() {
+        let name = j1939::NameBuilder::default().build();
+
+        let (socket0, peer0) = CANSocket::pair().unwrap();
+        let (socket1, peer1) = CANSocket::pair().unwrap();
+
+        let mut router = Router::new(vec![
+            ControlNetwork::from_socket(socket0, &name, "vcan0"),
+            ControlNetwork::from_socket(socket1, &name, "vcan1"),
+        ]);
+
+        let frame = |sa: u8, value: u8| {
+            FrameBuilder::new(
+                IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                    .sa(sa)
+                    .build(),
+            )
+            .copy_from_slice(&[value])
+            .build()
+        };
+
+        // The first network is busy, the second network sends a single frame.
+        for value in 0..4 {
+            peer0.send(&frame(0x6A, value)).await.unwrap();
+        }
+        peer1.send(&frame(0x6B, 0xFF)).await.unwrap();
+
+        let mut received = vec![];
+        for _ in 0..5 {
+            router.recv().await.unwrap();
+            received.push((
+                router.frame_source_net().unwrap(),
+                router.frame().unwrap().pdu()[0],
+            ));
+        }
+
+        assert_eq!(received, vec![(0, 0), (1, 0xFF), (0, 1), (0, 2), (0, 3)]);
+        assert_eq!(router.network(1).unwrap().traffic().report().rx_frames, 1);
+    }
+
+    #[tokio::test]
+    async fn test_control_network_clone_shares_bus() {
+        let name = j1939::NameBuilder::default().build();
+
+        let (socket, peer) = CANSocket::pair().unwrap();
+
+        let mut network = ControlNetwork::from_socket(socket, &name, "vcan0");
+        let mut clone = network.clone();
+
+        // A bus error seen on one clone is recovered by the other.
+        network.record_error(&io::Error::from_raw_os_error(libc::ENETDOWN));
+        assert_eq!(clone.errors().bus_off, 1);
+
+        let now = std::time::Instant::now();
+        assert!(clone.recover(now).is_none());
+        assert!(network.recovery().is_pending());
+
+        // Both clones receive on the same socket.
+        let frame = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .sa(0x6A)
+                .build(),
+        )
+        .copy_from_slice(&[0x01])
+        .build();
+
+        peer.send(&frame).await.unwrap();
+        clone.recv().await.unwrap();
+        assert_eq!(clone.frame_source(), Some(0x6A));
+
+        peer.send(&frame).await.unwrap();
+        network.recv().await.unwrap();
+        assert_eq!(network.frame_source(), Some(0x6A));
+    }
+
+    #[tokio::test]
+    async fn test_control_network_error_frame() {
+        let name = j1939::NameBuilder::default().build();
+
+        let (socket, peer) = CANSocket::pair().unwrap();
+
+        let mut network = ControlNetwork::from_socket(socket, &name, "vcan0");
+        let tick = network.clone();
+
+        let mut can_frame =
+            unsafe { std::mem::MaybeUninit::<libc::can_frame>::zeroed().assume_init() };
+        can_frame.can_id = libc::CAN_ERR_FLAG | crate::can::CAN_ERR_ACK;
+        can_frame.can_dlc = 8;
+
+        let buf = unsafe {
+            std::slice::from_raw_parts(
+                &can_frame as *const libc::can_frame as *const u8,
+                std::mem::size_of::<libc::can_frame>(),
+            )
+        };
+        peer.send_raw(buf).await.unwrap();
+
+        let frame = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .sa(0x6A)
+                .build(),
+        )
+        .copy_from_slice(&[0x01])
+        .build();
+        peer.send(&frame).await.unwrap();
+
+        // The error frame is counted on the shared bus state and skipped.
+        network.recv().await.unwrap();
+        assert_eq!(network.frame_source(), Some(0x6A));
+        assert_eq!(tick.errors().ack, 1);
+        assert_eq!(tick.errors().errors, 1);
+    }
+
+    #[test]
+    fn test_parsable_set() {
+        let parsers = ParsableSet::new()
+            .with_parser(KueblerEncoder::new("vcan0", 0x6A, 0xFB), Message::Encoder)
+            .with_parser(KueblerEncoder::new("vcan0", 0x6B, 0xFB), Message::Encoder)
+            .with_parser(KueblerEncoder::new("vcan0", 0x6C, 0xFB), Message::Encoder)
+            .with_parser(J1939ApplicationInspector, |_| Message::Other);
+
+        assert_eq!(parsers.len(), 4);
+
+        let frame = FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .sa(0x6B)
+                .build(),
+        )
+        .copy_from_slice(&[0x54, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+        .build();
+
+        let (idx, message) = parsers.parse(&frame).unwrap();
+        assert_eq!(idx, 1);
+        assert!(matches!(
+            message,
+            Message::Encoder(EncoderMessage::ProcessData(_))
+        ));
+
+        let matches = parsers.parse_all(&frame);
+        assert_eq!(
+            matches.iter().map(|(idx, _)| *idx).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        let frame = FrameBuilder::new(IdBuilder::from_pgn(PGN::TimeDate).sa(0x6B).build())
+            .copy_from_slice(&[0xFF; 8])
+            .build();
+        assert!(ParsableSet::new()
+            .with_parser(KueblerEncoder::new("vcan0", 0x6B, 0xFB), Message::Encoder)
+            .parse(&frame)
+            .is_none());
+    }
 
     #[test]
     fn test_filter_item_matches_1() {
@@ -602,4 +1734,59 @@ mod tests {
         assert!(filter.matches(&id0));
         assert!(!filter.matches(&id1));
     }
+
+    #[test]
+    fn test_filter_can_filters() {
+        use rand::Rng;
+
+        let mut filter = Filter::accept();
+        filter.push(FilterItem::with_pgn(PGN::CruiseControlVehicleSpeed.into()));
+        filter.push(FilterItem::with_source_address(0x6A).set_priority(3));
+        filter.push(FilterItem::with_pgn(0xEF00).set_destination_address(0x4A));
+
+        let can_filters = filter.can_filters().unwrap();
+        assert_eq!(can_filters.len(), 3);
+
+        let mut ids = vec![
+            IdBuilder::from_pgn(PGN::CruiseControlVehicleSpeed)
+                .sa(0x81)
+                .build(),
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_450))
+                .priority(3)
+                .sa(0x6A)
+                .build(),
+            IdBuilder::from_pgn(PGN::ProprietaryA)
+                .sa(0x20)
+                .da(0x4A)
+                .build(),
+            IdBuilder::from_pgn(PGN::ProprietaryA)
+                .sa(0x20)
+                .da(0x4B)
+                .build(),
+        ];
+
+        let mut rng = rand::thread_rng();
+        ids.extend((0..10_000).map(|_| Id::new(rng.gen_range(0..=0x1fff_ffff))));
+
+        for id in ids {
+            let can_id = id.as_raw() | libc::CAN_EFF_FLAG;
+
+            assert_eq!(
+                can_filters.iter().any(|f| f.matches(can_id)),
+                filter.matches(&id),
+                "{:?}",
+                id
+            );
+        }
+
+        let mut filter = Filter::accept();
+        filter.push(FilterItem::with_destination_address(0x4A));
+        assert_eq!(filter.can_filters(), None);
+
+        let mut filter = Filter::reject();
+        filter.push(FilterItem::with_pgn(0xEF00));
+        assert_eq!(filter.can_filters(), None);
+
+        assert_eq!(Filter::accept().can_filters(), None);
+    }
 }
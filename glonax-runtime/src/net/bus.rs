@@ -0,0 +1,329 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::core::{ModuleError, ModuleStatus};
+
+use super::{CANErrorFrame, CANSocket};
+use crate::can::{CAN_ERR_ACK, CAN_ERR_BUSERROR, CAN_ERR_BUSOFF, CAN_ERR_PROT};
+
+/// Source of pending socket errors.
+pub trait ErrorSource {
+    /// Take the pending error, clearing it in the process.
+    fn take_error(&self) -> io::Result<Option<io::Error>>;
+}
+
+impl ErrorSource for CANSocket {
+    fn take_error(&self) -> io::Result<Option<io::Error>> {
+        CANSocket::take_error(self)
+    }
+}
+
+/// Bus error statistics.
+///
+/// The statistics accumulate the errors reported by the socket and the error
+/// frames of the controller. A degrading bus shows up as protocol and
+/// acknowledgement errors before the controller goes bus-off and the network
+/// goes down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BusErrorStats {
+    /// Total number of errors.
+    pub errors: u64,
+    /// Network down events, reported when the controller went bus-off.
+    pub bus_off: u64,
+    /// Transmit queue overflows, frames were not acknowledged in time.
+    pub tx_overflow: u64,
+    /// Error frames for a transmission without acknowledgement.
+    pub ack: u64,
+    /// Error frames for a protocol violation, such as a CRC or stuff error.
+    pub protocol: u64,
+    /// Other errors.
+    pub other: u64,
+}
+
+impl BusErrorStats {
+    /// Record an error.
+    pub fn record(&mut self, error: &io::Error) {
+        self.errors += 1;
+
+        match error.raw_os_error() {
+            Some(libc::ENETDOWN) => self.bus_off += 1,
+            Some(libc::ENOBUFS) => self.tx_overflow += 1,
+            _ => self.other += 1,
+        }
+    }
+
+    /// Record an error frame.
+    pub fn record_frame(&mut self, frame: &CANErrorFrame) {
+        self.errors += 1;
+
+        if frame.class & CAN_ERR_BUSOFF != 0 {
+            self.bus_off += 1;
+        } else if frame.class & CAN_ERR_ACK != 0 {
+            self.ack += 1;
+        } else if frame.class & (CAN_ERR_PROT | CAN_ERR_BUSERROR) != 0 {
+            self.protocol += 1;
+        } else {
+            self.other += 1;
+        }
+    }
+
+    /// Poll the error source for a pending error.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if an error was recorded.
+    pub fn poll(&mut self, source: &impl ErrorSource) -> bool {
+        match source.take_error() {
+            Ok(Some(error)) => {
+                self.record(&error);
+                true
+            }
+            Ok(None) | Err(_) => false,
+        }
+    }
+
+    /// Module status of the bus.
+    ///
+    /// The bus is faulty when it went bus-off and degraded when any other
+    /// error occurred since the `previous` statistics were taken.
+    pub fn status(&self, name: String, previous: &BusErrorStats) -> ModuleStatus {
+        if self.bus_off > previous.bus_off {
+            ModuleStatus::faulty(name, ModuleError::GenericCommunicationError)
+        } else if self.errors > previous.errors {
+            ModuleStatus::degraded(name, None)
+        } else {
+            ModuleStatus::healthy(name)
+        }
+    }
+}
+
+impl std::fmt::Display for BusErrorStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Errors: {} Bus-off: {} Tx overflow: {} Ack: {} Protocol: {} Other: {}",
+            self.errors, self.bus_off, self.tx_overflow, self.ack, self.protocol, self.other
+        )
+    }
+}
+
+/// Minimum time between bus-off recovery attempts.
+const RECOVERY_BACKOFF_MIN: Duration = Duration::from_millis(100);
+/// Maximum time between bus-off recovery attempts.
+const RECOVERY_BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+/// Bus-off recovery.
+///
+/// When the controller went bus-off the socket stops delivering frames. The
+/// recovery schedules attempts to restore the socket, with an exponential
+/// backoff between attempts. The backoff is reset once the bus is known to be
+/// working again.
+#[derive(Clone, Debug)]
+pub struct BusRecovery {
+    /// Bus-off events seen so far.
+    bus_off: u64,
+    /// Time to wait before the next attempt.
+    backoff: Duration,
+    /// Moment of the next attempt, if recovery is pending.
+    next: Option<Instant>,
+    /// Number of attempts since the bus was last working.
+    attempts: u32,
+}
+
+impl Default for BusRecovery {
+    fn default() -> Self {
+        Self {
+            bus_off: 0,
+            backoff: RECOVERY_BACKOFF_MIN,
+            next: None,
+            attempts: 0,
+        }
+    }
+}
+
+impl BusRecovery {
+    /// Test if a recovery is pending.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.next.is_some()
+    }
+
+    /// Moment of the next recovery attempt, if recovery is pending.
+    #[inline]
+    pub fn next_attempt(&self) -> Option<Instant> {
+        self.next
+    }
+
+    /// Number of attempts since the bus was last working.
+    #[inline]
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Update the recovery from the bus error statistics.
+    ///
+    /// A new bus-off event schedules a recovery after the backoff.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if a recovery attempt is due. The next attempt is then
+    /// scheduled with twice the backoff, up to the maximum backoff.
+    pub fn update(&mut self, stats: &BusErrorStats, now: Instant) -> bool {
+        if stats.bus_off > self.bus_off {
+            self.bus_off = stats.bus_off;
+            self.next.get_or_insert(now + self.backoff);
+        }
+
+        match self.next {
+            Some(next) if now >= next => {
+                self.attempts += 1;
+                self.backoff = (self.backoff * 2).min(RECOVERY_BACKOFF_MAX);
+                self.next = Some(now + self.backoff);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark the recovery attempt as succeeded.
+    ///
+    /// The backoff is kept, so that a bus that goes bus-off again right after
+    /// recovery is not hammered with attempts.
+    pub fn restored(&mut self) {
+        self.next = None;
+    }
+
+    /// Mark the bus as working.
+    ///
+    /// This resets the backoff.
+    pub fn working(&mut self) {
+        self.next = None;
+        self.backoff = RECOVERY_BACKOFF_MIN;
+        self.attempts = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque};
+
+    use super::*;
+    use crate::core::ModuleState;
+
+    struct MockSocket(RefCell<VecDeque<i32>>);
+
+    impl ErrorSource for MockSocket {
+        fn take_error(&self) -> io::Result<Option<io::Error>> {
+            Ok(self
+                .0
+                .borrow_mut()
+                .pop_front()
+                .map(io::Error::from_raw_os_error))
+        }
+    }
+
+    #[test]
+    fn bus_error_stats() {
+        let socket = MockSocket(RefCell::new(VecDeque::from([
+            libc::ENOBUFS,
+            libc::ENOBUFS,
+            libc::EIO,
+            libc::ENETDOWN,
+        ])));
+
+        let mut stats = BusErrorStats::default();
+        let mut previous = stats;
+
+        assert!(stats.poll(&socket));
+        assert!(stats.poll(&socket));
+        assert_eq!(stats.tx_overflow, 2);
+        assert_eq!(
+            stats.status("bus".to_string(), &previous).state,
+            ModuleState::Degraded
+        );
+
+        previous = stats;
+        assert!(stats.poll(&socket));
+        assert_eq!(stats.other, 1);
+
+        assert!(stats.poll(&socket));
+        assert_eq!(stats.bus_off, 1);
+        assert_eq!(stats.errors, 4);
+        assert_eq!(
+            stats.status("bus".to_string(), &previous).state,
+            ModuleState::Faulty
+        );
+
+        previous = stats;
+        assert!(!stats.poll(&socket));
+        assert_eq!(stats, previous);
+        assert!(stats.status("bus".to_string(), &previous).is_healthy());
+    }
+
+    #[test]
+    fn bus_error_frames() {
+        let frame = |class| CANErrorFrame {
+            class,
+            data: [0; 8],
+        };
+
+        let mut stats = BusErrorStats::default();
+        let previous = stats;
+
+        stats.record_frame(&frame(CAN_ERR_ACK));
+        stats.record_frame(&frame(CAN_ERR_PROT | CAN_ERR_BUSERROR));
+        assert_eq!(stats.ack, 1);
+        assert_eq!(stats.protocol, 1);
+        assert_eq!(
+            stats.status("bus".to_string(), &previous).state,
+            ModuleState::Degraded
+        );
+
+        stats.record_frame(&frame(CAN_ERR_BUSOFF));
+        assert_eq!(stats.bus_off, 1);
+        assert_eq!(stats.errors, 3);
+        assert_eq!(
+            stats.status("bus".to_string(), &previous).state,
+            ModuleState::Faulty
+        );
+    }
+
+    #[test]
+    fn bus_off_recovery() {
+        let socket = MockSocket(RefCell::new(VecDeque::from([libc::ENETDOWN])));
+
+        let mut stats = BusErrorStats::default();
+        let mut recovery = BusRecovery::default();
+
+        let now = Instant::now();
+        assert!(!recovery.update(&stats, now));
+        assert!(!recovery.is_pending());
+
+        assert!(stats.poll(&socket));
+        assert!(!recovery.update(&stats, now));
+        assert_eq!(recovery.next_attempt(), Some(now + RECOVERY_BACKOFF_MIN));
+
+        let now = now + RECOVERY_BACKOFF_MIN;
+        assert!(recovery.update(&stats, now));
+        assert_eq!(recovery.attempts(), 1);
+        assert!(!recovery.update(&stats, now));
+
+        let now = now + RECOVERY_BACKOFF_MIN * 2;
+        assert!(recovery.update(&stats, now));
+        assert_eq!(recovery.attempts(), 2);
+
+        let mut at = now;
+        for _ in 0..16 {
+            at = recovery.next_attempt().unwrap();
+            assert!(recovery.update(&stats, at));
+        }
+        assert_eq!(recovery.next_attempt().unwrap() - at, RECOVERY_BACKOFF_MAX);
+
+        recovery.working();
+        assert!(!recovery.is_pending());
+        assert_eq!(recovery.attempts(), 0);
+        assert!(!recovery.update(&stats, now));
+    }
+}
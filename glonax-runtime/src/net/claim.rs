@@ -0,0 +1,294 @@
+use std::{io, ops::RangeInclusive, time::Duration};
+
+use j1939::{protocol, Frame, Name, PGN};
+
+use super::ControlNetwork;
+
+/// Time to wait for contending claims after an address is claimed.
+pub const CLAIM_TIMEOUT: Duration = Duration::from_millis(250);
+/// Source address of a node that cannot claim an address.
+pub const ADDRESS_NULL: u8 = 0xFE;
+/// Global destination address.
+const ADDRESS_GLOBAL: u8 = 0xFF;
+/// Addresses available to self-configurable nodes.
+const ARBITRARY_ADDRESS_RANGE: RangeInclusive<u8> = 128..=247;
+
+/// Numeric value of a NAME.
+///
+/// The NAME is compared as a 64-bit number, with the first byte on the wire
+/// as the least significant byte.
+pub fn name_value(name: &Name) -> u64 {
+    u64::from_le_bytes(name.to_bytes())
+}
+
+/// Test if a NAME has priority over another NAME.
+///
+/// The NAME with the lower value wins the arbitration of an address.
+///
+/// # Arguments
+///
+/// * `own` - The NAME of this node.
+/// * `other` - The NAME of the contending node.
+pub fn has_priority(own: u64, other: u64) -> bool {
+    own < other
+}
+
+/// Outcome of a frame received during an address claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contention {
+    /// The frame does not affect the claim.
+    None,
+    /// Another node claimed a different address.
+    Taken(u8),
+    /// Repeat the claim, either to defend the address or to answer a request.
+    Defend,
+    /// The address is lost to a node with a higher priority NAME.
+    Lost,
+}
+
+/// Address claim procedure.
+///
+/// Claims a source address on the network as described by SAE J1939-81. The
+/// claimant sends the Address Claimed message for the preferred address and
+/// listens for contending claims. A contending node with a lower NAME takes
+/// the address, in which case the claimant moves to the next free address if
+/// the NAME allows for arbitrary addresses. The address is held once no
+/// contention is heard for the claim timeout.
+pub struct AddressClaimant {
+    /// NAME of this node.
+    name: Name,
+    /// Preferred source address.
+    preferred: u8,
+    /// Time to wait for contending claims.
+    timeout: Duration,
+}
+
+impl AddressClaimant {
+    /// Construct a new address claimant.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The NAME of this node.
+    /// * `preferred` - The preferred source address.
+    pub fn new(name: Name, preferred: u8) -> Self {
+        Self {
+            name,
+            preferred,
+            timeout: CLAIM_TIMEOUT,
+        }
+    }
+
+    /// Set the time to wait for contending claims.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Evaluate a received frame against the claimed address.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address currently claimed.
+    /// * `frame` - The received frame.
+    pub fn contend(&self, address: u8, frame: &Frame) -> Contention {
+        match frame.id().pgn() {
+            PGN::AddressClaimed if frame.pdu().len() >= 8 => {
+                let other = Name::from_bytes(frame.pdu()[..8].try_into().unwrap());
+                let source = frame.id().source_address();
+
+                if other == self.name || source == ADDRESS_NULL {
+                    Contention::None
+                } else if source != address {
+                    Contention::Taken(source)
+                } else if has_priority(name_value(&self.name), name_value(&other)) {
+                    Contention::Defend
+                } else {
+                    Contention::Lost
+                }
+            }
+            PGN::Request
+                if frame.pdu().len() >= 3
+                    && protocol::request_from_pdu(frame.pdu()) == PGN::AddressClaimed =>
+            {
+                match frame.id().destination_address() {
+                    Some(da) if da != address && da != ADDRESS_GLOBAL => Contention::None,
+                    _ => Contention::Defend,
+                }
+            }
+            _ => Contention::None,
+        }
+    }
+
+    /// Find the next address to claim.
+    ///
+    /// Only a NAME with the arbitrary address capability can move to another
+    /// address.
+    ///
+    /// # Arguments
+    ///
+    /// * `taken` - The addresses claimed by other nodes or lost.
+    ///
+    /// # Returns
+    ///
+    /// The next free address, or `None` if no address is available.
+    pub fn next_address(&self, taken: &[u8]) -> Option<u8> {
+        if !self.name.arbitrary_address {
+            return None;
+        }
+
+        ARBITRARY_ADDRESS_RANGE
+            .into_iter()
+            .find(|address| !taken.contains(address))
+    }
+
+    /// Claim an address on the network.
+    ///
+    /// The network filter must accept the Address Claimed and Request
+    /// messages, contending claims are missed otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `network` - The control network.
+    ///
+    /// # Returns
+    ///
+    /// The address held by this node. Returns an error if no address could be
+    /// claimed, after the Cannot Claim Address message is sent.
+    pub async fn claim(&self, network: &mut ControlNetwork) -> io::Result<u8> {
+        let mut address = self.preferred;
+        let mut taken = Vec::new();
+
+        network
+            .send(&protocol::address_claimed(address, &self.name))
+            .await?;
+
+        let mut deadline = tokio::time::Instant::now() + self.timeout;
+
+        loop {
+            match tokio::time::timeout_at(deadline, network.recv()).await {
+                Ok(result) => result?,
+                Err(_) => return Ok(address),
+            }
+
+            let contention = match network.frame() {
+                Some(frame) => self.contend(address, frame),
+                None => continue,
+            };
+
+            match contention {
+                Contention::None => {}
+                Contention::Taken(source) => {
+                    if !taken.contains(&source) {
+                        taken.push(source);
+                    }
+                }
+                Contention::Defend => {
+                    network
+                        .send(&protocol::address_claimed(address, &self.name))
+                        .await?;
+                }
+                Contention::Lost => {
+                    taken.push(address);
+
+                    let Some(next) = self.next_address(&taken) else {
+                        network
+                            .send(&protocol::address_claimed(ADDRESS_NULL, &self.name))
+                            .await?;
+
+                        return Err(io::Error::new(
+                            io::ErrorKind::AddrInUse,
+                            format!("cannot claim address 0x{:X}", self.preferred),
+                        ));
+                    };
+
+                    log::debug!(
+                        "[{}] Address 0x{:X} lost, claim 0x{:X}",
+                        network.interface(),
+                        address,
+                        next
+                    );
+
+                    address = next;
+                    network
+                        .send(&protocol::address_claimed(address, &self.name))
+                        .await?;
+
+                    deadline = tokio::time::Instant::now() + self.timeout;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use j1939::NameBuilder;
+
+    use super::*;
+
+    fn name(identity_number: u32, arbitrary_address: bool) -> Name {
+        NameBuilder::default()
+            .identity_number(identity_number)
+            .manufacturer_code(0x717)
+            .function(0x1C)
+            .arbitrary_address(arbitrary_address)
+            .build()
+    }
+
+    #[test]
+    fn name_arbitration() {
+        let low = name(0x10, false);
+        let high = name(0x11, false);
+
+        assert!(has_priority(name_value(&low), name_value(&high)));
+        assert!(!has_priority(name_value(&high), name_value(&low)));
+        assert!(!has_priority(name_value(&low), name_value(&low)));
+
+        // The arbitrary address capability is the most significant bit.
+        assert!(has_priority(
+            name_value(&name(0xFFFF, false)),
+            name_value(&name(0x01, true))
+        ));
+
+        let claimant = AddressClaimant::new(low, 0x9E);
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::address_claimed(0x9E, &high)),
+            Contention::Defend
+        );
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::address_claimed(0x80, &high)),
+            Contention::Taken(0x80)
+        );
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::address_claimed(0x9E, &low)),
+            Contention::None
+        );
+
+        let claimant = AddressClaimant::new(high, 0x9E);
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::address_claimed(0x9E, &low)),
+            Contention::Lost
+        );
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::request(0x9E, 0x27, PGN::AddressClaimed)),
+            Contention::Defend
+        );
+        assert_eq!(
+            claimant.contend(0x9E, &protocol::request(0x4A, 0x27, PGN::AddressClaimed)),
+            Contention::None
+        );
+    }
+
+    #[test]
+    fn next_address() {
+        let claimant = AddressClaimant::new(name(0x10, true), 0x9E);
+        assert_eq!(claimant.next_address(&[0x9E]), Some(128));
+        assert_eq!(claimant.next_address(&[0x9E, 128, 129]), Some(130));
+
+        let taken: Vec<u8> = ARBITRARY_ADDRESS_RANGE.collect();
+        assert_eq!(claimant.next_address(&taken), None);
+
+        let claimant = AddressClaimant::new(name(0x10, false), 0x9E);
+        assert_eq!(claimant.next_address(&[0x9E]), None);
+    }
+}
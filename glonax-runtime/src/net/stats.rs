@@ -0,0 +1,346 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// Number of inter-frame arrival intervals kept.
+const INTERVAL_WINDOW: usize = 64;
+/// Smoothing factor of the round-trip time average.
+const RTT_ALPHA: f32 = 0.125;
+/// Smoothing factor of the transmit failure rate.
+const FAILURE_ALPHA: f32 = 0.1;
+/// Global destination address.
+const ADDRESS_GLOBAL: u8 = 0xFF;
+
+/// Network traffic statistics.
+///
+/// The statistics keep the frame and byte counters in both directions, the
+/// most recent inter-frame arrival intervals and a moving average of the
+/// round-trip time. The round-trip time is measured from a frame sent to a
+/// destination address to the next frame received from that address.
+#[derive(Clone, Debug, Default)]
+pub struct TrafficStats {
+    /// Frames received.
+    rx_frames: u64,
+    /// Bytes received.
+    rx_bytes: u64,
+    /// Frames sent.
+    tx_frames: u64,
+    /// Bytes sent.
+    tx_bytes: u64,
+    /// Frames that failed to send.
+    tx_failures: u64,
+    /// Moment of the last received frame.
+    last_rx: Option<Instant>,
+    /// Most recent inter-frame arrival intervals.
+    intervals: VecDeque<Duration>,
+    /// Unanswered frames by destination address.
+    pending: HashMap<u8, Instant>,
+    /// Moving average of the round-trip time.
+    rtt: Option<Duration>,
+    /// Moving average of the transmit failure rate.
+    failure_rate: f32,
+}
+
+impl TrafficStats {
+    /// Record a received frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The source address of the frame.
+    /// * `len` - The frame length in bytes.
+    /// * `now` - The moment the frame was received.
+    pub fn record_rx(&mut self, source: u8, len: usize, now: Instant) {
+        self.rx_frames += 1;
+        self.rx_bytes += len as u64;
+
+        if let Some(last) = self.last_rx.replace(now) {
+            if self.intervals.len() == INTERVAL_WINDOW {
+                self.intervals.pop_front();
+            }
+            self.intervals
+                .push_back(now.saturating_duration_since(last));
+        }
+
+        if let Some(sent) = self.pending.remove(&source) {
+            let sample = now.saturating_duration_since(sent);
+
+            self.rtt = Some(match self.rtt {
+                Some(rtt) => rtt.mul_f32(1.0 - RTT_ALPHA) + sample.mul_f32(RTT_ALPHA),
+                None => sample,
+            });
+        }
+    }
+
+    /// Record a sent frame.
+    ///
+    /// A frame to a destination address starts a round-trip measurement,
+    /// unless an earlier frame to that address is still unanswered.
+    ///
+    /// # Arguments
+    ///
+    /// * `destination` - The destination address of the frame, if any.
+    /// * `len` - The frame length in bytes.
+    /// * `now` - The moment the frame was sent.
+    pub fn record_tx(&mut self, destination: Option<u8>, len: usize, now: Instant) {
+        self.tx_frames += 1;
+        self.tx_bytes += len as u64;
+        self.failure_rate *= 1.0 - FAILURE_ALPHA;
+
+        if let Some(destination) = destination.filter(|da| *da != ADDRESS_GLOBAL) {
+            self.pending.entry(destination).or_insert(now);
+        }
+    }
+
+    /// Record a frame that failed to send.
+    pub fn record_tx_failure(&mut self) {
+        self.tx_failures += 1;
+        self.failure_rate = self.failure_rate * (1.0 - FAILURE_ALPHA) + FAILURE_ALPHA;
+    }
+
+    /// Mean inter-frame arrival interval over the recent frames.
+    pub fn interval_mean(&self) -> Option<Duration> {
+        if self.intervals.is_empty() {
+            return None;
+        }
+
+        Some(self.intervals.iter().sum::<Duration>() / self.intervals.len() as u32)
+    }
+
+    /// Received frames per second over the recent frames.
+    pub fn frame_rate(&self) -> f32 {
+        match self.interval_mean() {
+            Some(mean) if !mean.is_zero() => 1.0 / mean.as_secs_f32(),
+            _ => 0.0,
+        }
+    }
+
+    /// Moving average of the round-trip time.
+    #[inline]
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Moving average of the transmit failure rate, between 0 and 1.
+    #[inline]
+    pub fn failure_rate(&self) -> f32 {
+        self.failure_rate
+    }
+
+    /// Report of the statistics.
+    pub fn report(&self) -> TrafficReport {
+        TrafficReport {
+            rx_frames: self.rx_frames,
+            rx_bytes: self.rx_bytes,
+            tx_frames: self.tx_frames,
+            tx_bytes: self.tx_bytes,
+            tx_failures: self.tx_failures,
+            frame_rate: self.frame_rate(),
+            interval_ms: self.interval_mean().map(|d| d.as_secs_f32() * 1_000.0),
+            rtt_ms: self.rtt.map(|d| d.as_secs_f32() * 1_000.0),
+            failure_rate: self.failure_rate,
+        }
+    }
+}
+
+impl std::fmt::Display for TrafficStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.report())
+    }
+}
+
+/// Report of the network traffic statistics.
+#[derive(Clone, Debug, PartialEq, serde_derive::Serialize)]
+pub struct TrafficReport {
+    /// Frames received.
+    pub rx_frames: u64,
+    /// Bytes received.
+    pub rx_bytes: u64,
+    /// Frames sent.
+    pub tx_frames: u64,
+    /// Bytes sent.
+    pub tx_bytes: u64,
+    /// Frames that failed to send.
+    pub tx_failures: u64,
+    /// Received frames per second.
+    pub frame_rate: f32,
+    /// Mean inter-frame arrival interval in milliseconds.
+    pub interval_ms: Option<f32>,
+    /// Moving average of the round-trip time in milliseconds.
+    pub rtt_ms: Option<f32>,
+    /// Moving average of the transmit failure rate.
+    pub failure_rate: f32,
+}
+
+impl TrafficReport {
+    /// Serialize the report as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+impl std::fmt::Display for TrafficReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rx: {} ({} bytes) Tx: {} ({} bytes) Failures: {} Rate: {:.1} fps",
+            self.rx_frames,
+            self.rx_bytes,
+            self.tx_frames,
+            self.tx_bytes,
+            self.tx_failures,
+            self.frame_rate
+        )?;
+
+        if let Some(rtt_ms) = self.rtt_ms {
+            write!(f, " RTT: {:.1} ms", rtt_ms)?;
+        }
+
+        write!(f, " Failure rate: {:.1}%", self.failure_rate * 100.0)
+    }
+}
+
+/// Router statistics.
+///
+/// Counts the frames received on all networks of a router, the frames
+/// rejected by the router filter and the frames per PGN. The PGN counters
+/// are kept for every received frame, including the filtered frames.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RouterStats {
+    /// Frames received.
+    pub rx_total: u64,
+    /// Frames rejected by the router filter.
+    pub rx_filtered: u64,
+    /// Frames received per PGN.
+    pub pgn: HashMap<u32, u64>,
+}
+
+impl RouterStats {
+    /// Record a received frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `pgn` - The PGN of the frame.
+    /// * `accepted` - Whether the frame is accepted by the router filter.
+    pub fn record(&mut self, pgn: u32, accepted: bool) {
+        self.rx_total += 1;
+        if !accepted {
+            self.rx_filtered += 1;
+        }
+
+        *self.pgn.entry(pgn).or_default() += 1;
+    }
+
+    /// Number of frames received with a PGN.
+    pub fn pgn_count(&self, pgn: u32) -> u64 {
+        self.pgn.get(&pgn).copied().unwrap_or(0)
+    }
+
+    /// PGNs ordered by the number of frames, most frequent first.
+    pub fn histogram(&self) -> Vec<(u32, u64)> {
+        let mut histogram: Vec<_> = self.pgn.iter().map(|(pgn, count)| (*pgn, *count)).collect();
+        histogram.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        histogram
+    }
+}
+
+impl std::fmt::Display for RouterStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rx: {} Filtered: {} PGNs: {}",
+            self.rx_total,
+            self.rx_filtered,
+            self.pgn.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rtt(stats: &TrafficStats, expected: Duration) {
+        let rtt = stats.rtt().unwrap();
+        assert!(
+            rtt.abs_diff(expected) < Duration::from_micros(1),
+            "{:?}",
+            rtt
+        );
+    }
+
+    #[test]
+    fn traffic_intervals() {
+        let start = Instant::now();
+        let mut stats = TrafficStats::default();
+
+        assert_eq!(stats.interval_mean(), None);
+        assert_eq!(stats.frame_rate(), 0.0);
+
+        // A slow start which falls out of the window.
+        stats.record_rx(0x4A, 8, start);
+        stats.record_rx(0x4A, 8, start + Duration::from_secs(1));
+
+        let mut at = start + Duration::from_secs(1);
+        for _ in 0..INTERVAL_WINDOW {
+            at += Duration::from_millis(10);
+            stats.record_rx(0x4A, 8, at);
+        }
+
+        assert_eq!(stats.interval_mean(), Some(Duration::from_millis(10)));
+        assert!((stats.frame_rate() - 100.0).abs() < 1e-3);
+
+        let report = stats.report();
+        assert_eq!(report.rx_frames, INTERVAL_WINDOW as u64 + 2);
+        assert_eq!(report.rx_bytes, (INTERVAL_WINDOW as u64 + 2) * 8);
+        assert_eq!(report.rtt_ms, None);
+    }
+
+    #[test]
+    fn traffic_rtt() {
+        let start = Instant::now();
+        let mut stats = TrafficStats::default();
+
+        stats.record_tx(Some(0x4A), 8, start);
+        // Unrelated and global frames do not complete the measurement.
+        stats.record_rx(0x6A, 8, start + Duration::from_millis(1));
+        stats.record_tx(Some(ADDRESS_GLOBAL), 8, start + Duration::from_millis(2));
+        stats.record_tx(Some(0x4A), 8, start + Duration::from_millis(3));
+        stats.record_rx(0x4A, 8, start + Duration::from_millis(4));
+        assert_rtt(&stats, Duration::from_millis(4));
+
+        // No frame pending, the average is unchanged.
+        stats.record_rx(0x4A, 8, start + Duration::from_millis(6));
+        assert_rtt(&stats, Duration::from_millis(4));
+
+        stats.record_tx(Some(0x4A), 8, start + Duration::from_millis(10));
+        stats.record_rx(0x4A, 8, start + Duration::from_millis(22));
+        assert_rtt(&stats, Duration::from_millis(5));
+
+        let report = stats.report();
+        assert_eq!(report.tx_frames, 4);
+        assert_eq!(report.tx_bytes, 32);
+        assert!((report.rtt_ms.unwrap() - 5.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn traffic_failure_rate() {
+        let mut stats = TrafficStats::default();
+        let now = Instant::now();
+
+        stats.record_tx_failure();
+        assert!((stats.failure_rate() - 0.1).abs() < 1e-6);
+        stats.record_tx_failure();
+        assert!((stats.failure_rate() - 0.19).abs() < 1e-6);
+
+        for _ in 0..100 {
+            stats.record_tx(None, 8, now);
+        }
+        assert!(stats.failure_rate() < 1e-4);
+        assert_eq!(stats.report().tx_failures, 2);
+
+        let line = stats.to_string();
+        assert!(line.starts_with("Rx: 0 (0 bytes) Tx: 100 (800 bytes) Failures: 2"));
+        assert!(stats.report().to_json().contains("\"tx_failures\":2"));
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use j1939::{Frame, PGN};
+
+use super::ConnectionManagement;
+
+/// Time after which an inactive session is dropped.
+pub const SESSION_TIMEOUT: Duration = Duration::from_millis(750);
+/// Maximum message size of the transport protocol.
+const DATA_MAX_LENGTH: usize = 1785;
+/// Number of data bytes per data transfer packet.
+const DATA_FRAME_SIZE: usize = 7;
+/// Global destination address.
+const ADDRESS_GLOBAL: u8 = 0xFF;
+
+/// Message reassembled from a transport protocol session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LongMessage {
+    /// Parameter group number of the message.
+    pub pgn: PGN,
+    /// Source address.
+    pub source_address: u8,
+    /// Destination address, `None` for a broadcast message.
+    pub destination_address: Option<u8>,
+    /// Message data.
+    pub data: Vec<u8>,
+}
+
+impl LongMessage {
+    /// Frame identifier of the message.
+    ///
+    /// The identifier is used to match the message against a filter.
+    pub fn id(&self) -> j1939::Id {
+        let mut id = j1939::IdBuilder::from_pgn(self.pgn).sa(self.source_address);
+        if let Some(da) = self.destination_address {
+            id = id.da(da);
+        }
+
+        id.build()
+    }
+}
+
+/// Open transport protocol session.
+struct Session {
+    /// Parameter group number of the message.
+    pgn: PGN,
+    /// Message size in bytes.
+    size: usize,
+    /// Number of packets in the message.
+    packets: u8,
+    /// Sequence number of the next packet.
+    sequence: u8,
+    /// Data received so far.
+    data: Vec<u8>,
+    /// Moment of the last packet.
+    last: Instant,
+}
+
+/// Transport protocol reassembly.
+///
+/// Reassembles the broadcast (BAM) and connection mode (RTS/CTS) messages
+/// larger than a single frame. The reassembly is passive, it never sends a
+/// clear to send. Sessions are keyed by source and destination address, so a
+/// node can have a broadcast and a connection mode session open at the same
+/// time. A packet out of sequence aborts the session, as does a session that
+/// is inactive for longer than [`SESSION_TIMEOUT`].
+#[derive(Default)]
+pub struct Reassembler {
+    sessions: HashMap<(u8, u8), Session>,
+}
+
+impl Reassembler {
+    /// Number of open sessions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Test if there are no open sessions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Drop the sessions that are inactive for too long.
+    pub fn expire(&mut self, now: Instant) {
+        self.sessions
+            .retain(|_, session| now.saturating_duration_since(session.last) < SESSION_TIMEOUT);
+    }
+
+    /// Feed a frame to the reassembly.
+    ///
+    /// Frames other than the transport protocol frames are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The received frame.
+    /// * `now` - The moment the frame was received.
+    ///
+    /// # Returns
+    ///
+    /// The message if the frame completed a session.
+    pub fn feed(&mut self, frame: &Frame, now: Instant) -> Option<LongMessage> {
+        self.expire(now);
+
+        let source_address = frame.id().source_address();
+        let destination_address = frame.id().destination_address().unwrap_or(ADDRESS_GLOBAL);
+        let key = (source_address, destination_address);
+
+        match frame.id().pgn() {
+            PGN::TransportProtocolConnectionManagement => {
+                self.connection_management(key, frame.pdu(), now);
+                None
+            }
+            PGN::TransportProtocolDataTransfer => {
+                let pdu = frame.pdu();
+                if pdu.is_empty() {
+                    return None;
+                }
+
+                let session = self.sessions.get_mut(&key)?;
+                if pdu[0] != session.sequence {
+                    self.sessions.remove(&key);
+                    return None;
+                }
+
+                session.data.extend_from_slice(&pdu[1..]);
+                session.sequence = session.sequence.wrapping_add(1);
+                session.last = now;
+
+                if session.sequence <= session.packets {
+                    return None;
+                }
+
+                let mut session = self.sessions.remove(&key)?;
+                session.data.truncate(session.size);
+
+                Some(LongMessage {
+                    pgn: session.pgn,
+                    source_address,
+                    destination_address: Some(destination_address)
+                        .filter(|da| *da != ADDRESS_GLOBAL),
+                    data: session.data,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn connection_management(&mut self, key: (u8, u8), pdu: &[u8], now: Instant) {
+        if pdu.len() < 8 {
+            return;
+        }
+
+        let control = pdu[0];
+
+        if control == ConnectionManagement::Abort as u8 {
+            self.sessions.remove(&key);
+            // The abort can be sent by either side of the connection.
+            self.sessions.remove(&(key.1, key.0));
+            return;
+        }
+
+        let is_bam = control == ConnectionManagement::BroadcastAnnounceMessage as u8;
+        let is_rts = control == ConnectionManagement::RequestToSend as u8;
+
+        // A broadcast announce must be global, a request to send must not.
+        if !((is_bam && key.1 == ADDRESS_GLOBAL) || (is_rts && key.1 != ADDRESS_GLOBAL)) {
+            return;
+        }
+
+        let size = u16::from_le_bytes([pdu[1], pdu[2]]) as usize;
+        let packets = pdu[3];
+
+        if size <= 8 || size > DATA_MAX_LENGTH || packets as usize != size.div_ceil(DATA_FRAME_SIZE)
+        {
+            self.sessions.remove(&key);
+            return;
+        }
+
+        // A new announcement replaces any open session.
+        self.sessions.insert(
+            key,
+            Session {
+                pgn: PGN::from_le_bytes([pdu[5], pdu[6], pdu[7]]),
+                size,
+                packets,
+                sequence: 1,
+                data: Vec::with_capacity(packets as usize * DATA_FRAME_SIZE),
+                last: now,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use j1939::{FrameBuilder, IdBuilder};
+
+    use super::*;
+
+    fn broadcast(sa: u8, pgn: PGN, data: &[u8]) -> Vec<Frame> {
+        let mut transport = j1939::transport::BroadcastTransport::new(sa, pgn).with_data(data);
+
+        (0..=transport.packet_count())
+            .map(|_| transport.next_frame())
+            .collect()
+    }
+
+    fn abort(sa: u8, da: u8, pgn: PGN) -> Frame {
+        let pgn = pgn.to_le_bytes();
+
+        FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::TransportProtocolConnectionManagement)
+                .sa(sa)
+                .da(da)
+                .build(),
+        )
+        .copy_from_slice(&[0xFF, 0x01, 0xFF, 0xFF, 0xFF, pgn[0], pgn[1], pgn[2]])
+        .build()
+    }
+
+    #[test]
+    fn reassemble_interleaved() {
+        let now = Instant::now();
+
+        let data_bam: Vec<u8> = (0..23).collect();
+        let data_rts: Vec<u8> = (100..130).collect();
+
+        let bam = broadcast(0x20, PGN::SoftwareIdentification, &data_bam);
+        let rts =
+            super::super::destination_specific(0x4A, 0x30, PGN::ComponentIdentification, &data_rts);
+        assert_eq!(bam.len(), 5);
+        assert_eq!(rts.len(), 6);
+
+        let mut reassembler = Reassembler::default();
+        let mut messages = vec![];
+
+        for idx in 0..rts.len() {
+            for frame in [bam.get(idx), rts.get(idx)].into_iter().flatten() {
+                messages.extend(reassembler.feed(frame, now));
+            }
+        }
+
+        assert_eq!(messages.len(), 2);
+        assert!(reassembler.is_empty());
+
+        assert_eq!(messages[0].pgn, PGN::SoftwareIdentification);
+        assert_eq!(messages[0].source_address, 0x20);
+        assert_eq!(messages[0].destination_address, None);
+        assert_eq!(messages[0].data, data_bam);
+
+        assert_eq!(messages[1].pgn, PGN::ComponentIdentification);
+        assert_eq!(messages[1].source_address, 0x30);
+        assert_eq!(messages[1].destination_address, Some(0x4A));
+        assert_eq!(messages[1].data, data_rts);
+    }
+
+    #[test]
+    fn reassemble_abort() {
+        let now = Instant::now();
+        let data: Vec<u8> = (0..23).collect();
+        let frames = broadcast(0x20, PGN::SoftwareIdentification, &data);
+
+        let mut reassembler = Reassembler::default();
+
+        // Duplicate packet aborts the session.
+        assert!(reassembler.feed(&frames[0], now).is_none());
+        assert!(reassembler.feed(&frames[1], now).is_none());
+        assert!(reassembler.feed(&frames[1], now).is_none());
+        assert!(reassembler.is_empty());
+        assert!(frames[2..]
+            .iter()
+            .all(|frame| reassembler.feed(frame, now).is_none()));
+
+        // Out of order packet aborts the session.
+        assert!(reassembler.feed(&frames[0], now).is_none());
+        assert!(reassembler.feed(&frames[2], now).is_none());
+        assert!(reassembler.is_empty());
+
+        // Abort by the sender.
+        assert!(reassembler.feed(&frames[0], now).is_none());
+        assert!(reassembler
+            .feed(&abort(0x20, 0xFF, PGN::SoftwareIdentification), now)
+            .is_none());
+        assert!(reassembler.is_empty());
+
+        // Restart after an abort.
+        let message = frames
+            .iter()
+            .find_map(|frame| reassembler.feed(frame, now))
+            .unwrap();
+        assert_eq!(message.data, data);
+    }
+
+    #[test]
+    fn reassemble_timeout() {
+        let now = Instant::now();
+        let data: Vec<u8> = (0..23).collect();
+        let frames = broadcast(0x20, PGN::SoftwareIdentification, &data);
+
+        let mut reassembler = Reassembler::default();
+        assert!(reassembler.feed(&frames[0], now).is_none());
+        assert!(reassembler.feed(&frames[1], now).is_none());
+
+        // Inactive just below the timeout, the session continues.
+        let now = now + SESSION_TIMEOUT - Duration::from_millis(1);
+        assert!(reassembler.feed(&frames[2], now).is_none());
+        assert_eq!(reassembler.len(), 1);
+
+        let now = now + SESSION_TIMEOUT;
+        assert!(reassembler.feed(&frames[3], now).is_none());
+        assert!(reassembler.feed(&frames[4], now).is_none());
+        assert!(reassembler.is_empty());
+    }
+}
@@ -33,12 +33,14 @@ use crate::protocol::{frame, Stream};
 ///     Ok(())
 /// }
 /// ```
+#[derive(Clone)]
 pub struct ClientBuilder {
     session_name: String,
     control: bool,
     command: bool,
     failsafe: bool,
     stream: bool,
+    features: Option<frame::Features>,
 }
 
 impl ClientBuilder {
@@ -65,6 +67,7 @@ impl ClientBuilder {
             command: false,
             failsafe: false,
             stream: false,
+            features: None,
         }
     }
 
@@ -152,6 +155,43 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets the protocol features the client supports.
+    ///
+    /// When set, the client negotiates features with the server during the
+    /// handshake. The negotiated features are the features both sides support
+    /// and are available from `Stream::features`. A server that does not
+    /// negotiate features is treated as a server without optional features.
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - A bitmask of `frame::Features` flags.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use glonax::protocol::{client::ClientBuilder, frame::Features};
+    ///
+    /// let session_name = "my_session";
+    ///
+    /// let builder = ClientBuilder::new(session_name)
+    ///     .features(Features::KEEPALIVE | Features::SUBSCRIBE);
+    /// ```
+    pub fn features(mut self, features: u8) -> Self {
+        self.features = Some(frame::Features::new(features));
+        self
+    }
+
+    async fn handshake<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin>(
+        &self,
+        client: &mut Stream<T>,
+        flags: u8,
+    ) -> std::io::Result<crate::core::Instance> {
+        match self.features {
+            Some(features) => client.negotiate(&self.session_name, flags, features).await,
+            None => client.handshake(&self.session_name, flags).await,
+        }
+    }
+
     /// Establishes a connection to the server and returns a `Stream` and `Instance`.
     ///
     /// # Returns
@@ -220,7 +260,7 @@ impl ClientBuilder {
 
         let mut client = Stream::new(stream);
 
-        let instance = client.handshake(self.session_name, flags).await?;
+        let instance = self.handshake(&mut client, flags).await?;
 
         Ok((client, instance))
     }
@@ -246,7 +286,7 @@ impl ClientBuilder {
         let stream = UnixStream::connect(path).await?;
         let mut client = Stream::new(stream);
 
-        let instance = client.handshake(self.session_name, flags).await?;
+        let instance = self.handshake(&mut client, flags).await?;
 
         Ok((client, instance))
     }
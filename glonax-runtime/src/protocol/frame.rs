@@ -50,8 +50,11 @@ enum FrameMessage {
     Error = 0x0,
     _Echo = 0x1,
     Session = 0x10,
-    _Shutdown = 0x11,
+    Shutdown = 0x11,
     Request = 0x12,
+    Features = 0x13,
+    MultiRequest = 0x14,
+    Resume = 0x18,
 }
 
 #[derive(Debug)]
@@ -78,6 +81,15 @@ impl Frame {
         }
     }
 
+    /// Construct a frame containing a packet.
+    pub fn from_packet<P: super::Packetize>(packet: &P) -> Self {
+        let payload = packet.to_bytes();
+
+        let mut frame = Self::new(P::MESSAGE_TYPE, payload.len());
+        frame.put(&payload[..]);
+        frame
+    }
+
     #[inline]
     pub fn put(&mut self, payload: &[u8]) {
         self.buffer.put(payload);
@@ -142,6 +154,7 @@ impl Session {
     pub const MODE_STREAM: u8 = 0b0000_0001;
     pub const MODE_CONTROL: u8 = 0b0000_0010;
     pub const MODE_COMMAND: u8 = 0b0000_0100;
+    pub const MODE_NEGOTIATE: u8 = 0b0000_1000;
     pub const MODE_FAILSAFE: u8 = 0b0001_0000;
 
     // TODO: Convert mode to enum
@@ -172,16 +185,30 @@ impl Session {
         self.flags & Self::MODE_FAILSAFE != 0
     }
 
+    /// Test if the client wants to negotiate features.
+    ///
+    /// Servers that do not know this flag ignore it, which is what allows
+    /// a new client to talk to an old server.
+    #[inline]
+    pub fn is_negotiate(&self) -> bool {
+        self.flags & Self::MODE_NEGOTIATE != 0
+    }
+
+    #[inline]
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
     #[inline]
     pub fn name(&self) -> &str {
         &self.name
     }
 }
 
-impl TryFrom<Vec<u8>> for Session {
+impl TryFrom<&[u8]> for Session {
     type Error = FrameError;
 
-    fn try_from(buffer: Vec<u8>) -> Result<Self, Self::Error> {
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
         if buffer.is_empty() {
             Err(FrameError::FrameTooSmall)?
         }
@@ -202,6 +229,14 @@ impl TryFrom<Vec<u8>> for Session {
     }
 }
 
+impl TryFrom<Vec<u8>> for Session {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl super::Packetize for Session {
     const MESSAGE_TYPE: u8 = FrameMessage::Session as u8;
 
@@ -218,20 +253,35 @@ impl super::Packetize for Session {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SessionError {
     UnknownRequest = 0x0,
     UnknownMessage = 0x1,
     UnauthorizedControl = 0x2,
     UnauthorizedCommand = 0x3,
+    InvalidSession = 0x4,
+    IncompatibleVersion = 0x5,
 }
 
 impl SessionError {}
 
-impl TryFrom<Vec<u8>> for SessionError {
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::UnknownRequest => write!(f, "unknown request"),
+            Self::UnknownMessage => write!(f, "unknown message"),
+            Self::UnauthorizedControl => write!(f, "unauthorized control"),
+            Self::UnauthorizedCommand => write!(f, "unauthorized command"),
+            Self::InvalidSession => write!(f, "invalid session"),
+            Self::IncompatibleVersion => write!(f, "incompatible protocol version"),
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for SessionError {
     type Error = FrameError;
 
-    fn try_from(buffer: Vec<u8>) -> Result<Self, Self::Error> {
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
         if buffer.is_empty() {
             Err(FrameError::FrameTooSmall)?
         }
@@ -241,22 +291,78 @@ impl TryFrom<Vec<u8>> for SessionError {
             0x1 => Ok(Self::UnknownMessage),
             0x2 => Ok(Self::UnauthorizedControl),
             0x3 => Ok(Self::UnauthorizedCommand),
+            0x4 => Ok(Self::InvalidSession),
+            0x5 => Ok(Self::IncompatibleVersion),
             _ => Err(FrameError::InvalidMessage(buffer[0])),
         }
     }
 }
 
+impl TryFrom<Vec<u8>> for SessionError {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl super::Packetize for SessionError {
     const MESSAGE_TYPE: u8 = FrameMessage::Error as u8;
     const MESSAGE_SIZE: Option<usize> = Some(std::mem::size_of::<u8>());
 
     fn to_bytes(&self) -> Vec<u8> {
-        match self {
-            Self::UnknownRequest => vec![0x0],
-            Self::UnknownMessage => vec![0x1],
-            Self::UnauthorizedControl => vec![0x2],
-            Self::UnauthorizedCommand => vec![0x3],
-        }
+        vec![*self as u8]
+    }
+}
+
+/// Handshake rejected by the server.
+///
+/// This error is returned as the inner error of the `std::io::Error` from
+/// the handshake when the server refuses the session.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::protocol::frame::{HandshakeRejected, SessionError};
+///
+/// let error: std::io::Error = HandshakeRejected::new(SessionError::InvalidSession).into();
+///
+/// let rejected = HandshakeRejected::from_io_error(&error).unwrap();
+/// assert_eq!(rejected.reason, SessionError::InvalidSession);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandshakeRejected {
+    /// Reason of the rejection.
+    pub reason: SessionError,
+}
+
+impl HandshakeRejected {
+    /// Construct a new handshake rejection.
+    pub fn new(reason: SessionError) -> Self {
+        Self { reason }
+    }
+
+    /// Retrieve the handshake rejection from an I/O error.
+    ///
+    /// # Returns
+    ///
+    /// The handshake rejection, or `None` if the error is not a rejection.
+    pub fn from_io_error(error: &std::io::Error) -> Option<&Self> {
+        error.get_ref().and_then(|e| e.downcast_ref::<Self>())
+    }
+}
+
+impl std::error::Error for HandshakeRejected {}
+
+impl std::fmt::Display for HandshakeRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "handshake rejected: {}", self.reason)
+    }
+}
+
+impl From<HandshakeRejected> for std::io::Error {
+    fn from(value: HandshakeRejected) -> Self {
+        std::io::Error::new(std::io::ErrorKind::ConnectionRefused, value)
     }
 }
 
@@ -275,10 +381,10 @@ impl Request {
     }
 }
 
-impl TryFrom<Vec<u8>> for Request {
+impl TryFrom<&[u8]> for Request {
     type Error = FrameError;
 
-    fn try_from(buffer: Vec<u8>) -> Result<Self, Self::Error> {
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
         if buffer.is_empty() {
             Err(FrameError::FrameTooSmall)?
         }
@@ -287,6 +393,14 @@ impl TryFrom<Vec<u8>> for Request {
     }
 }
 
+impl TryFrom<Vec<u8>> for Request {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
 impl super::Packetize for Request {
     const MESSAGE_TYPE: u8 = FrameMessage::Request as u8;
     const MESSAGE_SIZE: Option<usize> = Some(std::mem::size_of::<u8>());
@@ -296,6 +410,257 @@ impl super::Packetize for Request {
     }
 }
 
+/// Protocol features.
+///
+/// Features are exchanged as a bitmask during the handshake. Both sides only
+/// use the features they have in common. A peer that does not negotiate has
+/// no optional features, so new features never break an older peer.
+///
+/// The negotiation is initiated by the client by setting the
+/// [`Session::MODE_NEGOTIATE`] flag on the session. A server that understands
+/// the flag announces its features before the instance, the client then
+/// answers with its own features. An old server ignores the flag, the client
+/// then receives the instance first, sends no features and falls back to no
+/// features.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    mask: u8,
+}
+
+impl Features {
+    pub const COMPRESSION: u8 = 0b0000_0001;
+    pub const TLS: u8 = 0b0000_0010;
+    pub const KEEPALIVE: u8 = 0b0000_0100;
+    pub const SUBSCRIBE: u8 = 0b0000_1000;
+
+    /// Features implemented by this runtime.
+    pub const SUPPORTED: u8 = 0;
+
+    /// Construct features from a bitmask.
+    ///
+    /// Unknown bits are kept, they are removed by the intersection with
+    /// the features of the peer.
+    pub fn new(mask: u8) -> Self {
+        Self { mask }
+    }
+
+    /// Features as a bitmask.
+    #[inline]
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Test if all features in `feature` are present.
+    #[inline]
+    pub fn contains(&self, feature: u8) -> bool {
+        self.mask & feature == feature
+    }
+
+    /// Features that are present in both sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use glonax::protocol::frame::Features;
+    ///
+    /// let client = Features::new(Features::COMPRESSION | Features::KEEPALIVE);
+    /// let server = Features::new(Features::KEEPALIVE | Features::SUBSCRIBE);
+    ///
+    /// assert_eq!(client.intersection(&server), Features::new(Features::KEEPALIVE));
+    /// ```
+    #[inline]
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.mask & other.mask)
+    }
+}
+
+impl std::fmt::Display for Features {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut features = Vec::new();
+
+        if self.contains(Self::COMPRESSION) {
+            features.push("compression");
+        }
+        if self.contains(Self::TLS) {
+            features.push("tls");
+        }
+        if self.contains(Self::KEEPALIVE) {
+            features.push("keepalive");
+        }
+        if self.contains(Self::SUBSCRIBE) {
+            features.push("subscribe");
+        }
+
+        if features.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", features.join(", "))
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for Features {
+    type Error = FrameError;
+
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        if buffer.is_empty() {
+            Err(FrameError::FrameTooSmall)?
+        }
+
+        Ok(Self::new(buffer[0]))
+    }
+}
+
+impl TryFrom<Vec<u8>> for Features {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl super::Packetize for Features {
+    const MESSAGE_TYPE: u8 = FrameMessage::Features as u8;
+    const MESSAGE_SIZE: Option<usize> = Some(std::mem::size_of::<u8>());
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![self.mask]
+    }
+}
+
+/// Request for multiple messages in a single round-trip.
+///
+/// The server answers with all requested messages in the order of the
+/// request. A message that cannot be provided is answered with an error in
+/// its place, so the response always contains one frame per request.
+pub struct MultiRequest {
+    messages: Vec<u8>,
+}
+
+impl MultiRequest {
+    pub fn new(messages: Vec<u8>) -> Self {
+        Self { messages }
+    }
+
+    #[inline]
+    pub fn messages(&self) -> &[u8] {
+        &self.messages
+    }
+}
+
+impl TryFrom<&[u8]> for MultiRequest {
+    type Error = FrameError;
+
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        if buffer.is_empty() {
+            Err(FrameError::FrameTooSmall)?
+        }
+
+        Ok(Self::new(buffer.to_vec()))
+    }
+}
+
+impl TryFrom<Vec<u8>> for MultiRequest {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl super::Packetize for MultiRequest {
+    const MESSAGE_TYPE: u8 = FrameMessage::MultiRequest as u8;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.messages.clone()
+    }
+}
+
+/// Session goodbye.
+///
+/// Sent by the client before it closes the connection, so the server can end
+/// the session without waiting for the connection to drop.
+#[derive(Debug)]
+pub struct Shutdown;
+
+impl TryFrom<&[u8]> for Shutdown {
+    type Error = FrameError;
+
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        if buffer.is_empty() {
+            Err(FrameError::FrameTooSmall)?
+        }
+
+        Ok(Self)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Shutdown {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl super::Packetize for Shutdown {
+    const MESSAGE_TYPE: u8 = FrameMessage::Shutdown as u8;
+    const MESSAGE_SIZE: Option<usize> = Some(std::mem::size_of::<u8>());
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![0x0]
+    }
+}
+
+/// Session resume token.
+///
+/// The token is issued by the server on request. A client that reconnects
+/// sends the token instead of a new session, the server then restores the
+/// session flags and features of the previous connection. The server expires
+/// the token when the client did not reconnect within the resume window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ResumeToken {
+    token: [u8; 16],
+}
+
+impl ResumeToken {
+    pub fn new(token: [u8; 16]) -> Self {
+        Self { token }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.token
+    }
+}
+
+impl TryFrom<&[u8]> for ResumeToken {
+    type Error = FrameError;
+
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        let token = buffer.try_into().map_err(|_| FrameError::FrameTooSmall)?;
+
+        Ok(Self::new(token))
+    }
+}
+
+impl TryFrom<Vec<u8>> for ResumeToken {
+    type Error = FrameError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
+    }
+}
+
+impl super::Packetize for ResumeToken {
+    const MESSAGE_TYPE: u8 = FrameMessage::Resume as u8;
+    const MESSAGE_SIZE: Option<usize> = Some(16);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.token.to_vec()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -391,6 +756,35 @@ mod tests {
         assert_eq!(session.unwrap_err(), FrameError::InvalidSessionFlags);
     }
 
+    #[test]
+    fn test_session_negotiate() {
+        use crate::protocol::Packetize;
+
+        let session = Session::new(
+            Session::MODE_STREAM | Session::MODE_NEGOTIATE,
+            "test".to_string(),
+        );
+
+        let session = Session::try_from(session.to_bytes()).unwrap();
+
+        assert!(session.is_stream());
+        assert!(session.is_negotiate());
+        assert_eq!(session.name(), "test");
+    }
+
+    #[test]
+    fn test_features() {
+        use crate::protocol::Packetize;
+
+        let features = Features::new(Features::TLS | Features::SUBSCRIBE);
+        let features = Features::try_from(features.to_bytes()).unwrap();
+
+        assert!(features.contains(Features::TLS));
+        assert!(!features.contains(Features::TLS | Features::COMPRESSION));
+        assert_eq!(features.to_string(), "tls, subscribe");
+        assert_eq!(Features::default().to_string(), "none");
+    }
+
     #[test]
     fn test_session_frame_too_small() {
         let session = Session::try_from(Vec::new());
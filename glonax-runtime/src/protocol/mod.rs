@@ -3,6 +3,7 @@ use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 // TODO: Should not be public
 pub mod client;
 pub mod frame;
+pub mod pool;
 
 // TODO: Maybe move up
 pub use client::{connect, connect_safe, unix_connect, unix_connect_safe};
@@ -22,7 +23,11 @@ const PROTO_HEADER: [u8; 3] = [b'L', b'X', b'R'];
 /// is only  changed when the protocol is changed in a way that is not backwards
 /// compatible. This is done to ensure that the protocol can be changed without
 /// breaking existing implementations.
-const PROTO_VERSION: u8 = 0x03;
+///
+/// Since version 4 every message starts with its own schema version, see
+/// [`crate::core::codec`]. Fields appended to a message change the schema
+/// version of the message, not the protocol version.
+const PROTO_VERSION: u8 = 0x04;
 
 /// The minimum buffer size required to read a frame.
 const PROTO_BUFFER_SIZE: usize = PROTO_HEADER.len()
@@ -41,16 +46,23 @@ const PROTO_BUFFER_SIZE: usize = PROTO_HEADER.len()
 /// and to reject packets that are too large.
 const MAX_PAYLOAD_SIZE: usize = 1_024;
 
+/// Maximum time to wait for the server to end the session on close.
+const CLOSE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// A packet that can be sent over the network.
 ///
 /// This trait is implemented for all packets that can be sent over the network.
-pub trait Packetize: TryFrom<Vec<u8>> + Sized {
+/// Packets are parsed from a borrowed slice, so that a received payload does
+/// not need to be copied into an owned buffer first.
+pub trait Packetize: for<'a> TryFrom<&'a [u8]> + Sized {
     /// The message type of the packet.
     const MESSAGE_TYPE: u8;
     /// If the packet has a fixed size, this is the size of the packet. If the
     /// packet has a variable size, this is `None`.
     ///
     /// This is used to validate the size of the packet when receiving a packet.
+    /// A larger packet is accepted, the trailing bytes hold fields of a later
+    /// schema version and are ignored.
     const MESSAGE_SIZE: Option<usize> = None;
 
     /// Convert packet to bytes.
@@ -59,11 +71,30 @@ pub trait Packetize: TryFrom<Vec<u8>> + Sized {
 
 pub struct Stream<T> {
     inner: T,
+    features: frame::Features,
+    /// Payload buffer, reused for every received packet.
+    buffer: Vec<u8>,
 }
 
 impl<T> Stream<T> {
     pub fn new(inner: T) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            features: frame::Features::default(),
+            buffer: Vec::with_capacity(MAX_PAYLOAD_SIZE),
+        }
+    }
+
+    /// Features negotiated with the peer.
+    #[inline]
+    pub fn features(&self) -> frame::Features {
+        self.features
+    }
+
+    /// Set the features negotiated with the peer.
+    #[inline]
+    pub fn set_features(&mut self, features: frame::Features) {
+        self.features = features;
     }
 
     #[inline]
@@ -86,7 +117,176 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Stream<T> {
         self.send_packet(&frame::Session::new(flags, session_name.to_string()))
             .await?;
 
+        self.recv_instance().await
+    }
+
+    /// Perform the handshake and negotiate features with the server.
+    ///
+    /// The negotiated features are the features supported by both the client
+    /// and the server. A server that does not negotiate features is treated
+    /// as a server without optional features. The client only sends its
+    /// features after the server announced its own, so a server that does not
+    /// negotiate never receives a message it does not understand.
+    ///
+    /// # Arguments
+    ///
+    /// * `session_name` - The name of the session.
+    /// * `flags` - The session flags.
+    /// * `features` - The features supported by the client.
+    ///
+    /// # Returns
+    ///
+    /// The server instance. The negotiated features are available from
+    /// [`Stream::features`].
+    pub async fn negotiate(
+        &mut self,
+        session_name: impl ToString,
+        flags: u8,
+        features: frame::Features,
+    ) -> std::io::Result<crate::core::Instance> {
+        self.send_packet(&frame::Session::new(
+            flags | frame::Session::MODE_NEGOTIATE,
+            session_name.to_string(),
+        ))
+        .await?;
+
+        let frame = self.read_handshake_frame().await?;
+        match frame.message {
+            frame::Features::MESSAGE_TYPE => {
+                let server_features = self
+                    .recv_packet::<frame::Features>(frame.payload_length)
+                    .await?;
+
+                let instance = self.recv_instance().await?;
+
+                self.send_packet(&features).await?;
+                self.features = features.intersection(&server_features);
+
+                Ok(instance)
+            }
+            crate::core::Instance::MESSAGE_TYPE => {
+                self.features = frame::Features::default();
+
+                self.recv_instance_frame(frame).await
+            }
+            _ => {
+                // Keep the stream aligned before the handshake is rejected.
+                self.skip_packet(frame.payload_length).await?;
+
+                Err(frame::HandshakeRejected::new(frame::SessionError::IncompatibleVersion).into())
+            }
+        }
+    }
+
+    /// Request a resume token for the current session.
+    ///
+    /// This must be called right after the handshake, before any other
+    /// request is made.
+    pub async fn request_resume_token(&mut self) -> std::io::Result<frame::ResumeToken> {
+        self.send_request(frame::ResumeToken::MESSAGE_TYPE).await?;
+
         let frame = self.read_frame().await?;
+        if frame.message != frame::ResumeToken::MESSAGE_TYPE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid response from server",
+            ));
+        }
+
+        self.recv_packet::<frame::ResumeToken>(frame.payload_length)
+            .await
+    }
+
+    /// Resume a previous session.
+    ///
+    /// The resume replaces the handshake on a new connection. The session
+    /// flags and features of the previous session are restored by the
+    /// server. An unknown or expired token, or a token whose session is
+    /// still active, is rejected with a `HandshakeRejected` error, the client
+    /// should then perform a new handshake.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The resume token issued for the previous session.
+    ///
+    /// # Returns
+    ///
+    /// The server instance.
+    pub async fn resume(
+        &mut self,
+        token: &frame::ResumeToken,
+    ) -> std::io::Result<crate::core::Instance> {
+        self.send_packet(token).await?;
+
+        self.recv_instance().await
+    }
+
+    /// Close the session.
+    ///
+    /// A goodbye is sent to the server, after which the server ends the
+    /// session and closes the connection. This method waits until the server
+    /// closed the connection, or until the close timeout expired. Any frames
+    /// received in the meantime are discarded.
+    pub async fn close(&mut self) -> std::io::Result<()> {
+        self.send_packet(&frame::Shutdown).await?;
+        self.inner.flush().await?;
+
+        let drain = async {
+            while let Ok(frame) = self.read_frame().await {
+                if self.skip_packet(frame.payload_length).await.is_err() {
+                    break;
+                }
+            }
+        };
+
+        if tokio::time::timeout(CLOSE_TIMEOUT, drain).await.is_err() {
+            log::debug!("Server did not close the session in time");
+        }
+
+        self.inner.shutdown().await
+    }
+
+    async fn recv_instance(&mut self) -> std::io::Result<crate::core::Instance> {
+        let frame = self.read_handshake_frame().await?;
+        self.recv_instance_frame(frame).await
+    }
+
+    /// Read a frame during the handshake.
+    ///
+    /// An error frame from the server or a frame with another protocol
+    /// version is returned as a `HandshakeRejected` error.
+    async fn read_handshake_frame(&mut self) -> std::io::Result<frame::Frame> {
+        let frame = match self.read_frame().await {
+            Ok(frame) => frame,
+            Err(e) => {
+                if let Some(frame::FrameError::VersionMismatch(_)) =
+                    e.get_ref().and_then(|e| e.downcast_ref())
+                {
+                    return Err(frame::HandshakeRejected::new(
+                        frame::SessionError::IncompatibleVersion,
+                    )
+                    .into());
+                }
+
+                return Err(e);
+            }
+        };
+
+        if frame.message == frame::SessionError::MESSAGE_TYPE {
+            let reason = self
+                .recv_packet::<frame::SessionError>(frame.payload_length)
+                .await?;
+
+            return Err(frame::HandshakeRejected::new(reason).into());
+        }
+
+        Ok(frame)
+    }
+
+    async fn recv_instance_frame(
+        &mut self,
+        frame: frame::Frame,
+    ) -> std::io::Result<crate::core::Instance> {
         if frame.message != crate::core::Instance::MESSAGE_TYPE {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -104,18 +304,36 @@ impl<T: AsyncWrite + AsyncRead + Unpin> Stream<T> {
 
 impl<T: AsyncWrite + Unpin> Stream<T> {
     pub async fn send_packet<P: Packetize>(&mut self, packet: &P) -> std::io::Result<()> {
-        let payload = packet.to_bytes();
-
-        let mut frame = frame::Frame::new(P::MESSAGE_TYPE, payload.len());
-        frame.put(&payload[..]);
+        let frame = frame::Frame::from_packet(packet);
 
         self.inner.write_all(frame.as_ref()).await
     }
 
+    /// Send multiple frames in a single write.
+    pub async fn send_frames(&mut self, frames: &[frame::Frame]) -> std::io::Result<()> {
+        let buffer = frames
+            .iter()
+            .flat_map(|frame| frame.as_ref())
+            .copied()
+            .collect::<Vec<_>>();
+
+        self.inner.write_all(&buffer).await
+    }
+
     #[inline]
     pub async fn send_request(&mut self, frame_message: u8) -> std::io::Result<()> {
         self.send_packet(&frame::Request::new(frame_message)).await
     }
+
+    /// Request multiple messages in a single round-trip.
+    ///
+    /// The server answers with one frame per requested message, in the
+    /// order of the request.
+    #[inline]
+    pub async fn send_multi_request(&mut self, frame_messages: &[u8]) -> std::io::Result<()> {
+        self.send_packet(&frame::MultiRequest::new(frame_messages.to_vec()))
+            .await
+    }
 }
 
 impl<T: AsyncRead + Unpin> Stream<T> {
@@ -124,14 +342,39 @@ impl<T: AsyncRead + Unpin> Stream<T> {
 
         self.inner.read_exact(&mut header_buffer).await?;
 
-        frame::Frame::try_from(&header_buffer[..]).map_err(|e| {
-            std::io::Error::new(
+        frame::Frame::try_from(&header_buffer[..])
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Read and discard a packet.
+    pub async fn skip_packet(&mut self, size: usize) -> std::io::Result<()> {
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
-                format!("Failed to parse frame: {}", e),
-            )
-        })
+                format!("Invalid data, packet size too large: {}", size),
+            ));
+        }
+
+        self.read_payload(size).await?;
+
+        Ok(())
     }
 
+    /// Read a payload into the stream buffer.
+    ///
+    /// The buffer is allocated once with the maximum payload size, so reading
+    /// a payload does not allocate.
+    async fn read_payload(&mut self, size: usize) -> std::io::Result<&[u8]> {
+        self.buffer.resize(size, 0);
+        self.inner.read_exact(&mut self.buffer).await?;
+
+        Ok(&self.buffer)
+    }
+
+    /// Receive a packet.
+    ///
+    /// The payload is read into the stream buffer and the packet is parsed from
+    /// the borrowed buffer. No allocation is made for the payload itself.
     pub async fn recv_packet<P: Packetize>(&mut self, size: usize) -> std::io::Result<P> {
         if size == 0 {
             return Err(std::io::Error::new(
@@ -140,11 +383,11 @@ impl<T: AsyncRead + Unpin> Stream<T> {
             ));
         }
 
-        if P::MESSAGE_SIZE.is_some() && size != P::MESSAGE_SIZE.unwrap() {
+        if P::MESSAGE_SIZE.is_some() && size < P::MESSAGE_SIZE.unwrap() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!(
-                    "Invalid packet size: expected {}, got {}",
+                    "Invalid packet size: expected at least {}, got {}",
                     P::MESSAGE_SIZE.unwrap(),
                     size
                 ),
@@ -158,13 +401,7 @@ impl<T: AsyncRead + Unpin> Stream<T> {
             ));
         }
 
-        let buffer = {
-            let payload_buffer = &mut vec![0u8; size];
-
-            self.inner.read_exact(payload_buffer).await?;
-
-            payload_buffer.clone()
-        };
+        let buffer = self.read_payload(size).await?;
 
         P::try_from(buffer).map_err(|_| {
             std::io::Error::new(std::io::ErrorKind::InvalidData, "Failed to parse packet")
@@ -183,11 +420,369 @@ mod tests {
 
     #[test]
     fn test_proto_version() {
-        assert_eq!(PROTO_VERSION, 0x03);
+        assert_eq!(PROTO_VERSION, 0x04);
     }
 
     #[test]
     fn test_proto_buffer_size() {
         assert_eq!(PROTO_BUFFER_SIZE, 10);
     }
+
+    fn instance() -> crate::core::Instance {
+        crate::core::Instance::new(
+            "d55bcd75-8d30-49af-ac18-ee7cbce7822f",
+            "test",
+            crate::core::MachineType::Excavator,
+            (3, 5, 13),
+            "ABC123",
+        )
+    }
+
+    /// Server that predates feature negotiation.
+    ///
+    /// This is the frame handling of the server before feature negotiation.
+    /// Unknown session flags are ignored. An unknown message is rejected
+    /// without reading its payload, so an unknown message would misalign the
+    /// stream.
+    async fn legacy_server(
+        mut server: Stream<tokio::io::DuplexStream>,
+    ) -> Vec<crate::core::Control> {
+        use crate::core::Control;
+
+        let mut controls = vec![];
+
+        while let Ok(frame) = server.read_frame().await {
+            match frame.message {
+                frame::Session::MESSAGE_TYPE => {
+                    server
+                        .recv_packet::<frame::Session>(frame.payload_length)
+                        .await
+                        .unwrap();
+
+                    server.send_packet(&instance()).await.unwrap();
+                }
+                Control::MESSAGE_TYPE => {
+                    controls.push(
+                        server
+                            .recv_packet::<Control>(frame.payload_length)
+                            .await
+                            .unwrap(),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        controls
+    }
+
+    /// Server that negotiates features.
+    async fn server(
+        mut server: Stream<tokio::io::DuplexStream>,
+        features: frame::Features,
+    ) -> frame::Features {
+        let frame = server.read_frame().await.unwrap();
+        let session = server
+            .recv_packet::<frame::Session>(frame.payload_length)
+            .await
+            .unwrap();
+
+        if session.is_negotiate() {
+            server.send_packet(&features).await.unwrap();
+        }
+        server.send_packet(&instance()).await.unwrap();
+
+        if session.is_negotiate() {
+            let frame = server.read_frame().await.unwrap();
+            let client_features = server
+                .recv_packet::<frame::Features>(frame.payload_length)
+                .await
+                .unwrap();
+
+            server.set_features(client_features.intersection(&features));
+        }
+
+        server.features()
+    }
+
+    #[tokio::test]
+    async fn negotiate_features() {
+        let pairs = [
+            (
+                frame::Features::COMPRESSION | frame::Features::KEEPALIVE,
+                frame::Features::KEEPALIVE | frame::Features::SUBSCRIBE,
+                frame::Features::KEEPALIVE,
+            ),
+            (
+                frame::Features::TLS,
+                frame::Features::COMPRESSION | frame::Features::SUBSCRIBE,
+                0,
+            ),
+            (0xFF, frame::Features::TLS, frame::Features::TLS),
+        ];
+
+        for (client_features, server_features, expected) in pairs {
+            let (a, b) = tokio::io::duplex(1_024);
+            let mut client = Stream::new(a);
+
+            let server_task = tokio::spawn(server(
+                Stream::new(b),
+                frame::Features::new(server_features),
+            ));
+
+            let instance = client
+                .negotiate("test", 0, frame::Features::new(client_features))
+                .await
+                .unwrap();
+
+            assert_eq!(instance, self::instance());
+            assert_eq!(client.features(), frame::Features::new(expected));
+            assert_eq!(server_task.await.unwrap(), frame::Features::new(expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn negotiate_legacy_server() {
+        use crate::core::Control;
+
+        let (a, b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+
+        let server_task = tokio::spawn(legacy_server(Stream::new(b)));
+
+        let instance = client
+            .negotiate("test", 0, frame::Features::new(0xFF))
+            .await
+            .unwrap();
+
+        assert_eq!(instance, self::instance());
+        assert_eq!(client.features(), frame::Features::default());
+
+        // The stream is still aligned after the handshake.
+        client.send_packet(&Control::LoadTare).await.unwrap();
+        client
+            .send_packet(&Control::MachineHorn(true))
+            .await
+            .unwrap();
+        drop(client);
+
+        assert_eq!(
+            server_task.await.unwrap(),
+            vec![Control::LoadTare, Control::MachineHorn(true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn negotiate_unknown_response() {
+        let (a, b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+        let mut server = Stream::new(b);
+
+        let server_task = tokio::spawn(async move {
+            let frame = server.read_frame().await.unwrap();
+            server.skip_packet(frame.payload_length).await.unwrap();
+
+            server
+                .send_packet(&crate::core::Control::LoadTare)
+                .await
+                .unwrap();
+            server.send_packet(&instance()).await.unwrap();
+        });
+
+        let err = client
+            .negotiate("test", 0, frame::Features::new(0xFF))
+            .await
+            .unwrap_err();
+        let rejected = frame::HandshakeRejected::from_io_error(&err).unwrap();
+        assert_eq!(rejected.reason, frame::SessionError::IncompatibleVersion);
+
+        // The unknown response is discarded, the next frame is intact.
+        server_task.await.unwrap();
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame.message, crate::core::Instance::MESSAGE_TYPE);
+    }
+
+    /// Server that rejects the session.
+    async fn rejecting_server(
+        mut server: Stream<tokio::io::DuplexStream>,
+        reason: frame::SessionError,
+    ) {
+        let frame = server.read_frame().await.unwrap();
+        server
+            .recv_packet::<frame::Session>(frame.payload_length)
+            .await
+            .unwrap();
+
+        server.send_packet(&reason).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn handshake_rejected() {
+        let reasons = [
+            frame::SessionError::UnknownRequest,
+            frame::SessionError::UnknownMessage,
+            frame::SessionError::UnauthorizedControl,
+            frame::SessionError::UnauthorizedCommand,
+            frame::SessionError::InvalidSession,
+            frame::SessionError::IncompatibleVersion,
+        ];
+
+        for reason in reasons {
+            let (a, b) = tokio::io::duplex(1_024);
+            let mut client = Stream::new(a);
+
+            let server_task = tokio::spawn(rejecting_server(Stream::new(b), reason));
+
+            let error = client.handshake("test", 0).await.unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::ConnectionRefused);
+
+            let rejected = frame::HandshakeRejected::from_io_error(&error).unwrap();
+            assert_eq!(rejected.reason, reason);
+
+            server_task.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn handshake_version_mismatch() {
+        let (a, mut b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+
+        let mut frame = frame::Frame::new(crate::core::Instance::MESSAGE_TYPE, 1);
+        frame.put(&[0]);
+
+        let mut buffer = frame.as_ref().to_vec();
+        buffer[3] = PROTO_VERSION + 1;
+        b.write_all(&buffer).await.unwrap();
+
+        let error = client.handshake("test", 0).await.unwrap_err();
+
+        let rejected = frame::HandshakeRejected::from_io_error(&error).unwrap();
+        assert_eq!(rejected.reason, frame::SessionError::IncompatibleVersion);
+    }
+
+    #[tokio::test]
+    async fn handshake_invalid_response() {
+        let (a, b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+        let mut server = Stream::new(b);
+
+        server
+            .send_packet(&frame::Request::new(0x15))
+            .await
+            .unwrap();
+
+        let error = client.handshake("test", 0).await.unwrap_err();
+
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(frame::HandshakeRejected::from_io_error(&error).is_none());
+    }
+
+    #[tokio::test]
+    async fn close_timeout() {
+        let (a, _b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+
+        // The peer never closes the connection.
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn negotiate_legacy_client() {
+        let (a, b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+
+        let server_task = tokio::spawn(server(
+            Stream::new(b),
+            frame::Features::new(frame::Features::KEEPALIVE),
+        ));
+
+        let instance = client.handshake("test", 0).await.unwrap();
+
+        assert_eq!(instance, self::instance());
+        assert_eq!(client.features(), frame::Features::default());
+        assert_eq!(server_task.await.unwrap(), frame::Features::default());
+    }
+
+    #[tokio::test]
+    async fn recv_packet_split_frame() {
+        use tokio::io::AsyncWriteExt;
+
+        let (mut a, b) = tokio::io::duplex(1_024);
+        let mut server = Stream::new(b);
+
+        let engine = crate::core::Engine::from_rpm(1_500);
+        let frame = frame::Frame::from_packet(&engine);
+
+        // The frame is split inside the header, the remainder arrives later.
+        let (head, tail) = frame.as_ref().split_at(5);
+        a.write_all(head).await.unwrap();
+
+        let writer = tokio::spawn({
+            let tail = tail.to_vec();
+            async move {
+                tokio::task::yield_now().await;
+                a.write_all(&tail).await.unwrap();
+                a
+            }
+        });
+
+        let frame = server.read_frame().await.unwrap();
+        let packet = server
+            .recv_packet::<crate::core::Engine>(frame.payload_length)
+            .await
+            .unwrap();
+
+        assert_eq!(packet, engine);
+
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn recv_packet_small_reads() {
+        use crate::core::{Actuator, Engine, Motion};
+
+        let engines = (0..4)
+            .map(|i| Engine::from_rpm(800 + i * 100))
+            .collect::<Vec<_>>();
+        let motion = Motion::new(Actuator::Boom, 12_000i16);
+
+        for chunk_size in [1, 3, 7] {
+            // The duplex buffer size limits the bytes available per read.
+            let (a, b) = tokio::io::duplex(chunk_size);
+            let mut client = Stream::new(a);
+            let mut server = Stream::new(b);
+
+            let writer = tokio::spawn({
+                let engines = engines.clone();
+                let motion = motion.clone();
+                async move {
+                    for engine in &engines {
+                        client.send_packet(engine).await.unwrap();
+                        client.send_packet(&motion).await.unwrap();
+                    }
+                }
+            });
+
+            for engine in &engines {
+                let frame = server.read_frame().await.unwrap();
+                assert_eq!(frame.message, Engine::MESSAGE_TYPE);
+                let packet = server
+                    .recv_packet::<Engine>(frame.payload_length)
+                    .await
+                    .unwrap();
+                assert_eq!(&packet, engine);
+
+                let frame = server.read_frame().await.unwrap();
+                assert_eq!(frame.message, Motion::MESSAGE_TYPE);
+                let packet = server
+                    .recv_packet::<Motion>(frame.payload_length)
+                    .await
+                    .unwrap();
+                assert_eq!(packet, motion);
+            }
+
+            writer.await.unwrap();
+        }
+    }
 }
@@ -0,0 +1,327 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpStream, UnixStream},
+};
+
+use crate::{
+    core::Instance,
+    protocol::{client::ClientBuilder, Packetize, Stream},
+};
+
+/// Default time after which an unused connection is closed.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum time to wait for a pooled connection to respond.
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct PooledConnection<T> {
+    stream: Stream<T>,
+    instance: Instance,
+    last_used: Instant,
+}
+
+/// A pool of client connections.
+///
+/// The pool keeps one connection per target, so that tools issuing many
+/// short requests do not pay the connection and handshake cost on each
+/// request. A pooled connection is validated before it is reused by
+/// requesting the instance from the server. Connections that were not used
+/// within the idle timeout are closed.
+///
+/// # Example
+///
+/// ```no_run
+/// use glonax::protocol::{client::ClientBuilder, pool::ClientPool};
+///
+/// #[tokio::main]
+/// async fn main() -> std::io::Result<()> {
+///     let mut pool = ClientPool::new(ClientBuilder::new("my_session"));
+///
+///     let (stream, instance) = pool.unix_get("/tmp/glonax.sock").await?;
+///
+///     // Use the `stream` and `instance` here...
+///
+///     Ok(())
+/// }
+/// ```
+pub struct ClientPool<T> {
+    builder: ClientBuilder,
+    idle_timeout: Duration,
+    connections: HashMap<String, PooledConnection<T>>,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> ClientPool<T> {
+    /// Construct a new empty pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `builder` - The client builder used for new connections.
+    pub fn new(builder: ClientBuilder) -> Self {
+        Self {
+            builder,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Set the time after which an unused connection is closed.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Number of pooled connections.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.connections.len()
+    }
+
+    /// Test if the pool has no connections.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Close the connection to a target.
+    pub fn remove(&mut self, target: &str) {
+        self.connections.remove(target);
+    }
+
+    /// Close all connections that exceeded the idle timeout.
+    pub fn evict_idle(&mut self) {
+        let idle_timeout = self.idle_timeout;
+
+        self.connections
+            .retain(|_, connection| connection.last_used.elapsed() < idle_timeout);
+    }
+
+    /// Take a pooled connection if it is still alive.
+    async fn take(&mut self, target: &str) -> Option<PooledConnection<T>> {
+        self.evict_idle();
+
+        let mut connection = self.connections.remove(target)?;
+
+        match tokio::time::timeout(VALIDATE_TIMEOUT, Self::validate(&mut connection.stream)).await {
+            Ok(Ok(instance)) if instance == connection.instance => Some(connection),
+            Ok(Ok(_)) => {
+                log::debug!("Pooled connection to {} changed instance", target);
+                None
+            }
+            Ok(Err(e)) => {
+                log::debug!("Pooled connection to {} failed: {}", target, e);
+                None
+            }
+            Err(_) => {
+                log::debug!("Pooled connection to {} timed out", target);
+                None
+            }
+        }
+    }
+
+    /// Request the instance from the server.
+    ///
+    /// Signals sent by the server in stream mode are skipped.
+    async fn validate(stream: &mut Stream<T>) -> std::io::Result<Instance> {
+        stream.send_request(Instance::MESSAGE_TYPE).await?;
+
+        loop {
+            let frame = stream.read_frame().await?;
+            if frame.message == Instance::MESSAGE_TYPE {
+                return stream.recv_packet::<Instance>(frame.payload_length).await;
+            }
+
+            stream.skip_packet(frame.payload_length).await?;
+        }
+    }
+
+    fn insert(
+        &mut self,
+        target: String,
+        mut connection: PooledConnection<T>,
+    ) -> (&mut Stream<T>, &Instance) {
+        connection.last_used = Instant::now();
+
+        let connection = self
+            .connections
+            .entry(target)
+            .insert_entry(connection)
+            .into_mut();
+
+        (&mut connection.stream, &connection.instance)
+    }
+}
+
+impl ClientPool<UnixStream> {
+    /// Retrieve a connection to a unix domain socket.
+    ///
+    /// A pooled connection is reused if it is still alive, otherwise a new
+    /// connection is established.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the unix domain socket.
+    ///
+    /// # Returns
+    ///
+    /// The stream and the server instance.
+    pub async fn unix_get(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<(&mut Stream<UnixStream>, &Instance)> {
+        let target = path.as_ref().display().to_string();
+
+        let connection = match self.take(&target).await {
+            Some(connection) => connection,
+            None => {
+                let (stream, instance) = self.builder.clone().unix_connect(path).await?;
+
+                PooledConnection {
+                    stream,
+                    instance,
+                    last_used: Instant::now(),
+                }
+            }
+        };
+
+        Ok(self.insert(target, connection))
+    }
+}
+
+impl ClientPool<TcpStream> {
+    /// Retrieve a connection to a network address.
+    ///
+    /// A pooled connection is reused if it is still alive, otherwise a new
+    /// connection is established.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address to connect to.
+    ///
+    /// # Returns
+    ///
+    /// The stream and the server instance.
+    pub async fn get(
+        &mut self,
+        address: &str,
+    ) -> std::io::Result<(&mut Stream<TcpStream>, &Instance)> {
+        let connection = match self.take(address).await {
+            Some(connection) => connection,
+            None => {
+                let (stream, instance) = self.builder.clone().connect(address).await?;
+
+                PooledConnection {
+                    stream,
+                    instance,
+                    last_used: Instant::now(),
+                }
+            }
+        };
+
+        Ok(self.insert(address.to_string(), connection))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::{core::MachineType, protocol::frame};
+
+    fn instance() -> Instance {
+        Instance::new(
+            "d55bcd75-8d30-49af-ac18-ee7cbce7822f",
+            "test",
+            MachineType::Excavator,
+            (3, 5, 13),
+            "ABC123",
+        )
+    }
+
+    fn listen(name: &str) -> (std::path::PathBuf, Arc<AtomicUsize>) {
+        let path =
+            std::env::temp_dir().join(format!("glonax-{}-{}.sock", name, std::process::id()));
+        if path.exists() {
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+
+        let connections_server = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                connections_server.fetch_add(1, Ordering::SeqCst);
+
+                tokio::spawn(async move {
+                    let mut server = Stream::new(stream);
+
+                    while let Ok(frame) = server.read_frame().await {
+                        match frame.message {
+                            frame::Session::MESSAGE_TYPE => {
+                                server
+                                    .recv_packet::<frame::Session>(frame.payload_length)
+                                    .await
+                                    .unwrap();
+                            }
+                            _ => {
+                                server
+                                    .recv_packet::<frame::Request>(frame.payload_length)
+                                    .await
+                                    .unwrap();
+                            }
+                        }
+
+                        server.send_packet(&instance()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (path, connections)
+    }
+
+    #[tokio::test]
+    async fn pool_reuse() {
+        let (path, connections) = listen("pool-reuse");
+
+        let mut pool = ClientPool::new(ClientBuilder::new("test"));
+
+        let (_, instance) = pool.unix_get(&path).await.unwrap();
+        assert_eq!(instance, &self::instance());
+
+        let (_, instance) = pool.unix_get(&path).await.unwrap();
+        assert_eq!(instance, &self::instance());
+
+        assert_eq!(pool.len(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn pool_idle_eviction() {
+        let (path, connections) = listen("pool-idle");
+
+        let mut pool =
+            ClientPool::new(ClientBuilder::new("test")).with_idle_timeout(Duration::ZERO);
+
+        pool.unix_get(&path).await.unwrap();
+        pool.unix_get(&path).await.unwrap();
+
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        pool.evict_idle();
+        assert!(pool.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
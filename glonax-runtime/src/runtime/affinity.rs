@@ -0,0 +1,69 @@
+use std::{future::Future, io, sync::Arc};
+
+/// Function that pins the current thread to a CPU core.
+pub(super) type PinFn = Arc<dyn Fn(usize) -> io::Result<()> + Send + Sync>;
+
+/// Pin the current thread to a CPU core.
+///
+/// # Arguments
+///
+/// * `core` - The CPU core index.
+///
+/// # Returns
+///
+/// Returns an error if the core does not exist or the thread is not allowed
+/// to run on the core.
+pub fn pin_current_thread(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("CPU core {} out of range", core),
+        ));
+    }
+
+    unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        libc::CPU_SET(core, &mut set);
+
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a future on a dedicated thread pinned to a CPU core.
+///
+/// The thread runs its own current-thread executor, so the future is never
+/// preempted by tasks on the shared executor. If pinning fails the future
+/// still runs, unpinned. The returned future completes when the thread
+/// finishes and resumes a panic of the pinned future.
+pub(super) fn spawn_pinned<F: Future<Output = ()> + Send + 'static>(
+    name: String,
+    core: usize,
+    pin: PinFn,
+    f: F,
+) -> impl Future<Output = ()> + Send {
+    let thread = std::thread::Builder::new()
+        .name(format!("pinned-{}", core))
+        .spawn(move || {
+            match pin(core) {
+                Ok(_) => log::debug!("Pinned '{}' to CPU core {}", name, core),
+                Err(e) => log::warn!("Failed to pin '{}' to CPU core {}: {}", name, core, e),
+            }
+
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build pinned executor")
+                .block_on(f);
+        })
+        .expect("failed to spawn pinned thread");
+
+    async move {
+        if let Ok(Err(panic)) = tokio::task::spawn_blocking(move || thread.join()).await {
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+struct MockTime {
+    /// Moment the mock clock was created.
+    base: Instant,
+    /// Time advanced since the base in nanoseconds.
+    offset: AtomicU64,
+}
+
+/// Runtime clock.
+///
+/// The clock is either the system clock or a mock clock. A mock clock only
+/// moves when it is explicitly advanced, so that timeouts and expiries can be
+/// tested deterministically without real sleeps. Clones of a mock clock share
+/// the same time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use glonax::runtime::Clock;
+///
+/// let clock = Clock::mock();
+///
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(5));
+///
+/// assert_eq!(clock.elapsed(start), Duration::from_secs(5));
+/// ```
+#[derive(Clone, Default)]
+pub struct Clock {
+    mock: Option<Arc<MockTime>>,
+}
+
+impl Clock {
+    /// Construct the system clock.
+    pub fn system() -> Self {
+        Self { mock: None }
+    }
+
+    /// Construct a mock clock.
+    pub fn mock() -> Self {
+        Self {
+            mock: Some(Arc::new(MockTime {
+                base: Instant::now(),
+                offset: AtomicU64::new(0),
+            })),
+        }
+    }
+
+    /// Test if this is a mock clock.
+    #[inline]
+    pub fn is_mock(&self) -> bool {
+        self.mock.is_some()
+    }
+
+    /// Current moment in time.
+    pub fn now(&self) -> Instant {
+        match &self.mock {
+            Some(mock) => mock.base + Duration::from_nanos(mock.offset.load(Ordering::SeqCst)),
+            None => Instant::now(),
+        }
+    }
+
+    /// Time elapsed since an earlier moment.
+    ///
+    /// Returns zero if the moment is in the future.
+    #[inline]
+    pub fn elapsed(&self, since: Instant) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+
+    /// Advance the mock clock.
+    ///
+    /// # Panics
+    ///
+    /// The system clock cannot be advanced.
+    pub fn advance(&self, duration: Duration) {
+        let mock = self.mock.as_ref().expect("system clock cannot be advanced");
+
+        mock.offset
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl std::fmt::Debug for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_mock() {
+            write!(f, "Clock(mock)")
+        } else {
+            write!(f, "Clock(system)")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::NetDriverContext;
+
+    #[test]
+    fn mock_clock_timeout() {
+        let clock = Clock::mock();
+
+        let ctx = NetDriverContext::default();
+        ctx.set_clock(clock.clone());
+
+        let timeout = Duration::from_secs(3600);
+        assert!(!ctx.is_rx_timeout(timeout));
+
+        clock.advance(timeout);
+        assert!(!ctx.is_rx_timeout(timeout));
+
+        clock.advance(Duration::from_millis(1));
+        assert!(ctx.is_rx_timeout(timeout));
+
+        ctx.rx_mark();
+        assert!(!ctx.is_rx_timeout(timeout));
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use crate::core::ModuleStatus;
+
+/// Minimum time between two deadline reports.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Deadline-miss detector.
+///
+/// Measures the execution time of each tick of a service pipeline. A tick
+/// misses its deadline if it takes longer than the tick interval plus the
+/// component delay threshold. Misses are reported as a degraded module
+/// status, at most once per report interval.
+#[derive(Clone, Debug)]
+pub struct DeadlineMonitor {
+    /// Tick deadline.
+    deadline: Duration,
+    /// Total number of ticks.
+    ticks: u64,
+    /// Total number of deadline misses.
+    misses: u64,
+    /// Longest tick execution time.
+    max_execution: Duration,
+    /// Number of misses at the last report.
+    reported_misses: u64,
+    /// Moment of the last report.
+    reported: Option<Instant>,
+}
+
+impl DeadlineMonitor {
+    /// Construct a new deadline monitor.
+    ///
+    /// The deadline is the tick interval with the component delay threshold
+    /// as tolerance.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            deadline: interval + crate::consts::COMPONENT_DELAY_THRESHOLD,
+            ticks: 0,
+            misses: 0,
+            max_execution: Duration::ZERO,
+            reported_misses: 0,
+            reported: None,
+        }
+    }
+
+    /// Tick deadline.
+    #[inline]
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Total number of ticks.
+    #[inline]
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Total number of deadline misses.
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Longest tick execution time.
+    #[inline]
+    pub fn max_execution(&self) -> Duration {
+        self.max_execution
+    }
+
+    /// Record the execution time of a tick.
+    ///
+    /// Returns `true` if the tick missed its deadline.
+    pub fn record(&mut self, execution: Duration) -> bool {
+        self.ticks += 1;
+        self.max_execution = self.max_execution.max(execution);
+
+        if execution > self.deadline {
+            self.misses += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Report the deadline status.
+    ///
+    /// Returns a degraded status if deadlines were missed since the last
+    /// report, and a healthy status on the first report after the misses
+    /// stopped. Returns `None` if nothing changed or the last report is too
+    /// recent.
+    pub fn report(&mut self, name: &str, now: Instant) -> Option<ModuleStatus> {
+        if self
+            .reported
+            .is_some_and(|reported| now.saturating_duration_since(reported) < REPORT_INTERVAL)
+        {
+            return None;
+        }
+
+        let status = if self.misses > self.reported_misses {
+            ModuleStatus::degraded(name.to_string(), None)
+        } else if self.reported.is_some() {
+            self.reported = None;
+            return Some(ModuleStatus::healthy(name.to_string()));
+        } else {
+            return None;
+        };
+
+        self.reported_misses = self.misses;
+        self.reported = Some(now);
+
+        Some(status)
+    }
+}
+
+impl std::fmt::Display for DeadlineMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} ticks missed the {:?} deadline, longest tick {:?}",
+            self.misses, self.ticks, self.deadline, self.max_execution
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ModuleState;
+
+    #[test]
+    fn deadline_miss() {
+        let mut monitor = DeadlineMonitor::new(Duration::from_millis(10));
+        let now = Instant::now();
+
+        assert!(!monitor.record(Duration::from_millis(2)));
+        assert!(!monitor.record(monitor.deadline()));
+        assert_eq!(monitor.report("tick", now), None);
+
+        assert!(monitor.record(Duration::from_millis(25)));
+        assert_eq!(monitor.misses(), 1);
+        assert_eq!(monitor.ticks(), 3);
+        assert_eq!(monitor.max_execution(), Duration::from_millis(25));
+
+        let status = monitor.report("tick", now).unwrap();
+        assert_eq!(status.state, ModuleState::Degraded);
+
+        assert!(monitor.record(Duration::from_millis(25)));
+        assert_eq!(monitor.report("tick", now), None);
+
+        let now = now + REPORT_INTERVAL;
+        let status = monitor.report("tick", now).unwrap();
+        assert_eq!(status.state, ModuleState::Degraded);
+
+        let now = now + REPORT_INTERVAL;
+        assert!(monitor.report("tick", now).unwrap().is_healthy());
+        assert_eq!(monitor.report("tick", now), None);
+    }
+}
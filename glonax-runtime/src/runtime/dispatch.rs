@@ -0,0 +1,365 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use tokio::sync::{broadcast::error::RecvError, Notify};
+
+use crate::core::Object;
+
+use super::{
+    queue::{CommandQueue, Overflow},
+    CommandReceiver, EmergencyLatch,
+};
+
+/// Bounded command queue of a subscriber.
+#[derive(Default)]
+struct SubscriberQueue {
+    /// Queued commands.
+    queue: Mutex<CommandQueue>,
+    /// Wakes the subscriber on a new command.
+    notify: Notify,
+    /// Whether the dispatcher is closed.
+    closed: AtomicBool,
+}
+
+/// Command dispatcher.
+///
+/// Routes the commands of the command channel to a bounded queue per
+/// subscriber. Safety commands are never dropped and are delivered ahead of
+/// the queued commands. When a subscriber falls behind, motion changes are
+/// coalesced per actuator instead of dropping arbitrary commands. Motion
+/// commands are discarded while the emergency stop is latched. Clones share
+/// the same subscribers.
+#[derive(Clone)]
+pub struct CommandDispatcher {
+    /// Maximum number of normal commands per subscriber.
+    capacity: usize,
+    /// Emergency stop latch.
+    latch: EmergencyLatch,
+    /// Subscriber queues.
+    subscribers: Arc<Mutex<Vec<Arc<SubscriberQueue>>>>,
+    /// Number of coalesced motion changes.
+    coalesced: Arc<AtomicU64>,
+    /// Number of dropped commands.
+    dropped: Arc<AtomicU64>,
+}
+
+impl CommandDispatcher {
+    /// Construct a new command dispatcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of normal commands per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            latch: EmergencyLatch::default(),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            coalesced: Arc::new(AtomicU64::new(0)),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Set the emergency stop latch.
+    pub fn with_latch(mut self, latch: EmergencyLatch) -> Self {
+        self.latch = latch;
+        self
+    }
+
+    /// Subscribe to the dispatched commands.
+    ///
+    /// The subscriber receives the commands dispatched after it subscribed.
+    pub fn subscribe(&self) -> CommandSubscriber {
+        let queue = Arc::new(SubscriberQueue::default());
+        self.subscribers.lock().unwrap().push(queue.clone());
+
+        CommandSubscriber {
+            queue,
+            latch: self.latch.clone(),
+        }
+    }
+
+    /// Dispatch a command to every subscriber.
+    ///
+    /// The command is admitted by the emergency stop latch first.
+    pub fn dispatch(&self, object: Object) {
+        let Some(object) = self.latch.admit(object) else {
+            debug!("Emergency stop latched, discarded command");
+            return;
+        };
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        // Drop the queues of the subscribers that are gone.
+        subscribers.retain(|subscriber| Arc::strong_count(subscriber) > 1);
+
+        for subscriber in subscribers.iter() {
+            let overflow = subscriber
+                .queue
+                .lock()
+                .unwrap()
+                .push_bounded(object.clone(), self.capacity);
+
+            match overflow {
+                Overflow::None => {}
+                Overflow::Coalesced => {
+                    self.coalesced.fetch_add(1, Ordering::Relaxed);
+                }
+                Overflow::Dropped => {
+                    warn!("Command subscriber full, dropped oldest command");
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            subscriber.notify.notify_one();
+        }
+    }
+
+    /// Close the dispatcher.
+    ///
+    /// The subscribers receive the queued commands, then the end of the
+    /// commands.
+    pub fn close(&self) {
+        for subscriber in self.subscribers.lock().unwrap().iter() {
+            subscriber.closed.store(true, Ordering::Release);
+            subscriber.notify.notify_one();
+        }
+    }
+
+    /// Route the commands of the command channel to the subscribers.
+    ///
+    /// The routing loop does nothing but dispatch, so it keeps up with the
+    /// command channel where a slow subscriber would lag behind. The
+    /// dispatcher is closed when the command channel is closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `command_rx` - The command channel receiver.
+    /// * `on_lag` - Called with the number of commands lost if the routing
+    ///   loop lagged behind the command channel.
+    pub async fn route(&self, mut command_rx: CommandReceiver, on_lag: impl Fn(u64)) {
+        loop {
+            match command_rx.recv().await {
+                Ok(object) => self.dispatch(object),
+                Err(RecvError::Lagged(count)) => {
+                    warn!("Command dispatcher lagged by {} objects", count);
+                    on_lag(count);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+
+        self.close();
+    }
+
+    /// Number of motion changes coalesced into a queued motion change.
+    pub fn coalesced(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    /// Number of commands dropped from a full subscriber queue.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Subscriber of the command dispatcher.
+///
+/// Motion queued before the emergency stop latched is cleared from the queue
+/// and never received.
+pub struct CommandSubscriber {
+    queue: Arc<SubscriberQueue>,
+    latch: EmergencyLatch,
+}
+
+impl CommandSubscriber {
+    /// Receive the next command without waiting.
+    pub fn try_recv(&mut self) -> Option<Object> {
+        let mut queue = self.queue.queue.lock().unwrap();
+
+        loop {
+            let object = queue.pop()?;
+            if !self.latch.discards(&object) {
+                return Some(object);
+            }
+        }
+    }
+
+    /// Receive the next command.
+    ///
+    /// Safety commands are received before the queued commands.
+    ///
+    /// # Returns
+    ///
+    /// The next command, or `None` once the dispatcher is closed and the queue
+    /// is drained.
+    pub async fn recv(&mut self) -> Option<Object> {
+        loop {
+            if let Some(object) = self.try_recv() {
+                return Some(object);
+            }
+
+            if self.queue.closed.load(Ordering::Acquire) {
+                return None;
+            }
+
+            self.queue.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Actuator, Control, Motion};
+
+    #[test]
+    fn stop_all_before_later_change() {
+        let dispatcher = CommandDispatcher::new(16);
+        let mut subscriber = dispatcher.subscribe();
+
+        for value in 0..100i16 {
+            dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Boom, value)));
+            dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Arm, -value)));
+        }
+        dispatcher.dispatch(Object::Motion(Motion::StopAll));
+        for value in 100..150i16 {
+            dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Boom, value)));
+        }
+
+        assert!(dispatcher.coalesced() > 0);
+        assert_eq!(dispatcher.dropped(), 0);
+
+        let mut received = vec![];
+        while let Some(object) = subscriber.try_recv() {
+            received.push(object);
+        }
+
+        assert!(received.len() <= 16 + 1);
+        assert_eq!(received[0], Object::Motion(Motion::StopAll));
+        assert_eq!(
+            received
+                .iter()
+                .filter(|object| **object == Object::Motion(Motion::StopAll))
+                .count(),
+            1
+        );
+
+        // The latest value after the stop is delivered, nothing from before the
+        // stop is delivered after it.
+        let last = |actuator: Actuator| {
+            received.iter().rev().find_map(|object| match object {
+                Object::Motion(Motion::Change(changes)) => changes
+                    .iter()
+                    .find(|change| change.actuator == actuator)
+                    .map(|change| change.value),
+                _ => None,
+            })
+        };
+        assert_eq!(last(Actuator::Boom), Some(149));
+        assert_eq!(last(Actuator::Arm), None);
+    }
+
+    #[test]
+    fn emergency_latch_discards_motion() {
+        let latch = EmergencyLatch::default();
+        let dispatcher = CommandDispatcher::new(16).with_latch(latch.clone());
+        let mut subscriber = dispatcher.subscribe();
+
+        dispatcher.dispatch(Object::Control(Control::EmergencyStop));
+        for value in 0..10i16 {
+            dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Boom, value)));
+        }
+
+        assert_eq!(subscriber.try_recv(), Some(Object::Motion(Motion::StopAll)));
+        assert_eq!(subscriber.try_recv(), None);
+
+        dispatcher.dispatch(Object::Control(Control::ResetEmergency));
+        dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Boom, 10i16)));
+
+        assert!(!latch.is_latched());
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(Object::Control(Control::ResetEmergency))
+        );
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(Object::Motion(Motion::new(Actuator::Boom, 10i16)))
+        );
+    }
+
+    #[test]
+    fn emergency_latch_clears_queued_motion() {
+        let latch = EmergencyLatch::default();
+        let dispatcher = CommandDispatcher::new(16).with_latch(latch.clone());
+        let mut subscriber = dispatcher.subscribe();
+
+        for value in 0..10i16 {
+            dispatcher.dispatch(Object::Motion(Motion::new(Actuator::Boom, value)));
+        }
+        dispatcher.dispatch(Object::Control(Control::MachineHorn(true)));
+
+        // Latched by a faulty module, before the stop is dispatched.
+        latch.trigger("laixer:hcu:0x4a:0x27");
+
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(Object::Control(Control::MachineHorn(true)))
+        );
+        assert_eq!(subscriber.try_recv(), None);
+
+        // A refused reset is never received.
+        latch.observe(&crate::core::ModuleStatus::degraded(
+            "laixer:hcu:0x4a:0x27".to_string(),
+            None,
+        ));
+        dispatcher.dispatch(Object::Control(Control::ResetEmergency));
+        assert!(latch.is_latched());
+        assert_eq!(subscriber.try_recv(), None);
+    }
+
+    #[tokio::test]
+    async fn route_flood() {
+        let (command_tx, _) = tokio::sync::broadcast::channel(1_024);
+        let dispatcher = CommandDispatcher::new(16);
+        let mut subscriber = dispatcher.subscribe();
+
+        let router = dispatcher.clone();
+        let command_rx = command_tx.subscribe();
+        let task = tokio::spawn(async move { router.route(command_rx, |_| {}).await });
+
+        for value in 0..500i16 {
+            command_tx
+                .send(Object::Motion(Motion::new(Actuator::Boom, value)))
+                .unwrap();
+
+            if value == 250 {
+                command_tx.send(Object::Motion(Motion::StopAll)).unwrap();
+            }
+        }
+        drop(command_tx);
+        task.await.unwrap();
+
+        let mut received = vec![];
+        while let Some(object) = subscriber.recv().await {
+            received.push(object);
+        }
+
+        let stop = received
+            .iter()
+            .position(|object| *object == Object::Motion(Motion::StopAll))
+            .unwrap();
+
+        // No change sent after the stop is received before it.
+        for object in &received[..stop] {
+            if let Object::Motion(Motion::Change(changes)) = object {
+                assert!(changes.iter().all(|change| change.value <= 250));
+            }
+        }
+        assert_eq!(
+            received.last(),
+            Some(&Object::Motion(Motion::new(Actuator::Boom, 499i16)))
+        );
+    }
+}
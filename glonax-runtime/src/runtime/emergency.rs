@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::core::{Control, ModuleState, ModuleStatus, Motion, Object};
+
+/// Module name of the emergency latch status.
+const MODULE_NAME: &str = "runtime:emergency";
+
+#[derive(Default)]
+struct EmergencyLatchInner {
+    /// Name of the source that latched the emergency stop.
+    source: Option<String>,
+    /// Last reported state per module.
+    modules: HashMap<String, ModuleState>,
+}
+
+/// Emergency stop latch.
+///
+/// The latch sits in the command dispatch path. Once latched by an operator
+/// command or a faulty module, every motion command other than a stop is
+/// discarded until the latch is explicitly reset. The reset is refused while
+/// any module is degraded or faulty. Modules in the emergency state are held
+/// by a stop latch of their own, which is released by the same reset. Clones
+/// share the same latch.
+#[derive(Clone, Default)]
+pub struct EmergencyLatch {
+    inner: Arc<Mutex<EmergencyLatchInner>>,
+}
+
+impl EmergencyLatch {
+    /// Latch the emergency stop.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The name of the source that latched the emergency stop.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the emergency stop was not latched before.
+    pub fn trigger(&self, source: impl ToString) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.source.is_some() {
+            return false;
+        }
+
+        inner.source = Some(source.to_string());
+
+        true
+    }
+
+    /// Observe a module status.
+    ///
+    /// A faulty module, such as a stalled service or a failing network unit,
+    /// latches the emergency stop.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the status latched the emergency stop.
+    pub fn observe(&self, status: &ModuleStatus) -> bool {
+        if status.name == MODULE_NAME {
+            return false;
+        }
+
+        self.inner
+            .lock()
+            .unwrap()
+            .modules
+            .insert(status.name.clone(), status.state);
+
+        status.state == ModuleState::Faulty && self.trigger(&status.name)
+    }
+
+    /// Reset the emergency stop.
+    ///
+    /// This must only be called on an explicit operator action.
+    ///
+    /// # Returns
+    ///
+    /// Returns the name of the first module that is not healthy if the reset
+    /// is refused.
+    pub fn reset(&self) -> Result<(), String> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some((name, _)) = inner
+            .modules
+            .iter()
+            .find(|(_, state)| matches!(state, ModuleState::Degraded | ModuleState::Faulty))
+        {
+            return Err(name.clone());
+        }
+
+        inner.source = None;
+
+        Ok(())
+    }
+
+    /// Test if the emergency stop is latched.
+    pub fn is_latched(&self) -> bool {
+        self.inner.lock().unwrap().source.is_some()
+    }
+
+    /// Name of the source that latched the emergency stop, if latched.
+    pub fn source(&self) -> Option<String> {
+        self.inner.lock().unwrap().source.clone()
+    }
+
+    /// Status of the emergency latch.
+    pub fn status(&self) -> ModuleStatus {
+        if self.is_latched() {
+            ModuleStatus::emergency(MODULE_NAME.to_string())
+        } else {
+            ModuleStatus::healthy(MODULE_NAME.to_string())
+        }
+    }
+
+    /// Test if the command is discarded while the emergency stop is latched.
+    ///
+    /// Every motion command other than a stop is discarded.
+    pub fn discards(&self, object: &Object) -> bool {
+        matches!(object, Object::Motion(motion) if !matches!(motion, Motion::StopAll | Motion::Stop(_)))
+            && self.is_latched()
+    }
+
+    /// Admit a command to the dispatch path.
+    ///
+    /// The emergency stop command latches the emergency stop and is replaced
+    /// by a stop of all motion. The reset command is only admitted if the
+    /// latch is reset, so the stop latches further down the path are released
+    /// together with this latch and never on a refused reset.
+    ///
+    /// # Returns
+    ///
+    /// The command to dispatch, or `None` if the command is discarded.
+    pub fn admit(&self, object: Object) -> Option<Object> {
+        match object {
+            Object::Control(Control::EmergencyStop) => {
+                if self.trigger("operator") {
+                    warn!("Emergency stop latched by operator");
+                }
+
+                Some(Object::Motion(Motion::StopAll))
+            }
+            Object::Control(Control::ResetEmergency) => {
+                if !self.is_latched() {
+                    return Some(object);
+                }
+
+                match self.reset() {
+                    Ok(()) => {
+                        info!("Emergency stop reset");
+                        Some(object)
+                    }
+                    Err(name) => {
+                        warn!("Emergency stop reset refused, {} not healthy", name);
+                        None
+                    }
+                }
+            }
+            _ if self.discards(&object) => None,
+            _ => Some(object),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Actuator, ModuleError};
+
+    #[test]
+    fn emergency_latch() {
+        let latch = EmergencyLatch::default();
+        let change = Object::Motion(Motion::new(Actuator::Boom, 1_000_i16));
+
+        assert_eq!(latch.admit(change.clone()), Some(change.clone()));
+
+        assert_eq!(
+            latch.admit(Object::Control(Control::EmergencyStop)),
+            Some(Object::Motion(Motion::StopAll))
+        );
+        assert!(latch.is_latched());
+        assert_eq!(latch.status().state, ModuleState::Emergency);
+
+        // Motion is swallowed, stops and other controls pass.
+        assert_eq!(latch.admit(change.clone()), None);
+        assert_eq!(latch.admit(Object::Motion(Motion::ResumeAll)), None);
+        assert_eq!(
+            latch.admit(Object::Motion(Motion::StopAll)),
+            Some(Object::Motion(Motion::StopAll))
+        );
+        assert_eq!(
+            latch.admit(Object::Control(Control::MachineHorn(true))),
+            Some(Object::Control(Control::MachineHorn(true)))
+        );
+
+        latch.admit(Object::Control(Control::ResetEmergency));
+        assert!(!latch.is_latched());
+        assert_eq!(latch.admit(change.clone()), Some(change));
+    }
+
+    #[test]
+    fn emergency_latch_module_fault() {
+        let latch = EmergencyLatch::default();
+        let unit = "laixer:hcu:0x4a:0x27".to_string();
+
+        assert!(!latch.observe(&ModuleStatus::healthy(unit.clone())));
+        assert!(!latch.observe(&ModuleStatus::degraded(unit.clone(), None)));
+        assert!(!latch.is_latched());
+
+        assert!(latch.observe(&ModuleStatus::faulty(
+            unit.clone(),
+            ModuleError::CommunicationTimeout
+        )));
+        assert_eq!(latch.source(), Some(unit.clone()));
+
+        // Reset is refused until every module is healthy.
+        assert_eq!(latch.reset(), Err(unit.clone()));
+        latch.observe(&ModuleStatus::degraded(unit.clone(), None));
+        assert!(latch.reset().is_err());
+        assert!(latch.is_latched());
+
+        latch.observe(&ModuleStatus::healthy(unit));
+        latch.observe(&ModuleStatus::emergency("laixer:vcu:0x12:0x27".to_string()));
+        assert!(latch.reset().is_ok());
+        assert!(!latch.is_latched());
+    }
+
+    #[test]
+    fn emergency_latch_refused_reset() {
+        let latch = EmergencyLatch::default();
+        let unit = "laixer:hcu:0x4a:0x27".to_string();
+
+        latch.admit(Object::Control(Control::EmergencyStop));
+        latch.observe(&ModuleStatus::degraded(unit.clone(), None));
+
+        // A refused reset is not dispatched, so no stop latch is released.
+        assert_eq!(latch.admit(Object::Control(Control::ResetEmergency)), None);
+        assert!(latch.is_latched());
+
+        latch.observe(&ModuleStatus::healthy(unit));
+        assert_eq!(
+            latch.admit(Object::Control(Control::ResetEmergency)),
+            Some(Object::Control(Control::ResetEmergency))
+        );
+        assert!(!latch.is_latched());
+    }
+}
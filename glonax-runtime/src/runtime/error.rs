@@ -14,6 +14,8 @@ pub enum Error {
     NetworkTimeout,
     /// Indicates an unhandled error with a device.
     Device(DeviceError),
+    /// A service failed before completing setup.
+    ServiceSetup(String),
     /// An I/O error occured.
     Io(io::Error),
 }
@@ -26,6 +28,7 @@ impl fmt::Display for Error {
             Error::MotionDeviceNotFound => write!(f, "no motion device was found on the network"),
             Error::CoreDeviceNotFound => write!(f, "no core device was found on the network"),
             Error::NetworkTimeout => write!(f, "timeout reached while contacting network ECUs"),
+            Error::ServiceSetup(name) => write!(f, "service '{}' failed during setup", name),
             Error::Io(e) => write!(f, "{}", e),
         }
     }
@@ -0,0 +1,104 @@
+/// Executor configuration.
+///
+/// Selects the flavor and the number of worker threads of the tokio runtime
+/// the services run on. The executor must be built before any service is
+/// scheduled. By default the multi-threaded flavor is used with one worker
+/// per CPU core. The current-thread flavor runs all services on the calling
+/// thread, which avoids thread overhead on single-core targets.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::runtime::ExecutorConfig;
+///
+/// let executor = ExecutorConfig::default().with_workers(2).build().unwrap();
+///
+/// assert_eq!(executor.metrics().num_workers(), 2);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde_derive::Deserialize)]
+pub struct ExecutorConfig {
+    /// Number of worker threads.
+    ///
+    /// Defaults to the number of CPU cores. Ignored by the current-thread
+    /// flavor.
+    pub workers: Option<usize>,
+    /// Use the current-thread flavor.
+    #[serde(default)]
+    pub current_thread: bool,
+}
+
+impl ExecutorConfig {
+    /// Set the number of worker threads.
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers);
+        self
+    }
+
+    /// Use the current-thread flavor.
+    pub fn with_current_thread(mut self) -> Self {
+        self.current_thread = true;
+        self
+    }
+
+    /// Build the executor.
+    ///
+    /// # Returns
+    ///
+    /// Returns the tokio runtime. Returns an error if the number of worker
+    /// threads is zero or the runtime cannot be created.
+    pub fn build(&self) -> std::io::Result<tokio::runtime::Runtime> {
+        let mut builder = if self.current_thread {
+            tokio::runtime::Builder::new_current_thread()
+        } else {
+            let mut builder = tokio::runtime::Builder::new_multi_thread();
+
+            if let Some(workers) = self.workers {
+                if workers == 0 {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "number of workers must be at least one",
+                    ));
+                }
+
+                builder.worker_threads(workers);
+            }
+
+            builder
+        };
+
+        builder.enable_all().build()
+    }
+}
+
+impl std::fmt::Display for ExecutorConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.current_thread {
+            write!(f, "current thread")
+        } else if let Some(workers) = self.workers {
+            write!(f, "multi thread with {} workers", workers)
+        } else {
+            write!(f, "multi thread")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executor_flavor() {
+        let executor = ExecutorConfig::default().with_workers(3).build().unwrap();
+        assert_eq!(executor.metrics().num_workers(), 3);
+
+        let executor = ExecutorConfig::default()
+            .with_workers(3)
+            .with_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(executor.metrics().num_workers(), 1);
+        assert_eq!(executor.block_on(async { 42 }), 42);
+
+        assert!(ExecutorConfig::default().with_workers(0).build().is_err());
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use crate::core::{ModuleError, Object, ObjectMessage};
 
-use super::SignalSender;
+use super::{Clock, SignalSender};
 
 pub struct NetDriverContextDetail {
     /// Number of messages sent.
@@ -18,18 +18,24 @@ pub struct NetDriverContextDetail {
     rx_last_message: Option<ObjectMessage>,
     /// Last time a message was received.
     rx_last: Instant,
+    /// Update rate reported by the unit.
+    update_rate: Option<f32>,
+    /// Last time the unit was ticked.
+    tick_last: Option<Instant>,
+    /// Clock used for the timeouts.
+    clock: Clock,
 }
 
 impl NetDriverContextDetail {
     /// Check if the last message was sent within a timeout.
     fn is_rx_timeout(&self, timeout: Duration) -> bool {
-        self.rx_last.elapsed() > timeout
+        self.clock.elapsed(self.rx_last) > timeout
     }
 
     /// Mark the last time a message was received.
     fn rx_mark(&mut self) {
         self.rx_count += 1; // TODO: Increment the number of messages received.
-        self.rx_last = Instant::now();
+        self.rx_last = self.clock.now();
     }
 }
 
@@ -41,6 +47,9 @@ impl Default for NetDriverContextDetail {
             rx_count: 0,
             rx_last_message: None,
             rx_last: Instant::now(),
+            update_rate: None,
+            tick_last: None,
+            clock: Clock::system(),
         }
     }
 }
@@ -51,20 +60,65 @@ pub struct NetDriverContext {
 }
 
 impl NetDriverContext {
-    pub fn inner(&self) -> std::sync::MutexGuard<NetDriverContextDetail> {
+    pub fn inner(&self) -> std::sync::MutexGuard<'_, NetDriverContextDetail> {
         self.detail.lock().unwrap()
     }
 
+    /// Set the clock used for the timeouts.
+    ///
+    /// The receive timeout restarts from the current moment of the clock.
+    pub fn set_clock(&self, clock: Clock) {
+        let mut detail = self.detail.lock().unwrap();
+
+        detail.rx_last = clock.now();
+        detail.clock = clock;
+    }
+
     /// Check if the last message was sent within a timeout.
     pub fn is_rx_timeout(&self, timeout: Duration) -> bool {
         self.detail.lock().unwrap().is_rx_timeout(timeout)
     }
 
+    /// Current moment of the context clock.
+    pub fn now(&self) -> Instant {
+        self.detail.lock().unwrap().clock.now()
+    }
+
+    /// Set the update rate of the unit in hertz.
+    ///
+    /// The update rate is reported in the module status of the unit.
+    pub fn set_update_rate(&self, update_rate: f32) {
+        self.detail.lock().unwrap().update_rate = Some(update_rate);
+    }
+
+    /// Update rate of the unit in hertz, if reported.
+    pub fn update_rate(&self) -> Option<f32> {
+        self.detail.lock().unwrap().update_rate
+    }
+
     /// Mark the last time a message was received.
     pub fn rx_mark(&self) {
         self.detail.lock().unwrap().rx_mark();
     }
 
+    /// Mark the tick of the unit.
+    ///
+    /// # Returns
+    ///
+    /// The time since the last tick, zero on the first tick.
+    pub fn tick_mark(&self) -> Duration {
+        let mut detail = self.detail.lock().unwrap();
+
+        let now = detail.clock.now();
+        let elapsed = detail
+            .tick_last
+            .map(|last| now.saturating_duration_since(last))
+            .unwrap_or_default();
+        detail.tick_last = Some(now);
+
+        elapsed
+    }
+
     pub fn set_tx_last_message(&self, message: ObjectMessage) {
         self.detail.lock().unwrap().tx_last_message = Some(message);
     }
@@ -100,6 +154,8 @@ pub enum J1939UnitError {
     SensorError,
     /// Hardware has an error.
     HardwareError,
+    /// Encoder position is frozen while the actuator moves.
+    EncoderFrozen,
     /// Unknown state.
     UnknownState,
     /// Unit has an i/o error.
@@ -118,6 +174,7 @@ impl std::fmt::Display for J1939UnitError {
                 Self::BusError => "bus error",
                 Self::SensorError => "sensor error",
                 Self::HardwareError => "hardware error",
+                Self::EncoderFrozen => "encoder frozen",
                 Self::UnknownState => "unknown state",
                 Self::IOError(error) => return write!(f, "i/o error: {}", error),
             }
@@ -125,6 +182,15 @@ impl std::fmt::Display for J1939UnitError {
     }
 }
 
+impl J1939UnitError {
+    /// Test if the unit is still functional with the error.
+    ///
+    /// A unit with such an error is reported as degraded instead of faulty.
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, Self::EncoderFrozen)
+    }
+}
+
 impl From<std::io::Error> for J1939UnitError {
     fn from(error: std::io::Error) -> Self {
         Self::IOError(error)
@@ -140,6 +206,7 @@ impl From<J1939UnitError> for ModuleError {
             J1939UnitError::BusError => ModuleError::GenericCommunicationError,
             J1939UnitError::SensorError => ModuleError::GenericCommunicationError,
             J1939UnitError::HardwareError => ModuleError::GenericCommunicationError,
+            J1939UnitError::EncoderFrozen => ModuleError::SensorFrozen,
             J1939UnitError::UnknownState => ModuleError::GenericCommunicationError,
             J1939UnitError::IOError(_) => ModuleError::IOError,
         }
@@ -175,6 +242,28 @@ pub trait J1939Unit: Send {
     /// Get the source address of the unit.
     fn source(&self) -> u8;
 
+    /// Describe the capabilities of the unit.
+    ///
+    /// This method will be called to assemble the machine capability descriptor. The
+    /// unit should add the actuators, controls and sensors it provides.
+    ///
+    /// This method is optional and may be a no-op.
+    #[allow(unused_variables)]
+    fn describe(&self, capability: &mut crate::core::Capability) {}
+
+    /// Share the unit with another task of the network service.
+    ///
+    /// The network service runs its tasks on clones of the service. A unit that
+    /// keeps state at runtime, such as a selected profile, must return a unit
+    /// sharing that state, so that a change made through one task is applied by
+    /// every task. A unit that returns `None` is constructed again from its
+    /// configuration.
+    ///
+    /// This method is optional and may be a no-op.
+    fn share(&self) -> Option<Box<dyn J1939Unit>> {
+        None
+    }
+
     /// Setup the unit.
     ///
     /// This method will be called to setup the unit. This method should be non-blocking and should
@@ -217,6 +306,23 @@ pub trait J1939Unit: Send {
         rx_queue: &mut Vec<Object>,
     ) -> Result<(), J1939UnitError>;
 
+    /// Try to accept a message reassembled by the transport protocol.
+    ///
+    /// This method will be called instead of `try_recv` when a frame completes a message
+    /// larger than a single frame. This method should be non-blocking and should only
+    /// perform asynchronous I/O operations.
+    ///
+    /// This method is optional and may be a no-op.
+    #[allow(unused_variables)]
+    fn try_recv_message(
+        &self,
+        ctx: &mut NetDriverContext,
+        message: &crate::net::LongMessage,
+        rx_queue: &mut Vec<Object>,
+    ) -> Result<(), J1939UnitError> {
+        Ok(())
+    }
+
     /// Trigger the unit manually.
     ///
     /// This method will be called to trigger the unit manually. This method should be non-blocking
@@ -233,6 +339,34 @@ pub trait J1939Unit: Send {
         Ok(())
     }
 
+    /// Test if a digital input is asserted.
+    ///
+    /// This method is called for every frame before any other processing and is
+    /// used for safety related inputs. Implementations must only inspect the frame
+    /// and return as fast as possible.
+    ///
+    /// This method is optional and may be a no-op.
+    #[allow(unused_variables)]
+    fn is_input_asserted(&self, frame: &j1939::Frame, input: u8) -> bool {
+        false
+    }
+
+    /// Stop all motion on the unit.
+    ///
+    /// This method will be called on the safety path when a stop is requested. The unit
+    /// must bring itself into a safe state and must remain there until it is explicitly
+    /// instructed otherwise. This method should be non-blocking.
+    ///
+    /// This method is optional and may be a no-op.
+    #[allow(unused_variables)]
+    fn stop_motion(
+        &self,
+        ctx: &mut NetDriverContext,
+        tx_queue: &mut Vec<j1939::Frame>,
+    ) -> Result<(), J1939UnitError> {
+        Ok(())
+    }
+
     /// Perform a tick operation on the unit.
     ///
     /// This method will be called periodically to perform any necessary operations on the unit.
@@ -258,11 +392,23 @@ pub trait NetworkService<Cnf> {
     ///
     /// # Returns
     ///
-    /// The newly created network service instance.
-    fn new(config: Cnf) -> Self
+    /// The newly created network service instance. If the service cannot be
+    /// created, the runtime reports the service as failed during setup.
+    fn new(config: Cnf) -> super::Result<Self>
     where
         Self: Sized;
 
+    /// Get the service context.
+    fn ctx(&self) -> super::ServiceContext {
+        super::ServiceContext::new(std::any::type_name::<Self>())
+    }
+
+    /// Set the runtime clock.
+    ///
+    /// This method is called once after the service is constructed, before
+    /// the service is cloned into its tasks.
+    fn set_clock(&mut self, _clock: Clock) {}
+
     /// Sets up the network service.
     ///
     /// This method is called during the initialization of the network service.
@@ -328,4 +474,18 @@ pub trait NetworkService<Cnf> {
     ///
     /// A future that resolves when the action has been performed.
     fn on_command(&mut self, object: &Object) -> impl Future<Output = ()> + Send;
+
+    /// Performs the stop action on the network.
+    ///
+    /// This method is called in a dedicated task, independent of the signal and
+    /// command channels. Implementations should wait for a stop request and then
+    /// bring all units into a safe state. The default implementation never
+    /// completes.
+    ///
+    /// # Returns
+    ///
+    /// A future that resolves when the stop action has been performed.
+    fn on_stop(&mut self) -> impl Future<Output = ()> + Send {
+        std::future::pending()
+    }
 }
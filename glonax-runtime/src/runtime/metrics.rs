@@ -0,0 +1,216 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use tokio::sync::broadcast::{error::RecvError, Receiver, Sender};
+
+use crate::core::{ModuleStatus, Object};
+
+/// Broadcast channel counters.
+///
+/// The counters are shared between the runtime and the tasks that observe
+/// the channel.
+#[derive(Default)]
+pub(super) struct ChannelCounters {
+    /// Messages sent on the channel.
+    sent: AtomicU64,
+    /// Number of times a receiver lagged behind.
+    lag_events: AtomicU64,
+    /// Messages skipped by lagging receivers.
+    lagged: AtomicU64,
+}
+
+impl ChannelCounters {
+    /// Record a receiver that lagged behind by `count` messages.
+    pub(super) fn record_lag(&self, count: u64) {
+        self.lag_events.fetch_add(1, Ordering::Relaxed);
+        self.lagged.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Count the messages sent on the channel.
+    ///
+    /// The receiver is owned by the metrics task and only used to count, a
+    /// lag of this receiver is not a lag of the channel consumers.
+    pub(super) async fn count(&self, mut receiver: Receiver<Object>) {
+        loop {
+            match receiver.recv().await {
+                Ok(_) => {
+                    self.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(RecvError::Lagged(count)) => {
+                    self.sent.fetch_add(count, Ordering::Relaxed);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+/// Broadcast channel with metrics.
+#[derive(Clone)]
+pub(super) struct MeteredChannel {
+    /// Channel name.
+    pub(super) name: &'static str,
+    /// Channel sender.
+    pub(super) sender: Sender<Object>,
+    /// Channel counters.
+    pub(super) counters: Arc<ChannelCounters>,
+}
+
+impl MeteredChannel {
+    pub(super) fn new(name: &'static str, sender: Sender<Object>) -> Self {
+        Self {
+            name,
+            sender,
+            counters: Arc::new(ChannelCounters::default()),
+        }
+    }
+
+    /// Take a snapshot of the channel metrics.
+    ///
+    /// The receiver used to count messages is not included in the number of
+    /// subscribers.
+    pub(super) fn metrics(&self, counting: bool) -> ChannelMetrics {
+        ChannelMetrics {
+            name: self.name,
+            sent: self.counters.sent.load(Ordering::Relaxed),
+            subscribers: self
+                .sender
+                .receiver_count()
+                .saturating_sub(usize::from(counting)),
+            depth: self.sender.len(),
+            lag_events: self.counters.lag_events.load(Ordering::Relaxed),
+            lagged: self.counters.lagged.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Broadcast channel metrics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelMetrics {
+    /// Channel name.
+    pub name: &'static str,
+    /// Messages sent on the channel.
+    pub sent: u64,
+    /// Number of subscribers.
+    pub subscribers: usize,
+    /// Messages queued for the slowest subscriber.
+    pub depth: usize,
+    /// Number of times a subscriber lagged behind.
+    pub lag_events: u64,
+    /// Messages skipped by lagging subscribers.
+    pub lagged: u64,
+}
+
+impl ChannelMetrics {
+    /// Module status of the channel.
+    ///
+    /// The channel is degraded when any subscriber lagged behind since the
+    /// `previous` metrics were taken.
+    pub fn status(&self, previous: Option<&ChannelMetrics>) -> ModuleStatus {
+        let lag_events = previous.map_or(self.lag_events, |previous| {
+            self.lag_events - previous.lag_events
+        });
+
+        let name = format!("runtime:channel:{}", self.name);
+        if lag_events > 0 {
+            ModuleStatus::degraded(name, None)
+        } else {
+            ModuleStatus::healthy(name)
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Channel: {} Sent: {} Subscribers: {} Depth: {} Lag events: {} Lagged: {}",
+            self.name, self.sent, self.subscribers, self.depth, self.lag_events, self.lagged
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        core::{Engine, ModuleState},
+        runtime::{NetworkService, NullConfig, Runtime, SignalSender},
+    };
+
+    #[derive(Clone)]
+    struct SlowService;
+
+    impl NetworkService<NullConfig> for SlowService {
+        fn new(_: NullConfig) -> crate::runtime::Result<Self> {
+            Ok(Self)
+        }
+
+        async fn recv(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_tick(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_command(&mut self, _: &Object) {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn channel_metrics() {
+        let mut runtime = Runtime::default();
+        runtime.register_channel_metrics(Duration::from_millis(50));
+        runtime.schedule_net_service::<SlowService, _>(NullConfig, Duration::from_millis(10));
+        runtime.wait_for_ready().await.unwrap();
+
+        let mut signal_rx = runtime.signal_tx.subscribe();
+
+        for _ in 0..64 {
+            runtime
+                .command_tx
+                .send(Object::Engine(Engine::from_rpm(1_200)))
+                .unwrap();
+        }
+
+        let metrics = tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                let metrics = runtime.channel_metrics();
+                if metrics[0].sent == 64 && metrics[0].lag_events > 0 {
+                    break metrics;
+                }
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(metrics[0].name, "command");
+        assert_eq!(metrics[0].subscribers, 1);
+        assert!(metrics[0].lagged >= 64 - crate::consts::QUEUE_SIZE_COMMAND as u64);
+        assert!(metrics[0].depth <= crate::consts::QUEUE_SIZE_COMMAND);
+        assert_eq!(metrics[1].name, "signal");
+        assert_eq!(metrics[1].lag_events, 0);
+
+        let status = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let Ok(Object::ModuleStatus(status)) = signal_rx.recv().await {
+                    if status.name == "runtime:channel:command" && !status.is_healthy() {
+                        break status;
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(status.state, ModuleState::Degraded);
+    }
+}
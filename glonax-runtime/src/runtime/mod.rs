@@ -1,12 +1,38 @@
+mod affinity;
+mod clock;
+mod deadline;
+mod dispatch;
+mod emergency;
 mod error;
+mod executor;
 mod j1939;
+mod metrics;
+mod queue;
+mod ready;
+mod stop;
+mod task;
+mod watchdog;
 
 use std::{future::Future, time::Duration};
 
-use tokio::sync::broadcast::{error::RecvError, Receiver, Sender};
+use tokio::sync::broadcast::{Receiver, Sender};
 
+use crate::core::{Control, Motion, Object};
+
+pub use self::affinity::pin_current_thread;
+pub use self::clock::Clock;
+pub use self::deadline::DeadlineMonitor;
+pub use self::dispatch::{CommandDispatcher, CommandSubscriber};
+pub use self::emergency::EmergencyLatch;
 pub use self::error::Error;
+pub use self::executor::ExecutorConfig;
 pub use self::j1939::{J1939Unit, J1939UnitError, NetDriverContext, NetworkService};
+pub use self::metrics::ChannelMetrics;
+pub use self::queue::{CommandQueue, Overflow};
+pub use self::ready::{sd_notify, ReadySummary};
+pub use self::stop::StopLatch;
+pub use self::task::TaskFailure;
+pub use self::watchdog::{Heartbeat, Watchdog};
 
 pub type Result<T = ()> = std::result::Result<T, error::Error>;
 
@@ -70,6 +96,27 @@ pub trait Service<Cnf> {
         }
     }
 
+    /// Set the runtime clock.
+    ///
+    /// This method is called once after the service is constructed. Services
+    /// with timing sensitive behavior should use this clock instead of the
+    /// system clock.
+    fn set_clock(&mut self, _clock: Clock) {}
+
+    /// Set the service heartbeat.
+    ///
+    /// This method is called once after the service is constructed. The
+    /// runtime touches the heartbeat on every call of the wait method. A
+    /// service that loops within the wait method must touch the heartbeat on
+    /// every iteration of its loop, or it is reported as stalled when watched.
+    fn set_heartbeat(&mut self, _heartbeat: Heartbeat) {}
+
+    /// Set the command sender.
+    ///
+    /// This method is called once after a pipe service is constructed. Pipe
+    /// services that act on commands should subscribe to the sender.
+    fn set_command_sender(&mut self, _command_tx: CommandSender) {}
+
     /// Setup the service.
     ///
     /// This method is called once on startup and should be used to initialize the service.
@@ -100,6 +147,28 @@ pub trait Service<Cnf> {
     fn wait_io_pub(&mut self, _signal_tx: SignalSender) -> impl Future<Output = ()> + Send {
         std::future::ready(())
     }
+
+    /// Wait for IO event and publish derived signals.
+    ///
+    /// This method is always called on a separate thread and
+    /// should be used to derive new signals from incoming signals.
+    /// The method is optional and does not need to be implemented.
+    fn wait_io_pipe(
+        &mut self,
+        _signal_tx: SignalSender,
+        _signal_rx: SignalReceiver,
+    ) -> impl Future<Output = ()> + Send {
+        std::future::ready(())
+    }
+}
+
+/// Teardown stage of a service.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Teardown {
+    /// Torn down first.
+    Service,
+    /// Torn down last, the final stop is sent to the network.
+    Network,
 }
 
 pub struct Runtime {
@@ -107,29 +176,76 @@ pub struct Runtime {
     command_tx: CommandSender,
     /// Signal sender.
     signal_tx: SignalSender,
-    /// Signal receiver.
-    signal_rx: SignalReceiver,
+    /// Command and signal channel metrics.
+    channels: [metrics::MeteredChannel; 2],
+    /// Whether the channel metrics are counted.
+    channel_metrics: bool,
+    /// Runtime clock.
+    clock: Clock,
     /// Runtime tasks.
     task_pool: Vec<tokio::task::JoinHandle<()>>,
+    /// Service tasks in startup order with their teardown stage and permit.
+    service_pool: Vec<(
+        Teardown,
+        tokio::sync::oneshot::Sender<()>,
+        tokio::task::JoinHandle<()>,
+    )>,
+    /// Failures of the runtime tasks.
+    task_failures: task::TaskFailures,
     /// Runtime event bus.
     shutdown: (
         tokio::sync::broadcast::Sender<()>,
         tokio::sync::broadcast::Receiver<()>,
     ),
+    /// Service readiness.
+    readiness: Vec<(String, tokio::sync::oneshot::Receiver<()>)>,
+    /// Services pinned to a CPU core.
+    affinity: Vec<(String, usize)>,
+    /// Function that pins a thread to a CPU core.
+    pin: affinity::PinFn,
+    /// Service watchdog.
+    watchdog: Watchdog,
+    /// Watchdog deadline per service.
+    watchdog_deadline: Vec<(String, Duration)>,
+    /// Whether the watchdog is registered.
+    watchdog_registered: bool,
+    /// Tick interval per network service.
+    tick_interval: Vec<(String, tokio::sync::watch::Sender<Option<Duration>>)>,
+    /// Command dispatcher, started with the first subscriber.
+    dispatcher: Option<CommandDispatcher>,
+    /// Emergency stop latch of the command dispatcher.
+    emergency: EmergencyLatch,
 }
 
 impl Default for Runtime {
     fn default() -> Self {
         let (command_tx, _) = tokio::sync::broadcast::channel(crate::consts::QUEUE_SIZE_COMMAND);
-        let (signal_tx, signal_rx) =
-            tokio::sync::broadcast::channel(crate::consts::QUEUE_SIZE_SIGNAL);
+        let (signal_tx, _) = tokio::sync::broadcast::channel(crate::consts::QUEUE_SIZE_SIGNAL);
+
+        let channels = [
+            metrics::MeteredChannel::new("command", command_tx.clone()),
+            metrics::MeteredChannel::new("signal", signal_tx.clone()),
+        ];
 
         Self {
             command_tx,
             signal_tx,
-            signal_rx,
+            channels,
+            channel_metrics: false,
+            clock: Clock::system(),
             task_pool: Vec::new(),
+            service_pool: Vec::new(),
+            task_failures: task::TaskFailures::default(),
             shutdown: tokio::sync::broadcast::channel(1),
+            readiness: Vec::new(),
+            affinity: Vec::new(),
+            pin: std::sync::Arc::new(affinity::pin_current_thread),
+            watchdog: Watchdog::default(),
+            watchdog_deadline: Vec::new(),
+            watchdog_registered: false,
+            tick_interval: Vec::new(),
+            dispatcher: None,
+            emergency: EmergencyLatch::default(),
         }
     }
 }
@@ -164,12 +280,409 @@ impl Runtime {
         });
     }
 
-    /// Spawns a future onto the runtime's executor.
+    /// Set the runtime clock.
+    ///
+    /// The clock is handed to every service scheduled after this call.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    /// Runtime clock.
+    #[inline]
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Count the messages on the command and signal channels.
+    ///
+    /// This method will spawn a task per channel that counts the messages sent
+    /// on the channel. The channel status is published as a module status
+    /// signal on every interval, a channel is degraded when any subscriber
+    /// lagged behind during the interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval at which the channel status is published.
+    pub fn register_channel_metrics(&mut self, interval: Duration) {
+        if self.channel_metrics {
+            return;
+        }
+
+        debug!("Register channel metrics");
+
+        self.channel_metrics = true;
+
+        for channel in self.channels.clone() {
+            let receiver = channel.sender.subscribe();
+            let mut shutdown = self.shutdown.0.subscribe();
+
+            self.spawn_named(format!("channel counter: {}", channel.name), async move {
+                tokio::select! {
+                    _ = channel.counters.count(receiver) => {}
+                    _ = shutdown.recv() => {}
+                }
+            });
+        }
+
+        let channels = self.channels.clone();
+        let signal_tx = self.signal_tx.clone();
+        let mut shutdown = self.shutdown.0.subscribe();
+
+        self.spawn_named("channel metrics", async move {
+            let mut previous: Vec<ChannelMetrics> = Vec::new();
+            let mut interval = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+
+                let metrics = channels
+                    .iter()
+                    .map(|channel| channel.metrics(true))
+                    .collect::<Vec<_>>();
+
+                for (idx, channel_metrics) in metrics.iter().enumerate() {
+                    let status = channel_metrics.status(previous.get(idx));
+                    if !status.is_healthy() {
+                        warn!("{}", channel_metrics);
+                    }
+
+                    signal_tx
+                        .send(crate::core::Object::ModuleStatus(status))
+                        .ok();
+                }
+
+                previous = metrics;
+            }
+        });
+    }
+
+    /// Retrieve the command and signal channel metrics.
+    ///
+    /// Messages are only counted after the channel metrics are registered.
+    pub fn channel_metrics(&self) -> Vec<ChannelMetrics> {
+        self.channels
+            .iter()
+            .map(|channel| channel.metrics(self.channel_metrics))
+            .collect()
+    }
+
+    /// Spawn a task under supervision.
+    fn supervise<F: Future<Output = ()> + Send + 'static>(
+        &self,
+        name: String,
+        critical: bool,
+        f: F,
+    ) -> tokio::task::JoinHandle<()> {
+        task::spawn_supervised(
+            name,
+            critical,
+            self.task_failures.clone(),
+            self.shutdown.0.clone(),
+            f,
+        )
+    }
+
+    /// Spawns a named future onto the runtime's executor.
     ///
     /// This method spawns a future onto the runtime's executor, allowing it to run in the background.
     /// The future must implement the `Future` trait with an output type of `()`, and it must also be `Send` and `'static`.
-    fn spawn<F: Future<Output = ()> + Send + 'static>(&mut self, f: F) {
-        self.task_pool.push(tokio::spawn(f));
+    /// If the task panics the failure is logged with the task name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the task.
+    /// * `f` - The future to run.
+    pub fn spawn_named<F: Future<Output = ()> + Send + 'static>(
+        &mut self,
+        name: impl ToString,
+        f: F,
+    ) {
+        let task = self.supervise(name.to_string(), false, f);
+        self.task_pool.push(task);
+    }
+
+    /// Spawns a named critical future onto the runtime's executor.
+    ///
+    /// Same as [`Runtime::spawn_named`], but the runtime is shutdown when the
+    /// task panics.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the task.
+    /// * `f` - The future to run.
+    pub fn spawn_critical<F: Future<Output = ()> + Send + 'static>(
+        &mut self,
+        name: impl ToString,
+        f: F,
+    ) {
+        let task = self.supervise(name.to_string(), true, f);
+        self.task_pool.push(task);
+    }
+
+    /// Pin a service to a CPU core.
+    ///
+    /// A pinned service runs on a dedicated thread bound to the core instead of
+    /// the shared executor, so that it is not preempted by other services. The
+    /// affinity must be set before the service is scheduled.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service name, without the service address.
+    /// * `core` - The CPU core index.
+    pub fn set_affinity(&mut self, service: impl ToString, core: usize) {
+        self.affinity.push((service.to_string(), core));
+    }
+
+    /// Test if a service name refers to a service.
+    ///
+    /// The service name matches with or without the service address.
+    fn is_service(name: &str, service: &str) -> bool {
+        name == service
+            || name
+                .strip_prefix(service)
+                .is_some_and(|address| address.starts_with(" on "))
+    }
+
+    /// Find the CPU core a service is pinned to.
+    fn service_affinity(&self, name: &str) -> Option<usize> {
+        self.affinity
+            .iter()
+            .find(|(service, _)| Self::is_service(name, service))
+            .map(|(_, core)| *core)
+    }
+
+    /// Set the watchdog deadline of a service.
+    ///
+    /// The service is reported as stalled when it does not touch its heartbeat
+    /// within the deadline. Network services are watched by default, their
+    /// tick loop must complete within a few pipeline intervals. Other services
+    /// are only watched with a deadline. The deadline must be set before the
+    /// service is scheduled.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service name, without the service address.
+    /// * `deadline` - The maximum time between two heartbeats.
+    pub fn set_watchdog(&mut self, service: impl ToString, deadline: Duration) {
+        self.watchdog_deadline.push((service.to_string(), deadline));
+    }
+
+    /// Construct the heartbeat of a service.
+    ///
+    /// The heartbeat is watched if a deadline is set for the service, or if
+    /// the service has a default deadline.
+    fn watch_service(&self, name: &str, default: Option<Duration>) -> Heartbeat {
+        let heartbeat = Heartbeat::new(self.clock.clone());
+
+        let deadline = self
+            .watchdog_deadline
+            .iter()
+            .find(|(service, _)| Self::is_service(name, service))
+            .map(|(_, deadline)| *deadline)
+            .or(default);
+
+        if let Some(deadline) = deadline {
+            debug!("Watch service: {} ({:?})", name, deadline);
+            self.watchdog.watch(name, heartbeat.clone(), deadline);
+        }
+
+        heartbeat
+    }
+
+    /// Subscribe to the command dispatcher.
+    ///
+    /// The dispatcher routes the command channel to a bounded queue per
+    /// subscriber, so a slow subscriber never loses a safety command. The
+    /// routing task is spawned with the first subscriber.
+    pub fn subscribe_commands(&mut self) -> CommandSubscriber {
+        if let Some(dispatcher) = &self.dispatcher {
+            return dispatcher.subscribe();
+        }
+
+        let dispatcher = CommandDispatcher::new(crate::consts::QUEUE_SIZE_COMMAND)
+            .with_latch(self.emergency.clone());
+        let subscriber = dispatcher.subscribe();
+
+        let router = dispatcher.clone();
+        let command_rx = self.command_tx.subscribe();
+        let command_counters = self.channels[0].counters.clone();
+        let mut shutdown = self.shutdown.0.subscribe();
+
+        self.spawn_named("command dispatcher", async move {
+            tokio::select! {
+                _ = router.route(command_rx, |count| command_counters.record_lag(count)) => {}
+                _ = shutdown.recv() => router.close(),
+            }
+        });
+
+        self.dispatcher = Some(dispatcher);
+
+        subscriber
+    }
+
+    /// Change the tick interval of a network service.
+    ///
+    /// The interval takes effect within one tick and is clamped to the minimum
+    /// tick interval. The tick interval control command changes the interval
+    /// of the network service at the network index of the command, network
+    /// services are indexed in the order they are scheduled.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - The service name, without the service address.
+    /// * `interval` - The tick interval, `None` restores the scheduled interval.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if a network service with the name is scheduled.
+    pub fn set_tick_interval(&self, service: &str, interval: Option<Duration>) -> bool {
+        let mut found = false;
+
+        for (_, interval_tx) in self
+            .tick_interval
+            .iter()
+            .filter(|(name, _)| Self::is_service(name, service))
+        {
+            interval_tx.send_replace(interval);
+            found = true;
+        }
+
+        found
+    }
+
+    /// Publish the status of stalled services.
+    ///
+    /// This method will spawn a task that checks the service heartbeats on
+    /// every interval. A stalled service is published as a degraded or faulty
+    /// module status signal on every interval, a recovered service is
+    /// published once as healthy.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval at which the heartbeats are checked.
+    pub fn register_watchdog(&mut self, interval: Duration) {
+        if self.watchdog_registered {
+            return;
+        }
+
+        debug!("Register watchdog");
+
+        self.watchdog_registered = true;
+
+        let watchdog = self.watchdog.clone();
+        let signal_tx = self.signal_tx.clone();
+        let mut shutdown = self.shutdown.0.subscribe();
+
+        self.spawn_named("watchdog", async move {
+            let mut interval = tokio::time::interval(interval);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.recv() => break,
+                }
+
+                for status in watchdog.check() {
+                    if status.is_healthy() {
+                        info!("{}", status);
+                    } else {
+                        warn!("{}", status);
+                    }
+
+                    signal_tx.send(Object::ModuleStatus(status)).ok();
+                }
+            }
+        });
+    }
+
+    /// Emergency stop latch of the command dispatcher.
+    pub fn emergency_latch(&self) -> &EmergencyLatch {
+        &self.emergency
+    }
+
+    /// Latch the emergency stop on a faulty module.
+    ///
+    /// This method will spawn a task that observes the module status signals.
+    /// A faulty module latches the emergency stop and stops all motion. The
+    /// latch state is published as a module status signal on every interval
+    /// and on every change.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval at which the latch state is published.
+    pub fn register_emergency_latch(&mut self, interval: Duration) {
+        debug!("Register emergency latch");
+
+        let latch = self.emergency.clone();
+        let command_tx = self.command_tx.clone();
+        let signal_tx = self.signal_tx.clone();
+        let mut signal_rx = self.signal_tx.subscribe();
+        let mut shutdown = self.shutdown.0.subscribe();
+
+        self.spawn_named("emergency latch", async move {
+            let mut interval = tokio::time::interval(interval);
+            let mut last_latched = None;
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        last_latched = None;
+                    }
+                    signal = signal_rx.recv() => match signal {
+                        Ok(Object::ModuleStatus(status)) => {
+                            if latch.observe(&status) {
+                                warn!("Emergency stop latched by {}", status);
+                                command_tx.send(Object::Motion(Motion::StopAll)).ok();
+                            }
+                        }
+                        Ok(_) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = shutdown.recv() => break,
+                }
+
+                let latched = latch.is_latched();
+                if last_latched != Some(latched) {
+                    last_latched = Some(latched);
+                    signal_tx.send(Object::ModuleStatus(latch.status())).ok();
+                }
+            }
+        });
+    }
+
+    /// Spawns a service onto the runtime's executor.
+    ///
+    /// The service must wait for the teardown permit before it tears down, so
+    /// that services are torn down by stage and in the reverse order of
+    /// startup within a stage.
+    fn spawn_service<F: Future<Output = ()> + Send + 'static>(
+        &mut self,
+        name: String,
+        critical: bool,
+        stage: Teardown,
+        teardown_tx: tokio::sync::oneshot::Sender<()>,
+        f: F,
+    ) {
+        let task = match self.service_affinity(&name) {
+            Some(core) => {
+                let f = affinity::spawn_pinned(name.clone(), core, self.pin.clone(), f);
+                self.supervise(name, critical, f)
+            }
+            None => self.supervise(name, critical, f),
+        };
+        self.service_pool.push((stage, teardown_tx, task));
+    }
+
+    /// Retrieve the failures of the runtime tasks.
+    ///
+    /// A task fails when it panics. Failures are recorded in the order they
+    /// occurred.
+    pub fn task_failures(&self) -> Vec<TaskFailure> {
+        self.task_failures.lock().unwrap().clone()
     }
 
     /// Listen for IO event service in the background.
@@ -182,28 +695,46 @@ impl Runtime {
         C: Clone + Send + 'static,
     {
         let command_tx = self.command_tx.clone();
-        let signal_rx = self.signal_rx.resubscribe();
+        let signal_tx = self.signal_tx.clone();
         let mut shutdown = self.shutdown.0.subscribe();
 
         let mut service = S::new(config.clone());
+        service.set_clock(self.clock.clone());
+
+        let heartbeat = self.watch_service(&service.ctx().to_string(), None);
+        service.set_heartbeat(heartbeat.clone());
 
         debug!("Schedule IO service: {}", service.ctx());
 
         if self.shutdown.1.is_empty() {
-            self.spawn(async move {
-                service.setup().await;
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            self.readiness.push((service.ctx().to_string(), ready_rx));
 
-                tokio::select! {
-                    _ = async {
-                        loop {
-                            service.wait_io_sub(command_tx.clone(), signal_rx.resubscribe()).await;
-                        }
-                    } => {}
-                    _ = shutdown.recv() => {}
-                }
+            let (teardown_tx, teardown_rx) = tokio::sync::oneshot::channel();
 
-                service.teardown().await;
-            });
+            self.spawn_service(
+                service.ctx().to_string(),
+                false,
+                Teardown::Service,
+                teardown_tx,
+                async move {
+                    service.setup().await;
+                    ready_tx.send(()).ok();
+
+                    tokio::select! {
+                        _ = async {
+                            loop {
+                                heartbeat.touch();
+                                service.wait_io_sub(command_tx.clone(), signal_tx.subscribe()).await;
+                            }
+                        } => {}
+                        _ = shutdown.recv() => {}
+                    }
+
+                    teardown_rx.await.ok();
+                    service.teardown().await;
+                },
+            );
         }
     }
 
@@ -216,98 +747,289 @@ impl Runtime {
         let mut shutdown = self.shutdown.0.subscribe();
 
         let mut service = S::new(config.clone());
+        service.set_clock(self.clock.clone());
+
+        let heartbeat = self.watch_service(&service.ctx().to_string(), None);
+        service.set_heartbeat(heartbeat.clone());
 
         debug!("Schedule IO service: {}", service.ctx());
 
         if self.shutdown.1.is_empty() {
-            self.spawn(async move {
-                service.setup().await;
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            self.readiness.push((service.ctx().to_string(), ready_rx));
 
-                tokio::select! {
-                    _ = async {
-                        loop {
-                            service.wait_io_pub(signal_tx.clone()).await;
-                        }
-                    } => {}
-                    _ = shutdown.recv() => {}
-                }
+            let (teardown_tx, teardown_rx) = tokio::sync::oneshot::channel();
 
-                service.teardown().await;
-            });
+            self.spawn_service(
+                service.ctx().to_string(),
+                false,
+                Teardown::Service,
+                teardown_tx,
+                async move {
+                    service.setup().await;
+                    ready_tx.send(()).ok();
+
+                    tokio::select! {
+                        _ = async {
+                            loop {
+                                heartbeat.touch();
+                                service.wait_io_pub(signal_tx.clone()).await;
+                            }
+                        } => {}
+                        _ = shutdown.recv() => {}
+                    }
+
+                    teardown_rx.await.ok();
+                    service.teardown().await;
+                },
+            );
+        }
+    }
+
+    /// Listen for IO event and publish derived signals in the background.
+    ///
+    /// This method will spawn a service in the background and return immediately. The service
+    /// receives all signals and can publish new signals on the same bus.
+    pub fn schedule_io_pipe_service<S, C>(&mut self, config: C)
+    where
+        S: Service<C> + Send + Sync + 'static,
+        C: Clone + Send + 'static,
+    {
+        let signal_tx = self.signal_tx.clone();
+        let mut shutdown = self.shutdown.0.subscribe();
+
+        let mut service = S::new(config.clone());
+        service.set_clock(self.clock.clone());
+        service.set_command_sender(self.command_tx.clone());
+
+        let heartbeat = self.watch_service(&service.ctx().to_string(), None);
+        service.set_heartbeat(heartbeat.clone());
+
+        debug!("Schedule IO service: {}", service.ctx());
+
+        if self.shutdown.1.is_empty() {
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            self.readiness.push((service.ctx().to_string(), ready_rx));
+
+            let (teardown_tx, teardown_rx) = tokio::sync::oneshot::channel();
+
+            self.spawn_service(
+                service.ctx().to_string(),
+                false,
+                Teardown::Service,
+                teardown_tx,
+                async move {
+                    service.setup().await;
+                    ready_tx.send(()).ok();
+
+                    tokio::select! {
+                        _ = async {
+                            loop {
+                                heartbeat.touch();
+                                service.wait_io_pipe(signal_tx.clone(), signal_tx.subscribe()).await;
+                            }
+                        } => {}
+                        _ = shutdown.recv() => {}
+                    }
+
+                    teardown_rx.await.ok();
+                    service.teardown().await;
+                },
+            );
         }
     }
 
+    /// Schedule a network service.
+    ///
+    /// The service is ticked with the tick interval between two ticks. The
+    /// interval can be changed while running, either with the tick interval
+    /// control command or with `set_tick_interval`. The control command
+    /// addresses the service by its network index, which counts the network
+    /// services in the order they are scheduled.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The service configuration.
+    /// * `duration` - The scheduled tick interval.
     pub fn schedule_net_service<S, C>(&mut self, config: C, duration: Duration)
     where
         S: NetworkService<C> + Clone + Send + 'static,
         C: Clone + Send + 'static,
     {
-        let mut command_rx = self.command_tx.subscribe();
-
         let signal1_tx = self.signal_tx.clone();
         let signal2_tx = self.signal_tx.clone();
 
-        let mut service1 = S::new(config.clone());
+        let mut service1 = match S::new(config.clone()) {
+            Ok(service) => service,
+            Err(e) => {
+                let name = match e {
+                    Error::ServiceSetup(name) => name,
+                    e => {
+                        error!("Failed to create network service: {}", e);
+                        std::any::type_name::<S>().to_string()
+                    }
+                };
+
+                // The service never becomes ready.
+                let (_, ready_rx) = tokio::sync::oneshot::channel();
+                self.readiness.push((name, ready_rx));
+                return;
+            }
+        };
+        service1.set_clock(self.clock.clone());
         let mut service2 = service1.clone();
         let mut service3 = service1.clone();
+        let mut service4 = service1.clone();
 
         if self.shutdown.1.is_empty() {
+            let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+            self.readiness.push((service1.ctx().to_string(), ready_rx));
+
+            let name = service1.ctx().to_string();
+            let mut tasks = Vec::with_capacity(3);
+
             let mut shutdown = self.shutdown.0.subscribe();
+            let clock = self.clock.clone();
+            let tick_name = name.clone();
+            let status_name = format!("runtime:tick:{}", name);
+            let heartbeat = self.watch_service(
+                &name,
+                Some(
+                    duration.max(crate::consts::SERVICE_PIPELINE_INTERVAL)
+                        * watchdog::DEADLINE_INTERVALS,
+                ),
+            );
 
-            self.spawn(async move {
-                service1.setup().await;
+            let network = self.tick_interval.len();
+            let (interval_tx, mut interval_rx) = tokio::sync::watch::channel(None);
+            self.tick_interval.push((name.clone(), interval_tx.clone()));
+
+            let mut commands = self.subscribe_commands();
+
+            tasks.push(self.supervise(format!("{}: tick", name), true, async move {
+                let mut monitor = DeadlineMonitor::new(duration);
 
                 tokio::select! {
                     _ = async {
                         loop {
-                            service1.recv(signal1_tx.clone()).await;
+                            heartbeat.touch();
+
+                            let start = clock.now();
+                            service2.on_tick(signal2_tx.clone()).await;
+
+                            monitor.record(clock.elapsed(start));
+
+                            if let Some(status) = monitor.report(&status_name, clock.now()) {
+                                if !status.is_healthy() {
+                                    warn!("[{}] Tick {}", tick_name, monitor);
+                                }
+
+                                signal2_tx.send(Object::ModuleStatus(status)).ok();
+                            }
+
+                            tick_sleep(&mut interval_rx, duration, &heartbeat).await;
                         }
                     } => {}
                     _ = shutdown.recv() => {}
                 }
+            }));
 
-                service1.teardown().await;
-            });
+            let mut shutdown = self.shutdown.0.subscribe();
+
+            tasks.push(
+                self.supervise(format!("{}: command", name), true, async move {
+                    tokio::select! {
+                        _ = async {
+                            // The dispatcher delivers safety commands ahead of
+                            // the commands that queued up while the last command
+                            // was handled.
+                            while let Some(object) = commands.recv().await {
+                                if let Object::Control(Control::TickInterval {
+                                    network: target,
+                                    interval,
+                                }) = object
+                                {
+                                    if target as usize == network {
+                                        let interval = (interval > 0)
+                                            .then(|| Duration::from_millis(interval as u64 * 10));
+                                        interval_tx.send_replace(interval);
+                                    }
+                                }
+
+                                service3.on_command(&object).await;
+                            }
+                        } => {}
+                        _ = shutdown.recv() => {}
+                    }
+                }),
+            );
 
             let mut shutdown = self.shutdown.0.subscribe();
 
-            self.spawn(async move {
+            tasks.push(self.supervise(format!("{}: stop", name), true, async move {
                 tokio::select! {
                     _ = async {
                         loop {
-                            service2.on_tick(signal2_tx.clone()).await;
-                            tokio::time::sleep(duration).await;
+                            service4.on_stop().await;
                         }
                     } => {}
                     _ = shutdown.recv() => {}
                 }
-            });
+            }));
 
+            let (teardown_tx, teardown_rx) = tokio::sync::oneshot::channel();
             let mut shutdown = self.shutdown.0.subscribe();
 
-            self.spawn(async move {
+            self.spawn_service(name, true, Teardown::Network, teardown_tx, async move {
+                service1.setup().await;
+                ready_tx.send(()).ok();
+
                 tokio::select! {
                     _ = async {
                         loop {
-                            match command_rx.recv().await {
-                                Ok(object) => {
-                                    service3.on_command(&object).await;
-                                }
-                                Err(RecvError::Lagged(count)) => {
-                                    warn!("Command receiver lagged by {} objects", count);
-                                }
-                                Err(RecvError::Closed) => {
-                                    break;
-                                }
-                            }
+                            service1.recv(signal1_tx.clone()).await;
                         }
                     } => {}
                     _ = shutdown.recv() => {}
                 }
+
+                for task in tasks {
+                    task.await.ok();
+                }
+
+                teardown_rx.await.ok();
+
+                // No more commands are handled, the final stop is the last
+                // command to reach the network before the service tears down.
+                service1.on_command(&Object::Motion(Motion::StopAll)).await;
+                service1.teardown().await;
             });
         }
     }
 
+    /// Wait for all scheduled services to complete setup.
+    ///
+    /// This method will block until every service scheduled so far has completed
+    /// its setup. If a service terminates before completing setup, the name of
+    /// that service is returned as an error.
+    ///
+    /// # Returns
+    ///
+    /// The names of all services that are ready.
+    pub async fn wait_for_ready(&mut self) -> Result<Vec<String>> {
+        let mut services = Vec::new();
+
+        for (name, ready_rx) in self.readiness.drain(..) {
+            if ready_rx.await.is_err() {
+                return Err(Error::ServiceSetup(name));
+            }
+
+            services.push(name);
+        }
+
+        Ok(services)
+    }
+
     /// Wait for the runtime to shutdown.
     ///
     /// This method will block until the runtime is shutdown.
@@ -317,10 +1039,441 @@ impl Runtime {
 
     /// Wait for all tasks to complete.
     ///
-    /// This method will block until all tasks are completed.    
+    /// This method will block until all tasks are completed. Services are torn
+    /// down one at a time, the network services after every other service, so
+    /// that services issuing commands are gone before the network services
+    /// send their final stop and close. Within a stage, services are torn down
+    /// in the reverse order of startup, regardless of the order in which the
+    /// stages were scheduled.
     pub async fn wait_for_tasks(&mut self) {
+        for stage in [Teardown::Service, Teardown::Network] {
+            while let Some(index) = self
+                .service_pool
+                .iter()
+                .rposition(|(service_stage, _, _)| *service_stage == stage)
+            {
+                let (_, teardown_tx, task) = self.service_pool.remove(index);
+
+                teardown_tx.send(()).ok();
+                task.await.ok();
+            }
+        }
+
         for task in self.task_pool.drain(..) {
             task.await.ok();
         }
     }
 }
+
+/// Sleep for the tick interval.
+///
+/// The sleep is cut short or extended when the tick interval changes, so the
+/// new interval takes effect within one tick. The time slept beyond the
+/// scheduled interval is idle time for the watchdog.
+///
+/// # Arguments
+///
+/// * `interval_rx` - The tick interval, `None` for the scheduled interval.
+/// * `scheduled` - The scheduled tick interval.
+/// * `heartbeat` - The service heartbeat.
+async fn tick_sleep(
+    interval_rx: &mut tokio::sync::watch::Receiver<Option<Duration>>,
+    scheduled: Duration,
+    heartbeat: &Heartbeat,
+) {
+    let start = tokio::time::Instant::now();
+
+    loop {
+        let interval = interval_rx
+            .borrow_and_update()
+            .map_or(scheduled, |interval| {
+                interval.max(crate::consts::SERVICE_TICK_INTERVAL_MIN)
+            });
+
+        heartbeat.set_idle(interval.saturating_sub(scheduled));
+
+        tokio::select! {
+            _ = tokio::time::sleep_until(start + interval) => break,
+            result = interval_rx.changed() => {
+                if result.is_err() {
+                    tokio::time::sleep_until(start + interval).await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::core::Engine;
+
+    type Events = Arc<Mutex<Vec<String>>>;
+
+    #[derive(Clone)]
+    struct RecordConfig(Events);
+
+    #[derive(Clone)]
+    struct RecordNetService(Events);
+
+    impl NetworkService<RecordConfig> for RecordNetService {
+        fn new(config: RecordConfig) -> Result<Self> {
+            Ok(Self(config.0))
+        }
+
+        async fn recv(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_tick(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_command(&mut self, object: &Object) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("net command: {:?}", object));
+        }
+
+        async fn teardown(&mut self) {
+            self.0.lock().unwrap().push("net teardown".to_string());
+        }
+    }
+
+    struct RecordIoService(Events);
+
+    impl Service<RecordConfig> for RecordIoService {
+        fn new(config: RecordConfig) -> Self {
+            Self(config.0)
+        }
+
+        async fn wait_io_sub(&mut self, _: CommandSender, _: SignalReceiver) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn teardown(&mut self) {
+            self.0.lock().unwrap().push("io teardown".to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn teardown_order() {
+        let events = Events::default();
+
+        // The services are scheduled in the order of the server, the network
+        // services last.
+        let mut runtime = Runtime::default();
+        runtime.schedule_io_sub_service::<RecordIoService, _>(RecordConfig(events.clone()));
+        runtime.schedule_net_service::<RecordNetService, _>(
+            RecordConfig(events.clone()),
+            Duration::from_millis(10),
+        );
+        runtime.wait_for_ready().await.unwrap();
+
+        let engine = Object::Engine(Engine::from_rpm(1_200));
+        runtime.command_tx.send(engine.clone()).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while events.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        runtime.shutdown.0.send(()).unwrap();
+        runtime.wait_for_tasks().await;
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                format!("net command: {:?}", engine),
+                "io teardown".to_string(),
+                format!("net command: {:?}", Object::Motion(Motion::StopAll)),
+                "net teardown".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn task_panic_capture() {
+        let mut runtime = Runtime::default();
+
+        runtime.spawn_named("faulty task", async {
+            panic!("sensor lost");
+        });
+        runtime.wait_for_tasks().await;
+
+        let failures = runtime.task_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "faulty task");
+        assert_eq!(
+            failures[0].to_string(),
+            "task 'faulty task' panicked: sensor lost"
+        );
+
+        runtime.spawn_critical("critical task", async {
+            panic!("bus lost");
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), runtime.wait_for_shutdown())
+            .await
+            .unwrap();
+        runtime.wait_for_tasks().await;
+
+        assert_eq!(runtime.task_failures()[1].name, "critical task");
+    }
+
+    #[tokio::test]
+    async fn service_affinity() {
+        let events = Events::default();
+
+        let pin: affinity::PinFn = {
+            let events = events.clone();
+            Arc::new(move |core| {
+                let thread = std::thread::current();
+                events
+                    .lock()
+                    .unwrap()
+                    .push(format!("pin {} {}", thread.name().unwrap(), core));
+                Ok(())
+            })
+        };
+
+        let mut runtime = Runtime {
+            pin,
+            ..Default::default()
+        };
+
+        runtime.set_affinity(std::any::type_name::<RecordIoService>(), 3);
+        runtime.schedule_io_sub_service::<RecordIoService, _>(RecordConfig(events.clone()));
+        runtime.schedule_net_service::<RecordNetService, _>(
+            RecordConfig(events.clone()),
+            Duration::from_millis(10),
+        );
+        runtime.wait_for_ready().await.unwrap();
+
+        runtime.shutdown.0.send(()).unwrap();
+        runtime.wait_for_tasks().await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events[0], "pin pinned-3 3");
+        assert_eq!(events.iter().filter(|e| e.starts_with("pin")).count(), 1);
+        assert!(events.contains(&"io teardown".to_string()));
+
+        assert_eq!(runtime.service_affinity("vehicle director"), None);
+        runtime.set_affinity("vehicle director", 1);
+        assert_eq!(runtime.service_affinity("vehicle director"), Some(1));
+        assert_eq!(
+            runtime.service_affinity("vehicle director on /run/glonax"),
+            Some(1)
+        );
+        assert_eq!(runtime.service_affinity("vehicle"), None);
+    }
+
+    #[test]
+    fn pin_out_of_range() {
+        assert!(pin_current_thread(usize::MAX).is_err());
+    }
+
+    #[derive(Clone)]
+    struct SlowTickService;
+
+    impl NetworkService<NullConfig> for SlowTickService {
+        fn new(_: NullConfig) -> Result<Self> {
+            Ok(Self)
+        }
+
+        async fn recv(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_tick(&mut self, _: SignalSender) {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+        }
+
+        async fn on_command(&mut self, _: &Object) {}
+    }
+
+    #[tokio::test]
+    async fn tick_deadline_miss() {
+        let mut runtime = Runtime::default();
+        let mut signal_rx = runtime.signal_tx.subscribe();
+
+        runtime.schedule_net_service::<SlowTickService, _>(NullConfig, Duration::from_millis(10));
+        runtime.wait_for_ready().await.unwrap();
+
+        let status = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let Ok(Object::ModuleStatus(status)) = signal_rx.recv().await {
+                    break status;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(status.name.starts_with("runtime:tick:"));
+        assert_eq!(status.state, crate::core::ModuleState::Degraded);
+
+        runtime.shutdown.0.send(()).unwrap();
+        runtime.wait_for_tasks().await;
+    }
+
+    #[derive(Clone)]
+    struct StallTickService(Arc<std::sync::atomic::AtomicBool>);
+
+    impl NetworkService<NullConfig> for StallTickService {
+        fn new(_: NullConfig) -> Result<Self> {
+            Ok(Self(Arc::new(std::sync::atomic::AtomicBool::new(false))))
+        }
+
+        fn ctx(&self) -> ServiceContext {
+            ServiceContext::new("stall service")
+        }
+
+        async fn recv(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_tick(&mut self, _: SignalSender) {
+            // The first tick blocks the tick loop.
+            if !self.0.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        }
+
+        async fn on_command(&mut self, _: &Object) {}
+    }
+
+    #[tokio::test]
+    async fn watchdog_stalled_service() {
+        let mut runtime = Runtime::default();
+        let mut signal_rx = runtime.signal_tx.subscribe();
+
+        runtime.register_watchdog(Duration::from_millis(10));
+        runtime.schedule_net_service::<StallTickService, _>(NullConfig, Duration::from_millis(10));
+        runtime.wait_for_ready().await.unwrap();
+        assert_eq!(runtime.watchdog.len(), 1);
+
+        let states = tokio::time::timeout(Duration::from_secs(2), async {
+            let mut states = vec![];
+            loop {
+                if let Ok(Object::ModuleStatus(status)) = signal_rx.recv().await {
+                    if status.name == "runtime:watchdog:stall service" {
+                        states.push(status.state);
+                        if status.is_healthy() {
+                            break states;
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        // Degraded after the deadline, faulty after four times the deadline.
+        assert_eq!(states[0], crate::core::ModuleState::Degraded);
+        assert!(states.contains(&crate::core::ModuleState::Faulty));
+        assert_eq!(states.last(), Some(&crate::core::ModuleState::Healthy));
+
+        runtime.shutdown.0.send(()).unwrap();
+        runtime.wait_for_tasks().await;
+    }
+
+    #[derive(Clone)]
+    struct CountTickService(Arc<std::sync::atomic::AtomicUsize>);
+
+    impl NetworkService<Arc<std::sync::atomic::AtomicUsize>> for CountTickService {
+        fn new(ticks: Arc<std::sync::atomic::AtomicUsize>) -> Result<Self> {
+            Ok(Self(ticks))
+        }
+
+        fn ctx(&self) -> ServiceContext {
+            ServiceContext::with_address("count service", "vcan0")
+        }
+
+        async fn recv(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn on_tick(&mut self, _: SignalSender) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        async fn on_command(&mut self, _: &Object) {}
+    }
+
+    #[tokio::test]
+    async fn tick_interval_change() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let other_ticks = Arc::new(AtomicUsize::new(0));
+
+        let mut runtime = Runtime::default();
+        runtime.schedule_net_service::<CountTickService, _>(
+            other_ticks.clone(),
+            Duration::from_millis(10),
+        );
+        runtime
+            .schedule_net_service::<CountTickService, _>(ticks.clone(), Duration::from_millis(10));
+        runtime.wait_for_ready().await.unwrap();
+
+        let count_ticks = |window: Duration| {
+            let ticks = ticks.clone();
+            let other_ticks = other_ticks.clone();
+            async move {
+                let start = ticks.load(Ordering::SeqCst);
+                let other_start = other_ticks.load(Ordering::SeqCst);
+                tokio::time::sleep(window).await;
+                (
+                    ticks.load(Ordering::SeqCst) - start,
+                    other_ticks.load(Ordering::SeqCst) - other_start,
+                )
+            }
+        };
+
+        let (count, _) = count_ticks(Duration::from_millis(200)).await;
+        assert!(count >= 8);
+
+        // Slow down the second network to 2.55 seconds, the tick in progress
+        // is extended. The first network keeps its interval.
+        runtime
+            .command_tx
+            .send(Object::Control(Control::TickInterval {
+                network: 1,
+                interval: 255,
+            }))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (count, other_count) = count_ticks(Duration::from_millis(300)).await;
+        assert!(count <= 1);
+        assert!(other_count >= 12);
+
+        // Restore the scheduled interval, the long sleep is cut short.
+        runtime
+            .command_tx
+            .send(Object::Control(Control::TickInterval {
+                network: 1,
+                interval: 0,
+            }))
+            .unwrap();
+        let (count, _) = count_ticks(Duration::from_millis(200)).await;
+        assert!(count >= 8);
+
+        // The interval is clamped to the minimum.
+        assert!(runtime.set_tick_interval("count service", Some(Duration::ZERO)));
+        assert!(!runtime.set_tick_interval("other service", None));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let (count, _) = count_ticks(Duration::from_millis(100)).await;
+        assert!(count > 0 && count <= 25);
+
+        runtime.shutdown.0.send(()).unwrap();
+        runtime.wait_for_tasks().await;
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::VecDeque;
+
+use crate::core::{Control, Motion, Object};
+
+/// Outcome of a command enqueued in a bounded queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The command was enqueued.
+    None,
+    /// The motion change was coalesced into a queued motion change.
+    Coalesced,
+    /// The oldest normal command was dropped to make room.
+    Dropped,
+}
+
+/// Two-level command queue.
+///
+/// Safety commands are dequeued before normal commands, so an emergency stop
+/// is never delayed behind a backlog of motion changes. The order within each
+/// level is preserved.
+///
+/// A stop purges the queued motion it overrides, so no motion from before the
+/// stop is delivered after it. A lock purges the queued unlocks for the same
+/// reason.
+#[derive(Debug, Default)]
+pub struct CommandQueue {
+    /// Safety commands.
+    safety: VecDeque<Object>,
+    /// Normal commands.
+    normal: VecDeque<Object>,
+    /// Number of normal commands queued before the last safety command.
+    barrier: usize,
+}
+
+impl CommandQueue {
+    /// Test if the command is a safety command.
+    ///
+    /// Safety commands stop motion, lock the hydraulics or shut the machine
+    /// down.
+    pub fn is_safety(object: &Object) -> bool {
+        matches!(
+            object,
+            Object::Motion(Motion::StopAll)
+                | Object::Motion(Motion::Stop(_))
+                | Object::Control(Control::HydraulicLock(true))
+                | Object::Control(Control::MachineShutdown)
+        )
+    }
+
+    /// Enqueue a command.
+    pub fn push(&mut self, object: Object) {
+        if Self::is_safety(&object) {
+            self.purge(&object);
+            self.barrier = self.normal.len();
+            self.safety.push_back(object);
+        } else {
+            self.normal.push_back(object);
+        }
+    }
+
+    /// Purge the queued normal commands overridden by a safety command.
+    fn purge(&mut self, object: &Object) {
+        match object {
+            Object::Motion(Motion::StopAll) => {
+                self.normal
+                    .retain(|queued| !matches!(queued, Object::Motion(_)));
+            }
+            Object::Motion(Motion::Stop(actuators)) => {
+                self.normal.retain_mut(|queued| match queued {
+                    Object::Motion(Motion::Change(changes)) => {
+                        changes.retain(|change| !actuators.contains(&change.actuator));
+                        !changes.is_empty()
+                    }
+                    _ => true,
+                });
+            }
+            Object::Control(Control::HydraulicLock(true)) => {
+                self.normal.retain(|queued| {
+                    !matches!(queued, Object::Control(Control::HydraulicLock(false)))
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Dequeue the oldest normal command.
+    fn pop_normal(&mut self) -> Option<Object> {
+        self.barrier = self.barrier.saturating_sub(1);
+        self.normal.pop_front()
+    }
+
+    /// Enqueue a command with a bounded number of normal commands.
+    ///
+    /// Safety commands are never dropped. When the normal commands are at
+    /// capacity, a motion change is coalesced into the most recent queued
+    /// motion change, the latest value per actuator wins. A motion change is
+    /// never coalesced into a change queued before the last safety command.
+    /// Any other command drops the oldest normal command.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The command to enqueue.
+    /// * `capacity` - The maximum number of normal commands.
+    pub fn push_bounded(&mut self, object: Object, capacity: usize) -> Overflow {
+        if Self::is_safety(&object) || self.normal.len() < capacity {
+            self.push(object);
+            return Overflow::None;
+        }
+
+        if let Object::Motion(Motion::Change(changes)) = &object {
+            let queued = self
+                .normal
+                .iter_mut()
+                .skip(self.barrier)
+                .rev()
+                .find_map(|queued| match queued {
+                    Object::Motion(Motion::Change(queued)) => Some(queued),
+                    _ => None,
+                });
+
+            if let Some(queued) = queued {
+                for change in changes {
+                    match queued.iter_mut().find(|c| c.actuator == change.actuator) {
+                        Some(queued_change) => queued_change.value = change.value,
+                        None => queued.push(*change),
+                    }
+                }
+
+                return Overflow::Coalesced;
+            }
+        }
+
+        self.pop_normal();
+        self.normal.push_back(object);
+
+        Overflow::Dropped
+    }
+
+    /// Dequeue the next command.
+    ///
+    /// Safety commands are returned before normal commands.
+    pub fn pop(&mut self) -> Option<Object> {
+        self.safety.pop_front().or_else(|| self.pop_normal())
+    }
+
+    /// Number of queued commands.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.safety.len() + self.normal.len()
+    }
+
+    /// Test if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.safety.is_empty() && self.normal.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::Actuator;
+
+    #[test]
+    fn stop_all_jumps_queue() {
+        let mut queue = CommandQueue::default();
+
+        let engine = Object::Engine(crate::core::Engine::from_rpm(1_200));
+        queue.push(engine.clone());
+        for value in 0..100i16 {
+            queue.push(Object::Motion(Motion::new(Actuator::Boom, value)));
+        }
+        queue.push(Object::Motion(Motion::StopAll));
+        queue.push(Object::Motion(Motion::new(Actuator::Arm, 7i16)));
+
+        assert_eq!(queue.len(), 3);
+
+        // No motion from before the stop follows it.
+        assert_eq!(queue.pop(), Some(Object::Motion(Motion::StopAll)));
+        assert_eq!(queue.pop(), Some(engine));
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::new(Actuator::Arm, 7i16)))
+        );
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn stop_purges_actuators() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Object::Motion(Motion::from_iter([
+            (Actuator::Boom, 10),
+            (Actuator::Arm, 20),
+        ])));
+        queue.push(Object::Motion(Motion::new(Actuator::Boom, 30i16)));
+        queue.push(Object::Motion(Motion::Stop(vec![Actuator::Boom])));
+
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::Stop(vec![Actuator::Boom])))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::new(Actuator::Arm, 20i16)))
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn bounded_no_coalesce_across_stop() {
+        let mut queue = CommandQueue::default();
+
+        for value in 0..2i16 {
+            queue.push(Object::Motion(Motion::new(Actuator::Arm, value)));
+        }
+        queue.push(Object::Motion(Motion::Stop(vec![Actuator::Boom])));
+
+        // The change after the stop is not merged into a change before it.
+        let object = Object::Motion(Motion::new(Actuator::Boom, 10i16));
+        assert_eq!(queue.push_bounded(object, 2), Overflow::Dropped);
+        let object = Object::Motion(Motion::new(Actuator::Boom, 20i16));
+        assert_eq!(queue.push_bounded(object, 2), Overflow::Coalesced);
+
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::Stop(vec![Actuator::Boom])))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::new(Actuator::Arm, 1i16)))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::new(Actuator::Boom, 20i16)))
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn lock_purges_unlock() {
+        let mut queue = CommandQueue::default();
+
+        queue.push(Object::Control(Control::HydraulicLock(false)));
+        queue.push(Object::Control(Control::HydraulicLock(true)));
+
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Control(Control::HydraulicLock(true)))
+        );
+        assert!(queue.is_empty());
+
+        // An unlock after the lock is kept in order.
+        queue.push(Object::Control(Control::HydraulicLock(true)));
+        queue.push(Object::Control(Control::HydraulicLock(false)));
+
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Control(Control::HydraulicLock(true)))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Control(Control::HydraulicLock(false)))
+        );
+    }
+
+    #[test]
+    fn bounded_coalesce() {
+        let mut queue = CommandQueue::default();
+
+        let engine = Object::Engine(crate::core::Engine::from_rpm(1_200));
+        assert_eq!(queue.push_bounded(engine.clone(), 3), Overflow::None);
+
+        for value in 0..2i16 {
+            let object = Object::Motion(Motion::new(Actuator::Boom, value));
+            assert_eq!(queue.push_bounded(object, 3), Overflow::None);
+        }
+
+        let object = Object::Motion(Motion::new(Actuator::Boom, 10i16));
+        assert_eq!(queue.push_bounded(object, 3), Overflow::Coalesced);
+        let object = Object::Motion(Motion::new(Actuator::Arm, 20i16));
+        assert_eq!(queue.push_bounded(object, 3), Overflow::Coalesced);
+
+        assert_eq!(queue.pop(), Some(engine));
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::new(Actuator::Boom, 0i16)))
+        );
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Motion(Motion::from_iter([
+                (Actuator::Boom, 10),
+                (Actuator::Arm, 20)
+            ])))
+        );
+
+        // The stop purges the queued motion changes.
+        for value in 0..3i16 {
+            queue.push(Object::Motion(Motion::new(Actuator::Boom, value)));
+        }
+        let object = Object::Motion(Motion::StopAll);
+        assert_eq!(queue.push_bounded(object, 3), Overflow::None);
+        assert_eq!(queue.pop(), Some(Object::Motion(Motion::StopAll)));
+        assert!(queue.is_empty());
+
+        // Without a queued motion change the oldest command is dropped.
+        for id in 0..3 {
+            queue.push(Object::Control(Control::AttachmentSelect(id)));
+        }
+        let object = Object::Control(Control::AttachmentSelect(3));
+        assert_eq!(queue.push_bounded(object, 3), Overflow::Dropped);
+        assert_eq!(
+            queue.pop(),
+            Some(Object::Control(Control::AttachmentSelect(1)))
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::path::Path;
+
+/// Notify the service manager about a state change.
+///
+/// The notification is sent to the socket in the `NOTIFY_SOCKET` environment
+/// variable, which is set by systemd for services of `Type=notify`. When the
+/// variable is not set this method does nothing.
+///
+/// # Arguments
+///
+/// * `state` - The state to send, for example `READY=1`.
+///
+/// # Returns
+///
+/// `true` if the notification was sent, `false` if there is no service manager.
+pub fn sd_notify(state: &str) -> std::io::Result<bool> {
+    use std::os::unix::{
+        ffi::OsStrExt,
+        net::{SocketAddr, UnixDatagram},
+    };
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+
+    let address = if let Some(name) = path.as_bytes().strip_prefix(b"@") {
+        use std::os::linux::net::SocketAddrExt;
+
+        SocketAddr::from_abstract_name(name)?
+    } else {
+        SocketAddr::from_pathname(&path)?
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to_addr(state.as_bytes(), &address)?;
+
+    Ok(true)
+}
+
+/// Summary of the runtime once all services are ready.
+#[derive(Clone, Debug, PartialEq, Eq, serde_derive::Serialize)]
+pub struct ReadySummary {
+    /// Runtime version.
+    pub version: String,
+    /// Instance unique identifier.
+    pub instance: String,
+    /// Listener addresses.
+    pub listeners: Vec<String>,
+    /// Active services.
+    pub services: Vec<String>,
+}
+
+impl ReadySummary {
+    /// Serialize the summary as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Write the summary as JSON to a readiness file.
+    ///
+    /// The file is written to a temporary file first and then moved in
+    /// place, so readers never observe a partially written file.
+    pub fn write(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let path_tmp = path.with_extension("tmp");
+
+        std::fs::write(&path_tmp, self.to_json())?;
+        std::fs::rename(&path_tmp, path)
+    }
+}
+
+impl std::fmt::Display for ReadySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "READY version={} instance={} listeners={} services={}",
+            self.version,
+            self.instance,
+            self.listeners.join(","),
+            self.services.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{
+        CommandSender, NullConfig, Runtime, Service, ServiceContext, SignalReceiver, SignalSender,
+    };
+
+    struct ReadyService;
+
+    impl Service<NullConfig> for ReadyService {
+        fn new(_: NullConfig) -> Self {
+            Self
+        }
+
+        fn ctx(&self) -> ServiceContext {
+            ServiceContext::new("ready service")
+        }
+
+        async fn wait_io_sub(&mut self, _: CommandSender, _: SignalReceiver) {
+            std::future::pending::<()>().await;
+        }
+
+        async fn wait_io_pub(&mut self, _: SignalSender) {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    struct FaultyService;
+
+    impl Service<NullConfig> for FaultyService {
+        fn new(_: NullConfig) -> Self {
+            Self
+        }
+
+        fn ctx(&self) -> ServiceContext {
+            ServiceContext::with_address("faulty service", "vcan9")
+        }
+
+        async fn setup(&mut self) {
+            panic!("setup failed");
+        }
+    }
+
+    #[test]
+    fn ready_summary_file() {
+        let summary = ReadySummary {
+            version: "3.5.13".to_string(),
+            instance: "d55bcd75-8d30-49af-ac18-ee7cbce7822f".to_string(),
+            listeners: vec!["unix://tmp/glonax.sock".to_string()],
+            services: vec!["ready service".to_string()],
+        };
+
+        let path = std::env::temp_dir().join(format!("glonax-ready-{}.json", std::process::id()));
+        summary.write(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            content,
+            r#"{"version":"3.5.13","instance":"d55bcd75-8d30-49af-ac18-ee7cbce7822f","listeners":["unix://tmp/glonax.sock"],"services":["ready service"]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn runtime_ready() {
+        let mut runtime = Runtime::default();
+
+        runtime.schedule_io_sub_service::<ReadyService, _>(NullConfig);
+        runtime.schedule_io_pub_service::<ReadyService, _>(NullConfig);
+
+        let services = runtime.wait_for_ready().await.unwrap();
+        assert_eq!(services, vec!["ready service", "ready service"]);
+    }
+
+    #[tokio::test]
+    async fn runtime_not_ready() {
+        let mut runtime = Runtime::default();
+
+        runtime.schedule_io_sub_service::<ReadyService, _>(NullConfig);
+        runtime.schedule_io_sub_service::<FaultyService, _>(NullConfig);
+
+        match runtime.wait_for_ready().await {
+            Err(crate::Error::ServiceSetup(name)) => assert_eq!(name, "faulty service on vcan9"),
+            _ => panic!("runtime must not be ready"),
+        }
+    }
+}
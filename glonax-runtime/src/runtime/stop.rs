@@ -0,0 +1,100 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use tokio::sync::Notify;
+
+#[derive(Default)]
+struct StopLatchInner {
+    /// Whether the stop is latched.
+    latched: AtomicBool,
+    /// Name of the unit that asserted the stop.
+    source: Mutex<Option<String>>,
+    /// Wakes the stop task.
+    notify: Notify,
+}
+
+/// Motion stop latch.
+///
+/// The latch is asserted by a hardwired stop input and stays asserted until
+/// it is explicitly released. Releasing the stop input does not release the
+/// latch. The latch is shared between clones, so the unit that observes the
+/// stop input and the task that reacts to it can run independently of each
+/// other and of the signal channel.
+#[derive(Clone, Default)]
+pub struct StopLatch {
+    inner: Arc<StopLatchInner>,
+}
+
+impl StopLatch {
+    /// Assert the stop latch.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The name of the unit that asserted the stop.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the latch was not asserted before.
+    pub fn assert(&self, source: impl ToString) -> bool {
+        if self.inner.latched.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+
+        *self.inner.source.lock().unwrap() = Some(source.to_string());
+        self.inner.notify.notify_one();
+
+        true
+    }
+
+    /// Release the stop latch.
+    ///
+    /// This must only be called on an explicit operator action.
+    pub fn release(&self) {
+        *self.inner.source.lock().unwrap() = None;
+        self.inner.latched.store(false, Ordering::SeqCst);
+    }
+
+    /// Test if the stop latch is asserted.
+    #[inline]
+    pub fn is_latched(&self) -> bool {
+        self.inner.latched.load(Ordering::SeqCst)
+    }
+
+    /// Name of the unit that asserted the stop, if latched.
+    pub fn source(&self) -> Option<String> {
+        self.inner.source.lock().unwrap().clone()
+    }
+
+    /// Wait until the stop latch is asserted.
+    ///
+    /// Each assertion wakes a single waiter once. An assertion made while no
+    /// task is waiting is not lost.
+    pub async fn wait(&self) {
+        self.inner.notify.notified().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stop_latch() {
+        let latch = StopLatch::default();
+        assert!(!latch.is_latched());
+
+        assert!(latch.assert("laixer:vcu:0x27:0x12"));
+        assert!(!latch.assert("laixer:vcu:0x27:0x12"));
+
+        latch.wait().await;
+
+        assert!(latch.is_latched());
+        assert_eq!(latch.source().unwrap(), "laixer:vcu:0x27:0x12");
+
+        latch.release();
+        assert!(!latch.is_latched());
+        assert!(latch.source().is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{sync::broadcast::Sender, task::JoinHandle};
+
+/// Failure of a runtime task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskFailure {
+    /// Task name.
+    pub name: String,
+    /// Panic message or reason the task was cancelled.
+    pub reason: String,
+}
+
+impl TaskFailure {
+    fn from_join_error(name: &str, error: tokio::task::JoinError) -> Self {
+        let reason = if error.is_panic() {
+            let panic = error.into_panic();
+
+            if let Some(message) = panic.downcast_ref::<&str>() {
+                format!("panicked: {}", message)
+            } else if let Some(message) = panic.downcast_ref::<String>() {
+                format!("panicked: {}", message)
+            } else {
+                "panicked".to_string()
+            }
+        } else {
+            "cancelled".to_string()
+        };
+
+        Self {
+            name: name.to_string(),
+            reason,
+        }
+    }
+}
+
+impl std::fmt::Display for TaskFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task '{}' {}", self.name, self.reason)
+    }
+}
+
+/// Failures of the runtime tasks.
+pub(super) type TaskFailures = Arc<Mutex<Vec<TaskFailure>>>;
+
+/// Spawn a named task under supervision.
+///
+/// A panic of the task is logged with the task name and recorded in the
+/// failures. The failure of a critical task triggers a runtime shutdown.
+pub(super) fn spawn_supervised<F: Future<Output = ()> + Send + 'static>(
+    name: String,
+    critical: bool,
+    failures: TaskFailures,
+    shutdown: Sender<()>,
+    f: F,
+) -> JoinHandle<()> {
+    let task = tokio::spawn(f);
+
+    tokio::spawn(async move {
+        if let Err(e) = task.await {
+            let failure = TaskFailure::from_join_error(&name, e);
+
+            log::error!("Runtime {}", failure);
+            failures.lock().unwrap().push(failure);
+
+            if critical {
+                log::error!("Critical task '{}' failed, shutting down", name);
+                shutdown.send(()).ok();
+            }
+        }
+    })
+}
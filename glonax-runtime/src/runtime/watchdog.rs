@@ -0,0 +1,207 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::core::{ModuleError, ModuleState, ModuleStatus};
+
+use super::Clock;
+
+/// Number of pipeline intervals in the default watchdog deadline.
+pub(super) const DEADLINE_INTERVALS: u32 = 10;
+/// Factor of the deadline after which a stalled service is faulty.
+const FAULTY_FACTOR: u32 = 4;
+
+/// Service heartbeat.
+///
+/// The heartbeat is touched by the service on every iteration of its loop.
+/// The heartbeat is armed by the first touch, so a service is not considered
+/// stalled during its setup. Clones share the same heartbeat.
+#[derive(Clone)]
+pub struct Heartbeat {
+    /// Runtime clock.
+    clock: Clock,
+    /// Moment of the last touch.
+    last: Arc<Mutex<Option<Instant>>>,
+    /// Time the service idles between two touches.
+    idle: Arc<Mutex<Duration>>,
+}
+
+impl Heartbeat {
+    /// Construct a new heartbeat.
+    pub fn new(clock: Clock) -> Self {
+        Self {
+            clock,
+            last: Arc::new(Mutex::new(None)),
+            idle: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Signal that the service is alive.
+    pub fn touch(&self) {
+        *self.last.lock().unwrap() = Some(self.clock.now());
+    }
+
+    /// Time since the last touch, `None` if never touched.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.last
+            .lock()
+            .unwrap()
+            .map(|last| self.clock.elapsed(last))
+    }
+
+    /// Set the time the service idles between two touches.
+    ///
+    /// The idle time is allowed on top of the watchdog deadline, so a service
+    /// that sleeps longer between iterations is not considered stalled.
+    pub fn set_idle(&self, idle: Duration) {
+        *self.idle.lock().unwrap() = idle;
+    }
+
+    /// Time the service idles between two touches.
+    pub fn idle(&self) -> Duration {
+        *self.idle.lock().unwrap()
+    }
+}
+
+struct WatchEntry {
+    /// Service name.
+    name: String,
+    /// Service heartbeat.
+    heartbeat: Heartbeat,
+    /// Maximum time between two touches.
+    deadline: Duration,
+    /// Last reported state.
+    state: ModuleState,
+}
+
+impl WatchEntry {
+    fn state(&self) -> ModuleState {
+        let idle = self.heartbeat.idle();
+
+        match self
+            .heartbeat
+            .elapsed()
+            .map(|elapsed| elapsed.saturating_sub(idle))
+        {
+            Some(elapsed) if elapsed > self.deadline * FAULTY_FACTOR => ModuleState::Faulty,
+            Some(elapsed) if elapsed > self.deadline => ModuleState::Degraded,
+            _ => ModuleState::Healthy,
+        }
+    }
+}
+
+/// Service watchdog.
+///
+/// Watches the heartbeats of the services. A service that does not touch its
+/// heartbeat within the deadline is degraded, a service that misses the
+/// deadline by far is faulty. Clones share the same watched services.
+#[derive(Clone, Default)]
+pub struct Watchdog {
+    entries: Arc<Mutex<Vec<WatchEntry>>>,
+}
+
+impl Watchdog {
+    /// Watch the heartbeat of a service.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The service name.
+    /// * `heartbeat` - The service heartbeat.
+    /// * `deadline` - The maximum time between two touches.
+    pub fn watch(&self, name: impl ToString, heartbeat: Heartbeat, deadline: Duration) {
+        self.entries.lock().unwrap().push(WatchEntry {
+            name: name.to_string(),
+            heartbeat,
+            deadline,
+            state: ModuleState::Healthy,
+        });
+    }
+
+    /// Number of watched services.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Test if no services are watched.
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().is_empty()
+    }
+
+    /// Check the heartbeats of the watched services.
+    ///
+    /// # Returns
+    ///
+    /// The status of every stalled service, and the healthy status of the
+    /// services that recovered since the last check.
+    pub fn check(&self) -> Vec<ModuleStatus> {
+        let mut statuses = Vec::new();
+
+        for entry in self.entries.lock().unwrap().iter_mut() {
+            let state = entry.state();
+            let recovered = state == ModuleState::Healthy && entry.state != ModuleState::Healthy;
+            entry.state = state;
+
+            let name = format!("runtime:watchdog:{}", entry.name);
+
+            match state {
+                ModuleState::Faulty => {
+                    statuses.push(ModuleStatus::faulty(name, ModuleError::ServiceStalled))
+                }
+                ModuleState::Degraded => statuses.push(ModuleStatus::degraded(
+                    name,
+                    Some(ModuleError::ServiceStalled),
+                )),
+                ModuleState::Healthy if recovered => statuses.push(ModuleStatus::healthy(name)),
+                _ => {}
+            }
+        }
+
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_states() {
+        let clock = Clock::mock();
+        let heartbeat = Heartbeat::new(clock.clone());
+
+        let watchdog = Watchdog::default();
+        watchdog.watch("director", heartbeat.clone(), Duration::from_millis(100));
+        assert_eq!(watchdog.len(), 1);
+
+        // Not armed until the first touch.
+        clock.advance(Duration::from_secs(1));
+        assert!(watchdog.check().is_empty());
+
+        heartbeat.touch();
+        clock.advance(Duration::from_millis(100));
+        assert!(watchdog.check().is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        let statuses = watchdog.check();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "runtime:watchdog:director");
+        assert_eq!(statuses[0].state, ModuleState::Degraded);
+        assert_eq!(statuses[0].error, Some(ModuleError::ServiceStalled));
+
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(watchdog.check()[0].state, ModuleState::Faulty);
+
+        heartbeat.touch();
+        assert_eq!(watchdog.check()[0].state, ModuleState::Healthy);
+        assert!(watchdog.check().is_empty());
+
+        // The idle time is allowed on top of the deadline.
+        heartbeat.set_idle(Duration::from_secs(1));
+        clock.advance(Duration::from_millis(1_100));
+        assert!(watchdog.check().is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(watchdog.check()[0].state, ModuleState::Degraded);
+    }
+}
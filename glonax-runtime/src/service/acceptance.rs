@@ -0,0 +1,571 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    core::{Actuator, Control, Motion, Object, Rotator},
+    runtime::{Clock, CommandSender, Service, ServiceContext, SignalReceiver},
+};
+
+use super::derivative::principal_angle;
+
+/// Actuators exercised by the acceptance test and their encoder source.
+///
+/// The actuators are visited in this order.
+const ACCEPTANCE_ACTUATORS: [(Actuator, u8); 4] = [
+    (Actuator::Boom, 0x6B),
+    (Actuator::Arm, 0x6C),
+    (Actuator::Attachment, 0x6D),
+    (Actuator::Slew, 0x6A),
+];
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct AcceptanceConfig {
+    /// Enable the acceptance test.
+    ///
+    /// The acceptance test moves the machine without operator input. When
+    /// enabled, the test is started by the acceptance test control command.
+    #[serde(default)]
+    pub enable: bool,
+    /// Actuator power during the test.
+    #[serde(default = "AcceptanceConfig::default_power")]
+    pub power: i16,
+    /// Maximum duration of each movement in milliseconds.
+    #[serde(default = "AcceptanceConfig::default_duration")]
+    pub duration: u64,
+    /// Encoder travel in radians at which a movement is stopped.
+    #[serde(default = "AcceptanceConfig::default_range")]
+    pub range: f32,
+    /// Minimum encoder response in radians.
+    #[serde(default = "AcceptanceConfig::default_threshold")]
+    pub threshold: f32,
+    /// Encoder timeout in milliseconds.
+    #[serde(default = "AcceptanceConfig::default_timeout")]
+    pub timeout: u64,
+}
+
+impl AcceptanceConfig {
+    fn default_power() -> i16 {
+        8_000
+    }
+
+    fn default_duration() -> u64 {
+        1_500
+    }
+
+    fn default_range() -> f32 {
+        0.2
+    }
+
+    fn default_threshold() -> f32 {
+        0.02
+    }
+
+    fn default_timeout() -> u64 {
+        250
+    }
+}
+
+impl Default for AcceptanceConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            power: Self::default_power(),
+            duration: Self::default_duration(),
+            range: Self::default_range(),
+            threshold: Self::default_threshold(),
+            timeout: Self::default_timeout(),
+        }
+    }
+}
+
+/// Acceptance test result of an actuator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AcceptanceResult {
+    /// Actuator under test.
+    pub actuator: Actuator,
+    /// Encoder response per movement, `None` if the encoder did not report.
+    pub response: [Option<f32>; 2],
+}
+
+impl AcceptanceResult {
+    /// Test if the encoder reported during both movements.
+    pub fn is_reporting(&self) -> bool {
+        self.response.iter().all(Option::is_some)
+    }
+
+    /// Test if the actuator passed.
+    ///
+    /// The actuator passes if the encoder responded to both movements.
+    pub fn is_pass(&self, threshold: f32) -> bool {
+        self.response
+            .iter()
+            .all(|response| response.is_some_and(|delta| delta.abs() >= threshold))
+    }
+}
+
+struct AcceptanceStep {
+    /// Index of the actuator under test.
+    index: usize,
+    /// Direction of the movement, 0 for extend and 1 for retract.
+    direction: usize,
+    /// Start of the movement.
+    start: Instant,
+    /// Encoder angle at the start of the movement.
+    start_angle: Option<f32>,
+}
+
+/// Deterministic acceptance program.
+///
+/// Each actuator is extended and retracted at a fixed power, one actuator at
+/// a time, while the encoder response is measured. A movement is stopped once
+/// the encoder travelled the configured range or the duration elapsed. The
+/// program aborts with a stop of all motion as soon as an encoder stops
+/// reporting or does not respond to a movement. The program is driven by the
+/// caller, which makes it independent of the runtime.
+pub struct AcceptanceProgram {
+    config: AcceptanceConfig,
+    /// Last encoder angle and moment of report per source.
+    angle: HashMap<u8, (f32, Instant)>,
+    /// Current movement.
+    step: Option<AcceptanceStep>,
+    /// Results of the finished actuators.
+    results: Vec<AcceptanceResult>,
+    /// Whether the program was aborted.
+    aborted: bool,
+}
+
+impl AcceptanceProgram {
+    /// Construct a new acceptance program.
+    pub fn new(config: AcceptanceConfig) -> Self {
+        Self {
+            config,
+            angle: HashMap::new(),
+            step: None,
+            results: Vec::with_capacity(ACCEPTANCE_ACTUATORS.len()),
+            aborted: false,
+        }
+    }
+
+    /// Record an encoder rotator.
+    ///
+    /// # Arguments
+    ///
+    /// * `rotator` - The encoder rotator.
+    /// * `now` - The moment the rotator was received.
+    pub fn on_rotator(&mut self, rotator: &Rotator, now: Instant) {
+        self.angle
+            .insert(rotator.source, (principal_angle(&rotator.rotator), now));
+    }
+
+    /// Test if the program finished.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.aborted || (self.results.len() == ACCEPTANCE_ACTUATORS.len() && self.step.is_none())
+    }
+
+    /// Test if the program was aborted.
+    #[inline]
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Results of the finished actuators.
+    #[inline]
+    pub fn results(&self) -> &[AcceptanceResult] {
+        &self.results
+    }
+
+    fn begin(&mut self, index: usize, direction: usize, now: Instant) -> Motion {
+        let (actuator, source) = ACCEPTANCE_ACTUATORS[index];
+
+        self.step = Some(AcceptanceStep {
+            index,
+            direction,
+            start: now,
+            start_angle: self.angle.get(&source).map(|(angle, _)| *angle),
+        });
+
+        let power = if direction == 0 {
+            self.config.power
+        } else {
+            -self.config.power
+        };
+
+        Motion::new(actuator, power)
+    }
+
+    /// Abort the program.
+    ///
+    /// Every motion is stopped.
+    pub fn abort(&mut self) -> Vec<Motion> {
+        self.step = None;
+        self.aborted = true;
+
+        vec![Motion::StopAll]
+    }
+
+    /// Advance the program.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current moment in time.
+    ///
+    /// # Returns
+    ///
+    /// The motion commands to send, empty if nothing changed.
+    pub fn tick(&mut self, now: Instant) -> Vec<Motion> {
+        if self.is_done() {
+            return vec![];
+        }
+
+        let Some(step) = &mut self.step else {
+            return vec![self.begin(0, 0, now)];
+        };
+
+        let (actuator, source) = ACCEPTANCE_ACTUATORS[step.index];
+        let (index, direction) = (step.index, step.direction);
+
+        let report = self.angle.get(&source).copied();
+
+        // The encoder may not have reported before the movement started.
+        if step.start_angle.is_none() {
+            step.start_angle = report.map(|(angle, _)| angle);
+        }
+
+        let response = step
+            .start_angle
+            .zip(report)
+            .map(|(start, (end, _))| end - start);
+
+        let last_report = report.map_or(step.start, |(_, at)| at.max(step.start));
+        let timeout =
+            now.saturating_duration_since(last_report) > Duration::from_millis(self.config.timeout);
+        let in_range = response.is_some_and(|delta| delta.abs() >= self.config.range);
+        let elapsed = now.saturating_duration_since(step.start);
+
+        if !timeout && !in_range && elapsed < Duration::from_millis(self.config.duration) {
+            return vec![];
+        }
+
+        if direction == 0 {
+            self.results.push(AcceptanceResult {
+                actuator,
+                response: [response, None],
+            });
+        } else {
+            self.results.last_mut().unwrap().response[1] = response;
+        }
+
+        if timeout {
+            error!("Acceptance {:?}: encoder not reporting, abort", actuator);
+            return self.abort();
+        } else if !response.is_some_and(|delta| delta.abs() >= self.config.threshold) {
+            error!("Acceptance {:?}: no encoder response, abort", actuator);
+            return self.abort();
+        }
+
+        if direction == 0 {
+            return vec![self.begin(index, 1, now)];
+        }
+
+        let mut motion = vec![Motion::Stop(vec![actuator])];
+        if index + 1 < ACCEPTANCE_ACTUATORS.len() {
+            motion.push(self.begin(index + 1, 0, now));
+        } else {
+            self.step = None;
+        }
+
+        motion
+    }
+}
+
+/// Factory acceptance test.
+///
+/// Runs the acceptance program on the acceptance test control command and
+/// reports pass or fail per actuator. The test never resumes motion, and is
+/// aborted on an emergency stop or a stop of all motion. Unless the test is
+/// enabled the service does nothing.
+pub struct AcceptanceTest {
+    config: AcceptanceConfig,
+    program: AcceptanceProgram,
+    clock: Clock,
+}
+
+impl AcceptanceTest {
+    fn send(command_tx: &CommandSender, motion: Motion) {
+        if let Err(e) = command_tx.send(Object::Motion(motion)) {
+            error!("Failed to send motion command: {}", e);
+        }
+    }
+
+    fn report(&self) {
+        let threshold = self.program.config.threshold;
+
+        for result in self.program.results() {
+            if result.is_pass(threshold) {
+                info!(
+                    "Acceptance {:?}: pass, response {:?}",
+                    result.actuator, result.response
+                );
+            } else if result.is_reporting() {
+                error!(
+                    "Acceptance {:?}: fail, no encoder response {:?}",
+                    result.actuator, result.response
+                );
+            } else {
+                error!(
+                    "Acceptance {:?}: fail, encoder not reporting",
+                    result.actuator
+                );
+            }
+        }
+
+        let passed = self
+            .program
+            .results()
+            .iter()
+            .filter(|result| result.is_pass(threshold))
+            .count();
+
+        if self.program.is_aborted() {
+            error!(
+                "Acceptance test aborted, {} of {} actuators passed",
+                passed,
+                ACCEPTANCE_ACTUATORS.len()
+            );
+        } else {
+            info!(
+                "Acceptance test finished, {} of {} actuators passed",
+                passed,
+                ACCEPTANCE_ACTUATORS.len()
+            );
+        }
+    }
+}
+
+impl Service<AcceptanceConfig> for AcceptanceTest {
+    fn new(config: AcceptanceConfig) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            program: AcceptanceProgram::new(config.clone()),
+            config,
+            clock: Clock::system(),
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("acceptance test")
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    async fn setup(&mut self) {
+        if self.program.config.enable {
+            warn!("Acceptance test is enabled, the machine moves on the acceptance test command");
+        }
+    }
+
+    async fn wait_io_sub(&mut self, command_tx: CommandSender, mut signal_rx: SignalReceiver) {
+        if !self.config.enable {
+            return std::future::pending().await;
+        }
+
+        let mut command_rx = command_tx.subscribe();
+
+        loop {
+            match command_rx.recv().await {
+                Ok(Object::Control(Control::AcceptanceTest)) => break,
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return std::future::pending().await,
+            }
+        }
+
+        info!("Acceptance test started");
+
+        self.program = AcceptanceProgram::new(self.config.clone());
+
+        let mut interval = tokio::time::interval(Duration::from_millis(50));
+
+        while !self.program.is_done() {
+            let motion = tokio::select! {
+                signal = signal_rx.recv() => {
+                    match signal {
+                        Ok(Object::Rotator(rotator)) => {
+                            self.program.on_rotator(&rotator, self.clock.now());
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(count)) => {
+                            warn!("Signal receiver lagged by {} objects", count);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+                command = command_rx.recv() => match command {
+                    Ok(Object::Control(Control::EmergencyStop) | Object::Motion(Motion::StopAll)) => {
+                        warn!("Acceptance test stopped by command, abort");
+                        self.program.abort()
+                    }
+                    _ => continue,
+                },
+                _ = interval.tick() => self.program.tick(self.clock.now()),
+            };
+
+            for motion in motion {
+                Self::send(&command_tx, motion);
+            }
+        }
+
+        Self::send(&command_tx, Motion::StopAll);
+
+        self.report();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::math::EulerAngles;
+    use nalgebra::Rotation3;
+
+    #[test]
+    fn acceptance_program() {
+        let config = AcceptanceConfig {
+            enable: true,
+            ..Default::default()
+        };
+        let mut program = AcceptanceProgram::new(config.clone());
+
+        // Encoder model, the slew encoder does not respond.
+        let mut angle = HashMap::<u8, f32>::new();
+        let mut power = HashMap::<Actuator, i16>::new();
+        let mut visited = Vec::new();
+
+        let mut now = Instant::now();
+
+        while !program.is_done() {
+            for motion in program.tick(now) {
+                match motion {
+                    Motion::Change(changes) => {
+                        for change in changes {
+                            if !visited.contains(&change.actuator) {
+                                visited.push(change.actuator);
+                            }
+                            power.insert(change.actuator, change.value);
+                        }
+                    }
+                    Motion::Stop(actuators) => {
+                        for actuator in actuators {
+                            power.insert(actuator, 0);
+                        }
+                    }
+                    Motion::StopAll => power.values_mut().for_each(|value| *value = 0),
+                    Motion::ResumeAll => panic!("acceptance test resumed motion"),
+                    _ => {}
+                }
+            }
+
+            for (actuator, source) in ACCEPTANCE_ACTUATORS {
+                let angle = angle.entry(source).or_default();
+                if actuator != Actuator::Slew {
+                    *angle += power.get(&actuator).copied().unwrap_or(0) as f32 * 1e-5;
+                }
+
+                program.on_rotator(
+                    &Rotator::relative(source, Rotation3::from_pitch(*angle)),
+                    now,
+                );
+            }
+
+            now += Duration::from_millis(50);
+        }
+
+        assert_eq!(
+            visited,
+            vec![
+                Actuator::Boom,
+                Actuator::Arm,
+                Actuator::Attachment,
+                Actuator::Slew
+            ]
+        );
+        assert!(power.values().all(|value| *value == 0));
+
+        let results = program.results();
+        assert_eq!(results.len(), 4);
+
+        // Each movement is stopped within one tick of the encoder range.
+        for result in &results[..3] {
+            assert!(result.is_pass(config.threshold), "{:?}", result);
+            for delta in result.response.iter().flatten() {
+                assert!(delta.abs() < config.range + 0.08 + 1e-3, "{:?}", result);
+            }
+        }
+
+        // The slew encoder reports, but does not respond.
+        assert!(program.is_aborted());
+        assert_eq!(results[3].actuator, Actuator::Slew);
+        assert!(!results[3].is_pass(config.threshold));
+    }
+
+    #[test]
+    fn acceptance_program_no_encoder() {
+        let mut program = AcceptanceProgram::new(AcceptanceConfig::default());
+        let start = Instant::now();
+        let mut now = start;
+        let mut motion = vec![];
+
+        while !program.is_done() {
+            motion.extend(program.tick(now));
+            now += Duration::from_millis(50);
+        }
+
+        // Aborted on the first actuator, long before the movement ends.
+        assert!(program.is_aborted());
+        assert!(now - start < Duration::from_millis(500));
+        assert_eq!(motion.last(), Some(&Motion::StopAll));
+
+        assert_eq!(program.results().len(), 1);
+        assert!(!program.results()[0].is_reporting());
+    }
+
+    #[test]
+    fn acceptance_program_encoder_lost() {
+        let mut program = AcceptanceProgram::new(AcceptanceConfig::default());
+        let mut now = Instant::now();
+        let mut angle = 0.0;
+
+        assert_eq!(
+            program.tick(now),
+            vec![Motion::new(Actuator::Boom, 8_000_i16)]
+        );
+
+        // The boom encoder reports for a while, then stops reporting.
+        for _ in 0..4 {
+            angle += 0.01;
+            program.on_rotator(&Rotator::relative(0x6B, Rotation3::from_pitch(angle)), now);
+            assert!(program.tick(now).is_empty());
+            now += Duration::from_millis(50);
+        }
+
+        while !program.is_done() {
+            let motion = program.tick(now);
+            if program.is_done() {
+                assert_eq!(motion, vec![Motion::StopAll]);
+            }
+            now += Duration::from_millis(50);
+        }
+
+        assert!(program.is_aborted());
+        assert_eq!(program.results().len(), 1);
+    }
+}
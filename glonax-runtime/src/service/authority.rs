@@ -1,16 +1,22 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use j1939::protocol;
 
 use crate::{
-    core::{ModuleStatus, Object},
-    net::ControlNetwork,
-    runtime::{J1939Unit, J1939UnitError, NetDriverContext, NetworkService, SignalSender},
+    core::{Control, ModuleError, ModuleStatus, Motion, Object},
+    net::{BusErrorStats, ControlNetwork},
+    runtime::{
+        Clock, J1939Unit, J1939UnitError, NetDriverContext, NetworkService, ServiceContext,
+        SignalSender, StopLatch,
+    },
 };
 
+/// Delay after a failed receive.
+const RECV_ERROR_DELAY: Duration = Duration::from_millis(10);
+
 // TODO: Move this to a separate module
 fn interval_decimation(interval: Duration, tick: u64, decimation: u64) -> bool {
-    tick as u128 % (decimation as u128 / interval.as_millis()) == 0
+    (tick as u128).is_multiple_of(decimation as u128 / interval.as_millis())
 }
 
 #[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
@@ -46,7 +52,7 @@ impl From<J1939Name> for j1939::Name {
     }
 }
 
-#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
 pub struct CanDriverConfig {
     /// Driver destination.
     pub da: u8,
@@ -54,13 +60,58 @@ pub struct CanDriverConfig {
     pub sa: Option<u8>,
     /// Timeout in milliseconds.
     pub timeout: Option<u64>,
+    /// Tick interval in milliseconds.
+    ///
+    /// The driver is ticked on every network tick if not set.
+    pub tick: Option<u64>,
     /// Vendor.
     pub vendor: String,
     /// Product.
     pub product: String,
+    /// Digital input wired to the backup stop button.
+    pub stop_input: Option<u8>,
+    /// Driver is optional and may not be present on the network.
+    #[serde(default)]
+    pub optional: bool,
+    /// Slew rate limit of the motion commands.
+    ///
+    /// Only applies to hydraulic drivers.
+    pub motion_ramp: Option<crate::driver::MotionRampConfig>,
+    /// Simulator configuration.
+    ///
+    /// Only applies to simulated drivers.
+    #[serde(default)]
+    pub simulator: crate::driver::SimulatorConfig,
 }
 
-#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
+impl CanDriverConfig {
+    /// Construct the driver from the configuration.
+    ///
+    /// Returns `None` if the vendor and product are unknown, or an error if
+    /// the driver configuration is invalid.
+    fn build(&self, interface: &str, sa: u8) -> std::io::Result<Option<Box<dyn J1939Unit>>> {
+        match (self.vendor.as_str(), self.product.as_str()) {
+            ("laixer", "hcu") => {
+                let mut hcu = crate::driver::HydraulicControlUnit::new(interface, self.da, sa);
+                if let Some(motion_ramp) = self.motion_ramp {
+                    hcu = hcu.with_ramp(motion_ramp);
+                }
+
+                Ok(Some(Box::new(hcu)))
+            }
+            ("laixer", "simulator") => {
+                let simulator = self.simulator.build(interface, self.da, sa)?;
+
+                Ok(Some(Box::new(simulator)))
+            }
+            (vendor, product) => Ok(crate::driver::net::driver_factory(
+                vendor, product, interface, self.da, sa,
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
 pub struct NetworkConfig {
     /// CAN network interface.
     pub interface: String,
@@ -74,21 +125,85 @@ pub struct NetworkConfig {
 
 struct NetDriverItem {
     driver: Box<dyn J1939Unit>,
+    config: Option<CanDriverConfig>,
     context: NetDriverContext,
     rx_timeout: Option<Duration>,
+    tick_interval: Option<Duration>,
+    tick_last: Option<Instant>,
+    stop_input: Option<u8>,
     last_status: Option<ModuleStatus>,
 }
 
 impl NetDriverItem {
-    fn new(driver: Box<dyn J1939Unit>, rx_timeout: Option<Duration>) -> Self {
+    fn new(
+        driver: Box<dyn J1939Unit>,
+        rx_timeout: Option<Duration>,
+        stop_input: Option<u8>,
+    ) -> Self {
         Self {
             driver,
+            config: None,
             context: NetDriverContext::default(),
             rx_timeout,
+            tick_interval: None,
+            tick_last: None,
+            stop_input,
             last_status: None,
         }
     }
 
+    fn with_tick_interval(mut self, tick_interval: Option<Duration>) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
+    fn with_config(mut self, config: CanDriverConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Construct the driver again from its configuration.
+    fn rebuild(&self, interface: &str) -> Option<Box<dyn J1939Unit>> {
+        let source = self.driver.source();
+
+        match &self.config {
+            Some(config) => config.build(interface, source).ok().flatten(),
+            None => crate::driver::net::driver_factory(
+                self.driver.vendor(),
+                self.driver.product(),
+                interface,
+                self.driver.destination(),
+                source,
+            ),
+        }
+    }
+
+    /// Test if the driver is due for a tick.
+    ///
+    /// A driver without a tick interval is always due. Otherwise the driver is
+    /// due when the interval passed since the last tick.
+    fn is_tick_due(&mut self, now: Instant) -> bool {
+        let Some(tick_interval) = self.tick_interval else {
+            return true;
+        };
+
+        let is_due = self
+            .tick_last
+            .is_none_or(|tick_last| now.saturating_duration_since(tick_last) >= tick_interval);
+
+        if is_due {
+            self.tick_last = Some(now);
+        }
+
+        is_due
+    }
+
+    /// Test if the frame asserts the stop input of this driver.
+    fn is_stop_asserted(&self, frame: &j1939::Frame) -> bool {
+        self.stop_input
+            .is_some_and(|input| self.driver.is_input_asserted(frame, input))
+    }
+
     pub fn is_rx_timeout(&self) -> bool {
         self.rx_timeout
             .map(|timeout| self.context.is_rx_timeout(timeout))
@@ -107,6 +222,15 @@ impl NetDriverItem {
         self.driver.try_recv(&mut self.context, frame, rx_queue)
     }
 
+    fn try_recv_message(
+        &mut self,
+        message: &crate::net::LongMessage,
+        rx_queue: &mut Vec<Object>,
+    ) -> Result<(), J1939UnitError> {
+        self.driver
+            .try_recv_message(&mut self.context, message, rx_queue)
+    }
+
     fn tick(&mut self, tx_queue: &mut Vec<j1939::Frame>) -> Result<(), J1939UnitError> {
         self.driver.tick(&mut self.context, tx_queue)
     }
@@ -119,6 +243,10 @@ impl NetDriverItem {
         self.driver.trigger(&mut self.context, tx_queue, object)
     }
 
+    fn stop_motion(&mut self, tx_queue: &mut Vec<j1939::Frame>) -> Result<(), J1939UnitError> {
+        self.driver.stop_motion(&mut self.context, tx_queue)
+    }
+
     fn teardown(&mut self, tx_queue: &mut Vec<j1939::Frame>) -> Result<(), J1939UnitError> {
         self.driver.teardown(&mut self.context, tx_queue)
     }
@@ -134,6 +262,9 @@ pub struct NetworkAuthority {
     network: ControlNetwork,
     default_address: u8,
     drivers: Vec<NetDriverItem>,
+    stop: StopLatch,
+    clock: Clock,
+    bus_errors: BusErrorStats,
     tick: u64,
     is_setup: bool,
 }
@@ -162,22 +293,27 @@ impl NetworkAuthority {
 
 impl Clone for NetworkAuthority {
     fn clone(&self) -> Self {
-        let network = ControlNetwork::bind(self.network.interface(), self.network.name()).unwrap();
+        // The clones share the socket, so that the bus errors seen by one
+        // clone are recovered by the other.
+        let network = self.network.clone();
 
+        // The drivers share their runtime state, so that a setting changed by
+        // a command is applied on the next tick.
         let mut drivers = Vec::new();
         for driver in &self.drivers {
-            let net_driver = crate::driver::net::driver_factory(
-                driver.driver.vendor(),
-                driver.driver.product(),
-                network.interface(),
-                driver.driver.destination(),
-                driver.driver.source(),
-            );
+            let net_driver = driver
+                .driver
+                .share()
+                .or_else(|| driver.rebuild(network.interface()));
 
             drivers.push(NetDriverItem {
                 driver: net_driver.unwrap(),
+                config: driver.config.clone(),
                 context: driver.context.clone(),
                 rx_timeout: driver.rx_timeout,
+                tick_interval: driver.tick_interval,
+                tick_last: driver.tick_last,
+                stop_input: driver.stop_input,
                 last_status: driver.last_status.clone(),
             });
         }
@@ -186,6 +322,9 @@ impl Clone for NetworkAuthority {
             network,
             default_address: self.default_address,
             drivers,
+            stop: self.stop.clone(),
+            clock: self.clock.clone(),
+            bus_errors: BusErrorStats::default(),
             tick: 0,
             is_setup: self.is_setup,
         }
@@ -193,29 +332,46 @@ impl Clone for NetworkAuthority {
 }
 
 impl NetworkService<NetworkConfig> for NetworkAuthority {
-    // TODO: Check if the network interface exists
-    // TODO: Return an error if the network interface does not exist
-    fn new(config: NetworkConfig) -> Self
+    fn new(config: NetworkConfig) -> crate::runtime::Result<Self>
     where
         Self: Sized,
     {
-        let network = ControlNetwork::bind(&config.interface, &config.name.into()).unwrap();
+        let network =
+            ControlNetwork::bind(&config.interface, &config.name.into()).map_err(|e| {
+                error!("[{}] Failed to bind network: {}", config.interface, e);
+
+                let ctx = ServiceContext::with_address("network authority", &config.interface);
+                crate::runtime::Error::ServiceSetup(ctx.to_string())
+            })?;
 
         let mut drivers = Vec::new();
         for driver in config.driver.iter() {
-            let net_driver = crate::driver::net::driver_factory(
-                &driver.vendor,
-                &driver.product,
-                network.interface(),
-                driver.da,
-                driver.sa.unwrap_or(config.address),
-            );
+            let net_driver = driver
+                .build(network.interface(), driver.sa.unwrap_or(config.address))
+                .map_err(|e| {
+                    error!(
+                        "[{}] Invalid driver configuration: {} {}: {}",
+                        network.interface(),
+                        driver.vendor,
+                        driver.product,
+                        e
+                    );
+
+                    let ctx =
+                        ServiceContext::with_address("network authority", network.interface());
+                    crate::runtime::Error::ServiceSetup(ctx.to_string())
+                })?;
 
             if let Some(net_driver) = net_driver {
-                drivers.push(NetDriverItem::new(
-                    net_driver,
-                    driver.timeout.map(Duration::from_millis),
-                ));
+                drivers.push(
+                    NetDriverItem::new(
+                        net_driver,
+                        driver.timeout.map(Duration::from_millis),
+                        driver.stop_input,
+                    )
+                    .with_tick_interval(driver.tick.map(Duration::from_millis))
+                    .with_config(driver.clone()),
+                );
             } else {
                 error!(
                     "[{}] Unknown driver: {} {}",
@@ -226,13 +382,28 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
             }
         }
 
-        Self {
+        Ok(Self {
             network,
             default_address: config.address,
             drivers,
+            stop: StopLatch::default(),
+            clock: Clock::system(),
+            bus_errors: BusErrorStats::default(),
             tick: 0,
             is_setup: false,
+        })
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::with_address("network authority", self.network.interface())
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        for driver in self.drivers.iter() {
+            driver.context.set_clock(clock.clone());
         }
+
+        self.clock = clock;
     }
 
     async fn setup(&mut self) {
@@ -254,9 +425,28 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
                 self.network.interface(),
                 e
             );
+
+            self.network.record_error(&e);
+
+            // Do not spin on a bus that is down, recovery happens on tick.
+            tokio::time::sleep(RECV_ERROR_DELAY).await;
+            return;
         }
 
         let frame = self.network.frame().unwrap();
+
+        // The stop input is checked before anything else, so a wedged signal
+        // channel or a slow driver cannot delay the stop.
+        for driver in self.drivers.iter() {
+            if driver.is_stop_asserted(frame) && self.stop.assert(driver.driver.name()) {
+                warn!(
+                    "[{}] {}: Stop input asserted",
+                    self.network.interface(),
+                    driver
+                );
+            }
+        }
+
         if frame.id().pgn() == j1939::PGN::Request {
             if frame.id().destination_address() != Some(self.default_address) {
                 return;
@@ -324,11 +514,18 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
             return;
         }
 
+        let message = self.network.message();
+
         for driver in self.drivers.iter_mut() {
             let mut rx_queue = Vec::new();
 
+            let result = match message {
+                Some(message) => driver.try_recv_message(message, &mut rx_queue),
+                None => driver.try_recv(frame, &mut rx_queue),
+            };
+
             // TODO: try_recv needs to return a result with state instructions (healthy, faulty, unknown)
-            if let Err(e) = driver.try_recv(frame, &mut rx_queue) {
+            if let Err(e) = result {
                 error!("[{}] {}: {}", self.network.interface(), driver, e);
                 // TODO: Set the unit error as driver status
             }
@@ -357,41 +554,86 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
             self.is_setup = true;
         }
 
+        while self.network.poll_errors() {}
+
+        let now = self.clock.now();
+
+        // The socket is bound again after a bus-off, with backoff between the
+        // attempts. The interface itself is restarted by the kernel if the
+        // controller is configured with a restart delay.
+        if let Some(result) = self.network.recover(now) {
+            warn!(
+                "[{}] Bus-off recovery attempt {}",
+                self.network.interface(),
+                self.network.recovery().attempts()
+            );
+
+            if let Err(e) = result {
+                error!(
+                    "[{}] Bus-off recovery failed: {}",
+                    self.network.interface(),
+                    e
+                );
+
+                let status = ModuleStatus::faulty(
+                    format!("j1939:bus:{}", self.network.interface()),
+                    ModuleError::GenericCommunicationError,
+                );
+
+                if let Err(e) = signal_tx.send(Object::ModuleStatus(status)) {
+                    error!(
+                        "[{}] Failed to send signal: {}",
+                        self.network.interface(),
+                        e
+                    );
+                }
+            }
+        }
+
         for driver in self.drivers.iter_mut() {
             let mut tx_queue = Vec::new();
 
-            let mut module_status: Option<ModuleStatus> = if driver.context.rx_count() > 0 {
+            // A driver that is not due for a tick keeps its last status.
+            let mut module_status: Option<ModuleStatus> = if !driver.is_tick_due(now) {
+                driver.last_status.clone()
+            } else if let Err(e) = driver.tick(&mut tx_queue) {
+                if e.is_degraded() {
+                    Some(ModuleStatus::degraded(driver.driver.name(), Some(e.into())))
+                } else {
+                    Some(ModuleStatus::faulty(driver.driver.name(), e.into()))
+                }
+            } else if driver.context.rx_count() > 0 {
                 Some(ModuleStatus::healthy(driver.driver.name()))
             } else {
                 None
             };
 
-            if let Err(e) = driver.tick(&mut tx_queue) {
-                module_status = Some(ModuleStatus::faulty(driver.driver.name(), e.into()));
-            }
-
             if driver.is_rx_timeout() {
                 let e = J1939UnitError::MessageTimeout;
                 module_status = Some(ModuleStatus::faulty(driver.driver.name(), e.into()));
             }
 
+            if self.stop.source() == Some(driver.driver.name()) {
+                module_status = Some(ModuleStatus::emergency(driver.driver.name()));
+            }
+
             let is_status_changed = if let Some(module_status) = &module_status {
                 let is_changed = driver.last_status.as_ref() != Some(module_status);
 
                 if is_changed {
-                    if driver.last_status.is_some() {
+                    if let Some(last_status) = &driver.last_status {
                         if module_status.is_healthy() {
                             info!(
                                 "[{}] Status change: {} => {}",
                                 self.network.interface(),
-                                driver.last_status.as_ref().unwrap(),
+                                last_status,
                                 module_status
                             );
                         } else {
                             error!(
                                 "[{}] Status change: {} => {}",
                                 self.network.interface(),
-                                driver.last_status.as_ref().unwrap(),
+                                last_status,
                                 module_status
                             );
                         }
@@ -417,7 +659,11 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
                 if interval_decimation(Duration::from_millis(10), self.tick, 100)
                     || is_status_changed
                 {
-                    if let Err(e) = signal_tx.send(Object::ModuleStatus(last_status.clone())) {
+                    let status = last_status
+                        .clone()
+                        .with_update_rate(driver.context.update_rate());
+
+                    if let Err(e) = signal_tx.send(Object::ModuleStatus(status)) {
                         error!(
                             "[{}] {}: Failed to send signal: {}",
                             self.network.interface(),
@@ -430,13 +676,65 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
 
             if let Err(e) = self.network.send_vectored(&tx_queue).await {
                 error!("[{}] {}: {}", self.network.interface(), driver, e);
+                self.network.record_error(&e);
             };
         }
 
+        if interval_decimation(Duration::from_millis(10), self.tick, 1_000) {
+            let bus_errors = self.network.errors();
+            let status = bus_errors.status(
+                format!("j1939:bus:{}", self.network.interface()),
+                &self.bus_errors,
+            );
+
+            if !status.is_healthy() {
+                warn!("[{}] Bus errors: {}", self.network.interface(), bus_errors);
+            }
+
+            debug!(
+                "[{}] Traffic: {}",
+                self.network.interface(),
+                self.network.traffic()
+            );
+
+            if let Err(e) = signal_tx.send(Object::ModuleStatus(status)) {
+                error!(
+                    "[{}] Failed to send signal: {}",
+                    self.network.interface(),
+                    e
+                );
+            }
+
+            self.bus_errors = bus_errors;
+        }
+
         self.tick = self.tick.wrapping_add(1);
     }
 
     async fn on_command(&mut self, object: &Object) {
+        // The stop is asserted again on the next frame if the stop input is
+        // still asserted.
+        if let Object::Control(Control::ResetEmergency) = object {
+            if self.stop.is_latched() {
+                info!(
+                    "[{}] Stop input latch reset by operator",
+                    self.network.interface()
+                );
+                self.stop.release();
+            }
+        }
+
+        if self.stop.is_latched() && !matches!(object, Object::Motion(Motion::StopAll)) {
+            if let Object::Motion(motion) = object {
+                warn!(
+                    "[{}] Motion is locked by stop input, discarding: {}",
+                    self.network.interface(),
+                    motion
+                );
+                return;
+            }
+        }
+
         for driver in self.drivers.iter_mut() {
             let mut tx_queue = Vec::new();
 
@@ -450,6 +748,28 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
         }
     }
 
+    async fn on_stop(&mut self) {
+        self.stop.wait().await;
+
+        warn!(
+            "[{}] Motion locked by stop input of {}",
+            self.network.interface(),
+            self.stop.source().unwrap_or_default()
+        );
+
+        for driver in self.drivers.iter_mut() {
+            let mut tx_queue = Vec::new();
+
+            if let Err(e) = driver.stop_motion(&mut tx_queue) {
+                error!("[{}] {}: {}", self.network.interface(), driver, e);
+            }
+
+            if let Err(e) = self.network.send_vectored(&tx_queue).await {
+                error!("[{}] {}: {}", self.network.interface(), driver, e);
+            };
+        }
+    }
+
     async fn teardown(&mut self) {
         for driver in self.drivers.iter_mut() {
             let mut tx_queue = Vec::new();
@@ -470,3 +790,281 @@ impl NetworkService<NetworkConfig> for NetworkAuthority {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use j1939::{FrameBuilder, IdBuilder, PGN};
+
+    use super::*;
+    use crate::{
+        core::{Actuator, SmoothingProfile},
+        driver::{
+            net::{
+                hydraulic::{ActuatorMessage, MotionRampConfig},
+                vcu::VehicleControlUnit,
+            },
+            HydraulicControlUnit,
+        },
+        net::CANSocket,
+    };
+
+    /// Maximum number of ticks between stop input and stop frame.
+    const STOP_TICK_BUDGET: u64 = 2;
+
+    fn vcu_status(inputs: u8) -> j1939::Frame {
+        FrameBuilder::new(
+            IdBuilder::from_pgn(PGN::ProprietaryB(65_288))
+                .sa(0x12)
+                .build(),
+        )
+        .copy_from_slice(&[0x14, 0xff, 0x0, inputs, 0x0, 0x0, 0x0, 0x0])
+        .build()
+    }
+
+    /// Network authority on an in-process network.
+    ///
+    /// The frames sent by the authority are received on the peer socket.
+    fn authority(drivers: Vec<NetDriverItem>) -> (NetworkAuthority, CANSocket) {
+        let name = j1939::NameBuilder::default().build();
+        let (network, peer) = ControlNetwork::pair("vcan0", &name).unwrap();
+
+        let authority = NetworkAuthority {
+            network,
+            default_address: 0x27,
+            drivers,
+            stop: StopLatch::default(),
+            clock: Clock::system(),
+            bus_errors: BusErrorStats::default(),
+            tick: 0,
+            is_setup: true,
+        };
+
+        (authority, peer)
+    }
+
+    /// Power of the boom in an actuator frame.
+    fn boom(frame: &j1939::Frame) -> i16 {
+        ActuatorMessage::from_frame(0x4A, 0x27, frame).actuators[0].unwrap()
+    }
+
+    #[tokio::test]
+    async fn motion_profile_shared() {
+        let hcu = NetDriverItem::new(
+            Box::new(HydraulicControlUnit::new("vcan0", 0x4A, 0x27)),
+            None,
+            None,
+        );
+
+        let (mut authority, peer) = authority(vec![hcu]);
+
+        let clock = Clock::mock();
+        authority.set_clock(clock.clone());
+
+        let mut command = authority.clone();
+        let mut tick = authority.clone();
+
+        let (signal_tx, _signal_rx) = tokio::sync::broadcast::channel(16);
+
+        let control = Object::Control(Control::MotionProfile(SmoothingProfile::Gentle));
+        command.on_command(&control).await;
+
+        let motion = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        command.on_command(&motion).await;
+        assert_eq!(boom(&peer.recv().await.unwrap()), 0);
+
+        // The tick continues on the profile selected by the command.
+        clock.advance(Duration::from_millis(10));
+        tick.on_tick(signal_tx.clone()).await;
+        assert_eq!(boom(&peer.recv().await.unwrap()), 2_500);
+
+        clock.advance(Duration::from_millis(10));
+        tick.on_tick(signal_tx).await;
+        assert_eq!(boom(&peer.recv().await.unwrap()), 5_000);
+    }
+
+    #[tokio::test]
+    async fn motion_ramp_shared() {
+        let hcu = HydraulicControlUnit::new("vcan0", 0x4A, 0x27).with_ramp(MotionRampConfig {
+            rise: 100_000.0,
+            fall: 50_000.0,
+            deadband: 0,
+        });
+
+        let (mut authority, peer) = authority(vec![NetDriverItem::new(Box::new(hcu), None, None)]);
+
+        let clock = Clock::mock();
+        authority.set_clock(clock.clone());
+
+        let mut command = authority.clone();
+        let mut tick = authority.clone();
+
+        let (signal_tx, _signal_rx) = tokio::sync::broadcast::channel(16);
+
+        let motion = Object::Motion(Motion::new(Actuator::Boom, 20_000_i16));
+        command.on_command(&motion).await;
+
+        let mut ramp = vec![boom(&peer.recv().await.unwrap())];
+        for step in 1..=25 {
+            clock.advance(Duration::from_millis(10));
+
+            // The client repeats the command while the service ticks.
+            if step % 10 == 0 {
+                command.on_command(&motion).await;
+                ramp.push(boom(&peer.recv().await.unwrap()));
+            }
+
+            tick.on_tick(signal_tx.clone()).await;
+            ramp.push(boom(&peer.recv().await.unwrap()));
+        }
+
+        // At most 1000 per 10 milliseconds on the bus, whichever task sends.
+        assert!(ramp
+            .windows(2)
+            .all(|step| step[1] >= step[0] && step[1] - step[0] <= 1_000));
+        assert_eq!(*ramp.last().unwrap(), 20_000);
+    }
+
+    #[tokio::test]
+    async fn stop_input_reaction() {
+        let vcu = NetDriverItem::new(
+            Box::new(VehicleControlUnit::new("vcan0", 0x12, 0x27)),
+            None,
+            Some(2),
+        );
+        let hcu = NetDriverItem::new(
+            Box::new(HydraulicControlUnit::new("vcan0", 0x4A, 0x27)),
+            None,
+            None,
+        );
+
+        let vcu_name = vcu.driver.name();
+        let lock_frame = HydraulicControlUnit::new("vcan0", 0x4A, 0x27).lock();
+
+        let (authority, peer) = authority(vec![vcu, hcu]);
+        let mut recv = authority.clone();
+        let mut stop = authority.clone();
+        let mut tick = authority.clone();
+
+        let (signal_tx, mut signal_rx) = tokio::sync::broadcast::channel(64);
+
+        // The receive and stop tasks as scheduled by the runtime.
+        let recv_task = tokio::spawn({
+            let signal_tx = signal_tx.clone();
+            async move {
+                loop {
+                    recv.recv(signal_tx.clone()).await;
+                }
+            }
+        });
+        let stop_task = tokio::spawn(async move {
+            loop {
+                stop.on_stop().await;
+            }
+        });
+
+        const ASSERT_TICK: u64 = 5;
+
+        for tick in 0.. {
+            let inputs = if tick >= ASSERT_TICK { 0b100 } else { 0b001 };
+            peer.send(&vcu_status(inputs)).await.unwrap();
+
+            let frame =
+                tokio::time::timeout(crate::consts::SERVICE_PIPELINE_INTERVAL, peer.recv()).await;
+
+            if let Ok(frame) = frame {
+                let frame = frame.unwrap();
+                assert_eq!(frame.id(), lock_frame.id());
+                assert_eq!(frame.pdu(), lock_frame.pdu());
+                assert!(tick >= ASSERT_TICK && tick - ASSERT_TICK <= STOP_TICK_BUDGET);
+                break;
+            }
+
+            assert!(tick < ASSERT_TICK + STOP_TICK_BUDGET);
+        }
+
+        // Releasing the button must not release the motion lock.
+        peer.send(&vcu_status(0b000)).await.unwrap();
+        tokio::time::sleep(crate::consts::SERVICE_PIPELINE_INTERVAL).await;
+
+        assert!(authority.stop.is_latched());
+        assert_eq!(authority.stop.source().unwrap(), vcu_name);
+
+        // The stop is reflected in the module status and the tick keeps the
+        // motion locked.
+        tick.on_tick(signal_tx).await;
+
+        let frame = peer.recv().await.unwrap();
+        assert_eq!(frame.pdu(), lock_frame.pdu());
+
+        let mut is_emergency = false;
+        while let Ok(object) = signal_rx.try_recv() {
+            if let Object::ModuleStatus(status) = object {
+                if status.name == vcu_name {
+                    is_emergency = status.state == crate::core::ModuleState::Emergency;
+                }
+            }
+        }
+        assert!(is_emergency);
+
+        recv_task.abort();
+        stop_task.abort();
+    }
+
+    #[test]
+    fn driver_tick_interval() {
+        let clock = Clock::mock();
+
+        let mut drivers = [None, Some(50), Some(200)].map(|tick| {
+            NetDriverItem::new(
+                Box::new(HydraulicControlUnit::new("vcan0", 0x4A, 0x27)),
+                None,
+                None,
+            )
+            .with_tick_interval(tick.map(Duration::from_millis))
+        });
+
+        let mut ticks = [0; 3];
+
+        for _ in 0..100 {
+            for (driver, count) in drivers.iter_mut().zip(ticks.iter_mut()) {
+                if driver.is_tick_due(clock.now()) {
+                    *count += 1;
+                }
+            }
+
+            clock.advance(Duration::from_millis(10));
+        }
+
+        assert_eq!(ticks, [100, 20, 5]);
+    }
+
+    #[tokio::test]
+    async fn network_not_ready() {
+        let mut runtime = crate::Runtime::default();
+
+        runtime.schedule_net_service::<NetworkAuthority, _>(
+            NetworkConfig {
+                interface: "glonax-missing".to_string(),
+                address: 0x27,
+                name: J1939Name {
+                    manufacturer_code: 0x717,
+                    function_instance: 0,
+                    ecu_instance: 0,
+                    function: 0x1C,
+                    vehicle_system: 2,
+                    vehicle_system_instance: 0,
+                    industry_group: 3,
+                },
+                driver: vec![],
+            },
+            Duration::from_millis(10),
+        );
+
+        match runtime.wait_for_ready().await {
+            Err(crate::Error::ServiceSetup(name)) => {
+                assert_eq!(name, "network authority on glonax-missing")
+            }
+            _ => panic!("runtime must not be ready"),
+        }
+    }
+}
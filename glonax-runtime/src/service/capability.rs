@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use crate::{
+    core::{Capability, MachineType, Object},
+    global,
+    runtime::{Service, ServiceContext, SignalReceiver, SignalSender},
+    world::FrameConvention,
+};
+
+use super::NetworkConfig;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapabilityConfig {
+    /// Machine type.
+    pub machine_type: MachineType,
+    /// J1939 network configuration.
+    pub network: Vec<NetworkConfig>,
+}
+
+/// Capability publisher.
+///
+/// Assembles the machine capability descriptor from configuration and driver
+/// presence. Optional drivers are only described once they report a healthy
+/// status. The descriptor is re-published whenever it changes.
+pub struct CapabilityPublisher {
+    /// Capability configuration.
+    config: CapabilityConfig,
+    /// Names of the drivers that are present on the network.
+    present: HashSet<String>,
+    /// Current capability descriptor.
+    capability: Capability,
+}
+
+impl CapabilityPublisher {
+    /// Assemble the capability descriptor.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The capability configuration.
+    /// * `present` - The names of the drivers that are present on the network.
+    ///
+    /// # Returns
+    ///
+    /// The capability descriptor.
+    fn assemble(config: &CapabilityConfig, present: &HashSet<String>) -> Capability {
+        let mut capability = Capability::new(config.machine_type);
+
+        for network in &config.network {
+            for driver_config in &network.driver {
+                let driver = crate::driver::net::driver_factory(
+                    &driver_config.vendor,
+                    &driver_config.product,
+                    &network.interface,
+                    driver_config.da,
+                    driver_config.sa.unwrap_or(network.address),
+                );
+
+                if let Some(driver) = driver {
+                    if !driver_config.optional || present.contains(&driver.name()) {
+                        driver.describe(&mut capability);
+                    }
+                }
+            }
+        }
+
+        for (segment, joint) in FrameConvention::default().joints() {
+            capability.add_segment(segment, joint.source);
+        }
+
+        capability
+    }
+}
+
+impl Service<CapabilityConfig> for CapabilityPublisher {
+    fn new(config: CapabilityConfig) -> Self
+    where
+        Self: Sized,
+    {
+        let present = HashSet::new();
+        let capability = Self::assemble(&config, &present);
+
+        global::set_capability(capability.clone());
+
+        Self {
+            config,
+            present,
+            capability,
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("capability publisher")
+    }
+
+    async fn setup(&mut self) {
+        debug!("Machine capability: {}", self.capability);
+    }
+
+    async fn wait_io_pipe(&mut self, signal_tx: SignalSender, mut signal_rx: SignalReceiver) {
+        while let Ok(signal) = signal_rx.recv().await {
+            if let Object::ModuleStatus(status) = signal {
+                if !status.is_healthy() || !self.present.insert(status.name.clone()) {
+                    continue;
+                }
+
+                let capability = Self::assemble(&self.config, &self.present);
+                if capability == self.capability {
+                    continue;
+                }
+
+                info!("Machine capability changed: {}", capability);
+
+                global::set_capability(capability.clone());
+
+                if let Err(e) = signal_tx.send(Object::Capability(capability.clone())) {
+                    error!("Failed to send capability: {}", e);
+                }
+
+                self.capability = capability;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Actuator;
+
+    const EXCAVATOR_CONFIG: &str = r#"
+        interface = "vcan0"
+        address = 0x27
+        driver = [
+            { da = 0x4A, vendor = "laixer", product = "hcu" },
+            { da = 0x6A, vendor = "kübler", product = "encoder" },
+            { da = 0x6B, vendor = "kübler", product = "encoder" },
+        ]
+
+        [name]
+        manufacturer_code = 0
+        function_instance = 0
+        ecu_instance = 0
+        function = 255
+        vehicle_system = 5
+        vehicle_system_instance = 5
+        industry_group = 3
+    "#;
+
+    const LOADER_CONFIG: &str = r#"
+        interface = "vcan0"
+        address = 0x27
+        driver = [
+            { da = 0x0, sa = 0x11, vendor = "volvo", product = "d7e" },
+            { da = 0x12, vendor = "laixer", product = "vcu" },
+            { da = 0x4A, vendor = "laixer", product = "hcu", optional = true },
+        ]
+
+        [name]
+        manufacturer_code = 0
+        function_instance = 0
+        ecu_instance = 0
+        function = 255
+        vehicle_system = 5
+        vehicle_system_instance = 5
+        industry_group = 3
+    "#;
+
+    fn config(machine_type: MachineType, network: &str) -> CapabilityConfig {
+        CapabilityConfig {
+            machine_type,
+            network: vec![toml::from_str(network).unwrap()],
+        }
+    }
+
+    #[test]
+    fn test_capability_excavator() {
+        let config = config(MachineType::Excavator, EXCAVATOR_CONFIG);
+        let capability = CapabilityPublisher::assemble(&config, &HashSet::new());
+
+        assert_eq!(capability.machine_type, MachineType::Excavator);
+        assert_eq!(capability.actuators().len(), 6);
+        assert_eq!(capability.actuators()[0].actuator, Actuator::Boom);
+        assert_eq!(capability.sensors().len(), 2);
+        assert_eq!(capability.sensors()[0].name, "frame");
+        assert_eq!(capability.sensors()[1].name, "boom");
+        assert_eq!(capability.segments().len(), 4);
+    }
+
+    #[test]
+    fn test_capability_optional_driver() {
+        let config = config(MachineType::WheelLoader, LOADER_CONFIG);
+        let capability = CapabilityPublisher::assemble(&config, &HashSet::new());
+
+        assert_eq!(capability.machine_type, MachineType::WheelLoader);
+        assert!(capability.actuators().is_empty());
+        assert_eq!(capability.sensors().len(), 1);
+        assert_eq!(capability.sensors()[0].unit, "rpm");
+
+        let present = HashSet::from(["laixer:hcu:0x27:0x4A".to_string()]);
+        let capability_present = CapabilityPublisher::assemble(&config, &present);
+
+        assert_ne!(capability, capability_present);
+        assert_eq!(capability_present.actuators().len(), 6);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, time::Instant};
+
+use nalgebra::Rotation3;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    core::{Object, RotationReference, Rotator, RotatorRate},
+    math::SavitzkyGolay,
+    runtime::{Service, ServiceContext, SignalReceiver, SignalSender},
+};
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
+pub struct RotatorDerivativeConfig {
+    /// Publish interval in milliseconds.
+    #[serde(default = "RotatorDerivativeConfig::default_interval")]
+    pub interval: u64,
+    /// Number of samples in the differentiation window.
+    #[serde(default = "RotatorDerivativeConfig::default_window")]
+    pub window: usize,
+    /// Maximum time between samples in milliseconds.
+    #[serde(default = "RotatorDerivativeConfig::default_max_gap")]
+    pub max_gap: u64,
+    /// Sources with a continuous rotation, such as the slew joint.
+    #[serde(default = "RotatorDerivativeConfig::default_continuous")]
+    pub continuous: Vec<u8>,
+}
+
+impl RotatorDerivativeConfig {
+    fn default_interval() -> u64 {
+        50
+    }
+
+    fn default_window() -> usize {
+        9
+    }
+
+    fn default_max_gap() -> u64 {
+        100
+    }
+
+    fn default_continuous() -> Vec<u8> {
+        vec![0x6A]
+    }
+}
+
+impl Default for RotatorDerivativeConfig {
+    fn default() -> Self {
+        Self {
+            interval: Self::default_interval(),
+            window: Self::default_window(),
+            max_gap: Self::default_max_gap(),
+            continuous: Self::default_continuous(),
+        }
+    }
+}
+
+/// Angle of a rotation around one of the principal axes.
+///
+/// Encoders rotate around a single principal axis, the angle is
+/// returned in the range of (-PI, PI].
+pub(super) fn principal_angle(rotation: &Rotation3<f32>) -> f32 {
+    let scaled_axis = rotation.scaled_axis();
+    scaled_axis.x + scaled_axis.y + scaled_axis.z
+}
+
+struct RotatorChannel {
+    /// Differentiation filter.
+    filter: SavitzkyGolay,
+    /// Whether the rotation is continuous.
+    continuous: bool,
+    /// Last raw angle.
+    last_angle: Option<f32>,
+    /// Unwrapped angle.
+    angle: f32,
+}
+
+impl RotatorChannel {
+    fn push(&mut self, time: f64, angle: f32) {
+        if self.continuous {
+            if let Some(last_angle) = self.last_angle {
+                self.angle += crate::math::shortest_rotation(angle - last_angle);
+            } else {
+                self.angle = angle;
+            }
+        } else {
+            self.angle = angle;
+        }
+
+        self.last_angle = Some(angle);
+        self.filter.push(time, self.angle);
+    }
+
+    fn rate(&mut self, source: u8, time: f64) -> RotatorRate {
+        if self.filter.is_stale(time) {
+            self.filter.reset();
+            self.last_angle = None;
+        }
+
+        match self.filter.derivative() {
+            Some((velocity, acceleration)) => RotatorRate::new(source, velocity, acceleration),
+            None => RotatorRate::invalid(source),
+        }
+    }
+}
+
+/// Derive the velocity and acceleration of encoder rotators.
+///
+/// Relative rotators are differentiated per source and published as
+/// `RotatorRate` signals at a fixed interval. When a source stops reporting
+/// the rate is published as invalid until the filter window is refilled.
+pub struct RotatorDerivative {
+    config: RotatorDerivativeConfig,
+    epoch: Instant,
+    channels: HashMap<u8, RotatorChannel>,
+}
+
+impl RotatorDerivative {
+    fn on_rotator(&mut self, time: f64, rotator: &Rotator) {
+        if rotator.reference != RotationReference::Relative {
+            return;
+        }
+
+        let channel = self
+            .channels
+            .entry(rotator.source)
+            .or_insert_with(|| RotatorChannel {
+                filter: SavitzkyGolay::new(
+                    self.config.window,
+                    self.config.max_gap as f64 / 1_000.0,
+                ),
+                continuous: self.config.continuous.contains(&rotator.source),
+                last_angle: None,
+                angle: 0.0,
+            });
+
+        channel.push(time, principal_angle(&rotator.rotator));
+    }
+
+    fn rates(&mut self, time: f64) -> Vec<RotatorRate> {
+        self.channels
+            .iter_mut()
+            .map(|(source, channel)| channel.rate(*source, time))
+            .collect()
+    }
+}
+
+impl Service<RotatorDerivativeConfig> for RotatorDerivative {
+    fn new(config: RotatorDerivativeConfig) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            config,
+            epoch: Instant::now(),
+            channels: HashMap::new(),
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("rotator derivative")
+    }
+
+    async fn wait_io_pipe(&mut self, signal_tx: SignalSender, mut signal_rx: SignalReceiver) {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_millis(self.config.interval));
+
+        loop {
+            tokio::select! {
+                signal = signal_rx.recv() => {
+                    match signal {
+                        Ok(Object::Rotator(rotator)) => {
+                            let time = self.epoch.elapsed().as_secs_f64();
+                            self.on_rotator(time, &rotator);
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(count)) => {
+                            warn!("Signal receiver lagged by {} objects", count);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = interval.tick() => {
+                    let time = self.epoch.elapsed().as_secs_f64();
+
+                    for rate in self.rates(time) {
+                        if let Err(e) = signal_tx.send(Object::RotatorRate(rate)) {
+                            error!("Failed to send signal: {}", e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::EulerAngles;
+
+    #[test]
+    fn rotator_derivative_wraparound() {
+        let mut service = RotatorDerivative::new(RotatorDerivativeConfig::default());
+
+        for i in 0..100 {
+            let time = i as f64 * 0.01;
+            let angle = crate::math::shortest_rotation(3.0 + 0.5 * time as f32);
+            let rotator = Rotator::relative(0x6A, Rotation3::from_yaw(angle));
+
+            service.on_rotator(time, &rotator);
+
+            let rates = service.rates(time);
+            if rates[0].valid {
+                assert!((rates[0].velocity - 0.5).abs() < 0.01);
+                assert!(rates[0].acceleration.abs() < 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn rotator_derivative_gap() {
+        let mut service = RotatorDerivative::new(RotatorDerivativeConfig::default());
+
+        let sample = |time: f64| {
+            let angle = (std::f32::consts::PI * time as f32).sin() * 0.5;
+            Rotator::relative(0x6B, Rotation3::from_pitch(angle))
+        };
+
+        for i in 0..50 {
+            service.on_rotator(i as f64 * 0.01, &sample(i as f64 * 0.01));
+        }
+
+        assert!(service.rates(0.5)[0].valid);
+        assert!(!service.rates(1.0)[0].valid);
+
+        for i in 100..150 {
+            let time = i as f64 * 0.01;
+            service.on_rotator(time, &sample(time));
+
+            let rate = service.rates(time)[0];
+            if rate.valid {
+                assert!(rate.velocity.abs() <= 0.5 * std::f32::consts::PI * 1.01);
+            }
+        }
+
+        assert!(service.rates(1.5)[0].valid);
+    }
+}
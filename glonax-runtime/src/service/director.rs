@@ -1,11 +1,13 @@
 use nalgebra::{Point3, Vector3};
 
 use crate::{
-    core::{Actuator, Control, Engine, Motion, Object},
-    driver::ActuatorState,
-    math::Linear,
-    runtime::{CommandSender, NullConfig, Service, ServiceContext, SignalReceiver},
-    world::{Actor, ActorBuilder, ActorSegment, World},
+    core::{Actuator, Control, Engine, MachineType, Motion, Object, Repository, Rotator},
+    driver::{ActuatorState, MotionProfile},
+    runtime::{CommandSender, Heartbeat, Service, ServiceContext, SignalReceiver},
+    world::{
+        Actor, ActorBuilder, ActorSegment, AttachmentRegistry, CollisionGeometry, JointDescriptor,
+        World, EFFECTOR_SEGMENT,
+    },
 };
 
 const ROBOT_ACTOR_NAME: &str = "volvo_ec240cl";
@@ -16,6 +18,11 @@ const ENCODER_ARM: u8 = 0x6C;
 const ENCODER_ATTACHMENT: u8 = 0x6D;
 const INCLINOMETER: u8 = 0x7A;
 
+/// Condition number of the boom/arm Jacobian above which damping is applied.
+const SINGULARITY_CONDITION_THRESHOLD: f32 = 50.0;
+/// Maximum damping factor applied near a kinematic singularity.
+const SINGULARITY_DAMPING_MAX: f32 = 50.0;
+
 #[allow(dead_code)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DirectorOperation {
@@ -34,18 +41,42 @@ impl std::fmt::Display for DirectorOperation {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DirectorConfig {
+    /// Collision geometry.
+    pub collision: CollisionGeometry,
+    /// Attachments available to the machine.
+    pub attachments: AttachmentRegistry,
+    /// Motion profile per joint.
+    pub motion_profile: MotionProfile,
+    /// Machine state snapshot to seed the world from.
+    pub snapshot: Option<std::path::PathBuf>,
+}
+
 // TODO:
-// - max kinematic reach from the actor
 // - motion rules
 
 pub struct Director {
     world: World,
+    collision: CollisionGeometry,
+    attachments: AttachmentRegistry,
+    /// Attachment change in progress, motion is held until resumed.
+    attachment_change: bool,
     operation: DirectorOperation,
     state: std::collections::HashMap<i32, DirectorLocslState>,
     frame_state: ActuatorState,
     boom_state: ActuatorState,
     arm_state: ActuatorState,
     attachment_state: ActuatorState,
+    singularity_damping: bool,
+    /// Machine state snapshot to seed the world from.
+    snapshot: Option<std::path::PathBuf>,
+    /// Rotator sources seeded from the snapshot and not yet confirmed.
+    ///
+    /// Motion is not commanded while the world holds unconfirmed state.
+    stale_rotator: std::collections::HashSet<u8>,
+    /// Service heartbeat, touched on every event.
+    heartbeat: Option<Heartbeat>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -94,37 +125,22 @@ impl Director {
         }
     }
 
-    fn dump_actor(actor: &Actor) {
-        let body_world_location = actor.world_location("frame");
-        trace!(
-            "Frame: X={:.2} Y={:.2} Z={:.2}",
-            body_world_location.x,
-            body_world_location.y,
-            body_world_location.z
-        );
-
-        let boom_world_location = actor.world_location("boom");
-        trace!(
-            "Boom: X={:.2} Y={:.2} Z={:.2}",
-            boom_world_location.x,
-            boom_world_location.y,
-            boom_world_location.z
-        );
-
-        let arm_world_location = actor.world_location("arm");
-        trace!(
-            "Arm: X={:.2} Y={:.2} Z={:.2}",
-            arm_world_location.x,
-            arm_world_location.y,
-            arm_world_location.z
-        );
+    fn dump_actor(actor: &Actor, collision: &CollisionGeometry) {
+        // The undercarriage is the actor origin.
+        for (name, transform) in actor.world_transforms().iter().skip(1) {
+            let location = transform.translation.vector;
+            trace!(
+                "{}: X={:.2} Y={:.2} Z={:.2}",
+                name,
+                location.x,
+                location.y,
+                location.z
+            );
+        }
 
-        let attachment_world_location = actor.world_location("attachment");
         trace!(
-            "Attachment: X={:.2} Y={:.2} Z={:.2}",
-            attachment_world_location.x,
-            attachment_world_location.y,
-            attachment_world_location.z
+            "Attachment ground clearance: {:.2}",
+            collision.ground_clearance(actor, "attachment")
         );
 
         let actor_world_distance =
@@ -132,6 +148,50 @@ impl Director {
         trace!("Actor origin distance: {:.2}", actor_world_distance);
     }
 
+    /// Seed the world from the machine state snapshot.
+    ///
+    /// The restored rotators set the initial pose of the actor and are stale
+    /// until the source reports live telemetry.
+    async fn seed_world(&mut self, path: &std::path::Path) -> std::io::Result<()> {
+        let mut repository =
+            Repository::new(crate::global::instance().clone(), MachineType::Excavator);
+        repository.read_snapshot(path).await?;
+
+        for rotator in repository.rotator.values() {
+            if repository.is_rotator_stale(rotator.source) {
+                self.set_pose(rotator);
+                self.stale_rotator.insert(rotator.source);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Test if the world holds state not confirmed by live telemetry.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        !self.stale_rotator.is_empty()
+    }
+
+    /// Apply the rotator to the pose of the actor.
+    fn set_pose(&mut self, rotator: &Rotator) {
+        let segment = self
+            .world
+            .convention()
+            .joint_by_source(rotator.source)
+            .map(|(segment, _)| segment.to_string());
+
+        let actor = self.world.get_actor_by_name_mut(ROBOT_ACTOR_NAME).unwrap();
+
+        if let Some(segment) = segment {
+            if rotator.reference == crate::core::RotationReference::Relative {
+                actor.set_segment_rotation(&segment, rotator.rotator);
+            }
+        } else if rotator.source == INCLINOMETER {
+            actor.set_rotation(rotator.rotator);
+        }
+    }
+
     fn elect_rotator_state(&self, rotator: &crate::core::Rotator) -> DirectorLocslState {
         match rotator.source {
             ENCODER_FRAME => {}
@@ -203,9 +263,6 @@ impl Director {
 
     // TODO: Returns a state, for example TargetOutOfRange, TargetOutOfReach, TargetInReach, etc.
     fn calculate_target_properties(actor: &Actor, target: &Actor) {
-        // TODO: Calculate this from the actor
-        const MAX_KINEMATIC_DISTANCE: f32 = 700.0;
-
         debug!("Objective target: {}", target.location());
 
         let actor_target_distance = nalgebra::distance(&actor.location(), &target.location());
@@ -219,7 +276,7 @@ impl Director {
             kinematic_target_distance
         );
 
-        if kinematic_target_distance > MAX_KINEMATIC_DISTANCE {
+        if !actor.is_reachable(target.location()) {
             warn!("Target is out of reach");
         }
     }
@@ -290,6 +347,61 @@ impl Director {
         actuator_error.push((Actuator::Arm, arm_angle));
     }
 
+    /// Damp the boom and arm errors near a kinematic singularity.
+    ///
+    /// When the boom and arm approach full extension the inverse kinematics
+    /// become ill-conditioned and small target changes result in large joint
+    /// errors. The condition number of the boom/arm Jacobian is used to detect
+    /// this and the joint errors are damped using damped least squares.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor` - The actor to calculate the Jacobian for.
+    /// * `actuator_error` - The actuator errors to damp.
+    ///
+    /// # Returns
+    ///
+    /// `true` if damping was applied, `false` otherwise.
+    fn damp_singularity(actor: &Actor, actuator_error: &mut [(Actuator, f32)]) -> bool {
+        let boom_length = actor.segment_location("arm").unwrap().x;
+        let arm_length = actor.segment_location("attachment").unwrap().x;
+
+        let boom_pitch = actor.segment_rotation("boom").unwrap().euler_angles().1;
+        let arm_pitch = actor.segment_rotation("arm").unwrap().euler_angles().1;
+
+        let jacobian = crate::math::planar_jacobian(boom_length, arm_length, boom_pitch, arm_pitch);
+
+        let condition = crate::math::condition_number(&jacobian);
+        if condition < SINGULARITY_CONDITION_THRESHOLD {
+            return false;
+        }
+
+        let damping =
+            SINGULARITY_DAMPING_MAX * (1.0 - SINGULARITY_CONDITION_THRESHOLD / condition).sqrt();
+
+        let boom_idx = actuator_error
+            .iter()
+            .position(|(a, _)| *a == Actuator::Boom);
+        let arm_idx = actuator_error.iter().position(|(a, _)| *a == Actuator::Arm);
+
+        if let (Some(boom_idx), Some(arm_idx)) = (boom_idx, arm_idx) {
+            let error =
+                nalgebra::Vector2::new(actuator_error[boom_idx].1, actuator_error[arm_idx].1);
+
+            let damped_error = crate::math::damped_least_squares(&jacobian, &error, damping);
+
+            debug!(
+                "Singularity damping: condition {:.1}, damping {:.2}",
+                condition, damping
+            );
+
+            actuator_error[boom_idx].1 = damped_error.x;
+            actuator_error[arm_idx].1 = damped_error.y;
+        }
+
+        true
+    }
+
     fn calculate_motion_control(
         &mut self,
         actuator_error: &[(Actuator, f32)],
@@ -352,37 +464,62 @@ impl Director {
         }
     }
 
+    /// Change the mounted attachment.
+    ///
+    /// All motion is stopped before the geometry is swapped. The effector
+    /// and the collision shape of the actor are replaced by those of the
+    /// selected attachment. Motion is held until it is explicitly resumed.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The attachment identifier.
+    /// * `command_tx` - The command sender used to stop motion.
+    fn change_attachment(&mut self, id: u8, command_tx: &CommandSender) {
+        let attachment = match self.attachments.select(id) {
+            Ok(attachment) => attachment,
+            Err(e) => {
+                warn!("Failed to change attachment: {}", e);
+                return;
+            }
+        };
+
+        let motion_command = Motion::StopAll;
+        if let Err(e) = command_tx.send(Object::Motion(motion_command)) {
+            error!("Failed to send motion command: {}", e);
+        }
+
+        self.attachment_change = true;
+
+        let actor = self.world.get_actor_by_name_mut(ROBOT_ACTOR_NAME).unwrap();
+        attachment.apply(actor);
+        self.collision.set_attachment(attachment);
+
+        info!(
+            "Attachment changed to {}, motion held until resumed",
+            attachment
+        );
+    }
+
+    fn on_command(&mut self, command: &Object, command_tx: &CommandSender) {
+        match command {
+            Object::Control(Control::AttachmentSelect(id)) => {
+                self.change_attachment(*id, command_tx);
+            }
+            Object::Motion(Motion::ResumeAll) if self.attachment_change => {
+                info!("Attachment change completed");
+                self.attachment_change = false;
+            }
+            _ => {}
+        }
+    }
+
     fn on_event(&mut self, event: &Object) {
         match event {
             Object::Rotator(rotator) => {
-                let actor = self.world.get_actor_by_name_mut(ROBOT_ACTOR_NAME).unwrap();
+                self.set_pose(rotator);
 
-                match rotator.source {
-                    ENCODER_FRAME => {
-                        if rotator.reference == crate::core::RotationReference::Relative {
-                            // TODO: We only set the yaw angle for the frame
-                            actor.set_segment_rotation("frame", rotator.rotator);
-                        }
-                    }
-                    ENCODER_BOOM => {
-                        if rotator.reference == crate::core::RotationReference::Relative {
-                            actor.set_segment_rotation("boom", rotator.rotator);
-                        }
-                    }
-                    ENCODER_ARM => {
-                        if rotator.reference == crate::core::RotationReference::Relative {
-                            actor.set_segment_rotation("arm", rotator.rotator);
-                        }
-                    }
-                    ENCODER_ATTACHMENT => {
-                        if rotator.reference == crate::core::RotationReference::Relative {
-                            actor.set_segment_rotation("attachment", rotator.rotator);
-                        }
-                    }
-                    INCLINOMETER => {
-                        actor.set_rotation(rotator.rotator);
-                    }
-                    _ => {}
+                if self.stale_rotator.remove(&rotator.source) && self.stale_rotator.is_empty() {
+                    info!("Restored pose confirmed by live telemetry");
                 }
 
                 self.state.insert(0, self.elect_rotator_state(rotator));
@@ -390,65 +527,86 @@ impl Director {
             Object::Engine(engine) => {
                 self.state.insert(1, self.elect_engine_state(engine));
             }
-            Object::Target(target) => {
-                if self.operation == DirectorOperation::Autonomous {
-                    let actor = ActorBuilder::new("target0")
-                        .with_location(target.point.coords)
-                        .with_rotation(target.orientation.into())
-                        .build();
-
-                    self.world.add_actor(actor);
-                }
+            Object::Target(target) if self.operation == DirectorOperation::Autonomous => {
+                let actor = ActorBuilder::new("target0")
+                    .with_location(target.point.coords)
+                    .with_rotation(target.orientation.into())
+                    .build();
+
+                self.world.add_actor(actor);
             }
             _ => {}
         }
     }
 }
 
-impl Service<NullConfig> for Director {
-    fn new(_: NullConfig) -> Self
+/// Construct the robot actor.
+///
+/// Segment locations are in centimeters.
+// TODO: Build the actor from configuration and machine instance
+pub(super) fn robot_actor() -> Actor {
+    ActorBuilder::new(ROBOT_ACTOR_NAME)
+        .attach_segment(
+            "undercarriage",
+            ActorSegment::new(Vector3::new(0.0, 0.0, 0.0)),
+        )
+        .attach_segment("frame", ActorSegment::new(Vector3::new(-4.0, 5.0, 107.0)))
+        .attach_segment("boom", ActorSegment::new(Vector3::new(4.0, 20.0, 33.0)))
+        .attach_segment("arm", ActorSegment::new(Vector3::new(510.0, 20.0, 5.0)))
+        .attach_segment(
+            "attachment",
+            ActorSegment::new(Vector3::new(310.0, -35.0, 45.0)),
+        )
+        .attach_segment(EFFECTOR_SEGMENT, ActorSegment::new(Vector3::zeros()))
+        .with_joint("frame", JointDescriptor::continuous(Vector3::z_axis()))
+        .with_joint(
+            "boom",
+            JointDescriptor::revolute(Vector3::y_axis(), -45_f32.to_radians(), 60_f32.to_radians())
+                .unwrap(),
+        )
+        .with_joint(
+            "arm",
+            JointDescriptor::revolute(Vector3::y_axis(), -2.76, -0.685).unwrap(),
+        )
+        .with_joint(
+            "attachment",
+            JointDescriptor::revolute(Vector3::y_axis(), -3.1, 0.0).unwrap(),
+        )
+        .build()
+}
+
+impl Service<DirectorConfig> for Director {
+    fn new(config: DirectorConfig) -> Self
     where
         Self: Sized,
     {
         let mut world = World::default();
 
-        // TODO: Build the actor from configuration and machine instance
-        let actor = ActorBuilder::new(ROBOT_ACTOR_NAME)
-            .attach_segment(
-                "undercarriage",
-                ActorSegment::new(Vector3::new(0.0, 0.0, 0.0)),
-            )
-            .attach_segment("frame", ActorSegment::new(Vector3::new(-4.0, 5.0, 107.0)))
-            .attach_segment("boom", ActorSegment::new(Vector3::new(4.0, 20.0, 33.0)))
-            .attach_segment("arm", ActorSegment::new(Vector3::new(510.0, 20.0, 5.0)))
-            .attach_segment(
-                "attachment",
-                ActorSegment::new(Vector3::new(310.0, -35.0, 45.0)),
-            )
-            .build();
-
         // TODO: Return weak reference to the actor
-        world.add_actor(actor);
+        world.add_actor(robot_actor());
 
-        // TODO: Build the profile from configuration
-        let frame_profile = Linear::new(7_000.0, 12_000.0, false);
-        let boom_profile = Linear::new(15_000.0, 12_000.0, false);
-        let arm_profile = Linear::new(15_000.0, 12_000.0, true);
-        let attachment_profile = Linear::new(15_000.0, 12_000.0, false);
+        let profile = config.motion_profile;
 
-        let frame_state = ActuatorState::bind(Actuator::Slew, frame_profile);
-        let boom_state = ActuatorState::bind(Actuator::Boom, boom_profile);
-        let arm_state = ActuatorState::bind(Actuator::Arm, arm_profile);
-        let attachment_state = ActuatorState::bind(Actuator::Attachment, attachment_profile);
+        let frame_state = ActuatorState::bind(Actuator::Slew, profile.frame);
+        let boom_state = ActuatorState::bind(Actuator::Boom, profile.boom);
+        let arm_state = ActuatorState::bind(Actuator::Arm, profile.arm);
+        let attachment_state = ActuatorState::bind(Actuator::Attachment, profile.attachment);
 
         Self {
             world,
+            collision: config.collision,
+            attachments: config.attachments,
+            attachment_change: false,
             operation: DirectorOperation::Supervised,
             state: std::collections::HashMap::new(),
             frame_state,
             boom_state,
             arm_state,
             attachment_state,
+            singularity_damping: false,
+            snapshot: config.snapshot,
+            stale_rotator: std::collections::HashSet::new(),
+            heartbeat: None,
         }
     }
 
@@ -456,12 +614,47 @@ impl Service<NullConfig> for Director {
         ServiceContext::new("vehicle director")
     }
 
+    fn set_heartbeat(&mut self, heartbeat: Heartbeat) {
+        self.heartbeat = Some(heartbeat);
+    }
+
     async fn setup(&mut self) {
         info!("Vehicle director is running in {} mode", self.operation);
+        debug!("World frame convention: {}", self.world.convention());
+
+        if let Some(snapshot) = self.snapshot.clone() {
+            match self.seed_world(&snapshot).await {
+                Ok(_) => info!(
+                    "Seeded world from {}, stale until confirmed",
+                    snapshot.display()
+                ),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warn!("Failed to seed world from snapshot: {}", e),
+            }
+        }
     }
 
     async fn wait_io_sub(&mut self, command_tx: CommandSender, mut signal_rx: SignalReceiver) {
-        while let Ok(signal) = signal_rx.recv().await {
+        let mut command_rx = command_tx.subscribe();
+
+        loop {
+            if let Some(heartbeat) = &self.heartbeat {
+                heartbeat.touch();
+            }
+
+            let signal = tokio::select! {
+                Ok(command) = command_rx.recv() => {
+                    self.on_command(&command, &command_tx);
+                    continue;
+                }
+                signal = signal_rx.recv() => {
+                    let Ok(signal) = signal else {
+                        break;
+                    };
+                    signal
+                }
+            };
+
             self.on_event(&signal);
 
             let max_state = self
@@ -493,15 +686,29 @@ impl Service<NullConfig> for Director {
                     let mut actuator_error = Vec::new();
                     let mut actuator_motion = Vec::new();
 
-                    Self::dump_actor(actor);
+                    Self::dump_actor(actor, &self.collision);
 
                     if let Some(target) = target {
                         Self::calculate_target_properties(actor, target);
                         Self::calculate_target_trajectory(actor, target, &mut actuator_error);
+
+                        let singularity_damping =
+                            Self::damp_singularity(actor, &mut actuator_error);
+                        if singularity_damping != self.singularity_damping {
+                            if singularity_damping {
+                                warn!("Kinematic singularity near, damping actuator commands");
+                            } else {
+                                info!("Kinematic singularity cleared");
+                            }
+                            self.singularity_damping = singularity_damping;
+                        }
+
                         self.calculate_motion_control(&actuator_error, &mut actuator_motion);
                     }
 
                     if self.operation == DirectorOperation::Autonomous
+                        && !self.attachment_change
+                        && !self.is_stale()
                         && !actuator_motion.is_empty()
                     {
                         let motion_command = Motion::from_iter(actuator_motion);
@@ -525,4 +732,175 @@ mod tests {
         assert!(DirectorLocslState::Nominal < DirectorLocslState::UnboundKinematics);
         assert!(DirectorLocslState::Nominal < DirectorLocslState::Emergency);
     }
+
+    #[test]
+    fn director_attachment_change() {
+        use crate::world::{AttachmentConfig, ShapeConfig};
+
+        let attachments = AttachmentRegistry::from_config(&[AttachmentConfig {
+            id: 3,
+            name: "ditch bucket".to_string(),
+            effector: [150.0, 0.0, -20.0],
+            shape: ShapeConfig {
+                half_extents: [75.0, 100.0, 25.0],
+                location: [75.0, 0.0, -10.0],
+            },
+        }])
+        .unwrap();
+
+        let mut director = Director::new(DirectorConfig {
+            attachments,
+            ..Default::default()
+        });
+
+        let (command_tx, mut command_rx) = tokio::sync::broadcast::channel(16);
+
+        let actor = director.world.get_actor_by_name(ROBOT_ACTOR_NAME).unwrap();
+        let attachment_location = actor.world_location("attachment");
+        assert_eq!(actor.world_location(EFFECTOR_SEGMENT), attachment_location);
+
+        director.on_command(&Object::Control(Control::AttachmentSelect(9)), &command_tx);
+        assert!(!director.attachment_change);
+        assert!(command_rx.try_recv().is_err());
+
+        director.on_command(&Object::Control(Control::AttachmentSelect(3)), &command_tx);
+        assert!(director.attachment_change);
+        assert_eq!(
+            command_rx.try_recv().unwrap(),
+            Object::Motion(Motion::StopAll)
+        );
+
+        let actor = director.world.get_actor_by_name(ROBOT_ACTOR_NAME).unwrap();
+        let effector = actor.world_location(EFFECTOR_SEGMENT);
+        assert!((effector.x - attachment_location.x - 150.0).abs() < 1e-3);
+        assert!((effector.z - attachment_location.z + 20.0).abs() < 1e-3);
+        assert_eq!(
+            director.collision.bucket_corners(actor, "attachment"),
+            director
+                .attachments
+                .active()
+                .unwrap()
+                .shape()
+                .corners(director.attachments.active().unwrap().location())
+                .map(|corner| actor
+                    .world_transformation("attachment")
+                    .transform_point(&corner))
+        );
+
+        director.on_command(&Object::Motion(Motion::ResumeAll), &command_tx);
+        assert!(!director.attachment_change);
+    }
+
+    #[test]
+    fn director_singularity_damping() {
+        use crate::math::EulerAngles;
+        use nalgebra::Rotation3;
+
+        let mut director = Director::new(DirectorConfig::default());
+
+        let actor = director
+            .world
+            .get_actor_by_name_mut(ROBOT_ACTOR_NAME)
+            .unwrap();
+        actor.set_segment_rotation("boom", Rotation3::from_pitch(20.0_f32.to_radians()));
+        actor.set_segment_rotation("arm", Rotation3::from_pitch(-0.1_f32.to_radians()));
+
+        let actor = director.world.get_actor_by_name(ROBOT_ACTOR_NAME).unwrap();
+
+        let mut actuator_error = vec![(Actuator::Boom, 1.2), (Actuator::Arm, -2.8)];
+        assert!(Director::damp_singularity(actor, &mut actuator_error));
+
+        let (boom_error, arm_error) = (actuator_error[0].1, actuator_error[1].1);
+        assert!(boom_error.is_finite() && arm_error.is_finite());
+        assert!(boom_error.hypot(arm_error) < 1.2_f32.hypot(2.8));
+
+        let mut actuator_motion = Vec::new();
+        director.calculate_motion_control(&actuator_error, &mut actuator_motion);
+
+        let mut actuator_motion_undamped = Vec::new();
+        director.calculate_motion_control(
+            &[(Actuator::Boom, 1.2), (Actuator::Arm, -2.8)],
+            &mut actuator_motion_undamped,
+        );
+
+        for ((_, value), (_, value_undamped)) in
+            actuator_motion.iter().zip(actuator_motion_undamped.iter())
+        {
+            assert!(value.unsigned_abs() <= value_undamped.unsigned_abs());
+        }
+    }
+
+    #[test]
+    fn director_singularity_no_damping() {
+        use crate::math::EulerAngles;
+        use nalgebra::Rotation3;
+
+        let mut director = Director::new(DirectorConfig::default());
+
+        let actor = director
+            .world
+            .get_actor_by_name_mut(ROBOT_ACTOR_NAME)
+            .unwrap();
+        actor.set_segment_rotation("boom", Rotation3::from_pitch(20.0_f32.to_radians()));
+        actor.set_segment_rotation("arm", Rotation3::from_pitch(-70.0_f32.to_radians()));
+
+        let actor = director.world.get_actor_by_name(ROBOT_ACTOR_NAME).unwrap();
+
+        let mut actuator_error = vec![(Actuator::Boom, 0.2), (Actuator::Arm, -0.4)];
+        assert!(!Director::damp_singularity(actor, &mut actuator_error));
+        assert_eq!(
+            actuator_error,
+            vec![(Actuator::Boom, 0.2), (Actuator::Arm, -0.4)]
+        );
+    }
+
+    #[tokio::test]
+    async fn director_seed_world() {
+        use crate::core::Instance;
+        use crate::math::EulerAngles;
+        use nalgebra::Rotation3;
+
+        let instance = crate::INSTANCE.get_or_init(|| {
+            Instance::new(
+                "d55bcd75-8d30-49af-ac18-ee7cbce7822f",
+                "test",
+                MachineType::Excavator,
+                (3, 5, 13),
+                "ABC123",
+            )
+        });
+
+        let boom = Rotator::relative(ENCODER_BOOM, Rotation3::from_pitch(0.5));
+
+        let mut repository = Repository::new(instance.clone(), MachineType::Excavator);
+        repository.update(Object::Rotator(boom));
+        repository.update(Object::Rotator(Rotator::relative(
+            ENCODER_ARM,
+            Rotation3::from_pitch(-1.2),
+        )));
+
+        let path = std::env::temp_dir().join(format!("glonax-director-{}.bin", std::process::id()));
+        repository.write_snapshot(&path).await.unwrap();
+
+        let mut director = Director::new(DirectorConfig {
+            snapshot: Some(path.clone()),
+            ..Default::default()
+        });
+        director.setup().await;
+        std::fs::remove_file(&path).unwrap();
+
+        let actor = director.world.get_actor_by_name(ROBOT_ACTOR_NAME).unwrap();
+        let rotation = actor.segment_rotation("boom").unwrap();
+        assert!((rotation.angle() - 0.5).abs() < 1e-6);
+        assert!(director.is_stale());
+
+        director.on_event(&Object::Rotator(boom));
+        assert!(director.is_stale());
+
+        director.on_event(&Object::Rotator(Rotator::relative(
+            ENCODER_ARM,
+            Rotation3::from_pitch(-1.0),
+        )));
+        assert!(!director.is_stale());
+    }
 }
@@ -1,20 +1,71 @@
+use std::{path::PathBuf, time::Duration};
+
+use tokio::time::Instant;
+
 use crate::{
-    core::{MachineType, Object, Repository},
+    core::{Control, MachineType, Object, Repository},
     global,
-    runtime::{CommandSender, NullConfig, Service, ServiceContext, SignalReceiver},
+    runtime::{CommandSender, Service, ServiceContext, SignalReceiver},
 };
 
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
+pub struct DistributorConfig {
+    /// Machine state snapshot file.
+    pub file: Option<PathBuf>,
+    /// Snapshot interval in seconds.
+    #[serde(default = "DistributorConfig::default_interval")]
+    pub interval: u64,
+    /// Seconds after which restored state not confirmed by live telemetry is discarded.
+    #[serde(default = "DistributorConfig::default_stale_timeout")]
+    pub stale_timeout: u64,
+}
+
+impl DistributorConfig {
+    fn default_interval() -> u64 {
+        5
+    }
+
+    fn default_stale_timeout() -> u64 {
+        60
+    }
+}
+
+impl Default for DistributorConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            interval: Self::default_interval(),
+            stale_timeout: Self::default_stale_timeout(),
+        }
+    }
+}
+
 pub struct Distributor {
     repository: Repository,
+    config: DistributorConfig,
+    /// Moment the machine state was restored from the snapshot.
+    restored_at: Option<Instant>,
 }
 
-impl Service<NullConfig> for Distributor {
-    fn new(_: NullConfig) -> Self
+impl Distributor {
+    async fn write_snapshot(&self) {
+        if let Some(file) = &self.config.file {
+            if let Err(e) = self.repository.write_snapshot(file).await {
+                error!("Failed to write machine state snapshot: {}", e);
+            }
+        }
+    }
+}
+
+impl Service<DistributorConfig> for Distributor {
+    fn new(config: DistributorConfig) -> Self
     where
         Self: Sized,
     {
         Self {
             repository: Repository::new(global::instance().clone(), MachineType::Excavator),
+            config,
+            restored_at: None,
         }
     }
 
@@ -22,27 +73,75 @@ impl Service<NullConfig> for Distributor {
         ServiceContext::new("distributor")
     }
 
-    async fn wait_io_sub(&mut self, _command_tx: CommandSender, mut signal_rx: SignalReceiver) {
-        while let Ok(signal) = signal_rx.recv().await {
-            match signal {
-                Object::Engine(engine) => {
-                    self.repository.engine = engine;
-                }
-                Object::Rotator(rotator) => {
-                    self.repository.rotator.insert(rotator.source, rotator);
-                    // debug!("Rotator size {}", self.repository.rotator.len());
+    async fn setup(&mut self) {
+        let Some(file) = &self.config.file else {
+            return;
+        };
+
+        match self.repository.read_snapshot(file).await {
+            Ok(_) => {
+                info!(
+                    "Restored machine state from {}, stale until confirmed",
+                    file.display()
+                );
+                self.restored_at = Some(Instant::now());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("No machine state snapshot at {}", file.display());
+            }
+            Err(e) => warn!("Failed to restore machine state: {}", e),
+        }
+    }
+
+    async fn teardown(&mut self) {
+        self.write_snapshot().await;
+    }
+
+    async fn wait_io_sub(&mut self, command_tx: CommandSender, mut signal_rx: SignalReceiver) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval.max(1)));
+        let mut command_rx = command_tx.subscribe();
+
+        // The motion profile is an operator preference, restore it right away.
+        if let Some(profile) = self.repository.motion_profile() {
+            info!("Restoring motion profile {}", profile);
+
+            let control = Control::MotionProfile(profile);
+            if let Err(e) = command_tx.send(Object::Control(control)) {
+                error!("Failed to send control command: {}", e);
+            }
+        }
+
+        loop {
+            tokio::select! {
+                Ok(command) = command_rx.recv() => {
+                    if let Object::Control(Control::MotionProfile(_)) = command {
+                        self.repository.update(command);
+                    }
                 }
-                Object::ModuleStatus(status) => {
-                    self.repository
-                        .module_status
-                        .insert(status.name.clone(), status);
-                    // debug!("Module status size {}", self.repository.module_status.len());
+                signal = signal_rx.recv() => {
+                    let Ok(signal) = signal else {
+                        break;
+                    };
+
+                    let is_stale = self.repository.is_stale();
+                    self.repository.update(signal);
+                    if is_stale && !self.repository.is_stale() {
+                        info!("Restored machine state confirmed by live telemetry");
+                    }
                 }
-                Object::Control(control) => {
-                    self.repository.control.insert(control);
-                    // debug!("Control size {}", self.repository.control.len());
+                _ = interval.tick() => {
+                    let stale_timeout = Duration::from_secs(self.config.stale_timeout);
+                    if self.repository.is_stale()
+                        && self.restored_at.is_some_and(|at| at.elapsed() >= stale_timeout)
+                    {
+                        warn!("Restored machine state not confirmed, discarding unconfirmed state");
+                        self.repository.expire_stale();
+                    }
+
+                    if !self.repository.is_stale() {
+                        self.write_snapshot().await;
+                    }
                 }
-                _ => {}
             }
         }
     }
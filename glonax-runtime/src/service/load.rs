@@ -0,0 +1,337 @@
+use std::collections::HashSet;
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    core::{Actuator, Control, CylinderPressure, LoadEstimate, Object, RotationReference},
+    runtime::{CommandSender, Service, ServiceContext, SignalReceiver, SignalSender},
+    world::{Actor, FrameConvention},
+};
+
+use super::director::robot_actor;
+
+/// Standard gravity in meters per second squared.
+const GRAVITY: f32 = 9.806_65;
+/// Pascal per bar.
+const BAR: f32 = 100_000.0;
+/// Meters per robot actor unit.
+const ACTOR_UNIT: f32 = 0.01;
+/// Minimum payload moment arm in meters.
+///
+/// Close to the boom pivot the estimate is dominated by measurement noise.
+const MIN_LEVER: f32 = 0.5;
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct LoadConfig {
+    /// Boom cylinder piston area in square meters.
+    pub head_area: f32,
+    /// Boom cylinder annulus area on the rod side in square meters.
+    pub rod_area: f32,
+    /// Boom cylinder moment arm around the boom pivot in meters.
+    pub moment_arm: f32,
+    /// Number of boom cylinders.
+    #[serde(default = "LoadConfig::default_cylinders")]
+    pub cylinders: u8,
+}
+
+impl LoadConfig {
+    fn default_cylinders() -> u8 {
+        2
+    }
+}
+
+/// Payload load model.
+///
+/// The boom cylinder force times its moment arm balances the moment of the
+/// boom, arm, attachment and payload around the boom pivot. The payload mass
+/// follows from this moment and the horizontal distance of the attachment to
+/// the boom pivot. The tare offset removes the empty attachment from the
+/// estimate.
+#[derive(Clone, Debug)]
+pub struct LoadModel {
+    config: LoadConfig,
+    /// Tare offset in kilograms.
+    tare: f32,
+}
+
+impl LoadModel {
+    /// Construct a new load model without tare offset.
+    pub fn new(config: LoadConfig) -> Self {
+        Self { config, tare: 0.0 }
+    }
+
+    /// Tare offset in kilograms.
+    #[inline]
+    pub fn tare_offset(&self) -> f32 {
+        self.tare
+    }
+
+    /// Total boom cylinder force in newton.
+    pub fn cylinder_force(&self, pressure: &CylinderPressure) -> f32 {
+        let force =
+            pressure.head * BAR * self.config.head_area - pressure.rod * BAR * self.config.rod_area;
+
+        force * self.config.cylinders as f32
+    }
+
+    /// Gross mass in kilograms, before the tare offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The boom cylinder pressure.
+    /// * `lever` - The horizontal distance of the attachment to the boom pivot in meters.
+    ///
+    /// # Returns
+    ///
+    /// The gross mass, or `None` if the attachment is too close to the boom pivot.
+    pub fn gross(&self, pressure: &CylinderPressure, lever: f32) -> Option<f32> {
+        if lever < MIN_LEVER {
+            return None;
+        }
+
+        let moment = self.cylinder_force(pressure) * self.config.moment_arm;
+
+        Some(moment / (GRAVITY * lever))
+    }
+
+    /// Estimate the payload.
+    ///
+    /// # Arguments
+    ///
+    /// * `pressure` - The boom cylinder pressure.
+    /// * `lever` - The horizontal distance of the attachment to the boom pivot in meters.
+    pub fn estimate(&self, pressure: &CylinderPressure, lever: f32) -> LoadEstimate {
+        match self.gross(pressure, lever) {
+            Some(gross) => LoadEstimate::new(gross - self.tare),
+            None => LoadEstimate::invalid(),
+        }
+    }
+
+    /// Tare the model.
+    ///
+    /// The current gross mass becomes the zero payload.
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` if the model could not be tared.
+    pub fn tare(&mut self, pressure: &CylinderPressure, lever: f32) -> bool {
+        match self.gross(pressure, lever) {
+            Some(gross) => {
+                self.tare = gross;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Payload load weighing.
+///
+/// Combines the boom cylinder pressure with the boom and arm geometry and
+/// publishes a `LoadEstimate` signal on every pressure update. The estimate
+/// is invalid until both the boom and arm encoders reported. The model is
+/// tared with the `LoadTare` control command.
+pub struct LoadWeighing {
+    model: LoadModel,
+    convention: FrameConvention,
+    actor: Actor,
+    /// Segments with a known rotation.
+    segments: HashSet<String>,
+    /// Last boom cylinder pressure.
+    pressure: Option<CylinderPressure>,
+    command_tx: Option<CommandSender>,
+}
+
+impl LoadWeighing {
+    /// Horizontal distance of the attachment to the boom pivot in meters.
+    ///
+    /// Returns `None` until the boom and arm rotations are known.
+    fn lever(&self) -> Option<f32> {
+        if !self.segments.contains("boom") || !self.segments.contains("arm") {
+            return None;
+        }
+
+        let boom = self.actor.world_location("boom");
+        let attachment = self.actor.world_location("attachment");
+
+        Some((attachment.xy() - boom.xy()).norm() * ACTOR_UNIT)
+    }
+
+    fn on_signal(&mut self, signal: &Object) -> Option<LoadEstimate> {
+        match signal {
+            Object::Rotator(rotator) if rotator.reference == RotationReference::Relative => {
+                if let Some((segment, _)) = self.convention.joint_by_source(rotator.source) {
+                    self.actor.set_segment_rotation(segment, rotator.rotator);
+                    self.segments.insert(segment.to_string());
+                }
+
+                None
+            }
+            Object::CylinderPressure(pressure) if pressure.actuator == Actuator::Boom => {
+                self.pressure = Some(*pressure);
+
+                Some(match self.lever() {
+                    Some(lever) => self.model.estimate(pressure, lever),
+                    None => LoadEstimate::invalid(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn on_command(&mut self, command: &Object) {
+        if let Object::Control(Control::LoadTare) = command {
+            let tared = self
+                .pressure
+                .zip(self.lever())
+                .is_some_and(|(pressure, lever)| self.model.tare(&pressure, lever));
+
+            if tared {
+                info!("Load tared at {:.0}kg", self.model.tare_offset());
+            } else {
+                warn!("Load cannot be tared without pressure and geometry");
+            }
+        }
+    }
+}
+
+impl Service<LoadConfig> for LoadWeighing {
+    fn new(config: LoadConfig) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            model: LoadModel::new(config),
+            convention: FrameConvention::excavator(),
+            actor: robot_actor(),
+            segments: HashSet::new(),
+            pressure: None,
+            command_tx: None,
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("load weighing")
+    }
+
+    fn set_command_sender(&mut self, command_tx: CommandSender) {
+        self.command_tx = Some(command_tx);
+    }
+
+    async fn wait_io_pipe(&mut self, signal_tx: SignalSender, mut signal_rx: SignalReceiver) {
+        let Some(mut command_rx) = self.command_tx.as_ref().map(|tx| tx.subscribe()) else {
+            return std::future::pending().await;
+        };
+
+        loop {
+            tokio::select! {
+                Ok(command) = command_rx.recv() => self.on_command(&command),
+                signal = signal_rx.recv() => {
+                    match signal {
+                        Ok(signal) => {
+                            if let Some(estimate) = self.on_signal(&signal) {
+                                if let Err(e) = signal_tx.send(Object::LoadEstimate(estimate)) {
+                                    error!("Failed to send signal: {}", e);
+                                }
+                            }
+                        }
+                        Err(RecvError::Lagged(count)) => {
+                            warn!("Signal receiver lagged by {} objects", count);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{core::Rotator, math::EulerAngles};
+    use nalgebra::Rotation3;
+
+    fn config() -> LoadConfig {
+        LoadConfig {
+            head_area: 0.02,
+            rod_area: 0.01,
+            moment_arm: 0.5,
+            cylinders: 1,
+        }
+    }
+
+    #[test]
+    fn load_estimate() {
+        let mut model = LoadModel::new(config());
+
+        // (100 bar * 0.02 m² - 20 bar * 0.01 m²) * 0.5 m = 90 kNm
+        let pressure = CylinderPressure::new(Actuator::Boom, 100.0, 20.0);
+        assert!((model.cylinder_force(&pressure) - 180_000.0).abs() < 1.0);
+
+        let estimate = model.estimate(&pressure, 3.0);
+        assert!(estimate.valid);
+        assert!((estimate.mass - 90_000.0 / (GRAVITY * 3.0)).abs() < 0.5);
+
+        // The same moment further out is a lighter load.
+        assert!(model.estimate(&pressure, 6.0).mass < estimate.mass);
+        assert!(!model.estimate(&pressure, 0.1).valid);
+
+        let mut config = config();
+        config.cylinders = 2;
+        let estimate_double = LoadModel::new(config).estimate(&pressure, 3.0);
+        assert!((estimate_double.mass - 2.0 * estimate.mass).abs() < 0.5);
+
+        assert!(!model.tare(&pressure, 0.1));
+        assert_eq!(model.tare_offset(), 0.0);
+    }
+
+    #[test]
+    fn load_tare() {
+        let mut model = LoadModel::new(config());
+
+        let empty = CylinderPressure::new(Actuator::Boom, 100.0, 20.0);
+        assert!(model.tare(&empty, 3.0));
+        assert!((model.tare_offset() - 90_000.0 / (GRAVITY * 3.0)).abs() < 0.5);
+        assert!(model.estimate(&empty, 3.0).mass.abs() < 1e-3);
+
+        // 20 bar more on the piston head is 20 kNm more moment.
+        let loaded = CylinderPressure::new(Actuator::Boom, 120.0, 20.0);
+        let estimate = model.estimate(&loaded, 3.0);
+        assert!((estimate.mass - 20_000.0 / (GRAVITY * 3.0)).abs() < 0.5);
+    }
+
+    #[test]
+    fn load_weighing_geometry() {
+        let mut service = LoadWeighing::new(config());
+
+        let pressure = Object::CylinderPressure(CylinderPressure::new(Actuator::Boom, 100.0, 20.0));
+
+        // No geometry yet.
+        let estimate = service.on_signal(&pressure).unwrap();
+        assert!(!estimate.valid);
+        service.on_command(&Object::Control(Control::LoadTare));
+        assert_eq!(service.model.tare_offset(), 0.0);
+
+        // Boom level and arm straight, the attachment is about 8.2m out.
+        service.on_signal(&Object::Rotator(Rotator::relative(
+            0x6B,
+            Rotation3::from_pitch(0.0),
+        )));
+        service.on_signal(&Object::Rotator(Rotator::relative(
+            0x6C,
+            Rotation3::from_pitch(0.0),
+        )));
+        let lever = service.lever().unwrap();
+        assert!((lever - 8.2).abs() < 0.01, "{}", lever);
+
+        let estimate = service.on_signal(&pressure).unwrap();
+        assert!(estimate.valid);
+        assert!((estimate.mass - 90_000.0 / (GRAVITY * lever)).abs() < 0.5);
+
+        service.on_command(&Object::Control(Control::LoadTare));
+        let estimate = service.on_signal(&pressure).unwrap();
+        assert!(estimate.mass.abs() < 1e-3);
+    }
+}
@@ -0,0 +1,363 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    core::{Actuator, Motion, Object, OperationHours},
+    runtime::{Clock, CommandSender, Service, ServiceContext, SignalReceiver, SignalSender},
+};
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
+pub struct MeterConfig {
+    /// Operation hours file.
+    pub file: Option<PathBuf>,
+    /// Persist and publish interval in seconds.
+    #[serde(default = "MeterConfig::default_interval")]
+    pub interval: u64,
+}
+
+impl MeterConfig {
+    fn default_interval() -> u64 {
+        60
+    }
+}
+
+impl Default for MeterConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            interval: Self::default_interval(),
+        }
+    }
+}
+
+/// Operation hours meter.
+///
+/// Accumulates the engine running time and the active time of each
+/// actuator. An actuator is active while its last motion command is not
+/// neutral. The meter is driven by the caller, which makes it independent
+/// of the runtime.
+pub struct Meter {
+    hours: OperationHours,
+    /// Moment of the last accumulation.
+    last: Option<Instant>,
+    /// Engine is running.
+    engine_running: bool,
+    /// Active actuators.
+    active: HashSet<Actuator>,
+}
+
+impl Meter {
+    /// Construct a new meter starting from the given totals.
+    pub fn new(hours: OperationHours) -> Self {
+        Self {
+            hours,
+            last: None,
+            engine_running: false,
+            active: HashSet::new(),
+        }
+    }
+
+    /// Cumulative operation hours.
+    #[inline]
+    pub fn hours(&self) -> &OperationHours {
+        &self.hours
+    }
+
+    /// Accumulate the time since the last accumulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current moment in time.
+    pub fn accumulate(&mut self, now: Instant) {
+        let Some(last) = self.last.replace(now) else {
+            return;
+        };
+
+        if !self.engine_running {
+            return;
+        }
+
+        let elapsed = now.saturating_duration_since(last);
+
+        self.hours.engine += elapsed;
+        for actuator in &self.active {
+            *self.hours.actuator.entry(*actuator).or_default() += elapsed;
+        }
+    }
+
+    /// Record an object.
+    ///
+    /// The time up to now is accumulated with the state before the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - The engine signal or motion command.
+    /// * `now` - The current moment in time.
+    pub fn on_object(&mut self, object: &Object, now: Instant) {
+        match object {
+            Object::Engine(engine) => {
+                self.accumulate(now);
+                self.engine_running = engine.is_running();
+            }
+            Object::Motion(motion) => {
+                self.accumulate(now);
+                self.on_motion(motion);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_motion(&mut self, motion: &Motion) {
+        match motion {
+            Motion::StopAll | Motion::ResetAll => self.active.clear(),
+            Motion::ResumeAll => {}
+            Motion::Stop(actuators) => {
+                for actuator in actuators {
+                    self.active.remove(actuator);
+                }
+            }
+            Motion::StraightDrive(value) => {
+                for actuator in [Actuator::LimpLeft, Actuator::LimpRight] {
+                    self.set_active(actuator, *value);
+                }
+            }
+            Motion::Change(changes) => {
+                for change in changes {
+                    self.set_active(change.actuator, change.value);
+                }
+            }
+        }
+    }
+
+    fn set_active(&mut self, actuator: Actuator, value: i16) {
+        if value == Motion::POWER_NEUTRAL {
+            self.active.remove(&actuator);
+        } else {
+            self.active.insert(actuator);
+        }
+    }
+}
+
+/// Write the operation hours to a file.
+///
+/// The file is written to a temporary file first and then moved in place,
+/// so a crash during the write never loses the totals.
+async fn write_hours(path: &Path, hours: &OperationHours) -> std::io::Result<()> {
+    use crate::protocol::frame::Frame;
+
+    let path_tmp = path.with_extension("tmp");
+
+    tokio::fs::write(&path_tmp, Frame::from_packet(hours).as_ref()).await?;
+    tokio::fs::rename(&path_tmp, path).await
+}
+
+/// Read the operation hours from a file.
+async fn read_hours(path: &Path) -> std::io::Result<OperationHours> {
+    use crate::protocol::{Packetize, Stream};
+
+    let buffer = tokio::fs::read(path).await?;
+    let mut stream = Stream::new(buffer.as_slice());
+
+    let frame = stream.read_frame().await?;
+    if frame.message != OperationHours::MESSAGE_TYPE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "not an operation hours file",
+        ));
+    }
+
+    stream.recv_packet(frame.payload_length).await
+}
+
+/// Operation hours metering.
+///
+/// Meters the engine hours and actuator duty cycles. The totals are
+/// persisted and published on an interval and restored on startup, so they
+/// survive restarts.
+pub struct OperationMeter {
+    config: MeterConfig,
+    meter: Meter,
+    clock: Clock,
+    command_tx: Option<CommandSender>,
+}
+
+impl OperationMeter {
+    async fn persist(&mut self) {
+        self.meter.accumulate(self.clock.now());
+
+        if let Some(file) = &self.config.file {
+            if let Err(e) = write_hours(file, self.meter.hours()).await {
+                error!("Failed to write operation hours: {}", e);
+            }
+        }
+    }
+}
+
+impl Service<MeterConfig> for OperationMeter {
+    fn new(config: MeterConfig) -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            config,
+            meter: Meter::new(OperationHours::default()),
+            clock: Clock::system(),
+            command_tx: None,
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("operation meter")
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
+    }
+
+    fn set_command_sender(&mut self, command_tx: CommandSender) {
+        self.command_tx = Some(command_tx);
+    }
+
+    async fn setup(&mut self) {
+        let Some(file) = &self.config.file else {
+            return;
+        };
+
+        match read_hours(file).await {
+            Ok(hours) => {
+                info!("Restored operation hours: {}", hours);
+                self.meter = Meter::new(hours);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("No operation hours at {}", file.display());
+            }
+            Err(e) => warn!("Failed to restore operation hours: {}", e),
+        }
+    }
+
+    async fn teardown(&mut self) {
+        self.persist().await;
+    }
+
+    async fn wait_io_pipe(&mut self, signal_tx: SignalSender, mut signal_rx: SignalReceiver) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval.max(1)));
+        let mut command_rx = self.command_tx.as_ref().map(|tx| tx.subscribe());
+
+        loop {
+            tokio::select! {
+                Ok(command) = async { command_rx.as_mut().unwrap().recv().await }, if command_rx.is_some() => {
+                    self.meter.on_object(&command, self.clock.now());
+                }
+                signal = signal_rx.recv() => {
+                    match signal {
+                        Ok(signal) => self.meter.on_object(&signal, self.clock.now()),
+                        Err(RecvError::Lagged(count)) => {
+                            warn!("Signal receiver lagged by {} objects", count);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                _ = interval.tick() => {
+                    self.persist().await;
+
+                    let hours = self.meter.hours().clone();
+                    if let Err(e) = signal_tx.send(Object::OperationHours(hours)) {
+                        error!("Failed to send signal: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::core::Engine;
+
+    #[test]
+    fn meter_accumulate() {
+        let clock = Clock::mock();
+        let mut meter = Meter::new(OperationHours::default());
+
+        // Motion without a running engine is not metered.
+        meter.on_object(
+            &Object::Motion(Motion::new(Actuator::Boom, 12_000_i16)),
+            clock.now(),
+        );
+        clock.advance(Duration::from_secs(600));
+        meter.on_object(&Object::Engine(Engine::from_rpm(1_200)), clock.now());
+        assert_eq!(meter.hours().engine, Duration::ZERO);
+
+        clock.advance(Duration::from_secs(1_800));
+        meter.on_object(&Object::Motion(Motion::StopAll), clock.now());
+
+        clock.advance(Duration::from_secs(1_800));
+        meter.on_object(&Object::Motion(Motion::StraightDrive(-8_000)), clock.now());
+
+        clock.advance(Duration::from_secs(900));
+        meter.on_object(&Object::Engine(Engine::shutdown()), clock.now());
+
+        // Engine off, nothing accumulates.
+        clock.advance(Duration::from_secs(3_600));
+        meter.accumulate(clock.now());
+
+        let hours = meter.hours();
+        assert_eq!(hours.engine, Duration::from_secs(4_500));
+        assert_eq!(hours.engine_hours(), 1.25);
+        assert_eq!(hours.active(Actuator::Boom), Duration::from_secs(1_800));
+        assert_eq!(hours.active(Actuator::LimpLeft), Duration::from_secs(900));
+        assert_eq!(hours.active(Actuator::LimpRight), Duration::from_secs(900));
+        assert_eq!(hours.duty_cycle(Actuator::Boom), 0.4);
+        assert_eq!(hours.active(Actuator::Arm), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn meter_persist() {
+        let path = std::env::temp_dir().join(format!("glonax-hours-{}.bin", std::process::id()));
+
+        let clock = Clock::mock();
+
+        let mut service = OperationMeter::new(MeterConfig {
+            file: Some(path.clone()),
+            ..Default::default()
+        });
+        service.set_clock(clock.clone());
+        service.setup().await;
+
+        let engine = Object::Engine(Engine::from_rpm(1_500));
+        service.meter.on_object(&engine, clock.now());
+        service.meter.on_object(
+            &Object::Motion(Motion::new(Actuator::Arm, -6_000_i16)),
+            clock.now(),
+        );
+
+        clock.advance(Duration::from_secs(3_600));
+        service.teardown().await;
+
+        // Restart, the totals continue from the persisted hours.
+        let mut service = OperationMeter::new(MeterConfig {
+            file: Some(path.clone()),
+            ..Default::default()
+        });
+        service.set_clock(clock.clone());
+        service.setup().await;
+        assert_eq!(service.meter.hours().engine, Duration::from_secs(3_600));
+
+        service.meter.on_object(&engine, clock.now());
+        clock.advance(Duration::from_secs(1_800));
+        service.teardown().await;
+
+        let hours = read_hours(&path).await.unwrap();
+        assert_eq!(hours.engine, Duration::from_secs(5_400));
+        assert_eq!(hours.active(Actuator::Arm), Duration::from_secs(3_600));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,21 @@
+pub use acceptance::{AcceptanceConfig, AcceptanceProgram, AcceptanceResult, AcceptanceTest};
 pub use authority::{NetworkAuthority, NetworkConfig};
-pub use director::Director;
-pub use distributor::Distributor;
+pub use capability::{CapabilityConfig, CapabilityPublisher};
+pub use derivative::{RotatorDerivative, RotatorDerivativeConfig};
+pub use director::{Director, DirectorConfig};
+pub use distributor::{Distributor, DistributorConfig};
+pub use load::{LoadConfig, LoadModel, LoadWeighing};
+pub use meter::{Meter, MeterConfig, OperationMeter};
+pub use replay::{SignalReplay, SignalReplayConfig};
 pub use server::{UnixServer, UnixServerConfig};
 
+mod acceptance;
 mod authority;
+mod capability;
+mod derivative;
 mod director;
 mod distributor;
+mod load;
+mod meter;
+mod replay;
 mod server;
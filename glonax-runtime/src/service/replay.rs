@@ -0,0 +1,356 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    core::Object,
+    driver::FrameTrace,
+    net::Reassembler,
+    runtime::{Clock, J1939Unit, NetDriverContext, Service, ServiceContext, SignalSender},
+};
+
+use super::NetworkConfig;
+
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct SignalReplayConfig {
+    /// Trace file, recorded with `candump -l`.
+    pub path: PathBuf,
+    /// Replay speed multiplier.
+    ///
+    /// The trace is replayed as fast as possible if the speed is not positive.
+    #[serde(default = "SignalReplayConfig::default_speed")]
+    pub speed: f32,
+    /// Restart the replay at the end of the trace.
+    #[serde(default, rename = "loop")]
+    pub looping: bool,
+    /// J1939 network configuration.
+    ///
+    /// The drivers of the networks decode the recorded frames.
+    #[serde(skip)]
+    pub network: Vec<NetworkConfig>,
+}
+
+impl SignalReplayConfig {
+    fn default_speed() -> f32 {
+        1.0
+    }
+}
+
+struct ReplayDriver {
+    driver: Box<dyn J1939Unit>,
+    context: NetDriverContext,
+}
+
+/// Signal replay.
+///
+/// Feeds the frames of a recorded trace through the network drivers and
+/// publishes the decoded signals, just as the network authority does with
+/// live frames. The frames are replayed at their recorded offset, divided by
+/// the configured speed. The replay stops at the end of the trace, unless it
+/// is configured to loop.
+pub struct SignalReplay {
+    /// Replay configuration.
+    config: SignalReplayConfig,
+    /// Network drivers.
+    ///
+    /// The drivers are not shared, the mutex only makes the service `Sync`.
+    drivers: Mutex<Vec<ReplayDriver>>,
+    /// Recorded trace.
+    trace: Arc<FrameTrace>,
+    /// Transport protocol reassembly.
+    transport: Reassembler,
+    /// Number of completed replays.
+    iteration: usize,
+    /// Runtime clock.
+    clock: Clock,
+}
+
+impl SignalReplay {
+    /// Replay the trace.
+    ///
+    /// Use this method to replay a trace that is not read from file.
+    pub fn with_trace(mut self, trace: FrameTrace) -> Self {
+        self.trace = Arc::new(trace);
+        self
+    }
+
+    /// Offset of a frame on the replay timeline.
+    fn replay_offset(&self, offset: Duration) -> Option<Duration> {
+        if self.config.speed > 0.0 && self.config.speed.is_finite() {
+            Some(offset.div_f32(self.config.speed))
+        } else {
+            None
+        }
+    }
+
+    /// Decode a recorded frame into signals.
+    ///
+    /// The first driver that accepts the frame consumes it.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The recorded frame.
+    ///
+    /// # Returns
+    ///
+    /// The decoded signals.
+    fn decode(&mut self, frame: &j1939::Frame) -> Vec<Object> {
+        let message = self.transport.feed(frame, self.clock.now());
+
+        for item in self.drivers.get_mut().unwrap().iter_mut() {
+            let mut rx_queue = Vec::new();
+
+            let result = match &message {
+                Some(message) => {
+                    item.driver
+                        .try_recv_message(&mut item.context, message, &mut rx_queue)
+                }
+                None => item
+                    .driver
+                    .try_recv(&mut item.context, frame, &mut rx_queue),
+            };
+
+            if let Err(e) = result {
+                error!("{}: {}", item.driver.name(), e);
+            }
+
+            if !rx_queue.is_empty() {
+                return rx_queue;
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Replay the trace once.
+    async fn replay(&mut self, signal_tx: &SignalSender) {
+        let start = tokio::time::Instant::now();
+        let trace = self.trace.clone();
+
+        for (offset, frame) in trace.iter() {
+            if let Some(offset) = self.replay_offset(*offset) {
+                tokio::time::sleep_until(start + offset).await;
+            }
+
+            for object in self.decode(frame) {
+                if let Err(e) = signal_tx.send(object) {
+                    error!("Failed to send signal: {}", e);
+                }
+            }
+        }
+    }
+}
+
+impl Service<SignalReplayConfig> for SignalReplay {
+    fn new(config: SignalReplayConfig) -> Self
+    where
+        Self: Sized,
+    {
+        let mut drivers = Vec::new();
+
+        for network in &config.network {
+            for driver_config in &network.driver {
+                let driver = crate::driver::net::driver_factory(
+                    &driver_config.vendor,
+                    &driver_config.product,
+                    &network.interface,
+                    driver_config.da,
+                    driver_config.sa.unwrap_or(network.address),
+                );
+
+                match driver {
+                    Some(driver) => drivers.push(ReplayDriver {
+                        driver,
+                        context: NetDriverContext::default(),
+                    }),
+                    None => error!(
+                        "Unknown driver {} {}",
+                        driver_config.vendor, driver_config.product
+                    ),
+                }
+            }
+        }
+
+        Self {
+            config,
+            drivers: Mutex::new(drivers),
+            trace: Arc::new(FrameTrace::default()),
+            transport: Reassembler::default(),
+            iteration: 0,
+            clock: Clock::system(),
+        }
+    }
+
+    fn ctx(&self) -> ServiceContext {
+        ServiceContext::new("signal replay")
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        for item in self.drivers.get_mut().unwrap().iter_mut() {
+            item.context.set_clock(clock.clone());
+        }
+
+        self.clock = clock;
+    }
+
+    async fn setup(&mut self) {
+        if !self.trace.is_empty() {
+            return;
+        }
+
+        match FrameTrace::from_file(&self.config.path) {
+            Ok(trace) => {
+                info!(
+                    "Replay {} frames over {:.1}s from {}",
+                    trace.len(),
+                    trace.duration().as_secs_f32(),
+                    self.config.path.display()
+                );
+
+                self.trace = Arc::new(trace);
+            }
+            Err(e) => error!("Failed to read trace {}: {}", self.config.path.display(), e),
+        }
+    }
+
+    async fn wait_io_pub(&mut self, signal_tx: SignalSender) {
+        if self.trace.is_empty() || (self.iteration > 0 && !self.config.looping) {
+            return std::future::pending().await;
+        }
+
+        self.replay(&signal_tx).await;
+        self.iteration += 1;
+
+        if self.config.looping {
+            debug!("Restart replay after {} iterations", self.iteration);
+        } else {
+            info!("Replay finished");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use j1939::Frame;
+
+    use super::*;
+    use crate::driver::net::encoder::ProcessDataMessage;
+
+    const NETWORK_CONFIG: &str = r#"
+        interface = "vcan0"
+        address = 0x27
+        name = { manufacturer_code = 0x717, function_instance = 6, ecu_instance = 0, function = 0x1C, vehicle_system = 2, vehicle_system_instance = 0, industry_group = 0 }
+        driver = [
+            { da = 0x6A, vendor = "kübler", product = "encoder" },
+            { da = 0x6B, vendor = "kübler", product = "encoder" },
+            { da = 0xFF, vendor = "j1939", product = "dm1" },
+        ]
+    "#;
+
+    fn config(speed: f32, looping: bool) -> SignalReplayConfig {
+        SignalReplayConfig {
+            path: PathBuf::new(),
+            speed,
+            looping,
+            network: vec![toml::from_str(NETWORK_CONFIG).unwrap()],
+        }
+    }
+
+    /// Record a handful of frames as they appear on the network.
+    fn record() -> String {
+        let dm1 = j1939::FrameBuilder::new(
+            j1939::IdBuilder::from_pgn(j1939::PGN::DiagnosticMessage1)
+                .sa(0x00)
+                .build(),
+        )
+        .copy_from_slice(&[0x04, 0xFF, 0x6E, 0x00, 0x00, 0x01, 0xFF, 0xFF])
+        .build();
+
+        let frames: Vec<Frame> = [(0x6A, 1_000), (0x6B, 2_000), (0x6A, 1_010)]
+            .into_iter()
+            .flat_map(|(sa, position)| ProcessDataMessage::from_position(sa, position).to_frame())
+            .chain([dm1])
+            .collect();
+
+        frames
+            .iter()
+            .enumerate()
+            .map(|(idx, frame)| {
+                let data: String = frame.pdu().iter().map(|b| format!("{:02X}", b)).collect();
+                format!(
+                    "(1718010000.{:06}) vcan0 {:08X}#{}\n",
+                    idx * 10_000,
+                    frame.id().as_raw(),
+                    data
+                )
+            })
+            .collect()
+    }
+
+    /// Decode the frames directly with the drivers.
+    fn expected(trace: &FrameTrace) -> Vec<Object> {
+        let mut replay = SignalReplay::new(config(0.0, false));
+
+        trace
+            .iter()
+            .flat_map(|(_, frame)| replay.decode(frame))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replay_in_order() {
+        let trace = FrameTrace::parse(&record()).unwrap();
+        let expected = expected(&trace);
+        assert_eq!(expected.len(), 4);
+        assert!(matches!(expected[3], Object::DiagnosticCodes(_)));
+
+        let (signal_tx, mut signal_rx) = tokio::sync::broadcast::channel(16);
+
+        let mut replay = SignalReplay::new(config(10.0, false)).with_trace(trace);
+        replay.setup().await;
+
+        let start = std::time::Instant::now();
+        replay.wait_io_pub(signal_tx).await;
+        assert!(start.elapsed() >= Duration::from_millis(3));
+
+        let mut received = vec![];
+        while let Ok(object) = signal_rx.try_recv() {
+            received.push(object);
+        }
+
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn replay_loop() {
+        let trace = FrameTrace::parse(&record()).unwrap();
+        let expected = expected(&trace);
+
+        let (signal_tx, mut signal_rx) = tokio::sync::broadcast::channel(16);
+
+        let mut replay = SignalReplay::new(config(0.0, true)).with_trace(trace);
+        replay.wait_io_pub(signal_tx.clone()).await;
+        replay.wait_io_pub(signal_tx.clone()).await;
+
+        let mut received = vec![];
+        while let Ok(object) = signal_rx.try_recv() {
+            received.push(object);
+        }
+
+        assert_eq!(received.len(), 2 * expected.len());
+        assert_eq!(&received[..expected.len()], &expected[..]);
+        assert_eq!(&received[expected.len()..], &expected[..]);
+
+        // Without looping the replay stops at the end of the trace.
+        let trace = FrameTrace::parse(&record()).unwrap();
+        let mut replay = SignalReplay::new(config(0.0, false)).with_trace(trace);
+        replay.wait_io_pub(signal_tx.clone()).await;
+        assert_eq!(signal_rx.len(), expected.len());
+
+        let result =
+            tokio::time::timeout(Duration::from_millis(10), replay.wait_io_pub(signal_tx)).await;
+        assert!(result.is_err());
+    }
+}
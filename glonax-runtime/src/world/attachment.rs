@@ -0,0 +1,284 @@
+use nalgebra::{Point3, Vector3};
+
+use super::{Actor, Cuboid, ShapeConfig};
+
+/// Name of the effector segment.
+///
+/// The effector segment is attached to the attachment segment and locates
+/// the working point of the active attachment, such as the bucket tip.
+pub const EFFECTOR_SEGMENT: &str = "effector";
+
+/// Attachment configuration.
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct AttachmentConfig {
+    /// Attachment identifier.
+    pub id: u8,
+    /// Attachment name.
+    pub name: String,
+    /// Effector location in the attachment segment frame.
+    pub effector: [f32; 3],
+    /// Collision shape in the attachment segment frame.
+    pub shape: ShapeConfig,
+}
+
+impl AttachmentConfig {
+    /// Build the attachment.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if any of the shape dimensions is not positive.
+    pub fn build(&self) -> std::io::Result<Attachment> {
+        Ok(Attachment {
+            id: self.id,
+            name: self.name.clone(),
+            effector: self.effector.into(),
+            shape: Cuboid::new(self.shape.half_extents.into())?,
+            location: self.shape.location.into(),
+        })
+    }
+}
+
+/// Machine attachment.
+///
+/// An attachment defines the effector and the collision shape that move
+/// along with the attachment segment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attachment {
+    /// Attachment identifier.
+    id: u8,
+    /// Attachment name.
+    name: String,
+    /// Effector location in the attachment segment frame.
+    effector: Vector3<f32>,
+    /// Collision shape.
+    shape: Cuboid,
+    /// Collision shape center in the attachment segment frame.
+    location: Point3<f32>,
+}
+
+impl Attachment {
+    /// Attachment identifier.
+    #[inline]
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Attachment name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Effector location in the attachment segment frame.
+    #[inline]
+    pub fn effector(&self) -> Vector3<f32> {
+        self.effector
+    }
+
+    /// Collision shape.
+    #[inline]
+    pub fn shape(&self) -> &Cuboid {
+        &self.shape
+    }
+
+    /// Collision shape center in the attachment segment frame.
+    #[inline]
+    pub fn location(&self) -> Point3<f32> {
+        self.location
+    }
+
+    /// Apply the attachment geometry to an actor.
+    ///
+    /// Moves the effector segment of the actor to the effector of this
+    /// attachment.
+    pub fn apply(&self, actor: &mut Actor) {
+        actor.set_segment_location(EFFECTOR_SEGMENT, self.effector);
+    }
+}
+
+impl std::fmt::Display for Attachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.id)
+    }
+}
+
+/// Attachment registry.
+///
+/// Holds the attachments available to the machine and the attachment that
+/// is currently mounted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttachmentRegistry {
+    /// Registered attachments.
+    attachments: Vec<Attachment>,
+    /// Identifier of the active attachment.
+    active: Option<u8>,
+}
+
+impl AttachmentRegistry {
+    /// Construct a registry from configuration.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if an attachment is invalid or an identifier is used
+    /// more than once.
+    pub fn from_config(config: &[AttachmentConfig]) -> std::io::Result<Self> {
+        let mut registry = Self::default();
+
+        for attachment in config {
+            registry.register(attachment.build()?)?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Register an attachment.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the attachment identifier is already registered.
+    pub fn register(&mut self, attachment: Attachment) -> std::io::Result<()> {
+        if self.get(attachment.id).is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("attachment {} is already registered", attachment.id),
+            ));
+        }
+
+        self.attachments.push(attachment);
+
+        Ok(())
+    }
+
+    /// Retrieve attachment by identifier.
+    pub fn get(&self, id: u8) -> Option<&Attachment> {
+        self.attachments
+            .iter()
+            .find(|attachment| attachment.id == id)
+    }
+
+    /// Active attachment, if any.
+    pub fn active(&self) -> Option<&Attachment> {
+        self.active.and_then(|id| self.get(id))
+    }
+
+    /// Select the active attachment.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The attachment identifier.
+    ///
+    /// # Returns
+    ///
+    /// The selected attachment, or an error if the attachment is not
+    /// registered.
+    pub fn select(&mut self, id: u8) -> std::io::Result<&Attachment> {
+        if self.get(id).is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("attachment {} is not registered", id),
+            ));
+        }
+
+        self.active = Some(id);
+
+        Ok(self.get(id).unwrap())
+    }
+
+    /// Iterate over the registered attachments.
+    pub fn iter(&self) -> impl Iterator<Item = &Attachment> {
+        self.attachments.iter()
+    }
+
+    /// Number of registered attachments.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.attachments.len()
+    }
+
+    /// Test if no attachments are registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.attachments.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::world::{ActorBuilder, ActorSegment, CollisionConfig};
+
+    fn registry() -> AttachmentRegistry {
+        AttachmentRegistry::from_config(&[
+            AttachmentConfig {
+                id: 1,
+                name: "bucket".to_string(),
+                effector: [1.5, 0.0, 0.0],
+                shape: ShapeConfig {
+                    half_extents: [0.75, 1.04, 0.25],
+                    location: [0.75, 0.0, 0.375],
+                },
+            },
+            AttachmentConfig {
+                id: 2,
+                name: "hammer".to_string(),
+                effector: [0.0, 0.0, -2.0],
+                shape: ShapeConfig {
+                    half_extents: [0.3, 0.3, 1.0],
+                    location: [0.0, 0.0, -1.0],
+                },
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn attachment_select_effector() {
+        let mut actor = ActorBuilder::new("excavator")
+            .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 1.5)))
+            .attach_segment("attachment", ActorSegment::new(Vector3::new(4.0, 0.0, 1.0)))
+            .attach_segment(EFFECTOR_SEGMENT, ActorSegment::new(Vector3::zeros()))
+            .build();
+
+        let mut registry = registry();
+        assert_eq!(registry.len(), 2);
+        assert!(registry.active().is_none());
+
+        registry.select(1).unwrap().apply(&mut actor);
+        assert_eq!(registry.active().unwrap().name(), "bucket");
+        assert_eq!(
+            actor.world_location(EFFECTOR_SEGMENT),
+            Point3::new(5.5, 0.0, 2.5)
+        );
+
+        registry.select(2).unwrap().apply(&mut actor);
+        assert_eq!(registry.active().unwrap().name(), "hammer");
+        assert_eq!(
+            actor.world_location(EFFECTOR_SEGMENT),
+            Point3::new(4.0, 0.0, 0.5)
+        );
+
+        let mut geometry = CollisionConfig::default().build().unwrap();
+        let clearance = geometry.ground_clearance(&actor, "attachment");
+        geometry.set_attachment(registry.active().unwrap());
+        assert!((geometry.ground_clearance(&actor, "attachment") - 0.5).abs() < 1e-5);
+        assert!(geometry.ground_clearance(&actor, "attachment") < clearance);
+    }
+
+    #[test]
+    fn attachment_registry_invalid() {
+        let mut registry = registry();
+
+        assert_eq!(
+            registry.select(7).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+        assert!(registry.active().is_none());
+
+        let duplicate = registry.get(1).unwrap().clone();
+        assert_eq!(
+            registry.register(duplicate).unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use nalgebra::{Point3, Vector3};
+
+use super::{Actor, Attachment};
+
+/// Axis aligned box shape.
+///
+/// The box is defined by its half extents around its center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cuboid {
+    half_extents: Vector3<f32>,
+}
+
+impl Cuboid {
+    /// Construct a new box shape.
+    ///
+    /// # Arguments
+    ///
+    /// * `half_extents` - The half extents of the box.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if any of the half extents is not positive.
+    pub fn new(half_extents: Vector3<f32>) -> std::io::Result<Self> {
+        if !half_extents.iter().all(|v| v.is_finite() && *v > 0.0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "box dimensions must be positive: ({}, {}, {})",
+                    half_extents.x, half_extents.y, half_extents.z
+                ),
+            ));
+        }
+
+        Ok(Self { half_extents })
+    }
+
+    /// Half extents of the box.
+    #[inline]
+    pub fn half_extents(&self) -> Vector3<f32> {
+        self.half_extents
+    }
+
+    /// Corners of the box around a center.
+    pub fn corners(&self, center: Point3<f32>) -> [Point3<f32>; 8] {
+        let h = self.half_extents;
+
+        std::array::from_fn(|i| {
+            let sign = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+            center + Vector3::new(sign(1) * h.x, sign(2) * h.y, sign(4) * h.z)
+        })
+    }
+}
+
+/// Collision shape configuration.
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct ShapeConfig {
+    /// Half extents of the box.
+    pub half_extents: [f32; 3],
+    /// Location of the box center.
+    ///
+    /// The ground is located in the world frame, the bucket in the frame of
+    /// the segment it is attached to.
+    pub location: [f32; 3],
+}
+
+/// Collision geometry configuration.
+///
+/// Each machine and attachment defines its own collision shapes. The
+/// defaults describe a flat ground with its surface at zero height and a
+/// standard digging bucket.
+#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq)]
+pub struct CollisionConfig {
+    /// Ground plane.
+    #[serde(default = "CollisionConfig::default_ground")]
+    pub ground: ShapeConfig,
+    /// Bucket geometry.
+    #[serde(default = "CollisionConfig::default_bucket")]
+    pub bucket: ShapeConfig,
+}
+
+impl CollisionConfig {
+    fn default_ground() -> ShapeConfig {
+        ShapeConfig {
+            half_extents: [10.0, 10.0, 1.0],
+            location: [0.0, 0.0, -1.0],
+        }
+    }
+
+    fn default_bucket() -> ShapeConfig {
+        ShapeConfig {
+            half_extents: [0.75, 1.04, 0.25],
+            location: [0.75, 0.0, 0.375],
+        }
+    }
+
+    /// Build the collision geometry.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if any of the shape dimensions is not positive.
+    pub fn build(&self) -> std::io::Result<CollisionGeometry> {
+        Ok(CollisionGeometry {
+            ground: Cuboid::new(self.ground.half_extents.into())?,
+            ground_location: self.ground.location.into(),
+            bucket: Cuboid::new(self.bucket.half_extents.into())?,
+            bucket_location: self.bucket.location.into(),
+        })
+    }
+}
+
+impl Default for CollisionConfig {
+    fn default() -> Self {
+        Self {
+            ground: Self::default_ground(),
+            bucket: Self::default_bucket(),
+        }
+    }
+}
+
+/// Ground plane and bucket collision shapes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollisionGeometry {
+    /// Ground shape.
+    ground: Cuboid,
+    /// Ground center in the world frame.
+    ground_location: Point3<f32>,
+    /// Bucket shape.
+    bucket: Cuboid,
+    /// Bucket center in the segment frame.
+    bucket_location: Point3<f32>,
+}
+
+impl Default for CollisionGeometry {
+    fn default() -> Self {
+        CollisionConfig::default().build().unwrap()
+    }
+}
+
+impl CollisionGeometry {
+    /// Height of the ground surface.
+    #[inline]
+    pub fn ground_height(&self) -> f32 {
+        self.ground_location.z + self.ground.half_extents().z
+    }
+
+    /// Replace the bucket shape by the shape of an attachment.
+    pub fn set_attachment(&mut self, attachment: &Attachment) {
+        self.bucket = *attachment.shape();
+        self.bucket_location = attachment.location();
+    }
+
+    /// Bucket corners in the world frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor` - The actor carrying the bucket.
+    /// * `segment` - The segment the bucket is attached to.
+    pub fn bucket_corners(&self, actor: &Actor, segment: &str) -> [Point3<f32>; 8] {
+        let transform = actor.world_transformation(segment);
+
+        self.bucket
+            .corners(self.bucket_location)
+            .map(|corner| transform.transform_point(&corner))
+    }
+
+    /// Ground clearance of the bucket.
+    ///
+    /// The clearance is the height of the lowest bucket corner above the
+    /// ground surface. A negative clearance means the bucket is below the
+    /// ground surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor` - The actor carrying the bucket.
+    /// * `segment` - The segment the bucket is attached to.
+    pub fn ground_clearance(&self, actor: &Actor, segment: &str) -> f32 {
+        let lowest = self
+            .bucket_corners(actor, segment)
+            .iter()
+            .map(|corner| corner.z)
+            .fold(f32::INFINITY, f32::min);
+
+        lowest - self.ground_height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::world::{ActorBuilder, ActorSegment};
+
+    fn actor() -> Actor {
+        ActorBuilder::new("excavator")
+            .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 1.5)))
+            .attach_segment("attachment", ActorSegment::new(Vector3::new(4.0, 0.0, 1.0)))
+            .build()
+    }
+
+    #[test]
+    fn ground_clearance_bucket_size() {
+        let actor = actor();
+
+        let geometry = CollisionConfig::default().build().unwrap();
+        assert_eq!(geometry.ground_height(), 0.0);
+        // Attachment at 2.5m, bucket center 0.375m above, half height 0.25m.
+        let clearance = geometry.ground_clearance(&actor, "attachment");
+        assert!((clearance - 2.625).abs() < 1e-5, "{}", clearance);
+
+        let config = CollisionConfig {
+            bucket: ShapeConfig {
+                half_extents: [1.0, 1.2, 0.6],
+                location: [1.0, 0.0, -0.4],
+            },
+            ..Default::default()
+        };
+        let geometry = config.build().unwrap();
+        let clearance = geometry.ground_clearance(&actor, "attachment");
+        assert!((clearance - 1.5).abs() < 1e-5, "{}", clearance);
+    }
+
+    #[test]
+    fn ground_clearance_below_ground() {
+        let mut actor = actor();
+        actor.set_location(Vector3::new(0.0, 0.0, -2.5));
+
+        let config = CollisionConfig {
+            ground: ShapeConfig {
+                half_extents: [10.0, 10.0, 0.5],
+                location: [0.0, 0.0, 0.0],
+            },
+            ..Default::default()
+        };
+        let geometry = config.build().unwrap();
+        assert_eq!(geometry.ground_height(), 0.5);
+        assert!(geometry.ground_clearance(&actor, "attachment") < 0.0);
+    }
+
+    #[test]
+    fn shape_dimensions_positive() {
+        assert!(Cuboid::new(Vector3::new(1.0, 0.0, 1.0)).is_err());
+        assert!(Cuboid::new(Vector3::new(1.0, 1.0, -0.5)).is_err());
+        assert!(Cuboid::new(Vector3::new(f32::NAN, 1.0, 1.0)).is_err());
+
+        let config = CollisionConfig {
+            bucket: ShapeConfig {
+                half_extents: [0.75, 0.0, 0.25],
+                location: [0.0; 3],
+            },
+            ..Default::default()
+        };
+        assert!(config.build().is_err());
+    }
+
+    #[test]
+    fn cuboid_corners() {
+        let cuboid = Cuboid::new(Vector3::new(1.0, 2.0, 3.0)).unwrap();
+        let corners = cuboid.corners(Point3::new(0.0, 0.0, 1.0));
+
+        assert!(corners.contains(&Point3::new(-1.0, -2.0, -2.0)));
+        assert!(corners.contains(&Point3::new(1.0, 2.0, 4.0)));
+        assert_eq!(
+            corners.iter().map(|c| c.z).fold(f32::INFINITY, f32::min),
+            -2.0
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Frame conventions.
+//!
+//! All world coordinates use a right handed frame with the X axis pointing
+//! forward, the Y axis pointing left and the Z axis pointing up. Rotations
+//! follow the right hand rule around the joint axis.
+//!
+//! A frame convention describes for each joint the axis of rotation in the
+//! parent segment frame, the zero reference of the joint and the direction in
+//! which the encoder counts. This keeps the mapping from encoder reading to
+//! segment rotation in a single place.
+
+use nalgebra::{UnitVector3, Vector3};
+
+use crate::driver::EncoderConverter;
+
+/// Joint reference.
+///
+/// Describes how an encoder reading maps onto a segment rotation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointReference {
+    /// Encoder source address.
+    pub source: u8,
+    /// Axis of rotation in the parent segment frame.
+    pub axis: UnitVector3<f32>,
+    /// Encoder angle in radians at which the joint is at its zero reference.
+    pub offset: f32,
+    /// Whether the encoder counts opposite to the right hand rule.
+    pub invert: bool,
+}
+
+impl JointReference {
+    /// Construct a new joint reference.
+    pub fn new(source: u8, axis: UnitVector3<f32>, offset: f32, invert: bool) -> Self {
+        Self {
+            source,
+            axis,
+            offset,
+            invert,
+        }
+    }
+}
+
+/// Frame convention.
+///
+/// Describes the joints of an actor and how their encoder readings
+/// map onto segment rotations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameConvention {
+    /// Convention name.
+    name: String,
+    /// Joint references by segment name.
+    joints: Vec<(String, JointReference)>,
+}
+
+impl FrameConvention {
+    /// Construct a new frame convention without joints.
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            joints: Vec::new(),
+        }
+    }
+
+    /// Attach joint reference to a segment.
+    pub fn with_joint(mut self, segment: impl ToString, joint: JointReference) -> Self {
+        self.joints.push((segment.to_string(), joint));
+        self
+    }
+
+    /// Excavator frame convention.
+    ///
+    /// The frame rotates around the Z axis relative to the undercarriage. The
+    /// boom, arm and attachment rotate around the Y axis of their parent. The
+    /// zero reference of the frame is aligned with the undercarriage, the zero
+    /// reference of the boom, arm and attachment is parallel to the X axis of
+    /// their parent. The boom encoder reads 60 degrees at its zero reference.
+    pub fn excavator() -> Self {
+        Self::new("excavator")
+            .with_joint(
+                "frame",
+                JointReference::new(0x6A, Vector3::z_axis(), 0.0, true),
+            )
+            .with_joint(
+                "boom",
+                JointReference::new(0x6B, Vector3::y_axis(), 60_f32.to_radians(), true),
+            )
+            .with_joint(
+                "arm",
+                JointReference::new(0x6C, Vector3::y_axis(), 0.0, true),
+            )
+            .with_joint(
+                "attachment",
+                JointReference::new(0x6D, Vector3::y_axis(), 0.0, true),
+            )
+    }
+
+    /// Convention name.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Iterate over the segment names and joint references.
+    pub fn joints(&self) -> impl Iterator<Item = (&str, &JointReference)> {
+        self.joints
+            .iter()
+            .map(|(name, joint)| (name.as_str(), joint))
+    }
+
+    /// Retrieve joint reference by segment name.
+    pub fn joint(&self, segment: impl ToString) -> Option<&JointReference> {
+        self.joints
+            .iter()
+            .find(|(name, _)| name == &segment.to_string())
+            .map(|(_, joint)| joint)
+    }
+
+    /// Retrieve segment name and joint reference by encoder source.
+    pub fn joint_by_source(&self, source: u8) -> Option<(&str, &JointReference)> {
+        self.joints
+            .iter()
+            .find(|(_, joint)| joint.source == source)
+            .map(|(name, joint)| (name.as_str(), joint))
+    }
+
+    /// Construct an encoder converter for an encoder source.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The encoder source address.
+    /// * `factor` - The number of encoder units per radian.
+    ///
+    /// # Returns
+    ///
+    /// The encoder converter, or `None` if the source is not part of the convention.
+    pub fn encoder_converter(&self, source: u8, factor: f32) -> Option<EncoderConverter> {
+        self.joint_by_source(source)
+            .map(|(_, joint)| EncoderConverter::new(factor, joint.offset, joint.invert, joint.axis))
+    }
+}
+
+impl Default for FrameConvention {
+    fn default() -> Self {
+        Self::excavator()
+    }
+}
+
+impl std::fmt::Display for FrameConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (X forward, Y left, Z up)", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{ActorBuilder, ActorSegment};
+
+    #[test]
+    fn test_excavator_convention() {
+        let convention = FrameConvention::excavator();
+
+        let (segment, joint) = convention.joint_by_source(0x6B).unwrap();
+        assert_eq!(segment, "boom");
+        assert_eq!(joint.axis, Vector3::y_axis());
+
+        assert!(convention.joint("bucket").is_none());
+        assert!(convention.encoder_converter(0x7A, 1000.0).is_none());
+    }
+
+    #[test]
+    fn test_encoder_world_orientation() {
+        let convention = FrameConvention::excavator();
+
+        let mut actor = ActorBuilder::new("excavator")
+            .attach_segment("undercarriage", ActorSegment::new(Vector3::zeros()))
+            .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 100.0)))
+            .attach_segment("boom", ActorSegment::new(Vector3::new(0.0, 0.0, 30.0)))
+            .attach_segment("arm", ActorSegment::new(Vector3::new(500.0, 0.0, 0.0)))
+            .build();
+
+        // Boom encoder at its zero reference, boom is level.
+        let converter = convention.encoder_converter(0x6B, 1000.0).unwrap();
+        actor.set_segment_rotation("boom", converter.to_rotation(60_f32.to_radians() * 1000.0));
+
+        let arm = actor.world_location("arm");
+        assert!((arm.x - 500.0).abs() < 1e-3);
+        assert!((arm.z - 130.0).abs() < 1e-3);
+
+        // Frame encoder at a quarter turn, boom points to the right.
+        let converter = convention.encoder_converter(0x6A, 1000.0).unwrap();
+        actor.set_segment_rotation(
+            "frame",
+            converter.to_rotation(std::f32::consts::FRAC_PI_2 * 1000.0),
+        );
+
+        let arm = actor.world_location("arm");
+        assert!(arm.x.abs() < 1e-3);
+        assert!((arm.y + 500.0).abs() < 1e-3);
+        assert!((arm.z - 130.0).abs() < 1e-3);
+    }
+}
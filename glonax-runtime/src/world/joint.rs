@@ -0,0 +1,181 @@
+use nalgebra::{Rotation3, UnitQuaternion, UnitVector3, Vector3};
+
+use crate::core::codec::{DecodeError, Decoder, Encoder};
+
+/// Joint type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointType {
+    /// Joint that rotates without limits.
+    Continuous,
+    /// Joint that rotates between a minimum and maximum angle in radians.
+    Revolute { min: f32, max: f32 },
+}
+
+/// Joint descriptor.
+///
+/// Describes how a segment rotates relative to its parent. The joint angle is
+/// the rotation of the segment around the joint axis, in the range -π to π.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointDescriptor {
+    /// Joint type.
+    joint_type: JointType,
+    /// Axis of rotation in the parent segment frame.
+    axis: UnitVector3<f32>,
+}
+
+impl JointDescriptor {
+    /// Construct a new continuous joint.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The axis of rotation in the parent segment frame.
+    pub fn continuous(axis: UnitVector3<f32>) -> Self {
+        Self {
+            joint_type: JointType::Continuous,
+            axis,
+        }
+    }
+
+    /// Construct a new revolute joint.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The axis of rotation in the parent segment frame.
+    /// * `min` - The minimum joint angle in radians.
+    /// * `max` - The maximum joint angle in radians.
+    ///
+    /// # Returns
+    ///
+    /// Returns an error if the limits are not finite or the minimum exceeds
+    /// the maximum.
+    pub fn revolute(axis: UnitVector3<f32>, min: f32, max: f32) -> std::io::Result<Self> {
+        if !(min.is_finite() && max.is_finite() && min <= max) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid joint limits: {} to {}", min, max),
+            ));
+        }
+
+        Ok(Self {
+            joint_type: JointType::Revolute { min, max },
+            axis,
+        })
+    }
+
+    /// Joint type.
+    #[inline]
+    pub fn joint_type(&self) -> JointType {
+        self.joint_type
+    }
+
+    /// Axis of rotation in the parent segment frame.
+    #[inline]
+    pub fn axis(&self) -> UnitVector3<f32> {
+        self.axis
+    }
+
+    /// Joint angle of a segment rotation.
+    ///
+    /// Only the rotation around the joint axis is taken into account.
+    pub fn angle(&self, rotation: &Rotation3<f32>) -> f32 {
+        crate::math::twist_angle(&UnitQuaternion::from_rotation_matrix(rotation), &self.axis)
+    }
+
+    /// Test if the joint angle is within the joint limits.
+    pub fn contains(&self, angle: f32) -> bool {
+        match self.joint_type {
+            JointType::Continuous => true,
+            JointType::Revolute { min, max } => (min..=max).contains(&angle),
+        }
+    }
+
+    /// Clamp the joint angle to the joint limits.
+    pub fn clamp(&self, angle: f32) -> f32 {
+        match self.joint_type {
+            JointType::Continuous => angle,
+            JointType::Revolute { min, max } => angle.clamp(min, max),
+        }
+    }
+
+    /// Encode an optional joint.
+    ///
+    /// The joint kind is followed by the axis, and the limits for a revolute
+    /// joint.
+    pub(super) fn encode(joint: Option<&Self>, buf: &mut Encoder) {
+        let Some(joint) = joint else {
+            buf.put_u8(0);
+            return;
+        };
+
+        match joint.joint_type {
+            JointType::Continuous => buf.put_u8(1),
+            JointType::Revolute { .. } => buf.put_u8(2),
+        }
+
+        buf.put_f32(joint.axis.x);
+        buf.put_f32(joint.axis.y);
+        buf.put_f32(joint.axis.z);
+
+        if let JointType::Revolute { min, max } = joint.joint_type {
+            buf.put_f32(min);
+            buf.put_f32(max);
+        }
+    }
+
+    /// Decode an optional joint.
+    pub(super) fn decode(decoder: &mut Decoder) -> Result<Option<Self>, DecodeError> {
+        let kind = decoder.get_u8()?;
+        if kind == 0 {
+            return Ok(None);
+        } else if kind > 2 {
+            return Err(decoder.invalid(1));
+        }
+
+        let axis = Vector3::new(decoder.get_f32()?, decoder.get_f32()?, decoder.get_f32()?);
+        let axis = UnitVector3::try_new(axis, f32::EPSILON)
+            .filter(|axis| axis.iter().all(|v| v.is_finite()))
+            .ok_or_else(|| decoder.invalid(12))?;
+
+        if kind == 1 {
+            return Ok(Some(Self::continuous(axis)));
+        }
+
+        let min = decoder.get_f32()?;
+        let max = decoder.get_f32()?;
+
+        Self::revolute(axis, min, max)
+            .map(Some)
+            .map_err(|_| decoder.invalid(8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joint_angle() {
+        let joint = JointDescriptor::revolute(Vector3::y_axis(), -0.5, 1.0).unwrap();
+
+        for angle in [-3.0, -1.0, 0.0, 0.4, 1.0, 3.1] {
+            let rotation = Rotation3::from_axis_angle(&Vector3::y_axis(), angle);
+            assert!((joint.angle(&rotation) - angle).abs() < 1e-5);
+        }
+
+        // Rotation around another axis does not move the joint.
+        let rotation = Rotation3::from_axis_angle(&Vector3::z_axis(), 1.0);
+        assert!(joint.angle(&rotation).abs() < 1e-6);
+
+        assert!(joint.contains(0.5));
+        assert!(!joint.contains(1.1));
+        assert_eq!(joint.clamp(-2.0), -0.5);
+        assert_eq!(joint.clamp(2.0), 1.0);
+
+        let joint = JointDescriptor::continuous(Vector3::z_axis());
+        assert!(joint.contains(3.0));
+        assert_eq!(joint.clamp(3.0), 3.0);
+
+        assert!(JointDescriptor::revolute(Vector3::y_axis(), 1.0, -1.0).is_err());
+        assert!(JointDescriptor::revolute(Vector3::y_axis(), f32::NAN, 1.0).is_err());
+    }
+}
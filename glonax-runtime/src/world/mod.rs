@@ -1,11 +1,50 @@
-use nalgebra::{Matrix4, Point3, Rotation3, Translation3, Vector3};
+use nalgebra::{
+    IsometryMatrix3, Matrix4, Point3, Quaternion, Rotation3, Translation3, UnitQuaternion, Vector3,
+};
+
+use crate::core::codec::{DecodeError, Decoder, Encoder};
+
+pub use attachment::{Attachment, AttachmentConfig, AttachmentRegistry, EFFECTOR_SEGMENT};
+pub use collision::{CollisionConfig, CollisionGeometry, Cuboid, ShapeConfig};
+pub use convention::{FrameConvention, JointReference};
+pub use joint::{JointDescriptor, JointType};
+
+mod attachment;
+mod collision;
+mod convention;
+mod joint;
+
+/// Maximum number of segments of an actor.
+const MAX_SEGMENTS: usize = 64;
+
+/// Actor schema version.
+///
+/// Payloads without a version start with the high byte of the name length,
+/// which is zero and rejected by the decoder.
+const SCHEMA_VERSION: u8 = 0x01;
 
 #[derive(Default)]
 pub struct World {
     actors: Vec<Actor>, // TODO: Use Vec<Rc<Actor>>?
+    /// Frame convention.
+    convention: FrameConvention,
 }
 
 impl World {
+    /// Construct a new world with a frame convention.
+    pub fn with_convention(convention: FrameConvention) -> Self {
+        Self {
+            actors: Vec::new(),
+            convention,
+        }
+    }
+
+    /// Active frame convention.
+    #[inline]
+    pub fn convention(&self) -> &FrameConvention {
+        &self.convention
+    }
+
     /// Add actor to world and return index.
     #[inline]
     pub fn add_actor(&mut self, actor: Actor) -> usize {
@@ -26,17 +65,13 @@ impl World {
     }
 
     /// Retrieve actor by name.
-    pub fn get_actor_by_name(&self, name: impl ToString) -> Option<&Actor> {
-        self.actors
-            .iter()
-            .find(|actor| actor.name() == name.to_string())
+    pub fn get_actor_by_name(&self, name: &str) -> Option<&Actor> {
+        self.actors.iter().find(|actor| actor.name() == name)
     }
 
     /// Retrieve actor by name mutably.
-    pub fn get_actor_by_name_mut(&mut self, name: impl ToString) -> Option<&mut Actor> {
-        self.actors
-            .iter_mut()
-            .find(|actor| actor.name() == name.to_string())
+    pub fn get_actor_by_name_mut(&mut self, name: &str) -> Option<&mut Actor> {
+        self.actors.iter_mut().find(|actor| actor.name() == name)
     }
 }
 
@@ -62,6 +97,14 @@ impl ActorBuilder {
         self
     }
 
+    /// Set the joint of an attached segment.
+    pub fn with_joint(mut self, name: &str, joint: JointDescriptor) -> Self {
+        if let Some((_, segment)) = self.segments.iter_mut().find(|(sname, _)| sname == name) {
+            segment.set_joint(joint);
+        }
+        self
+    }
+
     pub fn with_location(mut self, location: Vector3<f32>) -> Self {
         if self.segments.is_empty() {
             self.segments
@@ -136,112 +179,244 @@ impl Actor {
         self.segments[0].1.set_rotation(rotation);
     }
 
-    pub fn set_segment_rotation(&mut self, name: impl ToString, rotation: Rotation3<f32>) {
-        for (sname, segment) in self.segments.iter_mut() {
-            if sname == &name.to_string() {
-                segment.set_rotation(rotation);
-                break;
-            }
-        }
+    /// Retrieve segment by name.
+    fn segment(&self, name: &str) -> Option<&ActorSegment> {
+        self.segments
+            .iter()
+            .find(|(sname, _)| sname == name)
+            .map(|(_, segment)| segment)
     }
 
-    pub fn add_segment_rotation(&mut self, name: impl ToString, rotation: Rotation3<f32>) {
-        for (sname, segment) in self.segments.iter_mut() {
-            if sname == &name.to_string() {
-                segment.add_rotation(rotation);
-                break;
-            }
+    /// Retrieve segment by name mutably.
+    fn segment_mut(&mut self, name: &str) -> Option<&mut ActorSegment> {
+        self.segments
+            .iter_mut()
+            .find(|(sname, _)| sname == name)
+            .map(|(_, segment)| segment)
+    }
+
+    pub fn set_segment_rotation(&mut self, name: &str, rotation: Rotation3<f32>) {
+        if let Some(segment) = self.segment_mut(name) {
+            segment.set_rotation(rotation);
         }
     }
 
-    pub fn segment_location(&self, name: impl ToString) -> Option<Point3<f32>> {
-        for (sname, segment) in &self.segments {
-            if sname == &name.to_string() {
-                return Some(segment.location());
-            }
+    /// Set the location of a segment relative to its parent.
+    pub fn set_segment_location(&mut self, name: &str, location: Vector3<f32>) {
+        if let Some(segment) = self.segment_mut(name) {
+            segment.set_location(location);
         }
+    }
 
-        None
+    pub fn add_segment_rotation(&mut self, name: &str, rotation: Rotation3<f32>) {
+        if let Some(segment) = self.segment_mut(name) {
+            segment.add_rotation(rotation);
+        }
     }
 
-    pub fn world_location(&self, name: impl ToString) -> Point3<f32> {
-        let mut transform = Matrix4::identity();
+    pub fn segment_location(&self, name: &str) -> Option<Point3<f32>> {
+        self.segment(name).map(|segment| segment.location())
+    }
 
-        for (sname, segment) in self.segments.iter() {
-            transform *= segment.transformation();
+    pub fn segment_rotation(&self, name: &str) -> Option<Rotation3<f32>> {
+        self.segment(name).map(|segment| segment.rotation())
+    }
 
-            if sname == &name.to_string() {
+    /// Cumulative transforms from the segment frames to the world frame.
+    fn world_chain(&self) -> impl Iterator<Item = (&str, IsometryMatrix3<f32>)> {
+        self.segments
+            .iter()
+            .scan(IsometryMatrix3::identity(), |transform, (name, segment)| {
+                *transform *= segment.isometry;
+                Some((name.as_str(), *transform))
+            })
+    }
+
+    /// Transforms from the segment frames to the world frame.
+    ///
+    /// The transform chain is computed once for all segments, which is
+    /// cheaper than a query per segment.
+    ///
+    /// # Returns
+    ///
+    /// The segment names and world transforms, in segment order.
+    pub fn world_transforms(&self) -> Vec<(&str, IsometryMatrix3<f32>)> {
+        self.world_chain().collect()
+    }
+
+    /// Transform from the segment frame to the world frame.
+    ///
+    /// The transform of the last segment is returned if the segment does not
+    /// exist.
+    pub fn world_transform(&self, name: &str) -> IsometryMatrix3<f32> {
+        let mut last = IsometryMatrix3::identity();
+
+        for (sname, transform) in self.world_chain() {
+            last = transform;
+
+            if sname == name {
                 break;
             }
         }
 
-        transform.transform_point(&Point3::new(0.0, 0.0, 0.0))
+        last
     }
-}
-
-impl Actor {
-    pub fn to_bytes(&self) -> Vec<u8> {
-        use bytes::BufMut;
 
-        let mut buf = bytes::BytesMut::with_capacity(64);
+    /// Transformation from the segment frame to the world frame.
+    pub fn world_transformation(&self, name: &str) -> Matrix4<f32> {
+        self.world_transform(name).to_homogeneous()
+    }
 
-        // buf.put_u8(self.ty as u8);
+    pub fn world_location(&self, name: &str) -> Point3<f32> {
+        self.world_transform(name).translation.vector.into()
+    }
 
-        let name_bytes = self.name.as_bytes();
-        buf.put_u16(name_bytes.len() as u16);
-        buf.put(name_bytes);
+    /// Rotation from the segment frame to the world frame.
+    pub fn world_rotation(&self, name: &str) -> Rotation3<f32> {
+        self.world_transform(name).rotation
+    }
 
-        buf.put_u8(self.segments.len() as u8);
+    /// Clamp the segment rotations to their joint limits.
+    ///
+    /// A segment outside its limits is rotated around the joint axis to the
+    /// nearest limit.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if any segment was clamped.
+    pub fn clamp_to_limits(&mut self) -> bool {
+        let mut clamped = false;
 
-        for (name, segment) in &self.segments {
-            let name_bytes = name.as_bytes();
-            buf.put_u16(name_bytes.len() as u16);
-            buf.put(name_bytes);
+        for (_, segment) in self.segments.iter_mut() {
+            let Some(joint) = segment.joint else {
+                continue;
+            };
 
-            buf.put(&segment.to_bytes()[..]);
+            let angle = joint.angle(&segment.rotation());
+            if !joint.contains(angle) {
+                segment.set_rotation(Rotation3::from_axis_angle(
+                    &joint.axis(),
+                    joint.clamp(angle),
+                ));
+                clamped = true;
+            }
         }
 
-        buf.to_vec()
+        clamped
+    }
+
+    /// Kinematic reach of the actor.
+    ///
+    /// The reach is the sum of the segment lengths following the first joint.
+    ///
+    /// # Returns
+    ///
+    /// The name of the first jointed segment, which is the base of the
+    /// kinematic chain, and the reach from its origin.
+    pub fn reach(&self) -> Option<(&str, f32)> {
+        let base = self
+            .segments
+            .iter()
+            .position(|(_, segment)| segment.joint.is_some())?;
+
+        let reach = self.segments[base + 1..]
+            .iter()
+            .map(|(_, segment)| segment.location().coords.norm())
+            .sum();
+
+        Some((&self.segments[base].0, reach))
+    }
+
+    /// Test if a target is within the kinematic reach of the actor.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The target location in the world frame.
+    ///
+    /// # Returns
+    ///
+    /// Returns `false` if the target is beyond the reach, or the actor has no
+    /// joints.
+    pub fn is_reachable(&self, target: Point3<f32>) -> bool {
+        match self.reach() {
+            Some((base, reach)) => nalgebra::distance(&self.world_location(base), &target) <= reach,
+            None => false,
+        }
     }
 }
 
-impl TryFrom<Vec<u8>> for Actor {
-    type Error = ();
+impl Actor {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::with_capacity(SCHEMA_VERSION, 64);
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        use bytes::Buf;
+        encoder.put_bounded_string(&self.name);
+        encoder.put_u8(self.segments.len() as u8);
+
+        for (name, segment) in &self.segments {
+            encoder.put_bounded_string(name);
+            encoder.put_slice(&segment.to_bytes());
+            JointDescriptor::encode(segment.joint(), &mut encoder);
+        }
 
-        let mut buf = bytes::Bytes::copy_from_slice(&value);
+        encoder.into_vec()
+    }
+}
 
-        // let ty = buf.get_u8();
-        let name_len = buf.get_u16() as usize;
-        let name = String::from_utf8_lossy(&buf[..name_len]).to_string();
-        buf.advance(name_len);
+impl Actor {
+    /// Decode an actor from the decoder.
+    ///
+    /// Every length on the wire is checked against the remaining bytes before
+    /// anything is read or allocated.
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+        let name = decoder.get_string()?;
+
+        let segment_count = decoder.get_u8()? as usize;
+        if segment_count == 0 || segment_count > MAX_SEGMENTS {
+            return Err(decoder.invalid(1));
+        }
 
-        let segment_count = buf.get_u8() as usize;
+        // Every segment holds at least its name length, its transformation and
+        // its joint kind.
+        let min_size = segment_count * (2 + ActorSegment::BYTE_SIZE + 1);
+        if decoder.remaining() < min_size {
+            return Err(DecodeError::ShortBuffer {
+                offset: decoder.offset(),
+                len: min_size,
+            });
+        }
 
         let mut segments = Vec::with_capacity(segment_count);
 
         for _ in 0..segment_count {
-            let name_len = buf.get_u16() as usize;
-            let name = String::from_utf8_lossy(&buf[..name_len]).to_string();
-            buf.advance(name_len);
-
-            let segment = ActorSegment::try_from(&buf[..]).unwrap();
-            buf.advance(24); // TODO: Remove magic number
+            let name = decoder.get_string()?;
+            let mut segment = ActorSegment::decode(decoder)?;
+            segment.joint = JointDescriptor::decode(decoder)?;
 
             segments.push((name, segment));
         }
 
-        // log::debug!("Actor: {} {}", name, ty);
-        // log::debug!("segment_count: {}", segment_count);
+        Ok(Self { name, segments })
+    }
+}
 
-        // segments.iter().for_each(|(name, segment)| {
-        //     let (roll, pitch, yaw) = segment.rotation().euler_angles();
-        //     log::debug!("segment: {} R={} P={} Y={}", name, roll, pitch, yaw);
-        // });
+impl TryFrom<&[u8]> for Actor {
+    type Error = DecodeError;
 
-        Ok(Self { name, segments })
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut decoder = Decoder::new(value)?;
+        if decoder.version() > SCHEMA_VERSION {
+            return Err(DecodeError::UnsupportedVersion(decoder.version()));
+        }
+
+        Self::decode(&mut decoder)
+    }
+}
+
+impl TryFrom<Vec<u8>> for Actor {
+    type Error = DecodeError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_slice())
     }
 }
 
@@ -256,6 +431,8 @@ impl crate::protocol::Packetize for Actor {
 #[derive(Clone)]
 pub struct ActorSegment {
     isometry: nalgebra::IsometryMatrix3<f32>,
+    /// Joint to the parent segment.
+    joint: Option<JointDescriptor>,
 }
 
 impl ActorSegment {
@@ -266,9 +443,16 @@ impl ActorSegment {
                 nalgebra::Translation3::from(location),
                 nalgebra::Rotation3::identity(),
             ),
+            joint: None,
         }
     }
 
+    /// Set the joint to the parent segment.
+    pub fn with_joint(mut self, joint: JointDescriptor) -> Self {
+        self.joint = Some(joint);
+        self
+    }
+
     /// Segment location.
     #[inline]
     pub fn location(&self) -> Point3<f32> {
@@ -281,6 +465,12 @@ impl ActorSegment {
         self.isometry.rotation
     }
 
+    /// Joint to the parent segment.
+    #[inline]
+    pub fn joint(&self) -> Option<&JointDescriptor> {
+        self.joint.as_ref()
+    }
+
     /// Segment transformation.
     #[inline]
     pub fn transformation(&self) -> Matrix4<f32> {
@@ -293,6 +483,12 @@ impl ActorSegment {
         self.isometry.translation = Translation3::from(location);
     }
 
+    /// Set the joint to the parent segment.
+    #[inline]
+    pub fn set_joint(&mut self, joint: JointDescriptor) {
+        self.joint = Some(joint);
+    }
+
     /// Set segment absolute rotation.
     #[inline]
     pub fn set_rotation(&mut self, rotation: Rotation3<f32>) {
@@ -313,44 +509,69 @@ impl ActorSegment {
 }
 
 impl ActorSegment {
+    /// Size of the encoded segment.
+    ///
+    /// The segment is encoded as the translation followed by the rotation as
+    /// a unit quaternion. Unlike Euler angles the quaternion is not ambiguous
+    /// near a pitch of ±90°.
+    pub const BYTE_SIZE: usize = 7 * std::mem::size_of::<f32>();
+
     pub fn to_bytes(&self) -> Vec<u8> {
         use bytes::BufMut;
 
-        let mut buf = bytes::BytesMut::with_capacity(64);
+        let mut buf = bytes::BytesMut::with_capacity(Self::BYTE_SIZE);
 
         buf.put_f32(self.isometry.translation.vector.x);
         buf.put_f32(self.isometry.translation.vector.y);
         buf.put_f32(self.isometry.translation.vector.z);
 
-        let (roll, pitch, yaw) = self.isometry.rotation.euler_angles();
-        buf.put_f32(roll);
-        buf.put_f32(pitch);
-        buf.put_f32(yaw);
+        let rotation = UnitQuaternion::from_rotation_matrix(&self.isometry.rotation);
+        buf.put_f32(rotation.w);
+        buf.put_f32(rotation.i);
+        buf.put_f32(rotation.j);
+        buf.put_f32(rotation.k);
 
         buf.to_vec()
     }
-}
-
-impl TryFrom<&[u8]> for ActorSegment {
-    type Error = ();
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        use bytes::Buf;
+    /// Decode a segment from the decoder.
+    ///
+    /// The quaternion is normalized, a zero or non-finite quaternion is
+    /// rejected.
+    fn decode(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+        let translation = Vector3::new(decoder.get_f32()?, decoder.get_f32()?, decoder.get_f32()?);
+        let quaternion = Quaternion::new(
+            decoder.get_f32()?,
+            decoder.get_f32()?,
+            decoder.get_f32()?,
+            decoder.get_f32()?,
+        );
 
-        let mut buf = bytes::Bytes::copy_from_slice(value);
+        let norm = quaternion.norm();
+        if !(norm.is_finite() && norm > f32::EPSILON) {
+            return Err(decoder.invalid(4 * std::mem::size_of::<f32>()));
+        }
 
-        let translation = Vector3::new(buf.get_f32(), buf.get_f32(), buf.get_f32());
-        let rotation = Rotation3::from_euler_angles(buf.get_f32(), buf.get_f32(), buf.get_f32());
+        let rotation = UnitQuaternion::from_quaternion(quaternion).to_rotation_matrix();
 
         Ok(Self {
             isometry: nalgebra::IsometryMatrix3::from_parts(
                 nalgebra::Translation3::from(translation),
                 rotation,
             ),
+            joint: None,
         })
     }
 }
 
+impl TryFrom<&[u8]> for ActorSegment {
+    type Error = DecodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        Self::decode(&mut Decoder::unversioned(value))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,4 +603,263 @@ mod tests {
             Rotation3::from_euler_angles(0.0, 0.0, 2.0 * std::f32::consts::PI)
         );
     }
+
+    fn excavator() -> Actor {
+        ActorBuilder::new("excavator")
+            .attach_segment(
+                "undercarriage",
+                ActorSegment::new(Vector3::new(0.0, 0.0, 0.0)),
+            )
+            .attach_segment("frame", ActorSegment::new(Vector3::new(0.0, 0.0, 1.295)))
+            .attach_segment("boom", ActorSegment::new(Vector3::new(0.16, 0.0, 0.595)))
+            .attach_segment("arm", ActorSegment::new(Vector3::new(6.0, 0.0, 0.0)))
+            .attach_segment(
+                "attachment",
+                ActorSegment::new(Vector3::new(2.97, 0.0, 0.0)),
+            )
+            .with_joint("frame", JointDescriptor::continuous(Vector3::z_axis()))
+            .with_joint(
+                "boom",
+                JointDescriptor::revolute(Vector3::y_axis(), -0.8, 1.0).unwrap(),
+            )
+            .with_joint(
+                "arm",
+                JointDescriptor::revolute(Vector3::y_axis(), -2.7, -0.7).unwrap(),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_actor_roundtrip() {
+        let mut actor = excavator();
+        actor.set_segment_rotation("frame", Rotation3::from_euler_angles(0.0, 0.0, 1.2));
+        actor.set_segment_rotation("boom", Rotation3::from_euler_angles(0.0, -0.6, 0.0));
+        actor.set_segment_rotation("arm", Rotation3::from_euler_angles(0.0, 1.1, 0.0));
+
+        let bytes = actor.to_bytes();
+        let decoded = Actor::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(decoded.name(), "excavator");
+        assert_eq!(decoded.segments.len(), 5);
+
+        for ((name, segment), (decoded_name, decoded_segment)) in
+            actor.segments.iter().zip(&decoded.segments)
+        {
+            assert_eq!(name, decoded_name);
+            assert_eq!(segment.location(), decoded_segment.location());
+            assert_eq!(segment.joint(), decoded_segment.joint());
+            assert!(segment.rotation().angle_to(&decoded_segment.rotation()) < 1e-5);
+        }
+
+        assert!(
+            (actor.world_location("attachment") - decoded.world_location("attachment")).norm()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn test_actor_roundtrip_three_segments() {
+        let mut actor = ActorBuilder::new("arm")
+            .attach_segment("base", ActorSegment::new(Vector3::new(0.0, 0.0, 1.0)))
+            .attach_segment("upper", ActorSegment::new(Vector3::new(2.0, 0.0, 0.0)))
+            .attach_segment("lower", ActorSegment::new(Vector3::new(1.5, 0.0, 0.0)))
+            .build();
+        actor.set_segment_rotation("upper", Rotation3::from_euler_angles(0.0, 0.4, 0.0));
+        actor.set_segment_rotation("lower", Rotation3::from_euler_angles(0.0, -1.2, 0.0));
+
+        let bytes = actor.to_bytes();
+        let decoded = Actor::try_from(bytes.as_slice()).unwrap();
+
+        // Every segment is consumed, none is skipped or read at an offset.
+        assert_eq!(
+            decoded.world_transforms().len(),
+            actor.world_transforms().len()
+        );
+        for ((name, transform), (decoded_name, decoded_transform)) in actor
+            .world_transforms()
+            .iter()
+            .zip(decoded.world_transforms())
+        {
+            assert_eq!(*name, decoded_name);
+            assert!(
+                (transform.to_homogeneous() - decoded_transform.to_homogeneous()).norm() < 1e-5
+            );
+        }
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_actor_roundtrip_steep_pitch() {
+        use std::f32::consts::FRAC_PI_2;
+
+        for pitch in [
+            -FRAC_PI_2,
+            -FRAC_PI_2 + 1e-3,
+            -1.4,
+            1.4,
+            FRAC_PI_2 - 1e-3,
+            FRAC_PI_2,
+        ] {
+            for (roll, yaw) in [(0.0, 0.0), (0.3, 1.2), (-2.1, -0.7)] {
+                let rotation = Rotation3::from_euler_angles(roll, pitch, yaw);
+
+                let mut segment = ActorSegment::new(Vector3::new(6.0, 0.0, 0.0));
+                segment.set_rotation(rotation);
+
+                let bytes = segment.to_bytes();
+                assert_eq!(bytes.len(), ActorSegment::BYTE_SIZE);
+
+                let decoded = ActorSegment::try_from(bytes.as_slice()).unwrap();
+                assert_eq!(decoded.location(), segment.location());
+                assert!((rotation.matrix() - decoded.rotation().matrix()).norm() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_actor_decode_malformed() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let bytes = excavator().to_bytes();
+
+        // Every truncation is rejected.
+        for len in 0..bytes.len() {
+            assert!(Actor::try_from(&bytes[..len]).is_err());
+        }
+
+        // Payload without a version, or from a newer schema.
+        assert_eq!(
+            Actor::try_from(&bytes[1..]).err(),
+            Some(DecodeError::UnsupportedVersion(0))
+        );
+        assert_eq!(
+            Actor::try_from(&[0x02, 0x00, 0x01, b'a', 0x00][..]).err(),
+            Some(DecodeError::UnsupportedVersion(2))
+        );
+
+        // Name longer than the buffer.
+        assert_eq!(
+            Actor::try_from(&[0x01, 0xFF, 0xFF, b'a'][..]).err(),
+            Some(DecodeError::ShortBuffer {
+                offset: 3,
+                len: 0xFFFF
+            })
+        );
+
+        // No segments, or more segments than the buffer can hold.
+        assert_eq!(
+            Actor::try_from(&[0x01, 0x00, 0x01, b'a', 0x00][..]).err(),
+            Some(DecodeError::InvalidValue { offset: 4 })
+        );
+        assert!(matches!(
+            Actor::try_from(&[0x01, 0x00, 0x01, b'a', 0x20, 0x00, 0x00][..]),
+            Err(DecodeError::ShortBuffer { offset: 5, .. })
+        ));
+        assert_eq!(
+            Actor::try_from(&[0x01, 0x00, 0x01, b'a', 0xFF][..]).err(),
+            Some(DecodeError::InvalidValue { offset: 4 })
+        );
+
+        // Zero quaternion.
+        let mut zero = ActorSegment::new(Vector3::zeros()).to_bytes();
+        zero[12..].fill(0);
+        assert!(ActorSegment::try_from(zero.as_slice()).is_err());
+
+        let mut rng = StdRng::seed_from_u64(0x69);
+
+        for _ in 0..2_000 {
+            let len = rng.gen_range(0..256);
+            let random: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let _ = Actor::try_from(random.as_slice());
+
+            let mut corrupt = bytes.clone();
+            for _ in 0..rng.gen_range(1..4) {
+                let idx = rng.gen_range(0..corrupt.len());
+                corrupt[idx] = rng.gen();
+            }
+            corrupt.truncate(rng.gen_range(0..=corrupt.len()));
+            let _ = Actor::try_from(corrupt.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_actor_joint_limits() {
+        let mut actor = excavator();
+        actor.set_segment_rotation("arm", Rotation3::from_axis_angle(&Vector3::y_axis(), -1.0));
+        assert!(!actor.clamp_to_limits());
+
+        actor.set_segment_rotation("frame", Rotation3::from_axis_angle(&Vector3::z_axis(), 3.0));
+        actor.set_segment_rotation("boom", Rotation3::from_axis_angle(&Vector3::y_axis(), 1.4));
+        actor.set_segment_rotation("arm", Rotation3::from_axis_angle(&Vector3::y_axis(), -3.0));
+        assert!(actor.clamp_to_limits());
+        assert!(!actor.clamp_to_limits());
+
+        let angle = |name: &str| {
+            let (_, segment) = actor
+                .segments
+                .iter()
+                .find(|(sname, _)| sname == name)
+                .unwrap();
+            segment.joint().unwrap().angle(&segment.rotation())
+        };
+        assert!((angle("frame") - 3.0).abs() < 1e-5);
+        assert!((angle("boom") - 1.0).abs() < 1e-5);
+        assert!((angle("arm") + 2.7).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_actor_reachability() {
+        let actor = excavator();
+
+        let (base, reach) = actor.reach().unwrap();
+        assert_eq!(base, "frame");
+        assert!((reach - (0.16_f32.hypot(0.595) + 6.0 + 2.97)).abs() < 1e-5);
+
+        let base = actor.world_location("frame");
+        assert!(actor.is_reachable(base + Vector3::new(reach - 0.01, 0.0, 0.0)));
+        assert!(actor.is_reachable(actor.world_location("attachment")));
+        assert!(!actor.is_reachable(base + Vector3::new(0.0, reach + 0.01, 0.0)));
+        assert!(!actor.is_reachable(Point3::new(20.0, 0.0, 0.0)));
+
+        // Without joints nothing is reachable.
+        let actor = ActorBuilder::new("static").build();
+        assert!(actor.reach().is_none());
+        assert!(!actor.is_reachable(Point3::origin()));
+    }
+
+    #[test]
+    fn test_actor_world_transforms() {
+        let mut actor = excavator();
+        actor.set_segment_rotation("frame", Rotation3::from_euler_angles(0.0, 0.0, 0.7));
+        actor.set_segment_rotation("boom", Rotation3::from_euler_angles(0.0, -0.4, 0.0));
+        actor.set_segment_rotation("arm", Rotation3::from_euler_angles(0.0, 1.3, 0.0));
+        actor.set_segment_rotation("attachment", Rotation3::from_euler_angles(0.0, 0.5, 0.0));
+
+        let transforms = actor.world_transforms();
+        assert_eq!(
+            transforms.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+            vec!["undercarriage", "frame", "boom", "arm", "attachment"]
+        );
+
+        for (name, transform) in &transforms {
+            assert_eq!(transform.to_homogeneous(), actor.world_transformation(name));
+            assert_eq!(
+                Point3::from(transform.translation.vector),
+                actor.world_location(name)
+            );
+            assert_eq!(transform.rotation, actor.world_rotation(name));
+        }
+
+        let rotation = actor.world_rotation("arm");
+        let expected = Rotation3::from_euler_angles(0.0, 0.0, 0.7)
+            * Rotation3::from_euler_angles(0.0, -0.4, 0.0)
+            * Rotation3::from_euler_angles(0.0, 1.3, 0.0);
+        assert!(rotation.angle_to(&expected) < 1e-5);
+
+        // An unknown segment resolves to the end of the chain.
+        assert_eq!(
+            actor.world_location("bucket"),
+            actor.world_location("attachment")
+        );
+    }
 }
@@ -0,0 +1,80 @@
+//! Steady state allocation test for the protocol receive path.
+//!
+//! The test replaces the global allocator, so it runs in its own test binary
+//! without the test harness. The test runs on the main thread only, every
+//! allocation in the process is counted.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use glonax::{
+    core::{Engine, Rotator},
+    protocol::Stream,
+};
+
+/// Allocator that counts the allocations.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn recv_packet_no_allocation() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let (a, b) = tokio::io::duplex(4_096);
+        let mut client = Stream::new(a);
+        let mut server = Stream::new(b);
+
+        let engine = Engine::from_rpm(1_500);
+        let rotator =
+            Rotator::absolute(0x6A, nalgebra::Rotation3::from_euler_angles(0.1, 0.2, 0.3));
+
+        for _ in 0..16 {
+            client.send_packet(&engine).await.unwrap();
+            client.send_packet(&rotator).await.unwrap();
+        }
+
+        for _ in 0..16 {
+            let frame = server.read_frame().await.unwrap();
+
+            let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+            let packet = server
+                .recv_packet::<Engine>(frame.payload_length)
+                .await
+                .unwrap();
+            assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), allocations);
+            assert_eq!(packet, engine);
+
+            let frame = server.read_frame().await.unwrap();
+
+            let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+            let packet = server
+                .recv_packet::<Rotator>(frame.payload_length)
+                .await
+                .unwrap();
+            assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), allocations);
+            assert_eq!(packet.source, rotator.source);
+        }
+    });
+}
+
+fn main() {
+    recv_packet_no_allocation();
+
+    println!("test recv_packet_no_allocation ... ok");
+}
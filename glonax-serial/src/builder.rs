@@ -13,7 +13,8 @@ use crate::{BaudRate, CharSize, FlowControl, Parity, StopBits};
 pub struct Builder {
     fd: RawFd,
     termios: Termios,
-    timeout: Duration,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
     exclusive: bool,
 }
 
@@ -40,10 +41,7 @@ impl Builder {
             ISIG, OPOST, VMIN, VTIME,
         };
 
-        let mut termios = match Termios::from_fd(fd) {
-            Ok(t) => t,
-            Err(e) => return Err(e.into()),
-        };
+        let mut termios = Termios::from_fd(fd)?;
 
         // Setup TTY for binary serial port access.
         termios.c_iflag &= !(INLCR | IGNCR | ICRNL | IGNBRK);
@@ -57,15 +55,43 @@ impl Builder {
         Ok(Self {
             fd,
             termios,
-            timeout: Duration::from_millis(100),
+            read_timeout: None,
+            write_timeout: None,
             exclusive: true,
         })
     }
 
+    /// Set the read and write timeout.
     #[inline]
-    pub fn set_timeout(mut self, timeout: Duration) -> super::Result<Self> {
-        self.timeout = timeout;
-        Ok(self)
+    pub fn set_timeout(self, timeout: Duration) -> super::Result<Self> {
+        Ok(self.set_read_timeout(timeout).set_write_timeout(timeout))
+    }
+
+    /// Set the read timeout.
+    ///
+    /// A read that receives no data within the timeout fails with
+    /// `TimedOut`. The timeout is also set as the terminal inter-character
+    /// timer, in tenths of a second, so the device never blocks a reader
+    /// forever.
+    pub fn set_read_timeout(mut self, timeout: Duration) -> Self {
+        use libc::{VMIN, VTIME};
+
+        let deciseconds = timeout.as_millis().div_ceil(100).clamp(1, 255);
+
+        self.termios.c_cc[VMIN] = 0;
+        self.termios.c_cc[VTIME] = deciseconds as libc::cc_t;
+
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the write timeout.
+    ///
+    /// A write that cannot be queued within the timeout fails with
+    /// `TimedOut`.
+    pub fn set_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = Some(timeout);
+        self
     }
 
     #[inline]
@@ -104,11 +130,11 @@ impl Builder {
             BaudRate::Baud38400 => B38400,
             BaudRate::Baud57600 => B57600,
             BaudRate::Baud115200 => B115200,
-            BaudRate::BaudOther(230400) => B230400,
-            BaudRate::BaudOther(460800) => B460800,
+            BaudRate::Baud230400 | BaudRate::BaudOther(230400) => B230400,
+            BaudRate::Baud460800 | BaudRate::BaudOther(460800) => B460800,
             BaudRate::BaudOther(500000) => B500000,
             BaudRate::BaudOther(576000) => B576000,
-            BaudRate::BaudOther(921600) => B921600,
+            BaudRate::Baud921600 | BaudRate::BaudOther(921600) => B921600,
             BaudRate::BaudOther(1000000) => B1000000,
             BaudRate::BaudOther(1152000) => B1152000,
             BaudRate::BaudOther(1500000) => B1500000,
@@ -221,6 +247,10 @@ impl Builder {
             return Err(err.into());
         }
 
-        crate::Uart::from_impl(crate::imp::Uart(self.fd))
+        crate::Uart::from_impl(
+            crate::imp::Uart(self.fd),
+            self.read_timeout,
+            self.write_timeout,
+        )
     }
 }
@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Driver of the legacy on-board serial ports.
+///
+/// The kernel registers these ports whether or not the hardware exists.
+const LEGACY_DRIVER: &str = "serial8250";
+
+/// Maximum number of parent devices searched for the USB device.
+const USB_DEVICE_DEPTH: usize = 4;
+
+/// Serial port information.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortInfo {
+    /// Device path.
+    pub path: PathBuf,
+    /// Stable device path under `/dev/serial/by-id`, if any.
+    pub by_id: Option<PathBuf>,
+    /// Kernel driver.
+    pub driver: Option<String>,
+    /// USB product name.
+    pub product: Option<String>,
+    /// USB vendor and product identifier.
+    pub vid_pid: Option<(u16, u16)>,
+}
+
+impl PortInfo {
+    /// Test if the port is the USB device with the vendor and product identifier.
+    #[inline]
+    pub fn is_usb_device(&self, vid: u16, pid: u16) -> bool {
+        self.vid_pid == Some((vid, pid))
+    }
+}
+
+fn read_attribute(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_hex_attribute(path: &Path) -> Option<u16> {
+    u16::from_str_radix(&read_attribute(path)?, 16).ok()
+}
+
+fn link_name(path: &Path) -> Option<String> {
+    fs::read_link(path)
+        .ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Find the USB device of a tty device.
+///
+/// The USB device is the first parent with a vendor identifier.
+fn usb_device(device: &Path) -> Option<PathBuf> {
+    device
+        .ancestors()
+        .take(USB_DEVICE_DEPTH)
+        .find(|path| path.join("idVendor").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Stable device paths by device name.
+fn by_id_links(dev: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dev.join("serial/by-id")) else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| Some((link_name(&entry.path())?, entry.path())))
+        .collect()
+}
+
+/// Scan for serial ports.
+///
+/// Only tty devices backed by hardware are reported. Virtual terminals and
+/// the legacy on-board ports are skipped.
+///
+/// # Arguments
+///
+/// * `sys_class_tty` - The tty class directory, `/sys/class/tty` on Linux.
+/// * `dev` - The device directory, `/dev` on Linux.
+///
+/// # Returns
+///
+/// The serial ports ordered by device path.
+pub fn scan_ports(sys_class_tty: &Path, dev: &Path) -> super::Result<Vec<PortInfo>> {
+    let by_id = by_id_links(dev);
+
+    let mut ports = vec![];
+
+    for entry in fs::read_dir(sys_class_tty)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let Ok(device) = fs::canonicalize(entry.path().join("device")) else {
+            continue;
+        };
+
+        let driver = link_name(&device.join("driver"));
+        if driver.as_deref() == Some(LEGACY_DRIVER) {
+            continue;
+        }
+
+        let usb = usb_device(&device);
+
+        let vid_pid = usb.as_ref().and_then(|usb| {
+            Some((
+                read_hex_attribute(&usb.join("idVendor"))?,
+                read_hex_attribute(&usb.join("idProduct"))?,
+            ))
+        });
+
+        ports.push(PortInfo {
+            path: dev.join(&name),
+            by_id: by_id
+                .iter()
+                .find(|(target, _)| target == &name)
+                .map(|(_, path)| path.clone()),
+            driver,
+            product: usb.and_then(|usb| read_attribute(&usb.join("product"))),
+            vid_pid,
+        });
+    }
+
+    ports.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(ports)
+}
+
+/// List the available serial ports.
+///
+/// ## Example
+///
+/// ```no_run
+/// for port in glonax_serial::available_ports().unwrap() {
+///     println!("{} {:?}", port.path.display(), port.vid_pid);
+/// }
+/// ```
+pub fn available_ports() -> super::Result<Vec<PortInfo>> {
+    scan_ports(Path::new("/sys/class/tty"), Path::new("/dev"))
+}
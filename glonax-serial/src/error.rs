@@ -26,6 +26,9 @@ pub enum ErrorKind {
     /// A parameter was incorrect.
     InvalidInput,
 
+    /// The operation did not complete within the timeout.
+    TimedOut,
+
     /// An I/O error occured.
     ///
     /// The type of I/O error is determined by the inner `io::ErrorKind`.
@@ -67,7 +70,12 @@ impl StdError for Error {
 
 impl From<io::Error> for Error {
     fn from(io_error: io::Error) -> Error {
-        Error::new(ErrorKind::Io(io_error.kind()), format!("{}", io_error))
+        let kind = match io_error.kind() {
+            io::ErrorKind::TimedOut => ErrorKind::TimedOut,
+            kind => ErrorKind::Io(kind),
+        };
+
+        Error::new(kind, format!("{}", io_error))
     }
 }
 
@@ -77,16 +85,18 @@ pub fn last_os_error() -> Error {
 
 pub fn from_raw_os_error(errno: i32) -> Error {
     use libc::{
-        EACCES, EBUSY, EINTR, EINVAL, EISDIR, ELOOP, ENAMETOOLONG, ENODEV, ENOENT, ENOTDIR, ENXIO,
-        EWOULDBLOCK,
+        EACCES, EBUSY, EINTR, EINVAL, EISDIR, ELOOP, ENAMETOOLONG, ENODEV, ENOENT, ENOTDIR, ENOTTY,
+        ENXIO, ETIMEDOUT, EWOULDBLOCK,
     };
 
     let kind = match errno {
         EBUSY | EISDIR | ELOOP | ENOTDIR | ENOENT | ENODEV | ENXIO | EACCES => ErrorKind::NoDevice,
         EINVAL | ENAMETOOLONG => ErrorKind::InvalidInput,
+        ETIMEDOUT => ErrorKind::TimedOut,
 
         EINTR => ErrorKind::Io(io::ErrorKind::Interrupted),
         EWOULDBLOCK => ErrorKind::Io(io::ErrorKind::WouldBlock),
+        ENOTTY => ErrorKind::Io(io::ErrorKind::Unsupported),
         _ => ErrorKind::Io(io::ErrorKind::Other),
     };
 
@@ -1,6 +1,15 @@
-use std::io::{Read, Write};
+use std::{
+    future::Future,
+    io::{Read, Write},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use tokio::io::{self, unix::AsyncFd, AsyncRead, AsyncWrite};
+use tokio::{
+    io::{self, unix::AsyncFd, AsyncRead, AsyncWrite},
+    time::Sleep,
+};
 
 use crate::{BaudRate, FlowControl, Parity, StopBits};
 
@@ -9,6 +18,41 @@ use crate::{BaudRate, FlowControl, Parity, StopBits};
 /// The port will be closed when the value is dropped.
 pub struct Uart {
     inner: AsyncFd<crate::imp::Uart>,
+    /// Read timeout.
+    read_timeout: Option<Duration>,
+    /// Write timeout.
+    write_timeout: Option<Duration>,
+    /// Deadline of the pending read.
+    read_deadline: Option<Pin<Box<Sleep>>>,
+    /// Deadline of the pending write.
+    write_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+/// Poll the deadline of a pending operation.
+///
+/// Returns `TimedOut` once the timeout elapsed, or pending if there is no
+/// timeout. The deadline is armed on the first poll.
+fn poll_deadline(
+    deadline: &mut Option<Pin<Box<Sleep>>>,
+    timeout: Option<Duration>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let Some(timeout) = timeout else {
+        return Poll::Pending;
+    };
+
+    let sleep = deadline.get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+
+    match sleep.as_mut().poll(cx) {
+        Poll::Ready(()) => {
+            *deadline = None;
+            Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "serial operation timed out",
+            )))
+        }
+        Poll::Pending => Poll::Pending,
+    }
 }
 
 impl Uart {
@@ -32,24 +76,132 @@ impl Uart {
             .build()
     }
 
-    pub(crate) fn from_impl(value: crate::imp::Uart) -> super::Result<Self> {
+    pub(crate) fn from_impl(
+        value: crate::imp::Uart,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    ) -> super::Result<Self> {
         Ok(Self {
             inner: AsyncFd::new(value)?,
+            read_timeout,
+            write_timeout,
+            read_deadline: None,
+            write_deadline: None,
         })
     }
 
+    /// Read timeout.
+    #[inline]
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+
+    /// Write timeout.
+    #[inline]
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write_timeout
+    }
+
+    /// Send a break condition.
+    ///
+    /// The line is held in the break condition for the duration. The break
+    /// condition is cleared when the future is dropped before it completes.
+    pub async fn send_break(&self, duration: Duration) -> super::Result<()> {
+        /// Clear the break condition on drop.
+        struct BreakGuard<'a>(&'a crate::imp::Uart);
+
+        impl Drop for BreakGuard<'_> {
+            fn drop(&mut self) {
+                let _ = self.0.set_break(false);
+            }
+        }
+
+        let uart = self.inner.get_ref();
+
+        uart.set_break(true)?;
+        let guard = BreakGuard(uart);
+
+        tokio::time::sleep(duration).await;
+
+        std::mem::forget(guard);
+        uart.set_break(false)
+    }
+
+    /// Set the Data Terminal Ready (DTR) line.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(Unsupported)` if the device has no modem control lines.
+    pub fn set_dtr(&self, level: bool) -> super::Result<()> {
+        self.inner.get_ref().set_modem_line(libc::TIOCM_DTR, level)
+    }
+
+    /// Set the Request To Send (RTS) line.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(Unsupported)` if the device has no modem control lines.
+    pub fn set_rts(&self, level: bool) -> super::Result<()> {
+        self.inner.get_ref().set_modem_line(libc::TIOCM_RTS, level)
+    }
+
+    /// Read the Clear To Send (CTS) line.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(Unsupported)` if the device has no modem control lines.
+    pub fn read_cts(&self) -> super::Result<bool> {
+        self.inner.get_ref().modem_line(libc::TIOCM_CTS)
+    }
+
+    /// Read the Data Carrier Detect (DCD) line.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(Unsupported)` if the device has no modem control lines.
+    pub fn read_dcd(&self) -> super::Result<bool> {
+        self.inner.get_ref().modem_line(libc::TIOCM_CAR)
+    }
+
+    /// Read from the device.
+    ///
+    /// Fails with `TimedOut` if no data arrives within the read timeout.
     pub async fn try_read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.read_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.read_ready(buf))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "serial read timed out"))?,
+            None => self.read_ready(buf).await,
+        }
+    }
+
+    /// Write to the device.
+    ///
+    /// Fails with `TimedOut` if the device does not accept data within the
+    /// write timeout.
+    pub async fn try_write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.write_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.write_ready(buf))
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "serial write timed out"))?,
+            None => self.write_ready(buf).await,
+        }
+    }
+
+    async fn read_ready(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timed = self.read_timeout.is_some();
+
         loop {
             let mut guard = self.inner.readable_mut().await?;
 
-            match guard.try_io(|inner| inner.get_mut().read(buf)) {
+            match guard.try_io(|inner| Self::read_impl(inner.get_mut(), buf, timed)) {
                 Ok(result) => return result,
                 Err(_would_block) => continue,
             }
         }
     }
 
-    pub async fn try_write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    async fn write_ready(&mut self, buf: &[u8]) -> io::Result<usize> {
         loop {
             let mut guard = self.inner.writable_mut().await?;
 
@@ -59,26 +211,46 @@ impl Uart {
             }
         }
     }
+
+    /// Read from the device.
+    ///
+    /// With a read timeout the terminal returns zero bytes when its timer
+    /// expires. That is not the end of the stream, so it is reported as
+    /// `WouldBlock` and the read is rescheduled.
+    fn read_impl(uart: &mut crate::imp::Uart, buf: &mut [u8], timed: bool) -> io::Result<usize> {
+        match uart.read(buf) {
+            Ok(0) if timed && !buf.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+            result => result,
+        }
+    }
 }
 
 impl AsyncRead for Uart {
     fn poll_read(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
         buf: &mut io::ReadBuf<'_>,
-    ) -> std::task::Poll<std::io::Result<()>> {
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        let timed = this.read_timeout.is_some();
+
         loop {
-            let mut guard = match self.inner.poll_read_ready_mut(cx)? {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
+            let mut guard = match this.inner.poll_read_ready_mut(cx)? {
+                Poll::Ready(guard) => guard,
+                Poll::Pending => {
+                    return poll_deadline(&mut this.read_deadline, this.read_timeout, cx);
+                }
             };
 
-            match guard.try_io(|inner| inner.get_mut().read(buf.initialize_unfilled())) {
+            match guard
+                .try_io(|inner| Self::read_impl(inner.get_mut(), buf.initialize_unfilled(), timed))
+            {
                 Ok(Ok(size)) => {
+                    this.read_deadline = None;
                     buf.advance(size);
-                    return std::task::Poll::Ready(Ok(()));
+                    return Poll::Ready(Ok(()));
                 }
-                Ok(Err(e)) => break std::task::Poll::Ready(Err(e)),
+                Ok(Err(e)) => break Poll::Ready(Err(e)),
                 Err(_would_block) => continue,
             }
         }
@@ -87,18 +259,26 @@ impl AsyncRead for Uart {
 
 impl AsyncWrite for Uart {
     fn poll_write(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
         buf: &[u8],
-    ) -> std::task::Poll<Result<usize, std::io::Error>> {
+    ) -> Poll<Result<usize, std::io::Error>> {
+        let this = &mut *self;
+
         loop {
-            let mut guard = match self.inner.poll_write_ready_mut(cx)? {
-                std::task::Poll::Ready(guard) => guard,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
+            let mut guard = match this.inner.poll_write_ready_mut(cx)? {
+                Poll::Ready(guard) => guard,
+                Poll::Pending => {
+                    return poll_deadline(&mut this.write_deadline, this.write_timeout, cx)
+                        .map_ok(|_| 0);
+                }
             };
 
             match guard.try_io(|inner| inner.get_mut().write(buf)) {
-                Ok(result) => return std::task::Poll::Ready(result),
+                Ok(result) => {
+                    this.write_deadline = None;
+                    return Poll::Ready(result);
+                }
                 Err(_would_block) => continue,
             }
         }
@@ -15,6 +15,49 @@ impl Drop for Uart {
     }
 }
 
+impl Uart {
+    /// Set or clear the break condition.
+    pub(crate) fn set_break(&self, enable: bool) -> crate::Result<()> {
+        let request = if enable {
+            libc::TIOCSBRK
+        } else {
+            libc::TIOCCBRK
+        };
+
+        if unsafe { libc::ioctl(self.0, request) } < 0 {
+            return Err(crate::error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Set or clear a modem control line.
+    pub(crate) fn set_modem_line(&self, line: libc::c_int, enable: bool) -> crate::Result<()> {
+        let request = if enable {
+            libc::TIOCMBIS
+        } else {
+            libc::TIOCMBIC
+        };
+
+        if unsafe { libc::ioctl(self.0, request, &line) } < 0 {
+            return Err(crate::error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Read the state of a modem control line.
+    pub(crate) fn modem_line(&self, line: libc::c_int) -> crate::Result<bool> {
+        let mut lines: libc::c_int = 0;
+
+        if unsafe { libc::ioctl(self.0, libc::TIOCMGET, &mut lines) } < 0 {
+            return Err(crate::error::last_os_error());
+        }
+
+        Ok(lines & line != 0)
+    }
+}
+
 impl AsRawFd for Uart {
     fn as_raw_fd(&self) -> RawFd {
         self.0
@@ -26,7 +69,7 @@ impl io::Read for Uart {
         let len = unsafe {
             libc::read(
                 self.0.as_raw_fd(),
-                buf.as_ptr() as *mut libc::c_void,
+                buf.as_mut_ptr() as *mut libc::c_void,
                 buf.len() as libc::size_t,
             )
         };
@@ -44,7 +87,7 @@ impl io::Write for Uart {
         let len = unsafe {
             libc::write(
                 self.0.as_raw_fd(),
-                buf.as_ptr() as *mut libc::c_void,
+                buf.as_ptr() as *const libc::c_void,
                 buf.len() as libc::size_t,
             )
         };
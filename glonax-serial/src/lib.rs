@@ -1,9 +1,11 @@
 mod builder;
+mod enumerate;
 mod error;
 mod future;
 mod imp;
 
 pub use builder::*;
+pub use enumerate::{available_ports, scan_ports, PortInfo};
 pub use error::{Error, ErrorKind, Result};
 pub use future::Uart;
 
@@ -39,6 +41,12 @@ pub enum BaudRate {
     Baud57600,
     /// 115,200 baud.
     Baud115200,
+    /// 230,400 baud.
+    Baud230400,
+    /// 460,800 baud.
+    Baud460800,
+    /// 921,600 baud.
+    Baud921600,
     /// Non-standard baud rates.
     ///
     /// `BaudOther` can be used to set non-standard baud rates by setting its member to be the
@@ -64,6 +72,7 @@ impl BaudRate {
     /// # use glonax_serial::BaudRate;
     /// assert_eq!(BaudRate::Baud9600, BaudRate::from_speed(9600));
     /// assert_eq!(BaudRate::Baud115200, BaudRate::from_speed(115200));
+    /// assert_eq!(BaudRate::Baud921600, BaudRate::from_speed(921600));
     /// assert_eq!(BaudRate::BaudOther(4000000), BaudRate::from_speed(4000000));
     /// ```
     pub fn from_speed(speed: usize) -> BaudRate {
@@ -79,6 +88,9 @@ impl BaudRate {
             38400 => BaudRate::Baud38400,
             57600 => BaudRate::Baud57600,
             115200 => BaudRate::Baud115200,
+            230400 => BaudRate::Baud230400,
+            460800 => BaudRate::Baud460800,
+            921600 => BaudRate::Baud921600,
             n => BaudRate::BaudOther(n),
         }
     }
@@ -91,6 +103,7 @@ impl BaudRate {
     /// # use glonax_serial::BaudRate;
     /// assert_eq!(9600, BaudRate::Baud9600.speed());
     /// assert_eq!(115200, BaudRate::Baud115200.speed());
+    /// assert_eq!(921600, BaudRate::Baud921600.speed());
     /// assert_eq!(4000000, BaudRate::BaudOther(4000000).speed());
     /// ```
     pub fn speed(&self) -> usize {
@@ -106,6 +119,9 @@ impl BaudRate {
             BaudRate::Baud38400 => 38400,
             BaudRate::Baud57600 => 57600,
             BaudRate::Baud115200 => 115200,
+            BaudRate::Baud230400 => 230400,
+            BaudRate::Baud460800 => 460800,
+            BaudRate::Baud921600 => 921600,
             BaudRate::BaudOther(n) => n,
         }
     }
@@ -0,0 +1,39 @@
+use std::os::fd::AsRawFd;
+
+use glonax_serial::BaudRate;
+use termios::{cfgetispeed, cfgetospeed, os::linux::B921600, Termios};
+
+mod common;
+
+use common::open_pty;
+
+#[test]
+fn baud_rate_round_trip() {
+    for speed in [230_400, 460_800, 921_600] {
+        let baud_rate = BaudRate::from_speed(speed);
+
+        assert!(!matches!(baud_rate, BaudRate::BaudOther(_)));
+        assert_eq!(baud_rate.speed(), speed);
+    }
+
+    assert_eq!(BaudRate::from_speed(921_600), BaudRate::Baud921600);
+    assert_eq!(BaudRate::from_speed(921_600).speed(), 921_600);
+}
+
+#[tokio::test]
+async fn baud_rate_termios() {
+    let (_master, path) = open_pty();
+
+    let _uart = glonax_serial::builder(&path)
+        .unwrap()
+        .set_baud_rate(BaudRate::Baud921600)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let slave = std::fs::File::open(&path).unwrap();
+    let termios = Termios::from_fd(slave.as_raw_fd()).unwrap();
+
+    assert_eq!(cfgetospeed(&termios), B921600);
+    assert_eq!(cfgetispeed(&termios), B921600);
+}
@@ -0,0 +1,23 @@
+use std::{ffi::CStr, fs::File, os::fd::FromRawFd, path::PathBuf};
+
+/// Open a pseudo terminal pair.
+///
+/// Returns the master side and the path of the slave device.
+pub fn open_pty() -> (File, PathBuf) {
+    unsafe {
+        let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(fd >= 0, "{}", std::io::Error::last_os_error());
+
+        let master = File::from_raw_fd(fd);
+
+        assert_eq!(libc::grantpt(fd), 0);
+        assert_eq!(libc::unlockpt(fd), 0);
+
+        let mut name = [0 as libc::c_char; 128];
+        assert_eq!(libc::ptsname_r(fd, name.as_mut_ptr(), name.len()), 0);
+
+        let path = CStr::from_ptr(name.as_ptr()).to_str().unwrap().into();
+
+        (master, path)
+    }
+}
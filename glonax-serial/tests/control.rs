@@ -0,0 +1,51 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use glonax_serial::ErrorKind;
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+use common::open_pty;
+
+#[tokio::test]
+async fn send_break() {
+    let (mut master, path) = open_pty();
+
+    let mut uart = glonax_serial::builder(&path).unwrap().build().unwrap();
+
+    let start = Instant::now();
+    uart.send_break(Duration::from_millis(100)).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    // A cancelled break is cleared and the line keeps working.
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(10),
+        uart.send_break(Duration::from_secs(5)),
+    )
+    .await;
+    assert!(cancelled.is_err());
+
+    master.write_all(b"glonax").unwrap();
+
+    let mut buf = [0; 16];
+    let size = uart.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..size], b"glonax");
+}
+
+#[tokio::test]
+async fn modem_lines_unsupported() {
+    let (_master, path) = open_pty();
+
+    let uart = glonax_serial::builder(&path).unwrap().build().unwrap();
+
+    // A pseudo terminal has no modem control lines.
+    let unsupported = ErrorKind::Io(std::io::ErrorKind::Unsupported);
+
+    assert_eq!(uart.set_dtr(true).unwrap_err().kind(), unsupported);
+    assert_eq!(uart.set_rts(false).unwrap_err().kind(), unsupported);
+    assert_eq!(uart.read_cts().unwrap_err().kind(), unsupported);
+    assert_eq!(uart.read_dcd().unwrap_err().kind(), unsupported);
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+/// Build a fixture of the sysfs and device directory layout.
+fn fixture(root: &Path) -> (PathBuf, PathBuf) {
+    let sys = root.join("sys");
+    let class_tty = sys.join("class/tty");
+    let dev = root.join("dev");
+
+    let _ = fs::remove_dir_all(root);
+    fs::create_dir_all(&class_tty).unwrap();
+    fs::create_dir_all(dev.join("serial/by-id")).unwrap();
+    fs::create_dir_all(sys.join("bus/usb-serial/drivers/ftdi_sio")).unwrap();
+    fs::create_dir_all(sys.join("bus/usb/drivers/cdc_acm")).unwrap();
+    fs::create_dir_all(sys.join("bus/platform/drivers/serial8250")).unwrap();
+
+    // USB serial converter, the tty device is below the USB interface.
+    let usb = sys.join("devices/pci0000:00/usb1/1-1");
+    let usb_port = usb.join("1-1:1.0/ttyUSB0");
+    fs::create_dir_all(&usb_port).unwrap();
+    fs::write(usb.join("idVendor"), "0403\n").unwrap();
+    fs::write(usb.join("idProduct"), "6001\n").unwrap();
+    fs::write(usb.join("product"), "FT232R USB UART\n").unwrap();
+    symlink(
+        sys.join("bus/usb-serial/drivers/ftdi_sio"),
+        usb_port.join("driver"),
+    )
+    .unwrap();
+    fs::create_dir_all(class_tty.join("ttyUSB0")).unwrap();
+    symlink(&usb_port, class_tty.join("ttyUSB0/device")).unwrap();
+    symlink(
+        "../../ttyUSB0",
+        dev.join("serial/by-id/usb-FTDI_FT232R_USB_UART-if00-port0"),
+    )
+    .unwrap();
+
+    // USB modem, the tty device is the USB interface.
+    let acm = sys.join("devices/pci0000:00/usb1/1-2");
+    let acm_port = acm.join("1-2:1.0");
+    fs::create_dir_all(&acm_port).unwrap();
+    fs::write(acm.join("idVendor"), "1546\n").unwrap();
+    fs::write(acm.join("idProduct"), "01a8\n").unwrap();
+    fs::write(acm.join("product"), "u-blox GNSS receiver\n").unwrap();
+    symlink(sys.join("bus/usb/drivers/cdc_acm"), acm_port.join("driver")).unwrap();
+    fs::create_dir_all(class_tty.join("ttyACM0")).unwrap();
+    symlink(&acm_port, class_tty.join("ttyACM0/device")).unwrap();
+
+    // Legacy on-board port.
+    let legacy = sys.join("devices/platform/serial8250/tty/ttyS0");
+    fs::create_dir_all(&legacy).unwrap();
+    symlink(
+        sys.join("bus/platform/drivers/serial8250"),
+        legacy.join("driver"),
+    )
+    .unwrap();
+    fs::create_dir_all(class_tty.join("ttyS0")).unwrap();
+    symlink(&legacy, class_tty.join("ttyS0/device")).unwrap();
+
+    // Virtual terminal without a device.
+    fs::create_dir_all(class_tty.join("tty0")).unwrap();
+
+    (class_tty, dev)
+}
+
+#[test]
+fn scan_ports_fixture() {
+    let root = std::env::temp_dir().join(format!("glonax-serial-sys-{}", std::process::id()));
+    let (class_tty, dev) = fixture(&root);
+
+    let ports = glonax_serial::scan_ports(&class_tty, &dev).unwrap();
+    assert_eq!(ports.len(), 2);
+
+    let acm = &ports[0];
+    assert_eq!(acm.path, dev.join("ttyACM0"));
+    assert_eq!(acm.by_id, None);
+    assert_eq!(acm.driver.as_deref(), Some("cdc_acm"));
+    assert_eq!(acm.product.as_deref(), Some("u-blox GNSS receiver"));
+    assert_eq!(acm.vid_pid, Some((0x1546, 0x01A8)));
+    assert!(acm.is_usb_device(0x1546, 0x01A8));
+
+    let usb = &ports[1];
+    assert_eq!(usb.path, dev.join("ttyUSB0"));
+    assert_eq!(
+        usb.by_id,
+        Some(dev.join("serial/by-id/usb-FTDI_FT232R_USB_UART-if00-port0"))
+    );
+    assert_eq!(usb.driver.as_deref(), Some("ftdi_sio"));
+    assert_eq!(usb.product.as_deref(), Some("FT232R USB UART"));
+    assert_eq!(usb.vid_pid, Some((0x0403, 0x6001)));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn scan_ports_missing() {
+    let root = Path::new("/nonexistent/glonax");
+
+    let err =
+        glonax_serial::scan_ports(&root.join("sys/class/tty"), &root.join("dev")).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        glonax_serial::ErrorKind::Io(std::io::ErrorKind::NotFound)
+    );
+}
+
+#[test]
+fn available_ports() {
+    for port in glonax_serial::available_ports().unwrap() {
+        assert!(port.path.starts_with("/dev"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+use common::open_pty;
+
+#[tokio::test]
+async fn read_timeout() {
+    let (mut master, path) = open_pty();
+
+    let mut uart = glonax_serial::builder(&path)
+        .unwrap()
+        .set_read_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    assert_eq!(uart.read_timeout(), Some(Duration::from_millis(100)));
+    assert_eq!(uart.write_timeout(), None);
+
+    let mut buf = [0; 16];
+
+    let start = Instant::now();
+    let err = uart.try_read(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let err = uart.read(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    master.write_all(b"glonax").unwrap();
+
+    let size = uart.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..size], b"glonax");
+}
@@ -1,9 +1,3 @@
-#[derive(Clone, Debug, serde_derive::Deserialize, PartialEq, Eq)]
-pub struct SimulationConfig {
-    /// Enable simulation jitter.
-    pub jitter: bool,
-}
-
 #[derive(Copy, Clone, Debug, PartialEq, Eq, serde_derive::Deserialize)]
 pub enum OperationMode {
     /// Normal operation mode.
@@ -46,10 +40,46 @@ pub struct Config {
     pub mode: OperationMode,
     /// Machine instance.
     pub machine: MachineConfig,
+    /// Readiness file path.
+    pub ready_file: Option<std::path::PathBuf>,
     /// Unix socket listener configuration.
     #[serde(default)]
     pub unix_listener: glonax::service::UnixServerConfig,
     /// J1939 network configuration.
     #[serde(default)]
     pub j1939: Vec<glonax::service::NetworkConfig>,
+    /// Machine state persistence configuration.
+    #[serde(default)]
+    pub state: glonax::service::DistributorConfig,
+    /// Operation hours meter configuration.
+    #[serde(default)]
+    pub meter: glonax::service::MeterConfig,
+    /// Rotator derivative configuration.
+    pub rotator_derivative: Option<glonax::service::RotatorDerivativeConfig>,
+    /// Collision geometry configuration.
+    #[serde(default)]
+    pub collision: glonax::world::CollisionConfig,
+    /// Attachments available to the machine.
+    #[serde(default)]
+    pub attachment: Vec<glonax::world::AttachmentConfig>,
+    /// Motion profile per joint.
+    #[serde(default)]
+    pub motion_profile: glonax::driver::MotionProfileConfig,
+    /// Load weighing configuration.
+    pub load: Option<glonax::service::LoadConfig>,
+    /// Signal replay configuration.
+    ///
+    /// The recorded trace is replayed instead of the J1939 networks.
+    pub replay: Option<glonax::service::SignalReplayConfig>,
+    /// Factory acceptance test configuration.
+    pub acceptance: Option<glonax::service::AcceptanceConfig>,
+    /// Executor configuration.
+    #[serde(default)]
+    pub executor: glonax::runtime::ExecutorConfig,
+    /// Services pinned to a CPU core.
+    #[serde(default)]
+    pub affinity: std::collections::HashMap<String, usize>,
+    /// Watchdog deadline per service in milliseconds.
+    #[serde(default)]
+    pub watchdog: std::collections::HashMap<String, u64>,
 }
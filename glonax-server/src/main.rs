@@ -26,6 +26,30 @@ struct Args {
     /// Enable pilot mode only.
     #[arg(long, default_value_t = false)]
     pilot_only: bool,
+    /// Simulation time scale.
+    ///
+    /// Compresses all simulated delays by this factor. Only applies to
+    /// simulated drivers, real hardware always runs in real time.
+    ///
+    /// Overrides the simulator configuration.
+    #[arg(long, value_name = "FACTOR")]
+    time_scale: Option<f32>,
+    /// Replay a recorded trace.
+    ///
+    /// Simulated drivers send the frames from the trace, recorded with
+    /// `candump -l`, instead of the synthetic model.
+    #[arg(long, value_name = "TRACE", value_hint = ValueHint::FilePath)]
+    replay: Option<std::path::PathBuf>,
+    /// Number of worker threads.
+    ///
+    /// Overrides the executor configuration.
+    #[arg(long, value_name = "N")]
+    workers: Option<usize>,
+    /// Run all services on a single thread.
+    ///
+    /// Overrides the executor configuration.
+    #[arg(long)]
+    current_thread: bool,
     /// Quiet output (no logging).
     #[arg(long)]
     quiet: bool,
@@ -37,8 +61,7 @@ struct Args {
     verbose: u8,
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     use log::LevelFilter;
 
     let args = Args::parse();
@@ -77,10 +100,20 @@ async fn main() -> anyhow::Result<()> {
 
     log::trace!("{:#?}", config);
 
-    run(config, args).await
+    let mut executor = config.executor.clone();
+    if let Some(workers) = args.workers {
+        executor = executor.with_workers(workers);
+    }
+    if args.current_thread {
+        executor = executor.with_current_thread();
+    }
+
+    log::debug!("Executor: {}", executor);
+
+    executor.build()?.block_on(run(config, args))
 }
 
-async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
+async fn run(mut config: config::Config, args: Args) -> anyhow::Result<()> {
     use glonax::consts::*;
     use glonax::service;
 
@@ -117,20 +150,147 @@ async fn run(config: config::Config, args: Args) -> anyhow::Result<()> {
 
     glonax::global::set_instance(instance);
 
+    let collision = config.collision.build()?;
+    log::debug!("Ground surface at {:.2}m", collision.ground_height());
+
+    let attachments = glonax::world::AttachmentRegistry::from_config(&config.attachment)?;
+    for attachment in attachments.iter() {
+        log::debug!("Attachment {}", attachment);
+    }
+
+    let motion_profile = config.motion_profile.build()?;
+
+    if let Some(time_scale) = args.time_scale {
+        log::info!("Simulation time scale: {}x", time_scale);
+
+        for driver in config
+            .j1939
+            .iter_mut()
+            .flat_map(|net| net.driver.iter_mut())
+        {
+            driver.simulator.time_scale = time_scale;
+        }
+    }
+
+    if let Some(path) = &args.replay {
+        let trace = glonax::driver::FrameTrace::from_file(path)?;
+        log::info!(
+            "Replay {} frames over {:.1}s from {}",
+            trace.len(),
+            trace.duration().as_secs_f32(),
+            path.display()
+        );
+
+        let trace = std::sync::Arc::new(trace);
+        for driver in config
+            .j1939
+            .iter_mut()
+            .flat_map(|net| net.driver.iter_mut())
+        {
+            driver.simulator.replay = Some(trace.clone());
+        }
+    }
+
     let mut runtime = glonax::Runtime::default();
     runtime.register_shutdown_signal();
+    runtime.register_channel_metrics(std::time::Duration::from_secs(1));
+
+    for (service, core) in &config.affinity {
+        log::info!("Pin service '{}' to CPU core {}", service, core);
+        runtime.set_affinity(service, *core);
+    }
+
+    for (service, deadline) in &config.watchdog {
+        runtime.set_watchdog(service, std::time::Duration::from_millis(*deadline));
+    }
+
+    runtime.register_watchdog(std::time::Duration::from_millis(100));
+    runtime.register_emergency_latch(std::time::Duration::from_secs(1));
 
     runtime.schedule_io_sub_service::<service::UnixServer, _>(config.clone().unix_listener);
-    runtime.schedule_io_sub_service::<service::Director, _>(glonax::runtime::NullConfig {});
-    runtime.schedule_io_sub_service::<service::Distributor, _>(glonax::runtime::NullConfig {});
+    runtime.schedule_io_sub_service::<service::Director, _>(service::DirectorConfig {
+        collision,
+        attachments,
+        motion_profile,
+        snapshot: config.state.file.clone(),
+    });
+    runtime.schedule_io_sub_service::<service::Distributor, _>(config.state.clone());
 
-    for j1939_net_config in &config.j1939 {
-        runtime.schedule_net_service::<service::NetworkAuthority, _>(
-            j1939_net_config.clone(),
-            std::time::Duration::from_millis(10),
+    runtime.schedule_io_pipe_service::<service::OperationMeter, _>(config.meter.clone());
+
+    runtime.schedule_io_pipe_service::<service::CapabilityPublisher, _>(
+        service::CapabilityConfig {
+            machine_type: machine.machine_type,
+            network: config.j1939.clone(),
+        },
+    );
+
+    if let Some(rotator_derivative_config) = &config.rotator_derivative {
+        runtime.schedule_io_pipe_service::<service::RotatorDerivative, _>(
+            rotator_derivative_config.clone(),
         );
     }
 
+    if let Some(load_config) = &config.load {
+        runtime.schedule_io_pipe_service::<service::LoadWeighing, _>(load_config.clone());
+    }
+
+    if let Some(acceptance_config) = &config.acceptance {
+        runtime.schedule_io_sub_service::<service::AcceptanceTest, _>(acceptance_config.clone());
+    }
+
+    if let Some(replay_config) = &config.replay {
+        runtime.schedule_io_pub_service::<service::SignalReplay, _>(service::SignalReplayConfig {
+            network: config.j1939.clone(),
+            ..replay_config.clone()
+        });
+    } else {
+        for j1939_net_config in &config.j1939 {
+            runtime.schedule_net_service::<service::NetworkAuthority, _>(
+                j1939_net_config.clone(),
+                std::time::Duration::from_millis(10),
+            );
+        }
+    }
+
+    match runtime.wait_for_ready().await {
+        Ok(services) => {
+            let summary = glonax::runtime::ReadySummary {
+                version: VERSION.to_string(),
+                instance: glonax::global::instance().id().to_string(),
+                listeners: vec![format!(
+                    "unix://{}",
+                    config.unix_listener.path.to_string_lossy()
+                )],
+                services,
+            };
+
+            if let Some(ready_file) = &config.ready_file {
+                if let Err(e) = summary.write(ready_file) {
+                    log::error!("Failed to write readiness file: {}", e);
+                }
+            }
+
+            if let Err(e) = glonax::runtime::sd_notify("READY=1") {
+                log::error!("Failed to notify service manager: {}", e);
+            }
+
+            log::info!("{}", summary);
+        }
+        Err(e) => {
+            if let glonax::Error::ServiceSetup(name) = &e {
+                log::error!("FAILED service={}", name);
+
+                let state = format!("STATUS=FAILED service={}", name);
+                if let Err(e) = glonax::runtime::sd_notify(&state) {
+                    log::error!("Failed to notify service manager: {}", e);
+                }
+            }
+
+            return Err(e.into());
+        }
+    }
+
     runtime.wait_for_shutdown().await;
 
     log::info!("Waiting for shutdown");
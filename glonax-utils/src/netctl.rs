@@ -44,6 +44,18 @@ pub(crate) mod consts {
     pub const J1939_NAME_VEHICLE_SYSTEM: u8 = 2;
 }
 
+/// J1939 name of this tool.
+fn name() -> glonax::j1939::Name {
+    glonax::j1939::NameBuilder::default()
+        .identity_number(0x1)
+        .manufacturer_code(consts::J1939_NAME_MANUFACTURER_CODE)
+        .function_instance(consts::J1939_NAME_FUNCTION_INSTANCE)
+        .ecu_instance(consts::J1939_NAME_ECU_INSTANCE)
+        .function(consts::J1939_NAME_FUNCTION)
+        .vehicle_system(consts::J1939_NAME_VEHICLE_SYSTEM)
+        .build()
+}
+
 fn style_address(address: u8) -> String {
     Purple.paint(format!("[0x{:X?}]", address)).to_string()
 }
@@ -57,6 +69,175 @@ fn j1939_address(address: String) -> Result<u8, std::num::ParseIntError> {
     }
 }
 
+/// Exit code for a generic failure.
+const EXIT_FAILURE: u8 = 1;
+/// Exit code when the network interface does not exist.
+const EXIT_INTERFACE_NOT_FOUND: u8 = 2;
+/// Exit code when the node did not respond.
+const EXIT_NO_RESPONSE: u8 = 3;
+
+/// Subcommand failure.
+#[derive(Debug)]
+enum Failure {
+    /// The network interface does not exist.
+    InterfaceNotFound(String),
+    /// The node did not respond in time.
+    NoResponse(u8),
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::InterfaceNotFound(interface) => {
+                write!(f, "network interface '{}' not found", interface)
+            }
+            Failure::NoResponse(address) => write!(f, "node 0x{:X} did not respond", address),
+        }
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Map an error onto the process exit code.
+fn exit_code(error: &anyhow::Error) -> u8 {
+    match error.downcast_ref::<Failure>() {
+        Some(Failure::InterfaceNotFound(_)) => EXIT_INTERFACE_NOT_FOUND,
+        Some(Failure::NoResponse(_)) => EXIT_NO_RESPONSE,
+        None => EXIT_FAILURE,
+    }
+}
+
+/// Map a bind error onto a failure.
+fn bind_error(interface: &str, error: std::io::Error) -> anyhow::Error {
+    if error.kind() == std::io::ErrorKind::NotFound || error.raw_os_error() == Some(libc::ENODEV) {
+        Failure::InterfaceNotFound(interface.to_owned()).into()
+    } else {
+        anyhow::Error::new(error).context(format!("failed to bind to interface '{}'", interface))
+    }
+}
+
+/// Bind a raw socket to a network interface.
+fn bind_socket(interface: &str) -> anyhow::Result<CANSocket> {
+    CANSocket::bind(&SockAddrCAN::new(interface)).map_err(|e| bind_error(interface, e))
+}
+
+/// Bind a control network to a network interface.
+fn bind_network(interface: &str, name: &glonax::j1939::Name) -> anyhow::Result<ControlNetwork> {
+    ControlNetwork::bind(interface, name).map_err(|e| bind_error(interface, e))
+}
+
+/// Time to wait for the node to report its motion lock state.
+const MOTION_LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Check the actuator command interlock.
+///
+/// Actuator commands are refused unless motion is explicitly unlocked by the
+/// operator, or the node reports that motion is unlocked.
+fn actuator_interlock(address: u8, force_unlock: bool, locked: Option<bool>) -> anyhow::Result<()> {
+    if force_unlock {
+        return Ok(());
+    }
+
+    match locked {
+        Some(false) => Ok(()),
+        Some(true) => Err(anyhow::anyhow!(
+            "Motion is locked, use --force-unlock to unlock motion"
+        )),
+        None => Err(anyhow::Error::new(Failure::NoResponse(address))
+            .context("Motion lock state is unknown, use --force-unlock to unlock motion")),
+    }
+}
+
+/// Wait for the motion lock state reported by a hydraulic control unit.
+///
+/// Returns `None` if the unit did not report its state in time.
+async fn motion_locked(
+    network: &mut ControlNetwork,
+    hcu: &mut glonax::driver::HydraulicControlUnit,
+) -> Option<bool> {
+    use glonax::driver::net::hydraulic::HydraulicMessage;
+
+    tokio::time::timeout(MOTION_LOCK_TIMEOUT, async {
+        loop {
+            if network.recv().await.is_err() {
+                return None;
+            }
+
+            if let Some(HydraulicMessage::Status(status)) = network.try_accept(hcu) {
+                return Some(status.locked);
+            }
+        }
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Unlock motion or check the actuator command interlock.
+///
+/// With `force_unlock` motion is unlocked on the unit. Otherwise the command
+/// is refused unless the unit reports motion unlocked.
+async fn motion_unlock(
+    interface: &str,
+    socket: &CANSocket,
+    hcu: &glonax::driver::HydraulicControlUnit,
+    force_unlock: bool,
+) -> anyhow::Result<()> {
+    use glonax::runtime::J1939Unit;
+
+    let destination_address = hcu.destination();
+
+    if force_unlock {
+        info!(
+            "{} Turn lock {}",
+            style_address(destination_address),
+            Red.paint("off")
+        );
+
+        socket.send(&hcu.unlock()).await?;
+
+        return Ok(());
+    }
+
+    let mut network = bind_network(interface, &name())?;
+    let locked = motion_locked(&mut network, &mut hcu.clone()).await;
+
+    actuator_interlock(destination_address, false, locked)
+}
+
+/// Actuator sweep waveform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum Waveform {
+    /// Linear ramp from minimum to maximum and back.
+    Triangle,
+    /// Sinusoidal motion from minimum to maximum and back.
+    Sine,
+}
+
+impl Waveform {
+    /// Actuator value at a moment in the sweep.
+    ///
+    /// Each period starts and ends at neutral, so the actuator does not jump
+    /// when the sweep starts. The maximum of `amplitude` is reached after a
+    /// quarter period and the minimum of `-amplitude` after three quarters.
+    fn value(
+        &self,
+        amplitude: i16,
+        period: std::time::Duration,
+        elapsed: std::time::Duration,
+    ) -> i16 {
+        // Shift the waveform so the period starts at the rising zero crossing.
+        let phase = (elapsed.as_secs_f64() / period.as_secs_f64() + 0.25).fract();
+
+        let level = match self {
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sine => -(std::f64::consts::TAU * phase).cos(),
+        };
+
+        (amplitude as f64 * level).round() as i16
+    }
+}
+
 struct Interval {
     interval: Option<tokio::time::Interval>,
 }
@@ -81,405 +262,464 @@ impl Interval {
     }
 }
 
+/// Message of any of the analyzed units.
+enum Message {
+    Engine(glonax::driver::EngineMessage),
+    Encoder(&'static str, glonax::driver::net::encoder::EncoderMessage),
+    Inclinometer(glonax::driver::net::inclino::InclinoMessage),
+    Hydraulic(glonax::driver::net::hydraulic::HydraulicMessage),
+    Vehicle(glonax::driver::net::vcu::VehicleMessage),
+    Diagnostic(Vec<glonax::core::DiagnosticTroubleCode>),
+    J1939(glonax::driver::J1939Message),
+}
+
 /// Analyze incoming frames and print their contents to the screen.
 async fn analyze_frames(mut network: ControlNetwork) -> anyhow::Result<()> {
     use glonax::driver::{
-        HydraulicControlUnit, J1939ApplicationInspector, J1939Message, KueblerEncoder,
-        KueblerInclinometer, VehicleControlUnit, VolvoD7E,
+        DiagnosticMessageService, HydraulicControlUnit, J1939ApplicationInspector, J1939Message,
+        KueblerEncoder, KueblerInclinometer, VehicleControlUnit, VolvoD7E,
     };
 
     debug!("Print incoming frames to screen");
 
-    let mut ems0 = VolvoD7E::new(
-        network.interface(),
-        consts::J1939_ADDRESS_ENGINE0,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut enc0 = KueblerEncoder::new(
-        network.interface(),
-        consts::J1939_ADDRESS_ENCODER0,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut enc1 = KueblerEncoder::new(
-        network.interface(),
-        consts::J1939_ADDRESS_ENCODER1,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut enc2 = KueblerEncoder::new(
-        network.interface(),
-        consts::J1939_ADDRESS_ENCODER2,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut enc3 = KueblerEncoder::new(
-        network.interface(),
-        consts::J1939_ADDRESS_ENCODER3,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut imu0 = KueblerInclinometer::new(
-        network.interface(),
-        consts::J1939_ADDRESS_IMU0,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut hcu0 = HydraulicControlUnit::new(
-        network.interface(),
-        consts::J1939_ADDRESS_HCU0,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut vcu0 = VehicleControlUnit::new(
-        network.interface(),
-        consts::J1939_ADDRESS_VCU0,
-        consts::J1939_ADDRESS_OBDL,
-    );
-    let mut jis0 = J1939ApplicationInspector;
+    let mut parsers = ParsableSet::new()
+        .with_parser(
+            VolvoD7E::new(
+                network.interface(),
+                consts::J1939_ADDRESS_ENGINE0,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            Message::Engine,
+        )
+        .with_parser(
+            KueblerEncoder::new(
+                network.interface(),
+                consts::J1939_ADDRESS_ENCODER2,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            |message| Message::Encoder("Arm", message),
+        )
+        .with_parser(
+            KueblerEncoder::new(
+                network.interface(),
+                consts::J1939_ADDRESS_ENCODER1,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            |message| Message::Encoder("Boom", message),
+        )
+        .with_parser(
+            KueblerEncoder::new(
+                network.interface(),
+                consts::J1939_ADDRESS_ENCODER0,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            |message| Message::Encoder("Frame", message),
+        )
+        .with_parser(
+            KueblerEncoder::new(
+                network.interface(),
+                consts::J1939_ADDRESS_ENCODER3,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            |message| Message::Encoder("Attachment", message),
+        )
+        .with_parser(
+            KueblerInclinometer::new(
+                network.interface(),
+                consts::J1939_ADDRESS_IMU0,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            Message::Inclinometer,
+        )
+        .with_parser(
+            HydraulicControlUnit::new(
+                network.interface(),
+                consts::J1939_ADDRESS_HCU0,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            Message::Hydraulic,
+        )
+        .with_parser(
+            VehicleControlUnit::new(
+                network.interface(),
+                consts::J1939_ADDRESS_VCU0,
+                consts::J1939_ADDRESS_OBDL,
+            ),
+            Message::Vehicle,
+        )
+        .with_parser(
+            DiagnosticMessageService::new(network.interface(), 0xFF, consts::J1939_ADDRESS_OBDL),
+            Message::Diagnostic,
+        )
+        .with_parser(J1939ApplicationInspector, Message::J1939);
 
     loop {
         network.recv().await?;
 
-        if let Some(message) = network.try_accept(&mut ems0) {
+        for (_, message) in network.try_accept_many(&mut parsers) {
             match message {
-                glonax::driver::EngineMessage::TorqueSpeedControl(control) => {
-                    info!(
-                        "{} {} {} » Torque speed control: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        control
-                    );
-                }
-                glonax::driver::EngineMessage::BrakeController1(controller) => {
-                    info!(
-                        "{} {} {} » Brake controller: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        controller
-                    );
-                }
-                glonax::driver::EngineMessage::EngineController1(controller) => {
-                    info!(
-                        "{} {} {} » Engine controller: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        controller
-                    );
-                }
-                glonax::driver::EngineMessage::EngineController2(controller) => {
-                    info!(
-                        "{} {} {} » Engine controller: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        controller
-                    );
-                }
-                glonax::driver::EngineMessage::EngineController3(controller) => {
-                    info!(
-                        "{} {} {} » Engine controller: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        controller
-                    );
-                }
-                glonax::driver::EngineMessage::FanDrive(fan) => {
-                    info!(
-                        "{} {} {} » Fan drive: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        fan
-                    );
-                }
-                glonax::driver::EngineMessage::VehicleDistance(distance) => {
-                    info!(
-                        "{} {} {} » Vehicle distance: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        distance
-                    );
-                }
-                glonax::driver::EngineMessage::Shutdown(shutdown) => {
-                    info!(
-                        "{} {} {} » Shutdown: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        shutdown
-                    );
-                }
-                glonax::driver::EngineMessage::EngineTemperature1(temperature) => {
-                    info!(
-                        "{} {} {} » Engine temperature: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        temperature
-                    );
-                }
-                glonax::driver::EngineMessage::EngineFluidLevelPressure1(fluid) => {
-                    info!(
-                        "{} {} {} » Engine fluid level pressure: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        fluid
-                    );
-                }
-                glonax::driver::EngineMessage::EngineFluidLevelPressure2(fluid) => {
-                    info!(
-                        "{} {} {} » Engine fluid level pressure: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        fluid
-                    );
-                }
-                glonax::driver::EngineMessage::FuelEconomy(economy) => {
-                    info!(
-                        "{} {} {} » Fuel economy: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        economy
-                    );
-                }
-                glonax::driver::EngineMessage::FuelConsumption(consumption) => {
-                    info!(
-                        "{} {} {} » Fuel consumption: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        consumption
-                    );
-                }
-                glonax::driver::EngineMessage::AmbientConditions(conditions) => {
-                    info!(
-                        "{} {} {} » Ambient conditions: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        conditions
-                    );
-                }
-                glonax::driver::EngineMessage::PowerTakeoffInformation(info) => {
-                    info!(
-                        "{} {} {} » Power takeoff information: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        info
-                    );
-                }
-                glonax::driver::EngineMessage::TankInformation1(info) => {
-                    info!(
-                        "{} {} {} » Tank information: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        info
-                    );
-                }
-                glonax::driver::EngineMessage::VehicleElectricalPower(power) => {
-                    info!(
-                        "{} {} {} » Vehicle electrical power: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        power
-                    );
-                }
-                glonax::driver::EngineMessage::InletExhaustConditions1(conditions) => {
-                    info!(
-                        "{} {} {} » Inlet exhaust conditions: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Engine"),
-                        conditions
-                    );
-                }
-            }
-        } else if let Some(message) = network.try_accept(&mut enc2) {
-            if let glonax::driver::net::encoder::EncoderMessage::ProcessData(data) = message {
-                info!(
-                    "{} {} {} » {}",
-                    chrono::Utc::now().format("%T%.3f"),
-                    style_address(network.frame_source().unwrap()),
-                    Yellow.bold().paint("Arm"),
-                    data
-                );
-            }
-        } else if let Some(message) = network.try_accept(&mut enc1) {
-            if let glonax::driver::net::encoder::EncoderMessage::ProcessData(data) = message {
-                info!(
-                    "{} {} {} » {}",
-                    chrono::Utc::now().format("%T%.3f"),
-                    style_address(network.frame_source().unwrap()),
-                    Yellow.bold().paint("Boom"),
-                    data
-                );
-            }
-        } else if let Some(message) = network.try_accept(&mut enc0) {
-            if let glonax::driver::net::encoder::EncoderMessage::ProcessData(data) = message {
-                info!(
-                    "{} {} {} » {}",
-                    chrono::Utc::now().format("%T%.3f"),
-                    style_address(network.frame_source().unwrap()),
-                    Yellow.bold().paint("Frame"),
-                    data
-                );
-            }
-        } else if let Some(message) = network.try_accept(&mut enc3) {
-            if let glonax::driver::net::encoder::EncoderMessage::ProcessData(data) = message {
-                info!(
-                    "{} {} {} » {}",
-                    chrono::Utc::now().format("%T%.3f"),
-                    style_address(network.frame_source().unwrap()),
-                    Yellow.bold().paint("Attachment"),
-                    data
-                );
-            }
-        } else if let Some(message) = network.try_accept(&mut imu0) {
-            if let glonax::driver::net::inclino::InclinoMessage::ProcessData(data) = message {
-                info!(
-                    "{} {} {} » {}",
-                    chrono::Utc::now().format("%T%.3f"),
-                    style_address(network.frame_source().unwrap()),
-                    Yellow.bold().paint("Inclinometer"),
-                    data
-                );
-            }
-        } else if let Some(message) = network.try_accept(&mut hcu0) {
-            match message {
-                glonax::driver::net::hydraulic::HydraulicMessage::Actuator(actuator) => {
-                    info!(
-                        "{} {} {} » Actuator: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Hydraulic"),
-                        actuator
-                    );
-                }
-                glonax::driver::net::hydraulic::HydraulicMessage::MotionConfig(motion) => {
-                    info!(
-                        "{} {} {} » Motion config: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Hydraulic"),
-                        motion
-                    );
-                }
-                glonax::driver::net::hydraulic::HydraulicMessage::VecraftConfig(config) => {
-                    info!(
-                        "{} {} {} » Vecraft config: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Hydraulic"),
-                        config
-                    );
-                }
-                glonax::driver::net::hydraulic::HydraulicMessage::Status(status) => {
-                    info!(
-                        "{} {} {} » Status: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Hydraulic"),
-                        status
-                    );
-                }
-                _ => {}
-            }
-        } else if let Some(message) = network.try_accept(&mut vcu0) {
-            match message {
-                glonax::driver::net::vcu::VehicleMessage::VecraftConfig(config) => {
-                    info!(
-                        "{} {} {} » Vecraft config: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Vehicle"),
-                        config
-                    );
-                }
-                glonax::driver::net::vcu::VehicleMessage::Status(status) => {
-                    info!(
-                        "{} {} {} » Status: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("Vehicle"),
-                        status
-                    );
-                }
-                _ => {}
-            }
-        } else if let Some(message) = network.try_accept(&mut jis0) {
-            match message {
-                J1939Message::SoftwareIndent((major, minor, patch)) => {
-                    info!(
-                        "{} {} {} » Software identification: {}.{}.{}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        major,
-                        minor,
-                        patch
-                    );
-                }
-                J1939Message::RequestPGN(pgn) => {
-                    info!(
-                        "{} {} {} » Request for PGN: {:?}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        pgn
-                    );
-                }
-                J1939Message::AddressClaim(name) => {
-                    info!(
-                        "{} {} {} » Name: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        name
-                    );
-                }
-                J1939Message::Acknowledged(acknowledged) => {
-                    info!(
-                        "{} {} {} » Acknowledged: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        acknowledged
-                    );
+                Message::Engine(message) => match message {
+                    glonax::driver::EngineMessage::TorqueSpeedControl(control) => {
+                        info!(
+                            "{} {} {} » Torque speed control: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            control
+                        );
+                    }
+                    glonax::driver::EngineMessage::BrakeController1(controller) => {
+                        info!(
+                            "{} {} {} » Brake controller: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            controller
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineController1(controller) => {
+                        info!(
+                            "{} {} {} » Engine controller: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            controller
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineController2(controller) => {
+                        info!(
+                            "{} {} {} » Engine controller: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            controller
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineController3(controller) => {
+                        info!(
+                            "{} {} {} » Engine controller: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            controller
+                        );
+                    }
+                    glonax::driver::EngineMessage::FanDrive(fan) => {
+                        info!(
+                            "{} {} {} » Fan drive: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            fan
+                        );
+                    }
+                    glonax::driver::EngineMessage::VehicleDistance(distance) => {
+                        info!(
+                            "{} {} {} » Vehicle distance: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            distance
+                        );
+                    }
+                    glonax::driver::EngineMessage::Shutdown(shutdown) => {
+                        info!(
+                            "{} {} {} » Shutdown: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            shutdown
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineTemperature1(temperature) => {
+                        info!(
+                            "{} {} {} » Engine temperature: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            temperature
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineFluidLevelPressure1(fluid) => {
+                        info!(
+                            "{} {} {} » Engine fluid level pressure: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            fluid
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineFluidLevelPressure2(fluid) => {
+                        info!(
+                            "{} {} {} » Engine fluid level pressure: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            fluid
+                        );
+                    }
+                    glonax::driver::EngineMessage::FuelEconomy(economy) => {
+                        info!(
+                            "{} {} {} » Fuel economy: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            economy
+                        );
+                    }
+                    glonax::driver::EngineMessage::FuelConsumption(consumption) => {
+                        info!(
+                            "{} {} {} » Fuel consumption: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            consumption
+                        );
+                    }
+                    glonax::driver::EngineMessage::AmbientConditions(conditions) => {
+                        info!(
+                            "{} {} {} » Ambient conditions: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            conditions
+                        );
+                    }
+                    glonax::driver::EngineMessage::PowerTakeoffInformation(info) => {
+                        info!(
+                            "{} {} {} » Power takeoff information: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            info
+                        );
+                    }
+                    glonax::driver::EngineMessage::TankInformation1(info) => {
+                        info!(
+                            "{} {} {} » Tank information: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            info
+                        );
+                    }
+                    glonax::driver::EngineMessage::VehicleElectricalPower(power) => {
+                        info!(
+                            "{} {} {} » Vehicle electrical power: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            power
+                        );
+                    }
+                    glonax::driver::EngineMessage::InletExhaustConditions1(conditions) => {
+                        info!(
+                            "{} {} {} » Inlet exhaust conditions: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            conditions
+                        );
+                    }
+                    glonax::driver::EngineMessage::EngineSpeed(rpm) => {
+                        info!(
+                            "{} {} {} » Engine speed: {} rpm",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            rpm
+                        );
+                    }
+                    glonax::driver::EngineMessage::DriverDemand(demand) => {
+                        info!(
+                            "{} {} {} » Driver demand: {}%",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            demand
+                        );
+                    }
+                    glonax::driver::EngineMessage::ActualEngine(actual) => {
+                        info!(
+                            "{} {} {} » Actual engine: {}%",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Engine"),
+                            actual
+                        );
+                    }
+                },
+                Message::Encoder(label, message) => {
+                    if let glonax::driver::net::encoder::EncoderMessage::ProcessData(data) = message
+                    {
+                        info!(
+                            "{} {} {} » {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint(label),
+                            data
+                        );
+                    }
                 }
-                J1939Message::TimeDate(time) => {
-                    info!(
-                        "{} {} {} » Time and date: {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        time
-                    );
+                Message::Inclinometer(message) => {
+                    if let glonax::driver::net::inclino::InclinoMessage::ProcessData(data) = message
+                    {
+                        info!(
+                            "{} {} {} » {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Inclinometer"),
+                            data
+                        );
+                    }
                 }
-                J1939Message::ActiveDiagnosticTroubleCodes(diagnostic) => {
+                Message::Hydraulic(message) => match message {
+                    glonax::driver::net::hydraulic::HydraulicMessage::Actuator(actuator) => {
+                        info!(
+                            "{} {} {} » Actuator: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Hydraulic"),
+                            actuator
+                        );
+                    }
+                    glonax::driver::net::hydraulic::HydraulicMessage::MotionConfig(motion) => {
+                        info!(
+                            "{} {} {} » Motion config: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Hydraulic"),
+                            motion
+                        );
+                    }
+                    glonax::driver::net::hydraulic::HydraulicMessage::VecraftConfig(config) => {
+                        info!(
+                            "{} {} {} » Vecraft config: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Hydraulic"),
+                            config
+                        );
+                    }
+                    glonax::driver::net::hydraulic::HydraulicMessage::Status(status) => {
+                        info!(
+                            "{} {} {} » Status: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Hydraulic"),
+                            status
+                        );
+                    }
+                    _ => {}
+                },
+                Message::Vehicle(message) => match message {
+                    glonax::driver::net::vcu::VehicleMessage::VecraftConfig(config) => {
+                        info!(
+                            "{} {} {} » Vecraft config: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Vehicle"),
+                            config
+                        );
+                    }
+                    glonax::driver::net::vcu::VehicleMessage::Status(status) => {
+                        info!(
+                            "{} {} {} » Status: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("Vehicle"),
+                            status
+                        );
+                    }
+                    _ => {}
+                },
+                Message::Diagnostic(codes) => {
+                    let codes = if codes.is_empty() {
+                        "none".to_string()
+                    } else {
+                        codes
+                            .iter()
+                            .map(|code| code.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+
                     info!(
-                        "{} {} {} » Active diagnostic trouble codes: SPN: {} FMI {}",
-                        chrono::Utc::now().format("%T%.3f"),
-                        style_address(network.frame_source().unwrap()),
-                        Yellow.bold().paint("J1939"),
-                        diagnostic.suspect_parameter_number,
-                        diagnostic.failure_mode_identifier
-                    );
-                }
-                J1939Message::ProprietaryB(data) => {
-                    debug!(
-                        "{} {} {} » Proprietary B: {:02X?}",
+                        "{} {} {} » Active diagnostic trouble codes: {}",
                         chrono::Utc::now().format("%T%.3f"),
                         style_address(network.frame_source().unwrap()),
                         Yellow.bold().paint("J1939"),
-                        data
+                        codes
                     );
                 }
+                Message::J1939(message) => match message {
+                    J1939Message::SoftwareIndent((major, minor, patch)) => {
+                        info!(
+                            "{} {} {} » Software identification: {}.{}.{}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            major,
+                            minor,
+                            patch
+                        );
+                    }
+                    J1939Message::RequestPGN(pgn) => {
+                        info!(
+                            "{} {} {} » Request for PGN: {:?}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            pgn
+                        );
+                    }
+                    J1939Message::AddressClaim(name) => {
+                        info!(
+                            "{} {} {} » Name: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            name
+                        );
+                    }
+                    J1939Message::Acknowledged(acknowledged) => {
+                        info!(
+                            "{} {} {} » Acknowledged: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            acknowledged
+                        );
+                    }
+                    J1939Message::TimeDate(time) => {
+                        info!(
+                            "{} {} {} » Time and date: {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            time
+                        );
+                    }
+                    J1939Message::ActiveDiagnosticTroubleCodes(diagnostic) => {
+                        info!(
+                            "{} {} {} » Active diagnostic trouble codes: SPN: {} FMI {}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            diagnostic.suspect_parameter_number,
+                            diagnostic.failure_mode_identifier
+                        );
+                    }
+                    J1939Message::ProprietaryB(data) => {
+                        debug!(
+                            "{} {} {} » Proprietary B: {:02X?}",
+                            chrono::Utc::now().format("%T%.3f"),
+                            style_address(network.frame_source().unwrap()),
+                            Yellow.bold().paint("J1939"),
+                            data
+                        );
+                    }
+                },
             }
         }
     }
@@ -715,7 +955,35 @@ enum HCUCommand {
     /// Enable or disable motion lock.
     Lock { toggle: String },
     /// Actuator motion.
-    Actuator { actuator: u8, value: i16 },
+    ///
+    /// The command is refused unless the unit reports motion unlocked.
+    Actuator {
+        actuator: u8,
+        value: i16,
+        /// Unlock motion before sending the command.
+        #[arg(long)]
+        force_unlock: bool,
+    },
+    /// Sweep an actuator from maximum to minimum and back.
+    ///
+    /// The sweep starts at neutral and runs until interrupted, after which the
+    /// actuator is stopped. The command is refused unless the unit reports
+    /// motion unlocked.
+    Sweep {
+        actuator: u8,
+        /// Sweep period in milliseconds.
+        #[arg(long, default_value_t = 4_000)]
+        period: u64,
+        /// Sweep amplitude.
+        #[arg(long, default_value_t = 12_000, value_parser = clap::value_parser!(i16).range(0..))]
+        amplitude: i16,
+        /// Sweep waveform.
+        #[arg(long, value_enum, default_value_t = Waveform::Triangle)]
+        waveform: Waveform,
+        /// Unlock motion before sending the command.
+        #[arg(long)]
+        force_unlock: bool,
+    },
 }
 
 #[derive(clap::Subcommand)]
@@ -731,9 +999,19 @@ enum VCUCommand {
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> std::process::ExitCode {
     let args = Args::parse();
 
+    match run(args).await {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
     let log_config = simplelog::ConfigBuilder::new()
         .set_time_level(log::LevelFilter::Off)
         .set_thread_level(log::LevelFilter::Off)
@@ -761,7 +1039,7 @@ async fn main() -> anyhow::Result<()> {
     match args.command {
         Command::Hcu { address, command } => {
             let destination_address = j1939_address(address)?;
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             let hcu0 = glonax::driver::HydraulicControlUnit::new(
                 args.interface.as_str(),
@@ -794,7 +1072,13 @@ async fn main() -> anyhow::Result<()> {
 
                     socket.send(&frames).await?;
                 }
-                HCUCommand::Actuator { actuator, value } => {
+                HCUCommand::Actuator {
+                    actuator,
+                    value,
+                    force_unlock,
+                } => {
+                    motion_unlock(&args.interface, &socket, &hcu0, force_unlock).await?;
+
                     info!(
                         "{} Set actuator {} to {}",
                         style_address(destination_address),
@@ -813,11 +1097,65 @@ async fn main() -> anyhow::Result<()> {
                         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
                     }
                 }
+                HCUCommand::Sweep {
+                    actuator,
+                    period,
+                    amplitude,
+                    waveform,
+                    force_unlock,
+                } => {
+                    if period == 0 {
+                        return Err(anyhow::anyhow!("Sweep period must be greater than zero"));
+                    }
+
+                    motion_unlock(&args.interface, &socket, &hcu0, force_unlock).await?;
+
+                    info!(
+                        "{} Sweep actuator {} with {:?} of {} over {}ms",
+                        style_address(destination_address),
+                        actuator,
+                        waveform,
+                        amplitude,
+                        period
+                    );
+
+                    let period = std::time::Duration::from_millis(period);
+                    let start = std::time::Instant::now();
+
+                    let ctrl_c = tokio::signal::ctrl_c();
+                    tokio::pin!(ctrl_c);
+
+                    loop {
+                        let value = waveform.value(amplitude, period, start.elapsed());
+
+                        socket
+                            .send_vectored(&hcu0.actuator_command([(actuator, value)].into()))
+                            .await?;
+
+                        tokio::select! {
+                            _ = tokio::time::sleep(std::time::Duration::from_millis(10)) => {}
+                            _ = &mut ctrl_c => {
+                                info!(
+                                    "{} Stop actuator {}",
+                                    style_address(destination_address),
+                                    actuator
+                                );
+
+                                socket
+                                    .send_vectored(&hcu0.actuator_command([(actuator, 0)].into()))
+                                    .await?;
+                                socket.send(&hcu0.motion_reset()).await?;
+
+                                break;
+                            }
+                        }
+                    }
+                }
             }
         }
         Command::Vecraft { address, command } => {
             let destination_address = j1939_address(address)?;
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             // TODO: Using HCU as Vecraft for now. Need to implement Vecraft driver.
             let hcu0 = glonax::driver::HydraulicControlUnit::new(
@@ -891,7 +1229,7 @@ async fn main() -> anyhow::Result<()> {
             use glonax::driver::net::engine::Engine;
 
             let destination_address = j1939_address(address)?;
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             // TODO: Replace string with enum
             let ems = if driver == "volvo" {
@@ -939,7 +1277,7 @@ async fn main() -> anyhow::Result<()> {
             use glonax::j1939::{protocol, PGN};
 
             let destination_address = j1939_address(address)?;
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             let pgn = PGN::from(pgn);
 
@@ -954,7 +1292,7 @@ async fn main() -> anyhow::Result<()> {
                 .await?;
         }
         Command::Send { interval, id, data } => {
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             let mut tick = Interval::new(interval);
 
@@ -977,7 +1315,7 @@ async fn main() -> anyhow::Result<()> {
                 return Err(anyhow::anyhow!("Data length is too short"));
             }
 
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             let pgn = PGN::from(pgn);
 
@@ -1005,7 +1343,7 @@ async fn main() -> anyhow::Result<()> {
         Command::Fuzzer { interval, id } => {
             use glonax::rand::Rng;
 
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
             let fuz0 = glonax::driver::Fuzzer::new(glonax::j1939::Id::new(u32::from_str_radix(
                 id.as_str(),
                 16,
@@ -1021,15 +1359,8 @@ async fn main() -> anyhow::Result<()> {
             }
         }
         Command::Diagnostic => {
-            let name = glonax::j1939::NameBuilder::default()
-                .identity_number(0x1)
-                .manufacturer_code(consts::J1939_NAME_MANUFACTURER_CODE)
-                .function_instance(consts::J1939_NAME_FUNCTION_INSTANCE)
-                .ecu_instance(consts::J1939_NAME_ECU_INSTANCE)
-                .function(consts::J1939_NAME_FUNCTION)
-                .vehicle_system(consts::J1939_NAME_VEHICLE_SYSTEM)
-                .build();
-            let network = ControlNetwork::bind(&args.interface, &name)?;
+            let name = name();
+            let network = bind_network(&args.interface, &name)?;
 
             diagnose(network).await?;
         }
@@ -1059,7 +1390,7 @@ async fn main() -> anyhow::Result<()> {
                 filter.push(FilterItem::with_source_address(addr?));
             }
 
-            let socket = CANSocket::bind(&SockAddrCAN::new(args.interface.as_str()))?;
+            let socket = bind_socket(&args.interface)?;
 
             print_frames(socket, filter).await?;
         }
@@ -1089,15 +1420,8 @@ async fn main() -> anyhow::Result<()> {
                 filter.push(FilterItem::with_source_address(addr?));
             }
 
-            let name = glonax::j1939::NameBuilder::default()
-                .identity_number(0x1)
-                .manufacturer_code(consts::J1939_NAME_MANUFACTURER_CODE)
-                .function_instance(consts::J1939_NAME_FUNCTION_INSTANCE)
-                .ecu_instance(consts::J1939_NAME_ECU_INSTANCE)
-                .function(consts::J1939_NAME_FUNCTION)
-                .vehicle_system(consts::J1939_NAME_VEHICLE_SYSTEM)
-                .build();
-            let network = ControlNetwork::bind(&args.interface, &name)?.with_filter(filter);
+            let name = name();
+            let network = bind_network(&args.interface, &name)?.with_filter(filter);
 
             analyze_frames(network).await?;
         }
@@ -1105,3 +1429,69 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actuator_interlock_locked() {
+        let error = actuator_interlock(0x4A, false, Some(true)).unwrap_err();
+        assert_eq!(exit_code(&error), EXIT_FAILURE);
+
+        let error = actuator_interlock(0x4A, false, None).unwrap_err();
+        assert_eq!(exit_code(&error), EXIT_NO_RESPONSE);
+    }
+
+    #[test]
+    fn actuator_interlock_unlocked() {
+        assert!(actuator_interlock(0x4A, false, Some(false)).is_ok());
+        assert!(actuator_interlock(0x4A, true, Some(true)).is_ok());
+        assert!(actuator_interlock(0x4A, true, None).is_ok());
+    }
+
+    #[test]
+    fn sweep_waveform() {
+        use std::time::Duration;
+
+        let period = Duration::from_millis(4_000);
+        let at = |waveform: Waveform, millis: u64| {
+            waveform.value(10_000, period, Duration::from_millis(millis))
+        };
+
+        let triangle = [0, 500, 1_000, 2_000, 3_000, 4_000, 5_000]
+            .map(|millis| at(Waveform::Triangle, millis));
+        assert_eq!(triangle, [0, 5_000, 10_000, 0, -10_000, 0, 10_000]);
+
+        let sine = [0, 1_000, 2_000, 3_000, 4_000].map(|millis| at(Waveform::Sine, millis));
+        assert_eq!(sine, [0, 10_000, 0, -10_000, 0]);
+
+        assert_eq!(at(Waveform::Sine, 500), 7_071);
+
+        // The sweep starts without a jump.
+        assert!(at(Waveform::Triangle, 10).abs() <= 100);
+        assert!(at(Waveform::Sine, 10).abs() <= 200);
+
+        for millis in (0..8_000).step_by(10) {
+            assert!(at(Waveform::Triangle, millis).abs() <= 10_000);
+            assert!(at(Waveform::Sine, millis).abs() <= 10_000);
+        }
+    }
+
+    #[test]
+    fn exit_code_failure() {
+        let error = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            .context("failed to send frame");
+        assert_eq!(exit_code(&error), EXIT_FAILURE);
+
+        let error = bind_error("can9", std::io::Error::from_raw_os_error(libc::ENODEV));
+        assert_eq!(exit_code(&error), EXIT_INTERFACE_NOT_FOUND);
+        assert_eq!(error.to_string(), "network interface 'can9' not found");
+
+        let error = bind_error(
+            "can0",
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+        assert_eq!(exit_code(&error), EXIT_FAILURE);
+    }
+}